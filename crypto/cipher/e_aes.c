@@ -65,6 +65,12 @@
 #define EVP_AEAD_AES_GCM_NONCE_LEN 12
 #define EVP_AEAD_AES_GCM_TAG_LEN 16
 
+/* |GFp_aead_ad_part_f| returns the |i|th part of the additional data |ad| and
+ * sets |*len| to the length of the part. The additional data is the
+ * concatenation of all of its parts. */
+typedef const uint8_t *(*GFp_aead_ad_part_f)(const void *ad, size_t i,
+                                             size_t *len);
+
  /* Declarations for extern functions only called by Rust code, to avoid
  * -Wmissing-prototypes warnings. */
 int GFp_aes_gcm_init(void *ctx_buf, size_t ctx_buf_len, const uint8_t *key,
@@ -72,11 +78,35 @@ int GFp_aes_gcm_init(void *ctx_buf, size_t ctx_buf_len, const uint8_t *key,
 int GFp_aes_gcm_open(const void *ctx_buf, uint8_t *out, size_t in_out_len,
                      uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
                      const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
-                     const uint8_t *in, const uint8_t *ad, size_t ad_len);
+                     const uint8_t *in, const void *ad, size_t num_ad_parts,
+                     GFp_aead_ad_part_f ad_part);
 int GFp_aes_gcm_seal(const void *ctx_buf, uint8_t *in_out, size_t in_out_len,
                      uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
                      const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
-                     const uint8_t *ad, size_t ad_len);
+                     const void *ad, size_t num_ad_parts,
+                     GFp_aead_ad_part_f ad_part);
+int GFp_aes_gcm_seal_chunk(const void *ctx_buf, uint8_t *in_out,
+                           size_t in_out_len, uint8_t Xi_out[16],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           uint64_t first_block, const void *ad,
+                           size_t num_ad_parts, GFp_aead_ad_part_f ad_part);
+int GFp_aes_gcm_open_chunk(const void *ctx_buf, uint8_t *in_out,
+                           size_t in_out_len, uint8_t Xi_out[16],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           uint64_t first_block, const void *ad,
+                           size_t num_ad_parts, GFp_aead_ad_part_f ad_part);
+int GFp_aes_gcm_combine_chunk(const void *ctx_buf, uint8_t Xi[16],
+                              const uint8_t chunk_Xi[16],
+                              uint64_t chunk_blocks);
+int GFp_aes_gcm_chunks_tag(const void *ctx_buf,
+                           uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           const uint8_t Xi[16], uint64_t ad_len,
+                           uint64_t in_out_len);
+int GFp_aes_block_init(void *ctx_buf, size_t ctx_buf_len, const uint8_t *key,
+                       size_t key_len);
+void GFp_aes_block_encrypt_blocks(const void *ctx_buf, uint8_t *in_out,
+                                  size_t num_blocks);
 int GFp_has_aes_hardware(void);
 
 
@@ -251,13 +281,53 @@ int GFp_aes_gcm_init(void *ctx_buf, size_t ctx_buf_len, const uint8_t *key,
 
 static int gfp_aes_gcm_init_and_aad(GCM128_CONTEXT *gcm, AES_KEY *ks,
                                     const void *ctx_buf, const uint8_t nonce[],
-                                    const uint8_t ad[], size_t ad_len) {
-  assert(ad != NULL || ad_len == 0);
+                                    const void *ad, size_t num_ad_parts,
+                                    GFp_aead_ad_part_f ad_part) {
   memcpy(ks, ctx_buf, sizeof(*ks));
   GFp_gcm128_init(gcm, ks, aes_block(), (const uint8_t *)ctx_buf + sizeof(*ks),
                   nonce);
-  if (ad_len > 0) {
-    if (!GFp_gcm128_aad(gcm, ad, ad_len)) {
+
+  /* Only the last call to |GFp_gcm128_aad| may be given a partial block, so
+   * the partial blocks at the ends of the parts are joined in |block|. */
+  uint8_t block[16];
+  size_t block_len = 0;
+  for (size_t i = 0; i < num_ad_parts; ++i) {
+    size_t len;
+    const uint8_t *part = ad_part(ad, i, &len);
+    assert(part != NULL || len == 0);
+    if (len == 0) {
+      continue;
+    }
+    if (block_len > 0) {
+      size_t n = sizeof(block) - block_len;
+      if (n > len) {
+        n = len;
+      }
+      memcpy(block + block_len, part, n);
+      block_len += n;
+      part += n;
+      len -= n;
+      if (block_len < sizeof(block)) {
+        continue;
+      }
+      if (!GFp_gcm128_aad(gcm, block, sizeof(block))) {
+        return 0;
+      }
+      block_len = 0;
+    }
+    size_t whole_blocks_len = len - (len % sizeof(block));
+    if (whole_blocks_len > 0) {
+      if (!GFp_gcm128_aad(gcm, part, whole_blocks_len)) {
+        return 0;
+      }
+    }
+    block_len = len - whole_blocks_len;
+    if (block_len > 0) {
+      memcpy(block, part + whole_blocks_len, block_len);
+    }
+  }
+  if (block_len > 0) {
+    if (!GFp_gcm128_aad(gcm, block, block_len)) {
       return 0;
     }
   }
@@ -267,14 +337,15 @@ static int gfp_aes_gcm_init_and_aad(GCM128_CONTEXT *gcm, AES_KEY *ks,
 int GFp_aes_gcm_seal(const void *ctx_buf, uint8_t *in_out, size_t in_out_len,
                      uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
                      const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
-                     const uint8_t *ad, size_t ad_len) {
+                     const void *ad, size_t num_ad_parts,
+                     GFp_aead_ad_part_f ad_part) {
   assert(in_out != NULL || in_out_len == 0);
   assert(aead_check_in_len(in_out_len));
-  assert(ad != NULL || ad_len == 0);
 
   GCM128_CONTEXT gcm;
   alignas(16) AES_KEY ks;
-  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, ad, ad_len)) {
+  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, ad, num_ad_parts,
+                                ad_part)) {
     return 0;
   }
   if (in_out_len > 0) {
@@ -297,16 +368,17 @@ int GFp_aes_gcm_seal(const void *ctx_buf, uint8_t *in_out, size_t in_out_len,
 int GFp_aes_gcm_open(const void *ctx_buf, uint8_t *out, size_t in_out_len,
                      uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
                      const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
-                     const uint8_t *in, const uint8_t *ad, size_t ad_len) {
+                     const uint8_t *in, const void *ad, size_t num_ad_parts,
+                     GFp_aead_ad_part_f ad_part) {
   assert(out != NULL || in_out_len == 0);
   assert(aead_check_in_len(in_out_len));
   assert(aead_check_alias(in, in_out_len, out));
   assert(in != NULL || in_out_len == 0);
-  assert(ad != NULL || ad_len == 0);
 
   GCM128_CONTEXT gcm;
   alignas(16) AES_KEY ks;
-  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, ad, ad_len)) {
+  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, ad, num_ad_parts,
+                                ad_part)) {
     return 0;
   }
   if (in_out_len > 0) {
@@ -325,6 +397,143 @@ int GFp_aes_gcm_open(const void *ctx_buf, uint8_t *out, size_t in_out_len,
   return 1;
 }
 
+/* |GFp_aes_gcm_seal_chunk| and |GFp_aes_gcm_open_chunk| seal or open, in
+ * place, the chunk of a message that starts |first_block| 16-byte blocks into
+ * it, so that the chunks of a large message can be processed on different
+ * threads. Every chunk but the last must be a whole number of blocks long.
+ * Only the first chunk is given the additional data. The GHASH of the chunk's
+ * ciphertext, after that of the additional data for the first chunk, is
+ * written to |Xi_out|; |GFp_aes_gcm_combine_chunk| combines the chunks' values
+ * and |GFp_aes_gcm_chunks_tag| computes the tag from the result. */
+static int gfp_aes_gcm_chunk(const void *ctx_buf, uint8_t *in_out,
+                             size_t in_out_len, uint8_t Xi_out[16],
+                             const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                             uint64_t first_block, const void *ad,
+                             size_t num_ad_parts, GFp_aead_ad_part_f ad_part,
+                             int encrypt) {
+  assert(in_out != NULL || in_out_len == 0);
+  assert(aead_check_in_len(in_out_len));
+  assert(first_block == 0 || num_ad_parts == 0);
+
+  GCM128_CONTEXT gcm;
+  alignas(16) AES_KEY ks;
+  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, ad, num_ad_parts,
+                                ad_part)) {
+    return 0;
+  }
+  /* The first block of the message uses counter 2. */
+  to_be_u32_ptr(gcm.Yi + 12, (uint32_t)(first_block + 2));
+  if (in_out_len > 0) {
+    aes_ctr_f ctr = aes_ctr();
+    int ok;
+    if (ctr != NULL) {
+      ok = encrypt
+        ? GFp_gcm128_encrypt_ctr32(&gcm, &ks, in_out, in_out, in_out_len, ctr)
+        : GFp_gcm128_decrypt_ctr32(&gcm, &ks, in_out, in_out, in_out_len, ctr);
+    } else {
+      ok = encrypt
+        ? GFp_gcm128_encrypt(&gcm, &ks, in_out, in_out, in_out_len)
+        : GFp_gcm128_decrypt(&gcm, &ks, in_out, in_out, in_out_len);
+    }
+    if (!ok) {
+      return 0;
+    }
+  }
+  memcpy(Xi_out, gcm.Xi, sizeof(gcm.Xi));
+  return 1;
+}
+
+int GFp_aes_gcm_seal_chunk(const void *ctx_buf, uint8_t *in_out,
+                           size_t in_out_len, uint8_t Xi_out[16],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           uint64_t first_block, const void *ad,
+                           size_t num_ad_parts, GFp_aead_ad_part_f ad_part) {
+  return gfp_aes_gcm_chunk(ctx_buf, in_out, in_out_len, Xi_out, nonce,
+                           first_block, ad, num_ad_parts, ad_part, 1);
+}
+
+int GFp_aes_gcm_open_chunk(const void *ctx_buf, uint8_t *in_out,
+                           size_t in_out_len, uint8_t Xi_out[16],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           uint64_t first_block, const void *ad,
+                           size_t num_ad_parts, GFp_aead_ad_part_f ad_part) {
+  return gfp_aes_gcm_chunk(ctx_buf, in_out, in_out_len, Xi_out, nonce,
+                           first_block, ad, num_ad_parts, ad_part, 0);
+}
+
+/* Sets |Xi|, the GHASH of the preceding chunks, to the GHASH of those chunks
+ * followed by the chunk of |chunk_blocks| blocks (counting a partial block as
+ * a block) whose GHASH on its own is |chunk_Xi|. */
+int GFp_aes_gcm_combine_chunk(const void *ctx_buf, uint8_t Xi[16],
+                              const uint8_t chunk_Xi[16],
+                              uint64_t chunk_blocks) {
+  static const uint8_t ZEROS[EVP_AEAD_AES_GCM_NONCE_LEN] = { 0 };
+  GCM128_CONTEXT gcm;
+  alignas(16) AES_KEY ks;
+  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, ZEROS, NULL, 0, NULL)) {
+    return 0;
+  }
+  memcpy(gcm.Xi, Xi, sizeof(gcm.Xi));
+  GFp_gcm128_mul_h_pow(&gcm, chunk_blocks);
+  for (size_t i = 0; i < sizeof(gcm.Xi); ++i) {
+    Xi[i] = gcm.Xi[i] ^ chunk_Xi[i];
+  }
+  return 1;
+}
+
+/* Computes the tag of a message of |in_out_len| bytes, with |ad_len| bytes of
+ * additional data, from |Xi|, the combined GHASH of all of its chunks. */
+int GFp_aes_gcm_chunks_tag(const void *ctx_buf,
+                           uint8_t tag_out[EVP_AEAD_AES_GCM_TAG_LEN],
+                           const uint8_t nonce[EVP_AEAD_AES_GCM_NONCE_LEN],
+                           const uint8_t Xi[16], uint64_t ad_len,
+                           uint64_t in_out_len) {
+  GCM128_CONTEXT gcm;
+  alignas(16) AES_KEY ks;
+  if (!gfp_aes_gcm_init_and_aad(&gcm, &ks, ctx_buf, nonce, NULL, 0, NULL)) {
+    return 0;
+  }
+  memcpy(gcm.Xi, Xi, sizeof(gcm.Xi));
+  gcm.len.u[0] = ad_len;
+  gcm.len.u[1] = in_out_len;
+  GFp_gcm128_tag(&gcm, tag_out);
+  return 1;
+}
+
+/* |GFp_aes_block_init| and |GFp_aes_block_encrypt_blocks| give the Rust code
+ * access to the same AES implementation that the AES-GCM code uses, for modes
+ * that are implemented in Rust. The key schedule is stored in |ctx_buf| in the
+ * same format as it is at the start of the AES-GCM context. */
+int GFp_aes_block_init(void *ctx_buf, size_t ctx_buf_len, const uint8_t *key,
+                       size_t key_len) {
+  alignas(16) AES_KEY ks;
+  assert(ctx_buf_len >= sizeof(ks));
+  if (ctx_buf_len < sizeof(ks) || (key_len != 16 && key_len != 24 &&
+                                   key_len != 32)) {
+    return 0;
+  }
+
+  /* XXX: Ignores return value, like |GFp_aes_gcm_init|; the key length was
+   * already checked above. */
+  (void)(aes_set_key())(key, key_len * 8, &ks);
+  memcpy(ctx_buf, &ks, sizeof(ks));
+  return 1;
+}
+
+/* Encrypts each of the |num_blocks| 16-byte blocks of |in_out| in place, i.e.
+ * in ECB mode. */
+void GFp_aes_block_encrypt_blocks(const void *ctx_buf, uint8_t *in_out,
+                                  size_t num_blocks) {
+  assert(in_out != NULL || num_blocks == 0);
+
+  alignas(16) AES_KEY ks;
+  memcpy(&ks, ctx_buf, sizeof(ks));
+  aes_block_f block = aes_block();
+  for (size_t i = 0; i < num_blocks; ++i) {
+    block(in_out + (16 * i), in_out + (16 * i), &ks);
+  }
+}
+
 
 int GFp_has_aes_hardware(void) {
 #if defined(AESNI)
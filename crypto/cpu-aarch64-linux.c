@@ -35,6 +35,7 @@ void GFp_cpuid_setup(void) {
   static const unsigned long kPMULL = 1 << 4;
   static const unsigned long kSHA1 = 1 << 5;
   static const unsigned long kSHA256 = 1 << 6;
+  static const unsigned long kSHA512 = 1 << 21;
 
   if ((hwcap & kNEON) == 0) {
     /* Matching OpenSSL, if NEON is missing, don't report other features
@@ -56,6 +57,9 @@ void GFp_cpuid_setup(void) {
   if (hwcap & kSHA256) {
     GFp_armcap_P |= ARMV8_SHA256;
   }
+  if (hwcap & kSHA512) {
+    GFp_armcap_P |= ARMV8_SHA512;
+  }
 }
 
 #endif /* OPENSSL_AARCH64 && !OPENSSL_STATIC_ARMCAP */
@@ -145,9 +145,11 @@ void GFp_cpuid_setup(void) {
   }
 
   uint32_t extended_features = 0;
+  uint32_t extended_features_ecx = 0;
   if (num_ids >= 7) {
     OPENSSL_cpuid(&eax, &ebx, &ecx, &edx, 7);
     extended_features = ebx;
+    extended_features_ecx = ecx;
   }
 
   /* Determine the number of cores sharing an L1 data cache to adjust the
@@ -204,12 +206,14 @@ void GFp_cpuid_setup(void) {
     ecx &= ~(1 << 12); /* FMA */
     ecx &= ~(1 << 11); /* AMD XOP */
     extended_features &= ~(1 << 5); /* AVX2 */
+    extended_features_ecx &= ~(1 << 9); /* VAES */
+    extended_features_ecx &= ~(1 << 10); /* VPCLMULQDQ */
   }
 
   GFp_ia32cap_P[0] = edx;
   GFp_ia32cap_P[1] = ecx;
   GFp_ia32cap_P[2] = extended_features;
-  GFp_ia32cap_P[3] = 0;
+  GFp_ia32cap_P[3] = extended_features_ecx;
 }
 
 #endif  /* !OPENSSL_NO_ASM && (OPENSSL_X86 || OPENSSL_X86_64) */
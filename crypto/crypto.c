@@ -68,6 +68,9 @@ uint32_t GFp_armcap_P =
 #endif
 #if defined(OPENSSL_STATIC_ARMCAP_PMULL)
     ARMV8_PMULL |
+#endif
+#if defined(OPENSSL_STATIC_ARMCAP_SHA512)
+    ARMV8_SHA512 |
 #endif
     0;
 
@@ -3640,6 +3640,23 @@ static const ge_precomp Bi[8] = {
     },
 };
 
+/* Sets Ai to A, 3A, 5A, ..., 15A, for use with the digits from |slide|. */
+static void ge_precompute_odd_multiples(ge_cached Ai[8], const ge_p3 *A) {
+  ge_p1p1 t;
+  ge_p3 u;
+  ge_p3 A2;
+  int i;
+
+  x25519_ge_p3_to_cached(&Ai[0], A);
+  ge_p3_dbl(&t, A);
+  x25519_ge_p1p1_to_p3(&A2, &t);
+  for (i = 1; i < 8; ++i) {
+    x25519_ge_add(&t, &A2, &Ai[i - 1]);
+    x25519_ge_p1p1_to_p3(&u, &t);
+    x25519_ge_p3_to_cached(&Ai[i], &u);
+  }
+}
+
 /* r = a * A + b * B
  * where a = a[0]+256*a[1]+...+256^31 a[31].
  * and b = b[0]+256*b[1]+...+256^31 b[31].
@@ -3651,36 +3668,12 @@ static void ge_double_scalarmult_vartime(ge_p2 *r, const uint8_t *a,
   ge_cached Ai[8]; /* A,3A,5A,7A,9A,11A,13A,15A */
   ge_p1p1 t;
   ge_p3 u;
-  ge_p3 A2;
   int i;
 
   slide(aslide, a);
   slide(bslide, b);
 
-  x25519_ge_p3_to_cached(&Ai[0], A);
-  ge_p3_dbl(&t, A);
-  x25519_ge_p1p1_to_p3(&A2, &t);
-  x25519_ge_add(&t, &A2, &Ai[0]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[1], &u);
-  x25519_ge_add(&t, &A2, &Ai[1]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[2], &u);
-  x25519_ge_add(&t, &A2, &Ai[2]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[3], &u);
-  x25519_ge_add(&t, &A2, &Ai[3]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[4], &u);
-  x25519_ge_add(&t, &A2, &Ai[4]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[5], &u);
-  x25519_ge_add(&t, &A2, &Ai[5]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[6], &u);
-  x25519_ge_add(&t, &A2, &Ai[6]);
-  x25519_ge_p1p1_to_p3(&u, &t);
-  x25519_ge_p3_to_cached(&Ai[7], &u);
+  ge_precompute_odd_multiples(Ai, A);
 
   ge_p2_0(r);
 
@@ -4548,11 +4541,17 @@ static void sc_muladd(uint8_t *s, const uint8_t *a, const uint8_t *b,
 
 /* Prototype to avoid -Wmissing-prototypes warnings. */
 void GFp_ed25519_public_from_private(uint8_t out[32], const uint8_t in[32]);
-void GFp_ed25519_sign(uint8_t *out_sig, const uint8_t *message,
-                      size_t message_len, const uint8_t private_key[64]);
-int GFp_ed25519_verify(const uint8_t *message, size_t message_len,
+void GFp_ed25519_sign(uint8_t *out_sig, const uint8_t *dom, size_t dom_len,
+                      const uint8_t *message, size_t message_len,
+                      const uint8_t az[64], const uint8_t public_key[32]);
+int GFp_ed25519_verify(const uint8_t *dom, size_t dom_len,
+                       const uint8_t *message, size_t message_len,
                        const uint8_t signature[64],
                        const uint8_t public_key[32]);
+int GFp_ed25519_verify_batch(size_t n, const uint8_t public_keys[][32],
+                             const uint8_t signatures[][64],
+                             const uint8_t hrams[][SHA512_DIGEST_LENGTH],
+                             const uint8_t z[][16]);
 
 
 void GFp_ed25519_public_from_private(uint8_t out[32], const uint8_t in[32]) {
@@ -4568,18 +4567,18 @@ void GFp_ed25519_public_from_private(uint8_t out[32], const uint8_t in[32]) {
   ge_p3_tobytes(out, &A);
 }
 
-void GFp_ed25519_sign(uint8_t *out_sig, const uint8_t *message,
-                      size_t message_len, const uint8_t private_key[64]) {
-  uint8_t az[SHA512_DIGEST_LENGTH];
-  GFp_SHA512_4(az, sizeof(az), private_key, 32, NULL, 0, NULL, 0, NULL, 0);
-
-  az[0] &= 248;
-  az[31] &= 63;
-  az[31] |= 64;
-
+/* |dom| is the RFC 8032 dom2(phflag, context) prefix that is hashed before
+ * everything else. It is empty (|dom_len| is zero) for plain Ed25519. For
+ * Ed25519ph, |message| is the SHA-512 digest of the actual message. |az| is
+ * the expanded private key: the SHA-512 digest of the seed, with the scalar
+ * in the first half already clamped, so that it is only computed once per key
+ * pair. */
+void GFp_ed25519_sign(uint8_t *out_sig, const uint8_t *dom, size_t dom_len,
+                      const uint8_t *message, size_t message_len,
+                      const uint8_t az[64], const uint8_t public_key[32]) {
   uint8_t nonce[SHA512_DIGEST_LENGTH];
-  GFp_SHA512_4(nonce, sizeof(nonce), az + 32, 32, message, message_len, NULL, 0,
-               NULL, 0);
+  GFp_SHA512_4(nonce, sizeof(nonce), dom, dom_len, az + 32, 32, message,
+               message_len, NULL, 0);
 
   x25519_sc_reduce(nonce);
   ge_p3 R;
@@ -4587,14 +4586,15 @@ void GFp_ed25519_sign(uint8_t *out_sig, const uint8_t *message,
   ge_p3_tobytes(out_sig, &R);
 
   uint8_t hram[SHA512_DIGEST_LENGTH];
-  GFp_SHA512_4(hram, sizeof(hram), out_sig, 32, private_key + 32, 32, message,
-               message_len, NULL, 0);
+  GFp_SHA512_4(hram, sizeof(hram), dom, dom_len, out_sig, 32,
+               public_key, 32, message, message_len);
 
   x25519_sc_reduce(hram);
   sc_muladd(out_sig + 32, hram, az, nonce);
 }
 
-int GFp_ed25519_verify(const uint8_t *message, size_t message_len,
+int GFp_ed25519_verify(const uint8_t *dom, size_t dom_len,
+                       const uint8_t *message, size_t message_len,
                        const uint8_t signature[64],
                        const uint8_t public_key[32]) {
   ge_p3 A;
@@ -4614,8 +4614,8 @@ int GFp_ed25519_verify(const uint8_t *message, size_t message_len,
   memcpy(scopy, signature + 32, 32);
 
   uint8_t h[SHA512_DIGEST_LENGTH];
-  GFp_SHA512_4(h, sizeof(h), signature, 32, public_key, 32, message,
-               message_len, NULL, 0);
+  GFp_SHA512_4(h, sizeof(h), dom, dom_len, signature, 32, public_key, 32,
+               message, message_len);
 
   x25519_sc_reduce(h);
 
@@ -4629,6 +4629,110 @@ int GFp_ed25519_verify(const uint8_t *message, size_t message_len,
 }
 
 
+/* The maximum number of signatures |GFp_ed25519_verify_batch| accepts. Its
+ * stack usage is proportional to this. */
+#define ED25519_VERIFY_BATCH_MAX 16
+
+/* Verifies |n| signatures at once. |hrams[i]| is SHA-512(R || A || M) for the
+ * ith signature, and the |z[i]| are (secret) random 128-bit values. Returns
+ * one if
+ *
+ *   [8]([-sum(z[i]*S[i])]B + sum([z[i]]R[i]) + sum([z[i]*k[i]]A[i]))
+ *
+ * is the identity, so all the signatures are (very probably) valid, and zero
+ * otherwise. Since the equation is cofactored, a signature whose R or A has a
+ * small-order component may be accepted here but rejected by
+ * |GFp_ed25519_verify|; this is unavoidable for batch verification. All the
+ * inputs are public except |z|, and the |z| only need to be unpredictable, so
+ * this doesn't need to be constant-time. */
+int GFp_ed25519_verify_batch(size_t n, const uint8_t public_keys[][32],
+                             const uint8_t signatures[][64],
+                             const uint8_t hrams[][SHA512_DIGEST_LENGTH],
+                             const uint8_t z[][16]) {
+  static const uint8_t kZero[32] = { 0 };
+
+  if (n == 0 || n > ED25519_VERIFY_BATCH_MAX) {
+    return 0;
+  }
+
+  /* Terms 2i and 2i+1 are [z[i]]R[i] and [z[i]*k[i]]A[i]. */
+  ge_cached tables[2 * ED25519_VERIFY_BATCH_MAX][8];
+  signed char slides[2 * ED25519_VERIFY_BATCH_MAX][256];
+  uint8_t zs_sum[32] = { 0 };
+
+  size_t i;
+  for (i = 0; i < n; ++i) {
+    ge_p3 R;
+    ge_p3 A;
+    if ((signatures[i][63] & 224) != 0 ||
+        x25519_ge_frombytes_vartime(&R, signatures[i]) != 0 ||
+        x25519_ge_frombytes_vartime(&A, public_keys[i]) != 0) {
+      return 0;
+    }
+
+    uint8_t k[SHA512_DIGEST_LENGTH];
+    memcpy(k, hrams[i], sizeof(k));
+    x25519_sc_reduce(k);
+
+    uint8_t zi[32] = { 0 };
+    memcpy(zi, z[i], 16);
+    uint8_t zk[32];
+    sc_muladd(zk, zi, k, kZero);
+    sc_muladd(zs_sum, zi, signatures[i] + 32, zs_sum);
+
+    slide(slides[2 * i], zi);
+    ge_precompute_odd_multiples(tables[2 * i], &R);
+    slide(slides[2 * i + 1], zk);
+    ge_precompute_odd_multiples(tables[2 * i + 1], &A);
+  }
+
+  signed char bslide[256];
+  slide(bslide, zs_sum);
+
+  /* Straus's method: all the terms share the doublings. */
+  ge_p2 r;
+  ge_p1p1 t;
+  ge_p3 u;
+  ge_p2_0(&r);
+  int pos;
+  for (pos = 255; pos >= 0; --pos) {
+    ge_p2_dbl(&t, &r);
+
+    size_t j;
+    for (j = 0; j < 2 * n; ++j) {
+      signed char digit = slides[j][pos];
+      if (digit > 0) {
+        x25519_ge_p1p1_to_p3(&u, &t);
+        x25519_ge_add(&t, &u, &tables[j][digit / 2]);
+      } else if (digit < 0) {
+        x25519_ge_p1p1_to_p3(&u, &t);
+        x25519_ge_sub(&t, &u, &tables[j][(-digit) / 2]);
+      }
+    }
+
+    /* The B term is subtracted. */
+    if (bslide[pos] > 0) {
+      x25519_ge_p1p1_to_p3(&u, &t);
+      ge_msub(&t, &u, &Bi[bslide[pos] / 2]);
+    } else if (bslide[pos] < 0) {
+      x25519_ge_p1p1_to_p3(&u, &t);
+      ge_madd(&t, &u, &Bi[(-bslide[pos]) / 2]);
+    }
+
+    x25519_ge_p1p1_to_p2(&r, &t);
+  }
+
+  /* Multiply by the cofactor. */
+  for (i = 0; i < 3; ++i) {
+    ge_p2_dbl(&t, &r);
+    x25519_ge_p1p1_to_p2(&r, &t);
+  }
+
+  fe y_minus_z;
+  fe_sub(y_minus_z, r.Y, r.Z);
+  return !fe_isnonzero(r.X) && !fe_isnonzero(y_minus_z);
+}
+
 #if defined(BORINGSSL_X25519_X86_64)
 
 static void x25519_scalar_mult(uint8_t out[32], const uint8_t scalar[32],
@@ -0,0 +1,710 @@
+/* Copyright 2017 Brian Smith.
+ *
+ * Permission to use, copy, modify, and/or distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+ * SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+ * OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+ * CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE. */
+
+/* Arithmetic for Ed448 (RFC 8032 Section 5.2): the untwisted Edwards curve
+ * x**2 + y**2 = 1 + d*x**2*y**2, d = -39081, over GF(p), p = 2**448 - 2**224
+ * - 1, and its scalars modulo the prime group order L. Also X448 (RFC 7748),
+ * which uses the Montgomery form of the same curve, Curve448.
+ *
+ * This is a simple, portable implementation. The hashing (SHAKE256) is done
+ * by the caller, in Rust. */
+
+#include <string.h>
+
+#include "../internal.h"
+
+
+/* Prototypes to avoid -Wmissing-prototypes warnings. */
+void GFp_ed448_scalar_mult_base(uint8_t out[57], const uint8_t scalar[57]);
+void GFp_ed448_scalar_reduce(uint8_t out[57], const uint8_t in[114]);
+void GFp_ed448_scalar_mul_add(uint8_t out[57], const uint8_t a[57],
+                              const uint8_t b[57], const uint8_t c[57]);
+int GFp_ed448_verify(const uint8_t r[57], const uint8_t s[57],
+                     const uint8_t k[57], const uint8_t public_key[57]);
+int GFp_x448_ecdh(uint8_t out_shared_key[56], const uint8_t private_key[56],
+                  const uint8_t peer_public_value[56]);
+void GFp_x448_public_from_private(uint8_t out_public_value[56],
+                                  const uint8_t private_key[56]);
+
+
+/* fe means field element. An element t, entries t[0]...t[15], represents the
+ * integer t[0] + 2**28 t[1] + 2**56 t[2] + ... + 2**420 t[15]. Every
+ * operation leaves each limb less than or equal to 2**28, which is enough
+ * headroom for |fe_mul|'s accumulators. */
+#define FE_LIMBS 16
+#define FE_LIMB_BITS 28
+#define FE_LIMB_MASK 0xfffffff
+
+typedef uint32_t fe[FE_LIMBS];
+
+/* p = 2**448 - 2**224 - 1. */
+static const fe kP = {
+  0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff,
+  0xfffffff, 0xfffffff, 0xffffffe, 0xfffffff, 0xfffffff, 0xfffffff,
+  0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff,
+};
+
+/* d = -39081 (mod p). */
+static const fe kD = {
+  0xfff6756, 0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff,
+  0xfffffff, 0xfffffff, 0xffffffe, 0xfffffff, 0xfffffff, 0xfffffff,
+  0xfffffff, 0xfffffff, 0xfffffff, 0xfffffff,
+};
+
+static void fe_0(fe h) { memset(h, 0, sizeof(fe)); }
+
+static void fe_1(fe h) {
+  fe_0(h);
+  h[0] = 1;
+}
+
+static void fe_copy(fe h, const fe f) { memcpy(h, f, sizeof(fe)); }
+
+/* Reduces the wide limbs of |in|, each of which must be less than 2**63, to
+ * the limbs of |h|, using 2**448 == 2**224 + 1 (mod p). */
+static void fe_carry(fe h, uint64_t in[FE_LIMBS]) {
+  size_t pass;
+  size_t i;
+  for (pass = 0; pass < 2; ++pass) {
+    for (i = 0; i < FE_LIMBS - 1; ++i) {
+      in[i + 1] += in[i] >> FE_LIMB_BITS;
+      in[i] &= FE_LIMB_MASK;
+    }
+    uint64_t top = in[FE_LIMBS - 1] >> FE_LIMB_BITS;
+    in[FE_LIMBS - 1] &= FE_LIMB_MASK;
+    in[0] += top;
+    in[FE_LIMBS / 2] += top;
+  }
+  for (i = 0; i < FE_LIMBS; ++i) {
+    h[i] = (uint32_t)in[i];
+  }
+}
+
+static void fe_add(fe h, const fe f, const fe g) {
+  uint64_t t[FE_LIMBS];
+  size_t i;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    t[i] = (uint64_t)f[i] + g[i];
+  }
+  fe_carry(h, t);
+}
+
+/* h = f - g, computed as f + 2p - g to avoid negative limbs. */
+static void fe_sub(fe h, const fe f, const fe g) {
+  uint64_t t[FE_LIMBS];
+  size_t i;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    t[i] = (uint64_t)f[i] + (2 * (uint64_t)kP[i]) - g[i];
+  }
+  fe_carry(h, t);
+}
+
+static void fe_neg(fe h, const fe f) {
+  fe zero;
+  fe_0(zero);
+  fe_sub(h, zero, f);
+}
+
+static void fe_mul(fe h, const fe f, const fe g) {
+  uint64_t t[2 * FE_LIMBS] = { 0 };
+  size_t i;
+  size_t j;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    for (j = 0; j < FE_LIMBS; ++j) {
+      t[i + j] += (uint64_t)f[i] * g[j];
+    }
+  }
+  /* Fold the upper half into the lower half, from the top down, using
+   * 2**448 == 2**224 + 1 (mod p). */
+  for (i = 2 * FE_LIMBS - 1; i >= FE_LIMBS; --i) {
+    t[i - FE_LIMBS] += t[i];
+    t[i - (FE_LIMBS / 2)] += t[i];
+  }
+  fe_carry(h, t);
+}
+
+static void fe_sq(fe h, const fe f) { fe_mul(h, f, f); }
+
+/* h = f**(2**n). */
+static void fe_sqn(fe h, const fe f, size_t n) {
+  size_t i;
+  fe_sq(h, f);
+  for (i = 1; i < n; ++i) {
+    fe_sq(h, h);
+  }
+}
+
+/* h = f**((p - 3) / 4) = f**(2**446 - 2**222 - 1). The exponent is 223 one
+ * bits, a zero bit, and 222 one bits. */
+static void fe_pow_p_minus_3_over_4(fe h, const fe f) {
+  fe x2, x3, x6, x12, x24, x30, x48, x96, x192, x222, x223, t;
+  fe_sq(t, f);
+  fe_mul(x2, t, f);
+  fe_sq(t, x2);
+  fe_mul(x3, t, f);
+  fe_sqn(t, x3, 3);
+  fe_mul(x6, t, x3);
+  fe_sqn(t, x6, 6);
+  fe_mul(x12, t, x6);
+  fe_sqn(t, x12, 12);
+  fe_mul(x24, t, x12);
+  fe_sqn(t, x24, 6);
+  fe_mul(x30, t, x6);
+  fe_sqn(t, x24, 24);
+  fe_mul(x48, t, x24);
+  fe_sqn(t, x48, 48);
+  fe_mul(x96, t, x48);
+  fe_sqn(t, x96, 96);
+  fe_mul(x192, t, x96);
+  fe_sqn(t, x192, 30);
+  fe_mul(x222, t, x30);
+  fe_sq(t, x222);
+  fe_mul(x223, t, f);
+  fe_sqn(t, x223, 223);
+  fe_mul(h, t, x222);
+}
+
+/* h = 1/f = f**(p - 2), where p - 2 = 4 * ((p - 3) / 4) + 1. */
+static void fe_invert(fe h, const fe f) {
+  fe t;
+  fe_pow_p_minus_3_over_4(t, f);
+  fe_sqn(t, t, 2);
+  fe_mul(h, t, f);
+}
+
+/* Writes the canonical (fully reduced) encoding of |f| to |s|. */
+static void fe_tobytes(uint8_t s[56], const fe f) {
+  /* Since every limb of |f| is at most 2**28, f < 2p. Calculate both f and
+   * f - p in canonical 28-bit limbs and select the one that is in [0, p). */
+  uint32_t f_norm[FE_LIMBS];
+  uint32_t f_minus_p[FE_LIMBS];
+  uint64_t carry = 0;
+  uint64_t borrow = 0;
+  size_t i;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    uint64_t x = (uint64_t)f[i] + carry;
+    f_norm[i] = (uint32_t)(x & FE_LIMB_MASK);
+    carry = x >> FE_LIMB_BITS;
+
+    uint64_t y = (uint64_t)f[i] - kP[i] - borrow;
+    f_minus_p[i] = (uint32_t)(y & FE_LIMB_MASK);
+    borrow = y >> 63;
+  }
+  /* |borrow| is 1 if and only if f < p. */
+  uint32_t mask = 0u - (uint32_t)borrow;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    f_norm[i] = (f_norm[i] & mask) | (f_minus_p[i] & ~mask);
+  }
+  for (i = 0; i < FE_LIMBS; i += 2) {
+    uint64_t x = (uint64_t)f_norm[i] | ((uint64_t)f_norm[i + 1] << 28);
+    size_t j;
+    for (j = 0; j < 7; ++j) {
+      s[(i / 2) * 7 + j] = (uint8_t)(x >> (8 * j));
+    }
+  }
+}
+
+/* Decodes |s| without reducing it; the caller must check that the encoding is
+ * canonical if that matters. */
+static void fe_frombytes(fe h, const uint8_t s[56]) {
+  size_t i;
+  for (i = 0; i < FE_LIMBS; i += 2) {
+    uint64_t x = 0;
+    size_t j;
+    for (j = 0; j < 7; ++j) {
+      x |= (uint64_t)s[(i / 2) * 7 + j] << (8 * j);
+    }
+    h[i] = (uint32_t)(x & FE_LIMB_MASK);
+    h[i + 1] = (uint32_t)(x >> 28);
+  }
+}
+
+static int fe_isnegative(const fe f) {
+  uint8_t s[56];
+  fe_tobytes(s, f);
+  return s[0] & 1;
+}
+
+static int fe_equal_vartime(const fe f, const fe g) {
+  uint8_t fs[56];
+  uint8_t gs[56];
+  fe_tobytes(fs, f);
+  fe_tobytes(gs, g);
+  return memcmp(fs, gs, sizeof(fs)) == 0;
+}
+
+/* Sets |h| to |g| if |mask| is all ones and leaves it alone if |mask| is
+ * zero. */
+static void fe_cmov(fe h, const fe g, uint32_t mask) {
+  size_t i;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    h[i] ^= mask & (h[i] ^ g[i]);
+  }
+}
+
+
+/* A point in extended coordinates (X:Y:Z:T), where x = X/Z, y = Y/Z, and
+ * x*y = T/Z. */
+typedef struct {
+  fe X;
+  fe Y;
+  fe Z;
+  fe T;
+} ge;
+
+/* The base point B from RFC 8032 Section 5.2, with Z = 1. */
+static const ge kBasePoint = {
+  { 0x70cc05e, 0x26a82bc, 0x0938e26, 0x80e18b0, 0x511433b, 0xf72ab66,
+    0x412ae1a, 0xa3d3a46, 0xa6de324, 0x0f1767e, 0x4657047, 0x36da9e1,
+    0x5a622bf, 0xed221d1, 0x66bed0d, 0x4f1970c },
+  { 0x230fa14, 0x08795bf, 0x7c8ad98, 0x132c4ed, 0x9c4fdbd, 0x1ce67c3,
+    0x73ad3ff, 0x05a0c2d, 0x7789c1e, 0xa398408, 0xa73736c, 0xc7624be,
+    0x03756c9, 0x2488762, 0x16eb6bc, 0x693f467 },
+  { 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0 },
+  { 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0 }, /* Set by |ge_base|. */
+};
+
+static void ge_identity(ge *h) {
+  fe_0(h->X);
+  fe_1(h->Y);
+  fe_1(h->Z);
+  fe_0(h->T);
+}
+
+static void ge_base(ge *h) {
+  *h = kBasePoint;
+  fe_mul(h->T, h->X, h->Y);
+}
+
+/* r = p + q, using the "add-2008-hwcd" formulas for a = 1, which are complete
+ * because d is not a square. |r| may alias |p| or |q|. The same formulas are
+ * used for doubling. */
+static void ge_add(ge *r, const ge *p, const ge *q) {
+  fe a, b, c, d, e, f, g, h, t;
+  fe_mul(a, p->X, q->X);
+  fe_mul(b, p->Y, q->Y);
+  fe_mul(c, p->T, q->T);
+  fe_mul(c, c, kD);
+  fe_mul(d, p->Z, q->Z);
+  fe_add(e, p->X, p->Y);
+  fe_add(t, q->X, q->Y);
+  fe_mul(e, e, t);
+  fe_sub(e, e, a);
+  fe_sub(e, e, b);
+  fe_sub(f, d, c);
+  fe_add(g, d, c);
+  fe_sub(h, b, a);
+  fe_mul(r->X, e, f);
+  fe_mul(r->Y, g, h);
+  fe_mul(r->T, e, h);
+  fe_mul(r->Z, f, g);
+}
+
+static void ge_double(ge *r, const ge *p) { ge_add(r, p, p); }
+
+static void ge_neg(ge *r, const ge *p) {
+  fe_neg(r->X, p->X);
+  fe_copy(r->Y, p->Y);
+  fe_copy(r->Z, p->Z);
+  fe_neg(r->T, p->T);
+}
+
+static void ge_cmov(ge *r, const ge *p, uint32_t mask) {
+  fe_cmov(r->X, p->X, mask);
+  fe_cmov(r->Y, p->Y, mask);
+  fe_cmov(r->Z, p->Z, mask);
+  fe_cmov(r->T, p->T, mask);
+}
+
+/* Encodes |p| as in RFC 8032 Section 5.2.2. */
+static void ge_tobytes(uint8_t s[57], const ge *p) {
+  fe z_inv, x, y;
+  fe_invert(z_inv, p->Z);
+  fe_mul(x, p->X, z_inv);
+  fe_mul(y, p->Y, z_inv);
+  fe_tobytes(s, y);
+  s[56] = (uint8_t)(fe_isnegative(x) << 7);
+}
+
+/* Decodes |s| as in RFC 8032 Section 5.2.3, returning zero if it isn't the
+ * canonical encoding of a point on the curve. */
+static int ge_frombytes_vartime(ge *h, const uint8_t s[57]) {
+  if ((s[56] & 0x7f) != 0) {
+    return 0;
+  }
+  int x_0 = s[56] >> 7;
+
+  fe y;
+  fe_frombytes(y, s);
+  uint8_t y_check[56];
+  fe_tobytes(y_check, y);
+  if (memcmp(y_check, s, sizeof(y_check)) != 0) {
+    return 0; /* y >= p. */
+  }
+
+  /* x**2 = u/v where u = y**2 - 1 and v = d*y**2 - 1. The candidate root is
+   * x = u**3 * v * (u**5 * v**3)**((p - 3) / 4). */
+  fe one, yy, u, v, u3, v3, t, x;
+  fe_1(one);
+  fe_sq(yy, y);
+  fe_sub(u, yy, one);
+  fe_mul(v, yy, kD);
+  fe_sub(v, v, one);
+
+  fe_sq(t, u);
+  fe_mul(u3, t, u);
+  fe_sq(t, v);
+  fe_mul(v3, t, v);
+  fe_sq(t, u);
+  fe_mul(t, t, u3);
+  fe_mul(t, t, v3); /* u**5 * v**3 */
+  fe_pow_p_minus_3_over_4(t, t);
+  fe_mul(x, u3, v);
+  fe_mul(x, x, t);
+
+  fe_sq(t, x);
+  fe_mul(t, t, v);
+  if (!fe_equal_vartime(t, u)) {
+    return 0; /* There is no square root. */
+  }
+
+  fe zero;
+  fe_0(zero);
+  if (fe_equal_vartime(x, zero) && x_0) {
+    return 0;
+  }
+  if (fe_isnegative(x) != x_0) {
+    fe_neg(x, x);
+  }
+
+  fe_copy(h->X, x);
+  fe_copy(h->Y, y);
+  fe_1(h->Z);
+  fe_mul(h->T, x, y);
+  return 1;
+}
+
+
+/* Scalars are represented as little-endian arrays of 32-bit words. */
+#define SC_WORDS 14 /* ceil(446 / 32). */
+#define SC_WIDE_WORDS 29 /* Enough for any input to |sc_reduce|. */
+
+/* L = 2**446 - 13818066809895115352007386748515426880336692474882178609894547503885. */
+static const uint32_t kL[SC_WORDS] = {
+  0xab5844f3, 0x2378c292, 0x8dc58f55, 0x216cc272, 0xaed63690, 0xc44edb49,
+  0x7cca23e9, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff,
+  0xffffffff, 0x3fffffff,
+};
+
+/* 2**446 - L. */
+#define SC_C_WORDS 7
+static const uint32_t kC[SC_C_WORDS] = {
+  0x54a7bb0d, 0xdc873d6d, 0x723a70aa, 0xde933d8d, 0x5129c96f, 0x3bb124b6,
+  0x8335dc16,
+};
+
+static void sc_from_bytes(uint32_t *out, size_t out_words, const uint8_t *in,
+                          size_t in_len) {
+  size_t i;
+  memset(out, 0, out_words * sizeof(uint32_t));
+  for (i = 0; i < in_len; ++i) {
+    out[i / 4] |= (uint32_t)in[i] << (8 * (i % 4));
+  }
+}
+
+static void sc_to_bytes(uint8_t out[57], const uint32_t in[SC_WORDS]) {
+  size_t i;
+  for (i = 0; i < 56; ++i) {
+    out[i] = (uint8_t)(in[i / 4] >> (8 * (i % 4)));
+  }
+  out[56] = 0;
+}
+
+/* Reduces |x|, which must be less than 2**928, modulo L in constant time,
+ * using 2**446 == kC (mod L). |x| is overwritten. */
+static void sc_reduce(uint32_t out[SC_WORDS], uint32_t x[SC_WIDE_WORDS]) {
+  /* Each round replaces x with (x mod 2**446) + (x >> 446) * kC, which
+   * shrinks it by about 222 bits, until x < 2**446 + kC < 2L after the fourth
+   * round. */
+  size_t round;
+  size_t i;
+  size_t j;
+  for (round = 0; round < 4; ++round) {
+    uint32_t hi[SC_WIDE_WORDS - (SC_WORDS - 1)];
+    for (i = 0; i < SC_WIDE_WORDS - (SC_WORDS - 1); ++i) {
+      uint32_t next =
+          (SC_WORDS + i < SC_WIDE_WORDS) ? x[SC_WORDS + i] : 0;
+      hi[i] = (x[SC_WORDS - 1 + i] >> 30) | (next << 2);
+    }
+    x[SC_WORDS - 1] &= 0x3fffffff;
+    for (i = SC_WORDS; i < SC_WIDE_WORDS; ++i) {
+      x[i] = 0;
+    }
+    for (i = 0; i < SC_WIDE_WORDS - (SC_WORDS - 1); ++i) {
+      uint64_t carry = 0;
+      for (j = 0; j < SC_C_WORDS; ++j) {
+        uint64_t t = (uint64_t)hi[i] * kC[j] + x[i + j] + carry;
+        x[i + j] = (uint32_t)t;
+        carry = t >> 32;
+      }
+      for (j = i + SC_C_WORDS; j < SC_WIDE_WORDS; ++j) {
+        uint64_t t = (uint64_t)x[j] + carry;
+        x[j] = (uint32_t)t;
+        carry = t >> 32;
+      }
+    }
+  }
+
+  /* Now x < 2L, and x fits in |SC_WORDS| words. Subtract L if x >= L. */
+  uint32_t x_minus_l[SC_WORDS];
+  uint64_t borrow = 0;
+  for (i = 0; i < SC_WORDS; ++i) {
+    uint64_t t = (uint64_t)x[i] - kL[i] - borrow;
+    x_minus_l[i] = (uint32_t)t;
+    borrow = (t >> 32) & 1;
+  }
+  uint32_t mask = 0u - (uint32_t)borrow; /* All ones if x < L. */
+  for (i = 0; i < SC_WORDS; ++i) {
+    out[i] = (x[i] & mask) | (x_minus_l[i] & ~mask);
+  }
+}
+
+/* Returns one if the 57-byte scalar |s| is less than L, and zero otherwise.
+ * This is only used for public values. */
+static int sc_is_reduced_vartime(const uint8_t s[57]) {
+  if (s[56] != 0) {
+    return 0;
+  }
+  uint32_t words[SC_WORDS];
+  sc_from_bytes(words, SC_WORDS, s, 56);
+  size_t i;
+  for (i = SC_WORDS; i > 0; --i) {
+    if (words[i - 1] != kL[i - 1]) {
+      return words[i - 1] < kL[i - 1];
+    }
+  }
+  return 0;
+}
+
+
+void GFp_ed448_scalar_mult_base(uint8_t out[57], const uint8_t scalar[57]) {
+  /* A fixed 4-bit window, with constant-time table lookups. */
+  ge table[16];
+  ge_identity(&table[0]);
+  ge_base(&table[1]);
+  size_t i;
+  for (i = 2; i < 16; ++i) {
+    ge_add(&table[i], &table[i - 1], &table[1]);
+  }
+
+  ge h;
+  ge_identity(&h);
+  for (i = 2 * 57; i > 0; --i) {
+    size_t nibble_index = i - 1;
+    unsigned nibble =
+        (scalar[nibble_index / 2] >> (4 * (nibble_index % 2))) & 0xf;
+    ge_double(&h, &h);
+    ge_double(&h, &h);
+    ge_double(&h, &h);
+    ge_double(&h, &h);
+    ge selected;
+    ge_identity(&selected);
+    unsigned j;
+    for (j = 1; j < 16; ++j) {
+      ge_cmov(&selected, &table[j],
+              (uint32_t)constant_time_is_zero_unsigned(nibble ^ j));
+    }
+    ge_add(&h, &h, &selected);
+  }
+  ge_tobytes(out, &h);
+}
+
+void GFp_ed448_scalar_reduce(uint8_t out[57], const uint8_t in[114]) {
+  uint32_t x[SC_WIDE_WORDS];
+  sc_from_bytes(x, SC_WIDE_WORDS, in, 114);
+  uint32_t r[SC_WORDS];
+  sc_reduce(r, x);
+  sc_to_bytes(out, r);
+}
+
+/* out = (a * b + c) mod L. |a| and |c| must be less than L; |b| must be less
+ * than 2**448. */
+void GFp_ed448_scalar_mul_add(uint8_t out[57], const uint8_t a[57],
+                              const uint8_t b[57], const uint8_t c[57]) {
+  uint32_t a_words[SC_WORDS];
+  uint32_t b_words[SC_WORDS];
+  uint32_t x[SC_WIDE_WORDS];
+  sc_from_bytes(a_words, SC_WORDS, a, 56);
+  sc_from_bytes(b_words, SC_WORDS, b, 56);
+  sc_from_bytes(x, SC_WIDE_WORDS, c, 56);
+  size_t i;
+  size_t j;
+  for (i = 0; i < SC_WORDS; ++i) {
+    uint64_t carry = 0;
+    for (j = 0; j < SC_WORDS; ++j) {
+      uint64_t t = (uint64_t)a_words[i] * b_words[j] + x[i + j] + carry;
+      x[i + j] = (uint32_t)t;
+      carry = t >> 32;
+    }
+    for (j = i + SC_WORDS; j < SC_WIDE_WORDS; ++j) {
+      uint64_t t = (uint64_t)x[j] + carry;
+      x[j] = (uint32_t)t;
+      carry = t >> 32;
+    }
+  }
+  uint32_t r[SC_WORDS];
+  sc_reduce(r, x);
+  sc_to_bytes(out, r);
+}
+
+/* Returns one if [s]B == R + [k]A, where |r| is the encoding of R and
+ * |public_key| is the encoding of A, and zero otherwise. |k| must be reduced.
+ * All inputs are public, so this doesn't need to be constant-time. */
+int GFp_ed448_verify(const uint8_t r[57], const uint8_t s[57],
+                     const uint8_t k[57], const uint8_t public_key[57]) {
+  if (!sc_is_reduced_vartime(s)) {
+    return 0;
+  }
+  ge a;
+  if (!ge_frombytes_vartime(&a, public_key)) {
+    return 0;
+  }
+  ge neg_a;
+  ge_neg(&neg_a, &a);
+  ge b;
+  ge_base(&b);
+
+  /* h = [s]B - [k]A, with simple interleaved double-and-add. */
+  ge h;
+  ge_identity(&h);
+  size_t i;
+  for (i = 8 * 56; i > 0; --i) {
+    size_t bit = i - 1;
+    ge_double(&h, &h);
+    if ((s[bit / 8] >> (bit % 8)) & 1) {
+      ge_add(&h, &h, &b);
+    }
+    if ((k[bit / 8] >> (bit % 8)) & 1) {
+      ge_add(&h, &h, &neg_a);
+    }
+  }
+
+  uint8_t r_check[57];
+  ge_tobytes(r_check, &h);
+  return memcmp(r_check, r, sizeof(r_check)) == 0;
+}
+
+
+/* X448 (RFC 7748 Section 5). */
+
+/* Swaps |f| and |g| if |mask| is all ones and leaves them alone if |mask| is
+ * zero. */
+static void fe_cswap(fe f, fe g, uint32_t mask) {
+  size_t i;
+  for (i = 0; i < FE_LIMBS; ++i) {
+    uint32_t x = mask & (f[i] ^ g[i]);
+    f[i] ^= x;
+    g[i] ^= x;
+  }
+}
+
+/* Computes the u-coordinate of [scalar]U, where |u| is the u-coordinate of U,
+ * using the constant-time Montgomery ladder of RFC 7748 Section 5. */
+static void x448_scalar_mult(uint8_t out[56], const uint8_t scalar[56],
+                             const uint8_t u[56]) {
+  uint8_t e[56];
+  memcpy(e, scalar, sizeof(e));
+  e[0] &= 252;
+  e[55] |= 128;
+
+  /* (A - 2) / 4 = 39081. */
+  static const fe kA24 = { 39081 };
+
+  fe x1;
+  fe x2;
+  fe z2;
+  fe x3;
+  fe z3;
+  fe_frombytes(x1, u);
+  fe_1(x2);
+  fe_0(z2);
+  fe_copy(x3, x1);
+  fe_1(z3);
+
+  uint32_t swap = 0;
+  size_t pos;
+  for (pos = 8 * 56; pos > 0; --pos) {
+    size_t bit = pos - 1;
+    uint32_t k_t = (e[bit / 8] >> (bit % 8)) & 1;
+    swap ^= k_t;
+    fe_cswap(x2, x3, 0u - swap);
+    fe_cswap(z2, z3, 0u - swap);
+    swap = k_t;
+
+    fe a;
+    fe aa;
+    fe b;
+    fe bb;
+    fe c;
+    fe d;
+    fe da;
+    fe cb;
+    fe ee;
+    fe t;
+    fe_add(a, x2, z2);
+    fe_sq(aa, a);
+    fe_sub(b, x2, z2);
+    fe_sq(bb, b);
+    fe_sub(ee, aa, bb);
+    fe_add(c, x3, z3);
+    fe_sub(d, x3, z3);
+    fe_mul(da, d, a);
+    fe_mul(cb, c, b);
+    fe_add(t, da, cb);
+    fe_sq(x3, t);
+    fe_sub(t, da, cb);
+    fe_sq(t, t);
+    fe_mul(z3, x1, t);
+    fe_mul(x2, aa, bb);
+    fe_mul(t, kA24, ee);
+    fe_add(t, aa, t);
+    fe_mul(z2, ee, t);
+  }
+  fe_cswap(x2, x3, 0u - swap);
+  fe_cswap(z2, z3, 0u - swap);
+
+  fe_invert(z2, z2);
+  fe_mul(x2, x2, z2);
+  fe_tobytes(out, x2);
+}
+
+/* Returns one on success and zero if the result is the all-zero value, which
+ * happens when the peer's public value is of small order. */
+int GFp_x448_ecdh(uint8_t out_shared_key[56], const uint8_t private_key[56],
+                  const uint8_t peer_public_value[56]) {
+  x448_scalar_mult(out_shared_key, private_key, peer_public_value);
+
+  uint8_t acc = 0;
+  size_t i;
+  for (i = 0; i < 56; ++i) {
+    acc |= out_shared_key[i];
+  }
+  return acc != 0;
+}
+
+void GFp_x448_public_from_private(uint8_t out_public_value[56],
+                                  const uint8_t private_key[56]) {
+  static const uint8_t kU[56] = { 5 };
+  x448_scalar_mult(out_public_value, private_key, kU);
+}
@@ -0,0 +1,270 @@
+#!/usr/bin/env perl
+
+# Copyright 2017 Brian Smith.
+#
+# Permission to use, copy, modify, and/or distribute this software for any
+# purpose with or without fee is hereby granted, provided that the above
+# copyright notice and this permission notice appear in all copies.
+#
+# THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+# WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+# MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+# SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+# WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+# OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+# CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+# Montgomery multiplication modulo the P-384 field prime
+#
+#   q = 2**384 - 2**128 - 2**96 + 2**32 - 1,
+#
+# for x86_64. This is the coarsely integrated operand scanning (CIOS) method,
+# fully unrolled, with the multiplications by each word of |b| interleaved
+# with the reduction steps so that the whole accumulator stays in registers.
+# It uses only the baseline x86_64 instruction set and is constant-time.
+#
+# Since -q**-1 mod 2**64 is 2**32 + 1, and because of the special form of
+# |q|, each reduction step is done with shifts, additions, and subtractions
+# instead of with seven multiplications.
+#
+# On a recent Xeon, this takes about 20% less time than |GFp_bn_mul_mont|.
+# Squaring uses the same code; a dedicated squaring routine would need more
+# registers than are available to keep everything in registers.
+
+$flavour = shift;
+$output  = shift;
+if ($flavour =~ /\./) { $output = $flavour; undef $flavour; }
+
+$win64=0; $win64=1 if ($flavour =~ /[nm]asm|mingw64/ || $output =~ /\.asm$/);
+
+$0 =~ m/(.*[\/\\])[^\/\\]+$/; $dir=$1;
+( $xlate="${dir}x86_64-xlate.pl" and -f $xlate ) or
+( $xlate="${dir}../../perlasm/x86_64-xlate.pl" and -f $xlate) or
+die "can't locate x86_64-xlate.pl";
+
+open OUT,"| \"$^X\" \"$xlate\" $flavour \"$output\"";
+*STDOUT=*OUT;
+
+$code.=<<___;
+.text
+
+# The field prime q.
+.align 64
+.Lp384_q:
+.quad 0x00000000ffffffff, 0xffffffff00000000, 0xfffffffffffffffe
+.quad 0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff
+___
+
+{
+my ($r_ptr,$a_ptr,$b_org,$b_ptr)=("%rdi","%rsi","%rdx","%rbx");
+my ($t0,$t1)=("%rcx","%rbp");
+
+# The accumulator is a window of eight words. Word |k| of the (unreduced)
+# result is kept in |acc($k)|; after reduction step |i|, word |i| is zero and
+# its register is reused for word |i + 8|.
+my @acc=map("%r$_",(8..15));
+sub acc { my $k = shift; return $acc[$k % 8]; }
+
+$code.=<<___;
+################################################################################
+# void GFp_p384_elem_mul_mont(
+#   uint64_t res[6],
+#   const uint64_t a[6],
+#   const uint64_t b[6]);
+
+.globl	GFp_p384_elem_mul_mont
+.type	GFp_p384_elem_mul_mont,\@function,3
+.align	32
+GFp_p384_elem_mul_mont:
+	push	%rbp
+	push	%rbx
+	push	%r12
+	push	%r13
+	push	%r14
+	push	%r15
+
+	mov	$b_org, $b_ptr
+	call	__p384_mul_montq
+
+	pop	%r15
+	pop	%r14
+	pop	%r13
+	pop	%r12
+	pop	%rbx
+	pop	%rbp
+	ret
+.size	GFp_p384_elem_mul_mont,.-GFp_p384_elem_mul_mont
+
+################################################################################
+# void GFp_p384_elem_sqr_mont(
+#   uint64_t res[6],
+#   const uint64_t a[6]);
+
+.globl	GFp_p384_elem_sqr_mont
+.type	GFp_p384_elem_sqr_mont,\@function,2
+.align	32
+GFp_p384_elem_sqr_mont:
+	push	%rbp
+	push	%rbx
+	push	%r12
+	push	%r13
+	push	%r14
+	push	%r15
+
+	mov	$a_ptr, $b_ptr
+	call	__p384_mul_montq
+
+	pop	%r15
+	pop	%r14
+	pop	%r13
+	pop	%r12
+	pop	%rbx
+	pop	%rbp
+	ret
+.size	GFp_p384_elem_sqr_mont,.-GFp_p384_elem_sqr_mont
+
+################################################################################
+# |res = a * b * 2**-384 mod q|, where |a| is at $a_ptr, |b| is at $b_ptr,
+# and |res| is at $r_ptr. Clobbers all the other general-purpose registers
+# except %rsp.
+
+.type	__p384_mul_montq,\@abi-omnipotent
+.align	32
+__p384_mul_montq:
+	########################################################################
+	# Multiply a by b[0]
+	mov	8*0($b_ptr), %rax
+	mov	%rax, $t1
+	mulq	8*0($a_ptr)
+	mov	%rax, @{[acc(0)]}
+	mov	%rdx, @{[acc(1)]}
+___
+for (my $j = 1; $j < 6; $j++) {
+$code.=<<___;
+
+	mov	$t1, %rax
+	mulq	8*$j($a_ptr)
+	add	%rax, @{[acc($j)]}
+	adc	\$0, %rdx
+	mov	%rdx, @{[acc($j + 1)]}
+___
+}
+$code.=<<___;
+	xor	@{[acc(7)]}, @{[acc(7)]}
+___
+
+for (my $i = 0; $i < 6; $i++) {
+if ($i > 0) {
+$code.=<<___;
+
+	########################################################################
+	# Multiply a by b[$i]
+	mov	8*$i($b_ptr), %rax
+	mov	%rax, $t1
+	mulq	8*0($a_ptr)
+	add	%rax, @{[acc($i)]}
+	adc	\$0, %rdx
+	mov	%rdx, $t0
+___
+for (my $j = 1; $j < 6; $j++) {
+$code.=<<___;
+
+	mov	$t1, %rax
+	mulq	8*$j($a_ptr)
+	add	$t0, @{[acc($i + $j)]}
+	adc	\$0, %rdx
+	add	%rax, @{[acc($i + $j)]}
+	adc	\$0, %rdx
+	mov	%rdx, $t0
+___
+}
+$code.=<<___;
+
+	add	$t0, @{[acc($i + 6)]}
+	adc	\$0, @{[acc($i + 7)]}
+___
+}
+
+$code.=<<___;
+
+	########################################################################
+	# Reduction step $i: add u*q, where u = acc[$i] * (2**32 + 1) mod 2**64,
+	# which makes acc[$i] zero. u*q is
+	#
+	#   u*2**384 + u*2**32 - (u*2**128 + u*2**96 + u),
+	#
+	# so this is done by adding the first two terms and then subtracting the
+	# others, without any multiplications.
+	mov	@{[acc($i)]}, $t1
+	shl	\$32, $t1
+	add	@{[acc($i)]}, $t1
+	mov	$t1, $t0
+	shl	\$32, $t0
+	mov	$t1, %rax
+	shr	\$32, %rax
+
+	add	$t0, @{[acc($i)]}
+	adc	%rax, @{[acc($i + 1)]}
+	adc	\$0, @{[acc($i + 2)]}
+	adc	\$0, @{[acc($i + 3)]}
+	adc	\$0, @{[acc($i + 4)]}
+	adc	\$0, @{[acc($i + 5)]}
+	adc	$t1, @{[acc($i + 6)]}
+	adc	\$0, @{[acc($i + 7)]}
+
+	xor	%edx, %edx
+	add	$t1, %rax
+	adc	\$0, %rdx
+
+	sub	$t1, @{[acc($i)]}
+	sbb	$t0, @{[acc($i + 1)]}
+	sbb	%rax, @{[acc($i + 2)]}
+	sbb	%rdx, @{[acc($i + 3)]}
+	sbb	\$0, @{[acc($i + 4)]}
+	sbb	\$0, @{[acc($i + 5)]}
+	sbb	\$0, @{[acc($i + 6)]}
+	sbb	\$0, @{[acc($i + 7)]}
+___
+}
+
+# The result is acc[6..12), with acc[12] being the carry, and it is less than
+# 2q. $a_ptr and $b_ptr are no longer needed, so they are used, along with
+# %rax, %rdx, $t0, and $t1, to save the unreduced value.
+my @t = ("%rax", "%rdx", $t0, $t1, $a_ptr, $b_ptr);
+$code.=<<___;
+
+	########################################################################
+	# Branch-less conditional subtraction of q
+___
+for (my $j = 0; $j < 6; $j++) {
+$code.=<<___;
+	mov	@{[acc($j + 6)]}, $t[$j]
+___
+}
+$code.=<<___;
+
+	sub	.Lp384_q+8*0(%rip), @{[acc(6)]}
+	sbb	.Lp384_q+8*1(%rip), @{[acc(7)]}
+	sbb	\$-2, @{[acc(8)]}
+	sbb	\$-1, @{[acc(9)]}
+	sbb	\$-1, @{[acc(10)]}
+	sbb	\$-1, @{[acc(11)]}
+	sbb	\$0, @{[acc(12)]}
+
+___
+for (my $j = 0; $j < 6; $j++) {
+$code.=<<___;
+	cmovc	$t[$j], @{[acc($j + 6)]}
+	mov	@{[acc($j + 6)]}, 8*$j($r_ptr)
+___
+}
+$code.=<<___;
+
+	ret
+.size	__p384_mul_montq,.-__p384_mul_montq
+___
+}
+
+$code =~ s/\`([^\`]*)\`/eval $1/gem;
+print $code;
+close STDOUT;
@@ -27,6 +27,8 @@ typedef BN_ULONG GFp_Limb;
 
 #define P256_LIMBS (256u / BN_BITS2)
 #define P384_LIMBS (384u / BN_BITS2)
+#define SECP256K1_LIMBS (256u / BN_BITS2)
+#define SM2_LIMBS (256u / BN_BITS2)
 
 GFp_Limb GFp_constant_time_limbs_are_zero(const GFp_Limb a[],
                                           size_t num_limbs);
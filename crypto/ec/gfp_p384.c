@@ -36,6 +36,7 @@ typedef GFp_Limb Scalar[P384_LIMBS];
 void GFp_p384_elem_add(Elem r, const Elem a, const Elem b);
 void GFp_p384_elem_div_by_2(Elem r, const Elem a);
 void GFp_p384_elem_mul_mont(Elem r, const Elem a, const Elem b);
+void GFp_p384_elem_sqr_mont(Elem r, const Elem a);
 void GFp_p384_elem_neg(Elem r, const Elem a);
 void GFp_p384_elem_sub(Elem r, const Elem a, const Elem b);
 void GFp_p384_scalar_inv_to_mont(ScalarMont r, const Scalar a);
@@ -183,15 +184,142 @@ static void elem_div_by_2(Elem r, const Elem a) {
   copy_conditional(r, adjusted, is_odd);
 }
 
+#if defined(OPENSSL_X86_64)
+
+/* Implemented in asm/p384-x86_64.pl. */
+
+static inline void elem_mul_mont(Elem r, const Elem a, const Elem b) {
+  GFp_p384_elem_mul_mont(r, a, b);
+}
+
+static inline void elem_sqr_mont(Elem r, const Elem a) {
+  GFp_p384_elem_sqr_mont(r, a);
+}
+
+#elif GFp_LIMB_BITS == 64 && !defined(_MSC_VER)
+
+/* On other 64-bit targets where |GFp_DoubleLimb| is available (aarch64,
+ * etc.), multiplication and squaring are done with constant-time operand
+ * scanning over fixed-length loops, followed by a separate Montgomery
+ * reduction, instead of with the generic |GFp_bn_mul_mont|. Everything is
+ * inlined so the compiler can unroll the loops and keep the limbs in
+ * registers, and squaring only needs 21 of the 36 limb multiplications. */
+#define GFp_P384_WIDE_MUL
+
+/* |r = a * b|. */
+static INLINE_IF_POSSIBLE void limbs_mul(GFp_Limb r[2 * P384_LIMBS],
+                                         const Elem a, const Elem b) {
+  for (size_t i = 0; i < 2 * P384_LIMBS; ++i) {
+    r[i] = 0;
+  }
+  for (size_t i = 0; i < P384_LIMBS; ++i) {
+    GFp_Limb carry = 0;
+    for (size_t j = 0; j < P384_LIMBS; ++j) {
+      GFp_DoubleLimb t = (GFp_DoubleLimb)a[i] * b[j] + r[i + j] + carry;
+      r[i + j] = (GFp_Limb)t;
+      carry = (GFp_Limb)(t >> GFp_LIMB_BITS);
+    }
+    r[i + P384_LIMBS] = carry;
+  }
+}
+
+/* |r = a * a|. Each cross product a[i]*a[j], i != j, is computed once and
+ * doubled, and then the squares a[i]*a[i] are added. */
+static INLINE_IF_POSSIBLE void limbs_sqr(GFp_Limb r[2 * P384_LIMBS],
+                                         const Elem a) {
+  for (size_t i = 0; i < 2 * P384_LIMBS; ++i) {
+    r[i] = 0;
+  }
+  for (size_t i = 0; i < P384_LIMBS; ++i) {
+    GFp_Limb carry = 0;
+    for (size_t j = i + 1; j < P384_LIMBS; ++j) {
+      GFp_DoubleLimb t = (GFp_DoubleLimb)a[i] * a[j] + r[i + j] + carry;
+      r[i + j] = (GFp_Limb)t;
+      carry = (GFp_Limb)(t >> GFp_LIMB_BITS);
+    }
+    r[i + P384_LIMBS] = carry;
+  }
+
+  /* The sum of the cross products is less than 2**767, so nothing is lost
+   * when doubling it. */
+  for (size_t i = 2 * P384_LIMBS - 1; i > 0; --i) {
+    r[i] = (r[i] << 1) | (r[i - 1] >> (GFp_LIMB_BITS - 1));
+  }
+  r[0] <<= 1;
+
+  GFp_Carry carry = 0;
+  for (size_t i = 0; i < P384_LIMBS; ++i) {
+    GFp_DoubleLimb t = (GFp_DoubleLimb)a[i] * a[i];
+    carry = gfp_adc(&r[2 * i], r[2 * i], (GFp_Limb)t, carry);
+    carry = gfp_adc(&r[2 * i + 1], r[2 * i + 1],
+                    (GFp_Limb)(t >> GFp_LIMB_BITS), carry);
+  }
+  assert(carry == 0);
+}
+
+/* |r = t * 2**-384 (mod m)|, where |t < m * 2**384|, |m| is odd, and
+ * |m_n0 == -m**-1 (mod 2**64)|. |t| is destroyed. */
+static INLINE_IF_POSSIBLE void mont_reduce(Elem r, GFp_Limb t[2 * P384_LIMBS],
+                                           const GFp_Limb m[P384_LIMBS],
+                                           GFp_Limb m_n0) {
+  /* |top| is the carry out of |t[i + P384_LIMBS]| from iteration |i - 1|,
+   * i.e. the carry into |t[i + P384_LIMBS]| for iteration |i|. */
+  GFp_Limb top = 0;
+  for (size_t i = 0; i < P384_LIMBS; ++i) {
+    GFp_Limb u = t[i] * m_n0;
+    GFp_Limb carry = 0;
+    for (size_t j = 0; j < P384_LIMBS; ++j) {
+      GFp_DoubleLimb x = (GFp_DoubleLimb)u * m[j] + t[i + j] + carry;
+      t[i + j] = (GFp_Limb)x;
+      carry = (GFp_Limb)(x >> GFp_LIMB_BITS);
+    }
+    assert(t[i] == 0);
+    GFp_DoubleLimb x = (GFp_DoubleLimb)t[i + P384_LIMBS] + carry + top;
+    t[i + P384_LIMBS] = (GFp_Limb)x;
+    top = (GFp_Limb)(x >> GFp_LIMB_BITS);
+  }
+
+  /* Now |top:t[P384_LIMBS..] < 2m|; subtract |m| once if needed. */
+  GFp_Limb *unreduced = &t[P384_LIMBS];
+  GFp_Limb overflow = constant_time_is_nonzero_size_t(top);
+  GFp_Limb no_borrow =
+      constant_time_is_zero_size_t(gfp_limbs_sub(r, unreduced, m,
+                                                 P384_LIMBS));
+  GFp_Limb keep_unreduced = ~constant_time_select_size_t(overflow, overflow,
+                                                         no_borrow);
+  copy_conditional(r, unreduced, keep_unreduced);
+}
+
+static const GFp_Limb Q_N0 = TOBN(0x1, 0x1);
+
+static inline void elem_mul_mont(Elem r, const Elem a, const Elem b) {
+  GFp_Limb product[2 * P384_LIMBS];
+  limbs_mul(product, a, b);
+  mont_reduce(r, product, Q, Q_N0);
+}
+
+static inline void elem_sqr_mont(Elem r, const Elem a) {
+  GFp_Limb product[2 * P384_LIMBS];
+  limbs_sqr(product, a);
+  mont_reduce(r, product, Q, Q_N0);
+}
+
+#else
+
 static inline void elem_mul_mont(Elem r, const Elem a, const Elem b) {
   static const BN_ULONG Q_N0[] = {
     BN_MONT_CTX_N0(0x1, 0x1)
   };
-  /* XXX: Not (clearly) constant-time; inefficient. TODO: Add a dedicated
-   * squaring routine. */
+  /* XXX: Not (clearly) constant-time; inefficient. */
   GFp_bn_mul_mont(r, a, b, Q, Q_N0, P384_LIMBS);
 }
 
+static inline void elem_sqr_mont(Elem r, const Elem a) {
+  elem_mul_mont(r, a, a);
+}
+
+#endif
+
 static inline void elem_mul_by_2(Elem r, const Elem a) {
   elem_add(r, a, a);
 }
@@ -203,10 +331,6 @@ static INLINE_IF_POSSIBLE void elem_mul_by_3(Elem r, const Elem a) {
   elem_add(r, doubled, a);
 }
 
-static inline void elem_sqr_mont(Elem r, const Elem a) {
-  elem_mul_mont(r, a, a);
-}
-
 void GFp_p384_elem_add(Elem r, const Elem a, const Elem b) {
   elem_add(r, a, b);
 }
@@ -219,10 +343,16 @@ void GFp_p384_elem_div_by_2(Elem r, const Elem a) {
   elem_div_by_2(r, a);
 }
 
+#if !defined(OPENSSL_X86_64)
 void GFp_p384_elem_mul_mont(Elem r, const Elem a, const Elem b) {
   elem_mul_mont(r, a, b);
 }
 
+void GFp_p384_elem_sqr_mont(Elem r, const Elem a) {
+  elem_sqr_mont(r, a);
+}
+#endif
+
 void GFp_p384_elem_neg(Elem r, const Elem a) {
   GFp_Limb is_zero = GFp_constant_time_limbs_are_zero(a, P384_LIMBS);
   GFp_Carry borrow = gfp_limbs_sub(r, Q, a, P384_LIMBS);
@@ -238,11 +368,18 @@ void GFp_p384_elem_neg(Elem r, const Elem a) {
 
 void GFp_p384_scalar_mul_mont(ScalarMont r, const ScalarMont a,
                               const ScalarMont b) {
+#if defined(GFp_P384_WIDE_MUL)
+  static const GFp_Limb N_N0 = TOBN(0x6ed46089, 0xe88fdc45);
+  GFp_Limb product[2 * P384_LIMBS];
+  limbs_mul(product, a, b);
+  mont_reduce(r, product, N, N_N0);
+#else
   static const BN_ULONG N_N0[] = {
     BN_MONT_CTX_N0(0x6ed46089, 0xe88fdc45)
   };
   /* XXX: Inefficient. TODO: Add dedicated multiplication routine. */
   GFp_bn_mul_mont(r, a, b, N, N_N0, P384_LIMBS);
+#endif
 }
 
 
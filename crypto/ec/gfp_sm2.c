@@ -0,0 +1,441 @@
+/* Copyright 2017 Brian Smith.
+ *
+ * Permission to use, copy, modify, and/or distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+ * SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+ * OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+ * CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE. */
+
+/* Arithmetic for the SM2 curve from GB/T 32918.5-2017, y**2 = x**3 - 3*x + b.
+ *
+ * The structure of this code mirrors gfp_secp256k1.c. Like the NIST curves,
+ * the curve's `a` coefficient is -3. */
+
+#include "gfp_internal.h"
+
+#include <string.h>
+
+#include "ecp_nistz.h"
+#include "../bn/internal.h"
+#include "../internal.h"
+
+#include "gfp_limbs.inl"
+
+ /* XXX: Here we assume that the conversion from |GFp_Carry| to |GFp_Limb|
+  * is constant-time, but we haven't verified that assumption. TODO: Fix it so
+  * we don't need to make that assumption. */
+
+
+typedef GFp_Limb Elem[SM2_LIMBS];
+typedef GFp_Limb ScalarMont[SM2_LIMBS];
+typedef GFp_Limb Scalar[SM2_LIMBS];
+
+typedef struct {
+  GFp_Limb X[SM2_LIMBS];
+  GFp_Limb Y[SM2_LIMBS];
+  GFp_Limb Z[SM2_LIMBS];
+} SM2_POINT;
+
+
+/* Prototypes to avoid -Wmissing-prototypes warnings. */
+void GFp_sm2_elem_add(Elem r, const Elem a, const Elem b);
+void GFp_sm2_elem_mul_mont(Elem r, const Elem a, const Elem b);
+void GFp_sm2_elem_neg(Elem r, const Elem a);
+void GFp_sm2_point_add(SM2_POINT *r, const SM2_POINT *a, const SM2_POINT *b);
+void GFp_sm2_point_double(SM2_POINT *r, const SM2_POINT *a);
+void GFp_sm2_point_mul(SM2_POINT *r, const GFp_Limb p_scalar[SM2_LIMBS],
+                       const GFp_Limb p_x[SM2_LIMBS],
+                       const GFp_Limb p_y[SM2_LIMBS]);
+void GFp_sm2_scalar_mul_mont(ScalarMont r, const ScalarMont a,
+                             const ScalarMont b);
+
+
+static const BN_ULONG Q[SM2_LIMBS] = {
+  TOBN(0xffffffff, 0xffffffff),
+  TOBN(0xffffffff, 0x00000000),
+  TOBN(0xffffffff, 0xffffffff),
+  TOBN(0xfffffffe, 0xffffffff),
+};
+
+static const BN_ULONG N[SM2_LIMBS] = {
+  TOBN(0x53bbf409, 0x39d54123),
+  TOBN(0x7203df6b, 0x21c6052b),
+  TOBN(0xffffffff, 0xffffffff),
+  TOBN(0xfffffffe, 0xffffffff),
+};
+
+OPENSSL_COMPILE_ASSERT(sizeof(size_t) == sizeof(GFp_Limb),
+                       size_t_and_gfp_limb_are_different_sizes);
+
+OPENSSL_COMPILE_ASSERT(sizeof(size_t) == sizeof(BN_ULONG),
+                       size_t_and_bn_ulong_are_different_sizes);
+
+
+/* R (mod q), i.e. 1 in the Montgomery domain. */
+static const BN_ULONG ONE[SM2_LIMBS] = {
+  TOBN(0x00000000, 0x00000001), TOBN(0x00000000, 0xffffffff), TOBN(0, 0),
+  TOBN(0x00000001, 0x00000000),
+};
+
+
+/* XXX: MSVC for x86 warns when it fails to inline these functions it should
+ * probably inline. */
+#if defined(_MSC_VER)  && defined(OPENSSL_X86)
+#define INLINE_IF_POSSIBLE __forceinline
+#else
+#define INLINE_IF_POSSIBLE inline
+#endif
+
+
+static INLINE_IF_POSSIBLE GFp_Limb is_equal(const Elem a, const Elem b) {
+  return GFp_constant_time_limbs_eq_limbs(a, b, SM2_LIMBS);
+}
+
+/* We encode infinity as (0,0), which is not on the curve, so it is OK. */
+static INLINE_IF_POSSIBLE GFp_Limb is_infinity(const Elem x, const Elem y) {
+  GFp_Limb acc = 0;
+  for (size_t i = 0; i < SM2_LIMBS; ++i) {
+    acc |= x[i] | y[i];
+  }
+  return constant_time_is_zero_size_t(acc);
+}
+
+static INLINE_IF_POSSIBLE void copy_conditional(Elem r, const Elem a,
+                                                const GFp_Limb condition) {
+  for (size_t i = 0; i < SM2_LIMBS; ++i) {
+    r[i] = constant_time_select_size_t(condition, a[i], r[i]);
+  }
+}
+
+
+static void elem_add(Elem r, const Elem a, const Elem b) {
+  GFp_Limb carry =
+      constant_time_is_nonzero_size_t(
+          gfp_limbs_add(r, a, b, SM2_LIMBS));
+  Elem adjusted;
+  GFp_Limb no_borrow =
+      constant_time_is_zero_size_t(
+          gfp_limbs_sub(adjusted, r, Q, SM2_LIMBS));
+  copy_conditional(r, adjusted,
+                   constant_time_select_size_t(carry, carry, no_borrow));
+}
+
+static void elem_sub(Elem r, const Elem a, const Elem b) {
+  GFp_Limb borrow =
+    constant_time_is_nonzero_size_t(gfp_limbs_sub(r, a, b, SM2_LIMBS));
+  Elem adjusted;
+  (void)gfp_limbs_add(adjusted, r, Q, SM2_LIMBS);
+  copy_conditional(r, adjusted, borrow);
+}
+
+static inline void elem_mul_mont(Elem r, const Elem a, const Elem b) {
+  static const BN_ULONG Q_N0[] = {
+    BN_MONT_CTX_N0(0x00000000, 0x00000001)
+  };
+  /* XXX: Not (clearly) constant-time; inefficient. TODO: Add a dedicated
+   * multiplication routine that takes advantage of the special form of q. */
+  GFp_bn_mul_mont(r, a, b, Q, Q_N0, SM2_LIMBS);
+}
+
+static inline void elem_mul_by_2(Elem r, const Elem a) {
+  elem_add(r, a, a);
+}
+
+static INLINE_IF_POSSIBLE void elem_mul_by_3(Elem r, const Elem a) {
+  /* XXX: inefficient. TODO: Replace with an integrated shift + add. */
+  Elem doubled;
+  elem_add(doubled, a, a);
+  elem_add(r, doubled, a);
+}
+
+static inline void elem_sqr_mont(Elem r, const Elem a) {
+  elem_mul_mont(r, a, a);
+}
+
+void GFp_sm2_elem_add(Elem r, const Elem a, const Elem b) {
+  elem_add(r, a, b);
+}
+
+void GFp_sm2_elem_mul_mont(Elem r, const Elem a, const Elem b) {
+  elem_mul_mont(r, a, b);
+}
+
+void GFp_sm2_elem_neg(Elem r, const Elem a) {
+  GFp_Limb is_zero = GFp_constant_time_limbs_are_zero(a, SM2_LIMBS);
+  GFp_Carry borrow = gfp_limbs_sub(r, Q, a, SM2_LIMBS);
+#if defined(NDEBUG)
+  (void)borrow;
+#endif
+  assert(borrow == 0);
+  for (size_t i = 0; i < SM2_LIMBS; ++i) {
+    r[i] = constant_time_select_size_t(is_zero, 0, r[i]);
+  }
+}
+
+
+void GFp_sm2_scalar_mul_mont(ScalarMont r, const ScalarMont a,
+                             const ScalarMont b) {
+  static const BN_ULONG N_N0[] = {
+    BN_MONT_CTX_N0(0x327f9e88, 0x72350975)
+  };
+  /* XXX: Inefficient. TODO: Add dedicated multiplication routine. */
+  GFp_bn_mul_mont(r, a, b, N, N_N0, SM2_LIMBS);
+}
+
+
+/* Point double: r = 2*a.
+ *
+ * Since a == -3, the tangent slope is M = 3*(X - Z**2)*(X + Z**2), as for the
+ * NIST curves:
+ *
+ *     X' = M**2 - 2*S
+ *     Y' = M*(S - X') - 8*Y**4
+ *     Z' = 2*Y*Z
+ *
+ * where S = 4*X*Y**2. `r` may alias `a`. */
+void GFp_sm2_point_double(SM2_POINT *r, const SM2_POINT *a) {
+  Elem Ysqr;
+  Elem Zsqr;
+  Elem S;
+  Elem M;
+  Elem T;
+
+  Elem res_x;
+  Elem res_y;
+  Elem res_z;
+
+  elem_sqr_mont(Ysqr, a->Y);      /* Y**2 */
+
+  elem_mul_mont(S, a->X, Ysqr);   /* X*Y**2 */
+  elem_mul_by_2(S, S);
+  elem_mul_by_2(S, S);            /* S = 4*X*Y**2 */
+
+  elem_sqr_mont(Zsqr, a->Z);
+  elem_add(M, a->X, Zsqr);
+  elem_sub(T, a->X, Zsqr);
+  elem_mul_mont(M, M, T);
+  elem_mul_by_3(M, M);            /* M = 3*(X - Z**2)*(X + Z**2) */
+
+  elem_mul_mont(res_z, a->Y, a->Z);
+  elem_mul_by_2(res_z, res_z);    /* Z' = 2*Y*Z */
+
+  elem_sqr_mont(res_x, M);
+  elem_mul_by_2(T, S);
+  elem_sub(res_x, res_x, T);      /* X' = M**2 - 2*S */
+
+  elem_sqr_mont(T, Ysqr);
+  elem_mul_by_2(T, T);
+  elem_mul_by_2(T, T);
+  elem_mul_by_2(T, T);            /* 8*Y**4 */
+
+  elem_sub(res_y, S, res_x);
+  elem_mul_mont(res_y, res_y, M);
+  elem_sub(res_y, res_y, T);      /* Y' = M*(S - X') - 8*Y**4 */
+
+  memcpy(r->X, res_x, sizeof(res_x));
+  memcpy(r->Y, res_y, sizeof(res_y));
+  memcpy(r->Z, res_z, sizeof(res_z));
+}
+
+/* Point addition: r = a+b. This is the same as |GFp_nistz384_point_add|. */
+void GFp_sm2_point_add(SM2_POINT *r, const SM2_POINT *a,
+                       const SM2_POINT *b) {
+  Elem U2, S2;
+  Elem U1, S1;
+  Elem Z1sqr;
+  Elem Z2sqr;
+  Elem H, R;
+  Elem Hsqr;
+  Elem Rsqr;
+  Elem Hcub;
+
+  Elem res_x;
+  Elem res_y;
+  Elem res_z;
+
+  const GFp_Limb *in1_x = a->X;
+  const GFp_Limb *in1_y = a->Y;
+  const GFp_Limb *in1_z = a->Z;
+
+  const GFp_Limb *in2_x = b->X;
+  const GFp_Limb *in2_y = b->Y;
+  const GFp_Limb *in2_z = b->Z;
+
+  GFp_Limb in1infty = is_infinity(a->X, a->Y);
+  GFp_Limb in2infty = is_infinity(b->X, b->Y);
+
+  elem_sqr_mont(Z2sqr, in2_z); /* Z2^2 */
+  elem_sqr_mont(Z1sqr, in1_z); /* Z1^2 */
+
+  elem_mul_mont(S1, Z2sqr, in2_z); /* S1 = Z2^3 */
+  elem_mul_mont(S2, Z1sqr, in1_z); /* S2 = Z1^3 */
+
+  elem_mul_mont(S1, S1, in1_y); /* S1 = Y1*Z2^3 */
+  elem_mul_mont(S2, S2, in2_y); /* S2 = Y2*Z1^3 */
+  elem_sub(R, S2, S1);          /* R = S2 - S1 */
+
+  elem_mul_mont(U1, in1_x, Z2sqr); /* U1 = X1*Z2^2 */
+  elem_mul_mont(U2, in2_x, Z1sqr); /* U2 = X2*Z1^2 */
+  elem_sub(H, U2, U1);             /* H = U2 - U1 */
+
+  /* This should not happen during sign/ecdh,
+   * so no constant time violation */
+  if (is_equal(U1, U2) && !in1infty && !in2infty) {
+    if (is_equal(S1, S2)) {
+      GFp_sm2_point_double(r, a);
+    } else {
+      memset(r, 0, sizeof(*r));
+    }
+    return;
+  }
+
+  elem_sqr_mont(Rsqr, R);             /* R^2 */
+  elem_mul_mont(res_z, H, in1_z);     /* Z3 = H*Z1*Z2 */
+  elem_sqr_mont(Hsqr, H);             /* H^2 */
+  elem_mul_mont(res_z, res_z, in2_z); /* Z3 = H*Z1*Z2 */
+  elem_mul_mont(Hcub, Hsqr, H);       /* H^3 */
+
+  elem_mul_mont(U2, U1, Hsqr); /* U1*H^2 */
+  elem_mul_by_2(Hsqr, U2);     /* 2*U1*H^2 */
+
+  elem_sub(res_x, Rsqr, Hsqr);
+  elem_sub(res_x, res_x, Hcub);
+
+  elem_sub(res_y, U2, res_x);
+
+  elem_mul_mont(S2, S1, Hcub);
+  elem_mul_mont(res_y, R, res_y);
+  elem_sub(res_y, res_y, S2);
+
+  copy_conditional(res_x, in2_x, in1infty);
+  copy_conditional(res_y, in2_y, in1infty);
+  copy_conditional(res_z, in2_z, in1infty);
+
+  copy_conditional(res_x, in1_x, in2infty);
+  copy_conditional(res_y, in1_y, in2infty);
+  copy_conditional(res_z, in1_z, in2infty);
+
+  memcpy(r->X, res_x, sizeof(res_x));
+  memcpy(r->Y, res_y, sizeof(res_y));
+  memcpy(r->Z, res_z, sizeof(res_z));
+}
+
+
+static void point_select_w5(SM2_POINT *out,
+                            const SM2_POINT table[16], size_t index) {
+  Elem x; memset(x, 0, sizeof(x));
+  Elem y; memset(y, 0, sizeof(y));
+  Elem z; memset(z, 0, sizeof(z));
+
+  for (size_t i = 0; i < 16; ++i) {
+    GFp_Limb mask = constant_time_eq_size_t(index, i + 1);
+    for (size_t j = 0; j < SM2_LIMBS; ++j) {
+      x[j] |= table[i].X[j] & mask;
+      y[j] |= table[i].Y[j] & mask;
+      z[j] |= table[i].Z[j] & mask;
+    }
+  }
+
+  memcpy(out->X, x, sizeof(x));
+  memcpy(out->Y, y, sizeof(y));
+  memcpy(out->Z, z, sizeof(z));
+}
+
+static void add_precomputed_w5(SM2_POINT *r, unsigned wvalue,
+                               const SM2_POINT table[16]) {
+  GFp_Limb recoded_is_negative;
+  unsigned int recoded;
+  booth_recode(&recoded_is_negative, &recoded, wvalue, 5);
+
+  alignas(64) SM2_POINT h;
+  point_select_w5(&h, table, recoded);
+
+  alignas(64) GFp_Limb tmp[SM2_LIMBS];
+  GFp_sm2_elem_neg(tmp, h.Y);
+  copy_conditional(h.Y, tmp, recoded_is_negative);
+
+  GFp_sm2_point_add(r, r, &h);
+}
+
+/* r = p * p_scalar. This is constant-time with respect to `p_scalar`: the
+ * sequence of doublings and additions is fixed and the table lookups are done
+ * with masking. */
+void GFp_sm2_point_mul(SM2_POINT *r, const GFp_Limb p_scalar[SM2_LIMBS],
+                       const GFp_Limb p_x[SM2_LIMBS],
+                       const GFp_Limb p_y[SM2_LIMBS]) {
+  static const unsigned kWindowSize = 5;
+  static const unsigned kMask = (1 << (5 /* kWindowSize */ + 1)) - 1;
+
+  uint8_t p_str[(SM2_LIMBS * BN_BYTES) + 1];
+  gfp_little_endian_bytes_from_scalar(p_str, sizeof(p_str) / sizeof(p_str[0]),
+                                      p_scalar, SM2_LIMBS);
+
+  /* table[0] is implicitly (0,0,0) (the point at infinity), therefore it is
+   * not stored. All other values are actually stored with an offset of -1 in
+   * table. */
+  alignas(64) SM2_POINT table[16];
+  SM2_POINT *row = table;
+
+  memcpy(row[1 - 1].X, p_x, SM2_LIMBS * BN_BYTES);
+  memcpy(row[1 - 1].Y, p_y, SM2_LIMBS * BN_BYTES);
+  memcpy(row[1 - 1].Z, ONE, SM2_LIMBS * BN_BYTES);
+
+  GFp_sm2_point_double(&row[2 - 1], &row[1 - 1]);
+  GFp_sm2_point_add(&row[3 - 1], &row[2 - 1], &row[1 - 1]);
+  GFp_sm2_point_double(&row[4 - 1], &row[2 - 1]);
+  GFp_sm2_point_double(&row[6 - 1], &row[3 - 1]);
+  GFp_sm2_point_double(&row[8 - 1], &row[4 - 1]);
+  GFp_sm2_point_double(&row[12 - 1], &row[6 - 1]);
+  GFp_sm2_point_add(&row[5 - 1], &row[4 - 1], &row[1 - 1]);
+  GFp_sm2_point_add(&row[7 - 1], &row[6 - 1], &row[1 - 1]);
+  GFp_sm2_point_add(&row[9 - 1], &row[8 - 1], &row[1 - 1]);
+  GFp_sm2_point_add(&row[13 - 1], &row[12 - 1], &row[1 - 1]);
+  GFp_sm2_point_double(&row[14 - 1], &row[7 - 1]);
+  GFp_sm2_point_double(&row[10 - 1], &row[5 - 1]);
+  GFp_sm2_point_add(&row[15 - 1], &row[14 - 1], &row[1 - 1]);
+  GFp_sm2_point_add(&row[11 - 1], &row[10 - 1], &row[1 - 1]);
+  GFp_sm2_point_double(&row[16 - 1], &row[8 - 1]);
+
+  static const unsigned START_INDEX = 256 - 1;
+  unsigned index = START_INDEX;
+
+  GFp_Limb recoded_is_negative;
+  unsigned recoded;
+
+  unsigned wvalue = p_str[(index - 1) / 8];
+  wvalue = (wvalue >> ((index - 1) % 8)) & kMask;
+
+  booth_recode(&recoded_is_negative, &recoded, wvalue, 5);
+  assert(!recoded_is_negative);
+
+  point_select_w5(r, table, recoded);
+
+  while (index >= kWindowSize) {
+    if (index != START_INDEX) {
+      unsigned off = (index - 1) / 8;
+
+      wvalue = p_str[off] | p_str[off + 1] << 8;
+      wvalue = (wvalue >> ((index - 1) % 8)) & kMask;
+      add_precomputed_w5(r, wvalue, table);
+    }
+
+    index -= kWindowSize;
+
+    GFp_sm2_point_double(r, r);
+    GFp_sm2_point_double(r, r);
+    GFp_sm2_point_double(r, r);
+    GFp_sm2_point_double(r, r);
+    GFp_sm2_point_double(r, r);
+  }
+
+  /* Final window */
+  wvalue = p_str[0];
+  wvalue = (wvalue << 1) & kMask;
+  add_precomputed_w5(r, wvalue, table);
+}
@@ -0,0 +1,281 @@
+/* Copyright 2017 Brian Smith.
+ *
+ * Permission to use, copy, modify, and/or distribute this software for any
+ * purpose with or without fee is hereby granted, provided that the above
+ * copyright notice and this permission notice appear in all copies.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+ * WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR ANY
+ * SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+ * WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+ * OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+ * CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE. */
+
+/* AES-GCM using the 256-bit (VEX-encoded) forms of VAESENC and VPCLMULQDQ,
+ * which process two blocks per instruction. Eight blocks are encrypted and
+ * hashed per iteration. Only the ymm registers are used, so this doesn't
+ * depend on AVX-512 or incur the frequency penalty that zmm registers can
+ * cause on some CPUs.
+ *
+ * GHASH is computed on byte-reflected blocks, as in the CLMUL code in
+ * ghash-x86_64.pl. With that representation, multiplying two values and
+ * reducing them with the method below yields their product times x, so the
+ * powers of H are premultiplied by x^-1 ("twisted"). */
+
+#include <openssl/aes.h>
+
+#include "internal.h"
+
+
+#if defined(GFp_VAES_GCM)
+
+#include <immintrin.h>
+
+#define VAES_TARGET \
+  __attribute__((target("aes,avx,avx2,pclmul,vaes,vpclmulqdq")))
+
+/* The number of bytes processed per iteration. */
+#define CHUNK_LEN (8 * 16)
+
+VAES_TARGET
+static __m128i bswap_128(__m128i x) {
+  const __m128i mask =
+      _mm_setr_epi8(15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0);
+  return _mm_shuffle_epi8(x, mask);
+}
+
+/* bswap_256 reverses the bytes of each 128-bit lane of |x|. */
+VAES_TARGET
+static __m256i bswap_256(__m256i x) {
+  const __m256i mask =
+      _mm256_setr_epi8(15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0,
+                       15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0);
+  return _mm256_shuffle_epi8(x, mask);
+}
+
+VAES_TARGET
+static __m128i gfpoly(void) {
+  return _mm_set_epi32((int)0xc2000000, 0, 0, 1);
+}
+
+/* reduce reduces the 256-bit product |hi|:|mi|:|lo| (with |mi| weighted by
+ * x^64) modulo the GHASH polynomial, in two folding steps. */
+VAES_TARGET
+static __m128i reduce(__m128i lo, __m128i mi, __m128i hi) {
+  const __m128i poly = gfpoly();
+  __m128i t = _mm_clmulepi64_si128(poly, lo, 0x01);
+  mi = _mm_xor_si128(mi, _mm_shuffle_epi32(lo, 0x4e));
+  mi = _mm_xor_si128(mi, t);
+  t = _mm_clmulepi64_si128(poly, mi, 0x01);
+  hi = _mm_xor_si128(hi, _mm_shuffle_epi32(mi, 0x4e));
+  return _mm_xor_si128(hi, t);
+}
+
+VAES_TARGET
+static __m128i gfmul(__m128i a, __m128i b) {
+  __m128i lo = _mm_clmulepi64_si128(a, b, 0x00);
+  __m128i mi = _mm_xor_si128(_mm_clmulepi64_si128(a, b, 0x01),
+                             _mm_clmulepi64_si128(a, b, 0x10));
+  __m128i hi = _mm_clmulepi64_si128(a, b, 0x11);
+  return reduce(lo, mi, hi);
+}
+
+VAES_TARGET
+static __m128i load_round_key(const AES_KEY *key, unsigned i) {
+  const void *p = &key->rd_key[4 * i];
+  return _mm_loadu_si128(p);
+}
+
+/* The key schedule is the one from |GFp_aesni_set_encrypt_key|, so
+ * |key->rounds| is one less than the number of rounds. */
+VAES_TARGET
+static __m128i aes_encrypt_block(const AES_KEY *key, __m128i block) {
+  block = _mm_xor_si128(block, load_round_key(key, 0));
+  unsigned i;
+  for (i = 1; i <= key->rounds; ++i) {
+    block = _mm_aesenc_si128(block, load_round_key(key, i));
+  }
+  return _mm_aesenclast_si128(block, load_round_key(key, key->rounds + 1));
+}
+
+/* vaes_encrypt_4x2 encrypts the eight counter blocks in |b|. */
+VAES_TARGET
+static void vaes_encrypt_4x2(const AES_KEY *key, __m256i b[4]) {
+  __m256i rk = _mm256_broadcastsi128_si256(load_round_key(key, 0));
+  size_t j;
+  for (j = 0; j < 4; ++j) {
+    b[j] = _mm256_xor_si256(b[j], rk);
+  }
+  unsigned i;
+  for (i = 1; i <= key->rounds; ++i) {
+    rk = _mm256_broadcastsi128_si256(load_round_key(key, i));
+    for (j = 0; j < 4; ++j) {
+      b[j] = _mm256_aesenc_epi128(b[j], rk);
+    }
+  }
+  rk = _mm256_broadcastsi128_si256(load_round_key(key, key->rounds + 1));
+  for (j = 0; j < 4; ++j) {
+    b[j] = _mm256_aesenclast_epi128(b[j], rk);
+  }
+}
+
+/* The state shared by |GFp_vaes_gcm_encrypt| and |GFp_vaes_gcm_decrypt|. The
+ * counter block and the GHASH state are kept byte-reflected. */
+typedef struct {
+  /* |h_powers[j]| holds H^(8 - 2j) and H^(7 - 2j), twisted, in its low and
+   * high lanes, matching the order of the blocks in a chunk. */
+  __m256i h_powers[4];
+  __m256i ctr;
+  __m128i xi;
+} vaes_gcm_state;
+
+VAES_TARGET
+static void vaes_gcm_state_init(vaes_gcm_state *state, const AES_KEY *key,
+                                const uint8_t ivec[16], const uint8_t Xi[16]) {
+  /* H' = H * x^-1, computed by shifting H left by one bit and reducing if
+   * the bit shifted out was set. */
+  __m128i h = bswap_128(aes_encrypt_block(key, _mm_setzero_si128()));
+  __m128i carry = _mm_shuffle_epi32(_mm_srai_epi32(h, 31), 0xff);
+  h = _mm_or_si128(_mm_slli_epi64(h, 1),
+                   _mm_slli_si128(_mm_srli_epi64(h, 63), 8));
+  h = _mm_xor_si128(h, _mm_and_si128(carry, gfpoly()));
+
+  __m128i powers[8];
+  powers[0] = h;
+  size_t i;
+  for (i = 1; i < 8; ++i) {
+    powers[i] = gfmul(powers[i - 1], h);
+  }
+  for (i = 0; i < 4; ++i) {
+    state->h_powers[i] =
+        _mm256_inserti128_si256(_mm256_castsi128_si256(powers[7 - 2 * i]),
+                                powers[6 - 2 * i], 1);
+  }
+
+  /* After reflection, the 32-bit big-endian counter is the low dword of the
+   * lane, so it can be incremented with |_mm256_add_epi32|. */
+  const void *ivec_p = ivec;
+  state->ctr = _mm256_add_epi32(
+      _mm256_broadcastsi128_si256(bswap_128(_mm_loadu_si128(ivec_p))),
+      _mm256_setr_epi32(0, 0, 0, 0, 1, 0, 0, 0));
+
+  const void *xi_p = Xi;
+  state->xi = bswap_128(_mm_loadu_si128(xi_p));
+}
+
+VAES_TARGET
+static void vaes_gcm_state_finish(const vaes_gcm_state *state,
+                                  uint8_t ivec[16], uint8_t Xi[16]) {
+  void *ivec_p = ivec;
+  _mm_storeu_si128(ivec_p, bswap_128(_mm256_castsi256_si128(state->ctr)));
+  void *xi_p = Xi;
+  _mm_storeu_si128(xi_p, bswap_128(state->xi));
+}
+
+/* next_counter_blocks sets |b| to the next eight counter blocks. */
+VAES_TARGET
+static void next_counter_blocks(vaes_gcm_state *state, __m256i b[4]) {
+  const __m256i two = _mm256_setr_epi32(2, 0, 0, 0, 2, 0, 0, 0);
+  size_t j;
+  for (j = 0; j < 4; ++j) {
+    b[j] = bswap_256(state->ctr);
+    state->ctr = _mm256_add_epi32(state->ctr, two);
+  }
+}
+
+/* ghash_4x2 hashes the eight ciphertext blocks in |c| into the state. */
+VAES_TARGET
+static void ghash_4x2(vaes_gcm_state *state, const __m256i c[4]) {
+  __m256i lo = _mm256_setzero_si256();
+  __m256i mi = _mm256_setzero_si256();
+  __m256i hi = _mm256_setzero_si256();
+  size_t j;
+  for (j = 0; j < 4; ++j) {
+    __m256i x = bswap_256(c[j]);
+    if (j == 0) {
+      x = _mm256_xor_si256(
+          x, _mm256_inserti128_si256(_mm256_setzero_si256(), state->xi, 0));
+    }
+    const __m256i k = state->h_powers[j];
+    lo = _mm256_xor_si256(lo, _mm256_clmulepi64_epi128(x, k, 0x00));
+    mi = _mm256_xor_si256(mi, _mm256_clmulepi64_epi128(x, k, 0x01));
+    mi = _mm256_xor_si256(mi, _mm256_clmulepi64_epi128(x, k, 0x10));
+    hi = _mm256_xor_si256(hi, _mm256_clmulepi64_epi128(x, k, 0x11));
+  }
+  state->xi = reduce(_mm_xor_si128(_mm256_castsi256_si128(lo),
+                                   _mm256_extracti128_si256(lo, 1)),
+                     _mm_xor_si128(_mm256_castsi256_si128(mi),
+                                   _mm256_extracti128_si256(mi, 1)),
+                     _mm_xor_si128(_mm256_castsi256_si128(hi),
+                                   _mm256_extracti128_si256(hi, 1)));
+}
+
+VAES_TARGET
+size_t GFp_vaes_gcm_encrypt(const uint8_t *in, uint8_t *out, size_t len,
+                            const AES_KEY *key, uint8_t ivec[16],
+                            uint8_t Xi[16]) {
+  size_t bulk = len - (len % CHUNK_LEN);
+  if (bulk == 0) {
+    return 0;
+  }
+
+  vaes_gcm_state state;
+  vaes_gcm_state_init(&state, key, ivec, Xi);
+
+  size_t done;
+  for (done = 0; done < bulk; done += CHUNK_LEN) {
+    __m256i b[4];
+    next_counter_blocks(&state, b);
+    vaes_encrypt_4x2(key, b);
+    size_t j;
+    for (j = 0; j < 4; ++j) {
+      const void *in_p = in + done + 32 * j;
+      void *out_p = out + done + 32 * j;
+      b[j] = _mm256_xor_si256(b[j], _mm256_loadu_si256(in_p));
+      _mm256_storeu_si256(out_p, b[j]);
+    }
+    ghash_4x2(&state, b);
+  }
+
+  vaes_gcm_state_finish(&state, ivec, Xi);
+  return bulk;
+}
+
+VAES_TARGET
+size_t GFp_vaes_gcm_decrypt(const uint8_t *in, uint8_t *out, size_t len,
+                            const AES_KEY *key, uint8_t ivec[16],
+                            uint8_t Xi[16]) {
+  size_t bulk = len - (len % CHUNK_LEN);
+  if (bulk == 0) {
+    return 0;
+  }
+
+  vaes_gcm_state state;
+  vaes_gcm_state_init(&state, key, ivec, Xi);
+
+  size_t done;
+  for (done = 0; done < bulk; done += CHUNK_LEN) {
+    /* Load all of the ciphertext before writing any plaintext so that
+     * in-place decryption works. */
+    __m256i c[4];
+    size_t j;
+    for (j = 0; j < 4; ++j) {
+      const void *in_p = in + done + 32 * j;
+      c[j] = _mm256_loadu_si256(in_p);
+    }
+    ghash_4x2(&state, c);
+    __m256i b[4];
+    next_counter_blocks(&state, b);
+    vaes_encrypt_4x2(key, b);
+    for (j = 0; j < 4; ++j) {
+      void *out_p = out + done + 32 * j;
+      _mm256_storeu_si256(out_p, _mm256_xor_si256(b[j], c[j]));
+    }
+  }
+
+  vaes_gcm_state_finish(&state, ivec, Xi);
+  return bulk;
+}
+
+#endif  /* GFp_VAES_GCM */
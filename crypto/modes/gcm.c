@@ -300,6 +300,15 @@ size_t GFp_aesni_gcm_decrypt(const uint8_t *in, uint8_t *out, size_t len,
                              const void *key, uint8_t ivec[16], uint8_t Xi[16]);
 #endif
 
+#if defined(GFp_VAES_GCM)
+static int vaes_gcm_enabled(aes_ctr_f stream) {
+  return stream == GFp_aesni_ctr32_encrypt_blocks &&
+         (GFp_ia32cap_P[2] & (1 << 5)) != 0 && /* AVX2 */
+         (GFp_ia32cap_P[3] & (1 << 9)) != 0 && /* VAES */
+         (GFp_ia32cap_P[3] & (1 << 10)) != 0; /* VPCLMULQDQ */
+}
+#endif
+
 #if defined(OPENSSL_X86)
 #define GHASH_ASM_X86
 void GFp_gcm_gmult_4bit_mmx(uint8_t Xi[16], const u128 Htable[16]);
@@ -355,7 +364,11 @@ void GFp_gcm128_init_serialized(
   static const alignas(16) uint8_t ZEROS[16] = { 0 };
   uint8_t H_be[16];
   (*block)(ZEROS, H_be, key);
+  GFp_gcm128_init_serialized_from_h(serialized_ctx, H_be);
+}
 
+void GFp_gcm128_init_serialized_from_h(
+    uint8_t serialized_ctx[GCM128_SERIALIZED_LEN], const uint8_t H_be[16]) {
   /* H is stored in host byte order */
   alignas(16) uint64_t H[2];
   H[0] = from_be_u64_ptr(H_be);
@@ -469,17 +482,18 @@ void GFp_gcm128_init(GCM128_CONTEXT *ctx, const AES_KEY *key,
 }
 
 int GFp_gcm128_aad(GCM128_CONTEXT *ctx, const uint8_t *aad, size_t len) {
-  assert(ctx->len.u[0] == 0);
+  assert(ctx->len.u[0] % 16 == 0);
   assert(ctx->len.u[1] == 0);
 
 #ifdef GCM_FUNCREF_4BIT
   gcm128_gmult_f gcm_gmult_p = ctx->gmult;
 #endif
 
-  ctx->len.u[0] = len;
-  if (ctx->len.u[0] > (UINT64_C(1) << 61)) {
+  uint64_t total_len = ctx->len.u[0] + len;
+  if (total_len < len || total_len > (UINT64_C(1) << 61)) {
     return 0;
   }
+  ctx->len.u[0] = total_len;
 
   if (len > 0) {
     for (;;) {
@@ -690,6 +704,15 @@ int GFp_gcm128_encrypt_ctr32(GCM128_CONTEXT *ctx, const AES_KEY *key,
     return 0;
   }
 
+#if defined(GFp_VAES_GCM)
+  if (vaes_gcm_enabled(stream)) {
+    size_t bulk = GFp_vaes_gcm_encrypt(in, out, len, key, ctx->Yi, ctx->Xi);
+    in += bulk;
+    out += bulk;
+    len -= bulk;
+  }
+#endif
+
 #if defined(AESNI_GCM)
   if (aesni_gcm_enabled(ctx, stream)) {
     /* |aesni_gcm_encrypt| may not process all the input given to it. It may
@@ -769,6 +792,15 @@ int GFp_gcm128_decrypt_ctr32(GCM128_CONTEXT *ctx, const AES_KEY *key,
     return 0;
   }
 
+#if defined(GFp_VAES_GCM)
+  if (vaes_gcm_enabled(stream)) {
+    size_t bulk = GFp_vaes_gcm_decrypt(in, out, len, key, ctx->Yi, ctx->Xi);
+    in += bulk;
+    out += bulk;
+    len -= bulk;
+  }
+#endif
+
 #if defined(AESNI_GCM)
   if (aesni_gcm_enabled(ctx, stream)) {
     /* |aesni_gcm_decrypt| may not process all the input given to it. It may
@@ -855,6 +887,74 @@ void GFp_gcm128_tag(GCM128_CONTEXT *ctx, uint8_t tag[16]) {
   }
 }
 
+void GFp_gcm128_ghash(const uint8_t serialized_ctx[GCM128_SERIALIZED_LEN],
+                      uint8_t Xi[16], const uint8_t *in, size_t len) {
+  assert(len % 16 == 0);
+
+  alignas(16) GCM128_CONTEXT gcm;
+  GCM128_CONTEXT *ctx = &gcm;
+  memcpy(ctx->Htable, serialized_ctx, GCM128_SERIALIZED_LEN);
+  gcm128_init_gmult_ghash(ctx);
+  memcpy(ctx->Xi, Xi, sizeof(ctx->Xi));
+
+#if defined(GHASH)
+#ifdef GCM_FUNCREF_4BIT
+  gcm128_ghash_f gcm_ghash_p = ctx->ghash;
+#endif
+  if (len > 0) {
+    GHASH(ctx, in, len);
+  }
+#else
+#ifdef GCM_FUNCREF_4BIT
+  gcm128_gmult_f gcm_gmult_p = ctx->gmult;
+#endif
+  for (; len > 0; in += 16, len -= 16) {
+    for (size_t i = 0; i < 16; ++i) {
+      ctx->Xi[i] ^= in[i];
+    }
+    GCM_MUL(ctx, Xi);
+  }
+#endif
+
+  memcpy(Xi, ctx->Xi, sizeof(ctx->Xi));
+}
+
+/* Sets |Xi| to |Xi|*Y, where |Htable| was computed from Y by
+ * |gcm128_init_htable|. */
+static void gcm128_gmult(const GCM128_CONTEXT *ctx, uint8_t Xi[16],
+                         const u128 Htable[GCM128_HTABLE_LEN]) {
+#ifdef GCM_FUNCREF_4BIT
+  (*ctx->gmult)(Xi, Htable);
+#else
+  (void)ctx;
+  GFp_gcm_gmult_4bit(Xi, Htable);
+#endif
+}
+
+void GFp_gcm128_mul_h_pow(GCM128_CONTEXT *ctx, uint64_t n) {
+  /* |h_pow| is H**(2**i) for the i-th bit of |n|, and |Htable| is the table
+   * for it. The multiplicative identity is the element with only the
+   * leftmost bit set, so multiplying it by H gives H. */
+  alignas(16) uint8_t h_pow[16] = { 0x80 };
+  gcm128_gmult(ctx, h_pow, ctx->Htable);
+  alignas(16) u128 Htable[GCM128_HTABLE_LEN];
+  memcpy(Htable, ctx->Htable, sizeof(Htable));
+
+  while (n != 0) {
+    if (n & 1) {
+      gcm128_gmult(ctx, ctx->Xi, Htable);
+    }
+    n >>= 1;
+    if (n != 0) {
+      gcm128_gmult(ctx, h_pow, Htable);
+      alignas(16) uint64_t H[2];
+      H[0] = from_be_u64_ptr(h_pow);
+      H[1] = from_be_u64_ptr(h_pow + 8);
+      gcm128_init_htable(Htable, H);
+    }
+  }
+}
+
 #if defined(OPENSSL_X86) || defined(OPENSSL_X86_64)
 int GFp_gcm_clmul_enabled(void) {
 #ifdef GHASH_ASM
@@ -105,6 +105,37 @@ struct gcm128_context {
 int GFp_gcm_clmul_enabled(void);
 #endif
 
+/* The VAES implementation of AES-GCM needs a compiler that supports the
+ * VAES and VPCLMULQDQ intrinsics and the |target| attribute. */
+#if defined(OPENSSL_X86_64) && !defined(OPENSSL_NO_ASM) && !defined(_MSC_VER)
+#if defined(__clang__)
+#if __clang_major__ >= 6
+#define GFp_VAES_GCM
+#endif
+#elif defined(__GNUC__)
+#if __GNUC__ >= 8
+#define GFp_VAES_GCM
+#endif
+#endif
+#endif
+
+#if defined(GFp_VAES_GCM)
+/* GFp_vaes_gcm_encrypt encrypts and hashes as many whole 128-byte chunks of
+ * |in| as it can, using VAES and VPCLMULQDQ, and returns the number of bytes
+ * that it processed. |key| must have been expanded by
+ * |GFp_aesni_set_encrypt_key|. |ivec| is the counter block, which is updated,
+ * and |Xi| is the GHASH state, which is also updated. */
+size_t GFp_vaes_gcm_encrypt(const uint8_t *in, uint8_t *out, size_t len,
+                            const AES_KEY *key, uint8_t ivec[16],
+                            uint8_t Xi[16]);
+
+/* GFp_vaes_gcm_decrypt is like |GFp_vaes_gcm_encrypt| except that it hashes
+ * and decrypts. */
+size_t GFp_vaes_gcm_decrypt(const uint8_t *in, uint8_t *out, size_t len,
+                            const AES_KEY *key, uint8_t ivec[16],
+                            uint8_t Xi[16]);
+#endif
+
 
 /* CTR. */
 
@@ -126,12 +157,20 @@ OPENSSL_EXPORT void GFp_gcm128_init_serialized(
     uint8_t serialized_ctx[GCM128_SERIALIZED_LEN], const AES_KEY *key,
     aes_block_f block);
 
+/* GFp_gcm128_init_serialized_from_h is like |GFp_gcm128_init_serialized|
+ * except that it takes the hash key |H_be| directly instead of deriving it
+ * from an AES key, for using GHASH on its own. */
+OPENSSL_EXPORT void GFp_gcm128_init_serialized_from_h(
+    uint8_t serialized_ctx[GCM128_SERIALIZED_LEN], const uint8_t H_be[16]);
+
 OPENSSL_EXPORT void GFp_gcm128_init(
     GCM128_CONTEXT *ctx, const AES_KEY *key, aes_block_f block,
     const uint8_t serialized_ctx[GCM128_SERIALIZED_LEN], const uint8_t *iv);
 
 /* GFp_gcm128_aad sets the authenticated data for an instance of GCM. This must
- * be called before and data is encrypted. It returns one on success and zero
+ * be called before and data is encrypted. It may be called more than once to
+ * process the authenticated data in parts, but then the length of every part
+ * except the last must be a multiple of 16. It returns one on success and zero
  * otherwise. */
 OPENSSL_EXPORT int GFp_gcm128_aad(GCM128_CONTEXT *ctx, const uint8_t *aad,
                                   size_t len);
@@ -171,6 +210,18 @@ OPENSSL_EXPORT int GFp_gcm128_decrypt_ctr32(GCM128_CONTEXT *ctx,
 /* GFp_gcm128_tag calculates the authenticator and copies it into |tag|. */
 OPENSSL_EXPORT void GFp_gcm128_tag(GCM128_CONTEXT *ctx, uint8_t tag[16]);
 
+/* GFp_gcm128_mul_h_pow multiplies |ctx->Xi| by H**|n|. This lets the GHASH
+ * values of the chunks of a message that were hashed separately be
+ * combined. */
+OPENSSL_EXPORT void GFp_gcm128_mul_h_pow(GCM128_CONTEXT *ctx, uint64_t n);
+
+/* GFp_gcm128_ghash updates the GHASH state |Xi| with the |len| bytes at |in|,
+ * using the key table |serialized_ctx| from
+ * |GFp_gcm128_init_serialized_from_h|. |len| must be a multiple of 16. */
+OPENSSL_EXPORT void GFp_gcm128_ghash(
+    const uint8_t serialized_ctx[GCM128_SERIALIZED_LEN], uint8_t Xi[16],
+    const uint8_t *in, size_t len);
+
 
 #if !defined(OPENSSL_NO_ASM) && \
     (defined(OPENSSL_X86) || defined(OPENSSL_X86_64))
@@ -168,7 +168,7 @@ $code.=<<___;
 .align	6
 $func:
 ___
-$code.=<<___	if ($SZ==4);
+$code.=<<___;
 #ifdef	__ILP32__
 	ldrsw	x16,.LGFp_armcap_P
 #else
@@ -177,7 +177,7 @@ $code.=<<___	if ($SZ==4);
 	adr	x17,.LGFp_armcap_P
 	add	x16,x16,x17
 	ldr	w16,[x16]
-	tst	w16,#ARMV8_SHA256
+	tst	w16,#ARMV8_SHA$BITS
 	b.ne	.Lv8_entry
 ___
 $code.=<<___;
@@ -404,15 +404,103 @@ $code.=<<___;
 ___
 }
 
+if ($SZ==8) {
+my $Ktbl="x3";
+
+# The working state is in v0-v7: four registers hold the state as the pairs
+# (a,b), (c,d), (e,f), and (g,h) and the other four are temporaries. The roles
+# of the registers rotate every two rounds, so instead of moving values
+# between them, the registers are renamed. The chaining value is kept in
+# v26-v29 between blocks.
+my @H=map("v$_",(0..3));
+my @T=map("v$_",(4..7));
+my @MSG=map("v$_",(16..23));
+my $K="v24";
+my $Z="v25";
+my @SAVE=map("v$_",(26..29));
+
+$code.=<<___;
+.type	sha512_block_armv8,%function
+.align	6
+sha512_block_armv8:
+.Lv8_entry:
+	stp		x29,x30,[sp,#-16]!
+	add		x29,sp,#0
+
+	ld1		{@SAVE[0].2d,@SAVE[1].2d,@SAVE[2].2d,@SAVE[3].2d},[$ctx]
+	adr		$Ktbl,.LK512
+
+.Loop_hw:
+	ld1		{@MSG[0].16b,@MSG[1].16b,@MSG[2].16b,@MSG[3].16b},[$inp],#64
+	ld1		{@MSG[4].16b,@MSG[5].16b,@MSG[6].16b,@MSG[7].16b},[$inp],#64
+	subs		$num,$num,#1
+___
+for (my $i=0;$i<8;$i++) {
+$code.=<<___;
+	rev64		@MSG[$i].16b,@MSG[$i].16b
+___
+}
+for (my $i=0;$i<4;$i++) {
+$code.=<<___;
+	orr		@H[$i].16b,@SAVE[$i].16b,@SAVE[$i].16b
+___
+}
+for (my $i=0;$i<40;$i++) {
+	my ($ab,$cd,$ef,$gh)=@H;
+	my ($x,$y,$sum,$new_cd)=@T;
+	my $w=@MSG[$i%8];
+	if ($i>=8) {
+$code.=<<___;
+	sha512su0	$w.2d,@MSG[($i+1)%8].2d
+	ext		$Z.16b,@MSG[($i+4)%8].16b,@MSG[($i+5)%8].16b,#8
+	sha512su1	$w.2d,@MSG[($i+7)%8].2d,$Z.2d
+___
+	}
+$code.=<<___;
+	ld1		{$K.2d},[$Ktbl],#16
+	add		$K.2d,$K.2d,$w.2d
+	ext		$K.16b,$K.16b,$K.16b,#8
+	ext		$x.16b,$ef.16b,$gh.16b,#8
+	ext		$y.16b,$cd.16b,$ef.16b,#8
+	add		$sum.2d,$K.2d,$gh.2d
+	sha512h		q${\substr($sum,1)},q${\substr($x,1)},$y.2d
+	add		$new_cd.2d,$cd.2d,$sum.2d
+	sha512h2	q${\substr($sum,1)},q${\substr($cd,1)},$ab.2d
+___
+	# The new (g,h) is in $sum and the new (c,d) is in $new_cd; the old
+	# (c,d) and (g,h) registers are free. For the next two rounds, the new
+	# (g,h) is (a,b), (a,b) is (c,d), the new (c,d) is (e,f), and (e,f) is
+	# (g,h).
+	@H=($sum,$ab,$new_cd,$ef);
+	@T=($x,$y,$gh,$cd);
+}
+$code.=<<___;
+	add		@SAVE[0].2d,@SAVE[0].2d,@H[0].2d
+	add		@SAVE[1].2d,@SAVE[1].2d,@H[1].2d
+	add		@SAVE[2].2d,@SAVE[2].2d,@H[2].2d
+	add		@SAVE[3].2d,@SAVE[3].2d,@H[3].2d
+	sub		$Ktbl,$Ktbl,#80*8			// rewind
+	b.ne		.Loop_hw
+
+	st1		{@SAVE[0].2d,@SAVE[1].2d,@SAVE[2].2d,@SAVE[3].2d},[$ctx]
+
+	ldr		x29,[sp],#16
+	ret
+.size	sha512_block_armv8,.-sha512_block_armv8
+___
+}
+
 $code.=<<___;
 .comm	GFp_armcap_P,4,4
 ___
 
 {   my  %opcode = (
 	"sha256h"	=> 0x5e004000,	"sha256h2"	=> 0x5e005000,
-	"sha256su0"	=> 0x5e282800,	"sha256su1"	=> 0x5e006000	);
+	"sha256su0"	=> 0x5e282800,	"sha256su1"	=> 0x5e006000,
+	"sha512h"	=> 0xce608000,	"sha512h2"	=> 0xce608400,
+	"sha512su0"	=> 0xcec08000,	"sha512su1"	=> 0xce608800	);
 
-    sub unsha256 {
+    sub unsha {
 	my ($mnemonic,$arg)=@_;
 
 	$arg =~ m/[qv]([0-9]+)[^,]*,\s*[qv]([0-9]+)[^,]*(?:,\s*[qv]([0-9]+))?/o
@@ -427,7 +515,7 @@ foreach(split("\n",$code)) {
 
 	s/\`([^\`]*)\`/eval($1)/geo;
 
-	s/\b(sha256\w+)\s+([qv].*)/unsha256($1,$2)/geo;
+	s/\b(sha(?:256|512)\w+)\s+([qv].*)/unsha($1,$2)/geo;
 
 	s/\.\w?32\b//o		and s/\.16b/\.4s/go;
 	m/(ld|st)1[^\[]+\[0\]/o	and s/\.4s/\.s/go;
@@ -119,5 +119,8 @@
 /* ARMV8_PMULL indicates support for carryless multiplication. */
 #define ARMV8_PMULL (1 << 5)
 
+/* ARMV8_SHA512 indicates support for hardware SHA-512 instructions. */
+#define ARMV8_SHA512 (1 << 6)
+
 
 #endif  /* OPENSSL_HEADER_ARM_ARCH_H */
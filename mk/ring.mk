@@ -34,11 +34,14 @@ RING_SRCS = $(addprefix $(RING_PREFIX), \
   crypto/cipher/e_aes.c \
   crypto/crypto.c \
   crypto/curve25519/curve25519.c \
+  crypto/curve448/curve448.c \
   crypto/ec/ecp_nistz.c \
   crypto/ec/ecp_nistz256.c \
   crypto/ec/gfp_constant_time.c \
   crypto/ec/gfp_p256.c \
   crypto/ec/gfp_p384.c \
+  crypto/ec/gfp_secp256k1.c \
+  crypto/ec/gfp_sm2.c \
   crypto/mem.c \
   crypto/modes/gcm.c \
   crypto/rand/sysrand.c \
@@ -80,8 +83,10 @@ RING_x86_64_SRCS = $(addprefix $(RING_PREFIX), \
   crypto/curve25519/x25519-x86_64.c \
   crypto/ec/asm/ecp_nistz256-x86_64.pl \
   crypto/ec/asm/p256-x86_64-asm.pl \
+  crypto/ec/asm/p384-x86_64.pl \
   crypto/modes/asm/aesni-gcm-x86_64.pl \
   crypto/modes/asm/ghash-x86_64.pl \
+  crypto/modes/gcm-vaes-x86_64.c \
   crypto/poly1305/asm/poly1305-x86_64.pl \
   crypto/sha/asm/sha256-x86_64.pl \
   crypto/sha/asm/sha512-x86_64.pl \
@@ -25,30 +25,114 @@
 //! [AEAD]: http://www-cse.ucsd.edu/~mihir/papers/oem.html
 //! [`crypto.cipher.AEAD`]: https://golang.org/pkg/crypto/cipher/#AEAD
 
+pub mod aes_kw;
 pub mod chacha20_poly1305_openssh;
+pub mod quic;
+pub mod tls;
 
 mod chacha20_poly1305;
 mod aes_gcm;
+mod aes_ccm;
+mod aes_gcm_siv;
+mod aegis;
+mod aes_siv;
+mod ascon;
+mod committing;
+mod polyval;
+mod rekeying;
+mod stream;
+mod xaes_256_gcm;
+
+#[cfg(feature = "nonce_reuse_detection")]
+mod nonce_reuse;
+
+use {constant_time, core, error, hkdf, init, pbkdf2, poly1305, polyfill};
+
+#[cfg(feature = "use_heap")]
+use std;
+
+pub use self::chacha20_poly1305::{CHACHA20_POLY1305, XCHACHA20_POLY1305};
+pub use self::aegis::{AEGIS_128L, AEGIS_256};
+pub use self::aes_ccm::{AES_128_CCM, AES_128_CCM_8, AES_128_CCM_BLE,
+                        AES_256_CCM, AES_256_CCM_8};
+pub use self::aes_gcm::{AES_128_GCM, AES_192_GCM, AES_256_GCM};
+pub use self::aes_gcm_siv::{AES_128_GCM_SIV, AES_256_GCM_SIV};
+pub use self::aes_siv::{AES_128_SIV_CMAC, AES_256_SIV_CMAC};
+pub use self::ascon::{ASCON_128, ASCON_128A};
+pub use self::committing::{COMMITTING_TAG_LEN, CommittingKey};
+pub use self::rekeying::{CHAIN_KEY_LEN, RekeyingKey};
+pub use self::stream::{STREAM_NONCE_SUFFIX_LEN, StreamingOpener,
+                       StreamingSealer};
+pub use self::xaes_256_gcm::XAES_256_GCM;
+
+#[cfg(feature = "nonce_reuse_detection")]
+pub use self::nonce_reuse::{DetectNonceReuse, NonceReuseDetectingKey,
+                            NonceReuseDetector};
+
+/// A nonce for a single AEAD sealing or opening operation.
+///
+/// Sealing two different messages with the same key and nonce destroys the
+/// confidentiality and authenticity of both for most of the algorithms in
+/// this module. The only way to construct a `Nonce` is
+/// `Nonce::assume_unique_for_key`, to make that requirement explicit.
+pub struct Nonce {
+    value: [u8; MAX_NONCE_LEN],
+    len: usize,
+}
 
-use {constant_time, error, init, poly1305, polyfill};
+impl Nonce {
+    /// Constructs a `Nonce` with the given value, assuming that the value is
+    /// unique for the lifetime of the key it is being used with.
+    ///
+    /// Fails if `value` is longer than the nonces of all the algorithms in
+    /// this module.
+    pub fn assume_unique_for_key(value: &[u8])
+                                 -> Result<Nonce, error::Unspecified> {
+        if value.len() > MAX_NONCE_LEN {
+            return Err(error::Unspecified);
+        }
+        let mut nonce = Nonce {
+            value: [0; MAX_NONCE_LEN],
+            len: value.len(),
+        };
+        nonce.value[..value.len()].copy_from_slice(value);
+        Ok(nonce)
+    }
+}
+
+impl AsRef<[u8]> for Nonce {
+    #[inline]
+    fn as_ref(&self) -> &[u8] { &self.value[..self.len] }
+}
 
-pub use self::chacha20_poly1305::CHACHA20_POLY1305;
-pub use self::aes_gcm::{AES_128_GCM, AES_256_GCM};
+/// A sequence of unique nonces.
+///
+/// A given `NonceSequence` must never return the same `Nonce` twice from
+/// `advance()`. Every sealing or opening operation of a `SealingKey` or
+/// `OpeningKey` uses the next nonce of the key's `NonceSequence`.
+pub trait NonceSequence {
+    /// Returns the next nonce in the sequence, or an error if the sequence is
+    /// exhausted.
+    fn advance(&mut self) -> Result<Nonce, error::Unspecified>;
+}
 
 /// A key for authenticating and decrypting (&ldquo;opening&rdquo;)
-/// AEAD-protected data.
+/// AEAD-protected data, with the nonces of a `NonceSequence`.
 ///
 /// C analog: `EVP_AEAD_CTX` with direction `evp_aead_open`
 ///
 /// Go analog: [`crypto.cipher.AEAD`]
-pub struct OpeningKey {
+pub struct OpeningKey<N: NonceSequence> {
     key: Key,
+    nonce_sequence: N,
 }
 
-impl OpeningKey {
+impl<N: NonceSequence> OpeningKey<N> {
     /// Create a new opening key.
     ///
-    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long. The inputs
+    /// given to `open_in_place` must have been sealed with the same sequence
+    /// of nonces as `nonce_sequence` returns.
     ///
     /// C analogs: `EVP_AEAD_CTX_init_with_direction` with direction
     ///            `evp_aead_open`, `EVP_AEAD_CTX_init`.
@@ -57,16 +141,22 @@ impl OpeningKey {
     ///   [`crypto.aes.NewCipher`](https://golang.org/pkg/crypto/aes/#NewCipher)
     /// + [`crypto.cipher.NewGCM`](https://golang.org/pkg/crypto/cipher/#NewGCM)
     #[inline]
-    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
-               -> Result<OpeningKey, error::Unspecified> {
-        let mut key = OpeningKey {
-            key: Key {
-                algorithm: algorithm,
-                ctx_buf: [0; KEY_CTX_BUF_ELEMS],
-            },
-        };
-        try!(key.key.init(key_bytes));
-        Ok(key)
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8],
+               nonce_sequence: N) -> Result<OpeningKey<N>, error::Unspecified> {
+        Ok(OpeningKey {
+            key: try!(Key::new(algorithm, key_bytes)),
+            nonce_sequence: nonce_sequence,
+        })
+    }
+
+    /// Create a new opening key from `key`, e.g. a key derived with HKDF.
+    #[inline]
+    pub fn with_unbound_key(key: UnboundKey, nonce_sequence: N)
+                            -> OpeningKey<N> {
+        OpeningKey {
+            key: key.key,
+            nonce_sequence: nonce_sequence,
+        }
     }
 
     /// The key's AEAD algorithm.
@@ -74,63 +164,66 @@ impl OpeningKey {
     /// C analog: `EVP_AEAD_CTX.aead`
     #[inline(always)]
     pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
-}
 
-/// Authenticates and decrypts (&ldquo;opens&rdquo;) data in place.
-///
-/// The input is `in_out[in_prefix_len..]`; i.e. the input is the part of
-/// `in_out` after the prefix. When `open` returns `Ok(out_len)`, the decrypted
-/// output is `in_out[..out_len]`; i.e. the output has been written over the
-/// top of the prefix and the input. To put it a different way, the output
-/// overwrites the input, shifted by `in_prefix_len` bytes. To have the output
-/// overwrite the input without shifting, pass 0 as `in_prefix_len`. (The
-/// input/output buffer is expressed this way because Rust's type system does
-/// not allow us to have two slices, one mutable and one immutable, that
-/// reference overlapping memory at the same time.)
-///
-/// C analog: `EVP_AEAD_CTX_open`
-///
-/// Go analog: [`AEAD.Open`](https://golang.org/pkg/crypto/cipher/#AEAD)
-pub fn open_in_place(key: &OpeningKey, nonce: &[u8], in_prefix_len: usize,
-                     in_out: &mut [u8], ad: &[u8])
-                     -> Result<usize, error::Unspecified> {
-    let nonce = try!(slice_as_array_ref!(nonce, NONCE_LEN));
-    let ciphertext_and_tag_len =
-        try!(in_out.len().checked_sub(in_prefix_len)
-                         .ok_or(error::Unspecified));
-    let ciphertext_len =
-        try!(ciphertext_and_tag_len.checked_sub(TAG_LEN)
-                                   .ok_or(error::Unspecified));
-    try!(check_per_nonce_max_bytes(ciphertext_len));
-    let (in_out, received_tag) =
-        in_out.split_at_mut(in_prefix_len + ciphertext_len);
-    let mut calculated_tag = [0u8; TAG_LEN];
-    try!((key.key.algorithm.open)(&key.key.ctx_buf, nonce, in_out,
-                                  in_prefix_len, &mut calculated_tag, ad));
-    if constant_time::verify_slices_are_equal(&calculated_tag, received_tag)
-            .is_err() {
-        // Zero out the plaintext so that it isn't accidentally leaked or used
-        // after verification fails. It would be safest if we could check the
-        // tag before decrypting, but some `open` implementations interleave
-        // authentication with decryption for performance.
-        for b in &mut in_out[..ciphertext_len] {
-            *b = 0;
-        }
-        return Err(error::Unspecified);
+    /// Authenticates and decrypts (&ldquo;opens&rdquo;) data in place, using
+    /// the next nonce of the key's nonce sequence.
+    ///
+    /// `in_prefix_len` and `in_out` are the same as for `open_in_place`. The
+    /// nonce is used up even if opening fails.
+    ///
+    /// C analog: `EVP_AEAD_CTX_open`
+    ///
+    /// Go analog: [`AEAD.Open`](https://golang.org/pkg/crypto/cipher/#AEAD)
+    pub fn open_in_place(&mut self, in_prefix_len: usize, in_out: &mut [u8],
+                         ad: &[u8]) -> Result<usize, error::Unspecified> {
+        let nonce = try!(self.nonce_sequence.advance());
+        self.key.open_in_place(nonce.as_ref(), in_prefix_len, in_out, &[ad])
+    }
+
+    /// Like `OpeningKey::open_in_place`, except the additional authenticated
+    /// data is the concatenation of the parts of `ad`.
+    pub fn open_in_place_with_ad_parts(&mut self, in_prefix_len: usize,
+                                       in_out: &mut [u8], ad: &[&[u8]])
+                                       -> Result<usize, error::Unspecified> {
+        let nonce = try!(self.nonce_sequence.advance());
+        self.key.open_in_place(nonce.as_ref(), in_prefix_len, in_out, ad)
+    }
+
+    /// Authenticates and decrypts (&ldquo;opens&rdquo;) data in place without
+    /// moving it, using the next nonce of the key's nonce sequence.
+    ///
+    /// `in_prefix_len` and `in_out` are the same as for `open_within`. The
+    /// nonce is used up even if opening fails.
+    pub fn open_within(&mut self, in_prefix_len: usize, in_out: &mut [u8],
+                       ad: &[u8])
+                       -> Result<core::ops::Range<usize>, error::Unspecified> {
+        let nonce = try!(self.nonce_sequence.advance());
+        self.key.open_within(nonce.as_ref(), in_prefix_len, in_out, &[ad])
     }
-    Ok(ciphertext_len) // `ciphertext_len` is also the plaintext length.
 }
 
-/// A key for encrypting and signing (&ldquo;sealing&rdquo;) data.
+/// A key for encrypting and signing (&ldquo;sealing&rdquo;) data, with the
+/// nonces of a `NonceSequence`.
+///
+/// A `SealingKey` counts the messages and the 16-byte blocks of plaintext it
+/// seals, and stops sealing with `SealError::KeyExhausted` once either
+/// reaches its algorithm's confidentiality limit.
 ///
 /// C analog: `EVP_AEAD_CTX` with direction `evp_aead_seal`.
 ///
 /// Go analog: [`AEAD`](https://golang.org/pkg/crypto/cipher/#AEAD)
-pub struct SealingKey {
+pub struct SealingKey<N: NonceSequence> {
     key: Key,
+    nonce_sequence: N,
+    messages_sealed: u64,
+    blocks_sealed: u64,
 }
 
-impl SealingKey {
+impl<N: NonceSequence> SealingKey<N> {
+    /// Create a new sealing key.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    ///
     /// C analogs: `EVP_AEAD_CTX_init_with_direction` with direction
     ///            `evp_aead_seal`, `EVP_AEAD_CTX_init`.
     ///
@@ -138,16 +231,184 @@ impl SealingKey {
     ///   [`crypto.aes.NewCipher`](https://golang.org/pkg/crypto/aes/#NewCipher)
     /// + [`crypto.cipher.NewGCM`](https://golang.org/pkg/crypto/cipher/#NewGCM)
     #[inline]
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8],
+               nonce_sequence: N) -> Result<SealingKey<N>, error::Unspecified> {
+        Ok(SealingKey {
+            key: try!(Key::new(algorithm, key_bytes)),
+            nonce_sequence: nonce_sequence,
+            messages_sealed: 0,
+            blocks_sealed: 0,
+        })
+    }
+
+    /// Create a new sealing key from `key`, e.g. a key derived with HKDF.
+    #[inline]
+    pub fn with_unbound_key(key: UnboundKey, nonce_sequence: N)
+                            -> SealingKey<N> {
+        SealingKey {
+            key: key.key,
+            nonce_sequence: nonce_sequence,
+            messages_sealed: 0,
+            blocks_sealed: 0,
+        }
+    }
+
+    /// The key's AEAD algorithm.
+    ///
+    /// C analog: `EVP_AEAD_CTX.aead`
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// Encrypts and signs (&ldquo;seals&rdquo;) data in place, using the next
+    /// nonce of the key's nonce sequence.
+    ///
+    /// `in_out` and `out_suffix_capacity` are the same as for
+    /// `seal_in_place`. The nonce is used up even if sealing fails. Fails
+    /// with `SealError::KeyExhausted`, without using a nonce, if sealing the
+    /// plaintext would exceed the key's confidentiality limit.
+    ///
+    /// C analog: `EVP_AEAD_CTX_seal`.
+    ///
+    /// Go analog: [`AEAD.Seal`](https://golang.org/pkg/crypto/cipher/#AEAD)
+    pub fn seal_in_place(&mut self, in_out: &mut [u8],
+                         out_suffix_capacity: usize, ad: &[u8])
+                         -> Result<usize, SealError> {
+        self.seal_in_place_with_ad_parts(in_out, out_suffix_capacity, &[ad])
+    }
+
+    /// Like `SealingKey::seal_in_place`, except the additional authenticated
+    /// data is the concatenation of the parts of `ad`.
+    pub fn seal_in_place_with_ad_parts(&mut self, in_out: &mut [u8],
+                                       out_suffix_capacity: usize,
+                                       ad: &[&[u8]])
+                                       -> Result<usize, SealError> {
+        let plaintext_len =
+            in_out.len().checked_sub(out_suffix_capacity).unwrap_or(0);
+        let nonce = try!(self.advance(plaintext_len));
+        let out_len = try!(self.key.seal_in_place(nonce.as_ref(), in_out,
+                                                  out_suffix_capacity, ad));
+        Ok(out_len)
+    }
+
+    /// Like `SealingKey::seal_in_place`, except the tag is appended to
+    /// `in_out` as for `seal_in_place_append_tag`.
+    #[cfg(feature = "use_heap")]
+    pub fn seal_in_place_append_tag(&mut self, in_out: &mut std::vec::Vec<u8>,
+                                    ad: &[u8])
+                                    -> Result<(), SealError> {
+        let nonce = try!(self.advance(in_out.len()));
+        try!(self.key.seal_in_place_append_tag(nonce.as_ref(), in_out, &[ad]));
+        Ok(())
+    }
+
+    /// Whether the key has sealed as many messages, or as many blocks, as its
+    /// confidentiality limit allows.
+    ///
+    /// Once it has, every sealing operation fails with
+    /// `SealError::KeyExhausted`, and the protocol must switch to a new key,
+    /// e.g. with a TLS 1.3 `KeyUpdate` or a QUIC key update.
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.messages_remaining() == 0 || self.blocks_remaining() == 0
+    }
+
+    /// The number of messages that can still be sealed with the key, so that
+    /// a protocol can start switching keys before the key is exhausted.
+    #[inline]
+    pub fn messages_remaining(&self) -> u64 {
+        self.key.algorithm.confidentiality_limit - self.messages_sealed
+    }
+
+    /// The number of 16-byte blocks of plaintext that can still be sealed
+    /// with the key. A partial block counts as a whole one.
+    #[inline]
+    pub fn blocks_remaining(&self) -> u64 {
+        self.key.algorithm.confidentiality_limit_blocks() - self.blocks_sealed
+    }
+
+    /// Returns the next nonce, counting it and the blocks of a
+    /// `plaintext_len`-byte plaintext as sealed whether or not sealing
+    /// succeeds.
+    fn advance(&mut self, plaintext_len: usize) -> Result<Nonce, SealError> {
+        let partial_block = if plaintext_len % BLOCK_LEN != 0 { 1 } else { 0 };
+        let blocks =
+            polyfill::u64_from_usize(plaintext_len / BLOCK_LEN) + partial_block;
+        if self.is_exhausted() || blocks > self.blocks_remaining() {
+            return Err(SealError::KeyExhausted);
+        }
+        let nonce = try!(self.nonce_sequence.advance());
+        self.messages_sealed += 1;
+        self.blocks_sealed += blocks;
+        Ok(nonce)
+    }
+}
+
+/// An error from sealing with a `SealingKey`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SealError {
+    /// Sealing the message would exceed the key's confidentiality limit;
+    /// see `SealingKey::is_exhausted()`. No nonce was used.
+    KeyExhausted,
+
+    /// Sealing failed for any other reason, e.g. because the nonce sequence
+    /// is exhausted or `in_out` is too short.
+    Unspecified,
+}
+
+impl core::fmt::Display for SealError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match *self {
+            SealError::KeyExhausted => "ring::aead::SealError::KeyExhausted",
+            SealError::Unspecified => "ring::aead::SealError::Unspecified",
+        })
+    }
+}
+
+#[cfg(feature = "use_heap")]
+impl std::error::Error for SealError {
+    #[inline]
+    fn cause(&self) -> Option<&std::error::Error> { None }
+
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SealError::KeyExhausted => "ring::aead::SealError::KeyExhausted",
+            SealError::Unspecified => "ring::aead::SealError::Unspecified",
+        }
+    }
+}
+
+impl From<error::Unspecified> for SealError {
+    fn from(_: error::Unspecified) -> Self { SealError::Unspecified }
+}
+
+impl From<SealError> for error::Unspecified {
+    fn from(_: SealError) -> Self { error::Unspecified }
+}
+
+/// A key for sealing and opening data with explicitly-given nonces, using
+/// `seal_in_place` and `open_in_place`.
+///
+/// `LessSafeKey` is less safe than `SealingKey` and `OpeningKey` because
+/// nothing stops a nonce from being used twice with it. Prefer those unless
+/// the nonces are dictated by a protocol in a way that a `NonceSequence`
+/// can't express.
+///
+/// C analog: `EVP_AEAD_CTX`
+pub struct LessSafeKey {
+    key: Key,
+}
+
+impl LessSafeKey {
+    /// Create a new key.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    ///
+    /// C analog: `EVP_AEAD_CTX_init`.
+    #[inline]
     pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
-               -> Result<SealingKey, error::Unspecified> {
-        let mut key = SealingKey {
-            key: Key {
-                algorithm: algorithm,
-                ctx_buf: [0; KEY_CTX_BUF_ELEMS],
-            },
-        };
-        try!(key.key.init(key_bytes));
-        Ok(key)
+               -> Result<LessSafeKey, error::Unspecified> {
+        Ok(LessSafeKey { key: try!(Key::new(algorithm, key_bytes)) })
     }
 
     /// The key's AEAD algorithm.
@@ -157,6 +418,130 @@ impl SealingKey {
     pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
 }
 
+impl From<UnboundKey> for LessSafeKey {
+    fn from(key: UnboundKey) -> LessSafeKey { LessSafeKey { key: key.key } }
+}
+
+/// An AEAD key that isn't yet bound to a direction or a nonce sequence.
+///
+/// An `UnboundKey` can be constructed directly from the output of HKDF or
+/// PBKDF2 with `From<hkdf::Okm<&'static Algorithm>>` or
+/// `From<pbkdf2::Okm<&'static Algorithm>>`, so that the key bytes never pass
+/// through a buffer of the caller's. It is then turned into a `SealingKey` or
+/// `OpeningKey` with their `with_unbound_key` constructors, or into a
+/// `LessSafeKey`.
+///
+/// ```
+/// use ring::{aead, digest, hkdf};
+///
+/// # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+/// let salt = hkdf::Salt::new(&digest::SHA256, b"salt");
+/// let prk = salt.extract(b"input keying material");
+/// let info: &[&[u8]] = &[b"client write key"];
+/// let key: aead::UnboundKey =
+///     try!(prk.expand(info, &aead::AES_128_GCM)).into();
+/// let key = aead::LessSafeKey::from(key);
+/// # let _ = key;
+/// # Ok(())
+/// # }
+/// # fn main() { main_with_result().unwrap() }
+/// ```
+pub struct UnboundKey {
+    key: Key,
+}
+
+impl UnboundKey {
+    /// Create a new key.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    #[inline]
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
+               -> Result<UnboundKey, error::Unspecified> {
+        Ok(UnboundKey { key: try!(Key::new(algorithm, key_bytes)) })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+}
+
+impl<'a> From<hkdf::Okm<'a, &'static Algorithm>> for UnboundKey {
+    fn from(okm: hkdf::Okm<'a, &'static Algorithm>) -> UnboundKey {
+        let algorithm = *okm.key_type();
+        let mut key_bytes = [0u8; MAX_KEY_LEN];
+        let key_bytes = &mut key_bytes[..algorithm.key_len()];
+        okm.fill(key_bytes).unwrap();
+        UnboundKey::new(algorithm, key_bytes).unwrap()
+    }
+}
+
+impl<'a> From<pbkdf2::Okm<'a, &'static Algorithm>> for UnboundKey {
+    fn from(okm: pbkdf2::Okm<'a, &'static Algorithm>) -> UnboundKey {
+        let algorithm = *okm.key_type();
+        let mut key_bytes = [0u8; MAX_KEY_LEN];
+        let key_bytes = &mut key_bytes[..algorithm.key_len()];
+        okm.fill(key_bytes).unwrap();
+        UnboundKey::new(algorithm, key_bytes).unwrap()
+    }
+}
+
+/// The key type of an AEAD key, for deriving `UnboundKey`s with HKDF or
+/// PBKDF2.
+impl hkdf::KeyType for &'static Algorithm {
+    fn len(&self) -> usize { self.key_len() }
+}
+
+// The longest `key_len` of any AEAD algorithm, AES-256-SIV's.
+const MAX_KEY_LEN: usize = 512 / 8;
+
+/// Authenticates and decrypts (&ldquo;opens&rdquo;) data in place.
+///
+/// The input is `in_out[in_prefix_len..]`; i.e. the input is the part of
+/// `in_out` after the prefix. When `open` returns `Ok(out_len)`, the decrypted
+/// output is `in_out[..out_len]`; i.e. the output has been written over the
+/// top of the prefix and the input. To put it a different way, the output
+/// overwrites the input, shifted by `in_prefix_len` bytes. To have the output
+/// overwrite the input without shifting, pass 0 as `in_prefix_len`. (The
+/// input/output buffer is expressed this way because Rust's type system does
+/// not allow us to have two slices, one mutable and one immutable, that
+/// reference overlapping memory at the same time.)
+///
+/// C analog: `EVP_AEAD_CTX_open`
+///
+/// Go analog: [`AEAD.Open`](https://golang.org/pkg/crypto/cipher/#AEAD)
+pub fn open_in_place(key: &LessSafeKey, nonce: &[u8], in_prefix_len: usize,
+                     in_out: &mut [u8], ad: &[u8])
+                     -> Result<usize, error::Unspecified> {
+    key.key.open_in_place(nonce, in_prefix_len, in_out, &[ad])
+}
+
+/// Like `open_in_place`, except the additional authenticated data is the
+/// concatenation of the parts of `ad`.
+///
+/// This saves protocols that authenticate several separate header fields
+/// from copying them into one buffer for every record.
+pub fn open_in_place_with_ad_parts(key: &LessSafeKey, nonce: &[u8],
+                                   in_prefix_len: usize, in_out: &mut [u8],
+                                   ad: &[&[u8]])
+                                   -> Result<usize, error::Unspecified> {
+    key.key.open_in_place(nonce, in_prefix_len, in_out, ad)
+}
+
+/// Authenticates and decrypts (&ldquo;opens&rdquo;) data in place without
+/// moving it.
+///
+/// The input is `in_out[in_prefix_len..]`, as for `open_in_place`, but the
+/// output is written over the input without being shifted over the prefix.
+/// When `open_within` returns `Ok(range)`, the decrypted output is
+/// `in_out[range]`, where `range.start` is `in_prefix_len`. This avoids
+/// moving the output when the caller, e.g. a TLS record layer that has the
+/// record header in the prefix, doesn't need it at the start of `in_out`.
+pub fn open_within(key: &LessSafeKey, nonce: &[u8], in_prefix_len: usize,
+                   in_out: &mut [u8], ad: &[u8])
+                   -> Result<core::ops::Range<usize>, error::Unspecified> {
+    key.key.open_within(nonce, in_prefix_len, in_out, &[ad])
+}
+
 /// Encrypts and signs (&ldquo;seals&rdquo;) data in place.
 ///
 /// `nonce` must be unique for every use of the key to seal data.
@@ -178,25 +563,99 @@ impl SealingKey {
 /// C analog: `EVP_AEAD_CTX_seal`.
 ///
 /// Go analog: [`AEAD.Seal`](https://golang.org/pkg/crypto/cipher/#AEAD)
-pub fn seal_in_place(key: &SealingKey, nonce: &[u8], in_out: &mut [u8],
+pub fn seal_in_place(key: &LessSafeKey, nonce: &[u8], in_out: &mut [u8],
                      out_suffix_capacity: usize, ad: &[u8])
                      -> Result<usize, error::Unspecified> {
-    if out_suffix_capacity < key.key.algorithm.max_overhead_len() {
-        return Err(error::Unspecified);
-    }
-    let nonce = try!(slice_as_array_ref!(nonce, NONCE_LEN));
-    let in_out_len =
-        try!(in_out.len().checked_sub(out_suffix_capacity)
-                         .ok_or(error::Unspecified));
-    try!(check_per_nonce_max_bytes(in_out_len));
-    let (in_out, tag_out) = in_out.split_at_mut(in_out_len);
-    let tag_out = try!(slice_as_array_ref_mut!(tag_out, TAG_LEN));
-    try!((key.key.algorithm.seal)(&key.key.ctx_buf, nonce, in_out, tag_out, ad));
-    Ok(in_out_len + TAG_LEN)
+    key.key.seal_in_place(nonce, in_out, out_suffix_capacity, &[ad])
+}
+
+/// Like `seal_in_place`, except the additional authenticated data is the
+/// concatenation of the parts of `ad`.
+///
+/// This saves protocols that authenticate several separate header fields
+/// from copying them into one buffer for every record.
+pub fn seal_in_place_with_ad_parts(key: &LessSafeKey, nonce: &[u8],
+                                   in_out: &mut [u8],
+                                   out_suffix_capacity: usize, ad: &[&[u8]])
+                                   -> Result<usize, error::Unspecified> {
+    key.key.seal_in_place(nonce, in_out, out_suffix_capacity, ad)
+}
+
+/// Encrypts and signs (&ldquo;seals&rdquo;) `plaintext` without modifying
+/// it, writing the ciphertext to `ciphertext_out` and the tag to the start of
+/// `tag_out`.
+///
+/// This is for callers whose input can't be written to, e.g. because it is
+/// borrowed from a memory-mapped file. `ciphertext_out` must be exactly as
+/// long as `plaintext`, and `tag_out` must be at least
+/// `key.algorithm().max_overhead_len()` bytes long. The length of the tag is
+/// returned.
+///
+/// C analog: `EVP_AEAD_CTX_seal_scatter`
+pub fn seal_scatter(key: &LessSafeKey, nonce: &[u8], plaintext: &[u8],
+                    ciphertext_out: &mut [u8], tag_out: &mut [u8], ad: &[u8])
+                    -> Result<usize, error::Unspecified> {
+    key.key.seal_scatter(nonce, plaintext, ciphertext_out, tag_out, &[ad])
+}
+
+/// Authenticates and decrypts (&ldquo;opens&rdquo;) `ciphertext`, with the
+/// tag `tag`, without modifying it, writing the plaintext to
+/// `plaintext_out`.
+///
+/// `plaintext_out` must be exactly as long as `ciphertext`. If opening fails,
+/// `plaintext_out` is zeroed.
+///
+/// C analog: `EVP_AEAD_CTX_open_gather`
+pub fn open_gather(key: &LessSafeKey, nonce: &[u8], ciphertext: &[u8],
+                   tag: &[u8], plaintext_out: &mut [u8], ad: &[u8])
+                   -> Result<(), error::Unspecified> {
+    key.key.open_gather(nonce, ciphertext, tag, plaintext_out, &[ad])
+}
+
+/// Like `seal_in_place`, except the tag is appended to `in_out`, which holds
+/// just the plaintext on input, instead of being written into space that the
+/// caller reserved for it.
+///
+/// On failure `in_out` is left unchanged.
+#[cfg(feature = "use_heap")]
+pub fn seal_in_place_append_tag(key: &LessSafeKey, nonce: &[u8],
+                                in_out: &mut std::vec::Vec<u8>, ad: &[u8])
+                                -> Result<(), error::Unspecified> {
+    key.key.seal_in_place_append_tag(nonce, in_out, &[ad])
+}
+
+/// Like `seal_in_place`, except that a long input is split into up to
+/// `threads` chunks that are sealed on separate threads.
+///
+/// Only `AES_128_GCM`, `AES_192_GCM`, and `AES_256_GCM` keys are supported.
+/// The output is exactly the same as `seal_in_place`'s; inputs that are too
+/// short to be worth splitting are sealed on the current thread. This is for
+/// bulk encryption, e.g. of disk images, that a single core can't keep up
+/// with.
+#[cfg(feature = "use_heap")]
+pub fn seal_in_place_parallel(key: &LessSafeKey, nonce: &[u8],
+                              in_out: &mut [u8], out_suffix_capacity: usize,
+                              ad: &[u8], threads: usize)
+                              -> Result<usize, error::Unspecified> {
+    key.key.seal_in_place_parallel(nonce, in_out, out_suffix_capacity, &[ad],
+                                   threads)
 }
 
-/// `OpeningKey` and `SealingKey` are type-safety wrappers around `Key`, which
-/// does all the actual work via the C AEAD interface.
+/// Like `open_in_place` with an `in_prefix_len` of zero, except that a long
+/// input is split into up to `threads` chunks that are opened on separate
+/// threads.
+///
+/// Only `AES_128_GCM`, `AES_192_GCM`, and `AES_256_GCM` keys are supported.
+/// See `seal_in_place_parallel`.
+#[cfg(feature = "use_heap")]
+pub fn open_in_place_parallel(key: &LessSafeKey, nonce: &[u8],
+                              in_out: &mut [u8], ad: &[u8], threads: usize)
+                              -> Result<usize, error::Unspecified> {
+    key.key.open_in_place_parallel(nonce, in_out, &[ad], threads)
+}
+
+/// `OpeningKey`, `SealingKey`, and `LessSafeKey` are type-safety wrappers
+/// around `Key`, which does all the actual work via the C AEAD interface.
 ///
 /// C analog: `EVP_AEAD_CTX`
 struct Key {
@@ -212,23 +671,235 @@ const KEY_CTX_BUF_ELEMS: usize = (KEY_CTX_BUF_LEN + 7) / 8;
 const KEY_CTX_BUF_LEN: usize = self::aes_gcm::AES_KEY_CTX_BUF_LEN;
 
 impl Key {
-    /// XXX: Assumes self.algorithm is already filled in.
-    ///
     /// C analogs: `EVP_AEAD_CTX_init`, `EVP_AEAD_CTX_init_with_direction`
-    fn init(&mut self, key_bytes: &[u8]) -> Result<(), error::Unspecified> {
+    fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
+           -> Result<Key, error::Unspecified> {
         init::init_once();
 
-        if key_bytes.len() != self.algorithm.key_len() {
+        if key_bytes.len() != algorithm.key_len() {
             return Err(error::Unspecified);
         }
 
-        let ctx_buf_bytes = polyfill::slice::u64_as_u8_mut(&mut self.ctx_buf);
-        (self.algorithm.init)(ctx_buf_bytes, key_bytes)
+        let mut key = Key {
+            algorithm: algorithm,
+            ctx_buf: [0; KEY_CTX_BUF_ELEMS],
+        };
+        {
+            let ctx_buf_bytes =
+                polyfill::slice::u64_as_u8_mut(&mut key.ctx_buf);
+            try!((algorithm.init)(ctx_buf_bytes, key_bytes));
+        }
+        Ok(key)
     }
 
     /// The key's AEAD algorithm.
     #[inline(always)]
     fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+
+    fn open_in_place(&self, nonce: &[u8], in_prefix_len: usize,
+                     in_out: &mut [u8], ad: &[&[u8]])
+                     -> Result<usize, error::Unspecified> {
+        let ciphertext_and_tag_len =
+            try!(in_out.len().checked_sub(in_prefix_len)
+                             .ok_or(error::Unspecified));
+        let ciphertext_len =
+            try!(ciphertext_and_tag_len.checked_sub(self.algorithm.tag_len)
+                                       .ok_or(error::Unspecified));
+        let (in_out, received_tag) =
+            in_out.split_at_mut(in_prefix_len + ciphertext_len);
+        try!(self.open_separate_tag(nonce, in_prefix_len, in_out,
+                                    received_tag, ad));
+        Ok(ciphertext_len) // `ciphertext_len` is also the plaintext length.
+    }
+
+    /// Opens `in_out[in_prefix_len..]`, which is just the ciphertext, using
+    /// `received_tag`, which must be exactly `tag_len` bytes long.
+    fn open_separate_tag(&self, nonce: &[u8], in_prefix_len: usize,
+                         in_out: &mut [u8], received_tag: &[u8],
+                         ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+        self.open_separate_tag_with(nonce, in_prefix_len, in_out,
+                                    received_tag, ad, self.algorithm.open)
+    }
+
+    /// Like `open_separate_tag`, except the tag is calculated by `open`
+    /// instead of `self.algorithm.open`.
+    fn open_separate_tag_with<F>(&self, nonce: &[u8], in_prefix_len: usize,
+                                 in_out: &mut [u8], received_tag: &[u8],
+                                 ad: &[&[u8]], open: F)
+                                 -> Result<(), error::Unspecified>
+            where F: FnOnce(&[u64; KEY_CTX_BUF_ELEMS], &[u8], &mut [u8],
+                            usize, &[u8; TAG_LEN], &mut [u8; TAG_LEN],
+                            &[&[u8]]) -> Result<(), error::Unspecified> {
+        try!(check_nonce_len(self.algorithm, nonce));
+        let tag_len = self.algorithm.tag_len;
+        if received_tag.len() != tag_len {
+            return Err(error::Unspecified);
+        }
+        let ciphertext_len =
+            try!(in_out.len().checked_sub(in_prefix_len)
+                             .ok_or(error::Unspecified));
+        try!(check_per_nonce_max_bytes(ciphertext_len));
+        let mut padded_received_tag = [0u8; TAG_LEN];
+        padded_received_tag[..tag_len].copy_from_slice(received_tag);
+        let mut calculated_tag = [0u8; TAG_LEN];
+        try!(open(&self.ctx_buf, nonce, in_out, in_prefix_len,
+                  &padded_received_tag, &mut calculated_tag, ad));
+        if constant_time::verify_slices_are_equal(&calculated_tag[..tag_len],
+                                                  received_tag).is_err() {
+            // Zero out the plaintext so that it isn't accidentally leaked or
+            // used after verification fails. It would be safest if we could
+            // check the tag before decrypting, but some `open` implementations
+            // interleave authentication with decryption for performance.
+            for b in &mut in_out[..ciphertext_len] {
+                *b = 0;
+            }
+            return Err(error::Unspecified);
+        }
+        Ok(())
+    }
+
+    fn open_gather(&self, nonce: &[u8], ciphertext: &[u8], tag: &[u8],
+                   plaintext_out: &mut [u8], ad: &[&[u8]])
+                   -> Result<(), error::Unspecified> {
+        if plaintext_out.len() != ciphertext.len() {
+            return Err(error::Unspecified);
+        }
+        plaintext_out.copy_from_slice(ciphertext);
+        self.open_separate_tag(nonce, 0, plaintext_out, tag, ad)
+    }
+
+    fn open_within(&self, nonce: &[u8], in_prefix_len: usize,
+                   in_out: &mut [u8], ad: &[&[u8]])
+                   -> Result<core::ops::Range<usize>, error::Unspecified> {
+        if in_prefix_len > in_out.len() {
+            return Err(error::Unspecified);
+        }
+        let plaintext_len =
+            try!(self.open_in_place(nonce, 0, &mut in_out[in_prefix_len..],
+                                    ad));
+        Ok(in_prefix_len..(in_prefix_len + plaintext_len))
+    }
+
+    fn seal_in_place(&self, nonce: &[u8], in_out: &mut [u8],
+                     out_suffix_capacity: usize, ad: &[&[u8]])
+                     -> Result<usize, error::Unspecified> {
+        if out_suffix_capacity < self.algorithm.max_overhead_len() {
+            return Err(error::Unspecified);
+        }
+        let in_out_len =
+            try!(in_out.len().checked_sub(out_suffix_capacity)
+                             .ok_or(error::Unspecified));
+        let (in_out, tag_out) = in_out.split_at_mut(in_out_len);
+        let tag_len = try!(self.seal_separate_tag(nonce, in_out, tag_out, ad));
+        Ok(in_out_len + tag_len)
+    }
+
+    /// Seals `in_out` and writes the tag to the start of `tag_out`, which
+    /// must be at least `tag_len` bytes long, returning the tag length.
+    fn seal_separate_tag(&self, nonce: &[u8], in_out: &mut [u8],
+                         tag_out: &mut [u8], ad: &[&[u8]])
+                         -> Result<usize, error::Unspecified> {
+        self.seal_separate_tag_with(nonce, in_out, tag_out, ad,
+                                    self.algorithm.seal)
+    }
+
+    /// Like `seal_separate_tag`, except the sealing is done by `seal` instead
+    /// of `self.algorithm.seal`.
+    fn seal_separate_tag_with<F>(&self, nonce: &[u8], in_out: &mut [u8],
+                                 tag_out: &mut [u8], ad: &[&[u8]], seal: F)
+                                 -> Result<usize, error::Unspecified>
+            where F: FnOnce(&[u64; KEY_CTX_BUF_ELEMS], &[u8], &mut [u8],
+                            &mut [u8; TAG_LEN], &[&[u8]])
+                            -> Result<(), error::Unspecified> {
+        try!(check_nonce_len(self.algorithm, nonce));
+        try!(check_per_nonce_max_bytes(in_out.len()));
+        let tag_len = self.algorithm.tag_len;
+        if tag_out.len() < tag_len {
+            return Err(error::Unspecified);
+        }
+        let mut tag = [0u8; TAG_LEN];
+        try!(seal(&self.ctx_buf, nonce, in_out, &mut tag, ad));
+        tag_out[..tag_len].copy_from_slice(&tag[..tag_len]);
+        Ok(tag_len)
+    }
+
+    fn seal_scatter(&self, nonce: &[u8], plaintext: &[u8],
+                    ciphertext_out: &mut [u8], tag_out: &mut [u8],
+                    ad: &[&[u8]]) -> Result<usize, error::Unspecified> {
+        if ciphertext_out.len() != plaintext.len() {
+            return Err(error::Unspecified);
+        }
+        ciphertext_out.copy_from_slice(plaintext);
+        self.seal_separate_tag(nonce, ciphertext_out, tag_out, ad)
+    }
+}
+
+#[cfg(feature = "use_heap")]
+impl Key {
+    fn seal_in_place_append_tag(&self, nonce: &[u8],
+                                in_out: &mut std::vec::Vec<u8>, ad: &[&[u8]])
+                                -> Result<(), error::Unspecified> {
+        let plaintext_len = in_out.len();
+        let max_overhead_len = self.algorithm.max_overhead_len();
+        in_out.resize(plaintext_len + max_overhead_len, 0);
+        match self.seal_in_place(nonce, in_out, max_overhead_len, ad) {
+            Ok(out_len) => {
+                in_out.truncate(out_len);
+                Ok(())
+            },
+            Err(e) => {
+                // Sealing fails before anything is encrypted.
+                in_out.truncate(plaintext_len);
+                Err(e)
+            },
+        }
+    }
+    fn seal_in_place_parallel(&self, nonce: &[u8], in_out: &mut [u8],
+                              out_suffix_capacity: usize, ad: &[&[u8]],
+                              threads: usize)
+                              -> Result<usize, error::Unspecified> {
+        try!(self.check_parallel_algorithm());
+        if out_suffix_capacity < self.algorithm.max_overhead_len() {
+            return Err(error::Unspecified);
+        }
+        let in_out_len =
+            try!(in_out.len().checked_sub(out_suffix_capacity)
+                             .ok_or(error::Unspecified));
+        let (in_out, tag_out) = in_out.split_at_mut(in_out_len);
+        let tag_len = try!(self.seal_separate_tag_with(
+            nonce, in_out, tag_out, ad, |ctx, nonce, in_out, tag_out, ad| {
+                aes_gcm::aes_gcm_seal_parallel(ctx, nonce, in_out, tag_out, ad,
+                                               threads)
+            }));
+        Ok(in_out_len + tag_len)
+    }
+
+    fn open_in_place_parallel(&self, nonce: &[u8], in_out: &mut [u8],
+                              ad: &[&[u8]], threads: usize)
+                              -> Result<usize, error::Unspecified> {
+        try!(self.check_parallel_algorithm());
+        let ciphertext_len =
+            try!(in_out.len().checked_sub(self.algorithm.tag_len)
+                             .ok_or(error::Unspecified));
+        let (in_out, received_tag) = in_out.split_at_mut(ciphertext_len);
+        try!(self.open_separate_tag_with(
+            nonce, 0, in_out, received_tag, ad,
+            |ctx, nonce, in_out, _in_prefix_len, _received_tag, tag_out, ad| {
+                aes_gcm::aes_gcm_open_parallel(ctx, nonce, in_out, tag_out, ad,
+                                               threads)
+            }));
+        Ok(ciphertext_len)
+    }
+
+    /// Only AES-GCM can process the chunks of a message separately.
+    fn check_parallel_algorithm(&self) -> Result<(), error::Unspecified> {
+        if !core::ptr::eq(self.algorithm, &AES_128_GCM) &&
+           !core::ptr::eq(self.algorithm, &AES_192_GCM) &&
+           !core::ptr::eq(self.algorithm, &AES_256_GCM) {
+            return Err(error::Unspecified);
+        }
+        Ok(())
+    }
 }
 
 /// An AEAD Algorithm.
@@ -240,15 +911,25 @@ impl Key {
 pub struct Algorithm {
     init: fn(ctx_buf: &mut [u8], key: &[u8]) -> Result<(), error::Unspecified>,
 
-    seal: fn(ctx: &[u64; KEY_CTX_BUF_ELEMS], nonce: &[u8; NONCE_LEN],
-              in_out: &mut [u8], tag_out: &mut [u8; TAG_LEN], ad: &[u8])
+    // `nonce` is always exactly `nonce_len` bytes long. `open` is given the
+    // received tag for the SIV constructions, which need it to decrypt; it
+    // must not compare it with the calculated tag itself. Algorithms with
+    // tags shorter than `TAG_LEN` use only the first `tag_len` bytes of the
+    // tags, and the received tag is padded with zeros. The additional data is
+    // the concatenation of the parts of `ad`.
+    seal: fn(ctx: &[u64; KEY_CTX_BUF_ELEMS], nonce: &[u8],
+              in_out: &mut [u8], tag_out: &mut [u8; TAG_LEN], ad: &[&[u8]])
               -> Result<(), error::Unspecified>,
-    open: fn(ctx: &[u64; KEY_CTX_BUF_ELEMS], nonce: &[u8; NONCE_LEN],
+    open: fn(ctx: &[u64; KEY_CTX_BUF_ELEMS], nonce: &[u8],
              in_out: &mut [u8], in_prefix_len: usize,
-             tag_out: &mut [u8; TAG_LEN], ad: &[u8])
+             received_tag: &[u8; TAG_LEN], tag_out: &mut [u8; TAG_LEN],
+             ad: &[&[u8]])
              -> Result<(), error::Unspecified>,
 
     key_len: usize,
+    nonce_len: usize,
+    tag_len: usize,
+    confidentiality_limit: u64,
 }
 
 impl Algorithm {
@@ -266,7 +947,7 @@ impl Algorithm {
     /// Go analog:
     ///   [`crypto.cipher.AEAD.Overhead`](https://golang.org/pkg/crypto/cipher/#AEAD)
     #[inline(always)]
-    pub fn max_overhead_len(&self) -> usize { TAG_LEN }
+    pub fn max_overhead_len(&self) -> usize { self.tag_len }
 
     /// The length of the nonces.
     ///
@@ -275,19 +956,97 @@ impl Algorithm {
     /// Go analog:
     ///   [`crypto.cipher.AEAD.NonceSize`](https://golang.org/pkg/crypto/cipher/#AEAD)
     #[inline(always)]
-    pub fn nonce_len(&self) -> usize { NONCE_LEN }
+    pub fn nonce_len(&self) -> usize { self.nonce_len }
+
+    /// The maximum number of messages that a `SealingKey` seals with a single
+    /// key.
+    ///
+    /// This is the confidentiality limit of [RFC 9001 Section 6.6] for the
+    /// algorithms that it covers. The other algorithms have no limit short of
+    /// 2**64 messages.
+    ///
+    /// The analysis in [RFC 9001 Appendix B] bounds the number of blocks that
+    /// are encrypted, and derives the limit on messages by assuming that each
+    /// is at most 2**16 bytes long. A `SealingKey` doesn't assume that; it
+    /// also stops once it has sealed `confidentiality_limit_blocks()` blocks,
+    /// so that the limit holds for longer messages too.
+    ///
+    /// [RFC 9001 Section 6.6]: https://tools.ietf.org/html/rfc9001#section-6.6
+    /// [RFC 9001 Appendix B]: https://tools.ietf.org/html/rfc9001#appendix-B
+    #[inline(always)]
+    pub fn confidentiality_limit(&self) -> u64 { self.confidentiality_limit }
+
+    /// The maximum number of 16-byte blocks of plaintext that a `SealingKey`
+    /// seals with a single key: `confidentiality_limit()` messages of 2**16
+    /// bytes each.
+    #[inline]
+    pub fn confidentiality_limit_blocks(&self) -> u64 {
+        self.confidentiality_limit.saturating_mul(LIMIT_MESSAGE_BLOCKS)
+    }
 }
 
 
 /// The maximum amount of overhead for the algorithms in this module.
 pub const MAX_OVERHEAD_LEN: usize = TAG_LEN;
 
-// All the AEADs we support use 128-bit tags.
+// Most of the AEADs we support use 128-bit tags, and none use longer ones.
 const TAG_LEN: usize = poly1305::TAG_LEN;
 
-// All the AEADs we support use 96-bit nonces.
+// Most of the AEADs we support use 96-bit nonces.
 const NONCE_LEN: usize = 96 / 8;
 
+// The `confidentiality_limit` of algorithms that don't have one.
+const NO_CONFIDENTIALITY_LIMIT: u64 = core::u64::MAX;
+
+// The length of the blocks that `SealingKey` counts.
+const BLOCK_LEN: usize = 16;
+
+// The number of blocks in each message that the confidentiality limits assume.
+const LIMIT_MESSAGE_BLOCKS: u64 = (1 << 16) / 16;
+
+// AEGIS-256 uses the longest nonces.
+const MAX_NONCE_LEN: usize = 256 / 8;
+
+/// The total length of the parts of the additional data.
+fn ad_len(ad: &[&[u8]]) -> u64 {
+    ad.iter().fold(0, |acc, part| acc + polyfill::u64_from_usize(part.len()))
+}
+
+/// Calls `f` with each 16-byte block of the concatenation of `parts`, with
+/// the last partial block, if any, padded with zeros.
+fn for_each_padded_block<'a, I, F>(parts: I, mut f: F)
+        where I: Iterator<Item=&'a [u8]>, F: FnMut(&[u8; 16]) {
+    let mut block = [0u8; 16];
+    let mut block_len = 0;
+    for part in parts {
+        let mut part = part;
+        while !part.is_empty() {
+            let n = core::cmp::min(block.len() - block_len, part.len());
+            block[block_len..(block_len + n)].copy_from_slice(&part[..n]);
+            block_len += n;
+            part = &part[n..];
+            if block_len == block.len() {
+                f(&block);
+                block_len = 0;
+            }
+        }
+    }
+    if block_len > 0 {
+        for b in &mut block[block_len..] {
+            *b = 0;
+        }
+        f(&block);
+    }
+}
+
+fn check_nonce_len(algorithm: &Algorithm, nonce: &[u8])
+                   -> Result<(), error::Unspecified> {
+    if nonce.len() != algorithm.nonce_len() {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
 
 /// |GFp_chacha_20| uses a 32-bit block counter, so we disallow individual
 /// operations that work on more than 256GB at a time, for all AEADs.
@@ -302,12 +1061,15 @@ fn check_per_nonce_max_bytes(in_out_len: usize)
 
 #[cfg(test)]
 mod tests {
-    use super::super::{aead, error, test};
+    use super::super::{aead, digest, error, hkdf, polyfill, test};
+    use core;
     use std::vec::Vec;
 
     pub fn test_aead(aead_alg: &'static aead::Algorithm, file_path: &str) {
         test_aead_key_sizes(aead_alg);
         test_aead_nonce_sizes(aead_alg).unwrap();
+        test_aead_confidentiality_limit(aead_alg);
+        test_aead_unbound_key_from_okm(aead_alg);
 
         test::from_file(file_path, |section, test_case| {
             assert_eq!(section, "");
@@ -324,11 +1086,11 @@ mod tests {
             for _ in 0..max_overhead_len {
                 s_in_out.push(0);
             }
-            let s_key = try!(aead::SealingKey::new(aead_alg, &key_bytes[..]));
+            let s_key = try!(aead::LessSafeKey::new(aead_alg, &key_bytes[..]));
             let s_result = aead::seal_in_place(&s_key, &nonce[..],
                                                &mut s_in_out[..],
                                                max_overhead_len, &ad);
-            let o_key = try!(aead::OpeningKey::new(aead_alg, &key_bytes[..]));
+            let o_key = try!(aead::LessSafeKey::new(aead_alg, &key_bytes[..]));
 
             ct.extend(tag);
 
@@ -421,6 +1183,11 @@ mod tests {
                 let o_result = aead::open_in_place(&o_key, &nonce[..],
                                                    *in_prefix_len,
                                                    &mut o_in_out[..], &ad);
+                let mut w_in_out = vec![123u8; *in_prefix_len];
+                w_in_out.extend_from_slice(&ct[..]);
+                let w_result = aead::open_within(&o_key, &nonce[..],
+                                                 *in_prefix_len,
+                                                 &mut w_in_out[..], &ad);
                 match error {
                     None => {
                         assert_eq!(Ok(ct.len()), s_result);
@@ -428,10 +1195,14 @@ mod tests {
                         assert_eq!(Ok(plaintext.len()), o_result);
                         assert_eq!(&plaintext[..],
                                    &o_in_out[..plaintext.len()]);
+                        let range = w_result.unwrap();
+                        assert_eq!(range.start, *in_prefix_len);
+                        assert_eq!(&plaintext[..], &w_in_out[range]);
                     },
                     Some(ref error) if error == "WRONG_NONCE_LENGTH" => {
                         assert_eq!(Err(error::Unspecified), s_result);
                         assert_eq!(Err(error::Unspecified), o_result);
+                        assert_eq!(Err(error::Unspecified), w_result);
                     },
                     Some(error) => {
                         unreachable!("Unexpected error test case: {}", error);
@@ -439,49 +1210,371 @@ mod tests {
                 };
             }
 
+            // Splitting the additional data into parts doesn't change the
+            // results.
+            let mut ad_splits: Vec<Vec<&[u8]>> = Vec::new();
+            for split in &[0, 1, 13, 15, 16, 17, ad.len() / 2] {
+                if *split <= ad.len() {
+                    let (a, b) = ad.split_at(*split);
+                    ad_splits.push(vec![&[], a, &[], b]);
+                }
+            }
+            ad_splits.push(ad.chunks(1).collect());
+            ad_splits.push(ad.chunks(7).collect());
+            for ad_parts in &ad_splits {
+                let mut s_in_out = plaintext.clone();
+                s_in_out.extend(vec![0u8; max_overhead_len]);
+                let s_result =
+                    aead::seal_in_place_with_ad_parts(&s_key, &nonce[..],
+                                                      &mut s_in_out[..],
+                                                      max_overhead_len,
+                                                      ad_parts);
+                let mut o_in_out = ct.clone();
+                let o_result =
+                    aead::open_in_place_with_ad_parts(&o_key, &nonce[..], 0,
+                                                      &mut o_in_out[..],
+                                                      ad_parts);
+                match error {
+                    None => {
+                        assert_eq!(Ok(ct.len()), s_result);
+                        assert_eq!(&ct[..], &s_in_out[..ct.len()]);
+                        assert_eq!(Ok(plaintext.len()), o_result);
+                        assert_eq!(&plaintext[..],
+                                   &o_in_out[..plaintext.len()]);
+                    },
+                    Some(_) => {
+                        assert_eq!(Err(error::Unspecified), s_result);
+                        assert_eq!(Err(error::Unspecified), o_result);
+                    },
+                };
+            }
+
+            #[cfg(feature = "use_heap")]
+            test_seal_in_place_append_tag(aead_alg, &key_bytes, &nonce,
+                                          &plaintext, &ad, &ct,
+                                          error.is_none());
+
+            // Sealing and opening into separate buffers give the same
+            // results, in two pieces.
+            let (expected_ct, expected_tag) = ct.split_at(plaintext.len());
+            let mut sc_ct = vec![0u8; plaintext.len()];
+            let mut sc_tag = vec![0u8; max_overhead_len];
+            let sc_result = aead::seal_scatter(&s_key, &nonce, &plaintext,
+                                               &mut sc_ct, &mut sc_tag, &ad);
+            let mut og_pt = vec![0u8; plaintext.len()];
+            let og_result = aead::open_gather(&o_key, &nonce, expected_ct,
+                                              expected_tag, &mut og_pt, &ad);
+            match error {
+                None => {
+                    assert_eq!(Ok(expected_tag.len()), sc_result);
+                    assert_eq!(expected_ct, &sc_ct[..]);
+                    assert_eq!(expected_tag, &sc_tag[..expected_tag.len()]);
+                    assert_eq!(Ok(()), og_result);
+                    assert_eq!(&plaintext[..], &og_pt[..]);
+
+                    let mut bad_tag = expected_tag.to_vec();
+                    bad_tag[0] ^= 1;
+                    assert!(aead::open_gather(&o_key, &nonce, expected_ct,
+                                              &bad_tag, &mut og_pt, &ad)
+                                .is_err());
+                    assert!(og_pt.iter().all(|b| *b == 0));
+                    assert!(aead::open_gather(&o_key, &nonce, expected_ct,
+                                              &expected_tag[1..], &mut og_pt,
+                                              &ad).is_err());
+                    assert!(aead::seal_scatter(&s_key, &nonce, &plaintext,
+                                               &mut sc_ct,
+                                               &mut sc_tag[..(expected_tag
+                                                              .len() - 1)],
+                                               &ad).is_err());
+                },
+                Some(_) => {
+                    assert_eq!(Err(error::Unspecified), sc_result);
+                    assert_eq!(Err(error::Unspecified), og_result);
+                },
+            };
+            let mut too_long = vec![0u8; plaintext.len() + 1];
+            assert!(aead::seal_scatter(&s_key, &nonce, &plaintext,
+                                       &mut too_long, &mut sc_tag, &ad)
+                        .is_err());
+            assert!(aead::open_gather(&o_key, &nonce, expected_ct,
+                                      expected_tag, &mut too_long, &ad)
+                        .is_err());
+
+            // The prefix can't be longer than `in_out`.
+            let mut w_in_out = ct.clone();
+            assert!(aead::open_within(&o_key, &nonce[..], ct.len() + 1,
+                                      &mut w_in_out[..], &ad).is_err());
+
+            // Keys with a nonce sequence that returns `nonce` only once give
+            // the same results, and then fail.
+            let mut s_key =
+                try!(aead::SealingKey::new(aead_alg, &key_bytes[..],
+                                           OneNonceSequence::new(&nonce)));
+            let mut s_in_out = plaintext.clone();
+            s_in_out.extend(vec![0u8; max_overhead_len]);
+            let s_result =
+                s_key.seal_in_place(&mut s_in_out, max_overhead_len, &ad);
+            let mut o_key =
+                try!(aead::OpeningKey::new(aead_alg, &key_bytes[..],
+                                           OneNonceSequence::new(&nonce)));
+            let mut o_in_out = ct.clone();
+            let o_result = o_key.open_in_place(0, &mut o_in_out, &ad);
+            match error {
+                None => {
+                    assert_eq!(Ok(ct.len()), s_result);
+                    assert_eq!(&ct[..], &s_in_out[..ct.len()]);
+                    assert_eq!(Ok(plaintext.len()), o_result);
+                    assert_eq!(&plaintext[..], &o_in_out[..plaintext.len()]);
+                },
+                Some(_) => {
+                    assert_eq!(Err(aead::SealError::Unspecified), s_result);
+                    assert_eq!(Err(error::Unspecified), o_result);
+                },
+            };
+            let mut s_in_out = plaintext.clone();
+            s_in_out.extend(vec![0u8; max_overhead_len]);
+            assert!(s_key.seal_in_place(&mut s_in_out, max_overhead_len, &ad)
+                         .is_err());
+            let mut o_in_out = ct.clone();
+            assert!(o_key.open_in_place(0, &mut o_in_out, &ad).is_err());
+
             Ok(())
         });
     }
 
+    /// A nonce sequence that returns a single nonce.
+    pub struct OneNonceSequence(Option<aead::Nonce>);
+
+    impl OneNonceSequence {
+        pub fn new(nonce: &[u8]) -> OneNonceSequence {
+            OneNonceSequence(aead::Nonce::assume_unique_for_key(nonce).ok())
+        }
+    }
+
+    impl aead::NonceSequence for OneNonceSequence {
+        fn advance(&mut self) -> Result<aead::Nonce, error::Unspecified> {
+            self.0.take().ok_or(error::Unspecified)
+        }
+    }
+
+    #[cfg(feature = "use_heap")]
+    fn test_seal_in_place_append_tag(aead_alg: &'static aead::Algorithm,
+                                     key_bytes: &[u8], nonce: &[u8],
+                                     plaintext: &[u8], ad: &[u8],
+                                     ct: &[u8], succeeds: bool) {
+        let key = aead::LessSafeKey::new(aead_alg, key_bytes).unwrap();
+        let mut in_out = plaintext.to_vec();
+        let result = aead::seal_in_place_append_tag(&key, nonce, &mut in_out,
+                                                    ad);
+        check_append_tag_result(result, error::Unspecified, &in_out,
+                                plaintext, ct, succeeds);
+
+        let mut key = aead::SealingKey::new(aead_alg, key_bytes,
+                                            OneNonceSequence::new(nonce))
+            .unwrap();
+        let mut in_out = plaintext.to_vec();
+        let result = key.seal_in_place_append_tag(&mut in_out, ad);
+        check_append_tag_result(result, aead::SealError::Unspecified, &in_out,
+                                plaintext, ct, succeeds);
+
+        // The nonce sequence is used up, so `in_out` is left alone.
+        let mut in_out = plaintext.to_vec();
+        assert!(key.seal_in_place_append_tag(&mut in_out, ad).is_err());
+        assert_eq!(plaintext, &in_out[..]);
+    }
+
+    #[cfg(feature = "use_heap")]
+    fn check_append_tag_result<E>(result: Result<(), E>, expected_error: E,
+                                  in_out: &[u8], plaintext: &[u8], ct: &[u8],
+                                  succeeds: bool)
+            where E: core::fmt::Debug + PartialEq {
+        if succeeds {
+            assert_eq!(Ok(()), result);
+            assert_eq!(ct, in_out);
+        } else {
+            assert_eq!(Err(expected_error), result);
+            assert_eq!(plaintext, in_out);
+        }
+    }
+
+    /// A nonce sequence of `len`-byte nonces that counts up from zero. The
+    /// nonces are only unique if `len` is at least 8.
+    struct CounterNonceSequence {
+        counter: u64,
+        len: usize,
+    }
+
+    impl aead::NonceSequence for CounterNonceSequence {
+        fn advance(&mut self) -> Result<aead::Nonce, error::Unspecified> {
+            let mut nonce = [0u8; aead::MAX_NONCE_LEN];
+            let counter = polyfill::slice::be_u8_from_u64(self.counter);
+            let n = core::cmp::min(self.len, counter.len());
+            nonce[..n].copy_from_slice(&counter[(counter.len() - n)..]);
+            self.counter += 1;
+            aead::Nonce::assume_unique_for_key(&nonce[..self.len])
+        }
+    }
+
+    fn test_aead_confidentiality_limit(aead_alg: &'static aead::Algorithm) {
+        let limit = aead_alg.confidentiality_limit();
+        let limit_blocks = aead_alg.confidentiality_limit_blocks();
+        let overhead = aead_alg.max_overhead_len();
+
+        let mut key = counter_sealing_key(aead_alg);
+        assert_eq!(key.messages_remaining(), limit);
+        assert_eq!(key.blocks_remaining(), limit_blocks);
+        assert!(!key.is_exhausted());
+
+        // Pretend all but two messages have been sealed, without sealing
+        // millions of messages.
+        key.messages_sealed = limit - 2;
+        let mut in_out = vec![0u8; overhead];
+
+        // A failed sealing operation still uses up the nonce.
+        assert_eq!(key.seal_in_place(&mut in_out, 0, b""),
+                   Err(aead::SealError::Unspecified));
+        assert_eq!(key.messages_remaining(), 1);
+
+        assert!(key.seal_in_place(&mut in_out, overhead, b"").is_ok());
+        assert!(key.is_exhausted());
+        assert_eq!(key.messages_remaining(), 0);
+        assert_eq!(key.seal_in_place(&mut in_out, overhead, b""),
+                   Err(aead::SealError::KeyExhausted));
+        assert_eq!(key.nonce_sequence.counter, 2);
+
+        // The messages that the limit assumes are 2**16 bytes long. Longer
+        // messages use up the limit on blocks first. Pretend all but three
+        // blocks have been sealed, in a single message.
+        let mut key = counter_sealing_key(aead_alg);
+        key.messages_sealed = 1;
+        key.blocks_sealed = limit_blocks - 3;
+        assert_eq!(key.blocks_remaining(), 3);
+
+        // A partial block counts as a whole one, and a message that doesn't
+        // fit fails without using a nonce, even though a shorter one fits.
+        let mut in_out = vec![0u8; (3 * 16) + 1 + overhead];
+        assert_eq!(key.seal_in_place(&mut in_out, overhead, b""),
+                   Err(aead::SealError::KeyExhausted));
+        assert_eq!(key.nonce_sequence.counter, 0);
+        assert!(!key.is_exhausted());
+
+        assert!(key.seal_in_place(&mut in_out[16..], overhead, b"").is_ok());
+        assert_eq!(key.blocks_remaining(), 0);
+        assert!(key.is_exhausted());
+        assert_eq!(key.seal_in_place(&mut in_out[..overhead], overhead, b""),
+                   Err(aead::SealError::KeyExhausted));
+        assert_eq!(key.nonce_sequence.counter, 1);
+    }
+
+    fn counter_sealing_key(aead_alg: &'static aead::Algorithm)
+                           -> aead::SealingKey<CounterNonceSequence> {
+        let key_bytes = vec![0u8; aead_alg.key_len()];
+        let nonce_sequence = CounterNonceSequence {
+            counter: 0,
+            len: aead_alg.nonce_len(),
+        };
+        aead::SealingKey::new(aead_alg, &key_bytes, nonce_sequence).unwrap()
+    }
+
+    fn test_aead_unbound_key_from_okm(aead_alg: &'static aead::Algorithm) {
+        let prk = hkdf::Prk::new_less_safe(&digest::SHA256, &[0x0b; 32]);
+        let info: &[&[u8]] = &[b"key"];
+
+        let mut key_bytes = vec![0u8; aead_alg.key_len()];
+        prk.expand(info, aead_alg).unwrap().fill(&mut key_bytes).unwrap();
+        let expected = aead::LessSafeKey::new(aead_alg, &key_bytes).unwrap();
+
+        let unbound: aead::UnboundKey =
+            prk.expand(info, aead_alg).unwrap().into();
+        assert_eq!(unbound.algorithm().key_len(), aead_alg.key_len());
+        let actual = aead::LessSafeKey::from(unbound);
+
+        // The keys are the same if they produce the same ciphertext.
+        let nonce = vec![0u8; aead_alg.nonce_len()];
+        let overhead = aead_alg.max_overhead_len();
+        let mut a = vec![0u8; 16 + overhead];
+        let mut b = vec![0u8; 16 + overhead];
+        let a_len =
+            aead::seal_in_place(&expected, &nonce, &mut a, overhead, b"")
+                .unwrap();
+        let b_len =
+            aead::seal_in_place(&actual, &nonce, &mut b, overhead, b"")
+                .unwrap();
+        assert_eq!(&a[..a_len], &b[..b_len]);
+
+        // A sealing key and an opening key derived the same way match.
+        let mut s_key = aead::SealingKey::with_unbound_key(
+            prk.expand(info, aead_alg).unwrap().into(),
+            OneNonceSequence::new(&nonce));
+        let mut o_key = aead::OpeningKey::with_unbound_key(
+            prk.expand(info, aead_alg).unwrap().into(),
+            OneNonceSequence::new(&nonce));
+        let mut in_out = vec![0u8; 16 + overhead];
+        let len = s_key.seal_in_place(&mut in_out, overhead, b"").unwrap();
+        assert_eq!(o_key.open_in_place(0, &mut in_out[..len], b"").unwrap(),
+                   16);
+    }
+
     fn test_aead_key_sizes(aead_alg: &'static aead::Algorithm) {
         let key_len = aead_alg.key_len();
         let key_data = vec![0u8; key_len * 2];
 
         // Key is the right size.
-        assert!(aead::OpeningKey::new(aead_alg, &key_data[..key_len]).is_ok());
-        assert!(aead::SealingKey::new(aead_alg, &key_data[..key_len]).is_ok());
+        assert!(aead::OpeningKey::new(aead_alg, &key_data[..key_len],
+                                      OneNonceSequence::new(&[])).is_ok());
+        assert!(aead::SealingKey::new(aead_alg, &key_data[..key_len],
+                                      OneNonceSequence::new(&[])).is_ok());
 
         // Key is one byte too small.
-        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len - 1)])
+        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len - 1)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
-        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len - 1)])
+        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len - 1)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
 
         // Key is one byte too large.
-        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len + 1)])
+        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len + 1)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
-        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len + 1)])
+        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len + 1)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
 
         // Key is half the required size.
-        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len / 2)])
+        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len / 2)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
-        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len / 2)])
+        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len / 2)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
 
         // Key is twice the required size.
-        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len * 2)])
+        assert!(aead::OpeningKey::new(aead_alg, &key_data[..(key_len * 2)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
-        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len * 2)])
+        assert!(aead::SealingKey::new(aead_alg, &key_data[..(key_len * 2)],
+                                      OneNonceSequence::new(&[]))
                     .is_err());
 
         // Key is empty.
-        assert!(aead::OpeningKey::new(aead_alg, &[]).is_err());
-        assert!(aead::SealingKey::new(aead_alg, &[]).is_err());
+        assert!(aead::OpeningKey::new(aead_alg, &[],
+                                      OneNonceSequence::new(&[])).is_err());
+        assert!(aead::SealingKey::new(aead_alg, &[],
+                                      OneNonceSequence::new(&[])).is_err());
 
         // Key is one byte.
-        assert!(aead::OpeningKey::new(aead_alg, &[0]).is_err());
-        assert!(aead::SealingKey::new(aead_alg, &[0]).is_err());
+        assert!(aead::OpeningKey::new(aead_alg, &[0],
+                                      OneNonceSequence::new(&[])).is_err());
+        assert!(aead::SealingKey::new(aead_alg, &[0],
+                                      OneNonceSequence::new(&[])).is_err());
+
+        // `LessSafeKey` checks the key size too.
+        assert!(aead::LessSafeKey::new(aead_alg, &key_data[..key_len]).is_ok());
+        assert!(aead::LessSafeKey::new(aead_alg, &key_data[..(key_len - 1)])
+                    .is_err());
+        assert!(aead::LessSafeKey::new(aead_alg, &key_data[..(key_len + 1)])
+                    .is_err());
     }
 
     // Test that we reject non-standard nonce sizes.
@@ -500,12 +1593,15 @@ mod tests {
                              -> Result<(), error::Unspecified> {
         let key_len = aead_alg.key_len;
         let key_data = vec![0u8; key_len];
-        let o_key = try!(aead::OpeningKey::new(aead_alg, &key_data[..key_len]));
-        let s_key = try!(aead::SealingKey::new(aead_alg, &key_data[..key_len]));
+        let o_key =
+            try!(aead::LessSafeKey::new(aead_alg, &key_data[..key_len]));
+        let s_key =
+            try!(aead::LessSafeKey::new(aead_alg, &key_data[..key_len]));
 
         let nonce_len = aead_alg.nonce_len();
 
-        let nonce = vec![0u8; nonce_len * 2];
+        // Long enough for all the nonce lengths tested below.
+        let nonce = vec![0u8; core::cmp::max(nonce_len * 2, 16)];
 
         let prefix_len = 0;
         let suffix_space = aead_alg.max_overhead_len();
@@ -539,12 +1635,12 @@ mod tests {
         }
 
         // Nonce is one byte too small.
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &nonce[..(nonce_len - 1)],
                                         &mut in_out, suffix_space, &ad).is_err());
         }
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &nonce[..(nonce_len - 1)],
                                         prefix_len, &mut in_out, &ad).is_err());
@@ -563,60 +1659,60 @@ mod tests {
         }
 
         // Nonce is half the required size.
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &nonce[..(nonce_len / 2)],
                                         &mut in_out, suffix_space, &ad).is_err());
         }
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &nonce[..(nonce_len / 2)],
                                         prefix_len, &mut in_out, &ad).is_err());
         }
 
         // Nonce is twice the required size.
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &nonce[..(nonce_len * 2)],
                                         &mut in_out, suffix_space, &ad).is_err());
         }
-        {
+        if nonce_len > 0 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &nonce[..(nonce_len * 2)],
                                         prefix_len, &mut in_out, &ad).is_err());
         }
 
         // Nonce is empty.
-        {
+        if nonce_len != 0 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &[], &mut in_out, suffix_space,
                                         &ad).is_err());
         }
-        {
+        if nonce_len != 0 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &[], prefix_len, &mut in_out,
                                         &ad).is_err());
         }
 
         // Nonce is one byte.
-        {
+        if nonce_len != 1 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &nonce[..1], &mut in_out,
                                         suffix_space, &ad).is_err());
         }
-        {
+        if nonce_len != 1 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &nonce[..1], prefix_len,
                                         &mut in_out, &ad).is_err());
         }
 
         // Nonce is 128 bits (16 bytes).
-        {
+        if nonce_len != 16 {
             let mut in_out = Vec::from(to_seal);
             assert!(aead::seal_in_place(&s_key, &nonce[..16], &mut in_out,
                                         suffix_space, &ad).is_err());
         }
-        {
+        if nonce_len != 16 {
             let mut in_out = Vec::from(to_open);
             assert!(aead::open_in_place(&o_key, &nonce[..16], prefix_len,
                                         &mut in_out, &ad).is_err());
@@ -0,0 +1,380 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! AEGIS-128L and AEGIS-256, as described in [draft-irtf-cfrg-aegis-aead],
+//! with 128-bit tags.
+//!
+//! AEGIS is built from the AES round function, so with AES instructions it
+//! is much faster than AES-GCM. This implementation uses the portable,
+//! constant-time `aes::round`, which is much slower than any of the
+//! hardware-accelerated AEADs.
+//!
+//! [draft-irtf-cfrg-aegis-aead]:
+//!     https://datatracker.ietf.org/doc/draft-irtf-cfrg-aegis-aead/
+
+use {aead, aes, core, error, polyfill};
+
+/// AEGIS-128L as described in [draft-irtf-cfrg-aegis-aead].
+///
+/// The keys are 128 bits and the nonces are 128 bits. Unlike AES-GCM, random
+/// nonces are safe for many more messages, but a nonce must still never be
+/// used twice with the same key.
+///
+/// [draft-irtf-cfrg-aegis-aead]:
+///     https://datatracker.ietf.org/doc/draft-irtf-cfrg-aegis-aead/
+pub static AEGIS_128L: aead::Algorithm = aead::Algorithm {
+    key_len: 128 / 8,
+    init: aegis_init,
+    seal: aegis_128l_seal,
+    open: aegis_128l_open,
+    nonce_len: 128 / 8,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// AEGIS-256 as described in [draft-irtf-cfrg-aegis-aead].
+///
+/// The keys are 256 bits and the nonces are 256 bits, so random nonces can be
+/// used for practically any number of messages.
+///
+/// [draft-irtf-cfrg-aegis-aead]:
+///     https://datatracker.ietf.org/doc/draft-irtf-cfrg-aegis-aead/
+pub static AEGIS_256: aead::Algorithm = aead::Algorithm {
+    key_len: 256 / 8,
+    init: aegis_init,
+    seal: aegis_256_seal,
+    open: aegis_256_open,
+    nonce_len: 256 / 8,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// Copies |key| into |ctx_buf|.
+fn aegis_init(ctx_buf: &mut [u8], key: &[u8])
+              -> Result<(), error::Unspecified> {
+    ctx_buf[..key.len()].copy_from_slice(key);
+    Ok(())
+}
+
+fn aegis_128l_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                   ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let mut state = State128L::new(ctx_as_key(ctx), nonce);
+    seal(&mut state, in_out, tag_out, ad)
+}
+
+fn aegis_128l_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], in_prefix_len: usize,
+                   _received_tag: &[u8; aead::TAG_LEN],
+                   tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                   -> Result<(), error::Unspecified> {
+    let mut state = State128L::new(ctx_as_key(ctx), nonce);
+    open(&mut state, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn aegis_256_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                  ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let mut state = State256::new(ctx_as_key(ctx), nonce);
+    seal(&mut state, in_out, tag_out, ad)
+}
+
+fn aegis_256_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], in_prefix_len: usize,
+                  _received_tag: &[u8; aead::TAG_LEN],
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    let mut state = State256::new(ctx_as_key(ctx), nonce);
+    open(&mut state, in_out, in_prefix_len, tag_out, ad)
+}
+
+/// The parts of AEGIS-128L and AEGIS-256 that differ. Everything else is
+/// done in terms of blocks of `rate()` bytes.
+trait State {
+    /// The number of bytes absorbed by each `update`.
+    fn rate(&self) -> usize;
+
+    /// Writes the key stream for the next `rate()` bytes of the message to
+    /// `z`.
+    fn key_stream(&self, z: &mut [u8]);
+
+    /// The `Update` function, with `rate()` bytes of input.
+    fn update(&mut self, m: &[u8]);
+
+    /// The `Finalize` function.
+    fn finalize(&mut self, ad_len: u64, msg_len: u64) -> Block;
+}
+
+fn seal<S: State>(state: &mut S, in_out: &mut [u8],
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    absorb_ad(state, ad);
+
+    let rate = state.rate();
+    let mut z = [0u8; MAX_RATE];
+    for chunk in in_out.chunks_mut(rate) {
+        // The last block of plaintext is padded with zeros.
+        let mut block = [0u8; MAX_RATE];
+        block[..chunk.len()].copy_from_slice(chunk);
+        state.key_stream(&mut z[..rate]);
+        state.update(&block[..rate]);
+        for (b, z) in chunk.iter_mut().zip(z.iter()) {
+            *b ^= *z;
+        }
+    }
+
+    *tag_out = state.finalize(aead::ad_len(ad),
+                              polyfill::u64_from_usize(in_out.len()));
+    Ok(())
+}
+
+fn open<S: State>(state: &mut S, in_out: &mut [u8], in_prefix_len: usize,
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    absorb_ad(state, ad);
+
+    // The output is written `in_prefix_len` bytes before the input, so each
+    // block of the input is read before the output overwrites it.
+    let rate = state.rate();
+    let ciphertext_len = in_out.len() - in_prefix_len;
+    let mut z = [0u8; MAX_RATE];
+    let mut offset = 0;
+    while offset < ciphertext_len {
+        let len = core::cmp::min(rate, ciphertext_len - offset);
+        let input = in_prefix_len + offset;
+        let mut block = [0u8; MAX_RATE];
+        block[..len].copy_from_slice(&in_out[input..(input + len)]);
+        state.key_stream(&mut z[..rate]);
+        // The padding of the last block is left as zeros, so the plaintext
+        // is absorbed the same way `seal` absorbs it.
+        for (b, z) in block[..len].iter_mut().zip(z.iter()) {
+            *b ^= *z;
+        }
+        state.update(&block[..rate]);
+        in_out[offset..(offset + len)].copy_from_slice(&block[..len]);
+        offset += len;
+    }
+
+    *tag_out = state.finalize(aead::ad_len(ad),
+                              polyfill::u64_from_usize(ciphertext_len));
+    Ok(())
+}
+
+/// Absorbs the concatenation of the parts of `ad`, padded with zeros to a
+/// multiple of `state.rate()` bytes.
+fn absorb_ad<S: State>(state: &mut S, ad: &[&[u8]]) {
+    let rate = state.rate();
+    let mut block = [0u8; MAX_RATE];
+    let mut block_len = 0;
+    aead::for_each_padded_block(ad.iter().cloned(), |b| {
+        block[block_len..(block_len + BLOCK_LEN)].copy_from_slice(b);
+        block_len += BLOCK_LEN;
+        if block_len == rate {
+            state.update(&block[..rate]);
+            block_len = 0;
+        }
+    });
+    if block_len > 0 {
+        for b in &mut block[block_len..rate] {
+            *b = 0;
+        }
+        state.update(&block[..rate]);
+    }
+}
+
+/// The AEGIS-128L state, S0 through S7.
+struct State128L {
+    s: [Block; 8],
+}
+
+impl State128L {
+    /// The `Init` function.
+    fn new(key: &[u8], nonce: &[u8]) -> State128L {
+        let key = as_block(key);
+        let nonce = as_block(nonce);
+        let key_xor_nonce = xor(key, nonce);
+        let mut state = State128L {
+            s: [key_xor_nonce, C1, C0, C1, key_xor_nonce, xor(key, &C0),
+                xor(key, &C1), xor(key, &C0)],
+        };
+        for _ in 0..10 {
+            state.update_blocks(nonce, key);
+        }
+        state
+    }
+
+    fn update_blocks(&mut self, m0: &Block, m1: &Block) {
+        let s = self.s;
+        self.s = [aes_round(&s[7], &xor(&s[0], m0)),
+                  aes_round(&s[0], &s[1]),
+                  aes_round(&s[1], &s[2]),
+                  aes_round(&s[2], &s[3]),
+                  aes_round(&s[3], &xor(&s[4], m1)),
+                  aes_round(&s[4], &s[5]),
+                  aes_round(&s[5], &s[6]),
+                  aes_round(&s[6], &s[7])];
+    }
+}
+
+impl State for State128L {
+    fn rate(&self) -> usize { 2 * BLOCK_LEN }
+
+    fn key_stream(&self, z: &mut [u8]) {
+        let s = &self.s;
+        let z0 = xor(&xor(&s[6], &s[1]), &and(&s[2], &s[3]));
+        let z1 = xor(&xor(&s[2], &s[5]), &and(&s[6], &s[7]));
+        z[..BLOCK_LEN].copy_from_slice(&z0);
+        z[BLOCK_LEN..].copy_from_slice(&z1);
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        self.update_blocks(as_block(&m[..BLOCK_LEN]),
+                           as_block(&m[BLOCK_LEN..]));
+    }
+
+    fn finalize(&mut self, ad_len: u64, msg_len: u64) -> Block {
+        let t = xor(&self.s[2], &lengths_block(ad_len, msg_len));
+        for _ in 0..7 {
+            self.update_blocks(&t, &t);
+        }
+        self.s[..7].iter().fold([0u8; BLOCK_LEN], |acc, s| xor(&acc, s))
+    }
+}
+
+/// The AEGIS-256 state, S0 through S5.
+struct State256 {
+    s: [Block; 6],
+}
+
+impl State256 {
+    /// The `Init` function.
+    fn new(key: &[u8], nonce: &[u8]) -> State256 {
+        let k0 = as_block(&key[..BLOCK_LEN]);
+        let k1 = as_block(&key[BLOCK_LEN..]);
+        let k0_xor_n0 = xor(k0, as_block(&nonce[..BLOCK_LEN]));
+        let k1_xor_n1 = xor(k1, as_block(&nonce[BLOCK_LEN..]));
+        let mut state = State256 {
+            s: [k0_xor_n0, k1_xor_n1, C1, C0, xor(k0, &C0), xor(k1, &C1)],
+        };
+        for _ in 0..4 {
+            state.update_block(k0);
+            state.update_block(k1);
+            state.update_block(&k0_xor_n0);
+            state.update_block(&k1_xor_n1);
+        }
+        state
+    }
+
+    fn update_block(&mut self, m: &Block) {
+        let s = self.s;
+        self.s = [aes_round(&s[5], &xor(&s[0], m)),
+                  aes_round(&s[0], &s[1]),
+                  aes_round(&s[1], &s[2]),
+                  aes_round(&s[2], &s[3]),
+                  aes_round(&s[3], &s[4]),
+                  aes_round(&s[4], &s[5])];
+    }
+}
+
+impl State for State256 {
+    fn rate(&self) -> usize { BLOCK_LEN }
+
+    fn key_stream(&self, z: &mut [u8]) {
+        let s = &self.s;
+        let z_block = xor(&xor(&xor(&s[1], &s[4]), &s[5]), &and(&s[2], &s[3]));
+        z.copy_from_slice(&z_block);
+    }
+
+    fn update(&mut self, m: &[u8]) { self.update_block(as_block(m)); }
+
+    fn finalize(&mut self, ad_len: u64, msg_len: u64) -> Block {
+        let t = xor(&self.s[3], &lengths_block(ad_len, msg_len));
+        for _ in 0..7 {
+            self.update_block(&t);
+        }
+        self.s.iter().fold([0u8; BLOCK_LEN], |acc, s| xor(&acc, s))
+    }
+}
+
+/// `LE64(ad_len_bits) || LE64(msg_len_bits)`.
+fn lengths_block(ad_len: u64, msg_len: u64) -> Block {
+    let lengths = [(ad_len * 8).to_le(), (msg_len * 8).to_le()];
+    *as_block(polyfill::slice::u64_as_u8(&lengths))
+}
+
+fn ctx_as_key(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS]) -> &[u8] {
+    &polyfill::slice::u64_as_u8(ctx)[..MAX_KEY_LEN]
+}
+
+/// `AESRound(input, round_key)`.
+#[inline]
+fn aes_round(input: &Block, round_key: &Block) -> Block {
+    let mut state = *input;
+    aes::round(&mut state, round_key);
+    state
+}
+
+#[inline]
+fn xor(a: &Block, b: &Block) -> Block {
+    let mut r = *a;
+    for (r, b) in r.iter_mut().zip(b.iter()) {
+        *r ^= *b;
+    }
+    r
+}
+
+#[inline]
+fn and(a: &Block, b: &Block) -> Block {
+    let mut r = *a;
+    for (r, b) in r.iter_mut().zip(b.iter()) {
+        *r &= *b;
+    }
+    r
+}
+
+#[inline]
+fn as_block(bytes: &[u8]) -> &Block {
+    slice_as_array_ref!(&bytes[..BLOCK_LEN], BLOCK_LEN).unwrap()
+}
+
+type Block = aes::Block;
+
+const BLOCK_LEN: usize = aes::BLOCK_LEN;
+
+const MAX_RATE: usize = 2 * BLOCK_LEN;
+
+const MAX_KEY_LEN: usize = 256 / 8;
+
+// The Fibonacci sequence modulo 256.
+const C0: Block = [0x00, 0x01, 0x01, 0x02, 0x03, 0x05, 0x08, 0x0d, 0x15, 0x22,
+                   0x37, 0x59, 0x90, 0xe9, 0x79, 0x62];
+const C1: Block = [0xdb, 0x3d, 0x18, 0x55, 0x6d, 0xc2, 0x2f, 0xf1, 0x20, 0x11,
+                   0x31, 0x42, 0x73, 0xb5, 0x28, 0xdd];
+
+
+#[cfg(test)]
+mod tests {
+    use aead;
+    use super::super::tests::test_aead;
+
+    #[test]
+    pub fn test_aegis_128l() {
+        test_aead(&aead::AEGIS_128L, "src/aead/aegis_128l_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aegis_256() {
+        test_aead(&aead::AEGIS_256, "src/aead/aegis_256_tests.txt");
+    }
+}
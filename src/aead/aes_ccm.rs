@@ -0,0 +1,338 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! AES-CCM, as described in [RFC 3610] and [NIST SP 800-38C].
+//!
+//! [RFC 3610]: https://tools.ietf.org/html/rfc3610
+//! [NIST SP 800-38C]:
+//!     http://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38c.pdf
+
+use {aead, aes, core, error, polyfill};
+
+/// AES-128 in CCM mode with 128-bit tags and 96 bit nonces.
+///
+/// This is `AEAD_AES_128_CCM` of [RFC 5116], as used in TLS.
+///
+/// [RFC 5116]: https://tools.ietf.org/html/rfc5116
+pub static AES_128_CCM: aead::Algorithm = aead::Algorithm {
+    key_len: AES_128_KEY_LEN,
+    init: aes_ccm_init,
+    seal: aes_ccm_16_seal,
+    open: aes_ccm_16_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: 16,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-256 in CCM mode with 128-bit tags and 96 bit nonces.
+///
+/// This is `AEAD_AES_256_CCM` of [RFC 5116], as used in TLS.
+///
+/// [RFC 5116]: https://tools.ietf.org/html/rfc5116
+pub static AES_256_CCM: aead::Algorithm = aead::Algorithm {
+    key_len: AES_256_KEY_LEN,
+    init: aes_ccm_init,
+    seal: aes_ccm_16_seal,
+    open: aes_ccm_16_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: 16,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-128 in CCM mode with 64-bit tags and 96 bit nonces.
+///
+/// This is `AEAD_AES_128_CCM_8` of [RFC 6655], as used in TLS for constrained
+/// devices.
+///
+/// [RFC 6655]: https://tools.ietf.org/html/rfc6655
+pub static AES_128_CCM_8: aead::Algorithm = aead::Algorithm {
+    key_len: AES_128_KEY_LEN,
+    init: aes_ccm_init,
+    seal: aes_ccm_8_seal,
+    open: aes_ccm_8_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: 8,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-256 in CCM mode with 64-bit tags and 96 bit nonces.
+///
+/// This is `AEAD_AES_256_CCM_8` of [RFC 6655].
+///
+/// [RFC 6655]: https://tools.ietf.org/html/rfc6655
+pub static AES_256_CCM_8: aead::Algorithm = aead::Algorithm {
+    key_len: AES_256_KEY_LEN,
+    init: aes_ccm_init,
+    seal: aes_ccm_8_seal,
+    open: aes_ccm_8_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: 8,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-128 in CCM mode with 32-bit tags and 104 bit nonces, as used by the
+/// Bluetooth LE link layer and by IEEE 802.15.4 (e.g. Zigbee) at its
+/// `ENC-MIC-32` security level.
+///
+/// The short tags give only limited protection against forgery, and the
+/// plaintext of each message is limited to 65535 bytes.
+pub static AES_128_CCM_BLE: aead::Algorithm = aead::Algorithm {
+    key_len: AES_128_KEY_LEN,
+    init: aes_ccm_init,
+    seal: aes_ccm_4_seal,
+    open: aes_ccm_4_open,
+    nonce_len: 13,
+    tag_len: 4,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+fn aes_ccm_init(ctx_buf: &mut [u8], key: &[u8])
+                -> Result<(), error::Unspecified> {
+    aes::init(ctx_buf, key)
+}
+
+fn aes_ccm_16_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                   ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(ctx, 16, nonce, in_out, tag_out, ad)
+}
+
+fn aes_ccm_16_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], in_prefix_len: usize,
+                   _received_tag: &[u8; aead::TAG_LEN],
+                   tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                   -> Result<(), error::Unspecified> {
+    open(ctx, 16, nonce, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn aes_ccm_8_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                  ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(ctx, 8, nonce, in_out, tag_out, ad)
+}
+
+fn aes_ccm_8_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], in_prefix_len: usize,
+                  _received_tag: &[u8; aead::TAG_LEN],
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    open(ctx, 8, nonce, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn aes_ccm_4_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                  ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(ctx, 4, nonce, in_out, tag_out, ad)
+}
+
+fn aes_ccm_4_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], in_prefix_len: usize,
+                  _received_tag: &[u8; aead::TAG_LEN],
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    open(ctx, 4, nonce, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], tag_len: usize, nonce: &[u8],
+        in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+        -> Result<(), error::Unspecified> {
+    let ctx = polyfill::slice::u64_as_u8(ctx);
+    try!(check_plaintext_len(nonce, in_out.len()));
+    *tag_out = cbc_mac(ctx, tag_len, nonce, ad, in_out);
+    xor_key_stream(ctx, nonce, tag_out, in_out, 0);
+    Ok(())
+}
+
+fn open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], tag_len: usize, nonce: &[u8],
+        in_out: &mut [u8], in_prefix_len: usize,
+        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+        -> Result<(), error::Unspecified> {
+    let ctx = polyfill::slice::u64_as_u8(ctx);
+    let plaintext_len = in_out.len() - in_prefix_len;
+    try!(check_plaintext_len(nonce, plaintext_len));
+    let mut s0 = [0u8; aead::TAG_LEN];
+    xor_key_stream(ctx, nonce, &mut s0, in_out, in_prefix_len);
+    *tag_out = cbc_mac(ctx, tag_len, nonce, ad, &in_out[..plaintext_len]);
+    for (t, s) in tag_out.iter_mut().zip(s0.iter()) {
+        *t ^= *s;
+    }
+    Ok(())
+}
+
+// The length of the message length field, `L`.
+#[inline]
+fn length_field_len(nonce: &[u8]) -> usize { 15 - nonce.len() }
+
+// The plaintext length must fit in the `L`-byte length field.
+fn check_plaintext_len(nonce: &[u8], plaintext_len: usize)
+                       -> Result<(), error::Unspecified> {
+    let l = length_field_len(nonce);
+    if l < 8 && (polyfill::u64_from_usize(plaintext_len) >> (8 * l)) != 0 {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+/// The CBC-MAC `T` of `ad` and `plaintext` (RFC 3610 Section 2.2). Only the
+/// first `tag_len` bytes are used.
+fn cbc_mac(ctx: &[u8], tag_len: usize, nonce: &[u8], ad: &[&[u8]],
+           plaintext: &[u8]) -> aes::Block {
+    let l = length_field_len(nonce);
+
+    // B_0.
+    let mut b0 = [0u8; aes::BLOCK_LEN];
+    let ad_len = aead::ad_len(ad);
+    let ad_flag = if ad_len == 0 { 0 } else { 0x40 };
+    b0[0] = ad_flag | ((((tag_len - 2) / 2) as u8) << 3) | ((l - 1) as u8);
+    b0[1..(1 + nonce.len())].copy_from_slice(nonce);
+    write_be(&mut b0[(1 + nonce.len())..],
+             polyfill::u64_from_usize(plaintext.len()));
+
+    let mut mac = CbcMac::new(ctx, &b0);
+
+    if ad_len > 0 {
+        // The encoded length of `ad` is followed by `ad`, padded together to a
+        // block boundary.
+        let mut encoded_len = [0u8; 10];
+        let encoded_len = if ad_len < 0xff00 {
+            write_be(&mut encoded_len[..2], ad_len);
+            &encoded_len[..2]
+        } else if ad_len <= 0xffffffff {
+            encoded_len[0] = 0xff;
+            encoded_len[1] = 0xfe;
+            write_be(&mut encoded_len[2..6], ad_len);
+            &encoded_len[..6]
+        } else {
+            encoded_len[0] = 0xff;
+            encoded_len[1] = 0xff;
+            write_be(&mut encoded_len[2..10], ad_len);
+            &encoded_len[..10]
+        };
+        aead::for_each_padded_block(
+            core::iter::once(encoded_len).chain(ad.iter().cloned()),
+            |block| mac.update_block(block));
+    }
+
+    mac.update_padded(plaintext);
+    mac.state
+}
+
+/// CBC-MAC with zero padding.
+struct CbcMac<'a> {
+    ctx: &'a [u8],
+    state: aes::Block,
+}
+
+impl<'a> CbcMac<'a> {
+    fn new(ctx: &'a [u8], b0: &aes::Block) -> CbcMac<'a> {
+        let mut state = *b0;
+        aes::encrypt_blocks(ctx, &mut state);
+        CbcMac { ctx: ctx, state: state }
+    }
+
+    fn update_block(&mut self, block: &aes::Block) {
+        for (s, b) in self.state.iter_mut().zip(block.iter()) {
+            *s ^= *b;
+        }
+        aes::encrypt_blocks(self.ctx, &mut self.state);
+    }
+
+    fn update_padded(&mut self, input: &[u8]) {
+        for chunk in input.chunks(aes::BLOCK_LEN) {
+            let mut block = [0u8; aes::BLOCK_LEN];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+}
+
+/// XORs the key stream block `S_0` into `tag`, and XORs the rest of the key
+/// stream into the input `in_out[in_prefix_len..]`, writing the output to
+/// `in_out[..(in_out.len() - in_prefix_len)]`.
+fn xor_key_stream(ctx: &[u8], nonce: &[u8], tag: &mut aes::Block,
+                  in_out: &mut [u8], in_prefix_len: usize) {
+    let l = length_field_len(nonce);
+
+    // A_0.
+    let mut counter = [0u8; aes::BLOCK_LEN];
+    counter[0] = (l - 1) as u8;
+    counter[1..(1 + nonce.len())].copy_from_slice(nonce);
+
+    let mut s0 = counter;
+    aes::encrypt_blocks(ctx, &mut s0);
+    for (t, s) in tag.iter_mut().zip(s0.iter()) {
+        *t ^= *s;
+    }
+
+    // The counter is public and can't overflow because of
+    // `check_plaintext_len`, so this doesn't need to be constant-time.
+    let mut i = 1u64;
+    aes::xor_key_stream(ctx, in_out, in_prefix_len, |block| {
+        write_be(&mut counter[(1 + nonce.len())..], i);
+        *block = counter;
+        i += 1;
+    });
+}
+
+/// Writes the `out.len()` least significant bytes of `value` to `out` in
+/// big-endian order.
+fn write_be(out: &mut [u8], value: u64) {
+    let mut value = value;
+    for b in out.iter_mut().rev() {
+        *b = value as u8;
+        value >>= 8;
+    }
+}
+
+const AES_128_KEY_LEN: usize = 128 / 8;
+const AES_256_KEY_LEN: usize = 256 / 8;
+
+// The confidentiality limit of [RFC 9001 Section 6.6] for `AEAD_AES_128_CCM`,
+// 2**21.5 messages rounded down. The same limit is used for the other key and
+// tag lengths.
+//
+// [RFC 9001 Section 6.6]: https://tools.ietf.org/html/rfc9001#section-6.6
+const CONFIDENTIALITY_LIMIT: u64 = 2965820;
+
+
+#[cfg(test)]
+mod tests {
+    use aead;
+    use super::super::tests::test_aead;
+
+    #[test]
+    pub fn test_aes_128_ccm() {
+        test_aead(&aead::AES_128_CCM, "src/aead/aes_128_ccm_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_256_ccm() {
+        test_aead(&aead::AES_256_CCM, "src/aead/aes_256_ccm_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_128_ccm_8() {
+        test_aead(&aead::AES_128_CCM_8, "src/aead/aes_128_ccm_8_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_256_ccm_8() {
+        test_aead(&aead::AES_256_CCM_8, "src/aead/aes_256_ccm_8_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_128_ccm_ble() {
+        test_aead(&aead::AES_128_CCM_BLE, "src/aead/aes_128_ccm_ble_tests.txt");
+    }
+}
@@ -12,7 +12,10 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-use {aead, bssl, c, error, polyfill};
+use {aead, aes, bssl, c, error, polyfill};
+
+#[cfg(feature = "use_heap")]
+use {core, std};
 
 /// AES-128 in GCM mode with 128-bit tags and 96 bit nonces.
 ///
@@ -24,6 +27,24 @@ pub static AES_128_GCM: aead::Algorithm = aead::Algorithm {
     init: aes_gcm_init,
     seal: aes_gcm_seal,
     open: aes_gcm_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-192 in GCM mode with 128-bit tags and 96 bit nonces.
+///
+/// C analog: `EVP_aead_aes_192_gcm`
+///
+/// Go analog: [`crypto.aes`](https://golang.org/pkg/crypto/aes/)
+pub static AES_192_GCM: aead::Algorithm = aead::Algorithm {
+    key_len: AES_192_KEY_LEN,
+    init: aes_gcm_init,
+    seal: aes_gcm_seal,
+    open: aes_gcm_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
 };
 
 /// AES-256 in GCM mode with 128-bit tags and 96 bit nonces.
@@ -36,51 +57,187 @@ pub static AES_256_GCM: aead::Algorithm = aead::Algorithm {
     init: aes_gcm_init,
     seal: aes_gcm_seal,
     open: aes_gcm_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: CONFIDENTIALITY_LIMIT,
 };
 
-fn aes_gcm_init(ctx_buf: &mut [u8], key: &[u8])
-                -> Result<(), error::Unspecified> {
+pub fn aes_gcm_init(ctx_buf: &mut [u8], key: &[u8])
+                    -> Result<(), error::Unspecified> {
     bssl::map_result(unsafe {
         GFp_aes_gcm_init(ctx_buf.as_mut_ptr(), ctx_buf.len(), key.as_ptr(),
                          key.len())
     })
 }
 
-fn aes_gcm_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
-                nonce: &[u8; aead::NONCE_LEN], in_out: &mut [u8],
-                tag: &mut [u8; aead::TAG_LEN], ad: &[u8])
-                -> Result<(), error::Unspecified> {
+pub fn aes_gcm_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                    in_out: &mut [u8], tag: &mut [u8; aead::TAG_LEN],
+                    ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let nonce = try!(slice_as_array_ref!(nonce, aead::NONCE_LEN));
     let ctx = polyfill::slice::u64_as_u8(ctx);
     bssl::map_result(unsafe {
         GFp_aes_gcm_seal(ctx.as_ptr(), in_out.as_mut_ptr(), in_out.len(), tag,
-                         nonce, ad.as_ptr(), ad.len())
+                         nonce, ad.as_ptr() as *const u8, ad.len(), ad_part)
     })
 }
 
-fn aes_gcm_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
-                nonce: &[u8; aead::NONCE_LEN], in_out: &mut [u8],
-                in_prefix_len: usize, tag_out: &mut [u8; aead::TAG_LEN],
-                ad: &[u8]) -> Result<(), error::Unspecified> {
+pub fn aes_gcm_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                    in_out: &mut [u8], in_prefix_len: usize,
+                    _received_tag: &[u8; aead::TAG_LEN],
+                    tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                    -> Result<(), error::Unspecified> {
+    let nonce = try!(slice_as_array_ref!(nonce, aead::NONCE_LEN));
     let ctx = polyfill::slice::u64_as_u8(ctx);
     bssl::map_result(unsafe {
         GFp_aes_gcm_open(ctx.as_ptr(), in_out.as_mut_ptr(),
                          in_out.len() - in_prefix_len, tag_out, nonce,
-                         in_out[in_prefix_len..].as_ptr(), ad.as_ptr(),
-                         ad.len())
+                         in_out[in_prefix_len..].as_ptr(),
+                         ad.as_ptr() as *const u8, ad.len(), ad_part)
+    })
+}
+
+/// Seals `in_out` like `aes_gcm_seal`, except that it is split into up to
+/// `threads` chunks that are encrypted and hashed on separate threads.
+#[cfg(feature = "use_heap")]
+pub fn aes_gcm_seal_parallel(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                             nonce: &[u8], in_out: &mut [u8],
+                             tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]],
+                             threads: usize)
+                             -> Result<(), error::Unspecified> {
+    match parallel_chunk_len(in_out.len(), threads) {
+        Some(chunk_len) =>
+            process_parallel(ctx, nonce, in_out, tag_out, ad, chunk_len,
+                             GFp_aes_gcm_seal_chunk),
+        None => aes_gcm_seal(ctx, nonce, in_out, tag_out, ad),
+    }
+}
+
+/// Opens `in_out` like `aes_gcm_open` with an `in_prefix_len` of zero, except
+/// that it is split into up to `threads` chunks that are hashed and decrypted
+/// on separate threads.
+#[cfg(feature = "use_heap")]
+pub fn aes_gcm_open_parallel(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                             nonce: &[u8], in_out: &mut [u8],
+                             tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]],
+                             threads: usize)
+                             -> Result<(), error::Unspecified> {
+    match parallel_chunk_len(in_out.len(), threads) {
+        Some(chunk_len) =>
+            process_parallel(ctx, nonce, in_out, tag_out, ad, chunk_len,
+                             GFp_aes_gcm_open_chunk),
+        None => aes_gcm_open(ctx, nonce, in_out, 0, &[0; aead::TAG_LEN],
+                             tag_out, ad),
+    }
+}
+
+/// The length of the chunks to split `len` bytes into for `threads` threads,
+/// or `None` if it isn't worth splitting them at all.
+#[cfg(feature = "use_heap")]
+fn parallel_chunk_len(len: usize, threads: usize) -> Option<usize> {
+    if threads < 2 {
+        return None;
+    }
+    let blocks_per_thread =
+        ((len + (BLOCK_LEN - 1)) / BLOCK_LEN + (threads - 1)) / threads;
+    let chunk_len =
+        core::cmp::max(blocks_per_thread * BLOCK_LEN, MIN_PARALLEL_CHUNK_LEN);
+    if len <= chunk_len {
+        return None;
+    }
+    Some(chunk_len)
+}
+
+#[cfg(feature = "use_heap")]
+fn process_parallel(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                    in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                    ad: &[&[u8]], chunk_len: usize, process_chunk: ChunkFn)
+                    -> Result<(), error::Unspecified> {
+    let nonce = *try!(slice_as_array_ref!(nonce, aead::NONCE_LEN));
+    let in_out_len = in_out.len();
+
+    // Each thread gets its own copy of the key, its chunk, and, for the first
+    // chunk, the additional data, which is joined into a single part.
+    let threads: std::vec::Vec<_> =
+        in_out.chunks(chunk_len).enumerate().map(|(i, chunk)| {
+            let ctx = *ctx;
+            let mut chunk = chunk.to_vec();
+            let ad: std::vec::Vec<u8> = if i == 0 {
+                ad.iter().flat_map(|part| part.iter().cloned()).collect()
+            } else {
+                std::vec::Vec::new()
+            };
+            std::thread::spawn(move || {
+                let ctx = polyfill::slice::u64_as_u8(&ctx);
+                let ad: &[&[u8]] = if ad.is_empty() { &[] } else { &[&ad] };
+                let first_block = (i * chunk_len) / BLOCK_LEN;
+                let mut chunk_xi = [0u8; BLOCK_LEN];
+                try!(bssl::map_result(unsafe {
+                    process_chunk(ctx.as_ptr(), chunk.as_mut_ptr(),
+                                  chunk.len(), &mut chunk_xi, &nonce,
+                                  polyfill::u64_from_usize(first_block),
+                                  ad.as_ptr() as *const u8, ad.len(), ad_part)
+                }));
+                Ok((chunk_xi, chunk))
+            })
+        }).collect();
+    let results: std::vec::Vec<Result<([u8; BLOCK_LEN],
+                                       std::vec::Vec<u8>),
+                                      error::Unspecified>> =
+        threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+    let ctx = polyfill::slice::u64_as_u8(ctx);
+    let mut xi = [0u8; BLOCK_LEN];
+    for (result, out) in results.into_iter().zip(in_out.chunks_mut(chunk_len)) {
+        let (chunk_xi, chunk) = try!(result);
+        out.copy_from_slice(&chunk);
+        let chunk_blocks = (chunk.len() + (BLOCK_LEN - 1)) / BLOCK_LEN;
+        try!(bssl::map_result(unsafe {
+            GFp_aes_gcm_combine_chunk(ctx.as_ptr(), &mut xi, &chunk_xi,
+                                      polyfill::u64_from_usize(chunk_blocks))
+        }));
+    }
+    bssl::map_result(unsafe {
+        GFp_aes_gcm_chunks_tag(ctx.as_ptr(), tag_out, &nonce, &xi,
+                               aead::ad_len(ad),
+                               polyfill::u64_from_usize(in_out_len))
     })
 }
 
+#[cfg(feature = "use_heap")]
+type ChunkFn = unsafe extern fn(ctx_buf: *const u8, in_out: *mut u8,
+                                in_out_len: c::size_t,
+                                xi_out: &mut [u8; BLOCK_LEN],
+                                nonce: &[u8; aead::NONCE_LEN],
+                                first_block: u64, ad: *const u8,
+                                num_ad_parts: c::size_t,
+                                ad_part: extern fn(ad: *const u8,
+                                                   i: c::size_t,
+                                                   len: &mut c::size_t)
+                                                   -> *const u8)
+                                -> c::int;
+
+/// The `GFp_aead_ad_part_f` callback that gives the C code the parts of the
+/// additional data. `ad` points to the first of the `&[u8]` parts.
+extern fn ad_part(ad: *const u8, i: c::size_t, len: &mut c::size_t)
+                  -> *const u8 {
+    let part: &[u8] = unsafe { *(ad as *const &[u8]).offset(i as isize) };
+    *len = part.len();
+    part.as_ptr()
+}
+
 
 const AES_128_KEY_LEN: usize = 128 / 8;
+const AES_192_KEY_LEN: usize = 192 / 8;
 const AES_256_KEY_LEN: usize = 32; // 256 / 8
 
-pub const AES_KEY_CTX_BUF_LEN: usize = AES_KEY_BUF_LEN + GCM128_SERIALIZED_LEN;
-
-// Keep this in sync with `AES_KEY` in aes.h.
-const AES_KEY_BUF_LEN: usize = (4 * 4 * (AES_MAX_ROUNDS + 1)) + 8;
+// The confidentiality limit of [RFC 9001 Section 6.6], which is lower than
+// the limit of [RFC 8446 Section 5.5] for TLS 1.3.
+//
+// [RFC 9001 Section 6.6]: https://tools.ietf.org/html/rfc9001#section-6.6
+// [RFC 8446 Section 5.5]: https://tools.ietf.org/html/rfc8446#section-5.5
+const CONFIDENTIALITY_LIMIT: u64 = 1 << 23;
 
-// Keep this in sync with `AES_MAXNR` in aes.h.
-const AES_MAX_ROUNDS: usize = 14;
+pub const AES_KEY_CTX_BUF_LEN: usize =
+    aes::KEY_CTX_BUF_LEN + GCM128_SERIALIZED_LEN;
 
 // Keep this in sync with `GCM128_SERIALIZED_LEN` in gcm.h.
 // TODO: test.
@@ -88,6 +245,13 @@ const AES_MAX_ROUNDS: usize = 14;
 // We should shrink it down on those platforms since this is still huge.
 const GCM128_SERIALIZED_LEN: usize = 16 * 16;
 
+#[cfg(feature = "use_heap")]
+const BLOCK_LEN: usize = 16;
+
+// Below this, starting a thread costs about as much as the work it saves.
+#[cfg(feature = "use_heap")]
+const MIN_PARALLEL_CHUNK_LEN: usize = 256 * 1024;
+
 
 extern {
     fn GFp_aes_gcm_init(ctx_buf: *mut u8, ctx_buf_len: c::size_t,
@@ -97,13 +261,52 @@ extern {
                         in_out_len: c::size_t,
                         tag_out: &mut [u8; aead::TAG_LEN],
                         nonce: &[u8; aead::NONCE_LEN], ad: *const u8,
-                        ad_len: c::size_t) -> c::int;
+                        num_ad_parts: c::size_t,
+                        ad_part: extern fn(ad: *const u8, i: c::size_t,
+                                           len: &mut c::size_t) -> *const u8)
+                        -> c::int;
 
     fn GFp_aes_gcm_open(ctx_buf: *const u8, out: *mut u8,
                         in_out_len: c::size_t,
                         tag_out: &mut [u8; aead::TAG_LEN],
                         nonce: &[u8; aead::NONCE_LEN], in_: *const u8,
-                        ad: *const u8, ad_len: c::size_t) -> c::int;
+                        ad: *const u8, num_ad_parts: c::size_t,
+                        ad_part: extern fn(ad: *const u8, i: c::size_t,
+                                           len: &mut c::size_t) -> *const u8)
+                        -> c::int;
+}
+
+#[cfg(feature = "use_heap")]
+extern {
+    fn GFp_aes_gcm_seal_chunk(ctx_buf: *const u8, in_out: *mut u8,
+                              in_out_len: c::size_t,
+                              xi_out: &mut [u8; BLOCK_LEN],
+                              nonce: &[u8; aead::NONCE_LEN], first_block: u64,
+                              ad: *const u8, num_ad_parts: c::size_t,
+                              ad_part: extern fn(ad: *const u8, i: c::size_t,
+                                                 len: &mut c::size_t)
+                                                 -> *const u8)
+                              -> c::int;
+
+    fn GFp_aes_gcm_open_chunk(ctx_buf: *const u8, in_out: *mut u8,
+                              in_out_len: c::size_t,
+                              xi_out: &mut [u8; BLOCK_LEN],
+                              nonce: &[u8; aead::NONCE_LEN], first_block: u64,
+                              ad: *const u8, num_ad_parts: c::size_t,
+                              ad_part: extern fn(ad: *const u8, i: c::size_t,
+                                                 len: &mut c::size_t)
+                                                 -> *const u8)
+                              -> c::int;
+
+    fn GFp_aes_gcm_combine_chunk(ctx_buf: *const u8, xi: &mut [u8; BLOCK_LEN],
+                                 chunk_xi: &[u8; BLOCK_LEN],
+                                 chunk_blocks: u64) -> c::int;
+
+    fn GFp_aes_gcm_chunks_tag(ctx_buf: *const u8,
+                              tag_out: &mut [u8; aead::TAG_LEN],
+                              nonce: &[u8; aead::NONCE_LEN],
+                              xi: &[u8; BLOCK_LEN], ad_len: u64,
+                              in_out_len: u64) -> c::int;
 }
 
 
@@ -112,18 +315,89 @@ mod tests {
     use {c, test};
     use super::super::super::aead;
     use super::super::tests::test_aead;
-    use super::AES_MAX_ROUNDS;
+    use aes::MAX_ROUNDS as AES_MAX_ROUNDS;
 
     #[test]
     pub fn test_aes_gcm_128() {
         test_aead(&aead::AES_128_GCM, "src/aead/aes_128_gcm_tests.txt");
     }
 
+    #[test]
+    pub fn test_aes_gcm_192() {
+        test_aead(&aead::AES_192_GCM, "src/aead/aes_192_gcm_tests.txt");
+    }
+
     #[test]
     pub fn test_aes_gcm_256() {
         test_aead(&aead::AES_256_GCM, "src/aead/aes_256_gcm_tests.txt");
     }
 
+    #[cfg(feature = "use_heap")]
+    #[test]
+    pub fn test_aes_gcm_parallel() {
+        use super::MIN_PARALLEL_CHUNK_LEN;
+        use std::vec::Vec;
+
+        let lens = [0, 100, MIN_PARALLEL_CHUNK_LEN + 1,
+                    (2 * MIN_PARALLEL_CHUNK_LEN) + 17,
+                    (3 * MIN_PARALLEL_CHUNK_LEN) + 48];
+        let nonce = [0x24; aead::NONCE_LEN];
+        for algorithm in &[&aead::AES_128_GCM, &aead::AES_192_GCM,
+                          &aead::AES_256_GCM] {
+            let key = aead::LessSafeKey::new(algorithm,
+                                             &vec![0x42; algorithm.key_len()])
+                .unwrap();
+            for len in lens.iter() {
+                let plaintext: Vec<u8> =
+                    (0..*len).map(|i| (i % 251) as u8).collect();
+                let mut expected = plaintext.clone();
+                expected.extend_from_slice(&[0; aead::MAX_OVERHEAD_LEN]);
+                assert_eq!(aead::seal_in_place(&key, &nonce, &mut expected,
+                                               aead::MAX_OVERHEAD_LEN, b"ad"),
+                           Ok(*len + aead::MAX_OVERHEAD_LEN));
+
+                for threads in &[0, 1, 2, 3, 8] {
+                    let mut in_out = plaintext.clone();
+                    in_out.extend_from_slice(&[0; aead::MAX_OVERHEAD_LEN]);
+                    assert_eq!(aead::seal_in_place_parallel(
+                                   &key, &nonce, &mut in_out,
+                                   aead::MAX_OVERHEAD_LEN, b"ad", *threads),
+                               Ok(*len + aead::MAX_OVERHEAD_LEN));
+                    assert!(in_out == expected);
+
+                    assert_eq!(aead::open_in_place_parallel(
+                                   &key, &nonce, &mut in_out, b"ad",
+                                   *threads),
+                               Ok(*len));
+                    assert!(in_out[..*len] == plaintext[..]);
+
+                    // A change to the last chunk or to the additional data,
+                    // which only the first chunk hashes, is detected.
+                    let mut in_out = expected.clone();
+                    in_out[*len] ^= 1;
+                    assert!(aead::open_in_place_parallel(
+                                &key, &nonce, &mut in_out, b"ad", *threads)
+                                .is_err());
+                    assert!(in_out[..*len].iter().all(|b| *b == 0));
+                    let mut in_out = expected.clone();
+                    assert!(aead::open_in_place_parallel(
+                                &key, &nonce, &mut in_out, b"aD", *threads)
+                                .is_err());
+                }
+            }
+        }
+
+        let key = aead::LessSafeKey::new(&aead::CHACHA20_POLY1305, &[0; 32])
+            .unwrap();
+        let mut in_out = [0u8; aead::MAX_OVERHEAD_LEN];
+        assert!(aead::seal_in_place_parallel(&key, &[0; aead::NONCE_LEN],
+                                             &mut in_out,
+                                             aead::MAX_OVERHEAD_LEN, b"", 2)
+                    .is_err());
+        assert!(aead::open_in_place_parallel(&key, &[0; aead::NONCE_LEN],
+                                             &mut in_out, b"", 2).is_err());
+    }
+
     #[test]
     pub fn test_aes() {
         test::from_file("src/aead/aes_tests.txt", |section, test_case| {
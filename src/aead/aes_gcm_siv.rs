@@ -0,0 +1,224 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use {aead, aes, error, polyfill};
+use super::polyval;
+
+/// AES-128 in GCM-SIV mode with 128-bit tags and 96 bit nonces, as described
+/// in [RFC 8452].
+///
+/// AES-GCM-SIV is nonce-misuse resistant: if a nonce is ever reused with the
+/// same key then the only thing that is revealed is whether the same
+/// plaintext was sealed with the same nonce and additional data, instead of
+/// the confidentiality and authenticity of everything being lost. It should
+/// still be used with unique nonces whenever possible.
+///
+/// C analog: `EVP_aead_aes_128_gcm_siv`
+///
+/// [RFC 8452]: https://tools.ietf.org/html/rfc8452
+pub static AES_128_GCM_SIV: aead::Algorithm = aead::Algorithm {
+    key_len: AES_128_KEY_LEN,
+    init: aes_gcm_siv_init,
+    seal: aes_128_gcm_siv_seal,
+    open: aes_128_gcm_siv_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-256 in GCM-SIV mode with 128-bit tags and 96 bit nonces, as described
+/// in [RFC 8452].
+///
+/// See `AES_128_GCM_SIV` for a description of its nonce-misuse resistance.
+///
+/// C analog: `EVP_aead_aes_256_gcm_siv`
+///
+/// [RFC 8452]: https://tools.ietf.org/html/rfc8452
+pub static AES_256_GCM_SIV: aead::Algorithm = aead::Algorithm {
+    key_len: AES_256_KEY_LEN,
+    init: aes_gcm_siv_init,
+    seal: aes_256_gcm_siv_seal,
+    open: aes_256_gcm_siv_open,
+    nonce_len: aead::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// Expands the key-generating key into `ctx_buf`.
+fn aes_gcm_siv_init(ctx_buf: &mut [u8], key: &[u8])
+                    -> Result<(), error::Unspecified> {
+    aes::init(ctx_buf, key)
+}
+
+fn aes_128_gcm_siv_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                        in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                        ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(ctx, AES_128_KEY_LEN, nonce, in_out, tag_out, ad)
+}
+
+fn aes_128_gcm_siv_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                        in_out: &mut [u8], in_prefix_len: usize,
+                        received_tag: &[u8; aead::TAG_LEN],
+                        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                        -> Result<(), error::Unspecified> {
+    open(ctx, AES_128_KEY_LEN, nonce, in_out, in_prefix_len, received_tag,
+         tag_out, ad)
+}
+
+fn aes_256_gcm_siv_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                        in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                        ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(ctx, AES_256_KEY_LEN, nonce, in_out, tag_out, ad)
+}
+
+fn aes_256_gcm_siv_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                        in_out: &mut [u8], in_prefix_len: usize,
+                        received_tag: &[u8; aead::TAG_LEN],
+                        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                        -> Result<(), error::Unspecified> {
+    open(ctx, AES_256_KEY_LEN, nonce, in_out, in_prefix_len, received_tag,
+         tag_out, ad)
+}
+
+fn seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], key_len: usize, nonce: &[u8],
+        in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+        -> Result<(), error::Unspecified> {
+    let nonce = try!(slice_as_array_ref!(nonce, aead::NONCE_LEN));
+    try!(check_input_lens(in_out.len(), ad));
+    let (auth_key, enc_key) = try!(derive_keys(ctx, key_len, nonce));
+    calculate_tag(&auth_key, &enc_key, nonce, ad, in_out, tag_out);
+    ctr32_xor(&enc_key, tag_out, in_out, 0);
+    Ok(())
+}
+
+fn open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], key_len: usize, nonce: &[u8],
+        in_out: &mut [u8], in_prefix_len: usize,
+        received_tag: &[u8; aead::TAG_LEN],
+        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+        -> Result<(), error::Unspecified> {
+    let nonce = try!(slice_as_array_ref!(nonce, aead::NONCE_LEN));
+    let plaintext_len = in_out.len() - in_prefix_len;
+    try!(check_input_lens(plaintext_len, ad));
+    let (auth_key, enc_key) = try!(derive_keys(ctx, key_len, nonce));
+    ctr32_xor(&enc_key, received_tag, in_out, in_prefix_len);
+    calculate_tag(&auth_key, &enc_key, nonce, ad, &in_out[..plaintext_len],
+                  tag_out);
+    Ok(())
+}
+
+// RFC 8452 Section 6 limits both the plaintext and the additional data to
+// 2**36 bytes.
+fn check_input_lens(plaintext_len: usize, ad: &[&[u8]])
+                    -> Result<(), error::Unspecified> {
+    const MAX_LEN: u64 = 1 << 36;
+    if polyfill::u64_from_usize(plaintext_len) > MAX_LEN ||
+       aead::ad_len(ad) > MAX_LEN {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+/// Derives the per-nonce message-authentication key and message-encryption
+/// key from the key-generating key, as described in RFC 8452 Section 4.
+fn derive_keys(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], key_len: usize,
+               nonce: &[u8; aead::NONCE_LEN])
+               -> Result<(polyval::Block, aes::Key), error::Unspecified> {
+    // Each derived block contributes its first half to the derived keys.
+    const HALF_BLOCK_LEN: usize = aes::BLOCK_LEN / 2;
+    let num_blocks = (polyval::BLOCK_LEN + key_len) / HALF_BLOCK_LEN;
+
+    let mut blocks = [0u8; (polyval::BLOCK_LEN + AES_256_KEY_LEN) * 2];
+    let blocks = &mut blocks[..(num_blocks * aes::BLOCK_LEN)];
+    for (i, block) in blocks.chunks_mut(aes::BLOCK_LEN).enumerate() {
+        block[..4].copy_from_slice(&le_u8_from_u32(i as u32));
+        block[4..].copy_from_slice(nonce);
+    }
+    aes::encrypt_blocks(polyfill::slice::u64_as_u8(ctx), blocks);
+
+    let mut derived = [0u8; polyval::BLOCK_LEN + AES_256_KEY_LEN];
+    for (derived, block) in derived.chunks_mut(HALF_BLOCK_LEN)
+                                   .zip(blocks.chunks(aes::BLOCK_LEN)) {
+        derived.copy_from_slice(&block[..HALF_BLOCK_LEN]);
+    }
+    let (auth_key, enc_key) = derived.split_at(polyval::BLOCK_LEN);
+
+    let auth_key = *try!(slice_as_array_ref!(auth_key, polyval::BLOCK_LEN));
+    let enc_key = try!(aes::Key::new(&enc_key[..key_len]));
+    Ok((auth_key, enc_key))
+}
+
+fn calculate_tag(auth_key: &polyval::Block, enc_key: &aes::Key,
+                 nonce: &[u8; aead::NONCE_LEN], ad: &[&[u8]], plaintext: &[u8],
+                 tag_out: &mut [u8; aead::TAG_LEN]) {
+    let mut ctx = polyval::Context::new(auth_key);
+    aead::for_each_padded_block(ad.iter().cloned(),
+                                |block| ctx.update_block(block));
+    ctx.update_padded(plaintext);
+    let lengths =
+        [(aead::ad_len(ad) * 8).to_le(),
+         (polyfill::u64_from_usize(plaintext.len()) * 8).to_le()];
+    ctx.update_block(
+        slice_as_array_ref!(polyfill::slice::u64_as_u8(&lengths),
+                            polyval::BLOCK_LEN).unwrap());
+
+    let mut s = ctx.finish();
+    for (s, n) in s.iter_mut().zip(nonce.iter()) {
+        *s ^= *n;
+    }
+    s[aes::BLOCK_LEN - 1] &= 0x7f;
+    enc_key.encrypt_block(&mut s);
+    *tag_out = s;
+}
+
+/// XORs the key stream into the input `in_out[in_prefix_len..]`, writing the
+/// output to `in_out[..(in_out.len() - in_prefix_len)]`. The initial counter
+/// block is the tag with its most significant bit set, and the counter is the
+/// first 32 bits of the block, in little-endian order.
+fn ctr32_xor(key: &aes::Key, tag: &[u8; aead::TAG_LEN], in_out: &mut [u8],
+             in_prefix_len: usize) {
+    let mut counter_block = *tag;
+    counter_block[aes::BLOCK_LEN - 1] |= 0x80;
+    let mut counter = polyfill::slice::u32_from_le_u8(
+        slice_as_array_ref!(&counter_block[..4], 4).unwrap());
+    key.xor_key_stream(in_out, in_prefix_len, |block| {
+        *block = counter_block;
+        block[..4].copy_from_slice(&le_u8_from_u32(counter));
+        counter = counter.wrapping_add(1);
+    });
+}
+
+#[inline]
+fn le_u8_from_u32(value: u32) -> [u8; 4] {
+    [value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]
+}
+
+const AES_128_KEY_LEN: usize = 128 / 8;
+const AES_256_KEY_LEN: usize = 256 / 8;
+
+
+#[cfg(test)]
+mod tests {
+    use aead;
+    use super::super::tests::test_aead;
+
+    #[test]
+    pub fn test_aes_128_gcm_siv() {
+        test_aead(&aead::AES_128_GCM_SIV, "src/aead/aes_128_gcm_siv_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_256_gcm_siv() {
+        test_aead(&aead::AES_256_GCM_SIV, "src/aead/aes_256_gcm_siv_tests.txt");
+    }
+}
@@ -0,0 +1,342 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! AES Key Wrap ([RFC 3394]) and AES Key Wrap with Padding ([RFC 5649]).
+//!
+//! These are the `KW` and `KWP` modes of [NIST SP 800-38F]. They are used to
+//! wrap keys for export from HSMs and PKCS#11 tokens, and by the JOSE
+//! `A128KW` and `A256KW` algorithms. They are deterministic and work only on
+//! short inputs; use the AEADs in `ring::aead` for anything other than keys.
+//!
+//! Only 128-bit and 256-bit key-encryption keys are supported. Unwrapping
+//! uses the (slow) portable AES decryption code, which is fine for keys but
+//! makes these modes unsuitable for bulk data.
+//!
+//! [RFC 3394]: https://tools.ietf.org/html/rfc3394
+//! [RFC 5649]: https://tools.ietf.org/html/rfc5649
+//! [NIST SP 800-38F]:
+//!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38F.pdf
+
+use {aes, constant_time, error, polyfill};
+
+/// The number of bytes that wrapping adds to the (padded) key data.
+pub const OVERHEAD_LEN: usize = SEMIBLOCK_LEN;
+
+/// A key-encryption key.
+pub struct Key {
+    encryption_key: aes::Key,
+    decryption_key: aes::DecryptionKey,
+}
+
+impl Key {
+    /// Constructs a new key-encryption key. `key_bytes` must be 16 bytes
+    /// (AES-128), 24 bytes (AES-192), or 32 bytes (AES-256) long.
+    pub fn new(key_bytes: &[u8]) -> Result<Key, error::Unspecified> {
+        Ok(Key {
+            encryption_key: try!(aes::Key::new(key_bytes)),
+            decryption_key: try!(aes::DecryptionKey::new(key_bytes)),
+        })
+    }
+
+    /// Wraps `key_data` using AES Key Wrap (RFC 3394), writing the result to
+    /// the start of `out` and returning its length, which is
+    /// `key_data.len() + OVERHEAD_LEN`.
+    ///
+    /// `key_data.len()` must be a multiple of 8 and at least 16.
+    pub fn wrap(&self, key_data: &[u8], out: &mut [u8])
+                -> Result<usize, error::Unspecified> {
+        if key_data.len() % SEMIBLOCK_LEN != 0 ||
+           key_data.len() < 2 * SEMIBLOCK_LEN {
+            return Err(error::Unspecified);
+        }
+        let out_len = key_data.len() + OVERHEAD_LEN;
+        if out.len() < out_len {
+            return Err(error::Unspecified);
+        }
+        let out = &mut out[..out_len];
+        out[SEMIBLOCK_LEN..].copy_from_slice(key_data);
+        self.w(&KW_IV, out);
+        Ok(out_len)
+    }
+
+    /// Unwraps `wrapped` using AES Key Wrap (RFC 3394), writing the key data
+    /// to the start of `out` and returning its length, which is
+    /// `wrapped.len() - OVERHEAD_LEN`.
+    ///
+    /// Nothing is written to `out` if the integrity check fails.
+    pub fn unwrap(&self, wrapped: &[u8], out: &mut [u8])
+                  -> Result<usize, error::Unspecified> {
+        if wrapped.len() % SEMIBLOCK_LEN != 0 ||
+           wrapped.len() < 3 * SEMIBLOCK_LEN {
+            return Err(error::Unspecified);
+        }
+        let out_len = wrapped.len() - OVERHEAD_LEN;
+        if out.len() < out_len {
+            return Err(error::Unspecified);
+        }
+        let mut a = [0u8; SEMIBLOCK_LEN];
+        a.copy_from_slice(&wrapped[..SEMIBLOCK_LEN]);
+        let mut r = [0u8; MAX_UNWRAP_LEN];
+        let r = try!(r.get_mut(..out_len).ok_or(error::Unspecified));
+        r.copy_from_slice(&wrapped[SEMIBLOCK_LEN..]);
+        self.w_inverse(&mut a, r);
+        let result = constant_time::verify_slices_are_equal(&a, &KW_IV)
+            .map(|()| {
+                out[..out_len].copy_from_slice(r);
+                out_len
+            });
+        polyfill::slice::zeroize(r);
+        result
+    }
+
+    /// Wraps `key_data` using AES Key Wrap with Padding (RFC 5649), writing
+    /// the result to the start of `out` and returning its length, which is
+    /// `key_data.len()` rounded up to a multiple of 8, plus `OVERHEAD_LEN`.
+    ///
+    /// `key_data` must not be empty.
+    pub fn wrap_with_padding(&self, key_data: &[u8], out: &mut [u8])
+                             -> Result<usize, error::Unspecified> {
+        if key_data.is_empty() ||
+           polyfill::u64_from_usize(key_data.len()) > 0xffffffff {
+            return Err(error::Unspecified);
+        }
+        let padded_len = padded_len(key_data.len());
+        let out_len = padded_len + OVERHEAD_LEN;
+        if out.len() < out_len {
+            return Err(error::Unspecified);
+        }
+        let out = &mut out[..out_len];
+        let aiv = kwp_aiv(key_data.len());
+        out[SEMIBLOCK_LEN..(SEMIBLOCK_LEN + key_data.len())]
+            .copy_from_slice(key_data);
+        for b in &mut out[(SEMIBLOCK_LEN + key_data.len())..] {
+            *b = 0;
+        }
+        if padded_len == SEMIBLOCK_LEN {
+            // A single semiblock is encrypted as one AES block.
+            out[..SEMIBLOCK_LEN].copy_from_slice(&aiv);
+            self.encryption_key.encrypt_blocks(out);
+        } else {
+            self.w(&aiv, out);
+        }
+        Ok(out_len)
+    }
+
+    /// Unwraps `wrapped` using AES Key Wrap with Padding (RFC 5649), writing
+    /// the key data to the start of `out` and returning its length.
+    ///
+    /// `out` must be at least `wrapped.len() - OVERHEAD_LEN` bytes long.
+    /// Nothing is written to `out` if the integrity check fails.
+    pub fn unwrap_with_padding(&self, wrapped: &[u8], out: &mut [u8])
+                               -> Result<usize, error::Unspecified> {
+        if wrapped.len() % SEMIBLOCK_LEN != 0 ||
+           wrapped.len() < 2 * SEMIBLOCK_LEN {
+            return Err(error::Unspecified);
+        }
+        let padded_len = wrapped.len() - OVERHEAD_LEN;
+        if out.len() < padded_len {
+            return Err(error::Unspecified);
+        }
+        let mut a = [0u8; SEMIBLOCK_LEN];
+        let mut p = [0u8; MAX_UNWRAP_LEN];
+        let p = try!(p.get_mut(..padded_len).ok_or(error::Unspecified));
+        if padded_len == SEMIBLOCK_LEN {
+            let mut block = [0u8; aes::BLOCK_LEN];
+            block.copy_from_slice(wrapped);
+            self.decryption_key.decrypt_block(&mut block);
+            a.copy_from_slice(&block[..SEMIBLOCK_LEN]);
+            p.copy_from_slice(&block[SEMIBLOCK_LEN..]);
+            polyfill::slice::zeroize(&mut block);
+        } else {
+            a.copy_from_slice(&wrapped[..SEMIBLOCK_LEN]);
+            p.copy_from_slice(&wrapped[SEMIBLOCK_LEN..]);
+            self.w_inverse(&mut a, p);
+        }
+
+        let result = check_kwp_aiv(&a, p).map(|key_data_len| {
+            out[..key_data_len].copy_from_slice(&p[..key_data_len]);
+            key_data_len
+        });
+        polyfill::slice::zeroize(p);
+        result
+    }
+
+    /// The wrapping function W of RFC 3394 Section 2.2.1, in the index-based
+    /// form. On input, `a_and_r[SEMIBLOCK_LEN..]` is the plaintext; on output
+    /// `a_and_r` is the ciphertext.
+    fn w(&self, iv: &[u8; SEMIBLOCK_LEN], a_and_r: &mut [u8]) {
+        let n = a_and_r.len() / SEMIBLOCK_LEN - 1;
+        let mut a = *iv;
+        let mut block = [0u8; aes::BLOCK_LEN];
+        for j in 0..6 {
+            for i in 1..(n + 1) {
+                let r = &mut a_and_r[(i * SEMIBLOCK_LEN)..
+                                     ((i + 1) * SEMIBLOCK_LEN)];
+                block[..SEMIBLOCK_LEN].copy_from_slice(&a);
+                block[SEMIBLOCK_LEN..].copy_from_slice(r);
+                self.encryption_key.encrypt_block(&mut block);
+                a.copy_from_slice(&block[..SEMIBLOCK_LEN]);
+                xor_t(&mut a, n * j + i);
+                r.copy_from_slice(&block[SEMIBLOCK_LEN..]);
+            }
+        }
+        a_and_r[..SEMIBLOCK_LEN].copy_from_slice(&a);
+        polyfill::slice::zeroize(&mut block);
+    }
+
+    /// The unwrapping function W^-1 of RFC 3394 Section 2.2.2, in the
+    /// index-based form.
+    fn w_inverse(&self, a: &mut [u8; SEMIBLOCK_LEN], r: &mut [u8]) {
+        let n = r.len() / SEMIBLOCK_LEN;
+        let mut block = [0u8; aes::BLOCK_LEN];
+        for j in (0..6).rev() {
+            for i in (1..(n + 1)).rev() {
+                let r = &mut r[((i - 1) * SEMIBLOCK_LEN)..(i * SEMIBLOCK_LEN)];
+                xor_t(a, n * j + i);
+                block[..SEMIBLOCK_LEN].copy_from_slice(a);
+                block[SEMIBLOCK_LEN..].copy_from_slice(r);
+                self.decryption_key.decrypt_block(&mut block);
+                a.copy_from_slice(&block[..SEMIBLOCK_LEN]);
+                r.copy_from_slice(&block[SEMIBLOCK_LEN..]);
+            }
+        }
+        polyfill::slice::zeroize(&mut block);
+    }
+}
+
+fn xor_t(a: &mut [u8; SEMIBLOCK_LEN], t: usize) {
+    let mut t = polyfill::u64_from_usize(t);
+    for b in a.iter_mut().rev() {
+        *b ^= t as u8;
+        t >>= 8;
+    }
+}
+
+#[inline]
+fn padded_len(len: usize) -> usize {
+    (len + SEMIBLOCK_LEN - 1) / SEMIBLOCK_LEN * SEMIBLOCK_LEN
+}
+
+/// The alternative initial value of RFC 5649 Section 3 for key data of
+/// length `len`.
+fn kwp_aiv(len: usize) -> [u8; SEMIBLOCK_LEN] {
+    let mut aiv = [0u8; SEMIBLOCK_LEN];
+    aiv[..4].copy_from_slice(&KWP_AIV_PREFIX);
+    aiv[4..].copy_from_slice(&polyfill::slice::be_u8_from_u32(len as u32));
+    aiv
+}
+
+/// Checks the alternative initial value `a` and the padding of `p` as
+/// described in RFC 5649 Section 3, returning the length of the key data.
+fn check_kwp_aiv(a: &[u8; SEMIBLOCK_LEN], p: &[u8])
+                 -> Result<usize, error::Unspecified> {
+    try!(constant_time::verify_slices_are_equal(&a[..4], &KWP_AIV_PREFIX));
+    let mli = polyfill::slice::u32_from_be_u8(
+        slice_as_array_ref!(&a[4..], 4).unwrap()) as usize;
+    if mli == 0 || padded_len(mli) != p.len() {
+        return Err(error::Unspecified);
+    }
+    let padding = p[mli..].iter().fold(0, |acc, b| acc | *b);
+    if padding != 0 {
+        return Err(error::Unspecified);
+    }
+    Ok(mli)
+}
+
+const SEMIBLOCK_LEN: usize = aes::BLOCK_LEN / 2;
+
+// The default initial value of RFC 3394 Section 2.2.3.1.
+const KW_IV: [u8; SEMIBLOCK_LEN] = [0xa6; SEMIBLOCK_LEN];
+
+// The constant part of the alternative initial value of RFC 5649 Section 3.
+const KWP_AIV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+// Unwrapping is done in a temporary buffer so that nothing is written to the
+// output when the integrity check fails. This limits the length of the keys
+// that can be unwrapped; it is long enough for 4096-bit RSA private keys.
+const MAX_UNWRAP_LEN: usize = 4096;
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    fn test_aes_kw() {
+        test_wrap("src/aead/aes_kw_tests.txt", Key::wrap, Key::unwrap);
+    }
+
+    #[test]
+    fn test_aes_kwp() {
+        test_wrap("src/aead/aes_kwp_tests.txt", Key::wrap_with_padding,
+                  Key::unwrap_with_padding);
+    }
+
+    fn test_wrap<W, U>(file_path: &str, wrap: W, unwrap: U)
+            where W: Fn(&Key, &[u8], &mut [u8])
+                        -> Result<usize, error::Unspecified>,
+                  U: Fn(&Key, &[u8], &mut [u8])
+                        -> Result<usize, error::Unspecified> {
+        test::from_file(file_path, |section, test_case| {
+            assert_eq!(section, "");
+            let kek = test_case.consume_bytes("KEK");
+            let key_data = test_case.consume_bytes("KEY");
+            let wrapped = test_case.consume_bytes("WRAPPED");
+
+            let kek = Key::new(&kek).unwrap();
+
+            let mut out = vec![0u8; wrapped.len() + 1];
+            assert_eq!(wrap(&kek, &key_data, &mut out), Ok(wrapped.len()));
+            assert_eq!(&out[..wrapped.len()], &wrapped[..]);
+            assert!(wrap(&kek, &key_data, &mut out[..(wrapped.len() - 1)])
+                        .is_err());
+
+            let mut out = vec![0u8; wrapped.len()];
+            assert_eq!(unwrap(&kek, &wrapped, &mut out), Ok(key_data.len()));
+            assert_eq!(&out[..key_data.len()], &key_data[..]);
+
+            // A change anywhere in the wrapped key is detected, and nothing
+            // is written to the output.
+            for &i in &[0, 7, 8, wrapped.len() / 2, wrapped.len() - 1] {
+                let mut corrupted = wrapped.clone();
+                corrupted[i] ^= 1;
+                let mut out = vec![0u8; wrapped.len()];
+                assert!(unwrap(&kek, &corrupted, &mut out).is_err());
+                assert!(out.iter().all(|b| *b == 0));
+            }
+            assert!(unwrap(&kek, &wrapped[..(wrapped.len() - 8)], &mut out)
+                        .is_err());
+            assert!(unwrap(&kek, &wrapped[1..], &mut out).is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_aes_kw_input_lens() {
+        let kek = Key::new(&[0; 16]).unwrap();
+        let mut out = [0u8; 64];
+        assert!(kek.wrap(&[], &mut out).is_err());
+        assert!(kek.wrap(&[0; 8], &mut out).is_err());
+        assert!(kek.wrap(&[0; 17], &mut out).is_err());
+        assert_eq!(kek.wrap(&[0; 16], &mut out), Ok(24));
+        assert!(kek.wrap_with_padding(&[], &mut out).is_err());
+        assert_eq!(kek.wrap_with_padding(&[0; 17], &mut out), Ok(32));
+
+        assert!(Key::new(&[0; 24]).is_ok());
+        assert!(Key::new(&[0; 15]).is_err());
+        assert!(Key::new(&[0; 33]).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use {aead, aes, error, polyfill};
+
+/// AES-SIV-CMAC with a 256-bit key (two AES-128 keys), as described in
+/// [RFC 5297].
+///
+/// This is the deterministic authenticated encryption mode of
+/// [RFC 5297 Section 2.6] with the additional data as the only header: the
+/// nonce length is zero and sealing the same plaintext with the same key and
+/// additional data always gives the same output. Thus it reveals whether the
+/// same plaintext and additional data were sealed twice, but nothing else. A
+/// nonce can be included at the end of the additional data when that is not
+/// acceptable.
+///
+/// The synthetic IV is output after the ciphertext, like the tags of the
+/// other AEADs in this module, instead of before it as in RFC 5297.
+///
+/// [RFC 5297]: https://tools.ietf.org/html/rfc5297
+/// [RFC 5297 Section 2.6]: https://tools.ietf.org/html/rfc5297#section-2.6
+pub static AES_128_SIV_CMAC: aead::Algorithm = aead::Algorithm {
+    key_len: 2 * AES_128_KEY_LEN,
+    init: aes_siv_init,
+    seal: aes_siv_seal,
+    open: aes_siv_open,
+    nonce_len: 0,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// AES-SIV-CMAC with a 512-bit key (two AES-256 keys), as described in
+/// [RFC 5297].
+///
+/// See `AES_128_SIV_CMAC` for details.
+///
+/// [RFC 5297]: https://tools.ietf.org/html/rfc5297
+pub static AES_256_SIV_CMAC: aead::Algorithm = aead::Algorithm {
+    key_len: 2 * AES_256_KEY_LEN,
+    init: aes_siv_init,
+    seal: aes_siv_seal,
+    open: aes_siv_open,
+    nonce_len: 0,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// Expands the first half of `key`, the CMAC key, and the second half, the
+/// CTR key, one after the other into `ctx_buf`.
+fn aes_siv_init(ctx_buf: &mut [u8], key: &[u8])
+                -> Result<(), error::Unspecified> {
+    let (mac_key, ctr_key) = key.split_at(key.len() / 2);
+    let (mac_ctx, ctr_ctx) = ctx_buf.split_at_mut(aes::KEY_CTX_BUF_LEN);
+    try!(aes::init(mac_ctx, mac_key));
+    aes::init(ctr_ctx, ctr_key)
+}
+
+fn aes_siv_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], _nonce: &[u8],
+                in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let (mac_ctx, ctr_ctx) = ctx_as_keys(ctx);
+    *tag_out = s2v(mac_ctx, ad, in_out);
+    ctr_xor(ctr_ctx, tag_out, in_out, 0);
+    Ok(())
+}
+
+fn aes_siv_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], _nonce: &[u8],
+                in_out: &mut [u8], in_prefix_len: usize,
+                received_tag: &[u8; aead::TAG_LEN],
+                tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                -> Result<(), error::Unspecified> {
+    let (mac_ctx, ctr_ctx) = ctx_as_keys(ctx);
+    ctr_xor(ctr_ctx, received_tag, in_out, in_prefix_len);
+    let plaintext_len = in_out.len() - in_prefix_len;
+    *tag_out = s2v(mac_ctx, ad, &in_out[..plaintext_len]);
+    Ok(())
+}
+
+fn ctx_as_keys(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS]) -> (&[u8], &[u8]) {
+    polyfill::slice::u64_as_u8(ctx).split_at(aes::KEY_CTX_BUF_LEN)
+}
+
+/// S2V (RFC 5297 Section 2.4) of the two strings `ad`, which is the
+/// concatenation of its parts, and `plaintext`.
+fn s2v(mac_ctx: &[u8], ad: &[&[u8]], plaintext: &[u8]) -> aes::Block {
+    let subkeys = aes::CmacSubkeys::new(mac_ctx);
+
+    let mut d = aes::cmac(mac_ctx, &subkeys, &[&[0u8; aes::BLOCK_LEN]]);
+    let ad_mac = aes::cmac(mac_ctx, &subkeys, ad);
+    d = aes::dbl(&d);
+    aes::xor_block(&mut d, &ad_mac);
+
+    if plaintext.len() >= aes::BLOCK_LEN {
+        // "xorend": XOR `d` into the last block of the plaintext.
+        let (head, last) =
+            plaintext.split_at(plaintext.len() - aes::BLOCK_LEN);
+        let mut t = d;
+        aes::xor_block(&mut t,
+                       slice_as_array_ref!(last, aes::BLOCK_LEN).unwrap());
+        aes::cmac(mac_ctx, &subkeys, &[head, &t])
+    } else {
+        let mut t = aes::dbl(&d);
+        for (t, p) in t.iter_mut().zip(plaintext.iter()) {
+            *t ^= *p;
+        }
+        t[plaintext.len()] ^= 0x80;
+        aes::cmac(mac_ctx, &subkeys, &[&t])
+    }
+}
+
+/// XORs the key stream into the input `in_out[in_prefix_len..]`, writing the
+/// output to `in_out[..(in_out.len() - in_prefix_len)]`. The initial counter
+/// block is the synthetic IV with bits 31 and 63 cleared, and the whole block
+/// is incremented as a big-endian number, as described in RFC 5297 Section
+/// 2.5.
+fn ctr_xor(ctr_ctx: &[u8], siv: &[u8; aead::TAG_LEN], in_out: &mut [u8],
+           in_prefix_len: usize) {
+    let mut counter = *siv;
+    counter[8] &= 0x7f;
+    counter[12] &= 0x7f;
+    aes::xor_key_stream(ctr_ctx, in_out, in_prefix_len, |block| {
+        *block = counter;
+        // The counter is derived from the synthetic IV, which isn't secret, so
+        // this doesn't need to be constant-time.
+        for b in counter.iter_mut().rev() {
+            *b = b.wrapping_add(1);
+            if *b != 0 {
+                break;
+            }
+        }
+    });
+}
+
+const AES_128_KEY_LEN: usize = 128 / 8;
+const AES_256_KEY_LEN: usize = 256 / 8;
+
+
+#[cfg(test)]
+mod tests {
+    use aead;
+    use super::super::tests::test_aead;
+
+    #[test]
+    pub fn test_aes_128_siv_cmac() {
+        test_aead(&aead::AES_128_SIV_CMAC,
+                  "src/aead/aes_128_siv_cmac_tests.txt");
+    }
+
+    #[test]
+    pub fn test_aes_256_siv_cmac() {
+        test_aead(&aead::AES_256_SIV_CMAC,
+                  "src/aead/aes_256_siv_cmac_tests.txt");
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use {aead, error, polyfill};
+
+/// Ascon-128 as described in [Ascon v1.2].
+///
+/// Ascon is the winner of the NIST lightweight cryptography competition. It
+/// is built from a 320-bit permutation that only uses 64-bit bitwise
+/// operations and rotations, so it is fast and constant-time on small
+/// processors without AES instructions. The keys, nonces, and tags are 128
+/// bits long.
+///
+/// [Ascon v1.2]:
+///     https://csrc.nist.gov/CSRC/media/Projects/lightweight-cryptography/documents/finalist-round/updated-spec-doc/ascon-spec-final.pdf
+pub static ASCON_128: aead::Algorithm = aead::Algorithm {
+    key_len: KEY_LEN,
+    init: ascon_init,
+    seal: ascon_128_seal,
+    open: ascon_128_open,
+    nonce_len: NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// Ascon-128a as described in [Ascon v1.2].
+///
+/// Ascon-128a absorbs 128 bits per permutation call instead of 64, so it is
+/// faster than `ASCON_128` for long messages. See `ASCON_128` for details.
+///
+/// [Ascon v1.2]:
+///     https://csrc.nist.gov/CSRC/media/Projects/lightweight-cryptography/documents/finalist-round/updated-spec-doc/ascon-spec-final.pdf
+pub static ASCON_128A: aead::Algorithm = aead::Algorithm {
+    key_len: KEY_LEN,
+    init: ascon_init,
+    seal: ascon_128a_seal,
+    open: ascon_128a_open,
+    nonce_len: NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+/// The parameters of an Ascon variant (Table 1 of the specification).
+struct Variant {
+    iv: u64,
+    rate: usize,
+    rounds_b: usize,
+}
+
+static VARIANT_128: Variant = Variant {
+    iv: 0x80400c0600000000,
+    rate: 64 / 8,
+    rounds_b: 6,
+};
+
+static VARIANT_128A: Variant = Variant {
+    iv: 0x80800c0800000000,
+    rate: 128 / 8,
+    rounds_b: 8,
+};
+
+/// Copies |key| into |ctx_buf|.
+fn ascon_init(ctx_buf: &mut [u8], key: &[u8])
+              -> Result<(), error::Unspecified> {
+    ctx_buf[..key.len()].copy_from_slice(key);
+    Ok(())
+}
+
+fn ascon_128_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                  ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(&VARIANT_128, ctx, nonce, in_out, tag_out, ad)
+}
+
+fn ascon_128_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                  in_out: &mut [u8], in_prefix_len: usize,
+                  _received_tag: &[u8; aead::TAG_LEN],
+                  tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                  -> Result<(), error::Unspecified> {
+    open(&VARIANT_128, ctx, nonce, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn ascon_128a_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                   ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    seal(&VARIANT_128A, ctx, nonce, in_out, tag_out, ad)
+}
+
+fn ascon_128a_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                   in_out: &mut [u8], in_prefix_len: usize,
+                   _received_tag: &[u8; aead::TAG_LEN],
+                   tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                   -> Result<(), error::Unspecified> {
+    open(&VARIANT_128A, ctx, nonce, in_out, in_prefix_len, tag_out, ad)
+}
+
+fn seal(variant: &Variant, ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+        nonce: &[u8], in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+        ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let key = ctx_as_key(ctx);
+    let mut state = State::new(variant, &key, nonce);
+    state.absorb_ad(variant, ad);
+
+    let rate = variant.rate;
+    let full_len = in_out.len() - (in_out.len() % rate);
+    let (full_blocks, last) = in_out.split_at_mut(full_len);
+    for block in full_blocks.chunks_mut(rate) {
+        for (i, word) in block.chunks_mut(8).enumerate() {
+            state.x[i] ^= be_u64(word);
+            word.copy_from_slice(
+                &polyfill::slice::be_u8_from_u64(state.x[i]));
+        }
+        state.permute(variant.rounds_b);
+    }
+    for (i, b) in last.iter_mut().enumerate() {
+        state.xor_byte(i, *b);
+        *b = state.byte(i);
+    }
+    state.xor_byte(last.len(), PADDING);
+
+    *tag_out = state.finalize(variant, &key);
+    Ok(())
+}
+
+fn open(variant: &Variant, ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+        nonce: &[u8], in_out: &mut [u8], in_prefix_len: usize,
+        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+        -> Result<(), error::Unspecified> {
+    let key = ctx_as_key(ctx);
+    let mut state = State::new(variant, &key, nonce);
+    state.absorb_ad(variant, ad);
+
+    // The output is written `in_prefix_len` bytes before the input, so each
+    // word of the input is read before the output overwrites it.
+    let rate = variant.rate;
+    let ciphertext_len = in_out.len() - in_prefix_len;
+    let full_len = ciphertext_len - (ciphertext_len % rate);
+    let mut offset = 0;
+    while offset < full_len {
+        for i in 0..(rate / 8) {
+            let start = offset + (8 * i);
+            let c = be_u64(&in_out[(in_prefix_len + start)..]);
+            let p = state.x[i] ^ c;
+            state.x[i] = c;
+            in_out[start..(start + 8)]
+                .copy_from_slice(&polyfill::slice::be_u8_from_u64(p));
+        }
+        state.permute(variant.rounds_b);
+        offset += rate;
+    }
+    for i in 0..(ciphertext_len - full_len) {
+        let c = in_out[in_prefix_len + full_len + i];
+        in_out[full_len + i] = state.byte(i) ^ c;
+        state.set_byte(i, c);
+    }
+    state.xor_byte(ciphertext_len - full_len, PADDING);
+
+    *tag_out = state.finalize(variant, &key);
+    Ok(())
+}
+
+fn ctx_as_key(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS]) -> [u64; 2] {
+    let key_bytes = &polyfill::slice::u64_as_u8(ctx)[..KEY_LEN];
+    [be_u64(&key_bytes[..8]), be_u64(&key_bytes[8..])]
+}
+
+/// The Ascon state, as five 64-bit words. The bytes of the state are numbered
+/// in big-endian order, starting with the most significant byte of `x[0]`.
+struct State {
+    x: [u64; 5],
+}
+
+impl State {
+    /// Initialization (Section 2.4.1).
+    fn new(variant: &Variant, key: &[u64; 2], nonce: &[u8]) -> State {
+        let mut state = State {
+            x: [variant.iv, key[0], key[1], be_u64(&nonce[..8]),
+                be_u64(&nonce[8..])],
+        };
+        state.permute(ROUNDS_A);
+        state.x[3] ^= key[0];
+        state.x[4] ^= key[1];
+        state
+    }
+
+    /// Processing the associated data (Section 2.4.2), which is the
+    /// concatenation of the parts of `ad`. Nothing is absorbed when it is
+    /// empty, but the domain separation bit is always flipped.
+    fn absorb_ad(&mut self, variant: &Variant, ad: &[&[u8]]) {
+        if aead::ad_len(ad) != 0 {
+            let mut n = 0;
+            for part in ad {
+                for b in part.iter() {
+                    self.xor_byte(n, *b);
+                    n += 1;
+                    if n == variant.rate {
+                        self.permute(variant.rounds_b);
+                        n = 0;
+                    }
+                }
+            }
+            self.xor_byte(n, PADDING);
+            self.permute(variant.rounds_b);
+        }
+        self.x[4] ^= 1;
+    }
+
+    /// Finalization (Section 2.4.4).
+    fn finalize(&mut self, variant: &Variant, key: &[u64; 2])
+                -> [u8; aead::TAG_LEN] {
+        let i = variant.rate / 8;
+        self.x[i] ^= key[0];
+        self.x[i + 1] ^= key[1];
+        self.permute(ROUNDS_A);
+        let mut tag = [0u8; aead::TAG_LEN];
+        tag[..8].copy_from_slice(
+            &polyfill::slice::be_u8_from_u64(self.x[3] ^ key[0]));
+        tag[8..].copy_from_slice(
+            &polyfill::slice::be_u8_from_u64(self.x[4] ^ key[1]));
+        tag
+    }
+
+    #[inline]
+    fn byte(&self, i: usize) -> u8 {
+        (self.x[i / 8] >> byte_shift(i)) as u8
+    }
+
+    #[inline]
+    fn xor_byte(&mut self, i: usize, b: u8) {
+        self.x[i / 8] ^= (b as u64) << byte_shift(i);
+    }
+
+    #[inline]
+    fn set_byte(&mut self, i: usize, b: u8) {
+        let shift = byte_shift(i);
+        self.x[i / 8] = (self.x[i / 8] & !(0xff << shift)) |
+                        ((b as u64) << shift);
+    }
+
+    /// The permutation p^rounds (Section 2.6), which is the last `rounds`
+    /// rounds of the 12-round permutation.
+    fn permute(&mut self, rounds: usize) {
+        let x = &mut self.x;
+        for r in (ROUNDS_A - rounds)..ROUNDS_A {
+            // Addition of constants.
+            x[2] ^= ((0xf - r as u64) << 4) | (r as u64);
+
+            // Substitution layer, bitsliced.
+            x[0] ^= x[4];
+            x[4] ^= x[3];
+            x[2] ^= x[1];
+            let t0 = !x[0] & x[1];
+            let t1 = !x[1] & x[2];
+            let t2 = !x[2] & x[3];
+            let t3 = !x[3] & x[4];
+            let t4 = !x[4] & x[0];
+            x[0] ^= t1;
+            x[1] ^= t2;
+            x[2] ^= t3;
+            x[3] ^= t4;
+            x[4] ^= t0;
+            x[1] ^= x[0];
+            x[0] ^= x[4];
+            x[3] ^= x[2];
+            x[2] = !x[2];
+
+            // Linear diffusion layer.
+            x[0] ^= x[0].rotate_right(19) ^ x[0].rotate_right(28);
+            x[1] ^= x[1].rotate_right(61) ^ x[1].rotate_right(39);
+            x[2] ^= x[2].rotate_right(1) ^ x[2].rotate_right(6);
+            x[3] ^= x[3].rotate_right(10) ^ x[3].rotate_right(17);
+            x[4] ^= x[4].rotate_right(7) ^ x[4].rotate_right(41);
+        }
+    }
+}
+
+#[inline]
+fn byte_shift(i: usize) -> u32 { (56 - (8 * (i % 8))) as u32 }
+
+/// The big-endian value of the first eight bytes of `bytes`.
+#[inline]
+fn be_u64(bytes: &[u8]) -> u64 {
+    polyfill::slice::u64_from_be_u8(
+        slice_as_array_ref!(&bytes[..8], 8).unwrap())
+}
+
+const ROUNDS_A: usize = 12;
+
+const PADDING: u8 = 0x80;
+
+const KEY_LEN: usize = 128 / 8;
+const NONCE_LEN: usize = 128 / 8;
+
+
+#[cfg(test)]
+mod tests {
+    use aead;
+    use super::super::tests::test_aead;
+
+    #[test]
+    pub fn test_ascon_128() {
+        test_aead(&aead::ASCON_128, "src/aead/ascon_128_tests.txt");
+    }
+
+    #[test]
+    pub fn test_ascon_128a() {
+        test_aead(&aead::ASCON_128A, "src/aead/ascon_128a_tests.txt");
+    }
+}
@@ -24,8 +24,30 @@ pub static CHACHA20_POLY1305: aead::Algorithm = aead::Algorithm {
     init: chacha20_poly1305_init,
     seal: chacha20_poly1305_seal,
     open: chacha20_poly1305_open,
+    nonce_len: chacha::NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
 };
 
+/// XChaCha20-Poly1305 as described in [draft-irtf-cfrg-xchacha-03].
+///
+/// The keys are 256 bits long and the nonces are 192 bits long. The nonces
+/// are long enough that they may be generated randomly for every message.
+///
+/// [draft-irtf-cfrg-xchacha-03]:
+///     https://tools.ietf.org/html/draft-irtf-cfrg-xchacha-03
+pub static XCHACHA20_POLY1305: aead::Algorithm = aead::Algorithm {
+    key_len: chacha::KEY_LEN_IN_BYTES,
+    init: chacha20_poly1305_init,
+    seal: xchacha20_poly1305_seal,
+    open: xchacha20_poly1305_open,
+    nonce_len: XCHACHA20_NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+const XCHACHA20_NONCE_LEN: usize = 192 / 8;
+
 /// Copies |key| into |ctx_buf|.
 pub fn chacha20_poly1305_init(ctx_buf: &mut [u8], key: &[u8])
                               -> Result<(), error::Unspecified> {
@@ -34,32 +56,84 @@ pub fn chacha20_poly1305_init(ctx_buf: &mut [u8], key: &[u8])
 }
 
 fn chacha20_poly1305_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
-                          nonce: &[u8; aead::NONCE_LEN], in_out: &mut [u8],
-                          tag_out: &mut [u8; aead::TAG_LEN], ad: &[u8])
+                          nonce: &[u8], in_out: &mut [u8],
+                          tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
                           -> Result<(), error::Unspecified> {
     let chacha20_key = try!(ctx_as_key(ctx));
-    let mut counter = chacha::make_counter(nonce, 1);
-    chacha::chacha20_xor_in_place(&chacha20_key, &counter, in_out);
-    counter[0] = 0;
-    aead_poly1305(tag_out, chacha20_key, &counter, ad, in_out);
+    let nonce = try!(slice_as_array_ref!(nonce, chacha::NONCE_LEN));
+    seal(chacha20_key, nonce, in_out, tag_out, ad);
     Ok(())
 }
 
 fn chacha20_poly1305_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
-                          nonce: &[u8; aead::NONCE_LEN], in_out: &mut [u8],
+                          nonce: &[u8], in_out: &mut [u8],
                           in_prefix_len: usize,
-                          tag_out: &mut [u8; aead::TAG_LEN], ad: &[u8])
+                          _received_tag: &[u8; aead::TAG_LEN],
+                          tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
                           -> Result<(), error::Unspecified> {
     let chacha20_key = try!(ctx_as_key(ctx));
+    let nonce = try!(slice_as_array_ref!(nonce, chacha::NONCE_LEN));
+    open(chacha20_key, nonce, in_out, in_prefix_len, tag_out, ad);
+    Ok(())
+}
+
+fn xchacha20_poly1305_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                           nonce: &[u8], in_out: &mut [u8],
+                           tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                           -> Result<(), error::Unspecified> {
+    let (subkey, nonce) = try!(xchacha20_subkey_and_nonce(ctx, nonce));
+    seal(&subkey, &nonce, in_out, tag_out, ad);
+    Ok(())
+}
+
+fn xchacha20_poly1305_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                           nonce: &[u8], in_out: &mut [u8],
+                           in_prefix_len: usize,
+                           _received_tag: &[u8; aead::TAG_LEN],
+                           tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                           -> Result<(), error::Unspecified> {
+    let (subkey, nonce) = try!(xchacha20_subkey_and_nonce(ctx, nonce));
+    open(&subkey, &nonce, in_out, in_prefix_len, tag_out, ad);
+    Ok(())
+}
+
+/// Derives the ChaCha20 subkey from the first 128 bits of the nonce using
+/// HChaCha20. The ChaCha20 nonce is four zero bytes followed by the
+/// remaining 64 bits of the XChaCha20 nonce.
+fn xchacha20_subkey_and_nonce(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                              nonce: &[u8])
+        -> Result<(chacha::Key, [u8; chacha::NONCE_LEN]),
+                  error::Unspecified> {
+    let chacha20_key = try!(ctx_as_key(ctx));
+    let nonce = try!(slice_as_array_ref!(nonce, XCHACHA20_NONCE_LEN));
+    let subkey = chacha::hchacha20(
+        chacha20_key,
+        try!(slice_as_array_ref!(&nonce[..chacha::HCHACHA20_NONCE_LEN],
+                                 chacha::HCHACHA20_NONCE_LEN)));
+    let mut chacha20_nonce = [0u8; chacha::NONCE_LEN];
+    chacha20_nonce[4..].copy_from_slice(&nonce[chacha::HCHACHA20_NONCE_LEN..]);
+    Ok((subkey, chacha20_nonce))
+}
+
+fn seal(chacha20_key: &chacha::Key, nonce: &[u8; chacha::NONCE_LEN],
+        in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]]) {
+    let mut counter = chacha::make_counter(nonce, 1);
+    chacha::chacha20_xor_in_place(chacha20_key, &counter, in_out);
+    counter[0] = 0;
+    aead_poly1305(tag_out, chacha20_key, &counter, ad, in_out);
+}
+
+fn open(chacha20_key: &chacha::Key, nonce: &[u8; chacha::NONCE_LEN],
+        in_out: &mut [u8], in_prefix_len: usize,
+        tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]]) {
     let mut counter = chacha::make_counter(nonce, 0);
     {
         let ciphertext = &in_out[in_prefix_len..];
         aead_poly1305(tag_out, chacha20_key, &counter, ad, ciphertext);
     }
     counter[0] = 1;
-    chacha::chacha20_xor_overlapping(&chacha20_key, &counter, in_out,
+    chacha::chacha20_xor_overlapping(chacha20_key, &counter, in_out,
                                      in_prefix_len);
-    Ok(())
 }
 
 fn ctx_as_key(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS])
@@ -70,14 +144,14 @@ fn ctx_as_key(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS])
 }
 
 fn aead_poly1305(tag_out: &mut [u8; aead::TAG_LEN], chacha20_key: &chacha::Key,
-                 counter: &chacha::Counter, ad: &[u8], ciphertext: &[u8]) {
+                 counter: &chacha::Counter, ad: &[&[u8]], ciphertext: &[u8]) {
     debug_assert_eq!(counter[0], 0);
-    let key = poly1305::Key::derive_using_chacha(chacha20_key, counter);
+    let key = chacha::derive_poly1305_key(chacha20_key, counter);
     let mut ctx = poly1305::SigningContext::from_key(key);
-    poly1305_update_padded_16(&mut ctx, ad);
+    aead::for_each_padded_block(ad.iter().cloned(), |block| ctx.update(block));
     poly1305_update_padded_16(&mut ctx, ciphertext);
     let lengths =
-        [polyfill::u64_from_usize(ad.len()).to_le(),
+        [aead::ad_len(ad).to_le(),
          polyfill::u64_from_usize(ciphertext.len()).to_le()];
     ctx.update(polyfill::slice::u64_as_u8(&lengths));
     ctx.sign(tag_out);
@@ -102,4 +176,10 @@ mod tests {
         aead::tests::test_aead(&aead::CHACHA20_POLY1305,
             "src/aead/chacha20_poly1305_tests.txt");
     }
+
+    #[test]
+    pub fn test_xchacha20_poly1305() {
+        aead::tests::test_aead(&aead::XCHACHA20_POLY1305,
+            "src/aead/xchacha20_poly1305_tests.txt");
+    }
 }
@@ -66,8 +66,7 @@ impl SealingKey {
         }
 
         counter[0] = 0;
-        let poly_key = poly1305::Key::derive_using_chacha(&self.key.k_2,
-                                                          &counter);
+        let poly_key = chacha::derive_poly1305_key(&self.key.k_2, &counter);
         poly1305::sign(poly_key, plaintext_in_ciphertext_out, tag_out);
     }
 }
@@ -116,8 +115,7 @@ impl OpeningKey {
         // We must verify the tag before decrypting so that
         // `ciphertext_in_plaintext_out` is unmodified if verification fails.
         // This is beyond what we guarantee.
-        let poly_key = poly1305::Key::derive_using_chacha(&self.key.k_2,
-                                                          &counter);
+        let poly_key = chacha::derive_poly1305_key(&self.key.k_2, &counter);
         try!(poly1305::verify(poly_key, ciphertext_in_plaintext_out, tag));
 
         let plaintext_in_ciphertext_out =
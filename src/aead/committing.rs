@@ -0,0 +1,252 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The CTX construction of [On Committing Authenticated-Encryption], which
+//! makes a ciphertext commit to the key, nonce, and additional data it was
+//! sealed with.
+//!
+//! AES-GCM and ChaCha20-Poly1305 aren't committing: an attacker can construct
+//! a ciphertext that opens successfully under two or more different keys.
+//! That breaks protocols that assume only the sender's key can open a message,
+//! e.g. message franking, and enables partitioning oracle attacks on
+//! password-based encryption. CTX replaces the tag `T` with
+//! `SHA-256(K||N||T||A)`, which is `COMMITTING_TAG_LEN` bytes long, so the
+//! ciphertext can't be opened with any other key, nonce, or additional data
+//! without a SHA-256 collision.
+//!
+//! [On Committing Authenticated-Encryption]:
+//!     https://eprint.iacr.org/2022/1260.pdf
+
+use {constant_time, core, digest, error};
+use super::{AES_128_GCM, AES_192_GCM, AES_256_GCM, Algorithm,
+            CHACHA20_POLY1305, Key, TAG_LEN, XCHACHA20_POLY1305,
+            check_nonce_len, check_per_nonce_max_bytes};
+
+/// The length of the tag of a `CommittingKey`.
+pub const COMMITTING_TAG_LEN: usize = 256 / 8;
+
+/// A key for sealing and opening with the CTX construction.
+///
+/// Like `LessSafeKey`, nothing stops a nonce from being used twice with it.
+pub struct CommittingKey {
+    key: Key,
+    key_bytes: [u8; MAX_KEY_LEN],
+}
+
+impl CommittingKey {
+    /// Constructs a new `CommittingKey`.
+    ///
+    /// `algorithm` must be `AES_128_GCM`, `AES_192_GCM`, `AES_256_GCM`,
+    /// `CHACHA20_POLY1305`, or `XCHACHA20_POLY1305`. CTX needs the tag to be
+    /// computed from the ciphertext, which the SIV constructions can't do.
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
+               -> Result<CommittingKey, error::Unspecified> {
+        if !SUPPORTED_ALGORITHMS.iter().any(|a| core::ptr::eq(*a, algorithm)) {
+            return Err(error::Unspecified);
+        }
+        let key = try!(Key::new(algorithm, key_bytes));
+        let mut committing_key = CommittingKey {
+            key: key,
+            key_bytes: [0; MAX_KEY_LEN],
+        };
+        committing_key.key_bytes[..key_bytes.len()].copy_from_slice(key_bytes);
+        Ok(committing_key)
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// Seals `in_out` like `seal_in_place`, except that the tag is
+    /// `COMMITTING_TAG_LEN` bytes long; `out_suffix_capacity` must be at
+    /// least that.
+    pub fn seal_in_place(&self, nonce: &[u8], in_out: &mut [u8],
+                         out_suffix_capacity: usize, ad: &[u8])
+                         -> Result<usize, error::Unspecified> {
+        if out_suffix_capacity < COMMITTING_TAG_LEN {
+            return Err(error::Unspecified);
+        }
+        let algorithm = self.key.algorithm;
+        try!(check_nonce_len(algorithm, nonce));
+        let in_out_len =
+            try!(in_out.len().checked_sub(out_suffix_capacity)
+                             .ok_or(error::Unspecified));
+        try!(check_per_nonce_max_bytes(in_out_len));
+        let (in_out, tag_out) = in_out.split_at_mut(in_out_len);
+        let mut tag = [0u8; TAG_LEN];
+        try!((algorithm.seal)(&self.key.ctx_buf, nonce, in_out, &mut tag,
+                              &[ad]));
+        let committing_tag = self.committing_tag(nonce, &tag, ad);
+        tag_out[..COMMITTING_TAG_LEN]
+            .copy_from_slice(committing_tag.as_ref());
+        Ok(in_out_len + COMMITTING_TAG_LEN)
+    }
+
+    /// Opens `in_out` like `open_in_place`, except that the tag is
+    /// `COMMITTING_TAG_LEN` bytes long.
+    pub fn open_in_place(&self, nonce: &[u8], in_prefix_len: usize,
+                         in_out: &mut [u8], ad: &[u8])
+                         -> Result<usize, error::Unspecified> {
+        let algorithm = self.key.algorithm;
+        try!(check_nonce_len(algorithm, nonce));
+        let ciphertext_and_tag_len =
+            try!(in_out.len().checked_sub(in_prefix_len)
+                             .ok_or(error::Unspecified));
+        let ciphertext_len =
+            try!(ciphertext_and_tag_len.checked_sub(COMMITTING_TAG_LEN)
+                                       .ok_or(error::Unspecified));
+        try!(check_per_nonce_max_bytes(ciphertext_len));
+        let (in_out, received_tag) =
+            in_out.split_at_mut(in_prefix_len + ciphertext_len);
+        // None of the supported algorithms use the received tag to decrypt.
+        let mut calculated_tag = [0u8; TAG_LEN];
+        try!((algorithm.open)(&self.key.ctx_buf, nonce, in_out, in_prefix_len,
+                              &[0u8; TAG_LEN], &mut calculated_tag, &[ad]));
+        let committing_tag = self.committing_tag(nonce, &calculated_tag, ad);
+        if constant_time::verify_slices_are_equal(committing_tag.as_ref(),
+                                                  received_tag).is_err() {
+            for b in &mut in_out[..ciphertext_len] {
+                *b = 0;
+            }
+            return Err(error::Unspecified);
+        }
+        Ok(ciphertext_len)
+    }
+
+    /// Computes `SHA-256(K||N||T||A)`. Everything but `A` has a fixed length
+    /// for a given algorithm, so the encoding is unambiguous.
+    fn committing_tag(&self, nonce: &[u8], tag: &[u8; TAG_LEN], ad: &[u8])
+                      -> digest::Digest {
+        let algorithm = self.key.algorithm;
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(&self.key_bytes[..algorithm.key_len]);
+        ctx.update(nonce);
+        ctx.update(&tag[..algorithm.tag_len]);
+        ctx.update(ad);
+        ctx.finish()
+    }
+}
+
+static SUPPORTED_ALGORITHMS: [&'static Algorithm; 5] = [
+    &AES_128_GCM,
+    &AES_192_GCM,
+    &AES_256_GCM,
+    &CHACHA20_POLY1305,
+    &XCHACHA20_POLY1305,
+];
+
+const MAX_KEY_LEN: usize = 256 / 8;
+
+
+#[cfg(test)]
+mod tests {
+    use {aead, core};
+    use super::*;
+
+    static ALGORITHMS: [&'static aead::Algorithm; 5] = [
+        &aead::AES_128_GCM,
+        &aead::AES_192_GCM,
+        &aead::AES_256_GCM,
+        &aead::CHACHA20_POLY1305,
+        &aead::XCHACHA20_POLY1305,
+    ];
+
+    #[test]
+    fn test_committing_round_trip() {
+        for algorithm in ALGORITHMS.iter() {
+            let key_bytes = vec![0x42; algorithm.key_len()];
+            let nonce = vec![0x24; algorithm.nonce_len()];
+            let key = CommittingKey::new(algorithm, &key_bytes).unwrap();
+            assert!(core::ptr::eq(key.algorithm(), *algorithm));
+
+            for len in &[0, 1, 15, 16, 17, 100] {
+                let plaintext = vec![0x5a; *len];
+                let mut in_out = plaintext.clone();
+                in_out.extend_from_slice(&[0; COMMITTING_TAG_LEN]);
+                assert!(key.seal_in_place(&nonce, &mut in_out,
+                                          COMMITTING_TAG_LEN - 1, b"ad")
+                           .is_err());
+                assert_eq!(key.seal_in_place(&nonce, &mut in_out,
+                                             COMMITTING_TAG_LEN, b"ad"),
+                           Ok(*len + COMMITTING_TAG_LEN));
+                let sealed = in_out.clone();
+
+                // The ciphertext is the same as the underlying AEAD's.
+                let mut expected = plaintext.clone();
+                expected.extend_from_slice(&[0; aead::MAX_OVERHEAD_LEN]);
+                let less_safe_key = aead::LessSafeKey::new(algorithm,
+                                                           &key_bytes)
+                    .unwrap();
+                assert!(aead::seal_in_place(&less_safe_key, &nonce,
+                                            &mut expected,
+                                            aead::MAX_OVERHEAD_LEN, b"ad")
+                            .is_ok());
+                assert_eq!(&sealed[..*len], &expected[..*len]);
+
+                let mut prefixed = vec![0xff; 3];
+                prefixed.extend_from_slice(&sealed);
+                assert_eq!(key.open_in_place(&nonce, 3, &mut prefixed, b"ad"),
+                           Ok(*len));
+                assert_eq!(&prefixed[..*len], &plaintext[..]);
+
+                // Any change to the ciphertext, tag, or additional data is
+                // detected, and the output is zeroed.
+                for i in 0..sealed.len() {
+                    let mut in_out = sealed.clone();
+                    in_out[i] ^= 1;
+                    assert!(key.open_in_place(&nonce, 0, &mut in_out, b"ad")
+                               .is_err());
+                    assert!(in_out[..*len].iter().all(|b| *b == 0));
+                }
+                let mut in_out = sealed.clone();
+                assert!(key.open_in_place(&nonce, 0, &mut in_out, b"aD")
+                           .is_err());
+
+                let mut in_out = sealed.clone();
+                assert!(key.open_in_place(&nonce, 0,
+                                          &mut in_out[..(COMMITTING_TAG_LEN
+                                                         - 1)],
+                                          b"ad").is_err());
+            }
+        }
+    }
+
+    // Opening with a different key fails even though the underlying AEAD's
+    // ciphertext and nonce are unchanged.
+    #[test]
+    fn test_committing_other_key() {
+        for algorithm in ALGORITHMS.iter() {
+            let nonce = vec![0; algorithm.nonce_len()];
+            let key_a = CommittingKey::new(algorithm,
+                                           &vec![1; algorithm.key_len()])
+                .unwrap();
+            let key_b = CommittingKey::new(algorithm,
+                                           &vec![2; algorithm.key_len()])
+                .unwrap();
+            let mut in_out = vec![0; 16 + COMMITTING_TAG_LEN];
+            assert!(key_a.seal_in_place(&nonce, &mut in_out,
+                                        COMMITTING_TAG_LEN, b"").is_ok());
+            assert!(key_b.open_in_place(&nonce, 0, &mut in_out, b"").is_err());
+        }
+    }
+
+    #[test]
+    fn test_committing_unsupported_algorithms() {
+        assert!(CommittingKey::new(&aead::AES_128_GCM_SIV, &[0; 16]).is_err());
+        assert!(CommittingKey::new(&aead::AES_128_SIV_CMAC, &[0; 32])
+                    .is_err());
+        assert!(CommittingKey::new(&aead::AES_128_GCM, &[0; 15]).is_err());
+    }
+}
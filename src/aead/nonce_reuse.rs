@@ -0,0 +1,261 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! Detection of nonce reuse, for testing and fuzzing.
+//!
+//! Sealing two messages with the same key and nonce is the most damaging
+//! mistake that can be made with an AEAD, and nothing about the ciphertexts
+//! shows that it has happened. The wrappers here remember the most recent
+//! nonces used with a key and panic when one of them is used again, so that
+//! the mistake shows up as a crash during development or fuzzing.
+//!
+//! They only remember a bounded number of nonces, so they can't prove that a
+//! nonce is never reused, and they cost a hash table lookup per message. They
+//! are only available with the `nonce_reuse_detection` feature, which is not
+//! meant to be enabled in production builds.
+
+use {error, std};
+use super::{Algorithm, LessSafeKey, MAX_NONCE_LEN, Nonce, NonceSequence};
+
+/// A record of the `capacity` most recently used nonces.
+pub struct NonceReuseDetector {
+    seen: std::collections::HashSet<SeenNonce>,
+    order: std::collections::VecDeque<SeenNonce>,
+    capacity: usize,
+}
+
+type SeenNonce = (usize, [u8; MAX_NONCE_LEN]);
+
+impl NonceReuseDetector {
+    /// Constructs a new `NonceReuseDetector` that remembers up to `capacity`
+    /// nonces, forgetting the oldest one when it is full.
+    pub fn new(capacity: usize) -> NonceReuseDetector {
+        NonceReuseDetector {
+            seen: std::collections::HashSet::with_capacity(capacity),
+            order: std::collections::VecDeque::with_capacity(capacity),
+            capacity: capacity,
+        }
+    }
+
+    /// Records a use of `nonce`, failing if it is one of the remembered
+    /// nonces. Nonces of different lengths are always different.
+    pub fn check(&mut self, nonce: &[u8]) -> Result<(), error::Unspecified> {
+        if nonce.len() > MAX_NONCE_LEN {
+            return Err(error::Unspecified);
+        }
+        let mut seen_nonce = (nonce.len(), [0u8; MAX_NONCE_LEN]);
+        seen_nonce.1[..nonce.len()].copy_from_slice(nonce);
+        if self.seen.contains(&seen_nonce) {
+            return Err(error::Unspecified);
+        }
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                let _ = self.seen.remove(&oldest);
+            }
+        }
+        let _ = self.seen.insert(seen_nonce);
+        self.order.push_back(seen_nonce);
+        Ok(())
+    }
+}
+
+/// A `NonceSequence` that panics when `nonce_sequence` returns a nonce that
+/// it returned recently.
+///
+/// Use it with a `SealingKey` to check a custom `NonceSequence`.
+pub struct DetectNonceReuse<N: NonceSequence> {
+    nonce_sequence: N,
+    detector: NonceReuseDetector,
+}
+
+impl<N: NonceSequence> DetectNonceReuse<N> {
+    /// Wraps `nonce_sequence`, remembering up to `capacity` of its nonces.
+    pub fn new(nonce_sequence: N, capacity: usize) -> DetectNonceReuse<N> {
+        DetectNonceReuse {
+            nonce_sequence: nonce_sequence,
+            detector: NonceReuseDetector::new(capacity),
+        }
+    }
+}
+
+impl<N: NonceSequence> NonceSequence for DetectNonceReuse<N> {
+    fn advance(&mut self) -> Result<Nonce, error::Unspecified> {
+        let nonce = try!(self.nonce_sequence.advance());
+        if self.detector.check(nonce.as_ref()).is_err() {
+            panic!("NonceSequence returned a nonce that it already returned");
+        }
+        Ok(nonce)
+    }
+}
+
+/// A `LessSafeKey` that panics when it is asked to seal with a nonce that it
+/// sealed with recently.
+///
+/// Only sealing is checked; opening the same message twice is harmless.
+pub struct NonceReuseDetectingKey {
+    key: LessSafeKey,
+    detector: NonceReuseDetector,
+}
+
+impl NonceReuseDetectingKey {
+    /// Constructs a new key that remembers up to `capacity` nonces.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8],
+               capacity: usize)
+               -> Result<NonceReuseDetectingKey, error::Unspecified> {
+        Ok(NonceReuseDetectingKey {
+            key: try!(LessSafeKey::new(algorithm, key_bytes)),
+            detector: NonceReuseDetector::new(capacity),
+        })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// The wrapped key, for opening.
+    #[inline(always)]
+    pub fn key(&self) -> &LessSafeKey { &self.key }
+
+    /// Like `aead::seal_in_place`, except that it panics if `nonce` is one of
+    /// the remembered nonces.
+    pub fn seal_in_place(&mut self, nonce: &[u8], in_out: &mut [u8],
+                         out_suffix_capacity: usize, ad: &[u8])
+                         -> Result<usize, error::Unspecified> {
+        self.check(nonce);
+        super::seal_in_place(&self.key, nonce, in_out, out_suffix_capacity, ad)
+    }
+
+    /// Like `aead::seal_in_place_append_tag`, except that it panics if
+    /// `nonce` is one of the remembered nonces.
+    pub fn seal_in_place_append_tag(&mut self, nonce: &[u8],
+                                    in_out: &mut std::vec::Vec<u8>, ad: &[u8])
+                                    -> Result<(), error::Unspecified> {
+        self.check(nonce);
+        super::seal_in_place_append_tag(&self.key, nonce, in_out, ad)
+    }
+
+    fn check(&mut self, nonce: &[u8]) {
+        // Nonces of the wrong length are rejected by the key instead.
+        if nonce.len() == self.key.algorithm().nonce_len() &&
+           self.detector.check(nonce).is_err() {
+            panic!("A nonce was used twice with the same key");
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {aead, error};
+    use super::*;
+
+    #[test]
+    fn test_nonce_reuse_detector() {
+        let mut detector = NonceReuseDetector::new(2);
+        assert!(detector.check(&[1]).is_ok());
+        assert!(detector.check(&[1, 0]).is_ok());
+        assert!(detector.check(&[1]).is_err());
+        assert!(detector.check(&[1, 0]).is_err());
+
+        // The oldest nonce is forgotten.
+        assert!(detector.check(&[2]).is_ok());
+        assert!(detector.check(&[1]).is_ok());
+        assert!(detector.check(&[2]).is_err());
+
+        assert!(detector.check(&[0; MAX_NONCE_LEN + 1]).is_err());
+
+        let mut detector = NonceReuseDetector::new(0);
+        assert!(detector.check(&[1]).is_ok());
+        assert!(detector.check(&[1]).is_ok());
+    }
+
+    struct RepeatingNonceSequence {
+        count: u8,
+    }
+
+    impl NonceSequence for RepeatingNonceSequence {
+        fn advance(&mut self) -> Result<Nonce, error::Unspecified> {
+            self.count += 1;
+            Nonce::assume_unique_for_key(&[self.count % 3; aead::NONCE_LEN])
+        }
+    }
+
+    #[test]
+    fn test_detect_nonce_reuse_unique() {
+        let mut key = aead::SealingKey::new(
+            &aead::AES_128_GCM, &[0; 16],
+            DetectNonceReuse::new(RepeatingNonceSequence { count: 0 }, 3))
+            .unwrap();
+        let mut in_out = [0; aead::MAX_OVERHEAD_LEN];
+        for _ in 0..3 {
+            assert!(key.seal_in_place(&mut in_out, aead::MAX_OVERHEAD_LEN, b"")
+                       .is_ok());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_detect_nonce_reuse_repeated() {
+        let mut key = aead::SealingKey::new(
+            &aead::AES_128_GCM, &[0; 16],
+            DetectNonceReuse::new(RepeatingNonceSequence { count: 0 }, 3))
+            .unwrap();
+        let mut in_out = [0; aead::MAX_OVERHEAD_LEN];
+        for _ in 0..4 {
+            let _ = key.seal_in_place(&mut in_out, aead::MAX_OVERHEAD_LEN,
+                                      b"");
+        }
+    }
+
+    #[test]
+    fn test_nonce_reuse_detecting_key_unique() {
+        let mut key =
+            NonceReuseDetectingKey::new(&aead::CHACHA20_POLY1305, &[0; 32],
+                                        16).unwrap();
+        let mut in_out = [0; aead::MAX_OVERHEAD_LEN];
+        for i in 0..3 {
+            let nonce = [i; aead::NONCE_LEN];
+            assert!(key.seal_in_place(&nonce, &mut in_out,
+                                      aead::MAX_OVERHEAD_LEN, b"").is_ok());
+            assert!(aead::open_in_place(key.key(), &nonce, 0, &mut in_out,
+                                        b"").is_ok());
+        }
+
+        // A nonce that the key rejects isn't remembered.
+        assert!(key.seal_in_place(&[0; 11], &mut in_out,
+                                  aead::MAX_OVERHEAD_LEN, b"").is_err());
+        let mut in_out = vec![1, 2, 3];
+        assert!(key.seal_in_place_append_tag(&[0; 11], &mut in_out, b"")
+                   .is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_nonce_reuse_detecting_key_repeated() {
+        let mut key =
+            NonceReuseDetectingKey::new(&aead::CHACHA20_POLY1305, &[0; 32],
+                                        16).unwrap();
+        let mut in_out = vec![];
+        let _ = key.seal_in_place_append_tag(&[1; aead::NONCE_LEN],
+                                             &mut in_out, b"");
+        let mut in_out = vec![];
+        let _ = key.seal_in_place_append_tag(&[1; aead::NONCE_LEN],
+                                             &mut in_out, b"");
+    }
+}
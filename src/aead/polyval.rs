@@ -0,0 +1,170 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! POLYVAL, as described in [RFC 8452 Section 3].
+//!
+//! The field multiplication is done with integer multiplications in which
+//! every fourth bit of each operand is masked out, so that the carries never
+//! reach the bits that are kept, as in BearSSL's `ghash_ctmul64`. This makes
+//! it constant-time without needing carry-less multiplication instructions.
+//!
+//! [RFC 8452 Section 3]: https://tools.ietf.org/html/rfc8452#section-3
+
+pub const BLOCK_LEN: usize = 16;
+
+pub type Block = [u8; BLOCK_LEN];
+
+pub struct Context {
+    h: [u64; 2],
+    s: [u64; 2],
+}
+
+impl Context {
+    pub fn new(key: &Block) -> Context {
+        Context {
+            h: elem_from_bytes(key),
+            s: [0, 0],
+        }
+    }
+
+    /// Processes `input`, padding the last partial block, if any, with
+    /// zeros.
+    pub fn update_padded(&mut self, input: &[u8]) {
+        for chunk in input.chunks(BLOCK_LEN) {
+            let mut block = [0u8; BLOCK_LEN];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    pub fn update_block(&mut self, block: &Block) {
+        let x = elem_from_bytes(block);
+        self.s[0] ^= x[0];
+        self.s[1] ^= x[1];
+        self.s = dot(&self.s, &self.h);
+    }
+
+    pub fn finish(self) -> Block {
+        let mut out = [0u8; BLOCK_LEN];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = (self.s[i / 8] >> (8 * (i % 8))) as u8;
+        }
+        out
+    }
+}
+
+fn elem_from_bytes(bytes: &Block) -> [u64; 2] {
+    [u64_from_le_u8(&bytes[..8]), u64_from_le_u8(&bytes[8..])]
+}
+
+fn u64_from_le_u8(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |acc, b| (acc << 8) | u64::from(*b))
+}
+
+// Returns a * b * x**-128 in POLYVAL's field.
+fn dot(a: &[u64; 2], b: &[u64; 2]) -> [u64; 2] {
+    let (a0, a1) = (a[0], a[1]);
+    let (b0, b1) = (b[0], b[1]);
+    let (a0r, a1r) = (rev64(a0), rev64(a1));
+    let (b0r, b1r) = (rev64(b0), rev64(b1));
+
+    // Karatsuba multiplication. `bmul64` gives the low half of each 128-bit
+    // product; the high half is computed from the bit-reversed inputs.
+    let z0 = bmul64(a0, b0);
+    let z1 = bmul64(a1, b1);
+    let z2 = bmul64(a0 ^ a1, b0 ^ b1) ^ z0 ^ z1;
+    let z0h = bmul64(a0r, b0r);
+    let z1h = bmul64(a1r, b1r);
+    let z2h = bmul64(a0r ^ a1r, b0r ^ b1r) ^ z0h ^ z1h;
+    let z0h = rev64(z0h) >> 1;
+    let z1h = rev64(z1h) >> 1;
+    let z2h = rev64(z2h) >> 1;
+
+    let v0 = z0;
+    let mut v1 = z0h ^ z2;
+    let mut v2 = z1 ^ z2h;
+    let mut v3 = z1h;
+
+    // Montgomery reduction by x**128 modulo
+    // x**128 + x**127 + x**126 + x**121 + 1.
+    v2 ^= v0 ^ (v0 >> 1) ^ (v0 >> 2) ^ (v0 >> 7);
+    v1 ^= (v0 << 63) ^ (v0 << 62) ^ (v0 << 57);
+    v3 ^= v1 ^ (v1 >> 1) ^ (v1 >> 2) ^ (v1 >> 7);
+    v2 ^= (v1 << 63) ^ (v1 << 62) ^ (v1 << 57);
+
+    [v2, v3]
+}
+
+// The low 64 bits of the carry-less product of `x` and `y`.
+fn bmul64(x: u64, y: u64) -> u64 {
+    const M0: u64 = 0x1111111111111111;
+    const M1: u64 = 0x2222222222222222;
+    const M2: u64 = 0x4444444444444444;
+    const M3: u64 = 0x8888888888888888;
+
+    let (x0, x1, x2, x3) = (x & M0, x & M1, x & M2, x & M3);
+    let (y0, y1, y2, y3) = (y & M0, y & M1, y & M2, y & M3);
+
+    let z0 = x0.wrapping_mul(y0) ^ x1.wrapping_mul(y3) ^
+             x2.wrapping_mul(y2) ^ x3.wrapping_mul(y1);
+    let z1 = x0.wrapping_mul(y1) ^ x1.wrapping_mul(y0) ^
+             x2.wrapping_mul(y3) ^ x3.wrapping_mul(y2);
+    let z2 = x0.wrapping_mul(y2) ^ x1.wrapping_mul(y1) ^
+             x2.wrapping_mul(y0) ^ x3.wrapping_mul(y3);
+    let z3 = x0.wrapping_mul(y3) ^ x1.wrapping_mul(y2) ^
+             x2.wrapping_mul(y1) ^ x3.wrapping_mul(y0);
+
+    (z0 & M0) | (z1 & M1) | (z2 & M2) | (z3 & M3)
+}
+
+// Reverses the order of the bits of `x`.
+fn rev64(x: u64) -> u64 {
+    let x = ((x & 0x5555555555555555) << 1) | ((x >> 1) & 0x5555555555555555);
+    let x = ((x & 0x3333333333333333) << 2) | ((x >> 2) & 0x3333333333333333);
+    let x = ((x & 0x0f0f0f0f0f0f0f0f) << 4) | ((x >> 4) & 0x0f0f0f0f0f0f0f0f);
+    x.swap_bytes()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    pub fn test_polyval() {
+        test::from_file("src/aead/polyval_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let h = test_case.consume_bytes("H");
+            let h = slice_as_array_ref!(&h, BLOCK_LEN).unwrap();
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Result");
+
+            let mut ctx = Context::new(&h);
+            ctx.update_padded(&input);
+            assert_eq!(&ctx.finish()[..], &expected[..]);
+
+            // Block-by-block processing gives the same result.
+            assert_eq!(input.len() % BLOCK_LEN, 0);
+            let mut ctx = Context::new(&h);
+            for block in input.chunks(BLOCK_LEN) {
+                ctx.update_block(slice_as_array_ref!(block, BLOCK_LEN)
+                                     .unwrap());
+            }
+            assert_eq!(&ctx.finish()[..], &expected[..]);
+
+            Ok(())
+        })
+    }
+}
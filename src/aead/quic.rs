@@ -0,0 +1,312 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! QUIC header protection, as described in [RFC 9001 Section 5.4].
+//!
+//! The packet payload is protected with one of the AEADs in `ring::aead`;
+//! this module only provides the masks that hide the packet number length
+//! and the packet number, and functions that apply them to a header.
+//!
+//! [RFC 9001 Section 5.4]: https://tools.ietf.org/html/rfc9001#section-5.4
+
+use {aes, chacha, error, polyfill};
+
+/// The length of the sample of ciphertext that a mask is derived from.
+pub const SAMPLE_LEN: usize = 16;
+
+/// The length of a header protection mask.
+pub const MASK_LEN: usize = 5;
+
+/// A header protection mask.
+pub type Mask = [u8; MASK_LEN];
+
+/// A QUIC header protection algorithm.
+pub struct Algorithm {
+    key_len: usize,
+    init: fn(ctx_buf: &mut [u8], key_bytes: &[u8])
+             -> Result<(), error::Unspecified>,
+    new_mask: fn(ctx: &[u64; KEY_CTX_BUF_ELEMS], sample: &[u8; SAMPLE_LEN])
+                 -> Mask,
+}
+
+impl Algorithm {
+    /// The length of the key.
+    #[inline(always)]
+    pub fn key_len(&self) -> usize { self.key_len }
+}
+
+/// AES-128 header protection, for use with `aead::AES_128_GCM` and
+/// `aead::AES_128_CCM`.
+pub static AES_128: Algorithm = Algorithm {
+    key_len: 128 / 8,
+    init: aes::init,
+    new_mask: aes_new_mask,
+};
+
+/// AES-256 header protection, for use with `aead::AES_256_GCM`.
+pub static AES_256: Algorithm = Algorithm {
+    key_len: 256 / 8,
+    init: aes::init,
+    new_mask: aes_new_mask,
+};
+
+/// ChaCha20 header protection, for use with `aead::CHACHA20_POLY1305`.
+pub static CHACHA20: Algorithm = Algorithm {
+    key_len: chacha::KEY_LEN_IN_BYTES,
+    init: chacha20_init,
+    new_mask: chacha20_new_mask,
+};
+
+fn aes_new_mask(ctx: &[u64; KEY_CTX_BUF_ELEMS], sample: &[u8; SAMPLE_LEN])
+                -> Mask {
+    let mut block = *sample;
+    aes::encrypt_blocks(polyfill::slice::u64_as_u8(ctx), &mut block);
+    let mut mask = [0u8; MASK_LEN];
+    mask.copy_from_slice(&block[..MASK_LEN]);
+    mask
+}
+
+/// Copies `key_bytes` into `ctx_buf`.
+fn chacha20_init(ctx_buf: &mut [u8], key_bytes: &[u8])
+                 -> Result<(), error::Unspecified> {
+    ctx_buf[..key_bytes.len()].copy_from_slice(key_bytes);
+    Ok(())
+}
+
+/// The first four bytes of the sample are the little-endian block counter
+/// and the rest is the nonce; the mask is the first five bytes of the key
+/// stream.
+fn chacha20_new_mask(ctx: &[u64; KEY_CTX_BUF_ELEMS],
+                     sample: &[u8; SAMPLE_LEN]) -> Mask {
+    let key_bytes = &polyfill::slice::u64_as_u8(ctx)
+        [..chacha::KEY_LEN_IN_BYTES];
+    let key = chacha::key_from_bytes(
+        slice_as_array_ref!(key_bytes, chacha::KEY_LEN_IN_BYTES).unwrap());
+    let (counter, nonce) = sample.split_at(4);
+    let counter = chacha::make_counter(
+        slice_as_array_ref!(nonce, chacha::NONCE_LEN).unwrap(),
+        polyfill::slice::u32_from_le_u8(
+            slice_as_array_ref!(counter, 4).unwrap()));
+    let mut mask = [0u8; MASK_LEN];
+    chacha::chacha20_xor_in_place(&key, &counter, &mut mask);
+    mask
+}
+
+/// A key for generating QUIC header protection masks.
+pub struct HeaderProtectionKey {
+    ctx_buf: [u64; KEY_CTX_BUF_ELEMS],
+    algorithm: &'static Algorithm,
+}
+
+impl HeaderProtectionKey {
+    /// Constructs a new `HeaderProtectionKey`.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len()` bytes long.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8])
+               -> Result<HeaderProtectionKey, error::Unspecified> {
+        if key_bytes.len() != algorithm.key_len {
+            return Err(error::Unspecified);
+        }
+        let mut key = HeaderProtectionKey {
+            ctx_buf: [0; KEY_CTX_BUF_ELEMS],
+            algorithm: algorithm,
+        };
+        try!((algorithm.init)(
+            polyfill::slice::u64_as_u8_mut(&mut key.ctx_buf), key_bytes));
+        Ok(key)
+    }
+
+    /// The key's algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+
+    /// Derives the mask for `sample`, which must be exactly `SAMPLE_LEN`
+    /// bytes of ciphertext taken as described in RFC 9001 Section 5.4.2.
+    pub fn new_mask(&self, sample: &[u8]) -> Result<Mask, error::Unspecified> {
+        let sample = try!(slice_as_array_ref!(sample, SAMPLE_LEN));
+        Ok((self.algorithm.new_mask)(&self.ctx_buf, sample))
+    }
+
+    /// Applies header protection to an unprotected header.
+    ///
+    /// `first` is the first byte of the header and `packet_number` starts at
+    /// the first byte of the packet number; it may extend past the end of the
+    /// packet number. The packet number length is taken from `first` before
+    /// it is masked, and is returned. Nothing is modified if `packet_number`
+    /// is shorter than that.
+    pub fn encrypt_in_place(&self, sample: &[u8], first: &mut u8,
+                            packet_number: &mut [u8])
+                            -> Result<usize, error::Unspecified> {
+        let mask = try!(self.new_mask(sample));
+        let pn_len = packet_number_len(*first);
+        if packet_number.len() < pn_len {
+            return Err(error::Unspecified);
+        }
+        *first ^= mask[0] & first_byte_mask_bits(*first);
+        xor_packet_number(&mask, &mut packet_number[..pn_len]);
+        Ok(pn_len)
+    }
+
+    /// Removes header protection from a protected header.
+    ///
+    /// `first` is the first byte of the header and `packet_number` starts at
+    /// the first byte of the packet number; since the packet number length
+    /// isn't known until `first` is unmasked, it should extend to the end of
+    /// the packet or to the maximum packet number length of four bytes. The
+    /// packet number length is returned. Nothing is modified if
+    /// `packet_number` is shorter than the packet number.
+    pub fn decrypt_in_place(&self, sample: &[u8], first: &mut u8,
+                            packet_number: &mut [u8])
+                            -> Result<usize, error::Unspecified> {
+        let mask = try!(self.new_mask(sample));
+        let unmasked_first = *first ^ (mask[0] & first_byte_mask_bits(*first));
+        let pn_len = packet_number_len(unmasked_first);
+        if packet_number.len() < pn_len {
+            return Err(error::Unspecified);
+        }
+        *first = unmasked_first;
+        xor_packet_number(&mask, &mut packet_number[..pn_len]);
+        Ok(pn_len)
+    }
+}
+
+/// The bits of the first byte that are protected: the four low bits for long
+/// headers and the five low bits for short headers.
+fn first_byte_mask_bits(first: u8) -> u8 {
+    if first & LONG_HEADER_FORM != 0 { 0x0f } else { 0x1f }
+}
+
+fn packet_number_len(unprotected_first: u8) -> usize {
+    ((unprotected_first & 0x03) as usize) + 1
+}
+
+fn xor_packet_number(mask: &Mask, packet_number: &mut [u8]) {
+    for (pn, m) in packet_number.iter_mut().zip(mask[1..].iter()) {
+        *pn ^= *m;
+    }
+}
+
+const LONG_HEADER_FORM: u8 = 0x80;
+
+const KEY_CTX_BUF_ELEMS: usize = (aes::KEY_CTX_BUF_LEN + 7) / 8;
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    fn test_quic_aes_128() {
+        test_new_mask(&AES_128, "src/aead/quic_aes_128_tests.txt");
+    }
+
+    #[test]
+    fn test_quic_aes_256() {
+        test_new_mask(&AES_256, "src/aead/quic_aes_256_tests.txt");
+    }
+
+    #[test]
+    fn test_quic_chacha20() {
+        test_new_mask(&CHACHA20, "src/aead/quic_chacha20_tests.txt");
+    }
+
+    fn test_new_mask(algorithm: &'static Algorithm, file_path: &str) {
+        test::from_file(file_path, |section, test_case| {
+            assert_eq!(section, "");
+            let key_bytes = test_case.consume_bytes("KEY");
+            let sample = test_case.consume_bytes("SAMPLE");
+            let mask = test_case.consume_bytes("MASK");
+
+            let key = HeaderProtectionKey::new(algorithm, &key_bytes).unwrap();
+            assert_eq!(&try!(key.new_mask(&sample))[..], &mask[..]);
+
+            assert!(key.new_mask(&sample[1..]).is_err());
+            assert!(HeaderProtectionKey::new(algorithm, &key_bytes[1..])
+                        .is_err());
+
+            Ok(())
+        });
+    }
+
+    // The client Initial packet of RFC 9001 Appendix A.2, which has a long
+    // header.
+    #[test]
+    fn test_quic_long_header() {
+        let key = HeaderProtectionKey::new(
+            &AES_128,
+            &[0x9f, 0x50, 0x44, 0x9e, 0x04, 0xa0, 0xe8, 0x10, 0x28, 0x3a,
+              0x1e, 0x99, 0x33, 0xad, 0xed, 0xd2]).unwrap();
+        let sample = [0xd1, 0xb1, 0xc9, 0x8d, 0xd7, 0x68, 0x9f, 0xb8, 0xec,
+                      0x11, 0xd2, 0x42, 0xb1, 0x23, 0xdc, 0x9b];
+        test_header(&key, &sample, 0xc3, &[0x00, 0x00, 0x00, 0x02],
+                    0xc0, &[0x7b, 0x9a, 0xec, 0x34]);
+    }
+
+    // The ChaCha20-Poly1305 short header packet of RFC 9001 Appendix A.5.
+    #[test]
+    fn test_quic_short_header() {
+        let key = HeaderProtectionKey::new(
+            &CHACHA20,
+            &[0x25, 0xa2, 0x82, 0xb9, 0xe8, 0x2f, 0x06, 0xf2, 0x1f, 0x48,
+              0x89, 0x17, 0xa4, 0xfc, 0x8f, 0x1b, 0x73, 0x57, 0x36, 0x85,
+              0x60, 0x85, 0x97, 0xd0, 0xef, 0xcb, 0x07, 0x6b, 0x0a, 0xb7,
+              0xa7, 0xa4]).unwrap();
+        let sample = [0x5e, 0x5c, 0xd5, 0x5c, 0x41, 0xf6, 0x90, 0x80, 0x57,
+                      0x5d, 0x79, 0x99, 0xc2, 0x5a, 0x5b, 0xfb];
+        test_header(&key, &sample, 0x42, &[0x00, 0xbf, 0xf4],
+                    0x4c, &[0xfe, 0x41, 0x89]);
+    }
+
+    fn test_header(key: &HeaderProtectionKey, sample: &[u8],
+                   unprotected_first: u8, unprotected_pn: &[u8],
+                   protected_first: u8, protected_pn: &[u8]) {
+        let pn_len = unprotected_pn.len();
+
+        // The packet number is followed by payload bytes that must not be
+        // touched.
+        let mut pn = [0xaa; 4 + 2];
+        pn[..pn_len].copy_from_slice(unprotected_pn);
+        let mut first = unprotected_first;
+        assert_eq!(key.encrypt_in_place(sample, &mut first, &mut pn),
+                   Ok(pn_len));
+        assert_eq!(first, protected_first);
+        assert_eq!(&pn[..pn_len], protected_pn);
+        assert!(pn[pn_len..].iter().all(|b| *b == 0xaa));
+
+        assert_eq!(key.decrypt_in_place(sample, &mut first, &mut pn),
+                   Ok(pn_len));
+        assert_eq!(first, unprotected_first);
+        assert_eq!(&pn[..pn_len], unprotected_pn);
+        assert!(pn[pn_len..].iter().all(|b| *b == 0xaa));
+
+        // Too-short packet numbers are rejected without modifying anything.
+        let mut first = unprotected_first;
+        let mut pn = unprotected_pn.to_vec();
+        assert!(key.encrypt_in_place(sample, &mut first,
+                                     &mut pn[..(pn_len - 1)]).is_err());
+        assert_eq!(first, unprotected_first);
+        assert_eq!(&pn[..], unprotected_pn);
+        let mut first = protected_first;
+        let mut pn = protected_pn.to_vec();
+        assert!(key.decrypt_in_place(sample, &mut first,
+                                     &mut pn[..(pn_len - 1)]).is_err());
+        assert_eq!(first, protected_first);
+        assert_eq!(&pn[..], protected_pn);
+
+        let mut first = unprotected_first;
+        assert!(key.encrypt_in_place(&sample[1..], &mut first, &mut pn)
+                   .is_err());
+    }
+}
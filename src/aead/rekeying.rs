@@ -0,0 +1,326 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! A symmetric key ratchet for long-lived sessions.
+//!
+//! A `RekeyingKey` protects one direction of a session. Records are numbered
+//! from zero, and every `records_per_epoch` records form an epoch with its
+//! own AEAD key. The key for each epoch and the chain key for the next epoch
+//! are derived from the current chain key, which is then forgotten, so
+//! compromising the state of a `RekeyingKey` does not reveal the records of
+//! earlier epochs. This is the symmetric ratchet of WireGuard and the Double
+//! Ratchet.
+//!
+//! For each epoch, with `chain_key` initially being the `chain_key` passed to
+//! `RekeyingKey::new`:
+//!
+//! ```ignore
+//! okm = HKDF-Expand-SHA256(PRK = chain_key, info = "ring rekeying",
+//!                          L = 32 + algorithm.key_len())
+//! chain_key = okm[..32]
+//! epoch_key = okm[32..]
+//! ```
+//!
+//! Record `n` is sealed with that epoch's key and the nonce `n`, encoded as a
+//! big-endian integer `algorithm.nonce_len()` bytes long.
+
+use {core, digest, error, hkdf, polyfill};
+use super::{Algorithm, Key, MAX_NONCE_LEN};
+
+/// The length of a `RekeyingKey`'s chain key.
+pub const CHAIN_KEY_LEN: usize = 256 / 8;
+
+/// A key that derives a new AEAD key every `records_per_epoch` records.
+///
+/// Records must be sealed in order, but they may be opened in any order as
+/// long as they are from the current epoch or the next one. A `RekeyingKey`
+/// doesn't detect replayed records; the caller must do that if it matters.
+pub struct RekeyingKey {
+    key: Key,
+    chain_key: [u8; CHAIN_KEY_LEN],
+    epoch: u64,
+    records_per_epoch: u64,
+    next_record_number: u64,
+}
+
+impl RekeyingKey {
+    /// Constructs a new `RekeyingKey` in epoch zero.
+    ///
+    /// `chain_key` must be exactly `CHAIN_KEY_LEN` bytes long and should be a
+    /// secret shared by both ends of the session, e.g. the output of a key
+    /// exchange. `records_per_epoch` must be at least one and at most
+    /// `algorithm.confidentiality_limit()`, and `algorithm.nonce_len()` must
+    /// be at least eight so that every record number has a distinct nonce.
+    pub fn new(algorithm: &'static Algorithm, chain_key: &[u8],
+               records_per_epoch: u64)
+               -> Result<RekeyingKey, error::Unspecified> {
+        if chain_key.len() != CHAIN_KEY_LEN || records_per_epoch == 0 ||
+           records_per_epoch > algorithm.confidentiality_limit() ||
+           algorithm.nonce_len() < 8 {
+            return Err(error::Unspecified);
+        }
+        let (key, next_chain_key) = try!(ratchet(algorithm, chain_key));
+        Ok(RekeyingKey {
+            key: key,
+            chain_key: next_chain_key,
+            epoch: 0,
+            records_per_epoch: records_per_epoch,
+            next_record_number: 0,
+        })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// The current epoch.
+    #[inline(always)]
+    pub fn epoch(&self) -> u64 { self.epoch }
+
+    /// The number of the record that the next call to `seal_in_place` will
+    /// seal. It must be sent along with the record so that the receiver can
+    /// pass it to `open_in_place`.
+    #[inline(always)]
+    pub fn next_record_number(&self) -> u64 { self.next_record_number }
+
+    /// Seals the next record like `seal_in_place`, moving to the next epoch
+    /// first if the current one is finished.
+    ///
+    /// The record is numbered `next_record_number()`.
+    pub fn seal_in_place(&mut self, in_out: &mut [u8],
+                         out_suffix_capacity: usize, ad: &[u8])
+                         -> Result<usize, error::Unspecified> {
+        let record_number = self.next_record_number;
+        // Record number `u64::max_value()` is never used so that
+        // `next_record_number` can't overflow.
+        if record_number == core::u64::MAX {
+            return Err(error::Unspecified);
+        }
+        let epoch = record_number / self.records_per_epoch;
+        if epoch != self.epoch {
+            let (key, next_chain_key) =
+                try!(ratchet(self.key.algorithm(), &self.chain_key));
+            self.commit(key, next_chain_key);
+        }
+        let nonce = nonce(self.key.algorithm(), record_number);
+        let nonce = &nonce[..self.key.algorithm().nonce_len()];
+        let out_len = try!(self.key.seal_in_place(nonce, in_out,
+                                                  out_suffix_capacity, &[ad]));
+        self.next_record_number = record_number + 1;
+        Ok(out_len)
+    }
+
+    /// Opens record number `record_number` like `open_in_place`.
+    ///
+    /// A record from the next epoch moves the key to that epoch, but only if
+    /// it is authentic. Records from earlier epochs can no longer be opened.
+    pub fn open_in_place(&mut self, record_number: u64, in_prefix_len: usize,
+                         in_out: &mut [u8], ad: &[u8])
+                         -> Result<usize, error::Unspecified> {
+        let algorithm = self.key.algorithm();
+        let epoch = record_number / self.records_per_epoch;
+        let nonce = nonce(algorithm, record_number);
+        let nonce = &nonce[..algorithm.nonce_len()];
+        if epoch == self.epoch {
+            return self.key.open_in_place(nonce, in_prefix_len, in_out, &[ad]);
+        }
+        if epoch != self.epoch + 1 {
+            return Err(error::Unspecified);
+        }
+        let (key, next_chain_key) = try!(ratchet(algorithm, &self.chain_key));
+        let plaintext_len =
+            try!(key.open_in_place(nonce, in_prefix_len, in_out, &[ad]));
+        self.commit(key, next_chain_key);
+        Ok(plaintext_len)
+    }
+
+    fn commit(&mut self, key: Key, next_chain_key: [u8; CHAIN_KEY_LEN]) {
+        self.key = key;
+        self.chain_key = next_chain_key;
+        self.epoch += 1;
+    }
+}
+
+fn ratchet(algorithm: &'static Algorithm, chain_key: &[u8])
+           -> Result<(Key, [u8; CHAIN_KEY_LEN]), error::Unspecified> {
+    let prk = hkdf::Prk::new_less_safe(&digest::SHA256, chain_key);
+    let mut okm = [0u8; CHAIN_KEY_LEN + MAX_KEY_LEN];
+    let okm = &mut okm[..(CHAIN_KEY_LEN + algorithm.key_len())];
+    try!(try!(prk.expand(&[INFO], RatchetOutput(okm.len()))).fill(okm));
+    let (next_chain_key_bytes, key_bytes) = okm.split_at(CHAIN_KEY_LEN);
+    let key = try!(Key::new(algorithm, key_bytes));
+    let mut next_chain_key = [0u8; CHAIN_KEY_LEN];
+    next_chain_key.copy_from_slice(next_chain_key_bytes);
+    Ok((key, next_chain_key))
+}
+
+// The output of a ratchet step: the next chain key followed by the key.
+struct RatchetOutput(usize);
+
+impl hkdf::KeyType for RatchetOutput {
+    fn len(&self) -> usize { self.0 }
+}
+
+fn nonce(algorithm: &Algorithm, record_number: u64) -> [u8; MAX_NONCE_LEN] {
+    let mut nonce = [0u8; MAX_NONCE_LEN];
+    let nonce_len = algorithm.nonce_len();
+    nonce[(nonce_len - 8)..nonce_len]
+        .copy_from_slice(&polyfill::slice::be_u8_from_u64(record_number));
+    nonce
+}
+
+const INFO: &'static [u8] = b"ring rekeying";
+
+// The longest `key_len` of any AEAD algorithm, AES-256-SIV's.
+const MAX_KEY_LEN: usize = 512 / 8;
+
+#[cfg(test)]
+mod tests {
+    use {aead, digest, error, hkdf, std};
+    use super::*;
+
+    fn seal(key: &mut RekeyingKey, plaintext: &[u8])
+            -> (u64, [u8; 4 + aead::MAX_OVERHEAD_LEN]) {
+        let record_number = key.next_record_number();
+        let mut in_out = [0u8; 4 + aead::MAX_OVERHEAD_LEN];
+        in_out[..4].copy_from_slice(plaintext);
+        let out_len =
+            key.seal_in_place(&mut in_out, aead::MAX_OVERHEAD_LEN, b"ad")
+               .unwrap();
+        assert_eq!(out_len, 4 + key.algorithm().tag_len);
+        (record_number, in_out)
+    }
+
+    fn open(key: &mut RekeyingKey, record_number: u64, record: &[u8])
+            -> Result<[u8; 4], error::Unspecified> {
+        let mut in_out = [0u8; 4 + aead::MAX_OVERHEAD_LEN];
+        let record_len = 4 + key.algorithm().tag_len;
+        in_out[..record_len].copy_from_slice(&record[..record_len]);
+        let plaintext_len =
+            try!(key.open_in_place(record_number, 0,
+                                   &mut in_out[..record_len], b"ad"));
+        assert_eq!(plaintext_len, 4);
+        let mut plaintext = [0u8; 4];
+        plaintext.copy_from_slice(&in_out[..4]);
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_rekeying_key_round_trip() {
+        let chain_key = [7u8; CHAIN_KEY_LEN];
+        for algorithm in [&aead::AES_128_GCM, &aead::AES_256_GCM,
+                          &aead::CHACHA20_POLY1305].iter() {
+            let mut sealer = RekeyingKey::new(algorithm, &chain_key, 3)
+                .unwrap();
+            let mut opener = RekeyingKey::new(algorithm, &chain_key, 3)
+                .unwrap();
+            for i in 0..10u8 {
+                let (record_number, record) = seal(&mut sealer, &[i; 4]);
+                assert_eq!(record_number, i as u64);
+                assert_eq!(sealer.epoch(), record_number / 3);
+                assert_eq!(open(&mut opener, record_number, &record).unwrap(),
+                           [i; 4]);
+                assert_eq!(opener.epoch(), sealer.epoch());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rekeying_key_derivation() {
+        // Epoch 1 is sealed with the second key derived from the chain.
+        let chain_key = [1u8; CHAIN_KEY_LEN];
+        let mut sealer =
+            RekeyingKey::new(&aead::CHACHA20_POLY1305, &chain_key, 2).unwrap();
+        let _ = seal(&mut sealer, b"zero");
+        let _ = seal(&mut sealer, b"one!");
+        let (record_number, record) = seal(&mut sealer, b"two!");
+        assert_eq!(record_number, 2);
+
+        let mut okm = [0u8; 2 * CHAIN_KEY_LEN];
+        let info: &[&[u8]] = &[b"ring rekeying"];
+        let prk = hkdf::Prk::new_less_safe(&digest::SHA256, &chain_key);
+        prk.expand(info, super::RatchetOutput(okm.len())).unwrap()
+           .fill(&mut okm).unwrap();
+        let prk = hkdf::Prk::new_less_safe(&digest::SHA256,
+                                           &okm[..CHAIN_KEY_LEN]);
+        prk.expand(info, super::RatchetOutput(okm.len())).unwrap()
+           .fill(&mut okm).unwrap();
+        let epoch_1_key =
+            aead::LessSafeKey::new(&aead::CHACHA20_POLY1305,
+                                   &okm[CHAIN_KEY_LEN..]).unwrap();
+        let mut nonce = [0u8; aead::NONCE_LEN];
+        nonce[aead::NONCE_LEN - 1] = 2;
+        let mut in_out = record;
+        let record_len = 4 + aead::TAG_LEN;
+        assert!(aead::open_in_place(&epoch_1_key, &nonce, 0,
+                                    &mut in_out[..record_len], b"ad").is_ok());
+        assert_eq!(&in_out[..4], b"two!");
+    }
+
+    #[test]
+    fn test_rekeying_key_epochs() {
+        let chain_key = [2u8; CHAIN_KEY_LEN];
+        let mut sealer =
+            RekeyingKey::new(&aead::AES_128_GCM, &chain_key, 2).unwrap();
+        let mut opener =
+            RekeyingKey::new(&aead::AES_128_GCM, &chain_key, 2).unwrap();
+        let records = (0..6).map(|_| seal(&mut sealer, b"data"))
+                            .collect::<std::vec::Vec<_>>();
+
+        // Records of the current epoch can be opened in any order.
+        assert!(open(&mut opener, 1, &records[1].1).is_ok());
+        assert!(open(&mut opener, 0, &records[0].1).is_ok());
+
+        // Records can't be opened with the wrong record number.
+        assert!(open(&mut opener, 0, &records[1].1).is_err());
+
+        // Records more than one epoch ahead can't be opened.
+        assert!(open(&mut opener, 4, &records[4].1).is_err());
+        assert_eq!(opener.epoch(), 0);
+
+        // A forged record from the next epoch doesn't move the key forward.
+        let mut forged = records[2].1;
+        forged[0] ^= 1;
+        assert!(open(&mut opener, 2, &forged).is_err());
+        assert_eq!(opener.epoch(), 0);
+
+        assert!(open(&mut opener, 3, &records[3].1).is_ok());
+        assert_eq!(opener.epoch(), 1);
+        assert!(open(&mut opener, 2, &records[2].1).is_ok());
+
+        // Records from earlier epochs can't be opened anymore.
+        assert!(open(&mut opener, 0, &records[0].1).is_err());
+
+        assert!(open(&mut opener, 4, &records[4].1).is_ok());
+        assert!(open(&mut opener, 5, &records[5].1).is_ok());
+        assert_eq!(opener.epoch(), 2);
+    }
+
+    #[test]
+    fn test_rekeying_key_new() {
+        let chain_key = [0u8; CHAIN_KEY_LEN];
+        assert!(RekeyingKey::new(&aead::AES_128_GCM, &chain_key, 1).is_ok());
+        assert!(RekeyingKey::new(&aead::AES_128_GCM,
+                                 &chain_key[..(CHAIN_KEY_LEN - 1)], 1)
+                    .is_err());
+        assert!(RekeyingKey::new(&aead::AES_128_GCM, &[0u8; CHAIN_KEY_LEN + 1],
+                                 1).is_err());
+        assert!(RekeyingKey::new(&aead::AES_128_GCM, &chain_key, 0).is_err());
+        let limit = aead::AES_128_GCM.confidentiality_limit();
+        assert!(RekeyingKey::new(&aead::AES_128_GCM, &chain_key, limit)
+                    .is_ok());
+        assert!(RekeyingKey::new(&aead::AES_128_GCM, &chain_key, limit + 1)
+                    .is_err());
+    }
+}
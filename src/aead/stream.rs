@@ -0,0 +1,338 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The STREAM construction of [Online Authenticated-Encryption and its
+//! Nonce-Reuse Misuse-Resistance], for sealing a long stream of data as a
+//! sequence of separately-sealed chunks.
+//!
+//! The nonce of each chunk is `nonce_prefix||counter||last_chunk`, where
+//! `counter` is the big-endian 32-bit index of the chunk in the stream and
+//! `last_chunk` is a byte that is 1 for the last chunk and 0 otherwise. Thus
+//! opening fails if the chunks are reordered, if chunks are removed, or if
+//! the stream is truncated, as long as the last chunk is opened with
+//! `open_last_chunk_in_place`.
+//!
+//! [Online Authenticated-Encryption and its Nonce-Reuse Misuse-Resistance]:
+//!     https://eprint.iacr.org/2015/189.pdf
+
+use {error, polyfill};
+use super::{Algorithm, LessSafeKey, MAX_NONCE_LEN, open_in_place,
+            seal_in_place};
+
+/// The length of the part of each chunk's nonce that is added by
+/// `StreamingSealer` and `StreamingOpener`. The nonce prefixes given to them
+/// must be `algorithm.nonce_len() - STREAM_NONCE_SUFFIX_LEN` bytes long.
+pub const STREAM_NONCE_SUFFIX_LEN: usize = 4 + 1;
+
+/// Seals a stream of data as a sequence of chunks using the STREAM
+/// construction.
+///
+/// The chunks must be opened in the same order by a `StreamingOpener` using
+/// the same key and nonce prefix.
+pub struct StreamingSealer {
+    key: LessSafeKey,
+    nonce: Nonce,
+}
+
+impl StreamingSealer {
+    /// Constructs a new `StreamingSealer`.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    /// `nonce_prefix` must be `STREAM_NONCE_SUFFIX_LEN` bytes shorter than
+    /// the nonces of `algorithm`, so the algorithm's nonces must be at least
+    /// that long. `nonce_prefix` must never be used for more than one stream
+    /// with the same key.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8],
+               nonce_prefix: &[u8])
+               -> Result<StreamingSealer, error::Unspecified> {
+        let key = try!(LessSafeKey::new(algorithm, key_bytes));
+        let nonce = try!(Nonce::new(algorithm, nonce_prefix));
+        Ok(StreamingSealer {
+            key: key,
+            nonce: nonce,
+        })
+    }
+
+    /// The sealer's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// Seals the next chunk of the stream, which must not be the last one.
+    ///
+    /// `in_out` and `out_suffix_capacity` are the same as for
+    /// `seal_in_place`. An error is returned after 2**32 - 1 chunks have been
+    /// sealed, since the last chunk must be sealed with
+    /// `seal_last_chunk_in_place`.
+    pub fn seal_chunk_in_place(&mut self, in_out: &mut [u8],
+                               out_suffix_capacity: usize, ad: &[u8])
+                               -> Result<usize, error::Unspecified> {
+        let len = try!(seal_in_place(&self.key, try!(self.nonce.next()),
+                                     in_out, out_suffix_capacity, ad));
+        self.nonce.advance();
+        Ok(len)
+    }
+
+    /// Seals the last chunk of the stream, which may be empty.
+    ///
+    /// `in_out` and `out_suffix_capacity` are the same as for
+    /// `seal_in_place`.
+    pub fn seal_last_chunk_in_place(mut self, in_out: &mut [u8],
+                                    out_suffix_capacity: usize, ad: &[u8])
+                                    -> Result<usize, error::Unspecified> {
+        seal_in_place(&self.key, self.nonce.last(), in_out,
+                      out_suffix_capacity, ad)
+    }
+}
+
+/// Opens a stream of data sealed by a `StreamingSealer`.
+pub struct StreamingOpener {
+    key: LessSafeKey,
+    nonce: Nonce,
+}
+
+impl StreamingOpener {
+    /// Constructs a new `StreamingOpener`.
+    ///
+    /// `key_bytes` must be exactly `algorithm.key_len` bytes long.
+    /// `nonce_prefix` must be the nonce prefix that the stream was sealed
+    /// with.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8],
+               nonce_prefix: &[u8])
+               -> Result<StreamingOpener, error::Unspecified> {
+        let key = try!(LessSafeKey::new(algorithm, key_bytes));
+        let nonce = try!(Nonce::new(algorithm, nonce_prefix));
+        Ok(StreamingOpener {
+            key: key,
+            nonce: nonce,
+        })
+    }
+
+    /// The opener's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.algorithm() }
+
+    /// Opens the next chunk of the stream, which must not be the last one.
+    ///
+    /// `in_prefix_len` and `in_out` are the same as for `open_in_place`. If
+    /// opening fails then the position in the stream doesn't change.
+    pub fn open_chunk_in_place(&mut self, in_prefix_len: usize,
+                               in_out: &mut [u8], ad: &[u8])
+                               -> Result<usize, error::Unspecified> {
+        let len = try!(open_in_place(&self.key, try!(self.nonce.next()),
+                                     in_prefix_len, in_out, ad));
+        self.nonce.advance();
+        Ok(len)
+    }
+
+    /// Opens the last chunk of the stream.
+    ///
+    /// `in_prefix_len` and `in_out` are the same as for `open_in_place`.
+    /// Opening fails if the chunk wasn't sealed as the last chunk of the
+    /// stream, so the stream must always be finished with this to detect
+    /// truncation.
+    pub fn open_last_chunk_in_place(mut self, in_prefix_len: usize,
+                                    in_out: &mut [u8], ad: &[u8])
+                                    -> Result<usize, error::Unspecified> {
+        open_in_place(&self.key, self.nonce.last(), in_prefix_len, in_out, ad)
+    }
+}
+
+struct Nonce {
+    value: [u8; MAX_NONCE_LEN],
+    len: usize,
+    counter: u32,
+}
+
+impl Nonce {
+    fn new(algorithm: &Algorithm, prefix: &[u8])
+           -> Result<Nonce, error::Unspecified> {
+        let len = algorithm.nonce_len();
+        if len < STREAM_NONCE_SUFFIX_LEN ||
+           prefix.len() != len - STREAM_NONCE_SUFFIX_LEN {
+            return Err(error::Unspecified);
+        }
+        let mut value = [0u8; MAX_NONCE_LEN];
+        value[..prefix.len()].copy_from_slice(prefix);
+        Ok(Nonce {
+            value: value,
+            len: len,
+            counter: 0,
+        })
+    }
+
+    /// The nonce for the next chunk, which isn't the last one.
+    fn next(&mut self) -> Result<&[u8], error::Unspecified> {
+        // The last counter value is reserved for the last chunk so that the
+        // counter never wraps around.
+        if self.counter == 0xffffffff {
+            return Err(error::Unspecified);
+        }
+        self.set_suffix(0);
+        Ok(&self.value[..self.len])
+    }
+
+    fn advance(&mut self) { self.counter += 1; }
+
+    fn last(&mut self) -> &[u8] {
+        self.set_suffix(1);
+        &self.value[..self.len]
+    }
+
+    fn set_suffix(&mut self, last_chunk: u8) {
+        let counter = polyfill::slice::be_u8_from_u32(self.counter);
+        let suffix = &mut self.value[(self.len - STREAM_NONCE_SUFFIX_LEN)..
+                                     self.len];
+        suffix[..4].copy_from_slice(&counter);
+        suffix[4] = last_chunk;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {aead, error};
+    use std::vec::Vec;
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn seal_stream(algorithm: &'static aead::Algorithm, nonce_prefix: &[u8],
+                   chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut sealer =
+            StreamingSealer::new(algorithm, &KEY[..algorithm.key_len()],
+                                 nonce_prefix).unwrap();
+        let suffix_len = algorithm.max_overhead_len();
+        let mut sealed = Vec::new();
+        let (last, chunks) = chunks.split_last().unwrap();
+        for chunk in chunks {
+            let mut in_out = chunk.to_vec();
+            in_out.extend_from_slice(&[0u8; aead::MAX_OVERHEAD_LEN]
+                                         [..suffix_len]);
+            let len = sealer.seal_chunk_in_place(&mut in_out, suffix_len, &[])
+                            .unwrap();
+            in_out.truncate(len);
+            sealed.push(in_out);
+        }
+        let mut in_out = last.to_vec();
+        in_out.extend_from_slice(&[0u8; aead::MAX_OVERHEAD_LEN][..suffix_len]);
+        let len = sealer.seal_last_chunk_in_place(&mut in_out, suffix_len, &[])
+                        .unwrap();
+        in_out.truncate(len);
+        sealed.push(in_out);
+        sealed
+    }
+
+    fn open_stream(algorithm: &'static aead::Algorithm, nonce_prefix: &[u8],
+                   sealed: &[Vec<u8>])
+                   -> Result<Vec<Vec<u8>>, error::Unspecified> {
+        let mut opener =
+            StreamingOpener::new(algorithm, &KEY[..algorithm.key_len()],
+                                 nonce_prefix).unwrap();
+        let mut opened = Vec::new();
+        let (last, chunks) = sealed.split_last().unwrap();
+        for chunk in chunks {
+            let mut in_out = chunk.clone();
+            let len = try!(opener.open_chunk_in_place(0, &mut in_out, &[]));
+            in_out.truncate(len);
+            opened.push(in_out);
+        }
+        let mut in_out = last.clone();
+        let len = try!(opener.open_last_chunk_in_place(0, &mut in_out, &[]));
+        in_out.truncate(len);
+        opened.push(in_out);
+        Ok(opened)
+    }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let chunks: [&[u8]; 4] = [b"one", b"two", b"three", b""];
+        for algorithm in &[&aead::AES_128_GCM, &aead::CHACHA20_POLY1305,
+                           &aead::XCHACHA20_POLY1305, &aead::AES_128_CCM_BLE] {
+            let prefix_len = algorithm.nonce_len() - STREAM_NONCE_SUFFIX_LEN;
+            let nonce_prefix = vec![1u8; prefix_len];
+            let sealed = seal_stream(algorithm, &nonce_prefix, &chunks);
+            let opened = open_stream(algorithm, &nonce_prefix, &sealed)
+                .unwrap();
+            assert_eq!(opened.len(), chunks.len());
+            for (opened, chunk) in opened.iter().zip(chunks.iter()) {
+                assert_eq!(&opened[..], *chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_nonces() {
+        let algorithm = &aead::AES_128_GCM;
+        let nonce_prefix = [0xaa; 7];
+        let chunks: [&[u8]; 3] = [b"one", b"two", b"three"];
+        let sealed = seal_stream(algorithm, &nonce_prefix, &chunks);
+
+        let key = aead::LessSafeKey::new(algorithm, &KEY[..16]).unwrap();
+        for (i, (chunk, sealed)) in chunks.iter().zip(sealed.iter())
+                                          .enumerate() {
+            let mut nonce = [0u8; 12];
+            nonce[..7].copy_from_slice(&nonce_prefix);
+            nonce[10] = i as u8;
+            nonce[11] = if i == chunks.len() - 1 { 1 } else { 0 };
+            let mut in_out = chunk.to_vec();
+            in_out.extend_from_slice(&[0u8; aead::MAX_OVERHEAD_LEN]);
+            let len = aead::seal_in_place(&key, &nonce, &mut in_out,
+                                          aead::MAX_OVERHEAD_LEN, &[])
+                          .unwrap();
+            assert_eq!(&in_out[..len], &sealed[..]);
+        }
+    }
+
+    #[test]
+    fn test_stream_tampering() {
+        let algorithm = &aead::CHACHA20_POLY1305;
+        let nonce_prefix = [0x55; 7];
+        let chunks: [&[u8]; 3] = [b"one", b"two", b"three"];
+        let sealed = seal_stream(algorithm, &nonce_prefix, &chunks);
+        assert!(open_stream(algorithm, &nonce_prefix, &sealed).is_ok());
+
+        // Reordered chunks.
+        let reordered = [sealed[1].clone(), sealed[0].clone(),
+                         sealed[2].clone()];
+        assert!(open_stream(algorithm, &nonce_prefix, &reordered).is_err());
+
+        // A missing chunk.
+        let missing = [sealed[0].clone(), sealed[2].clone()];
+        assert!(open_stream(algorithm, &nonce_prefix, &missing).is_err());
+
+        // Truncation.
+        assert!(open_stream(algorithm, &nonce_prefix, &sealed[..2]).is_err());
+
+        // Extension after the last chunk.
+        let extended = [sealed[0].clone(), sealed[1].clone(),
+                        sealed[2].clone(), sealed[2].clone()];
+        assert!(open_stream(algorithm, &nonce_prefix, &extended).is_err());
+
+        // A different nonce prefix.
+        assert!(open_stream(algorithm, &[0x56; 7], &sealed).is_err());
+    }
+
+    #[test]
+    fn test_stream_nonce_prefix_len() {
+        let algorithm = &aead::AES_128_GCM;
+        assert!(StreamingSealer::new(algorithm, &KEY[..16], &[0; 7]).is_ok());
+        assert!(StreamingSealer::new(algorithm, &KEY[..16], &[0; 8]).is_err());
+        assert!(StreamingOpener::new(algorithm, &KEY[..16], &[0; 7]).is_ok());
+        assert!(StreamingOpener::new(algorithm, &KEY[..16], &[0; 6]).is_err());
+
+        // AES-SIV-CMAC doesn't have nonces, so it can't be used.
+        assert!(StreamingSealer::new(&aead::AES_128_SIV_CMAC, &KEY, &[])
+                    .is_err());
+    }
+}
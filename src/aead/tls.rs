@@ -0,0 +1,616 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! TLS record protection, as described in [RFC 5246 Section 6.2.3.3] for
+//! TLS 1.2 and [RFC 8446 Section 5.2] for TLS 1.3.
+//!
+//! The sealers and openers in this module keep track of the sequence number
+//! of each record, and construct the nonce and the additional data of each
+//! record from it, so that a TLS implementation only has to deal with whole
+//! records. Record layer framing, fragmentation, and key updates are still up
+//! to the TLS implementation.
+//!
+//! Every record is sealed or opened in place. A record is a `HEADER_LEN`-byte
+//! header, followed by the TLS 1.2 explicit nonce, if any, followed by the
+//! ciphertext and the tag. Every function here fails without modifying the
+//! sequence number if the record is malformed. Once opening a record has
+//! failed, the connection must be closed with a `bad_record_mac` alert.
+//!
+//! [RFC 5246 Section 6.2.3.3]: https://tools.ietf.org/html/rfc5246#section-6.2.3.3
+//! [RFC 8446 Section 5.2]: https://tools.ietf.org/html/rfc8446#section-5.2
+
+use {core, error, polyfill};
+use super::{AES_128_GCM, AES_256_GCM, Algorithm, CHACHA20_POLY1305, Key,
+            NONCE_LEN};
+
+/// The length of a TLS record header.
+pub const HEADER_LEN: usize = 5;
+
+/// The length of the fixed part of the IV of AES-GCM in TLS 1.2, from
+/// [RFC 5288 Section 3].
+///
+/// [RFC 5288 Section 3]: https://tools.ietf.org/html/rfc5288#section-3
+pub const TLS12_AES_GCM_FIXED_IV_LEN: usize = 4;
+
+/// The length of the explicit nonce that precedes the ciphertext of an
+/// AES-GCM record in TLS 1.2.
+pub const TLS12_AES_GCM_EXPLICIT_NONCE_LEN: usize = 8;
+
+/// The `ContentType` of the outer header of every TLS 1.3 record.
+pub const CONTENT_TYPE_APPLICATION_DATA: u8 = 23;
+
+/// The maximum length of the plaintext of a TLS record, 2<sup>14</sup> bytes.
+pub const MAX_FRAGMENT_LEN: usize = 1 << 14;
+
+/// A key for sealing TLS 1.2 records.
+pub struct Tls12Sealer {
+    key: RecordKey,
+}
+
+impl Tls12Sealer {
+    /// Constructs a new `Tls12Sealer`.
+    ///
+    /// `algorithm` must be `AES_128_GCM` or `AES_256_GCM`, with a
+    /// `TLS12_AES_GCM_FIXED_IV_LEN`-byte `iv`, or `CHACHA20_POLY1305`, with
+    /// a `NONCE_LEN`-byte `iv` as described in [RFC 7905 Section 2]. The
+    /// sequence number starts at zero.
+    ///
+    /// [RFC 7905 Section 2]: https://tools.ietf.org/html/rfc7905#section-2
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+               -> Result<Tls12Sealer, error::Unspecified> {
+        Ok(Tls12Sealer { key: try!(RecordKey::new_tls12(algorithm, key_bytes,
+                                                       iv)) })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.key.algorithm() }
+
+    /// The sequence number of the next record.
+    #[inline(always)]
+    pub fn sequence_number(&self) -> u64 { self.key.sequence_number }
+
+    /// The length of the explicit nonce between the header and the
+    /// ciphertext of each record, which is
+    /// `TLS12_AES_GCM_EXPLICIT_NONCE_LEN` for AES-GCM and zero otherwise.
+    #[inline(always)]
+    pub fn explicit_nonce_len(&self) -> usize { self.key.explicit_nonce_len }
+
+    /// Seals a record of type `content_type` in place.
+    ///
+    /// The plaintext is the `content_len` bytes of `record` after the first
+    /// `HEADER_LEN + explicit_nonce_len()` bytes, which are overwritten with
+    /// the header and the explicit nonce. `record` must have room for the
+    /// tag after the plaintext. `content_len` must be at most
+    /// `MAX_FRAGMENT_LEN`. Returns the length of the record.
+    ///
+    /// Fails once the sequence number reaches the algorithm's
+    /// `confidentiality_limit()`; the connection must then be renegotiated
+    /// or closed.
+    pub fn seal_in_place(&mut self, content_type: u8, record: &mut [u8],
+                         content_len: usize)
+                         -> Result<usize, error::Unspecified> {
+        let algorithm = self.key.key.algorithm;
+        if content_len > MAX_FRAGMENT_LEN {
+            return Err(error::Unspecified);
+        }
+        let prefix_len = HEADER_LEN + self.key.explicit_nonce_len;
+        let fragment_len =
+            self.key.explicit_nonce_len + content_len + algorithm.tag_len;
+        if record.len() < HEADER_LEN + fragment_len {
+            return Err(error::Unspecified);
+        }
+        let (sequence_number, nonce) =
+            try!(self.key.advance(algorithm.confidentiality_limit));
+        let ad = tls12_ad(sequence_number, content_type, TLS12_VERSION,
+                          content_len);
+        let (prefix, in_out) =
+            record[..(HEADER_LEN + fragment_len)].split_at_mut(prefix_len);
+        write_header(prefix, content_type, TLS12_VERSION, fragment_len);
+        prefix[HEADER_LEN..]
+            .copy_from_slice(&nonce[(NONCE_LEN -
+                                     self.key.explicit_nonce_len)..]);
+        let sealed_len = try!(self.key.key.seal_in_place(&nonce, in_out,
+                                                         algorithm.tag_len,
+                                                         &[&ad]));
+        Ok(prefix_len + sealed_len)
+    }
+}
+
+/// A key for opening TLS 1.2 records.
+pub struct Tls12Opener {
+    key: RecordKey,
+}
+
+impl Tls12Opener {
+    /// Constructs a new `Tls12Opener`. The arguments are the same as for
+    /// `Tls12Sealer::new`.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+               -> Result<Tls12Opener, error::Unspecified> {
+        Ok(Tls12Opener { key: try!(RecordKey::new_tls12(algorithm, key_bytes,
+                                                       iv)) })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.key.algorithm() }
+
+    /// The sequence number of the next record.
+    #[inline(always)]
+    pub fn sequence_number(&self) -> u64 { self.key.sequence_number }
+
+    /// Opens the record `record`, which must be exactly one whole record, in
+    /// place.
+    ///
+    /// When `open_in_place` returns `Ok((content_type, content_len))`, the
+    /// plaintext is `record[..content_len]`. AES-GCM records are opened with
+    /// the nonce given in the record, as RFC 5288 requires. Fails if the
+    /// plaintext would be longer than `MAX_FRAGMENT_LEN`.
+    pub fn open_in_place(&mut self, record: &mut [u8])
+                         -> Result<(u8, usize), error::Unspecified> {
+        let algorithm = self.key.key.algorithm;
+        let fragment_len = try!(check_header(record, MAX_TLS12_FRAGMENT_LEN));
+        let content_type = record[0];
+        let version = [record[1], record[2]];
+        let prefix_len = HEADER_LEN + self.key.explicit_nonce_len;
+        let content_len =
+            try!(fragment_len.checked_sub(self.key.explicit_nonce_len +
+                                          algorithm.tag_len)
+                             .ok_or(error::Unspecified));
+        if content_len > MAX_FRAGMENT_LEN {
+            return Err(error::Unspecified);
+        }
+        let (sequence_number, mut nonce) = try!(self.key.advance(LAST_RECORD));
+        let explicit_nonce_start = NONCE_LEN - self.key.explicit_nonce_len;
+        nonce[explicit_nonce_start..]
+            .copy_from_slice(&record[HEADER_LEN..prefix_len]);
+        let ad = tls12_ad(sequence_number, content_type, version,
+                          content_len);
+        let content_len = try!(self.key.key.open_in_place(&nonce, prefix_len,
+                                                          record, &[&ad]));
+        Ok((content_type, content_len))
+    }
+}
+
+/// A key for sealing TLS 1.3 records.
+pub struct Tls13Sealer {
+    key: RecordKey,
+}
+
+impl Tls13Sealer {
+    /// Constructs a new `Tls13Sealer`.
+    ///
+    /// `key_bytes` and `iv` are the `[sender]_write_key` and
+    /// `[sender]_write_iv` of [RFC 8446 Section 7.3]. `iv` must be
+    /// `NONCE_LEN` bytes long. The sequence number starts at zero.
+    ///
+    /// [RFC 8446 Section 7.3]: https://tools.ietf.org/html/rfc8446#section-7.3
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+               -> Result<Tls13Sealer, error::Unspecified> {
+        Ok(Tls13Sealer { key: try!(RecordKey::new_tls13(algorithm, key_bytes,
+                                                       iv)) })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.key.algorithm() }
+
+    /// The sequence number of the next record.
+    #[inline(always)]
+    pub fn sequence_number(&self) -> u64 { self.key.sequence_number }
+
+    /// Seals a record with the inner content type `content_type` in place.
+    ///
+    /// The content is the `content_len` bytes of `record` after the first
+    /// `HEADER_LEN` bytes, which are overwritten with the header. `record`
+    /// must have room for the content type and the tag after the content.
+    /// `content_len` must be at most `MAX_FRAGMENT_LEN`. The record is not
+    /// padded. Returns the length of the record.
+    ///
+    /// Fails once the sequence number reaches the algorithm's
+    /// `confidentiality_limit()`; a `KeyUpdate` must be sent before then.
+    pub fn seal_in_place(&mut self, content_type: u8, record: &mut [u8],
+                         content_len: usize)
+                         -> Result<usize, error::Unspecified> {
+        let algorithm = self.key.key.algorithm;
+        if content_len > MAX_FRAGMENT_LEN {
+            return Err(error::Unspecified);
+        }
+        let fragment_len = content_len + 1 + algorithm.tag_len;
+        if record.len() < HEADER_LEN + fragment_len {
+            return Err(error::Unspecified);
+        }
+        let (_, nonce) =
+            try!(self.key.advance(algorithm.confidentiality_limit));
+        let (header, in_out) =
+            record[..(HEADER_LEN + fragment_len)].split_at_mut(HEADER_LEN);
+        write_header(header, CONTENT_TYPE_APPLICATION_DATA, TLS12_VERSION,
+                     fragment_len);
+        in_out[content_len] = content_type;
+        let sealed_len = try!(self.key.key.seal_in_place(&nonce, in_out,
+                                                         algorithm.tag_len,
+                                                         &[header]));
+        Ok(HEADER_LEN + sealed_len)
+    }
+}
+
+/// A key for opening TLS 1.3 records.
+pub struct Tls13Opener {
+    key: RecordKey,
+}
+
+impl Tls13Opener {
+    /// Constructs a new `Tls13Opener`. The arguments are the same as for
+    /// `Tls13Sealer::new`.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+               -> Result<Tls13Opener, error::Unspecified> {
+        Ok(Tls13Opener { key: try!(RecordKey::new_tls13(algorithm, key_bytes,
+                                                       iv)) })
+    }
+
+    /// The key's AEAD algorithm.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.key.key.algorithm() }
+
+    /// The sequence number of the next record.
+    #[inline(always)]
+    pub fn sequence_number(&self) -> u64 { self.key.sequence_number }
+
+    /// Opens the record `record`, which must be exactly one whole record, in
+    /// place, and removes its padding.
+    ///
+    /// When `open_in_place` returns `Ok((content_type, content_len))`,
+    /// `content_type` is the inner content type and the content is
+    /// `record[..content_len]`. Fails if the header isn't that of a
+    /// TLS 1.3 record, if the content would be longer than
+    /// `MAX_FRAGMENT_LEN`, or if the record is all padding, which must be
+    /// treated as an `unexpected_message` instead of a `bad_record_mac`.
+    pub fn open_in_place(&mut self, record: &mut [u8])
+                         -> Result<(u8, usize), error::Unspecified> {
+        let fragment_len = try!(check_header(record, MAX_TLS13_FRAGMENT_LEN));
+        if record[0] != CONTENT_TYPE_APPLICATION_DATA ||
+           record[1..3] != TLS12_VERSION {
+            return Err(error::Unspecified);
+        }
+        let inner_len = try!(fragment_len.checked_sub(self.key.key.algorithm
+                                                          .tag_len)
+                                         .ok_or(error::Unspecified));
+        if inner_len > MAX_FRAGMENT_LEN + 1 {
+            return Err(error::Unspecified);
+        }
+        let (_, nonce) = try!(self.key.advance(LAST_RECORD));
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&record[..HEADER_LEN]);
+        let inner_len = try!(self.key.key.open_in_place(&nonce, HEADER_LEN,
+                                                        record, &[&header]));
+        match record[..inner_len].iter().rposition(|b| *b != 0) {
+            Some(content_len) => Ok((record[content_len], content_len)),
+            None => Err(error::Unspecified),
+        }
+    }
+}
+
+/// The state shared by the sealers and the openers.
+struct RecordKey {
+    key: Key,
+    iv: [u8; NONCE_LEN],
+    explicit_nonce_len: usize,
+    sequence_number: u64,
+}
+
+impl RecordKey {
+    fn new_tls12(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+                 -> Result<RecordKey, error::Unspecified> {
+        let is_aes_gcm = core::ptr::eq(algorithm, &AES_128_GCM) ||
+                         core::ptr::eq(algorithm, &AES_256_GCM);
+        if is_aes_gcm {
+            if iv.len() != TLS12_AES_GCM_FIXED_IV_LEN {
+                return Err(error::Unspecified);
+            }
+            // The nonce is the fixed IV followed by the explicit nonce, which
+            // is the sequence number when sealing.
+            let mut full_iv = [0u8; NONCE_LEN];
+            full_iv[..TLS12_AES_GCM_FIXED_IV_LEN].copy_from_slice(iv);
+            RecordKey::new(algorithm, key_bytes, &full_iv,
+                           TLS12_AES_GCM_EXPLICIT_NONCE_LEN)
+        } else if core::ptr::eq(algorithm, &CHACHA20_POLY1305) {
+            RecordKey::new(algorithm, key_bytes, iv, 0)
+        } else {
+            Err(error::Unspecified)
+        }
+    }
+
+    fn new_tls13(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8])
+                 -> Result<RecordKey, error::Unspecified> {
+        RecordKey::new(algorithm, key_bytes, iv, 0)
+    }
+
+    fn new(algorithm: &'static Algorithm, key_bytes: &[u8], iv: &[u8],
+           explicit_nonce_len: usize)
+           -> Result<RecordKey, error::Unspecified> {
+        if algorithm.nonce_len != NONCE_LEN {
+            return Err(error::Unspecified);
+        }
+        let iv = try!(slice_as_array_ref!(iv, NONCE_LEN));
+        Ok(RecordKey {
+            key: try!(Key::new(algorithm, key_bytes)),
+            iv: *iv,
+            explicit_nonce_len: explicit_nonce_len,
+            sequence_number: 0,
+        })
+    }
+
+    /// Returns the sequence number of the next record and the IV XORed with
+    /// it, failing if the sequence number has reached `limit`. Sequence
+    /// numbers never wrap around.
+    fn advance(&mut self, limit: u64)
+               -> Result<(u64, [u8; NONCE_LEN]), error::Unspecified> {
+        let sequence_number = self.sequence_number;
+        if sequence_number >= limit {
+            return Err(error::Unspecified);
+        }
+        self.sequence_number += 1;
+        let mut nonce = self.iv;
+        let sequence_number_bytes =
+            polyfill::slice::be_u8_from_u64(sequence_number);
+        let nonce_suffix = &mut nonce[(NONCE_LEN - 8)..];
+        for (n, s) in nonce_suffix.iter_mut().zip(&sequence_number_bytes) {
+            *n ^= *s;
+        }
+        Ok((sequence_number, nonce))
+    }
+}
+
+/// Checks that the length in `record`'s header is the length of the rest of
+/// `record` and at most `max_fragment_len`, and returns it.
+fn check_header(record: &[u8], max_fragment_len: usize)
+                -> Result<usize, error::Unspecified> {
+    if record.len() < HEADER_LEN {
+        return Err(error::Unspecified);
+    }
+    let fragment_len = ((record[3] as usize) << 8) | (record[4] as usize);
+    if fragment_len != record.len() - HEADER_LEN ||
+       fragment_len > max_fragment_len {
+        return Err(error::Unspecified);
+    }
+    Ok(fragment_len)
+}
+
+fn write_header(header: &mut [u8], content_type: u8, version: [u8; 2],
+                fragment_len: usize) {
+    header[0] = content_type;
+    header[1..3].copy_from_slice(&version);
+    header[3] = (fragment_len >> 8) as u8;
+    header[4] = fragment_len as u8;
+}
+
+/// `seq_num || TLSCompressed.type || TLSCompressed.version ||
+/// TLSCompressed.length`.
+fn tls12_ad(sequence_number: u64, content_type: u8, version: [u8; 2],
+            content_len: usize) -> [u8; TLS12_AD_LEN] {
+    let mut ad = [0u8; TLS12_AD_LEN];
+    ad[..8].copy_from_slice(&polyfill::slice::be_u8_from_u64(sequence_number));
+    write_header(&mut ad[8..], content_type, version, content_len);
+    ad
+}
+
+const TLS12_AD_LEN: usize = 8 + HEADER_LEN;
+
+/// TLS 1.2, which is also the `legacy_record_version` of TLS 1.3.
+const TLS12_VERSION: [u8; 2] = [3, 3];
+
+const MAX_TLS12_FRAGMENT_LEN: usize = MAX_FRAGMENT_LEN + 2048;
+const MAX_TLS13_FRAGMENT_LEN: usize = MAX_FRAGMENT_LEN + 256;
+
+/// Openers accept every sequence number but the last one, so that
+/// `RecordKey::advance` never overflows.
+const LAST_RECORD: u64 = core::u64::MAX;
+
+
+#[cfg(test)]
+mod tests {
+    use {aead, polyfill, test};
+    use super::*;
+
+    #[test]
+    fn test_tls12() {
+        test::from_file("src/aead/tls12_tests.txt", |section, test_case| {
+            let algorithm = algorithm_from_section(section);
+            let key_bytes = test_case.consume_bytes("Key");
+            let iv = test_case.consume_bytes("IV");
+            let sequence_number = test_case.consume_bytes("Seq");
+            let sequence_number = polyfill::slice::u64_from_be_u8(
+                slice_as_array_ref!(&sequence_number, 8).unwrap());
+            let content_type = test_case.consume_usize("Type") as u8;
+            let content = test_case.consume_bytes("Content");
+            let expected_record = test_case.consume_bytes("Record");
+
+            // Records past the confidentiality limit can still be opened.
+            let mut sealer =
+                Tls12Sealer::new(algorithm, &key_bytes, &iv).unwrap();
+            sealer.key.sequence_number = sequence_number;
+            let prefix_len = HEADER_LEN + sealer.explicit_nonce_len();
+            let mut record = vec![0xff; prefix_len];
+            record.extend_from_slice(&content);
+            record.extend_from_slice(&[0; aead::MAX_OVERHEAD_LEN]);
+            let result = sealer.seal_in_place(content_type, &mut record,
+                                              content.len());
+            if sequence_number < algorithm.confidentiality_limit() {
+                assert_eq!(result, Ok(expected_record.len()));
+                assert_eq!(&record[..expected_record.len()],
+                           &expected_record[..]);
+                assert_eq!(sealer.sequence_number(), sequence_number + 1);
+            } else {
+                assert!(result.is_err());
+                assert_eq!(sealer.sequence_number(), sequence_number);
+            }
+
+            let mut opener =
+                Tls12Opener::new(algorithm, &key_bytes, &iv).unwrap();
+            opener.key.sequence_number = sequence_number;
+            let mut record = expected_record.clone();
+            assert_eq!(opener.open_in_place(&mut record),
+                       Ok((content_type, content.len())));
+            assert_eq!(&record[..content.len()], &content[..]);
+            assert_eq!(opener.sequence_number(), sequence_number + 1);
+
+            // The sequence number is authenticated.
+            let mut record = expected_record.clone();
+            assert!(opener.open_in_place(&mut record).is_err());
+
+            // So is the header.
+            let mut opener =
+                Tls12Opener::new(algorithm, &key_bytes, &iv).unwrap();
+            opener.key.sequence_number = sequence_number;
+            let mut record = expected_record.clone();
+            record[0] ^= 1;
+            assert!(opener.open_in_place(&mut record).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_tls13() {
+        test::from_file("src/aead/tls13_tests.txt", |section, test_case| {
+            let algorithm = algorithm_from_section(section);
+            let key_bytes = test_case.consume_bytes("Key");
+            let iv = test_case.consume_bytes("IV");
+            let sequence_number = test_case.consume_bytes("Seq");
+            let sequence_number = polyfill::slice::u64_from_be_u8(
+                slice_as_array_ref!(&sequence_number, 8).unwrap());
+            let content_type = test_case.consume_usize("Type") as u8;
+            let content = test_case.consume_bytes("Content");
+            let expected_record = test_case.consume_bytes("Record");
+            let padded = test_case.consume_usize("Padded") != 0;
+
+            if !padded &&
+               sequence_number < algorithm.confidentiality_limit() {
+                let mut sealer =
+                    Tls13Sealer::new(algorithm, &key_bytes, &iv).unwrap();
+                sealer.key.sequence_number = sequence_number;
+                let mut record = vec![0xff; HEADER_LEN];
+                record.extend_from_slice(&content);
+                record.extend_from_slice(&[0; 1 + aead::MAX_OVERHEAD_LEN]);
+                assert_eq!(sealer.seal_in_place(content_type, &mut record,
+                                                content.len()),
+                           Ok(expected_record.len()));
+                assert_eq!(&record[..], &expected_record[..]);
+                assert_eq!(sealer.sequence_number(), sequence_number + 1);
+            }
+
+            let mut opener =
+                Tls13Opener::new(algorithm, &key_bytes, &iv).unwrap();
+            opener.key.sequence_number = sequence_number;
+            let mut record = expected_record.clone();
+            assert_eq!(opener.open_in_place(&mut record),
+                       Ok((content_type, content.len())));
+            assert_eq!(&record[..content.len()], &content[..]);
+
+            // The next record has a different nonce.
+            let mut record = expected_record.clone();
+            assert!(opener.open_in_place(&mut record).is_err());
+
+            Ok(())
+        });
+    }
+
+    fn algorithm_from_section(section: &str) -> &'static aead::Algorithm {
+        match section {
+            "AES-128-GCM" => &aead::AES_128_GCM,
+            "AES-256-GCM" => &aead::AES_256_GCM,
+            "CHACHA20-POLY1305" => &aead::CHACHA20_POLY1305,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_tls_round_trip() {
+        let mut sealer = Tls13Sealer::new(&aead::AES_128_GCM, &[1; 16],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        let mut opener = Tls13Opener::new(&aead::AES_128_GCM, &[1; 16],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        for content_len in &[0, 1, 100, MAX_FRAGMENT_LEN] {
+            let mut record = vec![0x5a; HEADER_LEN + content_len + 17];
+            assert_eq!(sealer.seal_in_place(22, &mut record, *content_len),
+                       Ok(record.len()));
+            assert_eq!(opener.open_in_place(&mut record),
+                       Ok((22, *content_len)));
+            assert!(record[..*content_len].iter().all(|b| *b == 0x5a));
+        }
+
+        // Too long, or not enough room for the content type and the tag.
+        let mut record = vec![0; HEADER_LEN + MAX_FRAGMENT_LEN + 1 + 17];
+        assert!(sealer.seal_in_place(23, &mut record, MAX_FRAGMENT_LEN + 1)
+                      .is_err());
+        let mut record = vec![0; HEADER_LEN + 16];
+        assert!(sealer.seal_in_place(23, &mut record, 0).is_err());
+        assert_eq!(sealer.sequence_number(), 4);
+
+        // Truncated records, and records whose header has the wrong length,
+        // don't use up a sequence number.
+        let mut record = vec![0; HEADER_LEN + 10 + 17];
+        assert!(sealer.seal_in_place(23, &mut record, 10).is_ok());
+        assert!(opener.open_in_place(&mut record[..HEADER_LEN + 16])
+                      .is_err());
+        assert!(opener.open_in_place(&mut record[..3]).is_err());
+        assert_eq!(opener.sequence_number(), 4);
+        assert_eq!(opener.open_in_place(&mut record), Ok((23, 10)));
+    }
+
+    #[test]
+    fn test_tls_all_padding() {
+        // A record whose plaintext is all zeros has no content type.
+        let mut sealer = Tls13Sealer::new(&aead::CHACHA20_POLY1305, &[1; 32],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        let mut opener = Tls13Opener::new(&aead::CHACHA20_POLY1305, &[1; 32],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        let mut record = vec![0; HEADER_LEN + 4 + 17];
+        assert!(sealer.seal_in_place(0, &mut record, 4).is_ok());
+        assert!(opener.open_in_place(&mut record).is_err());
+    }
+
+    #[test]
+    fn test_tls_sequence_number_limit() {
+        let mut sealer = Tls13Sealer::new(&aead::AES_256_GCM, &[1; 32],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        sealer.key.sequence_number =
+            aead::AES_256_GCM.confidentiality_limit() - 1;
+        let mut record = vec![0; HEADER_LEN + 17];
+        assert!(sealer.seal_in_place(23, &mut record, 0).is_ok());
+        assert!(sealer.seal_in_place(23, &mut record, 0).is_err());
+
+        let mut opener = Tls12Opener::new(&aead::CHACHA20_POLY1305, &[1; 32],
+                                          &[2; aead::NONCE_LEN]).unwrap();
+        opener.key.sequence_number = core::u64::MAX;
+        let mut record = vec![23, 3, 3, 0, 16];
+        record.extend_from_slice(&[0; 16]);
+        assert!(opener.open_in_place(&mut record).is_err());
+        assert_eq!(opener.sequence_number(), core::u64::MAX);
+    }
+
+    #[test]
+    fn test_tls_unsupported() {
+        assert!(Tls12Sealer::new(&aead::AES_128_GCM, &[0; 16],
+                                 &[0; aead::NONCE_LEN]).is_err());
+        assert!(Tls12Sealer::new(&aead::CHACHA20_POLY1305, &[0; 32],
+                                 &[0; TLS12_AES_GCM_FIXED_IV_LEN]).is_err());
+        assert!(Tls12Sealer::new(&aead::AES_128_GCM_SIV, &[0; 16],
+                                 &[0; aead::NONCE_LEN]).is_err());
+        assert!(Tls13Sealer::new(&aead::XCHACHA20_POLY1305, &[0; 32],
+                                 &[0; 24]).is_err());
+        assert!(Tls13Opener::new(&aead::AES_128_GCM, &[0; 16], &[0; 11])
+                    .is_err());
+    }
+}
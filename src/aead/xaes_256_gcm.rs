@@ -0,0 +1,111 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use {aead, aes, error, polyfill};
+use super::aes_gcm::{aes_gcm_init, aes_gcm_open, aes_gcm_seal};
+
+/// XAES-256-GCM as described in the [C2SP XAES-256-GCM specification].
+///
+/// The keys are 256 bits long and the nonces are 192 bits long. The nonces
+/// are long enough that they may be generated randomly for every message.
+/// Each message is sealed with AES-256-GCM using a key derived from the
+/// first 96 bits of the nonce with AES-256-CMAC in counter mode (NIST SP
+/// 800-108r1) and the remaining 96 bits of the nonce.
+///
+/// [C2SP XAES-256-GCM specification]: https://c2sp.org/XAES-256-GCM
+pub static XAES_256_GCM: aead::Algorithm = aead::Algorithm {
+    key_len: KEY_LEN,
+    init: xaes_256_gcm_init,
+    seal: xaes_256_gcm_seal,
+    open: xaes_256_gcm_open,
+    nonce_len: NONCE_LEN,
+    tag_len: aead::TAG_LEN,
+    confidentiality_limit: aead::NO_CONFIDENTIALITY_LIMIT,
+};
+
+const KEY_LEN: usize = 256 / 8;
+const NONCE_LEN: usize = 192 / 8;
+
+/// Expands the key into `ctx_buf`, followed by the CMAC subkey K1 that the
+/// key derivation uses.
+fn xaes_256_gcm_init(ctx_buf: &mut [u8], key: &[u8])
+                     -> Result<(), error::Unspecified> {
+    let (aes_ctx, rest) = ctx_buf.split_at_mut(aes::KEY_CTX_BUF_LEN);
+    try!(aes::init(aes_ctx, key));
+    let mut l = [0u8; aes::BLOCK_LEN];
+    aes::encrypt_blocks(aes_ctx, &mut l);
+    rest[..aes::BLOCK_LEN].copy_from_slice(&aes::dbl(&l));
+    Ok(())
+}
+
+fn xaes_256_gcm_seal(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                     in_out: &mut [u8], tag_out: &mut [u8; aead::TAG_LEN],
+                     ad: &[&[u8]]) -> Result<(), error::Unspecified> {
+    let (gcm_ctx, gcm_nonce) = try!(derive_key_and_nonce(ctx, nonce));
+    aes_gcm_seal(&gcm_ctx, gcm_nonce, in_out, tag_out, ad)
+}
+
+fn xaes_256_gcm_open(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS], nonce: &[u8],
+                     in_out: &mut [u8], in_prefix_len: usize,
+                     received_tag: &[u8; aead::TAG_LEN],
+                     tag_out: &mut [u8; aead::TAG_LEN], ad: &[&[u8]])
+                     -> Result<(), error::Unspecified> {
+    let (gcm_ctx, gcm_nonce) = try!(derive_key_and_nonce(ctx, nonce));
+    aes_gcm_open(&gcm_ctx, gcm_nonce, in_out, in_prefix_len, received_tag,
+                 tag_out, ad)
+}
+
+/// Derives the AES-256-GCM key from the first 96 bits of the nonce and
+/// expands it into a new context. The AES-256-GCM nonce is the remaining 96
+/// bits of the XAES-256-GCM nonce.
+fn derive_key_and_nonce<'a>(ctx: &[u64; aead::KEY_CTX_BUF_ELEMS],
+                            nonce: &'a [u8])
+        -> Result<([u64; aead::KEY_CTX_BUF_ELEMS], &'a [u8]),
+                  error::Unspecified> {
+    if nonce.len() != NONCE_LEN {
+        return Err(error::Unspecified);
+    }
+    let (key_nonce, gcm_nonce) = nonce.split_at(NONCE_LEN - aead::NONCE_LEN);
+    let (aes_ctx, k1) =
+        polyfill::slice::u64_as_u8(ctx).split_at(aes::KEY_CTX_BUF_LEN);
+
+    // M_i = [i]_16 || "X" || 0x00 || N[:12], and the derived key is
+    // AES-256(M_1 ^ K1) || AES-256(M_2 ^ K1).
+    let mut derived_key = [0u8; KEY_LEN];
+    for (i, block) in derived_key.chunks_mut(aes::BLOCK_LEN).enumerate() {
+        block[1] = (i + 1) as u8;
+        block[2] = b'X';
+        block[4..].copy_from_slice(key_nonce);
+        for (b, k) in block.iter_mut().zip(k1.iter()) {
+            *b ^= *k;
+        }
+    }
+    aes::encrypt_blocks(aes_ctx, &mut derived_key);
+
+    let mut gcm_ctx = [0u64; aead::KEY_CTX_BUF_ELEMS];
+    try!(aes_gcm_init(polyfill::slice::u64_as_u8_mut(&mut gcm_ctx),
+                      &derived_key));
+    Ok((gcm_ctx, gcm_nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use aead;
+
+    #[test]
+    pub fn test_xaes_256_gcm() {
+        aead::tests::test_aead(&aead::XAES_256_GCM,
+                               "src/aead/xaes_256_gcm_tests.txt");
+    }
+}
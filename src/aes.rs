@@ -0,0 +1,577 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The AES block cipher.
+//!
+//! `Key` gives the modes that are implemented in Rust access to the same
+//! (hardware-accelerated, when available) AES implementation that AES-GCM
+//! uses.
+//!
+//! The AES implementations in crypto/aes only implement encryption, because
+//! that is all that AES-GCM needs. `DecryptionKey` is a small, portable
+//! implementation of the AES inverse cipher ([FIPS 197 Section 5.3]) for the
+//! few places that need to decrypt, e.g. PKCS#8 documents encrypted with
+//! AES-CBC. `round` is a single round of the cipher, as AEGIS uses it, built
+//! from the same pieces.
+//!
+//! The S-box is computed instead of looked up, so that there are no
+//! secret-dependent memory accesses or branches. That makes this
+//! implementation much slower than the encryption implementations, so it must
+//! not be used for bulk data.
+//!
+//! [FIPS 197 Section 5.3]:
+//!     http://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197.pdf
+
+// The decryption code is only used by the PKCS#8 code, which requires the
+// `use_heap` feature.
+#![cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+
+use {bssl, c, core, error, polyfill};
+
+pub const BLOCK_LEN: usize = 16;
+
+pub type Block = [u8; BLOCK_LEN];
+
+// Keep this in sync with `AES_MAXNR` in aes.h.
+pub const MAX_ROUNDS: usize = 14;
+
+/// An expanded AES-128, AES-192, or AES-256 key.
+pub struct Key {
+    ctx_buf: [u64; KEY_CTX_BUF_ELEMS],
+}
+
+impl Key {
+    pub fn new(key_bytes: &[u8]) -> Result<Key, error::Unspecified> {
+        let mut key = Key { ctx_buf: [0; KEY_CTX_BUF_ELEMS] };
+        try!(init(polyfill::slice::u64_as_u8_mut(&mut key.ctx_buf),
+                  key_bytes));
+        Ok(key)
+    }
+
+    #[inline]
+    pub fn encrypt_block(&self, block: &mut Block) {
+        self.encrypt_blocks(block)
+    }
+
+    /// Encrypts each block of `in_out` in place. `in_out.len()` must be a
+    /// multiple of `BLOCK_LEN`.
+    #[inline]
+    pub fn encrypt_blocks(&self, in_out: &mut [u8]) {
+        encrypt_blocks(polyfill::slice::u64_as_u8(&self.ctx_buf), in_out)
+    }
+
+    /// The expanded key, in the form that the functions taking a `ctx_buf`
+    /// take.
+    #[inline]
+    pub fn ctx_buf(&self) -> &[u8] { polyfill::slice::u64_as_u8(&self.ctx_buf) }
+
+    #[inline]
+    pub fn xor_key_stream<F>(&self, in_out: &mut [u8], in_prefix_len: usize,
+                             next_counter_block: F)
+                             where F: FnMut(&mut Block) {
+        xor_key_stream(polyfill::slice::u64_as_u8(&self.ctx_buf), in_out,
+                       in_prefix_len, next_counter_block)
+    }
+}
+
+/// Expands `key_bytes` into the start of `ctx_buf`, which must be at least
+/// `KEY_CTX_BUF_LEN` bytes long.
+pub fn init(ctx_buf: &mut [u8], key_bytes: &[u8])
+            -> Result<(), error::Unspecified> {
+    bssl::map_result(unsafe {
+        GFp_aes_block_init(ctx_buf.as_mut_ptr(), ctx_buf.len(),
+                           key_bytes.as_ptr(), key_bytes.len())
+    })
+}
+
+/// Encrypts each block of `in_out` in place using the key that `init`
+/// expanded into `ctx_buf`.
+pub fn encrypt_blocks(ctx_buf: &[u8], in_out: &mut [u8]) {
+    assert!(ctx_buf.len() >= KEY_CTX_BUF_LEN);
+    debug_assert_eq!(in_out.len() % BLOCK_LEN, 0);
+    unsafe {
+        GFp_aes_block_encrypt_blocks(ctx_buf.as_ptr(), in_out.as_mut_ptr(),
+                                     in_out.len() / BLOCK_LEN);
+    }
+}
+
+/// XORs a CTR-mode key stream into the input `in_out[in_prefix_len..]`,
+/// writing the output to `in_out[..(in_out.len() - in_prefix_len)]`.
+/// `next_counter_block` is called to fill in each successive counter block,
+/// starting with the first one, so that each mode can have its own counter
+/// format.
+pub fn xor_key_stream<F>(ctx_buf: &[u8], in_out: &mut [u8],
+                         in_prefix_len: usize, mut next_counter_block: F)
+                         where F: FnMut(&mut Block) {
+    const KEY_STREAM_BLOCKS: usize = 16;
+    let mut key_stream = [0u8; KEY_STREAM_BLOCKS * BLOCK_LEN];
+
+    let len = in_out.len() - in_prefix_len;
+    let mut done = 0;
+    while done < len {
+        let chunk_len = core::cmp::min(key_stream.len(), len - done);
+        let num_blocks = (chunk_len + BLOCK_LEN - 1) / BLOCK_LEN;
+        let key_stream = &mut key_stream[..(num_blocks * BLOCK_LEN)];
+        for block in key_stream.chunks_mut(BLOCK_LEN) {
+            next_counter_block(
+                slice_as_array_ref_mut!(block, BLOCK_LEN).unwrap());
+        }
+        encrypt_blocks(ctx_buf, key_stream);
+        for i in 0..chunk_len {
+            in_out[done + i] = in_out[in_prefix_len + done + i] ^ key_stream[i];
+        }
+        done += chunk_len;
+    }
+}
+
+/// The CMAC subkeys K1 and K2 of [RFC 4493 Section 2.3].
+///
+/// [RFC 4493 Section 2.3]: https://tools.ietf.org/html/rfc4493#section-2.3
+#[derive(Clone)]
+pub struct CmacSubkeys {
+    k1: Block,
+    k2: Block,
+}
+
+impl CmacSubkeys {
+    /// Derives the subkeys of the key that `init` expanded into `ctx_buf`.
+    pub fn new(ctx_buf: &[u8]) -> CmacSubkeys {
+        let mut l = [0u8; BLOCK_LEN];
+        encrypt_blocks(ctx_buf, &mut l);
+        let k1 = dbl(&l);
+        let k2 = dbl(&k1);
+        CmacSubkeys { k1: k1, k2: k2 }
+    }
+}
+
+/// The state of an AES-CMAC ([RFC 4493]) calculation.
+///
+/// [RFC 4493]: https://tools.ietf.org/html/rfc4493
+#[derive(Clone)]
+pub struct CmacState {
+    state: Block,
+    pending: Block,
+    pending_len: usize,
+}
+
+impl CmacState {
+    pub fn new() -> CmacState {
+        CmacState {
+            state: [0u8; BLOCK_LEN],
+            pending: [0u8; BLOCK_LEN],
+            pending_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, ctx_buf: &[u8], mut input: &[u8]) {
+        while !input.is_empty() {
+            // The last block is treated specially, so a full pending block is
+            // only processed once it is known not to be the last one.
+            if self.pending_len == BLOCK_LEN {
+                xor_block(&mut self.state, &self.pending);
+                encrypt_blocks(ctx_buf, &mut self.state);
+                self.pending_len = 0;
+            }
+            let n = core::cmp::min(BLOCK_LEN - self.pending_len, input.len());
+            self.pending[self.pending_len..(self.pending_len + n)]
+                .copy_from_slice(&input[..n]);
+            self.pending_len += n;
+            input = &input[n..];
+        }
+    }
+
+    pub fn finish(self, ctx_buf: &[u8], subkeys: &CmacSubkeys) -> Block {
+        let mut state = self.state;
+        let mut pending = self.pending;
+        if self.pending_len == BLOCK_LEN {
+            xor_block(&mut pending, &subkeys.k1);
+        } else {
+            for b in &mut pending[self.pending_len..] {
+                *b = 0;
+            }
+            pending[self.pending_len] = 0x80;
+            xor_block(&mut pending, &subkeys.k2);
+        }
+        xor_block(&mut state, &pending);
+        encrypt_blocks(ctx_buf, &mut state);
+        state
+    }
+}
+
+/// AES-CMAC of the concatenation of `parts`. See `CmacState`.
+pub fn cmac(ctx_buf: &[u8], subkeys: &CmacSubkeys, parts: &[&[u8]]) -> Block {
+    let mut state = CmacState::new();
+    for part in parts {
+        state.update(ctx_buf, part);
+    }
+    state.finish(ctx_buf, subkeys)
+}
+
+/// Multiplication by x in GF(2**128), with the block interpreted as a
+/// big-endian number, as in RFC 5297 Section 2.3.
+pub fn dbl(block: &Block) -> Block {
+    let mut r = [0u8; BLOCK_LEN];
+    for i in 0..(BLOCK_LEN - 1) {
+        r[i] = (block[i] << 1) | (block[i + 1] >> 7);
+    }
+    let carry_mask = 0u8.wrapping_sub(block[0] >> 7);
+    r[BLOCK_LEN - 1] = (block[BLOCK_LEN - 1] << 1) ^ (0x87 & carry_mask);
+    r
+}
+
+pub fn xor_block(a: &mut Block, b: &Block) {
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a ^= *b;
+    }
+}
+
+// Keep this in sync with `AES_KEY` in aes.h.
+pub const KEY_CTX_BUF_LEN: usize = (4 * 4 * (MAX_ROUNDS + 1)) + 8;
+
+const KEY_CTX_BUF_ELEMS: usize = (KEY_CTX_BUF_LEN + 7) / 8;
+
+extern {
+    fn GFp_aes_block_init(ctx_buf: *mut u8, ctx_buf_len: c::size_t,
+                          key: *const u8, key_len: c::size_t) -> c::int;
+    fn GFp_aes_block_encrypt_blocks(ctx_buf: *const u8, in_out: *mut u8,
+                                    num_blocks: c::size_t);
+}
+
+/// An AES-128, AES-192, or AES-256 key, expanded for decryption.
+pub struct DecryptionKey {
+    round_keys: [[u8; BLOCK_LEN]; MAX_ROUNDS + 1],
+    rounds: usize,
+}
+
+impl DecryptionKey {
+    pub fn new(key_bytes: &[u8]) -> Result<DecryptionKey, error::Unspecified> {
+        // `nk` is the key length in words.
+        let (nk, rounds) = match key_bytes.len() {
+            16 => (4, 10),
+            24 => (6, 12),
+            32 => (8, 14),
+            _ => {
+                return Err(error::Unspecified);
+            },
+        };
+
+        // Key expansion (FIPS 197 Section 5.2).
+        let mut w = [[0u8; 4]; 4 * (MAX_ROUNDS + 1)];
+        for (word, key_word) in w.iter_mut().zip(key_bytes.chunks(4)) {
+            word.copy_from_slice(key_word);
+        }
+        let mut rcon = 1;
+        for i in nk..(4 * (rounds + 1)) {
+            let mut temp = w[i - 1];
+            if i % nk == 0 {
+                temp = [sub_byte(temp[1]) ^ rcon, sub_byte(temp[2]),
+                        sub_byte(temp[3]), sub_byte(temp[0])];
+                rcon = xtime(rcon);
+            } else if nk > 6 && i % nk == 4 {
+                for b in temp.iter_mut() {
+                    *b = sub_byte(*b);
+                }
+            }
+            for j in 0..4 {
+                w[i][j] = w[i - nk][j] ^ temp[j];
+            }
+        }
+
+        let mut round_keys = [[0u8; BLOCK_LEN]; MAX_ROUNDS + 1];
+        for (round_key, words) in round_keys.iter_mut().zip(w.chunks(4)) {
+            for (dest, word) in round_key.chunks_mut(4).zip(words.iter()) {
+                dest.copy_from_slice(word);
+            }
+        }
+
+        Ok(DecryptionKey {
+            round_keys: round_keys,
+            rounds: rounds,
+        })
+    }
+
+    /// Decrypts `block` in place.
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_LEN]) {
+        add_round_key(block, &self.round_keys[self.rounds]);
+        for round in (1..self.rounds).rev() {
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+            add_round_key(block, &self.round_keys[round]);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        inv_sub_bytes(block);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+/// Decrypts `in_out` in place using CBC mode with the initialization vector
+/// `iv`. `in_out.len()` must be a multiple of `BLOCK_LEN`. Padding is not
+/// removed.
+pub fn decrypt_cbc_in_place(key: &DecryptionKey, iv: &[u8; BLOCK_LEN],
+                            in_out: &mut [u8])
+                            -> Result<(), error::Unspecified> {
+    if in_out.len() % BLOCK_LEN != 0 {
+        return Err(error::Unspecified);
+    }
+    let mut previous = *iv;
+    for chunk in in_out.chunks_mut(BLOCK_LEN) {
+        let block = try!(slice_as_array_ref_mut!(chunk, BLOCK_LEN));
+        let ciphertext = *block;
+        key.decrypt_block(block);
+        for (b, p) in block.iter_mut().zip(previous.iter()) {
+            *b ^= *p;
+        }
+        previous = ciphertext;
+    }
+    Ok(())
+}
+
+/// Performs one AES encryption round on `state`: SubBytes, ShiftRows,
+/// MixColumns, and then AddRoundKey with `round_key` (FIPS 197 Section 5.1).
+/// This is the `AESRound` function used by AEGIS.
+pub fn round(state: &mut [u8; BLOCK_LEN], round_key: &[u8; BLOCK_LEN]) {
+    sub_bytes(state);
+    shift_rows(state);
+    mix_columns(state);
+    add_round_key(state, round_key);
+}
+
+fn add_round_key(state: &mut [u8; BLOCK_LEN], round_key: &[u8; BLOCK_LEN]) {
+    for (s, k) in state.iter_mut().zip(round_key.iter()) {
+        *s ^= *k;
+    }
+}
+
+// The state is stored column by column, so row `r` of column `c` is at
+// `state[(4 * c) + r]`.
+fn shift_rows(state: &mut [u8; BLOCK_LEN]) {
+    let input = *state;
+    for c in 0..4 {
+        for r in 1..4 {
+            state[(4 * c) + r] = input[(4 * ((c + r) % 4)) + r];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; BLOCK_LEN]) {
+    let input = *state;
+    for c in 0..4 {
+        for r in 1..4 {
+            state[(4 * c) + r] = input[(4 * ((c + 4 - r) % 4)) + r];
+        }
+    }
+}
+
+fn sub_bytes(state: &mut [u8; BLOCK_LEN]) {
+    for b in state.iter_mut() {
+        *b = sub_byte(*b);
+    }
+}
+
+fn inv_sub_bytes(state: &mut [u8; BLOCK_LEN]) {
+    for b in state.iter_mut() {
+        *b = inv_sub_byte(*b);
+    }
+}
+
+fn mix_columns(state: &mut [u8; BLOCK_LEN]) {
+    for column in state.chunks_mut(4) {
+        let (a0, a1, a2, a3) = (column[0], column[1], column[2], column[3]);
+        column[0] = xtime(a0) ^ xtime(a1) ^ a1 ^ a2 ^ a3;
+        column[1] = a0 ^ xtime(a1) ^ xtime(a2) ^ a2 ^ a3;
+        column[2] = a0 ^ a1 ^ xtime(a2) ^ xtime(a3) ^ a3;
+        column[3] = xtime(a0) ^ a0 ^ a1 ^ a2 ^ xtime(a3);
+    }
+}
+
+fn inv_mix_columns(state: &mut [u8; BLOCK_LEN]) {
+    for column in state.chunks_mut(4) {
+        let (a0, a1, a2, a3) = (column[0], column[1], column[2], column[3]);
+        column[0] = mul(a0, 14) ^ mul(a1, 11) ^ mul(a2, 13) ^ mul(a3, 9);
+        column[1] = mul(a0, 9) ^ mul(a1, 14) ^ mul(a2, 11) ^ mul(a3, 13);
+        column[2] = mul(a0, 13) ^ mul(a1, 9) ^ mul(a2, 14) ^ mul(a3, 11);
+        column[3] = mul(a0, 11) ^ mul(a1, 13) ^ mul(a2, 9) ^ mul(a3, 14);
+    }
+}
+
+fn sub_byte(x: u8) -> u8 {
+    let b = inv(x);
+    b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^
+        b.rotate_left(4) ^ 0x63
+}
+
+fn inv_sub_byte(x: u8) -> u8 {
+    inv(x.rotate_left(1) ^ x.rotate_left(3) ^ x.rotate_left(6) ^ 0x05)
+}
+
+// Multiplication by x in GF(2**8) modulo x**8 + x**4 + x**3 + x + 1.
+fn xtime(a: u8) -> u8 {
+    (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7))
+}
+
+// Multiplication in GF(2**8), without branches.
+fn mul(a: u8, b: u8) -> u8 {
+    let mut a = a;
+    let mut b = b;
+    let mut r = 0;
+    for _ in 0..8 {
+        r ^= a & 0u8.wrapping_sub(b & 1);
+        a = xtime(a);
+        b >>= 1;
+    }
+    r
+}
+
+// The multiplicative inverse in GF(2**8), a**254, with `inv(0) == 0`.
+fn inv(a: u8) -> u8 {
+    let a2 = mul(a, a);
+    let a3 = mul(a2, a);
+    let a6 = mul(a3, a3);
+    let a12 = mul(a6, a6);
+    let a15 = mul(a12, a3);
+    let a30 = mul(a15, a15);
+    let a60 = mul(a30, a30);
+    let a120 = mul(a60, a60);
+    let a126 = mul(a120, a6);
+    let a127 = mul(a126, a);
+    mul(a127, a127)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    fn test_sub_byte() {
+        // A few values from FIPS 197 Figure 7.
+        assert_eq!(sub_byte(0x00), 0x63);
+        assert_eq!(sub_byte(0x01), 0x7c);
+        assert_eq!(sub_byte(0x53), 0xed);
+        assert_eq!(sub_byte(0xff), 0x16);
+
+        for x in 0..256 {
+            let x = x as u8;
+            assert_eq!(inv_sub_byte(sub_byte(x)), x);
+        }
+    }
+
+    #[test]
+    fn test_round() {
+        // The first round of the cipher example in FIPS 197 Appendix B.
+        let mut state = [0x19, 0x3d, 0xe3, 0xbe, 0xa0, 0xf4, 0xe2, 0x2b,
+                         0x9a, 0xc6, 0x8d, 0x2a, 0xe9, 0xf8, 0x48, 0x08];
+        let round_key = [0xa0, 0xfa, 0xfe, 0x17, 0x88, 0x54, 0x2c, 0xb1,
+                         0x23, 0xa3, 0x39, 0x39, 0x2a, 0x6c, 0x76, 0x05];
+        round(&mut state, &round_key);
+        assert_eq!(state, [0xa4, 0x9c, 0x7f, 0xf2, 0x68, 0x9f, 0x35, 0x2b,
+                           0x6b, 0x5b, 0xea, 0x43, 0x02, 0x6a, 0x50, 0x49]);
+    }
+
+    #[test]
+    pub fn test_aes_key() {
+        test::from_file("src/aead/aes_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let input = test_case.consume_bytes("Input");
+            let expected_output = test_case.consume_bytes("Output");
+
+            let key = Key::new(&key).unwrap();
+
+            let mut block = [0u8; BLOCK_LEN];
+            block.copy_from_slice(&input);
+            key.encrypt_block(&mut block);
+            assert_eq!(&block[..], &expected_output[..]);
+
+            // Multiple blocks are encrypted independently.
+            let mut blocks = [0u8; 3 * BLOCK_LEN];
+            for chunk in blocks.chunks_mut(BLOCK_LEN) {
+                chunk.copy_from_slice(&input);
+            }
+            key.encrypt_blocks(&mut blocks);
+            for chunk in blocks.chunks(BLOCK_LEN) {
+                assert_eq!(chunk, &expected_output[..]);
+            }
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    pub fn test_aes_key_len() {
+        assert!(Key::new(&[0u8; 16]).is_ok());
+        assert!(Key::new(&[0u8; 32]).is_ok());
+        assert!(Key::new(&[]).is_err());
+        assert!(Key::new(&[0u8; 15]).is_err());
+        assert!(Key::new(&[0u8; 17]).is_err());
+        assert!(Key::new(&[0u8; 33]).is_err());
+    }
+
+    #[test]
+    fn test_aes_decrypt() {
+        test::from_file("src/aead/aes_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let expected_output = test_case.consume_bytes("Input");
+            let input = test_case.consume_bytes("Output");
+
+            let key = DecryptionKey::new(&key).unwrap();
+            let mut block = [0u8; BLOCK_LEN];
+            block.copy_from_slice(&input);
+            key.decrypt_block(&mut block);
+            assert_eq!(&block[..], &expected_output[..]);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_aes_cbc_decrypt() {
+        test::from_file("src/aes_cbc_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let iv = test_case.consume_bytes("IV");
+            let iv = slice_as_array_ref!(&iv, BLOCK_LEN).unwrap();
+            let ciphertext = test_case.consume_bytes("Ciphertext");
+            let plaintext = test_case.consume_bytes("Plaintext");
+
+            let key = DecryptionKey::new(&key).unwrap();
+            let mut in_out = ciphertext.clone();
+            assert!(decrypt_cbc_in_place(&key, iv, &mut in_out).is_ok());
+            assert_eq!(in_out, plaintext);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_aes_cbc_decrypt_partial_block() {
+        let key = DecryptionKey::new(&[0u8; 16]).unwrap();
+        let mut in_out = [0u8; BLOCK_LEN + 1];
+        assert!(decrypt_cbc_in_place(&key, &[0u8; BLOCK_LEN],
+                                     &mut in_out).is_err());
+    }
+
+    #[test]
+    fn test_aes_decryption_key_len() {
+        for &len in [16, 24, 32].iter() {
+            assert!(DecryptionKey::new(&[0u8; 33][..len]).is_ok());
+        }
+        for &len in [0, 15, 17, 23, 25, 31, 33].iter() {
+            assert!(DecryptionKey::new(&[0u8; 33][..len]).is_err());
+        }
+    }
+}
@@ -12,13 +12,14 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-//! Key Agreement: ECDH, including X25519.
+//! Key Agreement: ECDH, including X25519 and X448.
 //!
 //! # Example
 //!
-//! Note that this example uses X25519, but ECDH using NIST P-256/P-384 is done
-//! exactly the same way, just substituting
-//! `agreement::ECDH_P256`/`agreement::ECDH_P384` for `agreement::X25519`.
+//! Note that this example uses X25519, but ECDH using X448 or NIST
+//! P-256/P-384 is done exactly the same way, just substituting
+//! `agreement::X448` or `agreement::ECDH_P256`/`agreement::ECDH_P384` for
+//! `agreement::X25519`.
 //!
 //! ```
 //! # extern crate untrusted;
@@ -87,6 +88,8 @@ pub use ec::suite_b::ecdh::{ECDH_P256, ECDH_P384};
 
 pub use ec::x25519::X25519;
 
+pub use ec::x448::X448;
+
 
 /// A key agreement algorithm.
 #[cfg_attr(not(test), allow(dead_code))]
@@ -182,7 +185,7 @@ impl<'a> EphemeralPrivateKey {
 /// key material from the key agreement operation and then returns what `kdf`
 /// returns.
 ///
-/// C analogs: `EC_POINT_oct2point` + `ECDH_compute_key`, `X25519`.
+/// C analogs: `EC_POINT_oct2point` + `ECDH_compute_key`, `X25519`, `X448`.
 pub fn agree_ephemeral<F, R, E>(my_private_key: EphemeralPrivateKey,
                                 peer_public_key_alg: &Algorithm,
                                 peer_public_key: untrusted::Input,
@@ -293,6 +296,8 @@ mod tests {
             &ECDH_P384
         } else if curve_name == "X25519" {
             &X25519
+        } else if curve_name == "X448" {
+            &X448
         } else {
             panic!("Unsupported curve: {}", curve_name);
         }
@@ -0,0 +1,615 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! Argon2 password hashing.
+//!
+//! Use `derive` to derive Argon2 outputs. Use `verify` to verify secrets
+//! against previously-derived outputs.
+//!
+//! `hash_encoded` and `verify_encoded` do the same with hashes in the
+//! [PHC string format], which records the parameters and the salt along
+//! with the hash, so that they can be stored in a single field and read by
+//! other implementations.
+//!
+//! Argon2 is a memory-hard function: unlike PBKDF2, whose cost can only be
+//! increased by increasing the amount of computation, Argon2 also requires a
+//! configurable amount of memory, which makes attacks using GPUs and custom
+//! hardware much more expensive. `ARGON2ID` should be used for password
+//! hashing unless there is a specific reason to use `ARGON2I`.
+//!
+//! Argon2 is specified in [RFC 9106], which also gives guidance on choosing
+//! the parameters. This implementation computes the lanes one after another
+//! instead of in parallel, so increasing the number of lanes doesn't reduce
+//! the time that `derive` takes.
+//!
+//! [RFC 9106]: https://tools.ietf.org/html/rfc9106
+//! [PHC string format]:
+//!     https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::argon2;
+//!
+//! // 19 MiB of memory, two passes over it, and a single lane.
+//! let params = argon2::Params::new(19 * 1024, 2, 1).unwrap();
+//!
+//! // The salt must be unique for each password; it would normally be
+//! // generated with a `SecureRandom` and stored next to the hash.
+//! let salt = [0x4a; 16];
+//!
+//! let mut hash = [0u8; 32];
+//! argon2::derive(&argon2::ARGON2ID, &params, &salt,
+//!                b"@74d7]404j|W}6u", &mut hash).unwrap();
+//!
+//! assert!(argon2::verify(&argon2::ARGON2ID, &params, &salt,
+//!                        b"@74d7]404j|W}6u", &hash).is_ok());
+//! assert!(argon2::verify(&argon2::ARGON2ID, &params, &salt,
+//!                        b"wrong password", &hash).is_err());
+//! ```
+
+use {blake2b, constant_time, core, error, phc, polyfill, std};
+
+/// An Argon2 variant.
+pub struct Algorithm {
+    type_id: u32,
+    phc_id: &'static str,
+}
+
+/// Argon2i, which chooses which memory blocks to read independently of the
+/// secret, for resistance against side-channel attacks.
+pub static ARGON2I: Algorithm = Algorithm {
+    type_id: 1,
+    phc_id: "argon2i",
+};
+
+/// Argon2id, which works like Argon2i for the first half of the first pass
+/// over the memory and chooses the remaining blocks using the contents of
+/// the memory, for better resistance against time-memory tradeoffs.
+pub static ARGON2ID: Algorithm = Algorithm {
+    type_id: 2,
+    phc_id: "argon2id",
+};
+
+/// The cost parameters of an Argon2 calculation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    memory_kib: u32,
+    iterations: u32,
+    lanes: u32,
+}
+
+impl Params {
+    /// Constructs the parameters for using `memory_kib` KiB of memory split
+    /// into `lanes` lanes, making `iterations` passes over it.
+    ///
+    /// | Parameter    | RFC 9106 Section 3.1 Term
+    /// |--------------|---------------------------------------
+    /// | `memory_kib` | m (memory size)
+    /// | `iterations` | t (number of passes)
+    /// | `lanes`      | p (degree of parallelism)
+    ///
+    /// Fails if `iterations` is zero, if `lanes` is zero or isn't less than
+    /// 2**24, or if `memory_kib` is less than 8 * `lanes`.
+    pub fn new(memory_kib: u32, iterations: u32, lanes: u32)
+               -> Result<Params, error::Unspecified> {
+        if iterations < 1 || lanes < 1 || lanes > MAX_LANES {
+            return Err(error::Unspecified);
+        }
+        if memory_kib < 2 * SYNC_POINTS * lanes {
+            return Err(error::Unspecified);
+        }
+        Ok(Params {
+            memory_kib: memory_kib,
+            iterations: iterations,
+            lanes: lanes,
+        })
+    }
+
+    /// The amount of memory to use, in KiB.
+    #[inline(always)]
+    pub fn memory_kib(&self) -> u32 { self.memory_kib }
+
+    /// The number of passes over the memory.
+    #[inline(always)]
+    pub fn iterations(&self) -> u32 { self.iterations }
+
+    /// The number of lanes that the memory is split into.
+    #[inline(always)]
+    pub fn lanes(&self) -> u32 { self.lanes }
+}
+
+/// The minimum length of the salt, in bytes.
+pub const MIN_SALT_LEN: usize = 8;
+
+/// The minimum length of the output, in bytes.
+pub const MIN_OUTPUT_LEN: usize = 4;
+
+/// Fills `out` with the key derived using Argon2 with the given inputs.
+///
+/// Do not use `derive` as part of verifying a secret; use `verify` instead,
+/// to minimize the effectiveness of timing attacks.
+///
+/// | Parameter   | RFC 9106 Section 3.1 Term
+/// |-------------|---------------------------------------
+/// | algorithm   | y (Argon2 type)
+/// | params      | m, t, p
+/// | salt        | S (salt)
+/// | secret      | P (password)
+/// | out         | tag
+/// | out.len()   | T (tag length)
+///
+/// Fails if `salt` is shorter than `MIN_SALT_LEN` or if `out` is shorter than
+/// `MIN_OUTPUT_LEN`.
+pub fn derive(algorithm: &'static Algorithm, params: &Params, salt: &[u8],
+              secret: &[u8], out: &mut [u8])
+              -> Result<(), error::Unspecified> {
+    argon2(algorithm, params, salt, secret, &[], &[], out)
+}
+
+/// Verifies that a previously-derived (e.g., using `derive`) Argon2 value
+/// matches the Argon2 value derived from the other inputs.
+///
+/// The comparison is done in constant time to prevent timing attacks.
+///
+/// | Parameter                  | RFC 9106 Section 3.1 Term
+/// |----------------------------|---------------------------------------
+/// | `algorithm`                | y (Argon2 type)
+/// | `params`                   | m, t, p
+/// | `salt`                     | S (salt)
+/// | `secret`                   | P (password)
+/// | `previously_derived`       | tag
+/// | `previously_derived.len()` | T (tag length)
+pub fn verify(algorithm: &'static Algorithm, params: &Params, salt: &[u8],
+              secret: &[u8], previously_derived: &[u8])
+              -> Result<(), error::Unspecified> {
+    let mut derived = vec![0u8; previously_derived.len()];
+    try!(derive(algorithm, params, salt, secret, &mut derived));
+    constant_time::verify_slices_are_equal(&derived, previously_derived)
+}
+
+/// Returns the Argon2 hash of `secret` in the PHC string format, e.g.
+/// `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`, with a hash of `out_len`
+/// bytes.
+///
+/// The salt must be unique for each hash; it should be generated with a
+/// `SecureRandom`.
+///
+/// Fails if `salt` is shorter than `MIN_SALT_LEN` or if `out_len` is less
+/// than `MIN_OUTPUT_LEN`.
+pub fn hash_encoded(algorithm: &'static Algorithm, params: &Params,
+                    salt: &[u8], secret: &[u8], out_len: usize)
+                    -> Result<std::string::String, error::Unspecified> {
+    let mut out = vec![0u8; out_len];
+    try!(derive(algorithm, params, salt, secret, &mut out));
+    Ok(phc::encode(algorithm.phc_id, Some(VERSION),
+                   &[("m", params.memory_kib), ("t", params.iterations),
+                     ("p", params.lanes)],
+                   salt, &out))
+}
+
+/// Verifies that `secret` matches `encoded`, an Argon2 hash in the PHC string
+/// format, e.g. one returned by `hash_encoded`.
+///
+/// Argon2i and Argon2id hashes of version 19 (0x13) are accepted. Hashes
+/// with the optional `keyid` or `data` parameters are rejected. The
+/// comparison is done in constant time to prevent timing attacks.
+pub fn verify_encoded(secret: &[u8], encoded: &str)
+                      -> Result<(), error::Unspecified> {
+    let decoded = try!(phc::decode(encoded));
+    let algorithm = match decoded.id {
+        "argon2i" => &ARGON2I,
+        "argon2id" => &ARGON2ID,
+        _ => {
+            return Err(error::Unspecified);
+        },
+    };
+    if decoded.version != Some(VERSION) {
+        return Err(error::Unspecified);
+    }
+    let mut params = decoded.params;
+    let memory_kib = try!(params.next("m"));
+    let iterations = try!(params.next("t"));
+    let lanes = try!(params.next("p"));
+    try!(params.finish());
+    let params = try!(Params::new(memory_kib, iterations, lanes));
+    verify(algorithm, &params, &decoded.salt, secret, &decoded.hash)
+}
+
+const VERSION: u32 = 0x13;
+
+const MAX_LANES: u32 = (1 << 24) - 1;
+
+// The number of slices that each lane is divided into.
+const SYNC_POINTS: u32 = 4;
+
+const BLOCK_LEN: usize = 1024;
+const BLOCK_WORDS: usize = BLOCK_LEN / 8;
+
+// The number of pseudo-random values in each block of addresses.
+const ADDRESSES_PER_BLOCK: usize = BLOCK_WORDS;
+
+type Block = [u64; BLOCK_WORDS];
+
+// `key` and `associated_data` are K and X, which `derive` doesn't expose.
+fn argon2(algorithm: &Algorithm, params: &Params, salt: &[u8], secret: &[u8],
+          key: &[u8], associated_data: &[u8], out: &mut [u8])
+          -> Result<(), error::Unspecified> {
+    if salt.len() < MIN_SALT_LEN || out.len() < MIN_OUTPUT_LEN {
+        return Err(error::Unspecified);
+    }
+    let tag_len = try!(u32_from_usize(out.len()));
+
+    let h0 = {
+        let mut ctx = blake2b::Context::new(blake2b::MAX_OUTPUT_LEN);
+        for value in &[params.lanes, tag_len, params.memory_kib,
+                       params.iterations, VERSION, algorithm.type_id] {
+            ctx.update(&polyfill::slice::le_u8_from_u32(*value));
+        }
+        for input in &[secret, salt, key, associated_data] {
+            let len = try!(u32_from_usize(input.len()));
+            ctx.update(&polyfill::slice::le_u8_from_u32(len));
+            ctx.update(input);
+        }
+        let mut h0 = [0u8; blake2b::MAX_OUTPUT_LEN];
+        ctx.finish(&mut h0);
+        h0
+    };
+
+    let lanes = params.lanes as usize;
+    let segment_len =
+        (params.memory_kib / (SYNC_POINTS * params.lanes)) as usize;
+    let lane_len = segment_len * SYNC_POINTS as usize;
+    let block_count = lane_len * lanes;
+
+    let mut memory = std::vec::Vec::with_capacity(block_count * BLOCK_WORDS);
+    memory.resize(block_count * BLOCK_WORDS, 0u64);
+
+    for lane in 0..lanes {
+        for i in 0..2 {
+            let mut block = [0u8; BLOCK_LEN];
+            hash_prime(&[&h0, &polyfill::slice::le_u8_from_u32(i as u32),
+                         &polyfill::slice::le_u8_from_u32(lane as u32)],
+                       &mut block);
+            let words = &mut memory[(lane * lane_len + i) * BLOCK_WORDS..]
+                                   [..BLOCK_WORDS];
+            for (word, bytes) in words.iter_mut().zip(block.chunks(8)) {
+                *word = u64_from_le_u8(bytes);
+            }
+        }
+    }
+
+    let filler = Filler {
+        type_id: algorithm.type_id,
+        iterations: params.iterations,
+        lanes: lanes,
+        lane_len: lane_len,
+        segment_len: segment_len,
+        block_count: block_count,
+    };
+    for pass in 0..params.iterations {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                filler.fill_segment(&mut memory, pass, slice, lane);
+            }
+        }
+    }
+
+    let mut last = [0u64; BLOCK_WORDS];
+    for lane in 0..lanes {
+        let block = block_at(&memory, lane * lane_len + lane_len - 1);
+        for (last, word) in last.iter_mut().zip(block) {
+            *last ^= *word;
+        }
+    }
+    let mut last_bytes = [0u8; BLOCK_LEN];
+    for (bytes, word) in last_bytes.chunks_mut(8).zip(last.iter()) {
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (word >> (8 * i)) as u8;
+        }
+    }
+    hash_prime(&[&last_bytes], out);
+
+    Ok(())
+}
+
+struct Filler {
+    type_id: u32,
+    iterations: u32,
+    lanes: usize,
+    lane_len: usize,
+    segment_len: usize,
+    block_count: usize,
+}
+
+impl Filler {
+    fn fill_segment(&self, memory: &mut [u64], pass: u32, slice: u32,
+                    lane: usize) {
+        let data_independent = self.type_id == ARGON2I.type_id ||
+            (self.type_id == ARGON2ID.type_id && pass == 0 &&
+             slice < SYNC_POINTS / 2);
+
+        let mut address_input = [0u64; BLOCK_WORDS];
+        address_input[0] = u64::from(pass);
+        address_input[1] = polyfill::u64_from_usize(lane);
+        address_input[2] = u64::from(slice);
+        address_input[3] = polyfill::u64_from_usize(self.block_count);
+        address_input[4] = u64::from(self.iterations);
+        address_input[5] = u64::from(self.type_id);
+        let mut addresses = [0u64; BLOCK_WORDS];
+
+        // The first two blocks of each lane were filled from H_0.
+        let first = if pass == 0 && slice == 0 { 2 } else { 0 };
+        if data_independent && first != 0 {
+            next_addresses(&mut address_input, &mut addresses);
+        }
+
+        let slice = slice as usize;
+        for index in first..self.segment_len {
+            let offset = slice * self.segment_len + index;
+            let current = lane * self.lane_len + offset;
+            let previous = if offset == 0 {
+                current + self.lane_len - 1
+            } else {
+                current - 1
+            };
+
+            let pseudo_random = if data_independent {
+                if index % ADDRESSES_PER_BLOCK == 0 {
+                    next_addresses(&mut address_input, &mut addresses);
+                }
+                addresses[index % ADDRESSES_PER_BLOCK]
+            } else {
+                block_at(memory, previous)[0]
+            };
+            let j1 = pseudo_random & 0xffffffff;
+            let j2 = pseudo_random >> 32;
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                (j2 % polyfill::u64_from_usize(self.lanes)) as usize
+            };
+            let same_lane = ref_lane == lane;
+
+            // The number of blocks that may be referenced: all the blocks
+            // that have been finished, except for the previous block and,
+            // in other lanes, the blocks in the current slice.
+            let finished = if pass == 0 {
+                slice * self.segment_len
+            } else {
+                self.lane_len - self.segment_len
+            };
+            let area_len = if same_lane {
+                finished + index - 1
+            } else if index == 0 {
+                finished - 1
+            } else {
+                finished
+            };
+
+            // Map `j1` non-uniformly onto the area, preferring recent blocks.
+            let area_len_64 = polyfill::u64_from_usize(area_len);
+            let x = (j1 * j1) >> 32;
+            let relative = area_len - 1 - ((area_len_64 * x) >> 32) as usize;
+            let start = if pass == 0 || slice == SYNC_POINTS as usize - 1 {
+                0
+            } else {
+                (slice + 1) * self.segment_len
+            };
+            let reference =
+                ref_lane * self.lane_len + (start + relative) % self.lane_len;
+
+            let new_block = compress(block_at(memory, previous),
+                                     block_at(memory, reference));
+            let current = &mut memory[current * BLOCK_WORDS..][..BLOCK_WORDS];
+            if pass == 0 {
+                current.copy_from_slice(&new_block);
+            } else {
+                for (current, new) in current.iter_mut().zip(new_block.iter()) {
+                    *current ^= *new;
+                }
+            }
+        }
+    }
+}
+
+fn next_addresses(input: &mut Block, addresses: &mut Block) {
+    const ZERO: Block = [0u64; BLOCK_WORDS];
+    input[6] += 1;
+    *addresses = compress(&ZERO, &compress(&ZERO, input));
+}
+
+fn block_at(memory: &[u64], i: usize) -> &[u64] {
+    &memory[i * BLOCK_WORDS..][..BLOCK_WORDS]
+}
+
+// The compression function G of RFC 9106 Section 3.5.
+fn compress(x: &[u64], y: &[u64]) -> Block {
+    let mut r = [0u64; BLOCK_WORDS];
+    for ((r, x), y) in r.iter_mut().zip(x).zip(y) {
+        *r = x ^ y;
+    }
+
+    let mut q = r;
+    for row in 0..8 {
+        let mut indices = [0; 16];
+        for (i, index) in indices.iter_mut().enumerate() {
+            *index = 16 * row + i;
+        }
+        permute(&mut q, &indices);
+    }
+    for column in 0..8 {
+        let mut indices = [0; 16];
+        for (i, index) in indices.iter_mut().enumerate() {
+            *index = 2 * column + 16 * (i / 2) + (i % 2);
+        }
+        permute(&mut q, &indices);
+    }
+
+    for (q, r) in q.iter_mut().zip(r.iter()) {
+        *q ^= *r;
+    }
+    q
+}
+
+// The permutation P of RFC 9106 Section 3.6, applied to the words of `block`
+// at `indices`.
+fn permute(block: &mut Block, indices: &[usize; 16]) {
+    let mut v = [0u64; 16];
+    for (v, &i) in v.iter_mut().zip(indices) {
+        *v = block[i];
+    }
+    gb(&mut v, 0, 4, 8, 12);
+    gb(&mut v, 1, 5, 9, 13);
+    gb(&mut v, 2, 6, 10, 14);
+    gb(&mut v, 3, 7, 11, 15);
+    gb(&mut v, 0, 5, 10, 15);
+    gb(&mut v, 1, 6, 11, 12);
+    gb(&mut v, 2, 7, 8, 13);
+    gb(&mut v, 3, 4, 9, 14);
+    for (v, &i) in v.iter().zip(indices) {
+        block[i] = *v;
+    }
+}
+
+// The BLAKE2b round function G, with the additions replaced by BlaMka's
+// multiply-and-add.
+#[inline(always)]
+fn gb(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = fbla_mka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = fbla_mka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = fbla_mka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = fbla_mka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+#[inline(always)]
+fn fbla_mka(x: u64, y: u64) -> u64 {
+    let product = (x & 0xffffffff).wrapping_mul(y & 0xffffffff);
+    x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+}
+
+// The variable-length hash function H' of RFC 9106 Section 3.3, applied to
+// the concatenation of `input`.
+fn hash_prime(input: &[&[u8]], out: &mut [u8]) {
+    let out_len = polyfill::slice::le_u8_from_u32(out.len() as u32);
+
+    let first_len = core::cmp::min(out.len(), blake2b::MAX_OUTPUT_LEN);
+    let mut ctx = blake2b::Context::new(first_len);
+    ctx.update(&out_len);
+    for input in input {
+        ctx.update(input);
+    }
+    if out.len() <= blake2b::MAX_OUTPUT_LEN {
+        ctx.finish(out);
+        return;
+    }
+
+    const HALF: usize = blake2b::MAX_OUTPUT_LEN / 2;
+    let mut v = [0u8; blake2b::MAX_OUTPUT_LEN];
+    ctx.finish(&mut v);
+    let r = (out.len() + HALF - 1) / HALF - 2;
+    out[..HALF].copy_from_slice(&v[..HALF]);
+    for i in 1..r {
+        let previous = v;
+        blake2b::hash(&[&previous], &mut v);
+        out[(i * HALF)..][..HALF].copy_from_slice(&v[..HALF]);
+    }
+    blake2b::hash(&[&v], &mut out[(r * HALF)..]);
+}
+
+fn u32_from_usize(value: usize) -> Result<u32, error::Unspecified> {
+    if polyfill::u64_from_usize(value) > u64::from(u32::max_value()) {
+        return Err(error::Unspecified);
+    }
+    Ok(value as u32)
+}
+
+fn u64_from_le_u8(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_argon2() {
+        test::from_file("src/argon2_tests.txt", |section, test_case| {
+            let algorithm = match section {
+                "Argon2i" => &ARGON2I,
+                "Argon2id" => &ARGON2ID,
+                _ => unreachable!(),
+            };
+            let memory_kib = test_case.consume_usize("Memory") as u32;
+            let iterations = test_case.consume_usize("Iterations") as u32;
+            let lanes = test_case.consume_usize("Lanes") as u32;
+            let password = test_case.consume_bytes("Password");
+            let salt = test_case.consume_bytes("Salt");
+            let secret = test_case.consume_bytes("Secret");
+            let associated_data = test_case.consume_bytes("AssociatedData");
+            let expected = test_case.consume_bytes("Tag");
+
+            let params = Params::new(memory_kib, iterations, lanes).unwrap();
+            let mut actual = vec![0u8; expected.len()];
+            assert!(super::argon2(algorithm, &params, &salt, &password,
+                                  &secret, &associated_data, &mut actual)
+                        .is_ok());
+            assert_eq!(actual, expected);
+
+            if secret.is_empty() && associated_data.is_empty() {
+                assert!(verify(algorithm, &params, &salt, &password,
+                               &expected).is_ok());
+                let mut wrong = expected.clone();
+                wrong[0] ^= 1;
+                assert!(verify(algorithm, &params, &salt, &password,
+                               &wrong).is_err());
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_argon2_params() {
+        assert!(Params::new(8, 1, 1).is_ok());
+        assert!(Params::new(7, 1, 1).is_err());
+        assert!(Params::new(8, 0, 1).is_err());
+        assert!(Params::new(8, 1, 0).is_err());
+        assert!(Params::new(15, 1, 2).is_err());
+        assert!(Params::new(16, 1, 2).is_ok());
+        assert!(Params::new(u32::max_value(), 1, (1 << 24) - 1).is_ok());
+        assert!(Params::new(u32::max_value(), 1, 1 << 24).is_err());
+    }
+
+    #[test]
+    fn test_argon2_lengths() {
+        let params = Params::new(8, 1, 1).unwrap();
+        let mut out = [0u8; MIN_OUTPUT_LEN];
+        assert!(derive(&ARGON2ID, &params, &[0; MIN_SALT_LEN - 1], b"",
+                       &mut out).is_err());
+        assert!(derive(&ARGON2ID, &params, &[0; MIN_SALT_LEN], b"",
+                       &mut out).is_ok());
+        assert!(derive(&ARGON2ID, &params, &[0; MIN_SALT_LEN], b"",
+                       &mut out[..(MIN_OUTPUT_LEN - 1)]).is_err());
+        assert!(verify(&ARGON2ID, &params, &[0; MIN_SALT_LEN], b"", &[])
+                    .is_err());
+    }
+}
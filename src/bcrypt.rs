@@ -0,0 +1,298 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! bcrypt password hashing.
+//!
+//! Use `verify` to verify secrets against existing bcrypt hashes in the
+//! modular crypt format, e.g. `$2b$12$...`. Use `hash` to produce such hashes
+//! and `derive` to compute the raw bcrypt output.
+//!
+//! bcrypt is supported for compatibility with existing password databases.
+//! New systems should use `argon2` instead; a system migrating away from
+//! bcrypt can re-hash each password with `argon2` the next time that it is
+//! successfully verified.
+//!
+//! bcrypt is described in [A Future-Adaptable Password Scheme]. This is the
+//! `$2b$` version of OpenBSD, which uses at most the first `MAX_SECRET_LEN`
+//! bytes of the secret.
+//!
+//! [A Future-Adaptable Password Scheme]:
+//!     https://www.usenix.org/legacy/events/usenix99/provos/provos.pdf
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::bcrypt;
+//!
+//! let hash = "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+//!
+//! assert!(bcrypt::verify(b"U*U", hash).is_ok());
+//! assert!(bcrypt::verify(b"wrong password", hash).is_err());
+//! ```
+
+use {blowfish, constant_time, core, error, polyfill};
+
+#[cfg(feature = "use_heap")]
+use std;
+
+/// The length of a bcrypt salt, in bytes.
+pub const SALT_LEN: usize = blowfish::SALT_LEN;
+
+/// The length of a bcrypt output, in bytes.
+pub const OUTPUT_LEN: usize = 23;
+
+/// The maximum number of bytes of the secret that bcrypt uses. Any bytes
+/// after these are ignored.
+pub const MAX_SECRET_LEN: usize = 72;
+
+/// The smallest supported cost.
+pub const MIN_COST: u32 = 4;
+
+/// The largest supported cost.
+pub const MAX_COST: u32 = 31;
+
+/// Fills `out` with the bcrypt output for `secret`, using 2**`cost`
+/// iterations of the key schedule.
+///
+/// Do not use `derive` as part of verifying a secret; use `verify` instead,
+/// to minimize the effectiveness of timing attacks.
+///
+/// Fails if `cost` is less than `MIN_COST` or greater than `MAX_COST`.
+pub fn derive(cost: u32, salt: &[u8; SALT_LEN], secret: &[u8],
+              out: &mut [u8; OUTPUT_LEN]) -> Result<(), error::Unspecified> {
+    if cost < MIN_COST || cost > MAX_COST {
+        return Err(error::Unspecified);
+    }
+
+    // The key is the secret with a terminating NUL, as in C.
+    let secret = &secret[..core::cmp::min(secret.len(), MAX_SECRET_LEN)];
+    let mut key = [0u8; MAX_SECRET_LEN + 1];
+    key[..secret.len()].copy_from_slice(secret);
+    let key = &key[..(secret.len() + 1)];
+
+    // EksBlowfishSetup.
+    let mut state = blowfish::State::new();
+    state.expand_key_with_salt(salt, key);
+    for _ in 0..(1u64 << cost) {
+        state.expand_key(key);
+        state.expand_key(salt);
+    }
+
+    let mut ctext = [0u32; 6];
+    for (word, bytes) in ctext.iter_mut().zip(CTEXT.chunks(4)) {
+        *word = polyfill::slice::u32_from_be_u8(
+            slice_as_array_ref!(bytes, 4).unwrap());
+    }
+    for _ in 0..64 {
+        for block in ctext.chunks_mut(2) {
+            let encrypted = state.encrypt(block[0], block[1]);
+            block[0] = encrypted.0;
+            block[1] = encrypted.1;
+        }
+    }
+
+    let mut output = [0u8; 4 * 6];
+    for (bytes, word) in output.chunks_mut(4).zip(ctext.iter()) {
+        bytes.copy_from_slice(&polyfill::slice::be_u8_from_u32(*word));
+    }
+    out.copy_from_slice(&output[..OUTPUT_LEN]);
+    Ok(())
+}
+
+/// Returns the bcrypt hash of `secret` in the modular crypt format, with the
+/// `$2b$` prefix.
+///
+/// The salt must be unique for each hash; it should be generated with a
+/// `SecureRandom`.
+///
+/// Fails if `cost` is less than `MIN_COST` or greater than `MAX_COST`.
+#[cfg(feature = "use_heap")]
+pub fn hash(cost: u32, salt: &[u8; SALT_LEN], secret: &[u8])
+            -> Result<std::string::String, error::Unspecified> {
+    let mut output = [0u8; OUTPUT_LEN];
+    try!(derive(cost, salt, secret, &mut output));
+
+    let mut encoded = [0u8; HASH_LEN];
+    encoded[..4].copy_from_slice(b"$2b$");
+    encoded[4] = b'0' + (cost / 10) as u8;
+    encoded[5] = b'0' + (cost % 10) as u8;
+    encoded[6] = b'$';
+    encode(salt, &mut encoded[7..(7 + ENCODED_SALT_LEN)]);
+    encode(&output, &mut encoded[(7 + ENCODED_SALT_LEN)..]);
+
+    // The encoding contains only ASCII characters.
+    Ok(std::string::String::from(core::str::from_utf8(&encoded).unwrap()))
+}
+
+/// Verifies that `secret` matches `hash`, a bcrypt hash in the modular crypt
+/// format.
+///
+/// The `$2a$`, `$2b$`, and `$2y$` prefixes are accepted and treated the same.
+/// The comparison is done in constant time to prevent timing attacks.
+pub fn verify(secret: &[u8], hash: &str) -> Result<(), error::Unspecified> {
+    let hash = hash.as_bytes();
+    if hash.len() != HASH_LEN || hash[0] != b'$' || hash[1] != b'2' ||
+       hash[3] != b'$' || hash[6] != b'$' {
+        return Err(error::Unspecified);
+    }
+    match hash[2] {
+        b'a' | b'b' | b'y' => (),
+        _ => {
+            return Err(error::Unspecified);
+        },
+    }
+    let cost = try!(decimal_digit(hash[4])) * 10 +
+               try!(decimal_digit(hash[5]));
+
+    let mut salt = [0u8; SALT_LEN];
+    try!(decode(&hash[7..(7 + ENCODED_SALT_LEN)], &mut salt));
+    let mut expected = [0u8; OUTPUT_LEN];
+    try!(decode(&hash[(7 + ENCODED_SALT_LEN)..], &mut expected));
+
+    let mut actual = [0u8; OUTPUT_LEN];
+    try!(derive(cost, &salt, secret, &mut actual));
+    constant_time::verify_slices_are_equal(&actual, &expected)
+}
+
+const CTEXT: &'static [u8; 24] = b"OrpheanBeholderScryDoubt";
+
+const ENCODED_SALT_LEN: usize = 22;
+const ENCODED_OUTPUT_LEN: usize = 31;
+
+// "$2b$", the two-digit cost, "$", the salt, and the output.
+const HASH_LEN: usize = 7 + ENCODED_SALT_LEN + ENCODED_OUTPUT_LEN;
+
+// bcrypt's base-64 alphabet, which is in a different order than the one of
+// RFC 4648.
+const ALPHABET: &'static [u8; 64] =
+    b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+fn decimal_digit(c: u8) -> Result<u32, error::Unspecified> {
+    if c < b'0' || c > b'9' {
+        return Err(error::Unspecified);
+    }
+    Ok(u32::from(c - b'0'))
+}
+
+// Encodes `input`, without padding, into `out`, which must be exactly long
+// enough.
+#[cfg(feature = "use_heap")]
+fn encode(input: &[u8], out: &mut [u8]) {
+    debug_assert_eq!(out.len(), (input.len() * 8 + 5) / 6);
+    for (i, out) in out.iter_mut().enumerate() {
+        let bit = 6 * i;
+        let byte = bit / 8;
+        let word = (u32::from(input[byte]) << 8) |
+                   input.get(byte + 1).map_or(0, |b| u32::from(*b));
+        let index = (word >> (10 - (bit % 8))) & 0x3f;
+        *out = ALPHABET[index as usize];
+    }
+}
+
+// Decodes `input` into `out`. Any bits of `input` beyond the length of `out`
+// are ignored.
+fn decode(input: &[u8], out: &mut [u8]) -> Result<(), error::Unspecified> {
+    debug_assert_eq!(input.len(), (out.len() * 8 + 5) / 6);
+    polyfill::slice::fill(out, 0);
+    for (i, c) in input.iter().enumerate() {
+        let value = match ALPHABET.iter().position(|a| a == c) {
+            Some(value) => value as u32,
+            None => {
+                return Err(error::Unspecified);
+            },
+        };
+        let bit = 6 * i;
+        let byte = bit / 8;
+        let shifted = (value << 10) >> (bit % 8);
+        out[byte] |= (shifted >> 8) as u8;
+        if byte + 1 < out.len() {
+            out[byte + 1] |= shifted as u8;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {core, std, test};
+
+    #[test]
+    fn test_bcrypt() {
+        test::from_file("src/bcrypt_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let secret = test_case.consume_bytes("Password");
+            let expected = test_case.consume_bytes("Hash");
+            let expected = core::str::from_utf8(&expected).unwrap();
+
+            assert!(verify(&secret, expected).is_ok());
+            let mut wrong = secret.clone();
+            wrong.push(b'!');
+            assert_eq!(verify(&wrong, expected).is_ok(),
+                       secret.len() >= MAX_SECRET_LEN);
+
+            // `hash` always uses the "$2b$" prefix.
+            #[cfg(feature = "use_heap")]
+            {
+                let cost = expected[4..6].parse::<u32>().unwrap();
+                let mut salt = [0u8; SALT_LEN];
+                assert!(decode(expected[7..29].as_bytes(), &mut salt).is_ok());
+                let actual = hash(cost, &salt, &secret).unwrap();
+                assert_eq!(&actual[..4], "$2b$");
+                assert_eq!(&actual[4..], &expected[4..]);
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_bcrypt_malformed() {
+        const HASH: &'static str =
+            "$2b$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        assert!(verify(b"U*U", HASH).is_ok());
+
+        let replace = |i: usize, c: &str| {
+            let mut s = std::string::String::from(&HASH[..i]);
+            s.push_str(c);
+            s.push_str(&HASH[(i + 1)..]);
+            s
+        };
+        assert!(verify(b"U*U", &replace(0, "%")).is_err());
+        assert!(verify(b"U*U", &replace(1, "3")).is_err());
+        assert!(verify(b"U*U", &replace(2, "x")).is_err());
+        assert!(verify(b"U*U", &replace(3, "%")).is_err());
+        assert!(verify(b"U*U", &replace(4, "a")).is_err());
+        assert!(verify(b"U*U", &replace(6, "%")).is_err());
+        assert!(verify(b"U*U", &replace(10, "+")).is_err());
+        assert!(verify(b"U*U", &replace(40, "=")).is_err());
+        assert!(verify(b"U*U", &HASH[..(HASH.len() - 1)]).is_err());
+        assert!(verify(b"U*U", &(std::string::String::from(HASH) + "."))
+                    .is_err());
+
+        // Costs outside of [MIN_COST, MAX_COST] are rejected.
+        assert!(verify(b"U*U", &replace(5, "3")).is_err());
+        assert!(verify(b"U*U", &replace(4, "3")).is_err());
+    }
+
+    #[test]
+    fn test_bcrypt_cost() {
+        let salt = [0u8; SALT_LEN];
+        let mut out = [0u8; OUTPUT_LEN];
+        assert!(derive(MIN_COST - 1, &salt, b"", &mut out).is_err());
+        assert!(derive(MAX_COST + 1, &salt, b"", &mut out).is_err());
+        assert!(derive(MIN_COST, &salt, b"", &mut out).is_ok());
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! BLAKE2b, as specified in [RFC 7693], without a key.
+//!
+//! [RFC 7693]: https://tools.ietf.org/html/rfc7693
+
+use {core, polyfill};
+
+pub const BLOCK_LEN: usize = 128;
+pub const MAX_OUTPUT_LEN: usize = 64;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// A context for multi-step BLAKE2b calculations with a fixed output length.
+pub struct Context {
+    h: [u64; 8],
+
+    // The number of bytes compressed so far, not including `pending`.
+    t: u64,
+
+    // The last block isn't compressed until `finish`, because it must be
+    // compressed with the finalization flag set, so `pending` may be full.
+    pending: [u8; BLOCK_LEN],
+    num_pending: usize,
+
+    output_len: usize,
+}
+
+impl Context {
+    /// Constructs a context for digests of `output_len` bytes.
+    ///
+    /// Panics unless `1 <= output_len <= MAX_OUTPUT_LEN`.
+    pub fn new(output_len: usize) -> Context {
+        assert!(output_len >= 1 && output_len <= MAX_OUTPUT_LEN);
+        let mut h = IV;
+        h[0] ^= 0x01010000 ^ (output_len as u64);
+        Context {
+            h: h,
+            t: 0,
+            pending: [0u8; BLOCK_LEN],
+            num_pending: 0,
+            output_len: output_len,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.num_pending == BLOCK_LEN {
+                self.t += BLOCK_LEN as u64;
+                let pending = self.pending;
+                compress(&mut self.h, &pending, self.t, false);
+                self.num_pending = 0;
+            }
+            let to_copy = core::cmp::min(BLOCK_LEN - self.num_pending,
+                                         data.len());
+            self.pending[self.num_pending..][..to_copy]
+                .copy_from_slice(&data[..to_copy]);
+            self.num_pending += to_copy;
+            data = &data[to_copy..];
+        }
+    }
+
+    /// Writes the digest into `out`, which must be `output_len` bytes long.
+    pub fn finish(mut self, out: &mut [u8]) {
+        assert_eq!(out.len(), self.output_len);
+        self.t += polyfill::u64_from_usize(self.num_pending);
+        for b in &mut self.pending[self.num_pending..] {
+            *b = 0;
+        }
+        let pending = self.pending;
+        compress(&mut self.h, &pending, self.t, true);
+
+        for (out, h) in out.chunks_mut(8).zip(self.h.iter()) {
+            for (i, out) in out.iter_mut().enumerate() {
+                *out = (h >> (8 * i)) as u8;
+            }
+        }
+    }
+}
+
+/// Writes the BLAKE2b digest of the concatenation of `parts`, of length
+/// `out.len()`, into `out`.
+pub fn hash(parts: &[&[u8]], out: &mut [u8]) {
+    let mut ctx = Context::new(out.len());
+    for part in parts {
+        ctx.update(part);
+    }
+    ctx.finish(out);
+}
+
+fn compress(h: &mut [u64; 8], block: &[u8; BLOCK_LEN], t: u64,
+            is_last: bool) {
+    let mut m = [0u64; 16];
+    for (m, word) in m.iter_mut().zip(block.chunks(8)) {
+        *m = word.iter().rev().fold(0, |acc, &b| (acc << 8) | u64::from(b));
+    }
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t;
+    // The high 64 bits of the 128-bit counter are always zero.
+    if is_last {
+        v[14] = !v[14];
+    }
+
+    for round in 0..12 {
+        let s = &SIGMA[round % 10];
+        g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+#[inline(always)]
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64,
+     y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_blake2b() {
+        test::from_file("src/blake2b_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let mut actual = vec![0u8; expected.len()];
+            hash(&[&input], &mut actual);
+            assert_eq!(actual, expected);
+
+            let mut ctx = Context::new(expected.len());
+            for chunk in input.chunks(BLOCK_LEN / 3) {
+                ctx.update(chunk);
+            }
+            let mut actual = vec![0u8; expected.len()];
+            ctx.finish(&mut actual);
+            assert_eq!(actual, expected);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,607 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! BLAKE3, as described in the [BLAKE3 specification].
+//!
+//! BLAKE3 is a hash function, a keyed hash function (a MAC and PRF), and a
+//! key derivation function, all with output of any length. The input is split
+//! into 1 KiB chunks that are the leaves of a binary tree, so large inputs can
+//! be hashed on several threads at once with `Context::update_parallel`.
+//!
+//! The outputs of the three modes are all different for the same input, and
+//! the output for a given input is a prefix of the longer outputs for the
+//! same input.
+//!
+//! Keyed hash values used as MACs must be compared with
+//! `ring::constant_time::verify_slices_are_equal`.
+//!
+//! [BLAKE3 specification]:
+//!     https://github.com/BLAKE3-team/BLAKE3-specs/blob/master/blake3.pdf
+
+use {core, polyfill};
+use polyfill::slice::{le_u8_from_u32, u32_from_le_u8};
+
+#[cfg(feature = "use_heap")]
+use std;
+
+/// The length of the default output, in bytes.
+pub const OUTPUT_LEN: usize = 32;
+
+/// The length of a key for `keyed_hash` and `Context::new_keyed`, in bytes.
+pub const KEY_LEN: usize = 32;
+
+/// A context for multi-step (Init-Update-Finish) BLAKE3 calculations.
+///
+/// # Examples
+///
+/// ```
+/// use ring::blake3;
+///
+/// let one_shot = blake3::hash(b"hello, world");
+///
+/// let mut ctx = blake3::Context::new();
+/// ctx.update(b"hello");
+/// ctx.update(b", ");
+/// ctx.update(b"world");
+/// let multi_part = ctx.finish();
+///
+/// assert_eq!(&one_shot[..], &multi_part[..]);
+/// ```
+#[derive(Clone)]
+pub struct Context {
+    key: Words,
+    flags: u32,
+    chunk: ChunkState,
+
+    // The chaining values of the complete subtrees to the left of `chunk`,
+    // largest first. There is one for each bit set in the number of chunks
+    // before `chunk`.
+    cv_stack: [Words; MAX_DEPTH],
+    cv_stack_len: usize,
+}
+
+impl Context {
+    /// Constructs a new context for hashing.
+    pub fn new() -> Context { Context::with_key_and_flags(&IV, 0) }
+
+    /// Constructs a new context for hashing with `key`.
+    pub fn new_keyed(key: &[u8; KEY_LEN]) -> Context {
+        Context::with_key_and_flags(&words_from_le_bytes(key), KEYED_HASH)
+    }
+
+    /// Constructs a new context for deriving keys from key material in the
+    /// given context.
+    ///
+    /// `context` should be hardcoded, globally unique, and
+    /// application-specific, e.g. "example.com 2019-12-25 16:18:03 session
+    /// tokens v1". It must not depend on secrets or on the key material.
+    pub fn new_derive_key(context: &str) -> Context {
+        let mut context_ctx = Context::with_key_and_flags(&IV,
+                                                          DERIVE_KEY_CONTEXT);
+        context_ctx.update(context.as_bytes());
+        let context_key = context_ctx.finish();
+        Context::with_key_and_flags(&words_from_le_bytes(&context_key),
+                                    DERIVE_KEY_MATERIAL)
+    }
+
+    fn with_key_and_flags(key: &Words, flags: u32) -> Context {
+        Context {
+            key: *key,
+            flags: flags,
+            chunk: ChunkState::new(key, 0, flags),
+            cv_stack: [[0; 8]; MAX_DEPTH],
+            cv_stack_len: 0,
+        }
+    }
+
+    /// Updates the hash with all the data in `data`. `update` may be called
+    /// zero or more times until `finish` or `finish_xof` is called.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        while !data.is_empty() {
+            // The chunk isn't added to the tree until there is more input,
+            // because the last chunk is finished differently if it is the
+            // root.
+            if self.chunk.len() == CHUNK_LEN {
+                self.add_full_chunk();
+            }
+            let n = core::cmp::min(CHUNK_LEN - self.chunk.len(), data.len());
+            self.chunk.update(&data[..n]);
+            data = &data[n..];
+        }
+    }
+
+    /// Updates the hash like `update`, except that complete subtrees of
+    /// `data` are hashed on up to `threads` threads at once.
+    ///
+    /// This is only worthwhile for large inputs; a good rule of thumb is at
+    /// least 128 KiB per thread. Each thread hashes its own copy of its part
+    /// of `data`.
+    #[cfg(feature = "use_heap")]
+    pub fn update_parallel(&mut self, data: &[u8], threads: usize) {
+        // Finish the current chunk so that the subtrees start on a chunk
+        // boundary.
+        let n = (CHUNK_LEN - self.chunk.len()) % CHUNK_LEN;
+        let n = core::cmp::min(n, data.len());
+        self.update(&data[..n]);
+        let data = &data[n..];
+        if data.is_empty() {
+            return;
+        }
+        if self.chunk.len() == CHUNK_LEN {
+            self.add_full_chunk();
+        }
+
+        // Split all but the last chunk of `data` into subtrees. A subtree of
+        // 2**k chunks must start at a multiple of 2**k chunks.
+        let whole_chunks = polyfill_u64((data.len() - 1) / CHUNK_LEN);
+        let max_subtree_chunks =
+            largest_power_of_two_at_most(
+                core::cmp::max(whole_chunks / polyfill_u64(threads.max(1)),
+                               1));
+        let mut subtrees = std::vec::Vec::new();
+        let mut chunk_counter = self.chunk.chunk_counter;
+        let mut offset = 0;
+        while (data.len() - offset) > CHUNK_LEN {
+            let remaining_chunks =
+                polyfill_u64((data.len() - offset - 1) / CHUNK_LEN);
+            let mut subtree_chunks = largest_power_of_two_at_most(
+                core::cmp::min(max_subtree_chunks, remaining_chunks));
+            while chunk_counter % subtree_chunks != 0 {
+                subtree_chunks /= 2;
+            }
+            let subtree_len =
+                polyfill::usize_from_u64(subtree_chunks).unwrap() *
+                CHUNK_LEN;
+            subtrees.push((offset, subtree_len, chunk_counter));
+            chunk_counter += subtree_chunks;
+            offset += subtree_len;
+        }
+        if threads < 2 || subtrees.len() < 2 {
+            self.update(data);
+            return;
+        }
+
+        let key = self.key;
+        let flags = self.flags;
+        let per_thread = (subtrees.len() + threads - 1) / threads;
+        let threads: std::vec::Vec<_> =
+            subtrees.chunks(per_thread).map(|group| {
+                // The subtrees of `group` are contiguous.
+                let start = group[0].0;
+                let (last_offset, last_len, _) = group[group.len() - 1];
+                let input = data[start..(last_offset + last_len)].to_vec();
+                let group = group.to_vec();
+                std::thread::spawn(move || {
+                    group.iter().map(|&(offset, len, chunk_counter)| {
+                        subtree_cv(&input[(offset - start)..][..len], &key,
+                                   chunk_counter, flags)
+                    }).collect::<std::vec::Vec<_>>()
+                })
+            }).collect();
+        let cvs = threads.into_iter()
+                         .flat_map(|thread| thread.join().unwrap());
+
+        for (&(_, len, chunk_counter), cv) in subtrees.iter().zip(cvs) {
+            let subtree_chunks = polyfill_u64(len / CHUNK_LEN);
+            self.push_cv(cv, (chunk_counter + subtree_chunks) / subtree_chunks);
+        }
+        self.chunk = ChunkState::new(&self.key, chunk_counter, self.flags);
+        self.update(&data[offset..]);
+    }
+
+    /// Finalizes the calculation and returns the default-length output.
+    pub fn finish(self) -> [u8; OUTPUT_LEN] {
+        let mut out = [0u8; OUTPUT_LEN];
+        self.finish_xof().squeeze(&mut out);
+        out
+    }
+
+    /// Finalizes the calculation and returns a reader for output of any
+    /// length.
+    pub fn finish_xof(self) -> OutputReader {
+        let mut output = self.chunk.output();
+        for cv in self.cv_stack[..self.cv_stack_len].iter().rev() {
+            output = parent_output(cv, &output.chaining_value(), &self.key,
+                                   self.flags);
+        }
+        OutputReader {
+            output: output,
+            block_counter: 0,
+            block: [0; BLOCK_LEN],
+            position: BLOCK_LEN,
+        }
+    }
+
+    fn add_full_chunk(&mut self) {
+        let cv = self.chunk.output().chaining_value();
+        let total_chunks = self.chunk.chunk_counter + 1;
+        self.push_cv(cv, total_chunks);
+        self.chunk = ChunkState::new(&self.key, total_chunks, self.flags);
+    }
+
+    // Adds the chaining value `cv` of a complete subtree to the tree.
+    // `total_units` is the number of subtrees of the same size, including
+    // this one, to the left of the end of the subtree. Each trailing zero
+    // bit of it means that this subtree completes a larger one.
+    fn push_cv(&mut self, cv: Words, total_units: u64) {
+        let mut cv = cv;
+        let mut total_units = total_units;
+        while total_units & 1 == 0 {
+            self.cv_stack_len -= 1;
+            cv = parent_output(&self.cv_stack[self.cv_stack_len], &cv,
+                               &self.key, self.flags).chaining_value();
+            total_units >>= 1;
+        }
+        self.cv_stack[self.cv_stack_len] = cv;
+        self.cv_stack_len += 1;
+    }
+}
+
+/// A reader for BLAKE3 output of any length.
+pub struct OutputReader {
+    output: Output,
+    block_counter: u64,
+    block: [u8; BLOCK_LEN],
+    position: usize,
+}
+
+impl OutputReader {
+    /// Fills `out` with the next `out.len()` bytes of the output.
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        for b in out.iter_mut() {
+            if self.position == BLOCK_LEN {
+                let words = self.output.root_block(self.block_counter);
+                for (bytes, word) in
+                        self.block.chunks_mut(4).zip(words.iter()) {
+                    bytes.copy_from_slice(&le_u8_from_u32(*word));
+                }
+                self.block_counter += 1;
+                self.position = 0;
+            }
+            *b = self.block[self.position];
+            self.position += 1;
+        }
+    }
+}
+
+/// Returns the BLAKE3 hash of `data`.
+pub fn hash(data: &[u8]) -> [u8; OUTPUT_LEN] {
+    let mut ctx = Context::new();
+    ctx.update(data);
+    ctx.finish()
+}
+
+/// Returns the BLAKE3 keyed hash of `data` with `key`.
+pub fn keyed_hash(key: &[u8; KEY_LEN], data: &[u8]) -> [u8; OUTPUT_LEN] {
+    let mut ctx = Context::new_keyed(key);
+    ctx.update(data);
+    ctx.finish()
+}
+
+/// Fills `out` with key material derived from `key_material` in `context`.
+/// See `Context::new_derive_key` for the requirements on `context`.
+pub fn derive_key(context: &str, key_material: &[u8], out: &mut [u8]) {
+    let mut ctx = Context::new_derive_key(context);
+    ctx.update(key_material);
+    ctx.finish_xof().squeeze(out);
+}
+
+type Words = [u32; 8];
+
+const BLOCK_LEN: usize = 64;
+// `BLOCK_LEN`, as the `u32` that `compress` takes.
+const BLOCK_LEN_U32: u32 = 64;
+const CHUNK_LEN: usize = 1024;
+
+// Enough for 2**64 bytes of input.
+const MAX_DEPTH: usize = 54;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+// The same as the initial hash value of SHA-256.
+const IV: Words = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// The message word permutation applied 0 to 6 times, for each round.
+const MSG_SCHEDULE: [[usize; 16]; 7] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8],
+    [3, 4, 10, 12, 13, 2, 7, 14, 6, 5, 9, 0, 11, 15, 8, 1],
+    [10, 7, 12, 9, 14, 3, 13, 15, 4, 0, 11, 2, 5, 8, 1, 6],
+    [12, 13, 9, 11, 15, 10, 14, 8, 7, 2, 5, 3, 0, 1, 6, 4],
+    [9, 14, 11, 5, 8, 12, 15, 1, 13, 3, 0, 10, 2, 6, 4, 7],
+    [11, 15, 5, 0, 1, 9, 8, 6, 14, 10, 2, 12, 3, 4, 7, 13],
+];
+
+#[inline(always)]
+fn g(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32,
+     y: u32) {
+    s[a] = s[a].wrapping_add(s[b]).wrapping_add(x);
+    s[d] = (s[d] ^ s[a]).rotate_right(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_right(12);
+    s[a] = s[a].wrapping_add(s[b]).wrapping_add(y);
+    s[d] = (s[d] ^ s[a]).rotate_right(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_right(7);
+}
+
+fn compress(cv: &Words, block: &[u32; 16], counter: u64, block_len: u32,
+            flags: u32) -> [u32; 16] {
+    let counter = polyfill::u32s_from_u64(counter);
+    let mut s = [
+        cv[0], cv[1], cv[2], cv[3], cv[4], cv[5], cv[6], cv[7],
+        IV[0], IV[1], IV[2], IV[3],
+        counter[0], counter[1], block_len, flags,
+    ];
+    for m in MSG_SCHEDULE.iter() {
+        // Columns.
+        g(&mut s, 0, 4, 8, 12, block[m[0]], block[m[1]]);
+        g(&mut s, 1, 5, 9, 13, block[m[2]], block[m[3]]);
+        g(&mut s, 2, 6, 10, 14, block[m[4]], block[m[5]]);
+        g(&mut s, 3, 7, 11, 15, block[m[6]], block[m[7]]);
+        // Diagonals.
+        g(&mut s, 0, 5, 10, 15, block[m[8]], block[m[9]]);
+        g(&mut s, 1, 6, 11, 12, block[m[10]], block[m[11]]);
+        g(&mut s, 2, 7, 8, 13, block[m[12]], block[m[13]]);
+        g(&mut s, 3, 4, 9, 14, block[m[14]], block[m[15]]);
+    }
+    for i in 0..8 {
+        s[i] ^= s[i + 8];
+        s[i + 8] ^= cv[i];
+    }
+    s
+}
+
+fn words_from_le_bytes(bytes: &[u8; KEY_LEN]) -> Words {
+    let mut words = [0u32; 8];
+    for (word, bytes) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32_from_le_u8(slice_as_array_ref!(bytes, 4).unwrap());
+    }
+    words
+}
+
+fn block_words(block: &[u8; BLOCK_LEN]) -> [u32; 16] {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+        *word = u32_from_le_u8(slice_as_array_ref!(bytes, 4).unwrap());
+    }
+    words
+}
+
+// The inputs to the compression of a node of the tree, which is finished
+// differently depending on whether the node is the root.
+#[derive(Clone)]
+struct Output {
+    cv: Words,
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> Words {
+        let s = compress(&self.cv, &self.block, self.counter, self.block_len,
+                         self.flags);
+        [s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]]
+    }
+
+    fn root_block(&self, block_counter: u64) -> [u32; 16] {
+        compress(&self.cv, &self.block, block_counter, self.block_len,
+                 self.flags | ROOT)
+    }
+}
+
+fn parent_output(left: &Words, right: &Words, key: &Words, flags: u32)
+                 -> Output {
+    let mut block = [0u32; 16];
+    block[..8].copy_from_slice(left);
+    block[8..].copy_from_slice(right);
+    Output {
+        cv: *key,
+        block: block,
+        counter: 0,
+        block_len: BLOCK_LEN_U32,
+        flags: flags | PARENT,
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    cv: Words,
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &Words, chunk_counter: u64, flags: u32) -> ChunkState {
+        ChunkState {
+            cv: *key,
+            chunk_counter: chunk_counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags: flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 { CHUNK_START } else { 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        while !data.is_empty() {
+            // The last block is kept until the end of the chunk, because it
+            // is compressed with different flags.
+            if self.block_len == BLOCK_LEN {
+                let words = block_words(&self.block);
+                let s = compress(&self.cv, &words, self.chunk_counter,
+                                 BLOCK_LEN_U32,
+                                 self.flags | self.start_flag());
+                self.cv.copy_from_slice(&s[..8]);
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+            let n = core::cmp::min(BLOCK_LEN - self.block_len, data.len());
+            self.block[self.block_len..(self.block_len + n)]
+                .copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            cv: self.cv,
+            block: block_words(&self.block),
+            counter: self.chunk_counter,
+            block_len: polyfill::u32_from_usize(self.block_len).unwrap(),
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+// Returns the chaining value of the subtree of whole chunks in `input`, the
+// number of which is a power of two, which starts at chunk `chunk_counter`.
+#[cfg(feature = "use_heap")]
+fn subtree_cv(input: &[u8], key: &Words, chunk_counter: u64, flags: u32)
+              -> Words {
+    if input.len() == CHUNK_LEN {
+        let mut chunk = ChunkState::new(key, chunk_counter, flags);
+        chunk.update(input);
+        return chunk.output().chaining_value();
+    }
+    let (left, right) = input.split_at(input.len() / 2);
+    let right_counter = chunk_counter + polyfill_u64(left.len() / CHUNK_LEN);
+    let left = subtree_cv(left, key, chunk_counter, flags);
+    let right = subtree_cv(right, key, right_counter, flags);
+    parent_output(&left, &right, key, flags).chaining_value()
+}
+
+#[cfg(feature = "use_heap")]
+fn largest_power_of_two_at_most(n: u64) -> u64 {
+    debug_assert!(n > 0);
+    1 << (63 - n.leading_zeros())
+}
+
+#[cfg(feature = "use_heap")]
+#[inline(always)]
+fn polyfill_u64(n: usize) -> u64 { polyfill::u64_from_usize(n) }
+
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+    use super::*;
+    use test;
+
+    const KEY: &'static [u8; KEY_LEN] = b"whats the Elvish word for friend";
+    const CONTEXT: &'static str =
+        "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    fn input(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn xof_output(ctx: Context, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        ctx.finish_xof().squeeze(&mut out);
+        out
+    }
+
+    #[test]
+    fn test_blake3() {
+        test::from_file("src/blake3_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let input = input(test_case.consume_usize("Len"));
+            let expected_hash = test_case.consume_bytes("Hash");
+            let expected_keyed_hash = test_case.consume_bytes("KeyedHash");
+            let expected_derive_key = test_case.consume_bytes("DeriveKey");
+
+            assert_eq!(&hash(&input)[..], &expected_hash[..OUTPUT_LEN]);
+            assert_eq!(&keyed_hash(KEY, &input)[..],
+                       &expected_keyed_hash[..OUTPUT_LEN]);
+            let mut derived = vec![0u8; expected_derive_key.len()];
+            derive_key(CONTEXT, &input, &mut derived);
+            assert_eq!(derived, expected_derive_key);
+
+            // Updating a byte at a time and squeezing the output in pieces
+            // give the same output.
+            let mut ctx = Context::new();
+            for b in input.chunks(1) {
+                ctx.update(b);
+            }
+            let mut reader = ctx.finish_xof();
+            let mut actual = vec![0u8; expected_hash.len()];
+            for piece in actual.chunks_mut(7) {
+                reader.squeeze(piece);
+            }
+            assert_eq!(actual, expected_hash);
+
+            let mut ctx = Context::new_keyed(KEY);
+            ctx.update(&input);
+            assert_eq!(xof_output(ctx, expected_keyed_hash.len()),
+                       expected_keyed_hash);
+
+            Ok(())
+        });
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_blake3_update_parallel() {
+        let data = input(100 * 1024 + 17);
+        for &prefix_len in &[0, 1, 1024, 1500, 3 * 1024] {
+            let mut expected = Context::new_keyed(KEY);
+            expected.update(&data[..prefix_len]);
+            expected.update(&data[prefix_len..]);
+            let expected = xof_output(expected, 100);
+
+            for threads in 1..6 {
+                let mut ctx = Context::new_keyed(KEY);
+                ctx.update(&data[..prefix_len]);
+                ctx.update_parallel(&data[prefix_len..], threads);
+                assert_eq!(xof_output(ctx, 100), expected);
+
+                // Updating in parallel after the last chunk has been
+                // completed, and with input that ends on a chunk boundary.
+                let mut ctx = Context::new_keyed(KEY);
+                ctx.update(&data[..prefix_len]);
+                ctx.update_parallel(&data[prefix_len..(100 * 1024)],
+                                    threads);
+                ctx.update_parallel(&data[(100 * 1024)..], threads);
+                assert_eq!(xof_output(ctx, 100), expected);
+            }
+        }
+    }
+}
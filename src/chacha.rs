@@ -13,8 +13,7 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-use c;
-use core;
+use {c, core, poly1305};
 use polyfill::slice::u32_from_le_u8;
 
 pub type Key = [u32; KEY_LEN_IN_BYTES / 4];
@@ -75,6 +74,54 @@ pub fn make_counter(nonce: &[u8; NONCE_LEN], counter: u32) -> Counter {
      u32_from_le_u8(slice_as_array_ref!(&nonce[8..12], 4).unwrap())]
 }
 
+/// Derives a Poly1305 key from the first 32 bytes of the key stream for
+/// `counter`, as described in RFC 7539 Section 2.6.
+pub fn derive_poly1305_key(key: &Key, counter: &Counter) -> poly1305::Key {
+    let mut bytes = [0u8; poly1305::KEY_LEN];
+    chacha20_xor_in_place(key, counter, &mut bytes);
+    poly1305::Key::new(&bytes)
+}
+
+/// HChaCha20 as described in [draft-irtf-cfrg-xchacha-03 Section 2.2]: the
+/// ChaCha20 block function without the final addition of the input, keeping
+/// only the first and last rows of the state as the output key.
+///
+/// [draft-irtf-cfrg-xchacha-03 Section 2.2]:
+///     https://tools.ietf.org/html/draft-irtf-cfrg-xchacha-03#section-2.2
+pub fn hchacha20(key: &Key, nonce: &[u8; HCHACHA20_NONCE_LEN]) -> Key {
+    let mut x = [
+        0x61707865, 0x3320646e, 0x79622d32, 0x6b206574,
+        key[0], key[1], key[2], key[3], key[4], key[5], key[6], key[7],
+        u32_from_le_u8(slice_as_array_ref!(&nonce[0..4], 4).unwrap()),
+        u32_from_le_u8(slice_as_array_ref!(&nonce[4..8], 4).unwrap()),
+        u32_from_le_u8(slice_as_array_ref!(&nonce[8..12], 4).unwrap()),
+        u32_from_le_u8(slice_as_array_ref!(&nonce[12..16], 4).unwrap()),
+    ];
+    for _ in 0..10 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    [x[0], x[1], x[2], x[3], x[12], x[13], x[14], x[15]]
+}
+
+#[inline(always)]
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
 extern {
     fn GFp_ChaCha20_ctr32(out: *mut u8, in_: *const u8, in_len: c::size_t,
                           key: &Key, counter: &Counter);
@@ -84,6 +131,8 @@ pub const KEY_LEN_IN_BYTES: usize = 256 / 8;
 
 pub const NONCE_LEN: usize = 12; /* 96 bits */
 
+pub const HCHACHA20_NONCE_LEN: usize = 16; /* 128 bits */
+
 #[cfg(test)]
 mod tests {
     use test;
@@ -131,6 +180,21 @@ mod tests {
         });
     }
 
+    // The test vector from draft-irtf-cfrg-xchacha-03 Section 2.2.1.
+    #[test]
+    pub fn hchacha20_test() {
+        let mut key_bytes = [0u8; KEY_LEN_IN_BYTES];
+        for (i, b) in key_bytes.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let key = key_from_bytes(&key_bytes);
+        let nonce = [0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a,
+                     0x00, 0x00, 0x00, 0x00, 0x31, 0x41, 0x59, 0x27];
+        let expected = [0x423b4182, 0xfe7bb227, 0x50420ed3, 0x737d878a,
+                        0xd5e4f9a0, 0x53a8748a, 0x13c42ec1, 0xdcecd326];
+        assert_eq!(hchacha20(&key, &nonce), expected);
+    }
+
     fn chacha20_test_case_inner(key: &Key, ctr: &Counter, input: &[u8],
                                 expected: &[u8], len: usize,
                                 in_out_buf: &mut [u8]) {
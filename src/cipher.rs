@@ -0,0 +1,243 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! Unauthenticated ciphers.
+//!
+//! These are building blocks for protocols that specify their own
+//! constructions, e.g. TLS and QUIC header protection. They provide
+//! confidentiality only: an attacker can flip bits of the ciphertext to flip
+//! the same bits of the plaintext without being detected. Use `ring::aead`
+//! instead unless a protocol requires one of these.
+
+use {aes, chacha, error, polyfill};
+
+/// The length of a ChaCha20 key.
+pub const CHACHA20_KEY_LEN: usize = chacha::KEY_LEN_IN_BYTES;
+
+/// The length of a ChaCha20 nonce.
+pub const CHACHA20_NONCE_LEN: usize = chacha::NONCE_LEN;
+
+/// A ChaCha20 key, as described in [RFC 7539].
+///
+/// [RFC 7539]: https://tools.ietf.org/html/rfc7539
+pub struct ChaCha20Key {
+    key: chacha::Key,
+}
+
+impl ChaCha20Key {
+    /// Constructs a new `ChaCha20Key`.
+    pub fn new(key_bytes: &[u8; CHACHA20_KEY_LEN]) -> ChaCha20Key {
+        ChaCha20Key { key: chacha::key_from_bytes(key_bytes) }
+    }
+
+    /// XORs the key stream for `nonce`, starting at the 64-byte block
+    /// `counter`, into `in_out`.
+    ///
+    /// The same (`nonce`, `counter`) pair must never be used twice with the
+    /// same key, except to decrypt what was encrypted with it. Fails without
+    /// modifying `in_out` if the 32-bit block counter would wrap around
+    /// before the end of `in_out`.
+    pub fn xor_in_place(&self, nonce: &[u8; CHACHA20_NONCE_LEN], counter: u32,
+                        in_out: &mut [u8])
+                        -> Result<(), error::Unspecified> {
+        let blocks = (polyfill::u64_from_usize(in_out.len()) +
+                      (CHACHA20_BLOCK_LEN - 1)) / CHACHA20_BLOCK_LEN;
+        if u64::from(counter) + blocks > (1u64 << 32) {
+            return Err(error::Unspecified);
+        }
+        let counter = chacha::make_counter(nonce, counter);
+        chacha::chacha20_xor_in_place(&self.key, &counter, in_out);
+        Ok(())
+    }
+}
+
+const CHACHA20_BLOCK_LEN: u64 = 64;
+
+/// The length of an AES block, and so of an AES-CTR counter block.
+pub const AES_BLOCK_LEN: usize = aes::BLOCK_LEN;
+
+/// An AES-128, AES-192, or AES-256 key for CTR mode, as described in
+/// [NIST SP 800-38A Section 6.5].
+///
+/// AES-CTR provides no integrity protection at all, and reusing a counter
+/// block with the same key reveals the XOR of the plaintexts. It is only
+/// provided for formats that require it; new protocols should use
+/// `ring::aead` instead.
+///
+/// [NIST SP 800-38A Section 6.5]:
+///     http://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a.pdf
+pub struct AesCtrKey {
+    key: aes::Key,
+}
+
+impl AesCtrKey {
+    /// Constructs a new `AesCtrKey`. `key_bytes` must be 16 bytes
+    /// (AES-128), 24 bytes (AES-192), or 32 bytes (AES-256) long.
+    pub fn new(key_bytes: &[u8]) -> Result<AesCtrKey, error::Unspecified> {
+        Ok(AesCtrKey { key: try!(aes::Key::new(key_bytes)) })
+    }
+
+    /// XORs the key stream starting at `counter_block` into `in_out`.
+    ///
+    /// The counter block is incremented as a 128-bit big-endian number for
+    /// each block of the key stream, so formats that use only part of the
+    /// block as the counter must ensure it doesn't overflow into the rest.
+    /// No counter block may ever be used twice with the same key.
+    pub fn xor_in_place(&self, counter_block: &[u8; AES_BLOCK_LEN],
+                        in_out: &mut [u8]) {
+        let mut counter = *counter_block;
+        self.key.xor_key_stream(in_out, 0, |block| {
+            *block = counter;
+            // The counter blocks aren't secret, so this doesn't need to be
+            // constant-time.
+            for b in counter.iter_mut().rev() {
+                *b = b.wrapping_add(1);
+                if *b != 0 {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// An AES-128, AES-192, or AES-256 key that only encrypts single blocks, for
+/// deriving masks from samples of ciphertext as QUIC header protection does.
+///
+/// A mask must only be used to hide values that are already protected some
+/// other way, as the packet number is in QUIC. This does not encrypt multiple
+/// blocks in ECB mode; it takes one block at a time so that it can't be
+/// misused as a general-purpose cipher.
+pub struct AesMaskKey {
+    key: aes::Key,
+}
+
+impl AesMaskKey {
+    /// Constructs a new `AesMaskKey`. `key_bytes` must be 16 bytes
+    /// (AES-128), 24 bytes (AES-192), or 32 bytes (AES-256) long.
+    pub fn new(key_bytes: &[u8]) -> Result<AesMaskKey, error::Unspecified> {
+        Ok(AesMaskKey { key: try!(aes::Key::new(key_bytes)) })
+    }
+
+    /// Returns the AES encryption of `sample`.
+    pub fn new_mask(&self, sample: &[u8; AES_BLOCK_LEN])
+                    -> [u8; AES_BLOCK_LEN] {
+        let mut mask = *sample;
+        self.key.encrypt_block(&mut mask);
+        mask
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    fn test_chacha20() {
+        test::from_file("src/chacha_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let key = try!(slice_as_array_ref!(&key, CHACHA20_KEY_LEN));
+            let counter = test_case.consume_usize("Ctr") as u32;
+            let nonce = test_case.consume_bytes("Nonce");
+            let nonce = try!(slice_as_array_ref!(&nonce, CHACHA20_NONCE_LEN));
+            let input = test_case.consume_bytes("Input");
+            let output = test_case.consume_bytes("Output");
+
+            let key = ChaCha20Key::new(key);
+            let mut in_out = input.clone();
+            assert!(key.xor_in_place(nonce, counter, &mut in_out).is_ok());
+            assert_eq!(&in_out[..], &output[..]);
+
+            // Encrypting the input in 64-byte pieces, advancing the counter
+            // for each one, gives the same output.
+            let mut in_out = input.clone();
+            for (i, chunk) in in_out.chunks_mut(64).enumerate() {
+                assert!(key.xor_in_place(nonce, counter + (i as u32), chunk)
+                           .is_ok());
+            }
+            assert_eq!(&in_out[..], &output[..]);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_chacha20_counter_overflow() {
+        let key = ChaCha20Key::new(&[0u8; CHACHA20_KEY_LEN]);
+        let nonce = [0u8; CHACHA20_NONCE_LEN];
+        let mut in_out = [0u8; 129];
+        assert!(key.xor_in_place(&nonce, 0xffffffff, &mut in_out[..65])
+                   .is_err());
+        assert!(key.xor_in_place(&nonce, 0xfffffffe, &mut in_out).is_err());
+        assert_eq!(&in_out[..], &[0u8; 129][..]);
+        assert!(key.xor_in_place(&nonce, 0xffffffff, &mut in_out[..64])
+                   .is_ok());
+        assert!(key.xor_in_place(&nonce, 0xfffffffe, &mut in_out[..128])
+                   .is_ok());
+        assert!(key.xor_in_place(&nonce, 0xffffffff, &mut []).is_ok());
+    }
+
+    #[test]
+    fn test_aes_ctr() {
+        test::from_file("src/aes_ctr_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let iv = test_case.consume_bytes("IV");
+            let iv = try!(slice_as_array_ref!(&iv, AES_BLOCK_LEN));
+            let plaintext = test_case.consume_bytes("Plaintext");
+            let ciphertext = test_case.consume_bytes("Ciphertext");
+
+            let key = AesCtrKey::new(&key).unwrap();
+
+            // Every prefix of the plaintext encrypts to the same prefix of
+            // the ciphertext, including partial blocks.
+            for len in 0..(plaintext.len() + 1) {
+                let mut in_out = plaintext[..len].to_vec();
+                key.xor_in_place(iv, &mut in_out);
+                assert_eq!(&in_out[..], &ciphertext[..len]);
+                key.xor_in_place(iv, &mut in_out);
+                assert_eq!(&in_out[..], &plaintext[..len]);
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_aes_ctr_key_len() {
+        assert!(AesCtrKey::new(&[0u8; 16]).is_ok());
+        assert!(AesCtrKey::new(&[0u8; 32]).is_ok());
+        assert!(AesCtrKey::new(&[0u8; 24]).is_ok());
+        assert!(AesCtrKey::new(&[0u8; 15]).is_err());
+        assert!(AesCtrKey::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_aes_mask() {
+        test::from_file("src/aead/aes_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let input = test_case.consume_bytes("Input");
+            let input = try!(slice_as_array_ref!(&input, AES_BLOCK_LEN));
+            let expected_output = test_case.consume_bytes("Output");
+
+            let key = AesMaskKey::new(&key).unwrap();
+            assert_eq!(&key.new_mask(input)[..], &expected_output[..]);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! CMAC, the block-cipher-based MAC specified in [RFC 4493] and
+//! [NIST SP 800-38B], using AES.
+//!
+//! A `Key` is constructed once for a key value, which expands the AES key and
+//! derives the CMAC subkeys. It can then be used for any number of `sign` and
+//! `verify` operations.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{cmac, rand};
+//! use ring::rand::SecureRandom;
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! let rng = rand::SystemRandom::new();
+//! let mut key_value = [0u8; 16];
+//! try!(rng.fill(&mut key_value));
+//! let key = try!(cmac::Key::new(&cmac::AES_128, &key_value));
+//!
+//! let tag = cmac::sign(&key, b"hello, world");
+//!
+//! try!(cmac::verify(&key, b"hello, world", &tag));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [RFC 4493]: https://tools.ietf.org/html/rfc4493
+//! [NIST SP 800-38B]:
+//!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38B.pdf
+
+use {aes, constant_time, error};
+
+/// A CMAC algorithm.
+pub struct Algorithm {
+    key_len: usize,
+}
+
+impl Algorithm {
+    /// The length of the key, in bytes.
+    #[inline(always)]
+    pub fn key_len(&self) -> usize { self.key_len }
+}
+
+/// CMAC using AES-128, as specified in RFC 4493.
+pub static AES_128: Algorithm = Algorithm { key_len: 128 / 8 };
+
+/// CMAC using AES-256.
+pub static AES_256: Algorithm = Algorithm { key_len: 256 / 8 };
+
+/// The length of a tag, in bytes.
+pub const TAG_LEN: usize = aes::BLOCK_LEN;
+
+/// The minimum length of a truncated tag that `verify` accepts, in bytes.
+/// SP 800-38B Appendix A recommends against shorter tags.
+pub const MIN_TAG_LEN: usize = 64 / 8;
+
+/// A key for signing and verifying with CMAC.
+pub struct Key {
+    algorithm: &'static Algorithm,
+    aes_key: aes::Key,
+    subkeys: aes::CmacSubkeys,
+}
+
+impl Key {
+    /// Constructs a key from `key_value`.
+    ///
+    /// Fails if `key_value.len()` isn't `algorithm.key_len()`.
+    pub fn new(algorithm: &'static Algorithm, key_value: &[u8])
+               -> Result<Key, error::Unspecified> {
+        if key_value.len() != algorithm.key_len {
+            return Err(error::Unspecified);
+        }
+        let aes_key = try!(aes::Key::new(key_value));
+        let subkeys = aes::CmacSubkeys::new(aes_key.ctx_buf());
+        Ok(Key {
+            algorithm: algorithm,
+            aes_key: aes_key,
+            subkeys: subkeys,
+        })
+    }
+
+    /// The algorithm for the key.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+}
+
+/// A context for multi-step (Init-Update-Finish) CMAC signing.
+///
+/// Use `sign` for single-step CMAC signing.
+#[derive(Clone)]
+pub struct Context<'a> {
+    key: &'a Key,
+    state: aes::CmacState,
+}
+
+impl<'a> Context<'a> {
+    /// Constructs a new CMAC signing context using the given key.
+    pub fn with_key(key: &'a Key) -> Context<'a> {
+        Context {
+            key: key,
+            state: aes::CmacState::new(),
+        }
+    }
+
+    /// Updates the CMAC with all the data in `data`. `update` may be called
+    /// zero or more times until `sign` is called.
+    pub fn update(&mut self, data: &[u8]) {
+        self.state.update(self.key.aes_key.ctx_buf(), data);
+    }
+
+    /// Finalizes the CMAC calculation and returns the tag.
+    pub fn sign(self) -> [u8; TAG_LEN] {
+        self.state.finish(self.key.aes_key.ctx_buf(), &self.key.subkeys)
+    }
+}
+
+/// Calculates the CMAC of `data` using the key `key` in one step.
+pub fn sign(key: &Key, data: &[u8]) -> [u8; TAG_LEN] {
+    let mut ctx = Context::with_key(key);
+    ctx.update(data);
+    ctx.sign()
+}
+
+/// Calculates the CMAC of `data` and checks in constant time that `tag` is
+/// equal to it or to its first `tag.len()` bytes.
+///
+/// Fails if the tags differ or if `tag` is shorter than `MIN_TAG_LEN` or
+/// longer than `TAG_LEN`.
+pub fn verify(key: &Key, data: &[u8], tag: &[u8])
+              -> Result<(), error::Unspecified> {
+    if tag.len() < MIN_TAG_LEN || tag.len() > TAG_LEN {
+        return Err(error::Unspecified);
+    }
+    let expected = sign(key, data);
+    constant_time::verify_slices_are_equal(&expected[..tag.len()], tag)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_cmac() {
+        test::from_file("src/cmac_tests.txt", |section, test_case| {
+            let algorithm = match section {
+                "AES-128" => &AES_128,
+                "AES-256" => &AES_256,
+                _ => unreachable!(),
+            };
+            let key_value = test_case.consume_bytes("Key");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Tag");
+
+            let key = Key::new(algorithm, &key_value).unwrap();
+            assert_eq!(&sign(&key, &input)[..], &expected[..]);
+
+            let mut ctx = Context::with_key(&key);
+            for chunk in input.chunks(7) {
+                ctx.update(chunk);
+            }
+            assert_eq!(&ctx.sign()[..], &expected[..]);
+
+            assert!(verify(&key, &input, &expected).is_ok());
+            assert!(verify(&key, &input, &expected[..MIN_TAG_LEN]).is_ok());
+            assert!(verify(&key, &input, &expected[..(MIN_TAG_LEN - 1)])
+                        .is_err());
+            let mut wrong = expected.clone();
+            wrong[TAG_LEN - 1] ^= 1;
+            assert!(verify(&key, &input, &wrong).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_cmac_key_len() {
+        assert!(Key::new(&AES_128, &[0; 16]).is_ok());
+        assert!(Key::new(&AES_128, &[0; 24]).is_err());
+        assert!(Key::new(&AES_128, &[0; 32]).is_err());
+        assert!(Key::new(&AES_256, &[0; 32]).is_ok());
+        assert!(Key::new(&AES_256, &[0; 16]).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The one-step key derivation functions that derive keys from the shared
+//! secret _Z_ of a key agreement, e.g. the output of
+//! `agreement::agree_ephemeral`, using a digest algorithm.
+//!
+//! `derive` is the concatenation KDF of [NIST SP 800-56A Section 5.8.1],
+//! which is used by JOSE ([RFC 7518 Section 4.6]) and many government
+//! profiles. `fixed_info` encodes its FixedInfo in the concatenation format.
+//!
+//! `derive_x963` is the ANSI X9.63 KDF of [SEC 1 Section 3.6.1], which is
+//! used by CMS ([RFC 5753]). It differs from `derive` only in that the
+//! counter follows _Z_ instead of preceding it.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{concat_kdf, digest};
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! # let z = [0u8; 32];
+//! // `z` is the output of `agreement::agree_ephemeral`.
+//! let fixed_info = concat_kdf::fixed_info(b"A128GCM", b"Alice", b"Bob",
+//!                                         &[0, 0, 0, 128], b"");
+//! let mut key = [0u8; 16];
+//! try!(concat_kdf::derive(&digest::SHA256, &z, &[&fixed_info], &mut key));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [NIST SP 800-56A Section 5.8.1]:
+//!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-56Ar2.pdf
+//! [RFC 7518 Section 4.6]: https://tools.ietf.org/html/rfc7518#section-4.6
+//! [SEC 1 Section 3.6.1]: http://www.secg.org/sec1-v2.pdf
+//! [RFC 5753]: https://tools.ietf.org/html/rfc5753
+
+use {digest, error, polyfill};
+
+#[cfg(feature = "use_heap")]
+use std;
+
+/// Fills `out` with the key derived from the shared secret `secret` and
+/// the concatenation of `fixed_info`, using the concatenation KDF.
+///
+/// | Parameter    | SP 800-56A Section 5.8.1 Term
+/// |--------------|---------------------------------------
+/// | `digest_alg` | H
+/// | `secret`     | Z
+/// | `fixed_info` | FixedInfo (the concatenation of the slices)
+/// | `out.len()`  | L / 8
+/// | `out`        | DerivedKeyingMaterial
+///
+/// Fails if `out` is empty or if more than 2**32 - 1 digests are needed.
+pub fn derive(digest_alg: &'static digest::Algorithm, secret: &[u8],
+              fixed_info: &[&[u8]], out: &mut [u8])
+              -> Result<(), error::Unspecified> {
+    derive_(digest_alg, secret, fixed_info, out, CounterPosition::BeforeZ)
+}
+
+/// Fills `out` with the key derived from the shared secret `secret` and
+/// `shared_info`, using the ANSI X9.63 KDF.
+///
+/// | Parameter     | SEC 1 Section 3.6.1 Term
+/// |---------------|---------------------------------------
+/// | `digest_alg`  | Hash
+/// | `secret`      | Z
+/// | `shared_info` | SharedInfo
+/// | `out.len()`   | keydatalen
+/// | `out`         | K
+///
+/// Fails if `out` is empty or if more than 2**32 - 1 digests are needed.
+pub fn derive_x963(digest_alg: &'static digest::Algorithm, secret: &[u8],
+                   shared_info: &[u8], out: &mut [u8])
+                   -> Result<(), error::Unspecified> {
+    derive_(digest_alg, secret, &[shared_info], out, CounterPosition::AfterZ)
+}
+
+/// Encodes FixedInfo in the concatenation format of SP 800-56A Section
+/// 5.8.1.2.1: `AlgorithmID || PartyUInfo || PartyVInfo || SuppPubInfo ||
+/// SuppPrivInfo`.
+///
+/// `algorithm_id`, `party_u_info`, and `party_v_info` are each encoded with
+/// a 32-bit big-endian length prefix (`Datalen || Data`), as RFC 7518
+/// requires. `supp_pub_info` and `supp_priv_info` are appended as-is, and
+/// either or both may be empty.
+///
+/// # Panics
+///
+/// `fixed_info` panics if the length of any of `algorithm_id`,
+/// `party_u_info`, or `party_v_info` doesn't fit in 32 bits.
+#[cfg(feature = "use_heap")]
+pub fn fixed_info(algorithm_id: &[u8], party_u_info: &[u8],
+                  party_v_info: &[u8], supp_pub_info: &[u8],
+                  supp_priv_info: &[u8]) -> std::vec::Vec<u8> {
+    let mut result = std::vec::Vec::with_capacity(
+        4 + algorithm_id.len() + 4 + party_u_info.len() + 4 +
+        party_v_info.len() + supp_pub_info.len() + supp_priv_info.len());
+    for data in &[algorithm_id, party_u_info, party_v_info] {
+        assert!(polyfill::u64_from_usize(data.len()) <=
+                u64::from(u32::max_value()));
+        result.extend_from_slice(
+            &polyfill::slice::be_u8_from_u32(data.len() as u32));
+        result.extend_from_slice(data);
+    }
+    result.extend_from_slice(supp_pub_info);
+    result.extend_from_slice(supp_priv_info);
+    result
+}
+
+enum CounterPosition {
+    BeforeZ,
+    AfterZ,
+}
+
+fn derive_(digest_alg: &'static digest::Algorithm, secret: &[u8],
+           info: &[&[u8]], out: &mut [u8], counter_position: CounterPosition)
+           -> Result<(), error::Unspecified> {
+    let h = digest_alg.output_len;
+    let num_blocks = polyfill::u64_from_usize((out.len() + h - 1) / h);
+    if num_blocks == 0 || num_blocks > u64::from(u32::max_value()) {
+        return Err(error::Unspecified);
+    }
+
+    let mut counter = 0u32;
+    for chunk in out.chunks_mut(h) {
+        counter += 1;
+        let counter_bytes = polyfill::slice::be_u8_from_u32(counter);
+        let mut ctx = digest::Context::new(digest_alg);
+        match counter_position {
+            CounterPosition::BeforeZ => {
+                ctx.update(&counter_bytes);
+                ctx.update(secret);
+            },
+            CounterPosition::AfterZ => {
+                ctx.update(secret);
+                ctx.update(&counter_bytes);
+            },
+        }
+        for info in info {
+            ctx.update(info);
+        }
+        let block = ctx.finish();
+        chunk.copy_from_slice(&block.as_ref()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {digest, error, test};
+
+    #[test]
+    fn test_concat_kdf() {
+        test::from_file("src/concat_kdf_tests.txt", |section, test_case| {
+            let digest_alg =
+                try!(test_case.consume_digest_alg("Hash")
+                              .ok_or(error::Unspecified));
+            let secret = test_case.consume_bytes("Z");
+            let info = test_case.consume_bytes("Info");
+            let expected = test_case.consume_bytes("Output");
+
+            let mut out = vec![0u8; expected.len()];
+            match section {
+                "Concat" => {
+                    try!(derive(digest_alg, &secret, &[&info], &mut out));
+                    assert_eq!(out, expected);
+
+                    // The fixed info may be split into several pieces.
+                    let (a, b) = info.split_at(info.len() / 2);
+                    let mut out = vec![0u8; expected.len()];
+                    try!(derive(digest_alg, &secret, &[a, b], &mut out));
+                    assert_eq!(out, expected);
+                },
+                "X9.63" => {
+                    try!(derive_x963(digest_alg, &secret, &info, &mut out));
+                    assert_eq!(out, expected);
+                },
+                _ => unreachable!(),
+            }
+
+            Ok(())
+        });
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_concat_kdf_fixed_info() {
+        // From RFC 7518 Appendix C.
+        let expected: &[u8] = &[
+            0, 0, 0, 7, b'A', b'1', b'2', b'8', b'G', b'C', b'M',
+            0, 0, 0, 5, b'A', b'l', b'i', b'c', b'e',
+            0, 0, 0, 3, b'B', b'o', b'b',
+            0, 0, 0, 128,
+        ];
+        assert_eq!(&fixed_info(b"A128GCM", b"Alice", b"Bob", &[0, 0, 0, 128],
+                               b"")[..],
+                   expected);
+
+        assert_eq!(&fixed_info(b"", b"", b"", b"pub", b"priv")[..],
+                   &b"\0\0\0\0\0\0\0\0\0\0\0\0pubpriv"[..]);
+    }
+
+    #[test]
+    fn test_concat_kdf_output_len() {
+        assert!(derive(&digest::SHA256, b"z", &[], &mut []).is_err());
+        assert!(derive_x963(&digest::SHA256, b"z", b"", &mut []).is_err());
+        assert!(derive(&digest::SHA256, b"z", &[], &mut [0; 1]).is_ok());
+        assert!(derive_x963(&digest::SHA256, b"z", b"", &mut [0; 1]).is_ok());
+    }
+}
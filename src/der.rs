@@ -19,6 +19,9 @@
 use untrusted;
 use error;
 
+#[cfg(feature = "use_heap")]
+use std;
+
 pub const CONSTRUCTED: u8 = 1 << 5;
 pub const CONTEXT_SPECIFIC: u8 = 2 << 6;
 
@@ -35,6 +38,8 @@ pub enum Tag {
     UTCTime = 0x17,
     GeneralizedTime = 0x18,
 
+    ContextSpecific1 = CONTEXT_SPECIFIC | 1,
+
     ContextSpecificConstructed0 = CONTEXT_SPECIFIC | CONSTRUCTED | 0,
     ContextSpecificConstructed1 = CONTEXT_SPECIFIC | CONSTRUCTED | 1,
     ContextSpecificConstructed3 = CONTEXT_SPECIFIC | CONSTRUCTED | 3,
@@ -177,6 +182,50 @@ pub fn positive_integer<'a>(input: &mut untrusted::Reader<'a>)
 }
 
 
+/// Returns the length of the DER encoding of a value of `value_len` bytes,
+/// including the tag and the length.
+///
+/// Only the lengths that `read_tag_and_get_value` accepts are supported.
+#[cfg(feature = "use_heap")]
+pub fn encoded_len(value_len: usize) -> usize {
+    let length_len = if value_len < 0x80 {
+        1
+    } else if value_len < 0x100 {
+        2
+    } else {
+        assert!(value_len <= 0xffff);
+        3
+    };
+    1 + length_len + value_len
+}
+
+/// Writes the tag and length of a value of `value_len` bytes to `output`. The
+/// caller must then write exactly the `value_len` bytes of the value.
+#[cfg(feature = "use_heap")]
+pub fn write_tag_and_len(output: &mut std::vec::Vec<u8>, tag: Tag,
+                         value_len: usize) {
+    output.push(tag as u8);
+    if value_len < 0x80 {
+        output.push(value_len as u8);
+    } else if value_len < 0x100 {
+        output.push(0x81);
+        output.push(value_len as u8);
+    } else {
+        assert!(value_len <= 0xffff);
+        output.push(0x82);
+        output.push((value_len >> 8) as u8);
+        output.push((value_len & 0xff) as u8);
+    }
+}
+
+/// Writes the DER encoding of `value` with the given tag to `output`.
+#[cfg(feature = "use_heap")]
+pub fn write_tlv(output: &mut std::vec::Vec<u8>, tag: Tag, value: &[u8]) {
+    write_tag_and_len(output, tag, value.len());
+    output.extend_from_slice(value);
+}
+
+
 #[cfg(test)]
 mod tests {
     use error;
@@ -256,6 +305,25 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_write_tlv() {
+        use std;
+
+        for &len in [0, 1, 0x7f, 0x80, 0xff, 0x100, 0xffff].iter() {
+            let value = vec![0xab; len];
+            let mut encoded = std::vec::Vec::new();
+            write_tlv(&mut encoded, Tag::OctetString, &value);
+            assert_eq!(encoded.len(), encoded_len(len));
+            with_good_i(&encoded, |input| {
+                let actual = try!(expect_tag_and_get_value(input,
+                                                           Tag::OctetString));
+                assert_eq!(actual, untrusted::Input::from(&value[..]));
+                Ok(())
+            });
+        }
+    }
+
     #[test]
     fn test_positive_integer() {
         with_bad_i(ZERO_INTEGER, |input| {
@@ -12,7 +12,7 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-//! SHA-2 and the legacy SHA-1 digest algorithm.
+//! SHA-2, SM3, and the legacy SHA-1 digest algorithm.
 //!
 //! If all the data is available in a single contiguous slice then the `digest`
 //! function should be used. Otherwise, the digest can be calculated in
@@ -24,9 +24,12 @@
 // The goal for this implementation is to drive the overhead as close to zero
 // as possible.
 
-use {c, init, polyfill};
+use {c, error, init, polyfill};
 use core;
 
+#[cfg(feature = "use_heap")]
+use std;
+
 // XXX: Replace with `const fn` when `const fn` is stable:
 // https://github.com/rust-lang/rust/issues/24111
 #[cfg(target_endian = "little")]
@@ -35,7 +38,23 @@ macro_rules! u32x2 {
     ( ((($second as u64) << 32) | ($first as u64)) )
 }
 
+mod keccak256;
 mod sha1;
+mod sha1dc;
+mod shake;
+mod sm3;
+
+pub use self::keccak256::{KECCAK256_OUTPUT_LEN, Keccak256Context, keccak256};
+pub use self::sha1dc::{HardenedSha1Context, hardened_sha1};
+pub use self::shake::{SHAKE128, SHAKE256, XofAlgorithm, XofContext, XofReader,
+                      cshake, xof};
+
+pub mod tree;
+
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub mod keccak;
 
 /// A context for multi-step (Init-Update-Finish) digest calculations.
 ///
@@ -139,6 +158,17 @@ impl Context {
         }
     }
 
+    /// Updates the digest with the concatenation of the slices in `data`,
+    /// without copying them into a contiguous buffer first.
+    ///
+    /// `ctx.update_vectored(&[a, b])` is equivalent to `ctx.update(a)`
+    /// followed by `ctx.update(b)`.
+    pub fn update_vectored(&mut self, data: &[&[u8]]) {
+        for part in data {
+            self.update(part);
+        }
+    }
+
     /// Finalizes the digest calculation and returns the digest value. `finish`
     /// consumes the context so it cannot be (mis-)used after `finish` has been
     /// called.
@@ -193,6 +223,120 @@ impl Context {
     /// The algorithm that this context is using.
     #[inline(always)]
     pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+
+    /// Saves the intermediate state of the calculation so that it can be
+    /// resumed later, e.g. after a process restart, with `Context::restore`.
+    ///
+    /// The saved state doesn't identify the algorithm, so the caller must
+    /// keep track of it. Anybody who can modify the saved state controls the
+    /// digest calculated from it, and the saved state reveals some of the
+    /// data hashed so far, so it should be stored at least as carefully as
+    /// the data itself.
+    pub fn save(&self) -> SavedState {
+        let chaining_len = self.algorithm.chaining_len;
+        let pending_start = BLOCK_COUNT_LEN + chaining_len;
+        let mut saved = SavedState {
+            bytes: [0u8; MAX_SAVED_STATE_LEN],
+            len: pending_start + self.num_pending,
+        };
+        saved.bytes[..BLOCK_COUNT_LEN].copy_from_slice(
+            &polyfill::slice::be_u8_from_u64(self.completed_data_blocks));
+        let chaining_value = (self.algorithm.format_output)(&self.state);
+        saved.bytes[BLOCK_COUNT_LEN..pending_start].copy_from_slice(
+            &polyfill::slice::u64_as_u8(&chaining_value)[..chaining_len]);
+        saved.bytes[pending_start..saved.len]
+            .copy_from_slice(&self.pending[..self.num_pending]);
+        saved
+    }
+
+    /// Constructs a context from state saved by `Context::save` for a
+    /// context using `algorithm`.
+    ///
+    /// Fails if `saved` isn't the right length for `algorithm`, or if its
+    /// block count is so large that the length of the input in bits, including
+    /// a final block, wouldn't fit in 64 bits. Because the saved state doesn't
+    /// identify the algorithm, state saved for a different algorithm with the
+    /// same chaining length is not detected.
+    pub fn restore(algorithm: &'static Algorithm, saved: &[u8])
+                   -> Result<Context, error::Unspecified> {
+        init::init_once();
+
+        let pending_start = BLOCK_COUNT_LEN + algorithm.chaining_len;
+        if saved.len() < pending_start ||
+           saved.len() - pending_start >= algorithm.block_len {
+            return Err(error::Unspecified);
+        }
+        let block_count = try!(slice_as_array_ref!(&saved[..BLOCK_COUNT_LEN],
+                                                   BLOCK_COUNT_LEN));
+        let block_count = polyfill::slice::u64_from_be_u8(block_count);
+        let block_len_bits = polyfill::u64_from_usize(algorithm.block_len) * 8;
+        let max_len_bits = block_count.checked_add(1)
+                                      .and_then(|n| n.checked_mul(
+                                          block_len_bits));
+        if max_len_bits.is_none() {
+            return Err(error::Unspecified);
+        }
+        let num_pending = saved.len() - pending_start;
+        let mut pending = [0u8; MAX_BLOCK_LEN];
+        pending[..num_pending].copy_from_slice(&saved[pending_start..]);
+
+        Ok(Context {
+            algorithm: algorithm,
+            state: (algorithm.parse_state)(
+                &saved[BLOCK_COUNT_LEN..pending_start]),
+            completed_data_blocks: block_count,
+            pending: pending,
+            num_pending: num_pending,
+        })
+    }
+}
+
+/// The intermediate state of a digest calculation, saved by
+/// `Context::save`.
+///
+/// Use `as_ref` to get the value as a `&[u8]`. It is the number of complete
+/// blocks that have been hashed, as a big-endian 64-bit integer, followed by
+/// the chaining value, encoded the same way as a digest value, followed by
+/// the input that hasn't been hashed yet because it doesn't fill a block.
+#[derive(Clone, Copy)]
+pub struct SavedState {
+    bytes: [u8; MAX_SAVED_STATE_LEN],
+    len: usize,
+}
+
+impl AsRef<[u8]> for SavedState {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] { &self.bytes[..self.len] }
+}
+
+/// The maximum length of a `SavedState` of any of the algorithms in this
+/// module.
+pub const MAX_SAVED_STATE_LEN: usize =
+    BLOCK_COUNT_LEN + MAX_CHAINING_LEN + MAX_BLOCK_LEN - 1;
+
+const BLOCK_COUNT_LEN: usize = 64 / 8;
+
+/// Writing to a `Context` updates the digest with the written data, so the
+/// digest of data from a `std::io::Read` can be calculated with
+/// `std::io::copy`. Writes never fail and always consume all their input.
+#[cfg(feature = "use_heap")]
+impl std::io::Write for Context {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice])
+                      -> std::io::Result<usize> {
+        let mut len = 0;
+        for buf in bufs {
+            self.update(buf);
+            len += buf.len();
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
 }
 
 // XXX: This should just be `#[derive(Clone)]` but that doesn't work because
@@ -278,7 +422,7 @@ pub struct Algorithm {
 
     /// The size of the chaining value of the digest function, in bytes. For
     /// non-truncated algorithms (SHA-1, SHA-256, SHA-512), this is equal to
-    /// `output_len`. For truncated algorithms (e.g. SHA-384, SHA-512/256),
+    /// `output_len`. For truncated algorithms (e.g. SHA-384, SHA-512/224),
     /// this is equal to the length before truncation. This is mostly helpful
     /// for determining the size of an HMAC key that is appropriate for the
     /// digest algorithm.
@@ -294,13 +438,25 @@ pub struct Algorithm {
                                        num: c::size_t),
     format_output: fn(input: &State) -> Output,
 
+    // The inverse of `format_output`, for the first `chaining_len` bytes of
+    // its output.
+    parse_state: fn(input: &[u8]) -> State,
+
     initial_state: State,
 }
 
 impl core::fmt::Debug for Algorithm {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
-        // This would have to change if/when we add other algorithms with the
-        // same output lengths.
+        // SM3 has the same output length as SHA-256, so it must be
+        // distinguished by identity.
+        let this: *const Algorithm = self;
+        let sm3: *const Algorithm = &SM3;
+        if this == sm3 {
+            return write!(fmt, "SM3");
+        }
+        if self.chaining_len == 512 / 8 && self.output_len == 224 / 8 {
+            return write!(fmt, "SHA-512/224");
+        }
         let n = if self.output_len == 20 {
             1
         } else {
@@ -320,6 +476,7 @@ pub static SHA1: Algorithm = Algorithm {
     len_len: 64 / 8,
     block_data_order: sha1::block_data_order,
     format_output: sha256_format_output,
+    parse_state: sha256_parse_state,
     initial_state: [
         u32x2!(0x67452301u32, 0xefcdab89u32),
         u32x2!(0x98badcfeu32, 0x10325476u32),
@@ -338,6 +495,7 @@ pub static SHA256: Algorithm = Algorithm {
     len_len: 64 / 8,
     block_data_order: GFp_sha256_block_data_order,
     format_output: sha256_format_output,
+    parse_state: sha256_parse_state,
     initial_state: [
         u32x2!(0x6a09e667u32, 0xbb67ae85u32),
         u32x2!(0x3c6ef372u32, 0xa54ff53au32),
@@ -357,6 +515,7 @@ pub static SHA384: Algorithm = Algorithm {
     len_len: 128 / 8,
     block_data_order: GFp_sha512_block_data_order,
     format_output: sha512_format_output,
+    parse_state: sha512_parse_state,
     initial_state: [
         0xcbbb9d5dc1059ed8,
         0x629a292a367cd507,
@@ -379,6 +538,7 @@ pub static SHA512: Algorithm = Algorithm {
     len_len: 128 / 8,
     block_data_order: GFp_sha512_block_data_order,
     format_output: sha512_format_output,
+    parse_state: sha512_parse_state,
     initial_state: [
         0x6a09e667f3bcc908,
         0xbb67ae8584caa73b,
@@ -391,6 +551,52 @@ pub static SHA512: Algorithm = Algorithm {
     ],
 };
 
+/// SHA-512/224 as specified in [FIPS 180-4].
+///
+/// This is SHA-512 with a different initial hash value, truncated to 224
+/// bits. It is not the same as SHA-224, which `ring` doesn't implement.
+///
+/// [FIPS 180-4]: http://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf
+pub static SHA512_224: Algorithm = Algorithm {
+    output_len: 224 / 8,
+    chaining_len: 512 / 8,
+    block_len: 1024 / 8,
+    len_len: 128 / 8,
+    block_data_order: GFp_sha512_block_data_order,
+    format_output: sha512_format_output,
+    parse_state: sha512_parse_state,
+    initial_state: [
+        0x8c3d37c819544da2,
+        0x73e1996689dcd4d6,
+        0x1dfab7ae32ff9c82,
+        0x679dd514582f9fcf,
+        0x0f6d2b697bd44da8,
+        0x77e36f7304c48942,
+        0x3f9d85a86a1d36c8,
+        0x1112e6ad91d692a1,
+    ],
+};
+
+/// SM3 as specified in [GB/T 32905-2016], for use with SM2.
+///
+/// [GB/T 32905-2016]: https://tools.ietf.org/html/draft-sca-cfrg-sm3-02
+pub static SM3: Algorithm = Algorithm {
+    output_len: 256 / 8,
+    chaining_len: sm3::CHAINING_LEN,
+    block_len: sm3::BLOCK_LEN,
+    len_len: 64 / 8,
+    block_data_order: sm3::block_data_order,
+    format_output: sha256_format_output,
+    parse_state: sha256_parse_state,
+    initial_state: [
+        u32x2!(0x7380166fu32, 0x4914b2b9u32),
+        u32x2!(0x172442d7u32, 0xda8a0600u32),
+        u32x2!(0xa96f30bcu32, 0x163138aau32),
+        u32x2!(0xe38dee4du32, 0xb0fb0e4eu32),
+        0, 0, 0, 0,
+    ],
+};
+
 // We use u64 to try to ensure 64-bit alignment/padding.
 type State = [u64; MAX_CHAINING_LEN / 8];
 
@@ -431,6 +637,27 @@ fn sha512_format_output(input: &State) -> Output {
      input[7].to_be()]
 }
 
+fn sha256_parse_state(input: &[u8]) -> State {
+    let mut state = [0u64; MAX_CHAINING_LEN / 8];
+    {
+        let words = polyfill::slice::u64_as_u32_mut(&mut state);
+        for (word, bytes) in words.iter_mut().zip(input.chunks(4)) {
+            let bytes = slice_as_array_ref!(bytes, 4).unwrap();
+            *word = polyfill::slice::u32_from_be_u8(bytes);
+        }
+    }
+    state
+}
+
+fn sha512_parse_state(input: &[u8]) -> State {
+    let mut state = [0u64; MAX_CHAINING_LEN / 8];
+    for (word, bytes) in state.iter_mut().zip(input.chunks(8)) {
+        let bytes = slice_as_array_ref!(bytes, 8).unwrap();
+        *word = polyfill::slice::u64_from_be_u8(bytes);
+    }
+    state
+}
+
 /// Calculates the SHA-512 digest of the concatenation of |part1| through
 /// |part4|. Any part<N> may be null if and only if the corresponding
 /// part<N>_len is zero. This ugliness exists in order to allow some of the C
@@ -472,18 +699,20 @@ extern {
 pub mod test_util {
     use super::super::digest;
 
-    pub static ALL_ALGORITHMS: [&'static digest::Algorithm; 4] = [
+    pub static ALL_ALGORITHMS: [&'static digest::Algorithm; 6] = [
         &digest::SHA1,
         &digest::SHA256,
         &digest::SHA384,
         &digest::SHA512,
+        &digest::SHA512_224,
+        &digest::SM3,
     ];
 }
 
 #[cfg(test)]
 mod tests {
     use std::vec::Vec;
-    use super::super::{digest, test};
+    use super::super::{digest, polyfill, test};
 
     /// Test vectors from BoringSSL.
     #[test]
@@ -668,6 +897,7 @@ mod tests {
     test_i_u_f!(test_i_u_f_sha256, digest::SHA256);
     test_i_u_f!(test_i_u_f_sha384, digest::SHA384);
     test_i_u_f!(test_i_u_f_sha512, digest::SHA512);
+    test_i_u_f!(test_i_u_f_sha512_224, digest::SHA512_224);
 
     /// See https://bugzilla.mozilla.org/show_bug.cgi?id=610162. This tests the
     /// calculation of 8GB of the byte 123.
@@ -743,12 +973,113 @@ mod tests {
         0xA6, 0xA9, 0x88, 0x3E, 0x2F, 0x09, 0xB9, 0x9A
     ]);
 
+    #[test]
+    fn test_save_restore() {
+        let input: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for alg in digest::test_util::ALL_ALGORITHMS.iter() {
+            let expected = digest::digest(alg, &input);
+            for &split in &[0, 1, 63, 64, 65, 127, 128, 129, 300] {
+                let mut ctx = digest::Context::new(alg);
+                ctx.update(&input[..split]);
+                let saved = ctx.save();
+                assert_eq!(saved.as_ref().len(),
+                           8 + alg.chaining_len + (split % alg.block_len));
+
+                let mut restored =
+                    digest::Context::restore(alg, saved.as_ref()).unwrap();
+                assert_eq!(restored.save().as_ref(), saved.as_ref());
+                restored.update(&input[split..]);
+                assert_eq!(restored.finish().as_ref(), expected.as_ref());
+            }
+
+            let saved = digest::Context::new(alg).save();
+            let mut too_long = Vec::from(saved.as_ref());
+            too_long.extend(&input[..alg.block_len]);
+            assert!(digest::Context::restore(alg, &too_long).is_err());
+            assert!(digest::Context::restore(
+                        alg, &saved.as_ref()[..(saved.as_ref().len() - 1)])
+                    .is_err());
+
+            // A block count so large that the length in bits of the input
+            // would overflow is rejected, instead of panicking later.
+            let block_len_bits = polyfill::u64_from_usize(alg.block_len * 8);
+            let max_block_count = (u64::max_value() / block_len_bits) - 1;
+            for &(block_count, ok) in &[(max_block_count, true),
+                                       (max_block_count + 1, false),
+                                       (u64::max_value(), false)] {
+                let mut huge = Vec::from(saved.as_ref());
+                huge[..8].copy_from_slice(
+                    &polyfill::slice::be_u8_from_u64(block_count));
+                huge.extend(&input[..(alg.block_len - 1)]);
+                let restored = digest::Context::restore(alg, &huge);
+                assert_eq!(restored.is_ok(), ok);
+                if let Ok(restored) = restored {
+                    let _ = restored.finish();
+                }
+            }
+        }
+
+        // The saved state of SHA-256 after hashing "abc" is a block count of
+        // zero, the initial hash value, and "abc".
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(b"abc");
+        let expected = test::from_hex(
+            "0000000000000000\
+             6a09e667bb67ae853c6ef372a54ff53a510e527f9b05688c1f83d9ab5be0cd19\
+             616263").unwrap();
+        assert_eq!(ctx.save().as_ref(), &expected[..]);
+    }
+
+    #[test]
+    fn test_update_vectored() {
+        let input: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        for alg in digest::test_util::ALL_ALGORITHMS.iter() {
+            let expected = digest::digest(alg, &input);
+            for &(a, b) in &[(0, 0), (1, 128), (64, 64), (100, 299),
+                             (0, 300)] {
+                let mut ctx = digest::Context::new(alg);
+                ctx.update_vectored(&[&input[..a], &input[a..b], &[],
+                                      &input[b..]]);
+                assert_eq!(ctx.finish().as_ref(), expected.as_ref());
+            }
+        }
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_io_write() {
+        use std;
+        use std::io::Write;
+
+        let input: Vec<u8> = (0..10000).map(|i| i as u8).collect();
+        for alg in digest::test_util::ALL_ALGORITHMS.iter() {
+            let expected = digest::digest(alg, &input);
+
+            let mut ctx = digest::Context::new(alg);
+            let copied =
+                std::io::copy(&mut &input[..], &mut ctx).unwrap();
+            assert_eq!(copied, input.len() as u64);
+            assert_eq!(ctx.finish().as_ref(), expected.as_ref());
+
+            let mut ctx = digest::Context::new(alg);
+            let written = ctx.write_vectored(&[
+                std::io::IoSlice::new(&input[..33]),
+                std::io::IoSlice::new(&input[33..]),
+            ]).unwrap();
+            assert_eq!(written, input.len());
+            ctx.flush().unwrap();
+            assert_eq!(ctx.finish().as_ref(), expected.as_ref());
+        }
+    }
+
     #[test]
     fn test_fmt_algorithm() {
         assert_eq!("SHA-1", &format!("{:?}", digest::SHA1));
         assert_eq!("SHA-256", &format!("{:?}", digest::SHA256));
         assert_eq!("SHA-384", &format!("{:?}", digest::SHA384));
         assert_eq!("SHA-512", &format!("{:?}", digest::SHA512));
+        assert_eq!("SHA-512/224", &format!("{:?}", digest::SHA512_224));
+        assert_eq!("SM3", &format!("{:?}", digest::SM3));
     }
 
     #[test]
@@ -770,6 +1101,11 @@ mod tests {
                     aada663027e4548637b6d19894aec4fb6c46a139fbf9",
                    &format!("{:?}",
                             digest::digest(&digest::SHA512, b"hello, world")));
+        assert_eq!("SHA-512/224:7cbabb02ab4083f5b270bdd94705137aa3aaa6\
+                    260a8e041b0f8f3046",
+                   &format!("{:?}",
+                            digest::digest(&digest::SHA512_224,
+                                           b"hello, world")));
 
     }
 }
@@ -0,0 +1,287 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The Keccak-f[1600] permutation and the sponge construction of
+//! [FIPS 202], used for SHAKE128 and SHAKE256, and the cSHAKE padding and
+//! encodings of [SP 800-185], used for cSHAKE and KMAC.
+//!
+//! Like the SHA-1 implementation, this favors size and simplicity over speed.
+//!
+//! [FIPS 202]: http://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf
+//! [SP 800-185]:
+//!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+
+use {core, polyfill};
+
+const STATE_LANES: usize = 25;
+
+const ROUNDS: usize = 24;
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a,
+    0x8000000080008000, 0x000000000000808b, 0x0000000080000001,
+    0x8000000080008081, 0x8000000000008009, 0x000000000000008a,
+    0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089,
+    0x8000000000008003, 0x8000000000008002, 0x8000000000000080,
+    0x000000000000800a, 0x800000008000000a, 0x8000000080008081,
+    0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// The rotation offsets of the ρ step, in the order in which the π step visits
+// the lanes, starting from lane 1.
+const RHO_OFFSETS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14,
+    27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+// The order in which the π step visits the lanes, starting from lane 1. Lane
+// (x, y) is at index `x + 5*y`.
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4,
+    15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+fn keccak_f1600(a: &mut [u64; STATE_LANES]) {
+    for round_constant in ROUND_CONSTANTS.iter() {
+        // θ
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+
+        // ρ and π
+        let mut last = a[1];
+        for (&lane, &offset) in PI_LANES.iter().zip(RHO_OFFSETS.iter()) {
+            let next = a[lane];
+            a[lane] = last.rotate_left(offset);
+            last = next;
+        }
+
+        // χ
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&a[(5 * y)..(5 * y + 5)]);
+            for x in 0..5 {
+                a[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // ι
+        a[0] ^= *round_constant;
+    }
+}
+
+/// A Keccak sponge. Input is absorbed with `absorb()`, and then output is
+/// squeezed out with `squeeze()`; absorbing after squeezing isn't supported.
+#[derive(Clone)]
+pub struct Sponge {
+    state: [u64; STATE_LANES],
+    rate: usize,
+    domain: u8,
+    position: usize,
+    squeezing: bool,
+}
+
+impl Sponge {
+    /// Constructs a sponge with a rate of `rate` bytes, padding the input
+    /// with the domain separation bits in `domain` (e.g. 0x1f for SHAKE,
+    /// which includes the first bit of the pad10*1 padding).
+    pub fn new(rate: usize, domain: u8) -> Sponge {
+        assert!(rate > 0 && rate < STATE_LANES * 8 && rate % 8 == 0);
+        Sponge {
+            state: [0; STATE_LANES],
+            rate: rate,
+            domain: domain,
+            position: 0,
+            squeezing: false,
+        }
+    }
+
+    /// Absorbs `data` into the sponge.
+    pub fn absorb(&mut self, data: &[u8]) {
+        assert!(!self.squeezing);
+        for &b in data {
+            self.xor_byte(self.position, b);
+            self.position += 1;
+            if self.position == self.rate {
+                keccak_f1600(&mut self.state);
+                self.position = 0;
+            }
+        }
+    }
+
+    /// Fills `out` with the next `out.len()` bytes of output.
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        if !self.squeezing {
+            let position = self.position;
+            let domain = self.domain;
+            let rate = self.rate;
+            self.xor_byte(position, domain);
+            self.xor_byte(rate - 1, 0x80);
+            keccak_f1600(&mut self.state);
+            self.position = 0;
+            self.squeezing = true;
+        }
+        for b in out.iter_mut() {
+            if self.position == self.rate {
+                keccak_f1600(&mut self.state);
+                self.position = 0;
+            }
+            *b = (self.state[self.position / 8] >> (8 * (self.position % 8)))
+                 as u8;
+            self.position += 1;
+        }
+    }
+
+    /// Absorbs `bytepad(encode_string(strings[0]) || ... ||
+    /// encode_string(strings[n - 1]), rate)`. The sponge must be at the start
+    /// of a block.
+    pub fn absorb_bytepad(&mut self, strings: &[&[u8]]) {
+        assert_eq!(self.position, 0);
+        let mut buf = [0u8; MAX_ENCODED_LEN];
+        let rate = polyfill::u64_from_usize(self.rate);
+        self.absorb(left_encode(rate, &mut buf));
+        for s in strings {
+            let s_bits = polyfill::u64_from_usize(s.len()) * 8;
+            self.absorb(left_encode(s_bits, &mut buf));
+            self.absorb(s);
+        }
+        while self.position != 0 {
+            self.absorb(&[0]);
+        }
+    }
+
+    #[inline]
+    fn xor_byte(&mut self, i: usize, b: u8) {
+        self.state[i / 8] ^= (b as u64) << (8 * (i % 8));
+    }
+}
+
+/// The domain separation bits of SHAKE, followed by the first bit of the
+/// pad10*1 padding.
+pub const SHAKE_DOMAIN: u8 = 0x1f;
+
+/// The domain separation bits of cSHAKE, followed by the first bit of the
+/// pad10*1 padding.
+pub const CSHAKE_DOMAIN: u8 = 0x04;
+
+/// Constructs a sponge for cSHAKE with a rate of `rate` bytes, the function
+/// name `name`, and the customization string `customization`. When both are
+/// empty, cSHAKE is the same as SHAKE.
+pub fn cshake(rate: usize, name: &[u8], customization: &[u8]) -> Sponge {
+    if name.is_empty() && customization.is_empty() {
+        return Sponge::new(rate, SHAKE_DOMAIN);
+    }
+    let mut sponge = Sponge::new(rate, CSHAKE_DOMAIN);
+    sponge.absorb_bytepad(&[name, customization]);
+    sponge
+}
+
+/// The maximum length of `left_encode(x)` or `right_encode(x)`.
+pub const MAX_ENCODED_LEN: usize = 1 + (64 / 8);
+
+/// Returns `left_encode(value)`: the length of the big-endian encoding of
+/// `value` without leading zeros, followed by that encoding.
+pub fn left_encode(value: u64, buf: &mut [u8; MAX_ENCODED_LEN]) -> &[u8] {
+    let n = encoded_len(value);
+    buf[0] = n as u8;
+    for (i, b) in buf[1..(n + 1)].iter_mut().enumerate() {
+        *b = (value >> (8 * (n - 1 - i))) as u8;
+    }
+    &buf[..(n + 1)]
+}
+
+/// Returns `right_encode(value)`: the big-endian encoding of `value` without
+/// leading zeros, followed by its length.
+pub fn right_encode(value: u64, buf: &mut [u8; MAX_ENCODED_LEN]) -> &[u8] {
+    let n = encoded_len(value);
+    for (i, b) in buf[..n].iter_mut().enumerate() {
+        *b = (value >> (8 * (n - 1 - i))) as u8;
+    }
+    buf[n] = n as u8;
+    &buf[..(n + 1)]
+}
+
+// The encoding of zero is one zero byte.
+fn encoded_len(value: u64) -> usize {
+    let bits = 64 - (value.leading_zeros() as usize);
+    core::cmp::max(1, (bits + 7) / 8)
+}
+
+/// The rate of SHAKE256, in bytes.
+const SHAKE256_RATE: usize = (1600 - (2 * 256)) / 8;
+
+/// Fills `out` with the SHAKE256 output for the concatenation of `parts`.
+pub fn shake256(parts: &[&[u8]], out: &mut [u8]) {
+    let mut sponge = Sponge::new(SHAKE256_RATE, SHAKE_DOMAIN);
+    for part in parts {
+        sponge.absorb(part);
+    }
+    sponge.squeeze(out);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{left_encode, right_encode, shake256, Sponge, MAX_ENCODED_LEN,
+                SHAKE256_RATE};
+    use test;
+
+    #[test]
+    fn test_encode() {
+        let mut buf = [0u8; MAX_ENCODED_LEN];
+        assert_eq!(left_encode(0, &mut buf), &[1, 0]);
+        assert_eq!(right_encode(0, &mut buf), &[0, 1]);
+        assert_eq!(left_encode(168, &mut buf), &[1, 168]);
+        assert_eq!(left_encode(256, &mut buf), &[2, 1, 0]);
+        assert_eq!(right_encode(256, &mut buf), &[1, 0, 2]);
+        assert_eq!(left_encode(0xffffffffffffffff, &mut buf),
+                   &[8, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_shake256() {
+        test::from_file("src/digest/shake256_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let mut actual = vec![0u8; expected.len()];
+            shake256(&[&input], &mut actual);
+            assert_eq!(actual, expected);
+
+            // Split the input and the output at arbitrary points.
+            let (a, b) = input.split_at(input.len() / 3);
+            let mut sponge = Sponge::new(SHAKE256_RATE, 0x1f);
+            sponge.absorb(a);
+            sponge.absorb(b);
+            let mut actual = vec![0u8; expected.len()];
+            {
+                let (x, y) = actual.split_at_mut(expected.len() / 2);
+                sponge.squeeze(x);
+                sponge.squeeze(y);
+            }
+            assert_eq!(actual, expected);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use super::keccak;
+
+/// The length of a Keccak-256 digest, in bytes.
+pub const KECCAK256_OUTPUT_LEN: usize = 256 / 8;
+
+const KECCAK256_RATE: usize = (1600 - (2 * 256)) / 8;
+
+// The original Keccak submission pads with just the pad10*1 padding, without
+// the domain separation bits that FIPS 202 added for SHA-3.
+const KECCAK_DOMAIN: u8 = 0x01;
+
+/// A context for multi-step (Init-Update-Finish) Keccak-256 calculations.
+///
+/// Keccak-256 is the hash function of the Keccak submission to the SHA-3
+/// competition, as used by Ethereum. It differs from SHA3-256 only in its
+/// padding, so the two have different outputs for every input. Don't use it
+/// except for compatibility with protocols that require it.
+///
+/// # Examples
+///
+/// ```
+/// use ring::digest;
+///
+/// let one_shot = digest::keccak256(b"hello, world");
+///
+/// let mut ctx = digest::Keccak256Context::new();
+/// ctx.update(b"hello");
+/// ctx.update(b", ");
+/// ctx.update(b"world");
+/// let multi_part = ctx.finish();
+///
+/// assert_eq!(&one_shot[..], &multi_part[..]);
+/// ```
+#[derive(Clone)]
+pub struct Keccak256Context {
+    sponge: keccak::Sponge,
+}
+
+impl Keccak256Context {
+    /// Constructs a new context.
+    pub fn new() -> Keccak256Context {
+        Keccak256Context {
+            sponge: keccak::Sponge::new(KECCAK256_RATE, KECCAK_DOMAIN),
+        }
+    }
+
+    /// Updates the digest with all the data in `data`. `update` may be called
+    /// zero or more times until `finish` is called.
+    pub fn update(&mut self, data: &[u8]) { self.sponge.absorb(data); }
+
+    /// Finalizes the digest calculation and returns the digest value.
+    pub fn finish(self) -> [u8; KECCAK256_OUTPUT_LEN] {
+        let mut sponge = self.sponge;
+        let mut out = [0u8; KECCAK256_OUTPUT_LEN];
+        sponge.squeeze(&mut out);
+        out
+    }
+}
+
+/// Returns the Keccak-256 digest of `data`. See `Keccak256Context`.
+pub fn keccak256(data: &[u8]) -> [u8; KECCAK256_OUTPUT_LEN] {
+    let mut ctx = Keccak256Context::new();
+    ctx.update(data);
+    ctx.finish()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_keccak256() {
+        test::from_file("src/digest/keccak256_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            assert_eq!(&keccak256(&input)[..], &expected[..]);
+
+            let mut ctx = Keccak256Context::new();
+            for b in input.chunks(7) {
+                ctx.update(b);
+            }
+            assert_eq!(&ctx.finish()[..], &expected[..]);
+
+            Ok(())
+        });
+    }
+}
@@ -24,9 +24,9 @@ pub type State = [u64; super::MAX_CHAINING_LEN / 8];
 
 pub const BLOCK_LEN: usize = 512 / 8;
 pub const CHAINING_LEN: usize = 160 / 8;
-const CHAINING_WORDS: usize = CHAINING_LEN / 4;
+pub const CHAINING_WORDS: usize = CHAINING_LEN / 4;
 
-type W32 = Wrapping<u32>;
+pub type W32 = Wrapping<u32>;
 
 #[inline]
 fn ch(x: W32, y: W32, z: W32) -> W32 { (x & y) | (!x & z) }
@@ -37,6 +37,46 @@ fn parity(x: W32, y: W32, z: W32) -> W32 { x ^ y ^ z }
 #[inline]
 fn maj(x: W32, y: W32, z: W32) -> W32 { (x & y) | (x & z) | (y & z) }
 
+/// Returns the sum of the constant and the logical function of step `t`,
+/// given the values `b`, `c`, and `d` of the working variables.
+#[inline]
+pub fn f_plus_k(t: usize, b: W32, c: W32, d: W32) -> W32 {
+    let (k, f) = match t {
+        0...19 => (0x5a827999, ch(b, c, d)),
+        20...39 => (0x6ed9eba1, parity(b, c, d)),
+        40...59 => (0x8f1bbcdc, maj(b, c, d)),
+        60...79 => (0xca62c1d6, parity(b, c, d)),
+        _ => unreachable!(),
+    };
+    f + Wrapping(k)
+}
+
+/// Fills `w` with the message schedule of `block`.
+#[inline]
+pub fn expand_message(block: &[u8; BLOCK_LEN], w: &mut [W32; 80]) {
+    for t in 0..16 {
+        let word = slice_as_array_ref!(&block[t * 4..][..4], 4).unwrap();
+        w[t] = Wrapping(polyfill::slice::u32_from_be_u8(word))
+    }
+    for t in 16..80 {
+        let wt = w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16];
+        w[t] = polyfill::wrapping_rotate_left_u32(wt, 1);
+    }
+}
+
+/// Applies step `t`, with the message schedule word `wt`, to the working
+/// variables `v`.
+#[inline]
+pub fn step(t: usize, v: &mut [W32; CHAINING_WORDS], wt: W32) {
+    let tt = polyfill::wrapping_rotate_left_u32(v[0], 5) +
+             f_plus_k(t, v[1], v[2], v[3]) + v[4] + wt;
+    v[4] = v[3];
+    v[3] = v[2];
+    v[2] = polyfill::wrapping_rotate_left_u32(v[1], 30);
+    v[1] = v[0];
+    v[0] = tt;
+}
+
 /// The main purpose in retaining this is to support legacy protocols and OCSP,
 /// none of which need a fast SHA-1 implementation.
 /// This implementation therefore favors size and simplicity over speed.
@@ -57,42 +97,15 @@ fn block_data_order_safe(state: &mut State, blocks: &[[u8; BLOCK_LEN]]) {
 
     let mut w: [W32; 80] = [Wrapping(0); 80];
     for block in blocks {
-        for t in 0..16 {
-            let word = slice_as_array_ref!(&block[t * 4..][..4], 4).unwrap();
-            w[t] = Wrapping(polyfill::slice::u32_from_be_u8(word))
-        }
-        for t in 16..80 {
-            let wt = w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16];
-            w[t] = polyfill::wrapping_rotate_left_u32(wt, 1);
-        }
-
-        let mut a = state[0];
-        let mut b = state[1];
-        let mut c = state[2];
-        let mut d = state[3];
-        let mut e = state[4];
+        expand_message(block, &mut w);
 
+        let mut v = *state;
         for t in 0..80 {
-            let (k, f) = match t {
-                0...19 => (0x5a827999, ch(b, c, d)),
-                20...39 => (0x6ed9eba1, parity(b, c, d)),
-                40...59 => (0x8f1bbcdc, maj(b, c, d)),
-                60...79 => (0xca62c1d6, parity(b, c, d)),
-                _ => unreachable!(),
-            };
-            let tt = polyfill::wrapping_rotate_left_u32(a, 5) + f + e +
-                     Wrapping(k) + w[t];
-            e = d;
-            d = c;
-            c = polyfill::wrapping_rotate_left_u32(b, 30);
-            b = a;
-            a = tt;
+            step(t, &mut v, w[t]);
         }
 
-        state[0] = state[0] + a;
-        state[1] = state[1] + b;
-        state[2] = state[2] + c;
-        state[3] = state[3] + d;
-        state[4] = state[4] + e;
+        for i in 0..CHAINING_WORDS {
+            state[i] = state[i] + v[i];
+        }
     }
 }
@@ -0,0 +1,436 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use {error, polyfill};
+use core;
+use core::num::Wrapping;
+use super::{Digest, SHA1, State, sha1};
+use super::sha1::{BLOCK_LEN, CHAINING_WORDS, W32};
+
+/// A context for multi-step (Init-Update-Finish) SHA-1 calculations that
+/// detect collision attacks.
+///
+/// For every input, the digest is the same as the `SHA1` digest, but
+/// `finish` fails if the input contains a block of a near-collision attack
+/// on SHA-1, such as the blocks of the SHAttered collision. This uses the
+/// [counter-cryptanalysis] of Stevens and Shumow: each block is checked for
+/// each of the 32 disturbance vectors that the known attacks and their
+/// likely improvements use, by computing what the other block of a
+/// colliding pair would have to be and checking whether it collides. This
+/// makes hashing many times slower than with `SHA1`.
+///
+/// This is for systems, like Git, that must keep using SHA-1 to identify
+/// content that may come from an attacker. Don't use SHA-1 in new protocols.
+///
+/// # Examples
+///
+/// ```
+/// use ring::digest;
+///
+/// let multi_part = {
+///     let mut ctx = digest::HardenedSha1Context::new();
+///     ctx.update(b"hello");
+///     ctx.update(b", world");
+///     ctx.finish().unwrap()
+/// };
+///
+/// let expected = digest::digest(&digest::SHA1, b"hello, world");
+/// assert_eq!(multi_part.as_ref(), expected.as_ref());
+/// ```
+///
+/// [counter-cryptanalysis]:
+///     https://github.com/cr-marcstevens/sha1collisiondetection
+pub struct HardenedSha1Context {
+    state: State,
+    pending: [u8; BLOCK_LEN],
+    num_pending: usize,
+    completed_data_blocks: u64,
+    collision_detected: bool,
+}
+
+impl HardenedSha1Context {
+    /// Constructs a new context.
+    pub fn new() -> HardenedSha1Context {
+        HardenedSha1Context {
+            state: SHA1.initial_state,
+            pending: [0u8; BLOCK_LEN],
+            num_pending: 0,
+            completed_data_blocks: 0,
+            collision_detected: false,
+        }
+    }
+
+    /// Updates the digest with all the data in `data`. `update` may be called
+    /// zero or more times until `finish` is called.
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.num_pending > 0 {
+            let to_copy = core::cmp::min(BLOCK_LEN - self.num_pending,
+                                         data.len());
+            self.pending[self.num_pending..][..to_copy]
+                .copy_from_slice(&data[..to_copy]);
+            self.num_pending += to_copy;
+            data = &data[to_copy..];
+            if self.num_pending < BLOCK_LEN {
+                return;
+            }
+            let pending = self.pending;
+            self.process_block(&pending);
+            self.completed_data_blocks += 1;
+            self.num_pending = 0;
+        }
+
+        for block in data.chunks(BLOCK_LEN) {
+            if block.len() < BLOCK_LEN {
+                self.pending[..block.len()].copy_from_slice(block);
+                self.num_pending = block.len();
+                break;
+            }
+            self.process_block(slice_as_array_ref!(block, BLOCK_LEN).unwrap());
+            self.completed_data_blocks += 1;
+        }
+    }
+
+    /// Finalizes the digest calculation and returns the digest value.
+    ///
+    /// Fails if a collision attack was detected.
+    pub fn finish(mut self) -> Result<Digest, error::Unspecified> {
+        let mut padded = [0u8; 2 * BLOCK_LEN];
+        padded[..self.num_pending]
+            .copy_from_slice(&self.pending[..self.num_pending]);
+        padded[self.num_pending] = 0x80;
+        let padded_len = if self.num_pending + 1 + 8 <= BLOCK_LEN {
+            BLOCK_LEN
+        } else {
+            2 * BLOCK_LEN
+        };
+
+        // Output the length, in bits, in big endian order.
+        let completed_data_bits: u64 =
+            self.completed_data_blocks
+                .checked_mul(polyfill::u64_from_usize(BLOCK_LEN)).unwrap()
+                .checked_add(polyfill::u64_from_usize(self.num_pending))
+                .unwrap()
+                .checked_mul(8).unwrap();
+        for (i, b) in padded[(padded_len - 8)..padded_len].iter_mut()
+                                                             .enumerate() {
+            *b = (completed_data_bits >> (56 - (8 * i))) as u8;
+        }
+
+        for block in padded[..padded_len].chunks(BLOCK_LEN) {
+            self.process_block(slice_as_array_ref!(block, BLOCK_LEN).unwrap());
+        }
+
+        if self.collision_detected {
+            return Err(error::Unspecified);
+        }
+        Ok(Digest {
+            algorithm: &SHA1,
+            value: (SHA1.format_output)(&self.state),
+        })
+    }
+
+    fn process_block(&mut self, block: &[u8; BLOCK_LEN]) {
+        let state = polyfill::slice::u64_as_u32_mut(&mut self.state);
+        let state = polyfill::slice::as_wrapping_mut(state);
+        let state = &mut state[..CHAINING_WORDS];
+        let state = slice_as_array_ref_mut!(state, CHAINING_WORDS).unwrap();
+        if compress(state, block) {
+            self.collision_detected = true;
+        }
+    }
+}
+
+/// Returns the SHA-1 digest of `data`, failing if a collision attack was
+/// detected. See `HardenedSha1Context`.
+pub fn hardened_sha1(data: &[u8]) -> Result<Digest, error::Unspecified> {
+    let mut ctx = HardenedSha1Context::new();
+    ctx.update(data);
+    ctx.finish()
+}
+
+// A disturbance vector, in the notation of Manuel's "Classification and
+// Generation of Disturbance Vectors for Collision Attacks against SHA-1".
+// Words `k` through `k + 15` of a type I vector are zero, except that word
+// `k + 15` is `1 << b`, and words `k + 1` and `k + 3` of a type II vector
+// are also nonzero. The other words follow from the SHA-1 message
+// expansion, forwards and backwards.
+struct DisturbanceVector {
+    is_type_ii: bool,
+    k: usize,
+    b: u32,
+
+    // The index in `TEST_STEPS` of the step at which the states of the two
+    // blocks of a colliding pair are the same.
+    saved_state: usize,
+}
+
+macro_rules! dv {
+    ( I($k:expr, $b:expr), $test_step:tt ) => {
+        DisturbanceVector {
+            is_type_ii: false, k: $k, b: $b,
+            saved_state: saved_state!($test_step),
+        }
+    };
+    ( II($k:expr, $b:expr), $test_step:tt ) => {
+        DisturbanceVector {
+            is_type_ii: true, k: $k, b: $b,
+            saved_state: saved_state!($test_step),
+        }
+    };
+}
+
+// The index of each of `TEST_STEPS`.
+macro_rules! saved_state {
+    (58) => { 0 };
+    (65) => { 1 };
+}
+
+// The disturbance vectors that sha1collisiondetection checks.
+static DISTURBANCE_VECTORS: [DisturbanceVector; 32] = [
+    dv!(I(43, 0), 58), dv!(I(44, 0), 58), dv!(I(45, 0), 58),
+    dv!(I(46, 0), 58), dv!(I(46, 2), 58), dv!(I(47, 0), 58),
+    dv!(I(47, 2), 58), dv!(I(48, 0), 58), dv!(I(48, 2), 58),
+    dv!(I(49, 0), 58), dv!(I(49, 2), 58), dv!(I(50, 0), 65),
+    dv!(I(50, 2), 65), dv!(I(51, 0), 65), dv!(I(51, 2), 65),
+    dv!(I(52, 0), 65),
+    dv!(II(45, 0), 58), dv!(II(46, 0), 58), dv!(II(46, 2), 58),
+    dv!(II(47, 0), 58), dv!(II(48, 0), 58), dv!(II(49, 0), 58),
+    dv!(II(49, 2), 58), dv!(II(50, 0), 65), dv!(II(50, 2), 65),
+    dv!(II(51, 0), 65), dv!(II(51, 2), 65), dv!(II(52, 0), 65),
+    dv!(II(53, 0), 65), dv!(II(54, 0), 65), dv!(II(55, 0), 65),
+    dv!(II(56, 0), 65),
+];
+
+// The steps at which the states are saved for the checks.
+const TEST_STEPS: [usize; 2] = [58, 65];
+
+impl DisturbanceVector {
+    // Fills `dm` with the differences between the message schedules of the two
+    // blocks of a colliding pair: the sum of the message differences of the
+    // local collisions that start at each of the disturbances.
+    fn message_differences(&self, dm: &mut [W32; 80]) {
+        // `dv[i + 5]` is word `i` of the vector, for `-5 <= i < 80`.
+        let mut dv = [0u32; 5 + 80];
+        let k = self.k + 5;
+        dv[k + 15] = 1 << self.b;
+        if self.is_type_ii {
+            dv[k + 1] = (1u32 << self.b).rotate_left(31);
+            dv[k + 3] = dv[k + 1];
+        }
+        for i in (k + 16)..dv.len() {
+            dv[i] = (dv[i - 3] ^ dv[i - 8] ^ dv[i - 14] ^ dv[i - 16])
+                        .rotate_left(1);
+        }
+        for i in (0..k).rev() {
+            dv[i] = dv[i + 16].rotate_right(1) ^ dv[i + 13] ^ dv[i + 8] ^
+                    dv[i + 2];
+        }
+
+        for t in 0..80 {
+            let i = t + 5;
+            dm[t] = Wrapping(dv[i] ^ dv[i - 1].rotate_left(5) ^ dv[i - 2] ^
+                             (dv[i - 3] ^ dv[i - 4] ^ dv[i - 5])
+                                .rotate_left(30));
+        }
+    }
+}
+
+// Compresses `block` into `state`, like `sha1::block_data_order`, and
+// returns whether the block is one of a colliding pair for any of the
+// disturbance vectors.
+fn compress(state: &mut [W32; CHAINING_WORDS], block: &[u8; BLOCK_LEN])
+            -> bool {
+    let mut w = [Wrapping(0); 80];
+    sha1::expand_message(block, &mut w);
+
+    let mut saved = [[Wrapping(0); CHAINING_WORDS]; 2];
+    let mut v = *state;
+    for t in 0..80 {
+        for (&test_step, saved) in TEST_STEPS.iter().zip(saved.iter_mut()) {
+            if t == test_step {
+                *saved = v;
+            }
+        }
+        sha1::step(t, &mut v, w[t]);
+    }
+    for i in 0..CHAINING_WORDS {
+        state[i] = state[i] + v[i];
+    }
+
+    let mut collision_detected = false;
+    let mut dm = [Wrapping(0); 80];
+    for dv in DISTURBANCE_VECTORS.iter() {
+        dv.message_differences(&mut dm);
+        let mut w2 = w;
+        for t in 0..80 {
+            w2[t] = w2[t] ^ dm[t];
+        }
+
+        let test_step = TEST_STEPS[dv.saved_state];
+        let saved = saved[dv.saved_state];
+
+        // Compute the chaining value that the other block of the pair would
+        // have been compressed with, and then the output of compressing it.
+        let mut other_state = saved;
+        for t in (0..test_step).rev() {
+            unstep(t, &mut other_state, w2[t]);
+        }
+        let mut v = saved;
+        for t in test_step..80 {
+            sha1::step(t, &mut v, w2[t]);
+        }
+
+        let mut difference = Wrapping(0);
+        for i in 0..CHAINING_WORDS {
+            difference |= (other_state[i] + v[i]) ^ state[i];
+        }
+        if difference == Wrapping(0) {
+            collision_detected = true;
+        }
+    }
+
+    collision_detected
+}
+
+// Undoes step `t`, with the message schedule word `wt`, of `sha1::step`.
+#[inline]
+fn unstep(t: usize, v: &mut [W32; CHAINING_WORDS], wt: W32) {
+    let a = v[1];
+    let b = polyfill::wrapping_rotate_left_u32(v[2], 2);
+    let c = v[3];
+    let d = v[4];
+    let e = v[0] - polyfill::wrapping_rotate_left_u32(a, 5) -
+            sha1::f_plus_k(t, b, c, d) - wt;
+    *v = [a, b, c, d, e];
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::num::Wrapping;
+    use super::*;
+    use super::{DISTURBANCE_VECTORS, unstep};
+    use digest::{SHA1, digest, sha1};
+    use test;
+
+    #[test]
+    fn test_hardened_sha1() {
+        for file_name in &["third-party/NIST/SHAVS/SHA1ShortMsg.rsp",
+                           "third-party/NIST/SHAVS/SHA1LongMsg.rsp"] {
+            test::from_file(file_name, |section, test_case| {
+                assert_eq!(section, "L = 20");
+                let len_bits = test_case.consume_usize("Len");
+                let mut msg = test_case.consume_bytes("Msg");
+                if len_bits == 0 {
+                    msg.truncate(0);
+                }
+                let expected = test_case.consume_bytes("MD");
+
+                assert_eq!(hardened_sha1(&msg).unwrap().as_ref(),
+                           &expected[..]);
+
+                let mut ctx = HardenedSha1Context::new();
+                for chunk in msg.chunks(17) {
+                    ctx.update(chunk);
+                }
+                assert_eq!(ctx.finish().unwrap().as_ref(), &expected[..]);
+
+                Ok(())
+            });
+        }
+
+        let data = [0x5au8; 1000];
+        assert_eq!(hardened_sha1(&data).unwrap().as_ref(),
+                   digest(&SHA1, &data).as_ref());
+    }
+
+    #[test]
+    fn test_hardened_sha1_shattered() {
+        test::from_file("src/digest/sha1dc_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("MD");
+
+            assert!(hardened_sha1(&input).is_err());
+
+            let mut ctx = HardenedSha1Context::new();
+            for chunk in input.chunks(17) {
+                ctx.update(chunk);
+            }
+            assert!(ctx.finish().is_err());
+
+            // The inputs still collide under `SHA1`.
+            assert_eq!(digest(&SHA1, &input).as_ref(), &expected[..]);
+
+            Ok(())
+        });
+    }
+
+    // The first and last words of the message differences of each
+    // disturbance vector, as tabulated in sha1collisiondetection.
+    #[test]
+    fn test_message_differences() {
+        const EXPECTED: [(u32, u32); 32] = [
+            (0x08000000, 0x80000599), (0xb4000008, 0x80000161),
+            (0xf4000014, 0x00000803), (0x2c000010, 0x8000004c),
+            (0xb0000040, 0x00000132), (0xc8000010, 0x800000e6),
+            (0x20000043, 0x0000039a), (0xb800000a, 0x00000408),
+            (0xe000002a, 0x00001020), (0x18000000, 0x00000164),
+            (0x60000000, 0x00000590), (0x0800000c, 0x00000018),
+            (0x20000030, 0x00000060), (0xe8000000, 0x80000202),
+            (0xa0000003, 0x0000080a), (0x04000010, 0x80000012),
+            (0xec000014, 0x00000967), (0x2400001c, 0x80000054),
+            (0x90000070, 0x00000152), (0x20000010, 0x000002e4),
+            (0xbc00001a, 0x8000041a), (0x3c000004, 0x8000016d),
+            (0xf0000010, 0x000005b6), (0xb400001c, 0x0000011b),
+            (0xd0000072, 0x0000046c), (0xc0000010, 0x8000024b),
+            (0x00000043, 0x0000092e), (0x0c000002, 0x00000014),
+            (0xcc000014, 0x00000089), (0x0400001c, 0x80000107),
+            (0x00000010, 0x4000004b), (0x2600001a, 0xc0000046),
+        ];
+        for (dv, &(first, last)) in
+                DISTURBANCE_VECTORS.iter().zip(EXPECTED.iter()) {
+            let mut dm = [Wrapping(0); 80];
+            dv.message_differences(&mut dm);
+            assert_eq!(dm[0], Wrapping(first));
+            assert_eq!(dm[79], Wrapping(last));
+
+            // The message differences follow the message expansion.
+            for t in 16..80 {
+                assert_eq!(dm[t].0,
+                           (dm[t - 3] ^ dm[t - 8] ^ dm[t - 14] ^ dm[t - 16]).0
+                               .rotate_left(1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unstep() {
+        let block = [0xa5u8; sha1::BLOCK_LEN];
+        let mut w = [Wrapping(0); 80];
+        sha1::expand_message(&block, &mut w);
+
+        let initial = [Wrapping(0x67452301), Wrapping(0xefcdab89),
+                       Wrapping(0x98badcfe), Wrapping(0x10325476),
+                       Wrapping(0xc3d2e1f0)];
+        let mut v = initial;
+        for t in 0..80 {
+            sha1::step(t, &mut v, w[t]);
+        }
+        for t in (0..80).rev() {
+            unstep(t, &mut v, w[t]);
+        }
+        assert_eq!(v, initial);
+    }
+}
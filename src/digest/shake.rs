@@ -0,0 +1,225 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+use super::keccak;
+
+/// An extendable-output function (XOF): a hash function whose output can be
+/// as long as the caller needs.
+///
+/// The output of an XOF for a given input is a prefix of the output for the
+/// same input with a longer output length, so protocols that use XOFs with
+/// different output lengths must separate them some other way.
+pub struct XofAlgorithm {
+    /// The security strength of the function against all attacks, in bits,
+    /// when at least `2 * security_bits` bits of output are used.
+    pub security_bits: usize,
+
+    rate: usize,
+}
+
+/// SHAKE128 as specified in [FIPS 202].
+///
+/// [FIPS 202]: http://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf
+pub static SHAKE128: XofAlgorithm = XofAlgorithm {
+    security_bits: 128,
+    rate: (1600 - (2 * 128)) / 8,
+};
+
+/// SHAKE256 as specified in [FIPS 202].
+///
+/// [FIPS 202]: http://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf
+pub static SHAKE256: XofAlgorithm = XofAlgorithm {
+    security_bits: 256,
+    rate: (1600 - (2 * 256)) / 8,
+};
+
+/// A context for absorbing the input of an XOF. Once all the input has been
+/// absorbed, `finish` returns an `XofReader` for squeezing out the output.
+///
+/// # Examples
+///
+/// ```
+/// use ring::digest;
+///
+/// let mut one_shot = [0u8; 64];
+/// digest::xof(&digest::SHAKE256, b"hello, world", &mut one_shot);
+///
+/// let mut ctx = digest::XofContext::new(&digest::SHAKE256);
+/// ctx.update(b"hello");
+/// ctx.update(b", ");
+/// ctx.update(b"world");
+/// let mut reader = ctx.finish();
+/// let mut multi_part = [0u8; 64];
+/// reader.squeeze(&mut multi_part[..10]);
+/// reader.squeeze(&mut multi_part[10..]);
+///
+/// assert_eq!(&one_shot[..], &multi_part[..]);
+/// ```
+pub struct XofContext {
+    algorithm: &'static XofAlgorithm,
+    sponge: keccak::Sponge,
+}
+
+impl XofContext {
+    /// Constructs a new context.
+    pub fn new(algorithm: &'static XofAlgorithm) -> XofContext {
+        XofContext {
+            algorithm: algorithm,
+            sponge: keccak::Sponge::new(algorithm.rate,
+                                        keccak::SHAKE_DOMAIN),
+        }
+    }
+
+    /// Constructs a new context for cSHAKE, as specified in
+    /// [SP 800-185 Section 3], using the SHAKE variant `algorithm`.
+    ///
+    /// `function_name` is reserved for functions defined by NIST, such as
+    /// "KMAC", and should otherwise be empty. `customization` separates the
+    /// uses of cSHAKE by different applications or purposes. When both are
+    /// empty, cSHAKE is the same as SHAKE.
+    ///
+    /// [SP 800-185 Section 3]:
+    ///     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+    pub fn new_customized(algorithm: &'static XofAlgorithm,
+                          function_name: &[u8], customization: &[u8])
+                          -> XofContext {
+        XofContext {
+            algorithm: algorithm,
+            sponge: keccak::cshake(algorithm.rate, function_name,
+                                   customization),
+        }
+    }
+
+    /// Absorbs `data`. It may be called zero or more times.
+    pub fn update(&mut self, data: &[u8]) { self.sponge.absorb(data); }
+
+    /// Finishes absorbing the input and returns a reader for the output.
+    pub fn finish(self) -> XofReader { XofReader { sponge: self.sponge } }
+
+    /// The algorithm that this context is using.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static XofAlgorithm { self.algorithm }
+}
+
+/// A reader for the output of an XOF.
+pub struct XofReader {
+    sponge: keccak::Sponge,
+}
+
+impl XofReader {
+    /// Fills `out` with the next `out.len()` bytes of the output.
+    ///
+    /// Squeezing the output in several pieces gives the same output as
+    /// squeezing it all at once.
+    pub fn squeeze(&mut self, out: &mut [u8]) { self.sponge.squeeze(out); }
+}
+
+/// Fills `out` with the output of `algorithm` for `data`.
+pub fn xof(algorithm: &'static XofAlgorithm, data: &[u8], out: &mut [u8]) {
+    let mut ctx = XofContext::new(algorithm);
+    ctx.update(data);
+    ctx.finish().squeeze(out);
+}
+
+/// Fills `out` with the cSHAKE output of `algorithm` for `data`, with the
+/// function name `function_name` and the customization string
+/// `customization`. See `XofContext::new_customized`.
+pub fn cshake(algorithm: &'static XofAlgorithm, function_name: &[u8],
+              customization: &[u8], data: &[u8], out: &mut [u8]) {
+    let mut ctx = XofContext::new_customized(algorithm, function_name,
+                                             customization);
+    ctx.update(data);
+    ctx.finish().squeeze(out);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+    use super::*;
+    use test;
+
+    fn test_xof(algorithm: &'static XofAlgorithm, file_name: &str) {
+        test::from_file(file_name, |section, test_case| {
+            assert_eq!(section, "");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let mut actual = vec![0u8; expected.len()];
+            xof(algorithm, &input, &mut actual);
+            assert_eq!(actual, expected);
+
+            // Absorbing and squeezing a byte at a time gives the same output.
+            let mut ctx = XofContext::new(algorithm);
+            for b in input.chunks(1) {
+                ctx.update(b);
+            }
+            let mut reader = ctx.finish();
+            let actual: Vec<u8> = expected.iter().map(|_| {
+                let mut b = [0u8; 1];
+                reader.squeeze(&mut b);
+                b[0]
+            }).collect();
+            assert_eq!(actual, expected);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_shake128() {
+        test_xof(&SHAKE128, "src/digest/shake128_tests.txt");
+    }
+
+    #[test]
+    fn test_shake256() {
+        test_xof(&SHAKE256, "src/digest/shake256_tests.txt");
+    }
+
+    #[test]
+    fn test_cshake() {
+        test::from_file("src/digest/cshake_tests.txt", |section, test_case| {
+            let algorithm = match section {
+                "cSHAKE128" => &SHAKE128,
+                "cSHAKE256" => &SHAKE256,
+                _ => unreachable!(),
+            };
+            let function_name = test_case.consume_bytes("FunctionName");
+            let customization = test_case.consume_bytes("Customization");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let mut actual = vec![0u8; expected.len()];
+            cshake(algorithm, &function_name, &customization, &input,
+                   &mut actual);
+            assert_eq!(actual, expected);
+
+            let mut ctx = XofContext::new_customized(algorithm, &function_name,
+                                                     &customization);
+            let (a, b) = input.split_at(input.len() / 2);
+            ctx.update(a);
+            ctx.update(b);
+            let mut reader = ctx.finish();
+            let mut actual = vec![0u8; expected.len()];
+            {
+                let (x, y) = actual.split_at_mut(expected.len() / 3);
+                reader.squeeze(x);
+                reader.squeeze(y);
+            }
+            assert_eq!(actual, expected);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The SM3 compression function, as specified in [GB/T 32905-2016].
+//!
+//! SM3 uses the same Merkle–Damgård padding as SHA-256, so only the block
+//! function is needed here.
+//!
+//! [GB/T 32905-2016]:
+//!     https://tools.ietf.org/html/draft-sca-cfrg-sm3-02
+
+use {c, polyfill};
+use core;
+use core::num::Wrapping;
+
+// XXX: Like `sha1::State`, this duplicates `super::State`.
+pub type State = [u64; super::MAX_CHAINING_LEN / 8];
+
+pub const BLOCK_LEN: usize = 512 / 8;
+pub const CHAINING_LEN: usize = 256 / 8;
+const CHAINING_WORDS: usize = CHAINING_LEN / 4;
+
+type W32 = Wrapping<u32>;
+
+#[inline]
+fn rotl(x: W32, n: usize) -> W32 {
+    polyfill::wrapping_rotate_left_u32(x, (n % 32) as u32)
+}
+
+#[inline]
+fn p0(x: W32) -> W32 { x ^ rotl(x, 9) ^ rotl(x, 17) }
+
+#[inline]
+fn p1(x: W32) -> W32 { x ^ rotl(x, 15) ^ rotl(x, 23) }
+
+#[inline]
+fn parity(x: W32, y: W32, z: W32) -> W32 { x ^ y ^ z }
+
+#[inline]
+fn maj(x: W32, y: W32, z: W32) -> W32 { (x & y) | (x & z) | (y & z) }
+
+#[inline]
+fn ch(x: W32, y: W32, z: W32) -> W32 { (x & y) | (!x & z) }
+
+/// Like SHA-1, SM3 is only needed for interoperability (here, with SM2), so
+/// this implementation favors size and simplicity over speed, and there is
+/// no assembly language implementation.
+pub unsafe extern fn block_data_order(state: &mut State,
+                                      data: *const u8, num: c::size_t) {
+    let data = data as *const [u8; BLOCK_LEN];
+    let blocks = core::slice::from_raw_parts(data, num);
+    block_data_order_safe(state, blocks)
+}
+
+fn block_data_order_safe(state: &mut State, blocks: &[[u8; BLOCK_LEN]]) {
+    let state = polyfill::slice::u64_as_u32_mut(state);
+    let state = polyfill::slice::as_wrapping_mut(state);
+    let state = &mut state[..CHAINING_WORDS];
+    let state = slice_as_array_ref_mut!(state, CHAINING_WORDS).unwrap();
+
+    let mut w: [W32; 68] = [Wrapping(0); 68];
+    for block in blocks {
+        for j in 0..16 {
+            let word = slice_as_array_ref!(&block[j * 4..][..4], 4).unwrap();
+            w[j] = Wrapping(polyfill::slice::u32_from_be_u8(word))
+        }
+        for j in 16..68 {
+            w[j] = p1(w[j - 16] ^ w[j - 9] ^ rotl(w[j - 3], 15)) ^
+                   rotl(w[j - 13], 7) ^ w[j - 6];
+        }
+
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
+        let mut f = state[5];
+        let mut g = state[6];
+        let mut h = state[7];
+
+        for j in 0..64 {
+            let (t, ff, gg) = match j {
+                0...15 => (0x79cc4519, parity(a, b, c), parity(e, f, g)),
+                16...63 => (0x7a879d8a, maj(a, b, c), ch(e, f, g)),
+                _ => unreachable!(),
+            };
+            let a12 = rotl(a, 12);
+            let ss1 = rotl(a12 + e + rotl(Wrapping(t), j), 7);
+            let ss2 = ss1 ^ a12;
+            let tt1 = ff + d + ss2 + (w[j] ^ w[j + 4]);
+            let tt2 = gg + h + ss1 + w[j];
+            d = c;
+            c = rotl(b, 9);
+            b = a;
+            a = tt1;
+            h = g;
+            g = rotl(f, 19);
+            f = e;
+            e = p0(tt2);
+        }
+
+        // Unlike SHA-1 and SHA-2, the new chaining value is XORed, not added,
+        // into the old one.
+        state[0] = state[0] ^ a;
+        state[1] = state[1] ^ b;
+        state[2] = state[2] ^ c;
+        state[3] = state[3] ^ d;
+        state[4] = state[4] ^ e;
+        state[5] = state[5] ^ f;
+        state[6] = state[6] ^ g;
+        state[7] = state[7] ^ h;
+    }
+}
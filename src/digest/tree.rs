@@ -0,0 +1,309 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! Merkle tree hashing, as specified in [RFC 6962 Section 2.1].
+//!
+//! The leaves of a tree are a list of byte strings, e.g. the entries of a
+//! log or the chunks of a file. Leaves are hashed with a leaf prefix and
+//! interior nodes with a node prefix, so that the hash of a leaf can't be
+//! mistaken for the hash of a node. The tree of `n > 1` leaves has the tree
+//! of the first `k` leaves as its left child and the tree of the rest as its
+//! right child, where `k` is the largest power of two smaller than `n`.
+//!
+//! An inclusion proof (audit path) for a leaf is the list of the hashes of
+//! the siblings of the nodes on the path from the leaf to the root. It lets
+//! somebody who knows only the root, the size of the tree, and the leaf
+//! check that the leaf is in the tree at the given index.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "use_heap")]
+//! # fn main() {
+//! use ring::digest::{self, tree};
+//!
+//! let data = [0u8; 10000];
+//! let chunks: Vec<&[u8]> = data.chunks(4096).collect();
+//!
+//! let hasher = tree::Hasher::new_rfc6962(&digest::SHA256);
+//! let root = hasher.root(&chunks);
+//! let proof = hasher.inclusion_proof(&chunks, 1).unwrap();
+//!
+//! assert!(hasher.verify_inclusion(chunks[1], 1, chunks.len(), &proof,
+//!                                 root.as_ref()).is_ok());
+//! # }
+//! # #[cfg(not(feature = "use_heap"))]
+//! # fn main() { }
+//! ```
+//!
+//! [RFC 6962 Section 2.1]: https://tools.ietf.org/html/rfc6962#section-2.1
+
+use error;
+use super::{Algorithm, Context, Digest, digest};
+
+#[cfg(feature = "use_heap")]
+use std;
+
+/// Hashes the leaves and nodes of Merkle trees with a particular digest
+/// algorithm and pair of prefixes.
+pub struct Hasher<'a> {
+    algorithm: &'static Algorithm,
+    leaf_prefix: &'a [u8],
+    node_prefix: &'a [u8],
+}
+
+impl<'a> Hasher<'a> {
+    /// Constructs a hasher that prefixes leaves with `leaf_prefix` and nodes
+    /// with `node_prefix`.
+    ///
+    /// Fails if one prefix is a prefix of the other (including if they are
+    /// equal), since then some leaves would have the same hash as nodes.
+    pub fn new(algorithm: &'static Algorithm, leaf_prefix: &'a [u8],
+               node_prefix: &'a [u8])
+               -> Result<Hasher<'a>, error::Unspecified> {
+        if leaf_prefix.starts_with(node_prefix) ||
+           node_prefix.starts_with(leaf_prefix) {
+            return Err(error::Unspecified);
+        }
+        Ok(Hasher {
+            algorithm: algorithm,
+            leaf_prefix: leaf_prefix,
+            node_prefix: node_prefix,
+        })
+    }
+
+    /// Constructs a hasher with the prefixes of RFC 6962: a zero byte for
+    /// leaves and a one byte for nodes.
+    pub fn new_rfc6962(algorithm: &'static Algorithm) -> Hasher<'static> {
+        Hasher {
+            algorithm: algorithm,
+            leaf_prefix: &[0x00],
+            node_prefix: &[0x01],
+        }
+    }
+
+    /// The digest algorithm that the hasher uses.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+
+    /// Returns the hash of the leaf `leaf`.
+    pub fn hash_leaf(&self, leaf: &[u8]) -> Digest {
+        let mut ctx = Context::new(self.algorithm);
+        ctx.update(self.leaf_prefix);
+        ctx.update(leaf);
+        ctx.finish()
+    }
+
+    /// Returns the hash of the node whose children have the hashes `left`
+    /// and `right`.
+    pub fn hash_children(&self, left: &[u8], right: &[u8]) -> Digest {
+        let mut ctx = Context::new(self.algorithm);
+        ctx.update(self.node_prefix);
+        ctx.update(left);
+        ctx.update(right);
+        ctx.finish()
+    }
+
+    /// Returns the root of the tree of `leaves`. The root of the empty tree
+    /// is the digest of the empty string.
+    pub fn root(&self, leaves: &[&[u8]]) -> Digest {
+        match leaves.len() {
+            0 => digest(self.algorithm, b""),
+            1 => self.hash_leaf(leaves[0]),
+            n => {
+                let (left, right) = leaves.split_at(split_point(n));
+                let left = self.root(left);
+                let right = self.root(right);
+                self.hash_children(left.as_ref(), right.as_ref())
+            },
+        }
+    }
+
+    /// Returns the inclusion proof for the leaf at `index` in the tree of
+    /// `leaves`, as the concatenation of its hashes in order from the leaf
+    /// to the root.
+    ///
+    /// Fails if `index` isn't less than `leaves.len()`.
+    #[cfg(feature = "use_heap")]
+    pub fn inclusion_proof(&self, leaves: &[&[u8]], index: usize)
+                           -> Result<std::vec::Vec<u8>, error::Unspecified> {
+        if index >= leaves.len() {
+            return Err(error::Unspecified);
+        }
+        let mut proof = std::vec::Vec::new();
+        self.append_path(leaves, index, &mut proof);
+        Ok(proof)
+    }
+
+    #[cfg(feature = "use_heap")]
+    fn append_path(&self, leaves: &[&[u8]], index: usize,
+                   proof: &mut std::vec::Vec<u8>) {
+        if leaves.len() <= 1 {
+            return;
+        }
+        let k = split_point(leaves.len());
+        let (left, right) = leaves.split_at(k);
+        if index < k {
+            self.append_path(left, index, proof);
+            proof.extend_from_slice(self.root(right).as_ref());
+        } else {
+            self.append_path(right, index - k, proof);
+            proof.extend_from_slice(self.root(left).as_ref());
+        }
+    }
+
+    /// Verifies that `leaf` is the leaf at `index` in the tree of
+    /// `tree_size` leaves with the root `root`, using the inclusion proof
+    /// `proof` in the format returned by `inclusion_proof`.
+    pub fn verify_inclusion(&self, leaf: &[u8], index: usize,
+                            tree_size: usize, proof: &[u8], root: &[u8])
+                            -> Result<(), error::Unspecified> {
+        let hash_len = self.algorithm.output_len;
+        if index >= tree_size || proof.len() % hash_len != 0 {
+            return Err(error::Unspecified);
+        }
+
+        // RFC 6962-bis Section 2.1.3.2.
+        let mut node = index;
+        let mut last_node = tree_size - 1;
+        let mut hash = self.hash_leaf(leaf);
+        for sibling in proof.chunks(hash_len) {
+            if last_node == 0 {
+                return Err(error::Unspecified);
+            }
+            if node % 2 == 1 || node == last_node {
+                hash = self.hash_children(sibling, hash.as_ref());
+                // Skip the levels at which the node has no right sibling.
+                while node % 2 == 0 && node != 0 {
+                    node /= 2;
+                    last_node /= 2;
+                }
+            } else {
+                hash = self.hash_children(hash.as_ref(), sibling);
+            }
+            node /= 2;
+            last_node /= 2;
+        }
+        if last_node != 0 || hash.as_ref() != root {
+            return Err(error::Unspecified);
+        }
+        Ok(())
+    }
+}
+
+// The number of leaves in the left subtree of a tree of `n > 1` leaves: the
+// largest power of two smaller than `n`.
+fn split_point(n: usize) -> usize {
+    debug_assert!(n > 1);
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+    use super::*;
+    use super::super::{SHA256, SHA384};
+    use test;
+
+    fn leaves() -> Vec<Vec<u8>> {
+        ["", "00", "10", "2021", "3031", "40414243", "5051525354555657",
+         "606162636465666768696a6b6c6d6e6f"]
+            .iter().map(|leaf| test::from_hex(leaf).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_tree() {
+        let leaves = leaves();
+        let leaves: Vec<&[u8]> = leaves.iter().map(|leaf| &leaf[..]).collect();
+        let rfc6962 = Hasher::new_rfc6962(&SHA256);
+        let custom = Hasher::new(&SHA384, b"leaf:", b"node:").unwrap();
+
+        test::from_file("src/digest/tree_tests.txt", |section, test_case| {
+            let hasher = match section {
+                "Roots" | "Proofs" => &rfc6962,
+                "CustomPrefixes" => &custom,
+                _ => unreachable!(),
+            };
+            let tree_size = test_case.consume_usize("TreeSize");
+            let leaves = &leaves[..tree_size];
+            let index = if section == "Proofs" {
+                Some(test_case.consume_usize("Index"))
+            } else {
+                None
+            };
+            let root = test_case.consume_bytes("Root");
+            assert_eq!(hasher.root(leaves).as_ref(), &root[..]);
+
+            let index = match index {
+                Some(index) => index,
+                None => { return Ok(()); },
+            };
+            let proof = test_case.consume_bytes("Proof");
+            assert!(hasher.verify_inclusion(leaves[index], index, tree_size,
+                                            &proof, &root).is_ok());
+            #[cfg(feature = "use_heap")]
+            assert_eq!(hasher.inclusion_proof(leaves, index).unwrap(), proof);
+
+            // The proof doesn't prove the inclusion of any other leaf, or of
+            // the same leaf at any other index.
+            for (other, other_leaf) in leaves.iter().enumerate() {
+                if other_leaf != &leaves[index] {
+                    assert!(hasher.verify_inclusion(other_leaf, index,
+                                                    tree_size, &proof, &root)
+                                  .is_err());
+                }
+                if other != index {
+                    assert!(hasher.verify_inclusion(leaves[index], other,
+                                                    tree_size, &proof, &root)
+                                  .is_err());
+                }
+            }
+            assert!(hasher.verify_inclusion(leaves[index], index, index,
+                                            &proof, &root).is_err());
+
+            // Truncated, extended, and corrupted proofs are rejected.
+            if !proof.is_empty() {
+                assert!(hasher.verify_inclusion(
+                            leaves[index], index, tree_size,
+                            &proof[..(proof.len() - 32)], &root).is_err());
+                let mut corrupted = proof.clone();
+                corrupted[0] ^= 1;
+                assert!(hasher.verify_inclusion(leaves[index], index,
+                                                tree_size, &corrupted, &root)
+                              .is_err());
+            }
+            let mut extended = proof.clone();
+            extended.extend_from_slice(&root);
+            assert!(hasher.verify_inclusion(leaves[index], index, tree_size,
+                                            &extended, &root).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_tree_prefixes() {
+        assert!(Hasher::new(&SHA256, b"", b"").is_err());
+        assert!(Hasher::new(&SHA256, b"a", b"a").is_err());
+        assert!(Hasher::new(&SHA256, b"a", b"ab").is_err());
+        assert!(Hasher::new(&SHA256, b"ab", b"a").is_err());
+        assert!(Hasher::new(&SHA256, b"", b"\x01").is_err());
+        assert!(Hasher::new(&SHA256, b"\x00", b"\x01").is_ok());
+    }
+}
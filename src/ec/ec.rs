@@ -75,7 +75,8 @@ impl<'a> PrivateKey {
 }
 
 
-const ELEM_MAX_BITS: usize = 384;
+// X448's 448-bit elements are the largest; P-384's are next.
+const ELEM_MAX_BITS: usize = 448;
 pub const ELEM_MAX_BYTES: usize = (ELEM_MAX_BITS + 7) / 8;
 
 const SCALAR_MAX_BYTES: usize = ELEM_MAX_BYTES;
@@ -90,3 +91,5 @@ pub mod eddsa;
 pub mod suite_b;
 
 pub mod x25519;
+
+pub mod x448;
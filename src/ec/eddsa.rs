@@ -14,15 +14,31 @@
 
 //! EdDSA Signatures.
 
-use {bssl, c, error, private, rand, signature};
+use {bssl, c, digest, error, init, private, rand, signature};
+#[cfg(feature = "use_heap")]
+use {der, pkcs8};
 use untrusted;
 
 /// Parameters for EdDSA signing and verification.
-pub struct EdDSAParameters;
+pub struct EdDSAParameters {
+    variant: Variant,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Variant {
+    Ed25519,
+    Ed25519ph,
+    Ed448,
+}
 
 /// An Ed25519 key pair, for signing.
+///
+/// The private key is stored in expanded form, i.e. as the clamped secret
+/// scalar and the nonce prefix derived from the seed with SHA-512, so that
+/// signing doesn't need to repeat that derivation for every signature.
 pub struct Ed25519KeyPair {
-    private_public: [u8; 64],
+    expanded_private_key: [u8; 64],
+    public_key: [u8; 32],
 }
 
 /// The raw bytes of the Ed25519 key pair, for serialization.
@@ -65,12 +81,96 @@ impl<'a> Ed25519KeyPair {
         Ok((key_pair, bytes))
     }
 
+    /// Generates a new key pair and returns the key pair serialized as a
+    /// PKCS#8 v2 document (see [RFC 5958]) that includes the public key, using
+    /// the `id-Ed25519` algorithm identifier (see [RFC 8410]).
+    ///
+    /// Use `from_pkcs8()` to load the key pair from the document.
+    ///
+    /// [RFC 5958]: https://tools.ietf.org/html/rfc5958
+    /// [RFC 8410]: https://tools.ietf.org/html/rfc8410
+    #[cfg(feature = "use_heap")]
+    pub fn generate_pkcs8(rng: &rand::SecureRandom)
+                          -> Result<pkcs8::Document, error::Unspecified> {
+        let (_, bytes) = try!(Ed25519KeyPair::generate_serializable(rng));
+        let mut private_key = [0u8; 2 + 32];
+        private_key[0] = der::Tag::OctetString as u8;
+        private_key[1] = 32;
+        private_key[2..].copy_from_slice(&bytes.private_key);
+        let document = pkcs8::wrap_key_v2(&ED25519_ALG_ID, &private_key,
+                                          &bytes.public_key);
+        for b in private_key.iter_mut() {
+            *b = 0;
+        }
+        Ok(document)
+    }
+
+    /// Constructs an Ed25519 key pair by parsing an unencrypted PKCS#8 v2
+    /// Ed25519 private key, such as one produced by `generate_pkcs8()`.
+    ///
+    /// PKCS#8 v1 documents, which don't contain the public key, are not
+    /// supported. The private and public keys are verified to be consistent,
+    /// as in `from_bytes()`.
+    #[cfg(feature = "use_heap")]
+    pub fn from_pkcs8(input: untrusted::Input)
+                      -> Result<Ed25519KeyPair, error::Unspecified> {
+        let (private_key, public_key) =
+            try!(pkcs8::unwrap_key_v2(&ED25519_ALG_ID, input));
+        // The `privateKey` is a `CurvePrivateKey`, which is an `OCTET STRING`
+        // containing the 32-byte private key.
+        let private_key = try!(private_key.read_all(error::Unspecified, |input| {
+            der::expect_tag_and_get_value(input, der::Tag::OctetString)
+        }));
+        Ed25519KeyPair::from_bytes(private_key.as_slice_less_safe(),
+                                   public_key.as_slice_less_safe())
+    }
+
+    /// Constructs an Ed25519 key pair from the 32-byte private key seed
+    /// `seed`, computing the public key from it.
+    ///
+    /// This is intended for use by code that deserializes key pairs in
+    /// formats that store only the seed, such as those used by SSH, Signal,
+    /// and Tor. Since there is no public key to check the seed against, it
+    /// is the caller's responsibility to ensure that `seed` wasn't corrupted
+    /// or swapped with some other value; prefer `from_seed_and_public_key()`
+    /// when the public key is available.
+    pub fn from_seed_unchecked(seed: &[u8])
+                               -> Result<Ed25519KeyPair, error::Unspecified> {
+        if seed.len() != 32 {
+            return Err(error::Unspecified);
+        }
+        let mut public_key = [0; 32];
+        unsafe {
+            GFp_ed25519_public_from_private(public_key.as_mut_ptr(),
+                                            seed.as_ptr());
+        }
+        Ed25519KeyPair::from_bytes_unchecked(seed, &public_key)
+    }
+
+    /// Constructs an Ed25519 key pair from the 32-byte private key seed
+    /// `seed` and the 32-byte public key `public_key`.
+    ///
+    /// The seed and public key will be verified to be consistent. This helps
+    /// protect, for example, against the accidental swapping of the public
+    /// and private components of the key pair. This also detects corruption
+    /// that might have occurred during storage of the key pair.
+    pub fn from_seed_and_public_key(seed: &[u8], public_key: &[u8])
+                                    -> Result<Ed25519KeyPair,
+                                              error::Unspecified> {
+        let pair = try!(Ed25519KeyPair::from_seed_unchecked(seed));
+        if public_key != pair.public_key_bytes() {
+            return Err(error::Unspecified);
+        }
+        Ok(pair)
+    }
+
     /// Copies key data from the given slices to create a new key pair. The
     /// first slice must hold the private key and the second slice must hold
     /// the public key. Both slices must contain 32 little-endian-encoded
     /// bytes.
     ///
-    /// This is intended for use by code that deserializes key pairs.
+    /// This is intended for use by code that deserializes key pairs. It is
+    /// equivalent to `from_seed_and_public_key()`.
     ///
     /// The private and public keys will be verified to be consistent. This
     /// helps protect, for example, against the accidental swapping of the
@@ -78,17 +178,7 @@ impl<'a> Ed25519KeyPair {
     /// corruption that might have occurred during storage of the key pair.
     pub fn from_bytes(private_key: &[u8], public_key: &[u8])
                       -> Result<Ed25519KeyPair, error::Unspecified> {
-        let pair = try!(Ed25519KeyPair::from_bytes_unchecked(private_key,
-                                                             public_key));
-        let mut public_key_check = [0; 32];
-        unsafe {
-            GFp_ed25519_public_from_private(public_key_check.as_mut_ptr(),
-                                            pair.private_public.as_ptr());
-        }
-        if public_key != public_key_check {
-            return Err(error::Unspecified);
-        }
-        Ok(pair)
+        Ed25519KeyPair::from_seed_and_public_key(private_key, public_key)
     }
 
     fn from_bytes_unchecked(private_key: &[u8], public_key: &[u8])
@@ -99,72 +189,574 @@ impl<'a> Ed25519KeyPair {
         if public_key.len() != 32 {
             return Err(error::Unspecified);
         }
-        let mut pair = Ed25519KeyPair { private_public: [0; 64] };
-        for i in 0..32 {
-            pair.private_public[i] = private_key[i];
-            pair.private_public[32 + i] = public_key[i];
-        }
+        let mut pair = Ed25519KeyPair {
+            expanded_private_key: [0; 64],
+            public_key: [0; 32],
+        };
+
+        // RFC 8032 Section 5.1.5, Steps 1 and 2.
+        let digest = digest::digest(&digest::SHA512, private_key);
+        pair.expanded_private_key.copy_from_slice(digest.as_ref());
+        pair.expanded_private_key[0] &= 248;
+        pair.expanded_private_key[31] &= 63;
+        pair.expanded_private_key[31] |= 64;
+
+        pair.public_key.copy_from_slice(public_key);
         Ok(pair)
     }
 
     /// Returns a reference to the little-endian-encoded public key bytes.
-    pub fn public_key_bytes(&'a self) -> &'a [u8] { &self.private_public[32..] }
+    pub fn public_key_bytes(&'a self) -> &'a [u8] { &self.public_key }
+
+    /// Returns the digest, using `digest_alg`, of the public key in
+    /// DER-encoded X.509 `SubjectPublicKeyInfo` form, using the `id-Ed25519`
+    /// algorithm identifier (see [RFC 8410 Section 4]).
+    ///
+    /// [RFC 8410 Section 4]: https://tools.ietf.org/html/rfc8410#section-4
+    pub fn fingerprint(&self, digest_alg: &'static digest::Algorithm)
+                       -> digest::Digest {
+        let mut ctx = digest::Context::new(digest_alg);
+        ctx.update(&ED25519_SPKI_PREFIX);
+        ctx.update(self.public_key_bytes());
+        ctx.finish()
+    }
 
     /// Returns the signature of the message `msg`.
     pub fn sign(&self, msg: &[u8]) -> signature::Signature {
+        self.sign_with_dom(&[], msg)
+    }
+
+    /// Returns the Ed25519ph signature of the message `msg`, with an empty
+    /// context (see [RFC 8032 Section 5.1]). Such signatures can be verified
+    /// with `ED25519PH`.
+    ///
+    /// [RFC 8032 Section 5.1]: https://tools.ietf.org/html/rfc8032#section-5.1
+    pub fn sign_ph(&self, msg: &[u8]) -> signature::Signature {
+        let digest = digest::digest(&digest::SHA512, msg);
+        self.sign_with_dom(ED25519PH_DOM, digest.as_ref())
+    }
+
+    /// Returns the Ed25519ph signature of the message whose SHA-512 digest is
+    /// `digest`. This is the same as `sign_ph()`, except the caller digests
+    /// the message, e.g. incrementally with `digest::Context`, so that the
+    /// whole message doesn't need to be in memory at once.
+    ///
+    /// Fails if `digest` wasn't calculated with `digest::SHA512`.
+    pub fn sign_ph_digest(&self, digest: &digest::Digest)
+                          -> Result<signature::Signature, error::Unspecified> {
+        let digest = try!(ed25519ph_digest_bytes(digest));
+        Ok(self.sign_with_dom(ED25519PH_DOM, digest))
+    }
+
+    /// Returns the Ed25519ctx signature of the message `msg` with the context
+    /// `context` (see [RFC 8032 Section 5.1]). Such signatures can be
+    /// verified with `signature::primitive::verify_ed25519ctx()`.
+    ///
+    /// Fails if `context` is empty or longer than 255 bytes. (RFC 8032 says
+    /// the context SHOULD NOT be empty; use `sign()` when there is no
+    /// context.)
+    ///
+    /// [RFC 8032 Section 5.1]: https://tools.ietf.org/html/rfc8032#section-5.1
+    pub fn sign_ctx(&self, context: &[u8], msg: &[u8])
+                    -> Result<signature::Signature, error::Unspecified> {
+        let mut dom = [0u8; ED25519_DOM_MAX_LEN];
+        let dom = try!(ed25519ctx_dom(context, &mut dom));
+        Ok(self.sign_with_dom(dom, msg))
+    }
+
+    fn sign_with_dom(&self, dom: &[u8], msg: &[u8]) -> signature::Signature {
         let mut signature_bytes = [0u8; 64];
         unsafe {
-            GFp_ed25519_sign(signature_bytes.as_mut_ptr(), msg.as_ptr(),
-                             msg.len(), self.private_public.as_ptr());
+            GFp_ed25519_sign(signature_bytes.as_mut_ptr(), dom.as_ptr(),
+                             dom.len(), msg.as_ptr(), msg.len(),
+                             self.expanded_private_key.as_ptr(),
+                             self.public_key.as_ptr());
         }
         signature::Signature::new(signature_bytes)
     }
 }
 
+impl signature::KeyPair for Ed25519KeyPair {
+    /// The 32-byte public key, the form accepted by `ED25519`; the same as
+    /// `public_key_bytes()`.
+    type PublicKey = [u8];
+
+    fn public_key(&self) -> &[u8] { self.public_key_bytes() }
+}
+
+impl signature::Signer for Ed25519KeyPair {
+    fn public_key_bytes(&self) -> &[u8] { self.public_key_bytes() }
+
+    fn max_signature_len(&self) -> usize { 64 }
+
+    fn sign(&self, msg: &[u8], _rng: &rand::SecureRandom,
+            signature: &mut [u8]) -> Result<usize, error::Unspecified> {
+        Ed25519KeyPair::sign(self, msg).write_to(signature)
+    }
+}
+
+
+// The DER encoding of a `SubjectPublicKeyInfo` for an Ed25519 public key, up
+// to the public key bytes: a `SEQUENCE` containing the `AlgorithmIdentifier`
+// for `id-Ed25519` (1.3.101.112) and a `BIT STRING` of the 32-byte key.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a,
+        0x30, 0x05,
+            0x06, 0x03, 0x2b, 0x65, 0x70,
+        0x03, 0x21, 0x00,
+];
+
+// The DER encoding of the `AlgorithmIdentifier` for `id-Ed25519`
+// (1.3.101.112), which has absent parameters (see RFC 8410 Section 3).
+#[cfg(feature = "use_heap")]
+const ED25519_ALG_ID: [u8; 7] = [
+    0x30, 0x05,
+        0x06, 0x03, 0x2b, 0x65, 0x70,
+];
+
+
+// dom2(1, "") from RFC 8032 Section 2: the prefix that distinguishes
+// Ed25519ph (with an empty context) from Ed25519.
+const ED25519PH_DOM: &'static [u8] = b"SigEd25519 no Ed25519 collisions\x01\x00";
+
+// The length of dom2(x, y) from RFC 8032 Section 2 for the longest context,
+// which is 255 bytes.
+const ED25519_DOM_MAX_LEN: usize = 32 + 1 + 1 + 255;
+
+// Writes dom2(0, context) from RFC 8032 Section 2, the prefix for Ed25519ctx
+// with the context `context`, to `out`.
+fn ed25519ctx_dom<'a>(context: &[u8], out: &'a mut [u8; ED25519_DOM_MAX_LEN])
+                      -> Result<&'a [u8], error::Unspecified> {
+    if context.is_empty() || context.len() > 255 {
+        return Err(error::Unspecified);
+    }
+    // The same as `ED25519PH_DOM`, but with a zero flag and the context.
+    let prefix = &ED25519PH_DOM[..32];
+    out[..32].copy_from_slice(prefix);
+    out[32] = 0;
+    out[33] = context.len() as u8;
+    let len = 34 + context.len();
+    out[34..len].copy_from_slice(context);
+    Ok(&out[..len])
+}
+
+fn ed25519ph_digest_bytes(digest: &digest::Digest)
+                          -> Result<&[u8], error::Unspecified> {
+    let actual: *const digest::Algorithm = digest.algorithm();
+    let expected: *const digest::Algorithm = &digest::SHA512;
+    if actual != expected {
+        return Err(error::Unspecified);
+    }
+    Ok(digest.as_ref())
+}
+
 
 /// Verification of [Ed25519] signatures.
 ///
 /// Ed25519 uses SHA-512 as the digest algorithm.
 ///
 /// [Ed25519]: https://ed25519.cr.yp.to/
-pub static ED25519: EdDSAParameters = EdDSAParameters {};
+pub static ED25519: EdDSAParameters = EdDSAParameters {
+    variant: Variant::Ed25519,
+};
+
+/// Verification of Ed25519ph signatures, with an empty context (see
+/// [RFC 8032 Section 5.1]).
+///
+/// The message is digested with SHA-512 before the signature is checked. To
+/// verify a signature of a message that has already been digested, use
+/// `signature::primitive::verify_ed25519ph_digest()`.
+///
+/// [RFC 8032 Section 5.1]: https://tools.ietf.org/html/rfc8032#section-5.1
+pub static ED25519PH: EdDSAParameters = EdDSAParameters {
+    variant: Variant::Ed25519ph,
+};
+
+/// Verification of [Ed448] signatures, with an empty context (see
+/// [RFC 8032 Section 5.2]).
+///
+/// Ed448 uses SHAKE256 as the digest algorithm. The public key is the 57-byte
+/// encoding of the point and the signature is 114 bytes.
+///
+/// [Ed448]: https://tools.ietf.org/html/rfc8032#section-5.2
+/// [RFC 8032 Section 5.2]: https://tools.ietf.org/html/rfc8032#section-5.2
+pub static ED448: EdDSAParameters = EdDSAParameters {
+    variant: Variant::Ed448,
+};
 
 impl signature::VerificationAlgorithm for EdDSAParameters {
     fn verify(&self, public_key: untrusted::Input, msg: untrusted::Input,
               signature: untrusted::Input) -> Result<(), error::Unspecified> {
-        let public_key = public_key.as_slice_less_safe();
-        if public_key.len() != 32 || signature.len() != 64 {
+        let msg = msg.as_slice_less_safe();
+        match self.variant {
+            Variant::Ed25519 =>
+                verify_with_dom(&[], public_key, msg, signature),
+            Variant::Ed25519ph => {
+                let digest = digest::digest(&digest::SHA512, msg);
+                verify_with_dom(ED25519PH_DOM, public_key, digest.as_ref(),
+                                signature)
+            },
+            Variant::Ed448 => ed448_verify(public_key, msg, signature),
+        }
+    }
+}
+
+/// Verifies the Ed25519ph signature `signature`, with an empty context, of
+/// the message whose SHA-512 digest is `digest`, using the public key
+/// `public_key`.
+///
+/// This is the counterpart of `Ed25519KeyPair::sign_ph_digest()`. Fails if
+/// `digest` wasn't calculated with `digest::SHA512`.
+pub fn verify_ed25519ph_digest(public_key: untrusted::Input,
+                               digest: &digest::Digest,
+                               signature: untrusted::Input)
+                               -> Result<(), error::Unspecified> {
+    let digest = try!(ed25519ph_digest_bytes(digest));
+    verify_with_dom(ED25519PH_DOM, public_key, digest, signature)
+}
+
+/// Verifies the Ed25519ctx signature `signature` of the message `msg` with
+/// the context `context`, using the public key `public_key`.
+///
+/// This is the counterpart of `Ed25519KeyPair::sign_ctx()`. Fails if
+/// `context` is empty or longer than 255 bytes.
+pub fn verify_ed25519ctx(public_key: untrusted::Input,
+                         context: untrusted::Input, msg: untrusted::Input,
+                         signature: untrusted::Input)
+                         -> Result<(), error::Unspecified> {
+    let mut dom = [0u8; ED25519_DOM_MAX_LEN];
+    let dom = try!(ed25519ctx_dom(context.as_slice_less_safe(), &mut dom));
+    verify_with_dom(dom, public_key, msg.as_slice_less_safe(), signature)
+}
+
+fn verify_with_dom(dom: &[u8], public_key: untrusted::Input, msg: &[u8],
+                   signature: untrusted::Input)
+                   -> Result<(), error::Unspecified> {
+    let public_key = public_key.as_slice_less_safe();
+    if public_key.len() != 32 || signature.len() != 64 {
+        return Err(error::Unspecified);
+    }
+    let signature = signature.as_slice_less_safe();
+    bssl::map_result(unsafe {
+        GFp_ed25519_verify(dom.as_ptr(), dom.len(), msg.as_ptr(), msg.len(),
+                           signature.as_ptr(), public_key.as_ptr())
+    })
+}
+
+/// Verifies a batch of Ed25519 signatures at once, which is substantially
+/// faster than verifying each of them with `signature::verify()`.
+///
+/// Each item of `items` is a `(public_key, msg, signature)` triple, in the
+/// same order as the arguments of `signature::verify()`. The result is
+/// `Ok(())` only if every signature is valid; it doesn't say which
+/// signatures are invalid, so callers that need to know that should fall
+/// back to verifying the signatures individually.
+///
+/// The signatures are checked together by verifying a random linear
+/// combination of their verification equations, using `rng` to choose the
+/// coefficients. The combined equation is multiplied by the cofactor 8, so a
+/// signature involving points that have a small-order component may be
+/// accepted by `verify_batch()` even though `signature::verify()` would reject
+/// it. No signature produced by a conforming signer is affected.
+pub fn verify_batch(items: &[(untrusted::Input, untrusted::Input,
+                              untrusted::Input)],
+                    rng: &rand::SecureRandom)
+                    -> Result<(), error::Unspecified> {
+    init::init_once();
+
+    for chunk in items.chunks(ED25519_VERIFY_BATCH_MAX) {
+        let mut public_keys = [0u8; ED25519_VERIFY_BATCH_MAX * 32];
+        let mut signatures = [0u8; ED25519_VERIFY_BATCH_MAX * 64];
+        let mut hrams = [0u8; ED25519_VERIFY_BATCH_MAX * 64];
+        let mut z = [0u8; ED25519_VERIFY_BATCH_MAX * 16];
+
+        for (i, &(public_key, msg, signature)) in chunk.iter().enumerate() {
+            let public_key = public_key.as_slice_less_safe();
+            let signature = signature.as_slice_less_safe();
+            if public_key.len() != 32 || signature.len() != 64 {
+                return Err(error::Unspecified);
+            }
+            public_keys[(i * 32)..((i + 1) * 32)].copy_from_slice(public_key);
+            signatures[(i * 64)..((i + 1) * 64)].copy_from_slice(signature);
+
+            let mut ctx = digest::Context::new(&digest::SHA512);
+            ctx.update(&signature[..32]);
+            ctx.update(public_key);
+            ctx.update(msg.as_slice_less_safe());
+            hrams[(i * 64)..((i + 1) * 64)]
+                .copy_from_slice(ctx.finish().as_ref());
+        }
+        try!(rng.fill(&mut z[..(chunk.len() * 16)]));
+
+        try!(bssl::map_result(unsafe {
+            GFp_ed25519_verify_batch(chunk.len(), public_keys.as_ptr(),
+                                     signatures.as_ptr(), hrams.as_ptr(),
+                                     z.as_ptr())
+        }));
+    }
+
+    Ok(())
+}
+
+// Must match `ED25519_VERIFY_BATCH_MAX` in curve25519.c.
+const ED25519_VERIFY_BATCH_MAX: usize = 16;
+
+impl private::Private for EdDSAParameters {}
+
+
+/// An Ed448 key pair, for signing.
+pub struct Ed448KeyPair {
+    private_public: [u8; 2 * ED448_ELEM_LEN],
+}
+
+impl<'a> Ed448KeyPair {
+    /// Generates a new random key pair. There is no way to extract the private
+    /// key bytes to save them; use `generate_pkcs8()` if the key pair needs to
+    /// be saved.
+    pub fn generate(rng: &rand::SecureRandom)
+                    -> Result<Ed448KeyPair, error::Unspecified> {
+        let mut seed = [0u8; ED448_ELEM_LEN];
+        try!(rng.fill(&mut seed));
+        Ed448KeyPair::from_seed_unchecked(&seed)
+    }
+
+    /// Generates a new key pair and returns the key pair serialized as a
+    /// PKCS#8 v2 document (see [RFC 5958]) that includes the public key, using
+    /// the `id-Ed448` algorithm identifier (see [RFC 8410]).
+    ///
+    /// Use `from_pkcs8()` to load the key pair from the document.
+    ///
+    /// [RFC 5958]: https://tools.ietf.org/html/rfc5958
+    /// [RFC 8410]: https://tools.ietf.org/html/rfc8410
+    #[cfg(feature = "use_heap")]
+    pub fn generate_pkcs8(rng: &rand::SecureRandom)
+                          -> Result<pkcs8::Document, error::Unspecified> {
+        let key_pair = try!(Ed448KeyPair::generate(rng));
+        let mut private_key = [0u8; 2 + ED448_ELEM_LEN];
+        private_key[0] = der::Tag::OctetString as u8;
+        private_key[1] = ED448_ELEM_LEN as u8;
+        private_key[2..].copy_from_slice(
+            &key_pair.private_public[..ED448_ELEM_LEN]);
+        let document = pkcs8::wrap_key_v2(&ED448_ALG_ID, &private_key,
+                                          key_pair.public_key_bytes());
+        for b in private_key.iter_mut() {
+            *b = 0;
+        }
+        Ok(document)
+    }
+
+    /// Constructs an Ed448 key pair by parsing an unencrypted PKCS#8 v2
+    /// Ed448 private key, such as one produced by `generate_pkcs8()`.
+    ///
+    /// PKCS#8 v1 documents, which don't contain the public key, are not
+    /// supported. The private and public keys are verified to be consistent,
+    /// as in `from_seed_and_public_key()`.
+    #[cfg(feature = "use_heap")]
+    pub fn from_pkcs8(input: untrusted::Input)
+                      -> Result<Ed448KeyPair, error::Unspecified> {
+        let (private_key, public_key) =
+            try!(pkcs8::unwrap_key_v2(&ED448_ALG_ID, input));
+        // The `privateKey` is a `CurvePrivateKey`, which is an `OCTET STRING`
+        // containing the 57-byte private key.
+        let private_key = try!(private_key.read_all(error::Unspecified, |input| {
+            der::expect_tag_and_get_value(input, der::Tag::OctetString)
+        }));
+        Ed448KeyPair::from_seed_and_public_key(
+            private_key.as_slice_less_safe(), public_key.as_slice_less_safe())
+    }
+
+    /// Constructs an Ed448 key pair from the 57-byte private key seed `seed`,
+    /// computing the public key from it.
+    ///
+    /// Since there is no public key to check the seed against, it is the
+    /// caller's responsibility to ensure that `seed` wasn't corrupted or
+    /// swapped with some other value; prefer `from_seed_and_public_key()`
+    /// when the public key is available.
+    pub fn from_seed_unchecked(seed: &[u8])
+                               -> Result<Ed448KeyPair, error::Unspecified> {
+        if seed.len() != ED448_ELEM_LEN {
             return Err(error::Unspecified);
         }
-        let msg = msg.as_slice_less_safe();
-        let signature = signature.as_slice_less_safe();
-        bssl::map_result(unsafe {
-            GFp_ed25519_verify(msg.as_ptr(), msg.len(), signature.as_ptr(),
-                               public_key.as_ptr())
+        let mut pair = Ed448KeyPair {
+            private_public: [0; 2 * ED448_ELEM_LEN],
+        };
+        pair.private_public[..ED448_ELEM_LEN].copy_from_slice(seed);
+        let (scalar, _) = ed448_expand_seed(seed);
+        unsafe {
+            GFp_ed448_scalar_mult_base(
+                pair.private_public[ED448_ELEM_LEN..].as_mut_ptr(),
+                scalar.as_ptr());
+        }
+        Ok(pair)
+    }
+
+    /// Constructs an Ed448 key pair from the 57-byte private key seed `seed`
+    /// and the 57-byte public key `public_key`.
+    ///
+    /// The seed and public key will be verified to be consistent. This helps
+    /// protect, for example, against the accidental swapping of the public
+    /// and private components of the key pair. This also detects corruption
+    /// that might have occurred during storage of the key pair.
+    pub fn from_seed_and_public_key(seed: &[u8], public_key: &[u8])
+                                    -> Result<Ed448KeyPair,
+                                              error::Unspecified> {
+        let pair = try!(Ed448KeyPair::from_seed_unchecked(seed));
+        if public_key != pair.public_key_bytes() {
+            return Err(error::Unspecified);
+        }
+        Ok(pair)
+    }
+
+    /// Returns a reference to the 57-byte encoded public key.
+    pub fn public_key_bytes(&'a self) -> &'a [u8] {
+        &self.private_public[ED448_ELEM_LEN..]
+    }
+
+    /// Returns the signature of the message `msg`, with an empty context.
+    pub fn sign(&self, msg: &[u8]) -> signature::Signature {
+        let (seed, public_key) = self.private_public.split_at(ED448_ELEM_LEN);
+        let (scalar, prefix) = ed448_expand_seed(seed);
+
+        let mut r = [0u8; ED448_ELEM_LEN];
+        ed448_hash_to_scalar(&[ED448_DOM, &prefix, msg], &mut r);
+
+        signature::Signature::new_using(|out| {
+            let (r_bytes, rest) = out.split_at_mut(ED448_ELEM_LEN);
+            let s_bytes = &mut rest[..ED448_ELEM_LEN];
+            unsafe {
+                GFp_ed448_scalar_mult_base(r_bytes.as_mut_ptr(), r.as_ptr());
+            }
+            let mut k = [0u8; ED448_ELEM_LEN];
+            ed448_hash_to_scalar(&[ED448_DOM, r_bytes, public_key, msg],
+                                 &mut k);
+            unsafe {
+                GFp_ed448_scalar_mul_add(s_bytes.as_mut_ptr(), k.as_ptr(),
+                                         scalar.as_ptr(), r.as_ptr());
+            }
+            ED448_SIGNATURE_LEN
         })
     }
 }
 
-impl private::Private for EdDSAParameters {}
+impl signature::KeyPair for Ed448KeyPair {
+    /// The 57-byte public key, the form accepted by `ED448`; the same as
+    /// `public_key_bytes()`.
+    type PublicKey = [u8];
+
+    fn public_key(&self) -> &[u8] { self.public_key_bytes() }
+}
+
+impl signature::Signer for Ed448KeyPair {
+    fn public_key_bytes(&self) -> &[u8] { self.public_key_bytes() }
+
+    fn max_signature_len(&self) -> usize { ED448_SIGNATURE_LEN }
+
+    fn sign(&self, msg: &[u8], _rng: &rand::SecureRandom,
+            signature: &mut [u8]) -> Result<usize, error::Unspecified> {
+        Ed448KeyPair::sign(self, msg).write_to(signature)
+    }
+}
+
+// The length of an encoded Ed448 point, scalar, or private key seed.
+const ED448_ELEM_LEN: usize = 57;
+
+const ED448_SIGNATURE_LEN: usize = 2 * ED448_ELEM_LEN;
+
+// dom4(0, "") from RFC 8032 Section 2, which prefixes every Ed448 hash input.
+const ED448_DOM: &'static [u8] = b"SigEd448\x00\x00";
+
+// The DER encoding of the `AlgorithmIdentifier` for `id-Ed448`
+// (1.3.101.113), which has absent parameters (see RFC 8410 Section 3).
+#[cfg(feature = "use_heap")]
+const ED448_ALG_ID: [u8; 7] = [
+    0x30, 0x05,
+        0x06, 0x03, 0x2b, 0x65, 0x71,
+];
+
+// Returns the clamped secret scalar and the hash prefix for `seed`, as in
+// RFC 8032 Section 5.2.5.
+fn ed448_expand_seed(seed: &[u8])
+                     -> ([u8; ED448_ELEM_LEN], [u8; ED448_ELEM_LEN]) {
+    let mut h = [0u8; 2 * ED448_ELEM_LEN];
+    digest::keccak::shake256(&[seed], &mut h);
+    let mut scalar = [0u8; ED448_ELEM_LEN];
+    let mut prefix = [0u8; ED448_ELEM_LEN];
+    scalar.copy_from_slice(&h[..ED448_ELEM_LEN]);
+    prefix.copy_from_slice(&h[ED448_ELEM_LEN..]);
+    scalar[0] &= 0xfc;
+    scalar[ED448_ELEM_LEN - 2] |= 0x80;
+    scalar[ED448_ELEM_LEN - 1] = 0;
+    (scalar, prefix)
+}
+
+// Sets `out` to SHAKE256(parts, 114) interpreted as a little-endian integer
+// and reduced modulo the group order.
+fn ed448_hash_to_scalar(parts: &[&[u8]], out: &mut [u8; ED448_ELEM_LEN]) {
+    let mut h = [0u8; 2 * ED448_ELEM_LEN];
+    digest::keccak::shake256(parts, &mut h);
+    unsafe {
+        GFp_ed448_scalar_reduce(out.as_mut_ptr(), h.as_ptr());
+    }
+}
+
+fn ed448_verify(public_key: untrusted::Input, msg: &[u8],
+                signature: untrusted::Input)
+                -> Result<(), error::Unspecified> {
+    let public_key = public_key.as_slice_less_safe();
+    if public_key.len() != ED448_ELEM_LEN ||
+       signature.len() != ED448_SIGNATURE_LEN {
+        return Err(error::Unspecified);
+    }
+    let (r, s) = signature.as_slice_less_safe().split_at(ED448_ELEM_LEN);
+    let mut k = [0u8; ED448_ELEM_LEN];
+    ed448_hash_to_scalar(&[ED448_DOM, r, public_key, msg], &mut k);
+    bssl::map_result(unsafe {
+        GFp_ed448_verify(r.as_ptr(), s.as_ptr(), k.as_ptr(),
+                         public_key.as_ptr())
+    })
+}
 
 
 extern  {
     fn GFp_ed25519_public_from_private(out: *mut u8/*[32]*/,
                                        in_: *const u8/*[32]*/);
 
-    fn GFp_ed25519_sign(out_sig: *mut u8/*[64]*/, message: *const u8,
-                        message_len: c::size_t, private_key: *const u8/*[64]*/);
+    fn GFp_ed25519_sign(out_sig: *mut u8/*[64]*/, dom: *const u8,
+                        dom_len: c::size_t, message: *const u8,
+                        message_len: c::size_t,
+                        expanded_private_key: *const u8/*[64]*/,
+                        public_key: *const u8/*[32]*/);
 
-    fn GFp_ed25519_verify(message: *const u8, message_len: c::size_t,
+    fn GFp_ed25519_verify(dom: *const u8, dom_len: c::size_t,
+                          message: *const u8, message_len: c::size_t,
                           signature: *const u8/*[64]*/,
                           public_key: *const u8/*[32]*/) -> c::int;
+
+    fn GFp_ed25519_verify_batch(n: c::size_t,
+                                public_keys: *const u8/*[n][32]*/,
+                                signatures: *const u8/*[n][64]*/,
+                                hrams: *const u8/*[n][64]*/,
+                                z: *const u8/*[n][16]*/) -> c::int;
+
+    fn GFp_ed448_scalar_mult_base(out: *mut u8/*[57]*/,
+                                  scalar: *const u8/*[57]*/);
+
+    fn GFp_ed448_scalar_reduce(out: *mut u8/*[57]*/, in_: *const u8/*[114]*/);
+
+    fn GFp_ed448_scalar_mul_add(out: *mut u8/*[57]*/, a: *const u8/*[57]*/,
+                                b: *const u8/*[57]*/, c: *const u8/*[57]*/);
+
+    fn GFp_ed448_verify(r: *const u8/*[57]*/, s: *const u8/*[57]*/,
+                        k: *const u8/*[57]*/, public_key: *const u8/*[57]*/)
+                        -> c::int;
 }
 
 
 #[cfg(test)]
 mod tests {
-    use {test, rand, signature};
-    use super::Ed25519KeyPair;
+    use {digest, test, rand, signature};
+    use std;
+    use super::{Ed25519KeyPair, Ed448KeyPair};
     use untrusted;
 
     /// Test vectors from BoringSSL.
@@ -195,6 +787,213 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_ed25519_verify_batch() {
+        let mut vectors = std::vec::Vec::new();
+        test::from_file("src/ec/ed25519_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let _ = test_case.consume_bytes("PRIV");
+            let public_key = test_case.consume_bytes("PUB");
+            let msg = test_case.consume_bytes("MESSAGE");
+            let sig = test_case.consume_bytes("SIG");
+            vectors.push((public_key, msg, sig));
+            Ok(())
+        });
+
+        let rng = rand::SystemRandom::new();
+
+        // Use batch sizes that aren't multiples of the internal chunk size.
+        for &n in [0, 1, 2, 17, 100, vectors.len()].iter() {
+            let items = vectors[..n].iter().map(|&(ref public_key, ref msg,
+                                                   ref sig)| {
+                (untrusted::Input::from(public_key),
+                 untrusted::Input::from(msg),
+                 untrusted::Input::from(sig))
+            }).collect::<std::vec::Vec<_>>();
+            assert!(signature::verify_batch(&items, &rng).is_ok());
+        }
+
+        // Corrupting any part of any item makes the whole batch fail.
+        for &i in [0, 16, 17, 99].iter() {
+            for part in 0..3 {
+                let mut bad = vectors[i].clone();
+                {
+                    let bytes = match part {
+                        0 => &mut bad.0,
+                        1 => &mut bad.1,
+                        _ => &mut bad.2,
+                    };
+                    if bytes.is_empty() {
+                        bytes.push(0);
+                    } else {
+                        bytes[0] ^= 1;
+                    }
+                }
+                let items = vectors[..100].iter().enumerate()
+                    .map(|(j, item)| {
+                        let &(ref public_key, ref msg, ref sig) =
+                            if j == i { &bad } else { item };
+                        (untrusted::Input::from(public_key),
+                         untrusted::Input::from(msg),
+                         untrusted::Input::from(sig))
+                    }).collect::<std::vec::Vec<_>>();
+                assert!(signature::verify_batch(&items, &rng).is_err());
+            }
+        }
+
+        // Malformed public keys and signatures are rejected.
+        let (ref public_key, ref msg, ref sig) = vectors[0];
+        let msg = untrusted::Input::from(msg);
+        assert!(signature::verify_batch(
+            &[(untrusted::Input::from(&public_key[..31]), msg,
+               untrusted::Input::from(sig))], &rng).is_err());
+        assert!(signature::verify_batch(
+            &[(untrusted::Input::from(public_key), msg,
+               untrusted::Input::from(&sig[..63]))], &rng).is_err());
+    }
+
+    /// Ed25519ph test vectors; see the file for their provenance.
+    #[test]
+    fn test_signature_ed25519ph() {
+        test::from_file("src/ec/ed25519ph_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let private_key = test_case.consume_bytes("PRIV");
+            assert_eq!(64, private_key.len());
+            let public_key = test_case.consume_bytes("PUB");
+            assert_eq!(32, public_key.len());
+            let msg = test_case.consume_bytes("MESSAGE");
+            let expected_sig = test_case.consume_bytes("SIG");
+
+            let key_pair = Ed25519KeyPair::from_bytes(&private_key[..32],
+                                                      &public_key).unwrap();
+            let actual_sig = key_pair.sign_ph(&msg);
+            assert_eq!(&expected_sig[..], actual_sig.as_slice());
+
+            let mut ctx = digest::Context::new(&digest::SHA512);
+            for chunk in msg.chunks(7) {
+                ctx.update(chunk);
+            }
+            let msg_digest = ctx.finish();
+            let actual_sig = key_pair.sign_ph_digest(&msg_digest).unwrap();
+            assert_eq!(&expected_sig[..], actual_sig.as_slice());
+
+            // Ed25519ph signatures aren't Ed25519 signatures.
+            assert!(&expected_sig[..] != key_pair.sign(&msg).as_slice());
+
+            let public_key = untrusted::Input::from(&public_key);
+            let expected_sig = untrusted::Input::from(&expected_sig);
+
+            assert!(signature::verify(&signature::ED25519PH, public_key,
+                                      untrusted::Input::from(&msg),
+                                      expected_sig).is_ok());
+            assert!(signature::primitive::verify_ed25519ph_digest(
+                        public_key, &msg_digest, expected_sig).is_ok());
+            assert!(signature::verify(&signature::ED25519, public_key,
+                                      untrusted::Input::from(&msg),
+                                      expected_sig).is_err());
+            assert!(signature::verify(&signature::ED25519PH, public_key,
+                                      untrusted::Input::from(&msg),
+                                      untrusted::Input::from(
+                                        key_pair.sign(&msg).as_slice()))
+                        .is_err());
+
+            // A digest using the wrong algorithm is rejected.
+            let sha256_digest = digest::digest(&digest::SHA256, &msg);
+            assert!(key_pair.sign_ph_digest(&sha256_digest).is_err());
+            assert!(signature::primitive::verify_ed25519ph_digest(
+                        public_key, &sha256_digest, expected_sig).is_err());
+
+            Ok(())
+        });
+    }
+
+    /// Ed25519ctx test vectors; see the file for their provenance.
+    #[test]
+    fn test_signature_ed25519ctx() {
+        test::from_file("src/ec/ed25519ctx_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let private_key = test_case.consume_bytes("PRIV");
+            assert_eq!(64, private_key.len());
+            let public_key = test_case.consume_bytes("PUB");
+            assert_eq!(32, public_key.len());
+            let msg = test_case.consume_bytes("MESSAGE");
+            let context = test_case.consume_bytes("CONTEXT");
+            let expected_sig = test_case.consume_bytes("SIG");
+
+            let key_pair = Ed25519KeyPair::from_bytes(&private_key[..32],
+                                                      &public_key).unwrap();
+            let actual_sig = key_pair.sign_ctx(&context, &msg).unwrap();
+            assert_eq!(&expected_sig[..], actual_sig.as_slice());
+
+            // Ed25519ctx signatures aren't Ed25519 signatures.
+            assert!(&expected_sig[..] != key_pair.sign(&msg).as_slice());
+
+            let public_key = untrusted::Input::from(&public_key);
+            let msg = untrusted::Input::from(&msg);
+            let expected_sig = untrusted::Input::from(&expected_sig);
+
+            assert!(signature::primitive::verify_ed25519ctx(
+                        public_key, untrusted::Input::from(&context), msg,
+                        expected_sig).is_ok());
+            assert!(signature::verify(&signature::ED25519, public_key, msg,
+                                      expected_sig).is_err());
+
+            // The context matters, including its length.
+            let mut longer_context = context.clone();
+            longer_context.push(0);
+            for other_context in &[&context[1..], &longer_context[..]] {
+                assert!(signature::primitive::verify_ed25519ctx(
+                            public_key, untrusted::Input::from(other_context),
+                            msg, expected_sig).is_err());
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_ed25519ctx_context_len() {
+        let rng = rand::SystemRandom::new();
+        let key_pair = Ed25519KeyPair::generate(&rng).unwrap();
+        let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+        let msg = b"hello, world";
+        let context = [0x42u8; 256];
+        for &(len, ok) in &[(0, false), (1, true), (255, true), (256, false)] {
+            let context = &context[..len];
+            let sig = key_pair.sign_ctx(context, msg);
+            assert_eq!(sig.is_ok(), ok);
+
+            // A valid signature from a shorter context doesn't verify either.
+            let sig = key_pair.sign_ctx(&[0x42], msg).unwrap();
+            assert_eq!(
+                signature::primitive::verify_ed25519ctx(
+                    public_key, untrusted::Input::from(context),
+                    untrusted::Input::from(msg),
+                    untrusted::Input::from(sig.as_slice())).is_ok(),
+                len == 1);
+        }
+    }
+
+    #[test]
+    fn test_ed25519_fingerprint() {
+        // The key from the first test vector in ed25519_tests.txt (RFC 8032
+        // Section 7.1, TEST 1). The expected value was calculated with
+        // Python's `cryptography` package as the SHA-256 digest of
+        // `public_bytes(Encoding.DER, PublicFormat.SubjectPublicKeyInfo)`.
+        let private_key = test::from_hex(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let public_key = test::from_hex(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap();
+        let key_pair =
+            Ed25519KeyPair::from_bytes(&private_key, &public_key).unwrap();
+        let fingerprint = key_pair.fingerprint(&digest::SHA256);
+        assert_eq!(fingerprint.as_ref(), &test::from_hex(
+            "06e3fd8fda29bb60ab59557de61edb0aecdb231134be30e75b455f8e1b792fa9")
+            .unwrap()[..]);
+    }
+
     #[test]
     fn test_ed25519_from_bytes_misuse() {
         let rng = rand::SystemRandom::new();
@@ -215,4 +1014,216 @@ mod tests {
         assert!(Ed25519KeyPair::from_bytes(&bytes.public_key,
                                            &bytes.private_key).is_err());
     }
+
+    #[test]
+    fn test_ed25519_from_seed() {
+        test::from_file("src/ec/ed25519_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let private_key = test_case.consume_bytes("PRIV");
+            let public_key = test_case.consume_bytes("PUB");
+            let _ = test_case.consume_bytes("MESSAGE");
+            let _ = test_case.consume_bytes("SIG");
+            let seed = &private_key[..32];
+
+            let key_pair = Ed25519KeyPair::from_seed_unchecked(seed).unwrap();
+            assert_eq!(key_pair.public_key_bytes(), &public_key[..]);
+
+            assert!(Ed25519KeyPair::from_seed_and_public_key(seed, &public_key)
+                        .is_ok());
+
+            Ok(())
+        });
+
+        let rng = rand::SystemRandom::new();
+        let (_, bytes) = Ed25519KeyPair::generate_serializable(&rng).unwrap();
+
+        // Truncated seed.
+        assert!(Ed25519KeyPair::from_seed_unchecked(&bytes.private_key[..31])
+                    .is_err());
+        assert!(Ed25519KeyPair::from_seed_and_public_key(
+                    &bytes.private_key[..31], &bytes.public_key).is_err());
+
+        // Truncated public key.
+        assert!(Ed25519KeyPair::from_seed_and_public_key(
+                    &bytes.private_key, &bytes.public_key[..31]).is_err());
+
+        // The 64-byte concatenated form isn't accepted as a seed.
+        let mut concatenated = [0u8; 64];
+        concatenated[..32].copy_from_slice(&bytes.private_key);
+        concatenated[32..].copy_from_slice(&bytes.public_key);
+        assert!(Ed25519KeyPair::from_seed_unchecked(&concatenated).is_err());
+
+        // Swapped public key and seed.
+        assert!(Ed25519KeyPair::from_seed_and_public_key(
+                    &bytes.public_key, &bytes.private_key).is_err());
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_ed25519_generate_pkcs8() {
+        // The private key from the first test vector in ed25519_tests.txt
+        // (RFC 8032 Section 7.1, TEST 1). The expected document is the
+        // `OneAsymmetricKey` encoding given in RFC 8410 Section 10.3, with
+        // the `attributes` omitted.
+        let seed = test::from_hex(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let rng = test::rand::FixedSliceRandom { bytes: &seed };
+        let document = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let expected = test::from_hex(
+            "3051020101300506032b657004220420\
+             9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60\
+             812100\
+             d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap();
+        assert_eq!(document.as_ref(), &expected[..]);
+
+        let key_pair =
+            Ed25519KeyPair::from_pkcs8(untrusted::Input::from(&expected))
+                .unwrap();
+        assert_eq!(key_pair.public_key_bytes(), &expected[51..]);
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_ed25519_from_pkcs8() {
+        let rng = rand::SystemRandom::new();
+        let document = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair =
+            Ed25519KeyPair::from_pkcs8(untrusted::Input::from(document.as_ref()))
+                .unwrap();
+        assert_eq!(key_pair.public_key_bytes(), &document.as_ref()[51..]);
+
+        // A PKCS#8 v1 document, which doesn't contain the public key.
+        const V1: &'static [u8] =
+            include_bytes!("ed25519_test_private_key_v1.pk8");
+        assert!(Ed25519KeyPair::from_pkcs8(untrusted::Input::from(V1))
+                    .is_err());
+
+        // A document with a public key that doesn't match the private key.
+        let mut bad = document.as_ref().to_vec();
+        bad[60] ^= 1;
+        assert!(Ed25519KeyPair::from_pkcs8(untrusted::Input::from(&bad))
+                    .is_err());
+
+        // Truncated document.
+        let truncated = &document.as_ref()[..(document.as_ref().len() - 1)];
+        assert!(Ed25519KeyPair::from_pkcs8(untrusted::Input::from(truncated))
+                    .is_err());
+    }
+
+    /// Ed448 test vectors; see the file for their provenance.
+    #[test]
+    fn test_signature_ed448() {
+        test::from_file("src/ec/ed448_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let seed = test_case.consume_bytes("SEED");
+            let public_key = test_case.consume_bytes("PUB");
+            let msg = test_case.consume_bytes("MESSAGE");
+            let expected_sig = test_case.consume_bytes("SIG");
+
+            let key_pair = Ed448KeyPair::from_seed_unchecked(&seed).unwrap();
+            assert_eq!(key_pair.public_key_bytes(), &public_key[..]);
+            let key_pair =
+                Ed448KeyPair::from_seed_and_public_key(&seed, &public_key)
+                    .unwrap();
+            let actual_sig = key_pair.sign(&msg);
+            assert_eq!(&expected_sig[..], actual_sig.as_slice());
+
+            let public_key = untrusted::Input::from(&public_key);
+            let msg = untrusted::Input::from(&msg);
+            assert!(signature::verify(&signature::ED448, public_key, msg,
+                                      untrusted::Input::from(&expected_sig))
+                        .is_ok());
+
+            // Corrupt each half of the signature.
+            for &i in [0, 57].iter() {
+                let mut bad_sig = expected_sig.clone();
+                bad_sig[i] ^= 1;
+                assert!(signature::verify(&signature::ED448, public_key, msg,
+                                          untrusted::Input::from(&bad_sig))
+                            .is_err());
+            }
+
+            // Truncated signature.
+            assert!(signature::verify(
+                        &signature::ED448, public_key, msg,
+                        untrusted::Input::from(&expected_sig[..113]))
+                        .is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_ed448_verify_rejects_non_canonical() {
+        let rng = rand::SystemRandom::new();
+        let key_pair = Ed448KeyPair::generate(&rng).unwrap();
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let sig = key_pair.sign(MESSAGE);
+        let msg = untrusted::Input::from(MESSAGE);
+        let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+        assert!(signature::verify(&signature::ED448, public_key, msg,
+                                  untrusted::Input::from(sig.as_slice()))
+                    .is_ok());
+
+        // S + L, which is congruent to S but isn't reduced.
+        const L: [u8; 57] = [
+            0xf3, 0x44, 0x58, 0xab, 0x92, 0xc2, 0x78, 0x23, 0x55, 0x8f, 0xc5,
+            0x8d, 0x72, 0xc2, 0x6c, 0x21, 0x90, 0x36, 0xd6, 0xae, 0x49, 0xdb,
+            0x4e, 0xc4, 0xe9, 0x23, 0xca, 0x7c, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x3f, 0x00,
+        ];
+        let mut bad_sig = [0u8; 114];
+        bad_sig.copy_from_slice(sig.as_slice());
+        let mut carry = 0u16;
+        for i in 0..57 {
+            let sum = (bad_sig[57 + i] as u16) + (L[i] as u16) + carry;
+            bad_sig[57 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert!(signature::verify(&signature::ED448, public_key, msg,
+                                  untrusted::Input::from(&bad_sig[..]))
+                    .is_err());
+
+        // A public key with a y coordinate that isn't reduced: p + 1 =
+        // 2**448 - 2**224 is congruent to y = 1, the neutral element's y.
+        let mut bad_public_key = [0u8; 57];
+        for b in bad_public_key[28..56].iter_mut() {
+            *b = 0xff;
+        }
+        assert!(signature::verify(&signature::ED448,
+                                  untrusted::Input::from(&bad_public_key[..]),
+                                  msg, untrusted::Input::from(sig.as_slice()))
+                    .is_err());
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn test_ed448_pkcs8() {
+        let rng = rand::SystemRandom::new();
+        let document = Ed448KeyPair::generate_pkcs8(&rng).unwrap();
+        // The outer `SEQUENCE` needs a two-byte length.
+        assert_eq!(document.as_ref().len(), 3 + 3 + 7 + (2 + 2 + 57) +
+                                            (2 + 1 + 57));
+        let key_pair =
+            Ed448KeyPair::from_pkcs8(untrusted::Input::from(document.as_ref()))
+                .unwrap();
+        assert_eq!(key_pair.public_key_bytes(),
+                   &document.as_ref()[(document.as_ref().len() - 57)..]);
+
+        // A document with a public key that doesn't match the private key.
+        let mut bad = document.as_ref().to_vec();
+        let last = bad.len() - 1;
+        bad[last] ^= 1;
+        assert!(Ed448KeyPair::from_pkcs8(untrusted::Input::from(&bad))
+                    .is_err());
+
+        // An Ed25519 document isn't an Ed448 document.
+        let ed25519 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        assert!(Ed448KeyPair::from_pkcs8(
+                    untrusted::Input::from(ed25519.as_ref())).is_err());
+    }
 }
@@ -12,11 +12,16 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-//! ECDSA Signatures using the P-256 and P-384 curves.
+//! ECDSA Signatures using the P-256, P-384, and secp256k1 curves.
 
-use {der, digest, error, private, signature};
+use {der, digest, ec, error, init, private, rand, signature};
+#[cfg(feature = "use_heap")]
+use pkcs8;
+#[cfg(feature = "use_heap")]
+use std;
 use super::verify_jacobian_point_is_on_the_curve;
 use super::ops::*;
+use super::private_key::*;
 use super::public_key::*;
 use untrusted;
 
@@ -24,6 +29,9 @@ use untrusted;
 pub struct ECDSAParameters {
     ops: &'static PublicScalarOps,
     digest_alg: &'static digest::Algorithm,
+    split_rs: fn(ops: &'static PublicScalarOps,
+                 input: &mut untrusted::Reader)
+                 -> Result<(Scalar, Scalar), error::Unspecified>,
 }
 
 impl signature::VerificationAlgorithm for ECDSAParameters {
@@ -47,18 +55,26 @@ impl signature::VerificationAlgorithm for ECDSAParameters {
         // Prerequisites #1 and #4 are outside the scope of what this function
         // can do. Prerequisite #2 is handled implicitly as the domain
         // parameters are hard-coded into the source. Prerequisite #3 is
-        // handled by `parse_uncompressed_point`.
+        // handled by `parse_point`.
         let peer_pub_key =
-            try!(parse_uncompressed_point(self.ops.public_key_ops, public_key));
+            try!(parse_point(self.ops.public_key_ops, public_key));
+        self.verify_parsed(&peer_pub_key, None, msg, signature)
+    }
+}
+
+impl private::Private for ECDSAParameters {}
 
+impl ECDSAParameters {
+    // Verifies an ECDSA signature using the already-parsed and validated
+    // public key `peer_pub_key`, and `tables` if they were precomputed for it.
+    fn verify_parsed(&self, peer_pub_key: &(Elem, Elem),
+                     tables: Option<&VerificationTables>,
+                     msg: untrusted::Input, signature: untrusted::Input)
+                     -> Result<(), error::Unspecified> {
         // NSA Guide Step 1: "If r and s are not both integers in the interval
         // [1, n − 1], output INVALID."
         let (r, s) = try!(signature.read_all(error::Unspecified, |input| {
-            der::nested(input, der::Tag::Sequence, error::Unspecified, |input| {
-                let r = try!(self.ops.scalar_parse(input));
-                let s = try!(self.ops.scalar_parse(input));
-                Ok((r, s))
-            })
+            (self.split_rs)(self.ops, input)
         }));
 
         // NSA Guide Step 2: "Use the selected hash function to compute H =
@@ -79,8 +95,32 @@ impl signature::VerificationAlgorithm for ECDSAParameters {
         // NSA Guide Step 6: "Compute the elliptic curve point
         // R = (xR, yR) = u1*G + u2*Q, using EC scalar multiplication and EC
         // addition. If R is equal to the point at infinity, output INVALID."
-        let product =
-            twin_mul(self.ops.private_key_ops, &u1, &u2, &peer_pub_key);
+        //
+        // With precomputed tables of multiples of both G and Q, this is done
+        // with Shamir's trick, sharing the doublings between the two
+        // multiplications. When only Q has a table, because the curve's
+        // `point_mul_base` already uses a static table for G (P-256), u1*G
+        // and u2*Q are computed separately, each with its own table, and
+        // then added.
+        let product = match tables {
+            Some(&VerificationTables {
+                public_key: ref q_table,
+                base_point: Some(ref g_table),
+            }) => {
+                self.ops.private_key_ops.twin_mul_using_tables(g_table, &u1,
+                                                                q_table, &u2)
+            },
+            Some(&VerificationTables {
+                public_key: ref q_table,
+                base_point: None,
+            }) => {
+                let ops = self.ops.private_key_ops;
+                let scaled_g = ops.point_mul_base(&u1);
+                let scaled_q = ops.point_mul_using_table(q_table, &u2);
+                ops.common.point_sum(&scaled_g, &scaled_q)
+            },
+            None => twin_mul(self.ops.private_key_ops, &u1, &u2, peer_pub_key),
+        };
 
         // Verify that the point we computed is on the curve; see
         // `verify_affine_point_is_on_the_curve_scaled` for details on why. It
@@ -123,7 +163,837 @@ impl signature::VerificationAlgorithm for ECDSAParameters {
     }
 }
 
-impl private::Private for ECDSAParameters {}
+/// An ECDSA public key that has been parsed and validated once, for
+/// verifying many signatures.
+///
+/// `signature::verify()` parses and validates the public key for every
+/// signature, which is a significant part of the cost of verification,
+/// especially for compressed public keys. When many signatures from the same
+/// key are verified, e.g. when auditing a Certificate Transparency log, it is
+/// faster to construct an `ECDSAVerificationKey` once and use it for each
+/// signature. The results are the same as `signature::verify()`'s.
+pub struct ECDSAVerificationKey {
+    alg: &'static ECDSAParameters,
+    public_key: (Elem, Elem),
+    #[cfg(feature = "use_heap")]
+    tables: Option<std::boxed::Box<VerificationTables>>,
+}
+
+// The tables of multiples that `ECDSAVerificationKey::verify()` uses once
+// `ECDSAVerificationKey::precompute_verification_tables()` has been called.
+#[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+struct VerificationTables {
+    public_key: PointTable,
+
+    // `None` when the curve's `point_mul_base` already uses a static table.
+    base_point: Option<PointTable>,
+}
+
+impl ECDSAVerificationKey {
+    /// Parses and validates the public key `public_key` for use with `alg`.
+    ///
+    /// `public_key` is accepted if and only if `ecdsa_validate_public_key`
+    /// would accept it.
+    pub fn new(alg: &'static ECDSAParameters, public_key: untrusted::Input)
+               -> Result<ECDSAVerificationKey, error::Unspecified> {
+        init::init_once();
+        let public_key = try!(parse_point(alg.ops.public_key_ops, public_key));
+        Ok(ECDSAVerificationKey {
+            alg: alg,
+            public_key: public_key,
+            #[cfg(feature = "use_heap")]
+            tables: None,
+        })
+    }
+
+    /// The verification algorithm the key is to be used with.
+    #[inline]
+    pub fn algorithm(&self) -> &'static ECDSAParameters { self.alg }
+
+    /// Precomputes tables of multiples of the public key, and of the curve's
+    /// generator if the implementation doesn't already have a static table
+    /// for it, that `verify()` will use.
+    ///
+    /// For the `ECDSA_P256_*` algorithms, only the public key gets a table;
+    /// `verify()` computes `u1*G` with P-256's static table and `u2*Q` with
+    /// the key's table, and then adds them. For the other algorithms, both
+    /// the public key and the generator get tables, and `verify()` computes
+    /// `u1*G + u2*Q` using Shamir's trick over both tables, sharing the
+    /// doublings between the two multiplications. The cost is about 4.5KB of
+    /// heap memory per key for the `ECDSA_P256_*` algorithms (about 9KB for
+    /// the others), and the one-time cost of computing the tables, which is
+    /// about that of verifying a signature or two. It is worthwhile when the
+    /// key is used to verify more than a few signatures.
+    #[cfg(feature = "use_heap")]
+    pub fn precompute_verification_tables(&mut self) {
+        if self.tables.is_none() {
+            let ops = self.alg.ops.private_key_ops;
+            self.tables = Some(std::boxed::Box::new(VerificationTables {
+                public_key: ops.point_table(&self.public_key),
+                base_point: ops.base_point_table(),
+            }));
+        }
+    }
+
+    /// Verify the signature `signature` of message `msg` with this public
+    /// key, as `signature::verify()` does.
+    pub fn verify(&self, msg: &[u8], signature: &[u8])
+                  -> Result<(), error::Unspecified> {
+        self.alg.verify_parsed(&self.public_key, self.tables(),
+                               untrusted::Input::from(msg),
+                               untrusted::Input::from(signature))
+    }
+
+    #[cfg(feature = "use_heap")]
+    fn tables(&self) -> Option<&VerificationTables> {
+        self.tables.as_ref().map(|tables| &**tables)
+    }
+
+    #[cfg(not(feature = "use_heap"))]
+    fn tables(&self) -> Option<&VerificationTables> { None }
+}
+
+impl signature::Verifier for ECDSAVerificationKey {
+    fn verify(&self, msg: &[u8], signature: &[u8])
+              -> Result<(), error::Unspecified> {
+        ECDSAVerificationKey::verify(self, msg, signature)
+    }
+}
+
+/// Converts the ASN.1 DER-encoded ECDSA signature `sig` to the fixed-length
+/// form, as used by the `ECDSA_*_FIXED` algorithms.
+///
+/// Only the curve of `alg` matters; its digest algorithm is ignored. Fails
+/// unless `sig` is a valid DER encoding of an `Ecdsa-Sig-Value` with `r` and
+/// `s` in the range [1, n), so the result is always well-formed.
+pub fn ecdsa_asn1_to_fixed(alg: &'static ECDSAParameters,
+                           sig: untrusted::Input)
+                           -> Result<signature::Signature, error::Unspecified> {
+    let (r, s) = try!(sig.read_all(error::Unspecified, |input| {
+        split_rs_asn1(alg.ops, input)
+    }));
+    Ok(format_rs_fixed(alg.ops, &r, &s))
+}
+
+/// Converts the fixed-length ECDSA signature `sig`, as used by the
+/// `ECDSA_*_FIXED` algorithms, to the ASN.1 DER-encoded form.
+///
+/// Only the curve of `alg` matters; its digest algorithm is ignored. Fails
+/// unless `sig` is exactly twice the length of the curve's order, with `r`
+/// and `s` in the range [1, n), so the result is always well-formed.
+pub fn ecdsa_fixed_to_asn1(alg: &'static ECDSAParameters,
+                           sig: untrusted::Input)
+                           -> Result<signature::Signature, error::Unspecified> {
+    let (r, s) = try!(sig.read_all(error::Unspecified, |input| {
+        split_rs_fixed(alg.ops, input)
+    }));
+    Ok(format_rs_asn1(alg.ops, &r, &s))
+}
+
+/// Validates the public key `public_key`, encoded as it would be for
+/// verification with `alg`, without verifying any signature.
+///
+/// This is useful for rejecting bad keys when they are registered rather than
+/// when they are first used. Only the curve of `alg` matters; its digest
+/// algorithm and signature format are ignored. `public_key` is accepted if
+/// and only if `signature::verify` with `alg` would accept it: it must be the
+/// uncompressed or compressed encoding of a point on the curve, which can't
+/// be the point at infinity. All the supported curves have prime order
+/// (cofactor 1), so every such point is in the correct subgroup; thus this is
+/// the ECC Full Public-Key Validation Routine of [NIST SP 800-56A, revision 2]
+/// Section 5.6.2.3.2.
+///
+/// [NIST SP 800-56A, revision 2]:
+///     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-56Ar2.pdf
+pub fn ecdsa_validate_public_key(alg: &'static ECDSAParameters,
+                                 public_key: untrusted::Input)
+                                 -> Result<(), error::Unspecified> {
+    let _ = try!(parse_point(alg.ops.public_key_ops, public_key));
+    Ok(())
+}
+
+/// An encoded ECDSA public key returned by `ecdsa_public_key_from_raw()` or
+/// `ecdsa_public_key_to_raw()`.
+pub struct ECDSAPublicKeyBytes {
+    bytes: [u8; ec::PUBLIC_KEY_MAX_LEN],
+    len: usize,
+}
+
+impl<'a> ECDSAPublicKeyBytes {
+    /// Returns a reference to the encoded public key.
+    pub fn as_slice(&'a self) -> &'a [u8] { &self.bytes[..self.len] }
+}
+
+/// Converts the raw public key `raw`, the big-endian-encoded x coordinate
+/// followed by the big-endian-encoded y coordinate, each zero-padded to the
+/// length of a field element, to the uncompressed form accepted by
+/// `signature::verify()` with `alg`.
+///
+/// This raw form is used by, e.g., WebAuthn/FIDO and many IoT protocols; for
+/// P-256 it is 64 bytes, and the uncompressed form is 65 bytes. Only the
+/// curve of `alg` matters. `raw` is validated as in
+/// `ecdsa_validate_public_key()`.
+pub fn ecdsa_public_key_from_raw(alg: &'static ECDSAParameters,
+                                 raw: untrusted::Input)
+                                 -> Result<ECDSAPublicKeyBytes,
+                                           error::Unspecified> {
+    let elem_len = alg.ops.public_key_ops.common.num_limbs * LIMB_BYTES;
+    if raw.len() != 2 * elem_len {
+        return Err(error::Unspecified);
+    }
+    let mut result = ECDSAPublicKeyBytes {
+        bytes: [0; ec::PUBLIC_KEY_MAX_LEN],
+        len: 1 + (2 * elem_len),
+    };
+    result.bytes[0] = 4; // Uncompressed encoding.
+    result.bytes[1..result.len].copy_from_slice(raw.as_slice_less_safe());
+    try!(ecdsa_validate_public_key(alg, untrusted::Input::from(
+                                            result.as_slice())));
+    Ok(result)
+}
+
+/// Converts the public key `public_key`, in the uncompressed or compressed
+/// form accepted by `signature::verify()` with `alg`, to the raw form
+/// described in `ecdsa_public_key_from_raw()`.
+///
+/// Only the curve of `alg` matters. `public_key` is validated as in
+/// `ecdsa_validate_public_key()`.
+pub fn ecdsa_public_key_to_raw(alg: &'static ECDSAParameters,
+                               public_key: untrusted::Input)
+                               -> Result<ECDSAPublicKeyBytes,
+                                         error::Unspecified> {
+    let cops = alg.ops.public_key_ops.common;
+    let num_limbs = cops.num_limbs;
+    let elem_len = num_limbs * LIMB_BYTES;
+    let (x, y) = try!(parse_point(alg.ops.public_key_ops, public_key));
+    let mut result = ECDSAPublicKeyBytes {
+        bytes: [0; ec::PUBLIC_KEY_MAX_LEN],
+        len: 2 * elem_len,
+    };
+    {
+        let (x_out, y_out) =
+            (&mut result.bytes[..(2 * elem_len)]).split_at_mut(elem_len);
+        let x = cops.elem_decoded(&ElemUnreduced::from(&x));
+        big_endian_from_limbs(x_out, &x.limbs[..num_limbs]);
+        let y = cops.elem_decoded(&ElemUnreduced::from(&y));
+        big_endian_from_limbs(y_out, &y.limbs[..num_limbs]);
+    }
+    Ok(result)
+}
+
+/// Recovers the public key that produced the signature `signature` of the
+/// message `msg` from the signature, its recovery ID `recovery_id`, and the
+/// message, like Ethereum's `ecrecover`.
+///
+/// `recovery_id` is the recovery ID returned by
+/// `ECDSAKeyPair::sign_recoverable()`, in the range [0, 3]. The public key is
+/// written to `public_key_out` in uncompressed form, as accepted by
+/// `signature::verify()` with `alg`; it must be exactly as long as that form,
+/// e.g. 65 bytes for secp256k1. Recovery succeeds only if `signature` is a
+/// well-formed signature for `alg`, and then `signature` is a valid signature
+/// of `msg` for the recovered public key.
+///
+/// The result is only as trustworthy as `recovery_id`: every signature is
+/// valid for a different public key for each recovery ID, so the recovered
+/// public key must be checked against an expected public key or the address
+/// derived from it.
+pub fn ecdsa_recover_public_key(alg: &'static ECDSAParameters,
+                                msg: untrusted::Input,
+                                signature: untrusted::Input, recovery_id: u8,
+                                public_key_out: &mut [u8])
+                                -> Result<(), error::Unspecified> {
+    init::init_once();
+    let ops = alg.ops;
+    let cops = ops.public_key_ops.common;
+    let num_limbs = cops.num_limbs;
+    let elem_len = num_limbs * LIMB_BYTES;
+    if recovery_id > 3 || public_key_out.len() != 1 + (2 * elem_len) {
+        return Err(error::Unspecified);
+    }
+
+    let (r, s) = try!(signature.read_all(error::Unspecified, |input| {
+        (alg.split_rs)(ops, input)
+    }));
+
+    // The x coordinate of R is r or r + n, and must be less than q.
+    let r_ = ops.scalar_as_elem_decoded(&r);
+    let x = if recovery_id & 2 == 0 {
+        r_
+    } else {
+        if !ops.elem_decoded_less_than(&r_, &ops.q_minus_n) {
+            return Err(error::Unspecified);
+        }
+        ops.elem_decoded_sum(&r_, &cops.n)
+    };
+
+    // Decompress R, which also verifies that it is on the curve.
+    let mut r_encoded = [0; 1 + ec::ELEM_MAX_BYTES];
+    r_encoded[0] = 2 | (recovery_id & 1);
+    big_endian_from_limbs(&mut r_encoded[1..(1 + elem_len)],
+                          &x.limbs[..num_limbs]);
+    let r_point = try!(parse_point(ops.public_key_ops, untrusted::Input::from(
+                                        &r_encoded[..(1 + elem_len)])));
+
+    // Q = r**-1 * (s*R - e*G) = (-e * r**-1)*G + (s * r**-1)*R.
+    let e = digest_scalar(ops, alg.digest_alg, msg);
+    let r_inv = ops.scalar_inv_to_mont(&r);
+    let u1 = ops.scalar_mul_mixed(&ops.scalar_negated(&e), &r_inv);
+    let u2 = ops.scalar_mul_mixed(&s, &r_inv);
+    let q = twin_mul(ops.private_key_ops, &u1, &u2, &r_point);
+
+    // Q is at infinity if s*R == e*G. Check that here, since
+    // `big_endian_affine_from_jacobian` assumes it can't be.
+    try!(cops.elem_verify_is_not_zero(&cops.point_z(&q)));
+
+    public_key_out[0] = 4; // Uncompressed encoding.
+    let (x_out, y_out) = (&mut public_key_out[1..]).split_at_mut(elem_len);
+    big_endian_affine_from_jacobian(ops.private_key_ops, Some(x_out),
+                                    Some(y_out), &q)
+}
+
+// Parses the `BIT STRING` containing the public key of an `ECPrivateKey`.
+fn parse_public_key_bits<'a>(input: &mut untrusted::Reader<'a>)
+                             -> Result<untrusted::Input<'a>,
+                                       error::Unspecified> {
+    let bits = try!(der::expect_tag_and_get_value(input, der::Tag::BitString));
+    bits.read_all(error::Unspecified, |input| {
+        // Only whole-byte public keys are supported.
+        let unused_bits = try!(input.read_byte());
+        if unused_bits != 0 {
+            return Err(error::Unspecified);
+        }
+        Ok(input.skip_to_end())
+    })
+}
+
+pub fn split_rs_asn1(ops: &'static PublicScalarOps,
+                     input: &mut untrusted::Reader)
+                     -> Result<(Scalar, Scalar), error::Unspecified> {
+    der::nested(input, der::Tag::Sequence, error::Unspecified, |input| {
+        let r = try!(ops.scalar_parse(input));
+        let s = try!(ops.scalar_parse(input));
+        Ok((r, s))
+    })
+}
+
+pub fn split_rs_fixed(ops: &'static PublicScalarOps,
+                      input: &mut untrusted::Reader)
+                      -> Result<(Scalar, Scalar), error::Unspecified> {
+    let r = try!(ops.scalar_parse_fixed(input));
+    let s = try!(ops.scalar_parse_fixed(input));
+    Ok((r, s))
+}
+
+
+/// An ECDSA signing algorithm.
+pub struct ECDSASigningAlgorithm {
+    verification_alg: &'static ECDSAParameters,
+    private_scalar_ops: &'static PrivateScalarOps,
+    format_rs: fn(ops: &PublicScalarOps, r: &Scalar, s: &Scalar)
+                  -> signature::Signature,
+    pkcs8_alg_id: &'static [u8],
+}
+
+impl ECDSASigningAlgorithm {
+    #[inline]
+    fn private_key_ops(&self) -> &'static PrivateKeyOps {
+        self.private_scalar_ops.scalar_ops.private_key_ops
+    }
+
+    #[inline]
+    fn public_key_len(&self) -> usize {
+        1 + (2 * self.private_key_ops().common.num_limbs * LIMB_BYTES)
+    }
+}
+
+/// An ECDSA key pair, for signing.
+pub struct ECDSAKeyPair {
+    alg: &'static ECDSASigningAlgorithm,
+    private_key: ec::PrivateKey,
+    public_key: [u8; ec::PUBLIC_KEY_MAX_LEN],
+    #[cfg(feature = "use_heap")]
+    base_point_table: Option<std::boxed::Box<PointTable>>,
+}
+
+impl<'a> ECDSAKeyPair {
+    /// Generates a new random key pair for the algorithm `alg`. There is no
+    /// way to extract the private key bytes to save them.
+    pub fn generate(alg: &'static ECDSASigningAlgorithm,
+                    rng: &rand::SecureRandom)
+                    -> Result<ECDSAKeyPair, error::Unspecified> {
+        init::init_once();
+        let private_key =
+            try!(generate_private_key(alg.private_key_ops(), rng));
+        ECDSAKeyPair::new(alg, private_key)
+    }
+
+    /// Generates a new key pair for the algorithm `alg` and returns the key
+    /// pair serialized as a PKCS#8 v1 document (see [RFC 5208]).
+    ///
+    /// The algorithm identifier is `id-ecPublicKey` with the curve's
+    /// `namedCurve` as its parameters, and the private key is an
+    /// `ECPrivateKey` (see [RFC 5915]) that includes the public key in
+    /// uncompressed form and omits the curve parameters. This is the form
+    /// that `openssl pkcs8 -topk8 -nocrypt` produces, and what TLS and JOSE
+    /// implementations usually expect.
+    ///
+    /// Use `from_pkcs8()` to load the key pair from the document.
+    ///
+    /// [RFC 5208]: https://tools.ietf.org/html/rfc5208
+    /// [RFC 5915]: https://tools.ietf.org/html/rfc5915
+    #[cfg(feature = "use_heap")]
+    pub fn generate_pkcs8(alg: &'static ECDSASigningAlgorithm,
+                          rng: &rand::SecureRandom)
+                          -> Result<pkcs8::Document, error::Unspecified> {
+        let key_pair = try!(ECDSAKeyPair::generate(alg, rng));
+        let private_key_len = alg.private_key_ops().common.num_limbs *
+                              LIMB_BYTES;
+        let private_key = &key_pair.private_key.bytes[..private_key_len];
+        let public_key = key_pair.public_key_bytes();
+
+        // The `publicKey` is a `BIT STRING` with no unused bits, explicitly
+        // tagged [1].
+        let public_key_len = der::encoded_len(1 + public_key.len());
+        let inner_len = 3 + der::encoded_len(private_key.len()) +
+                        der::encoded_len(public_key_len);
+
+        // The `ECPrivateKey` is built in a `Document` so that it is zeroized.
+        let mut ec_private_key =
+            pkcs8::Document::with_capacity(der::encoded_len(inner_len));
+        {
+            let output = ec_private_key.as_mut_vec();
+            der::write_tag_and_len(output, der::Tag::Sequence, inner_len);
+            output.extend_from_slice(&[der::Tag::Integer as u8, 1, 1]);
+            der::write_tlv(output, der::Tag::OctetString, private_key);
+            der::write_tag_and_len(output,
+                                   der::Tag::ContextSpecificConstructed1,
+                                   public_key_len);
+            der::write_tag_and_len(output, der::Tag::BitString,
+                                   1 + public_key.len());
+            output.push(0); // No unused bits.
+            output.extend_from_slice(public_key);
+        }
+        Ok(pkcs8::wrap_key(alg.pkcs8_alg_id, ec_private_key.as_ref()))
+    }
+
+    /// Constructs an ECDSA key pair for the algorithm `alg` by parsing an
+    /// unencrypted PKCS#8 v1 `PrivateKeyInfo` (see [RFC 5208]) containing an
+    /// `id-ecPublicKey` key, such as one produced by `generate_pkcs8()` or by
+    /// `openssl pkcs8 -topk8 -nocrypt`.
+    ///
+    /// The `namedCurve` in the algorithm identifier must be the curve of
+    /// `alg`. The `ECPrivateKey` is parsed as in `from_sec1_der()`, except
+    /// its curve parameters may be omitted since the algorithm identifier
+    /// already identifies the curve.
+    ///
+    /// [RFC 5208]: https://tools.ietf.org/html/rfc5208
+    #[cfg(feature = "use_heap")]
+    pub fn from_pkcs8(alg: &'static ECDSASigningAlgorithm,
+                      input: untrusted::Input)
+                      -> Result<ECDSAKeyPair, error::Unspecified> {
+        let ec_private_key = try!(pkcs8::unwrap_key(alg.pkcs8_alg_id, input));
+        ECDSAKeyPair::from_ec_private_key(alg, ec_private_key, false)
+    }
+
+    /// Constructs an ECDSA key pair for the algorithm `alg` from the bytes
+    /// of the private key and the public key.
+    ///
+    /// The private key must be encoded as a fixed-length big-endian integer
+    /// (the form used in the `privateKey` field of an `ECPrivateKey` in
+    /// [RFC 5915]) in the range [1, n). The public key must be encoded in
+    /// uncompressed form, like the public keys accepted by the
+    /// `ECDSA_*_ASN1` verification algorithms.
+    ///
+    /// The private and public keys will be verified to be consistent. This
+    /// helps protect, for example, against the accidental swapping of the
+    /// public and private components of the key pair. This also detects
+    /// corruption that might have occurred during storage of the key pair.
+    ///
+    /// [RFC 5915]: https://tools.ietf.org/html/rfc5915#section-3
+    pub fn from_bytes(alg: &'static ECDSASigningAlgorithm,
+                      private_key: untrusted::Input,
+                      public_key: untrusted::Input)
+                      -> Result<ECDSAKeyPair, error::Unspecified> {
+        init::init_once();
+        let private_key =
+            try!(private_key_from_bytes(alg.private_key_ops(), private_key));
+        let key_pair = try!(ECDSAKeyPair::new(alg, private_key));
+        if public_key.as_slice_less_safe() != key_pair.public_key_bytes() {
+            return Err(error::Unspecified);
+        }
+        Ok(key_pair)
+    }
+
+    /// Constructs an ECDSA key pair for the algorithm `alg` by parsing a
+    /// DER-encoded SEC1 `ECPrivateKey` (see [RFC 5915]), such as one produced
+    /// by `openssl ecparam -genkey -noout -outform DER`.
+    ///
+    /// The curve parameters must be present and must be the `namedCurve` of
+    /// the curve of `alg`; explicit curve parameters aren't supported. The
+    /// private key must be in the range [1, n) and must be encoded with
+    /// exactly the length of `n`. The public key is optional, but if it is
+    /// present then it must be encoded in uncompressed form and it must be
+    /// consistent with the private key, as in `from_bytes()`.
+    ///
+    /// [RFC 5915]: https://tools.ietf.org/html/rfc5915
+    pub fn from_sec1_der(alg: &'static ECDSASigningAlgorithm,
+                         input: untrusted::Input)
+                         -> Result<ECDSAKeyPair, error::Unspecified> {
+        ECDSAKeyPair::from_ec_private_key(alg, input, true)
+    }
+
+    fn from_ec_private_key(alg: &'static ECDSASigningAlgorithm,
+                           input: untrusted::Input,
+                           parameters_required: bool)
+                           -> Result<ECDSAKeyPair, error::Unspecified> {
+        init::init_once();
+
+        // The `ECParameters` are the `namedCurve` at the end of the algorithm
+        // identifier, after its `SEQUENCE` header and the `id-ecPublicKey`
+        // OID.
+        let curve_oid = untrusted::Input::from(&alg.pkcs8_alg_id[(2 + 9)..]);
+
+        let (private_key, public_key) =
+            try!(input.read_all(error::Unspecified, |input| {
+                der::nested(input, der::Tag::Sequence, error::Unspecified,
+                            |input| {
+                    let version = try!(der::small_nonnegative_integer(input));
+                    if version != 1 {
+                        return Err(error::Unspecified);
+                    }
+                    let private_key =
+                        try!(der::expect_tag_and_get_value(
+                                input, der::Tag::OctetString));
+                    if input.peek(der::Tag::ContextSpecificConstructed0 as u8) {
+                        let parameters =
+                            try!(der::expect_tag_and_get_value(
+                                    input,
+                                    der::Tag::ContextSpecificConstructed0));
+                        if parameters != curve_oid {
+                            return Err(error::Unspecified);
+                        }
+                    } else if parameters_required {
+                        return Err(error::Unspecified);
+                    }
+                    let public_key =
+                        if input.peek(
+                                der::Tag::ContextSpecificConstructed1 as u8) {
+                            Some(try!(der::nested(
+                                input, der::Tag::ContextSpecificConstructed1,
+                                error::Unspecified, parse_public_key_bits)))
+                        } else {
+                            None
+                        };
+                    Ok((private_key, public_key))
+                })
+            }));
+
+        let private_key =
+            try!(private_key_from_bytes(alg.private_key_ops(), private_key));
+        let key_pair = try!(ECDSAKeyPair::new(alg, private_key));
+        if let Some(public_key) = public_key {
+            if public_key.as_slice_less_safe() != key_pair.public_key_bytes() {
+                return Err(error::Unspecified);
+            }
+        }
+        Ok(key_pair)
+    }
+
+    fn new(alg: &'static ECDSASigningAlgorithm, private_key: ec::PrivateKey)
+           -> Result<ECDSAKeyPair, error::Unspecified> {
+        let mut public_key = [0; ec::PUBLIC_KEY_MAX_LEN];
+        try!(public_from_private(alg.private_key_ops(),
+                                 &mut public_key[..alg.public_key_len()],
+                                 &private_key));
+        Ok(ECDSAKeyPair {
+            alg: alg,
+            private_key: private_key,
+            public_key: public_key,
+            #[cfg(feature = "use_heap")]
+            base_point_table: None,
+        })
+    }
+
+    /// Precomputes a table of multiples of the curve's generator that
+    /// `sign()` will use to compute `k*G` for each nonce `k`.
+    ///
+    /// This makes signing noticeably faster for P-384 and secp256k1, at the
+    /// cost of about 4.5KB of heap memory per key pair, so it is worthwhile
+    /// for long-lived key pairs that sign many messages. The P-256
+    /// implementation already uses a large static table, so this does
+    /// nothing for the `ECDSA_P256_*` algorithms. Signatures are the same
+    /// either way.
+    #[cfg(feature = "use_heap")]
+    pub fn precompute_signing_tables(&mut self) {
+        if self.base_point_table.is_none() {
+            self.base_point_table = self.alg.private_key_ops()
+                                        .base_point_table()
+                                        .map(std::boxed::Box::new);
+        }
+    }
+
+    #[cfg(feature = "use_heap")]
+    fn point_mul_base(&self, k: &Scalar) -> Point {
+        let ops = self.alg.private_key_ops();
+        match self.base_point_table {
+            Some(ref table) => ops.point_mul_using_table(table, k),
+            None => ops.point_mul_base(k),
+        }
+    }
+
+    #[cfg(not(feature = "use_heap"))]
+    fn point_mul_base(&self, k: &Scalar) -> Point {
+        self.alg.private_key_ops().point_mul_base(k)
+    }
+
+    /// Returns a reference to the public key, encoded in uncompressed form.
+    pub fn public_key_bytes(&'a self) -> &'a [u8] {
+        &self.public_key[..self.alg.public_key_len()]
+    }
+
+    /// Returns a reference to the public key in the raw form described in
+    /// `ecdsa_public_key_from_raw()`; i.e. `public_key_bytes()` without the
+    /// leading byte that identifies the uncompressed form.
+    pub fn public_key_raw(&'a self) -> &'a [u8] {
+        &self.public_key[1..self.alg.public_key_len()]
+    }
+
+    /// Returns the signature of the message `msg`, encoded in the format of
+    /// the key pair's signing algorithm.
+    ///
+    /// A new nonce is chosen for each signature, uniformly at random from
+    /// the range [1, n), by rejection sampling bytes from `rng`. Note that
+    /// ECDSA signing is catastrophically insecure if `rng` is not a secure
+    /// random number generator. See `sign_hedged()` for a variant that
+    /// doesn't depend entirely on `rng`.
+    pub fn sign(&self, msg: &[u8], rng: &rand::SecureRandom)
+                -> Result<signature::Signature, error::Unspecified> {
+        // NSA Guide Steps 5 and 6: Compute the digest of the message and
+        // convert it to an integer. This doesn't depend on the nonce, so it
+        // is done before the nonce is generated.
+        let e = digest_scalar(self.alg.private_scalar_ops.scalar_ops,
+                              self.alg.verification_alg.digest_alg,
+                              untrusted::Input::from(msg));
+        self.sign_digest(&e, rng).map(|(signature, _)| signature)
+    }
+
+    /// Like `sign()`, but also returns the recovery ID of the signature, for
+    /// use with `ecdsa_recover_public_key()`.
+    ///
+    /// The recovery ID is in the range [0, 3]. Its low bit is the parity of
+    /// the y coordinate of the point R = k*G, and its high bit is set if the
+    /// x coordinate of R is at least n, so that r = x - n. The high bit is
+    /// only ever set for secp256k1, and then only with negligible
+    /// probability. This is the same recovery ID as Bitcoin's and Ethereum's,
+    /// e.g. Ethereum's `v` is the recovery ID plus 27.
+    pub fn sign_recoverable(&self, msg: &[u8], rng: &rand::SecureRandom)
+                            -> Result<(signature::Signature, u8),
+                                      error::Unspecified> {
+        let e = digest_scalar(self.alg.private_scalar_ops.scalar_ops,
+                              self.alg.verification_alg.digest_alg,
+                              untrusted::Input::from(msg));
+        self.sign_digest(&e, rng)
+    }
+
+    /// Returns the signature of the message `msg`, encoded in the format of
+    /// the key pair's signing algorithm, using a "hedged" nonce.
+    ///
+    /// The nonce is derived deterministically from the private key and the
+    /// digest of `msg` as described in [RFC 6979], with fresh random bytes
+    /// from `rng` mixed in as the additional data `k'` of [RFC 6979 Section
+    /// 3.6]. Unlike with `sign()`, the nonce remains secret and unique per
+    /// message even if `rng` is broken. Unlike with purely deterministic
+    /// signing, signing the same message twice gives different signatures,
+    /// which makes fault attacks that compare two signatures of the same
+    /// message impractical.
+    ///
+    /// The signatures are normal ECDSA signatures; verifiers can't tell how
+    /// the nonce was chosen.
+    ///
+    /// [RFC 6979]: https://tools.ietf.org/html/rfc6979
+    /// [RFC 6979 Section 3.6]: https://tools.ietf.org/html/rfc6979#section-3.6
+    pub fn sign_hedged(&self, msg: &[u8], rng: &rand::SecureRandom)
+                       -> Result<signature::Signature, error::Unspecified> {
+        let scalar_len = self.alg.private_key_ops().common.num_limbs *
+                         LIMB_BYTES;
+        let mut extra = [0; ec::SCALAR_MAX_BYTES];
+        try!(rng.fill(&mut extra[..scalar_len]));
+        self.sign_with_additional_data(msg, &extra[..scalar_len])
+    }
+
+    fn sign_with_additional_data(&self, msg: &[u8], additional_data: &[u8])
+            -> Result<signature::Signature, error::Unspecified> {
+        let scalar_ops = self.alg.private_scalar_ops.scalar_ops;
+        let num_limbs = scalar_ops.public_key_ops.common.num_limbs;
+        let scalar_len = num_limbs * LIMB_BYTES;
+
+        let e = digest_scalar(scalar_ops, self.alg.verification_alg.digest_alg,
+                              untrusted::Input::from(msg));
+
+        // RFC 6979 Section 3.2 Step d: bits2octets(h1) is the encoding of `e`
+        // since `digest_scalar` already reduces the digest (mod n).
+        let mut h1 = [0; ec::SCALAR_MAX_BYTES];
+        big_endian_from_limbs(&mut h1[..scalar_len], &e.limbs[..num_limbs]);
+
+        // RFC 6979 Section 3.2 Steps b through h are HMAC_DRBG instantiated
+        // with x as the entropy input, h1 as the nonce, and the additional
+        // data k' of RFC 6979 Section 3.6 as the personalization string. The
+        // first request returns the value T of Step h.2, and later requests
+        // return the candidates that Step h.3 generates after a candidate is
+        // rejected, either by `generate_private_key()` or because r or s is
+        // zero. This assumes that the length of n in bits is a multiple of 8,
+        // so that bits2int(T) is just T, which holds for all the curves that
+        // use it.
+        let rng = try!(rand::HmacDrbg::new(
+            self.alg.verification_alg.digest_alg,
+            &self.private_key.bytes[..scalar_len], &h1[..scalar_len],
+            additional_data));
+        self.sign_digest(&e, &rng).map(|(signature, _)| signature)
+    }
+
+    // Returns the signature and its recovery ID.
+    fn sign_digest(&self, e: &Scalar, rng: &rand::SecureRandom)
+                   -> Result<(signature::Signature, u8), error::Unspecified> {
+        // Generate an ECDSA signature as documented in the NSA Suite B
+        // Implementer's Guide to ECDSA Section 3.4.1: ECDSA Signature
+        // Generation. NSA Guide Steps 5 and 6 were done by the caller.
+        let ops = self.alg.private_scalar_ops;
+        let scalar_ops = ops.scalar_ops;
+        let private_key_ops = scalar_ops.private_key_ops;
+
+        let d = private_key_as_scalar(private_key_ops, &self.private_key);
+        let d = ops.scalar_to_mont(&d);
+
+        // XXX: The value 100 was chosen to match `generate_private_key()`.
+        for _ in 0..100 {
+            // NSA Guide Step 1: Generate the nonce `k` in [1, n) using the
+            // same procedure as is used for key pair generation.
+            let k = try!(generate_private_key(private_key_ops, rng));
+            let k = private_key_as_scalar(private_key_ops, &k);
+
+            // NSA Guide Step 2: Compute the inverse of `k`.
+            let k_inv = scalar_ops.scalar_inv_to_mont(&k);
+
+            // NSA Guide Step 3: Compute R = k*G, and convert it to affine
+            // form. `affine_from_jacobian` also verifies that the result is
+            // on the curve.
+            let r = self.point_mul_base(&k);
+            let (x, y) = try!(affine_from_jacobian(private_key_ops, &r));
+
+            // NSA Guide Step 4: Compute r = x (mod n). `x` is in [0, q) and
+            // q < 2*n, so at most one subtraction of n is needed. If r is
+            // zero then choose another `k`.
+            let x = private_key_ops.common.elem_decoded(&x);
+            let r = scalar_ops.scalar_from_unreduced_limbs(&x.limbs);
+            if ops.scalar_is_zero(&r) {
+                continue;
+            }
+
+            // NSA Guide Step 7: Compute s = (k**-1 * (e + d*r)) (mod n). If
+            // s is zero then choose another `k`.
+            let dr = scalar_ops.scalar_mul_mixed(&r, &d);
+            let e_plus_dr = ops.scalar_sum(e, &dr);
+            let s = scalar_ops.scalar_mul_mixed(&e_plus_dr, &k_inv);
+            if ops.scalar_is_zero(&s) {
+                continue;
+            }
+
+            // See `sign_recoverable()`.
+            let y = private_key_ops.common.elem_decoded(&y);
+            let y_is_odd = (y.limbs[0] & 1) == 1;
+            let x_is_reduced =
+                !scalar_ops.elem_decoded_equals(
+                    &scalar_ops.scalar_as_elem_decoded(&r), &x);
+            let recovery_id = (y_is_odd as u8) | ((x_is_reduced as u8) << 1);
+
+            // NSA Guide Step 8: Return (r, s).
+            return Ok(((self.alg.format_rs)(scalar_ops, &r, &s), recovery_id));
+        }
+
+        Err(error::Unspecified)
+    }
+}
+
+impl signature::KeyPair for ECDSAKeyPair {
+    /// The public key in uncompressed form, the form accepted by the
+    /// `ECDSA_*_ASN1` verification algorithms; the same as
+    /// `public_key_bytes()`.
+    type PublicKey = [u8];
+
+    fn public_key(&self) -> &[u8] { self.public_key_bytes() }
+}
+
+impl signature::Signer for ECDSAKeyPair {
+    fn public_key_bytes(&self) -> &[u8] { self.public_key_bytes() }
+
+    fn max_signature_len(&self) -> usize { signature::MAX_LEN }
+
+    fn sign(&self, msg: &[u8], rng: &rand::SecureRandom, signature: &mut [u8])
+            -> Result<usize, error::Unspecified> {
+        try!(ECDSAKeyPair::sign(self, msg, rng)).write_to(signature)
+    }
+}
+
+// Encodes (r, s) as an ASN.1 `Ecdsa-Sig-Value`. Both `r` and `s` must be
+// nonzero.
+pub fn format_rs_asn1(ops: &PublicScalarOps, r: &Scalar, s: &Scalar)
+                      -> signature::Signature {
+    signature::Signature::new_using(|out| {
+        let (tag_and_len, value) = out.split_at_mut(2);
+        let r_tlv_len = format_integer_tlv(ops, r, value);
+        let s_tlv_len = format_integer_tlv(ops, s, &mut value[r_tlv_len..]);
+        let value_len = r_tlv_len + s_tlv_len;
+
+        // `signature::MAX_LEN` is small enough that the length always fits in
+        // the short form.
+        debug_assert!(value_len < 0x80);
+        tag_and_len[0] = der::Tag::Sequence as u8;
+        tag_and_len[1] = value_len as u8;
+        2 + value_len
+    })
+}
+
+// Encodes (r, s) as the concatenation of their big-endian encodings, each
+// zero-padded to the length of `n`.
+pub fn format_rs_fixed(ops: &PublicScalarOps, r: &Scalar, s: &Scalar)
+                       -> signature::Signature {
+    let num_limbs = ops.public_key_ops.common.num_limbs;
+    let scalar_len = num_limbs * LIMB_BYTES;
+    signature::Signature::new_using(|out| {
+        let (r_out, rest) = out.split_at_mut(scalar_len);
+        big_endian_from_limbs(r_out, &r.limbs[..num_limbs]);
+        big_endian_from_limbs(&mut rest[..scalar_len], &s.limbs[..num_limbs]);
+        2 * scalar_len
+    })
+}
+
+// Writes `a`, which must be nonzero, as a DER-encoded `INTEGER` to the start
+// of `out`, returning the number of bytes written. `a` is public, so this
+// doesn't need to be constant-time.
+fn format_integer_tlv(ops: &PublicScalarOps, a: &Scalar, out: &mut [u8])
+                      -> usize {
+    let num_limbs = ops.public_key_ops.common.num_limbs;
+
+    // Leave room for a leading zero byte, in case the high bit is set.
+    let mut fixed = [0u8; 1 + ec::ELEM_MAX_BYTES];
+    let fixed = &mut fixed[..(1 + (num_limbs * LIMB_BYTES))];
+    big_endian_from_limbs(&mut fixed[1..], &a.limbs[..num_limbs]);
+
+    // Strip the leading zeros, except keep one if it is needed to keep the
+    // value positive. `a` is nonzero so there is always a nonzero byte.
+    let first_nonzero = fixed.iter().position(|b| *b != 0).unwrap();
+    let start = if (fixed[first_nonzero] & 0x80) != 0 {
+        first_nonzero - 1
+    } else {
+        first_nonzero
+    };
+    let value = &fixed[start..];
+
+    out[0] = der::Tag::Integer as u8;
+    out[1] = value.len() as u8;
+    out[2..(2 + value.len())].copy_from_slice(value);
+    2 + value.len()
+}
 
 
 /// Calculate the digest of `msg` using the digest algorithm `digest_alg`. Then
@@ -158,9 +1028,9 @@ fn digest_scalar(ops: &PublicScalarOps, digest_alg: &'static digest::Algorithm,
     digest_scalar_(ops, digest.as_ref())
 }
 
-// This is a separate function solely so that we can test specific digest
-// values like all-zero values and values larger than `n`.
-fn digest_scalar_(ops: &PublicScalarOps, digest: &[u8]) -> Scalar {
+// This is a separate function so that we can test specific digest values like
+// all-zero values and values larger than `n`, and so that SM2 can use it.
+pub fn digest_scalar_(ops: &PublicScalarOps, digest: &[u8]) -> Scalar {
     let num_limbs = ops.public_key_ops.common.num_limbs;
 
     let digest = if digest.len() > num_limbs * LIMB_BYTES {
@@ -177,8 +1047,8 @@ fn digest_scalar_(ops: &PublicScalarOps, digest: &[u8]) -> Scalar {
     ops.scalar_from_unreduced_limbs(&limbs)
 }
 
-fn twin_mul(ops: &PrivateKeyOps, g_scalar: &Scalar, p_scalar: &Scalar,
-            p_xy: &(Elem, Elem)) -> Point {
+pub fn twin_mul(ops: &PrivateKeyOps, g_scalar: &Scalar, p_scalar: &Scalar,
+                p_xy: &(Elem, Elem)) -> Point {
     // XXX: Inefficient. TODO: implement interleaved wNAF multiplication.
     let scaled_g = ops.point_mul_base(g_scalar);
     let scaled_p = ops.point_mul(p_scalar, p_xy);
@@ -194,6 +1064,7 @@ fn twin_mul(ops: &PrivateKeyOps, g_scalar: &Scalar, p_scalar: &Scalar,
 pub static ECDSA_P256_SHA256_ASN1: ECDSAParameters = ECDSAParameters {
     ops: &p256::PUBLIC_SCALAR_OPS,
     digest_alg: &digest::SHA256,
+    split_rs: split_rs_asn1,
 };
 
 /// *Not recommended*. Verification of ASN.1 DER-encoded ECDSA signatures using
@@ -209,6 +1080,7 @@ pub static ECDSA_P256_SHA256_ASN1: ECDSAParameters = ECDSAParameters {
 pub static ECDSA_P256_SHA384_ASN1: ECDSAParameters = ECDSAParameters {
     ops: &p256::PUBLIC_SCALAR_OPS,
     digest_alg: &digest::SHA384,
+    split_rs: split_rs_asn1,
 };
 
 /// *Not recommended*. Verification of ASN.1 DER-encoded ECDSA signatures using
@@ -224,6 +1096,7 @@ pub static ECDSA_P256_SHA384_ASN1: ECDSAParameters = ECDSAParameters {
 pub static ECDSA_P384_SHA256_ASN1: ECDSAParameters = ECDSAParameters {
     ops: &p384::PUBLIC_SCALAR_OPS,
     digest_alg: &digest::SHA256,
+    split_rs: split_rs_asn1,
 };
 
 /// Verification of ASN.1 DER-encoded ECDSA signatures using the P-384 curve
@@ -234,14 +1107,166 @@ pub static ECDSA_P384_SHA256_ASN1: ECDSAParameters = ECDSAParameters {
 pub static ECDSA_P384_SHA384_ASN1: ECDSAParameters = ECDSAParameters {
     ops: &p384::PUBLIC_SCALAR_OPS,
     digest_alg: &digest::SHA384,
+    split_rs: split_rs_asn1,
+};
+
+/// Verification of ASN.1 DER-encoded ECDSA signatures using the secp256k1
+/// curve and SHA-256.
+///
+/// secp256k1 is the curve used by Bitcoin and many other cryptocurrency
+/// protocols. Prefer P-256 or P-384 when there is a choice.
+///
+/// See "`ECDSA_*_ASN1` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_SECP256K1_SHA256_ASN1: ECDSAParameters = ECDSAParameters {
+    ops: &secp256k1::PUBLIC_SCALAR_OPS,
+    digest_alg: &digest::SHA256,
+    split_rs: split_rs_asn1,
+};
+
+
+/// Signing of ASN.1 DER-encoded ECDSA signatures using the P-256 curve and
+/// SHA-256.
+///
+/// See "`ECDSA_*_ASN1` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P256_SHA256_ASN1_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_P256_SHA256_ASN1,
+    private_scalar_ops: &p256::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_asn1,
+    pkcs8_alg_id: &P256_ALG_ID,
 };
 
+/// Signing of ASN.1 DER-encoded ECDSA signatures using the P-384 curve and
+/// SHA-384.
+///
+/// See "`ECDSA_*_ASN1` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P384_SHA384_ASN1_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_P384_SHA384_ASN1,
+    private_scalar_ops: &p384::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_asn1,
+    pkcs8_alg_id: &P384_ALG_ID,
+};
+
+/// Signing of ASN.1 DER-encoded ECDSA signatures using the secp256k1 curve
+/// and SHA-256.
+///
+/// See "`ECDSA_*_ASN1` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_SECP256K1_SHA256_ASN1_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_SECP256K1_SHA256_ASN1,
+    private_scalar_ops: &secp256k1::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_asn1,
+    pkcs8_alg_id: &SECP256K1_ALG_ID,
+};
+
+
+/// Verification of fixed-length (PKCS#11 style) ECDSA signatures using the
+/// P-256 curve and SHA-256.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P256_SHA256_FIXED: ECDSAParameters = ECDSAParameters {
+    ops: &p256::PUBLIC_SCALAR_OPS,
+    digest_alg: &digest::SHA256,
+    split_rs: split_rs_fixed,
+};
+
+/// Verification of fixed-length (PKCS#11 style) ECDSA signatures using the
+/// P-384 curve and SHA-384.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P384_SHA384_FIXED: ECDSAParameters = ECDSAParameters {
+    ops: &p384::PUBLIC_SCALAR_OPS,
+    digest_alg: &digest::SHA384,
+    split_rs: split_rs_fixed,
+};
+
+/// Verification of fixed-length (PKCS#11 style) ECDSA signatures using the
+/// secp256k1 curve and SHA-256.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_SECP256K1_SHA256_FIXED: ECDSAParameters = ECDSAParameters {
+    ops: &secp256k1::PUBLIC_SCALAR_OPS,
+    digest_alg: &digest::SHA256,
+    split_rs: split_rs_fixed,
+};
+
+
+/// Signing of fixed-length (PKCS#11 style) ECDSA signatures using the P-256
+/// curve and SHA-256.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P256_SHA256_FIXED_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_P256_SHA256_FIXED,
+    private_scalar_ops: &p256::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_fixed,
+    pkcs8_alg_id: &P256_ALG_ID,
+};
+
+/// Signing of fixed-length (PKCS#11 style) ECDSA signatures using the P-384
+/// curve and SHA-384.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_P384_SHA384_FIXED_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_P384_SHA384_FIXED,
+    private_scalar_ops: &p384::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_fixed,
+    pkcs8_alg_id: &P384_ALG_ID,
+};
+
+/// Signing of fixed-length (PKCS#11 style) ECDSA signatures using the
+/// secp256k1 curve and SHA-256.
+///
+/// See "`ECDSA_*_FIXED` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static ECDSA_SECP256K1_SHA256_FIXED_SIGNING: ECDSASigningAlgorithm =
+        ECDSASigningAlgorithm {
+    verification_alg: &ECDSA_SECP256K1_SHA256_FIXED,
+    private_scalar_ops: &secp256k1::PRIVATE_SCALAR_OPS,
+    format_rs: format_rs_fixed,
+    pkcs8_alg_id: &SECP256K1_ALG_ID,
+};
+
+
+// The `AlgorithmIdentifier`s of `id-ecPublicKey` (1.2.840.10045.2.1) keys on
+// each curve, with the `namedCurve` OID as the parameters.
+const P256_ALG_ID: [u8; 21] = [
+    0x30, 0x13,
+        0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01,
+        0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, // P-256
+];
+
+const P384_ALG_ID: [u8; 18] = [
+    0x30, 0x10,
+        0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01,
+        0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x22, // P-384
+];
+
+const SECP256K1_ALG_ID: [u8; 18] = [
+    0x30, 0x10,
+        0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01,
+        0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a, // secp256k1
+];
+
 
 #[cfg(test)]
 mod tests {
-    use {digest, test, signature};
+    use {der, digest, ec, error, rand, test, signature};
+    use std;
     use super::digest_scalar_;
     use super::super::ops::*;
+    use super::super::private_key::big_endian_from_limbs;
     use untrusted;
 
     #[test]
@@ -264,16 +1289,152 @@ mod tests {
 
             let expected_result = test_case.consume_string("Result");
 
-            let (alg, _, _) = alg_from_curve_and_digest(&curve_name,
-                                                        &digest_name);
+            let (alg, ops, _) = alg_from_curve_and_digest(&curve_name,
+                                                          &digest_name);
 
             let actual_result = signature::verify(alg, public_key, msg, sig);
             assert_eq!(actual_result.is_ok(), expected_result == "P (0 )");
 
+            // An `ECDSAVerificationKey` gives the same result, with or without
+            // the precomputed tables.
+            match signature::ECDSAVerificationKey::new(alg, public_key) {
+                Ok(key) => {
+                    let result = key.verify(msg.as_slice_less_safe(),
+                                            sig.as_slice_less_safe());
+                    assert_eq!(result.is_ok(), actual_result.is_ok());
+                    #[cfg(feature = "use_heap")]
+                    {
+                        let mut key = key;
+                        key.precompute_verification_tables();
+                        let result = key.verify(msg.as_slice_less_safe(),
+                                                sig.as_slice_less_safe());
+                        assert_eq!(result.is_ok(), actual_result.is_ok());
+                    }
+                },
+                Err(_) => {
+                    assert!(actual_result.is_err());
+                },
+            }
+
+            // Converting a well-encoded signature to the fixed-length form
+            // and back is lossless; DER is canonical. Every valid signature
+            // is well-encoded.
+            let scalar_len = ops.public_key_ops.common.num_limbs * LIMB_BYTES;
+            match signature::ecdsa_asn1_to_fixed(alg, sig) {
+                Ok(fixed) => {
+                    assert_eq!(fixed.as_slice(),
+                               &asn1_sig_to_fixed(sig, scalar_len)[..]);
+                    let asn1 = signature::ecdsa_fixed_to_asn1(
+                        alg, untrusted::Input::from(fixed.as_slice()))
+                        .unwrap();
+                    assert_eq!(asn1.as_slice(), sig.as_slice_less_safe());
+                },
+                Err(_) => {
+                    assert!(actual_result.is_err());
+                },
+            }
+
+            // Every valid signature is also valid in the fixed-length form.
+            if let Some((fixed_alg, fixed_scalar_len)) =
+                    fixed_alg_from_curve_and_digest(&curve_name, &digest_name) {
+                if actual_result.is_ok() {
+                    assert_eq!(scalar_len, fixed_scalar_len);
+                    let fixed_sig = asn1_sig_to_fixed(sig, scalar_len);
+                    assert!(signature::verify(
+                                fixed_alg, public_key, msg,
+                                untrusted::Input::from(&fixed_sig)).is_ok());
+                    assert!(signature::verify(fixed_alg, public_key, msg, sig)
+                                .is_err());
+                }
+            }
+
             Ok(())
         });
     }
 
+    #[test]
+    fn signature_ecdsa_validate_public_key_test() {
+        test::from_file("src/ec/suite_b/suite_b_public_key_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let curve_name = test_case.consume_string("Curve");
+            let public_key = test_case.consume_bytes("Q");
+            let public_key = untrusted::Input::from(&public_key);
+            let valid = test_case.consume_string("Result") == "P";
+
+            // The digest algorithm doesn't matter.
+            for digest_name in &["SHA256", "SHA384"] {
+                let (alg, _, _) =
+                    alg_from_curve_and_digest(&curve_name, digest_name);
+                assert_eq!(
+                    signature::ecdsa_validate_public_key(alg, public_key)
+                        .is_ok(),
+                    valid);
+                assert_eq!(
+                    signature::ECDSAVerificationKey::new(alg, public_key)
+                        .is_ok(),
+                    valid);
+            }
+
+            // Valid public keys round-trip through the raw form, which is
+            // the uncompressed form without the leading byte.
+            let (alg, _, _) = alg_from_curve_and_digest(&curve_name, "SHA256");
+            match signature::ecdsa_public_key_to_raw(alg, public_key) {
+                Ok(raw) => {
+                    assert!(valid);
+                    let uncompressed = signature::ecdsa_public_key_from_raw(
+                        alg, untrusted::Input::from(raw.as_slice())).unwrap();
+                    assert_eq!(uncompressed.as_slice()[0], 4);
+                    assert_eq!(&uncompressed.as_slice()[1..], raw.as_slice());
+                    if public_key.as_slice_less_safe()[0] == 4 {
+                        assert_eq!(uncompressed.as_slice(),
+                                   public_key.as_slice_less_safe());
+                    }
+                },
+                Err(_) => {
+                    assert!(!valid);
+                    // The raw form of an invalid uncompressed key is also
+                    // invalid.
+                    let public_key = public_key.as_slice_less_safe();
+                    if public_key.first() == Some(&4) {
+                        assert!(signature::ecdsa_public_key_from_raw(
+                                    alg, untrusted::Input::from(
+                                        &public_key[1..])).is_err());
+                    }
+                },
+            }
+
+            Ok(())
+        });
+
+        // Generated keys are always valid.
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in SIGNING_ALGS {
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+            assert!(signature::ecdsa_validate_public_key(verification_alg,
+                                                         public_key).is_ok());
+
+            let raw = key_pair.public_key_raw();
+            assert_eq!(raw, &key_pair.public_key_bytes()[1..]);
+            assert_eq!(signature::ecdsa_public_key_to_raw(verification_alg,
+                                                          public_key)
+                           .unwrap().as_slice(),
+                       raw);
+
+            // The raw form has a fixed length.
+            for len in &[0, raw.len() - 1, raw.len() + 1] {
+                let mut bytes = [0u8; ec::PUBLIC_KEY_MAX_LEN + 1];
+                bytes[..raw.len()].copy_from_slice(raw);
+                assert!(signature::ecdsa_public_key_from_raw(
+                            verification_alg,
+                            untrusted::Input::from(&bytes[..*len])).is_err());
+            }
+        }
+    }
+
     #[test]
     fn ecdsa_digest_scalar_test() {
         test::from_file("src/ec/suite_b/ecdsa_digest_scalar_tests.txt",
@@ -307,8 +1468,553 @@ mod tests {
         });
     }
 
+    #[test]
+    fn signature_ecdsa_sign_test() {
+        test::from_file("src/ec/suite_b/ecdsa_sign_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let curve_name = test_case.consume_string("Curve");
+            let digest_name = test_case.consume_string("Digest");
+
+            let msg = test_case.consume_bytes("Msg");
+            let private_key = test_case.consume_bytes("d");
+            let public_key = test_case.consume_bytes("Q");
+            let k = test_case.consume_bytes("k");
+            let expected_sig = test_case.consume_bytes("Sig");
+
+            let (signing_alg, verification_alg) =
+                signing_alg_from_curve_and_digest(&curve_name, &digest_name);
+
+            let key_pair = signature::ECDSAKeyPair::from_bytes(
+                signing_alg, untrusted::Input::from(&private_key),
+                untrusted::Input::from(&public_key)).unwrap();
+            assert_eq!(key_pair.public_key_bytes(), &public_key[..]);
+
+            let rng = test::rand::FixedSliceRandom { bytes: &k };
+            let actual_sig = key_pair.sign(&msg, &rng).unwrap();
+            assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+
+            assert!(signature::verify(verification_alg,
+                                      untrusted::Input::from(&public_key),
+                                      untrusted::Input::from(&msg),
+                                      untrusted::Input::from(&expected_sig))
+                        .is_ok());
+
+            // The same nonce gives the same (r, s) in the fixed-length form.
+            let (fixed_signing_alg, fixed_verification_alg) =
+                fixed_signing_alg_from_curve_and_digest(&curve_name,
+                                                        &digest_name);
+            let key_pair = signature::ECDSAKeyPair::from_bytes(
+                fixed_signing_alg, untrusted::Input::from(&private_key),
+                untrusted::Input::from(&public_key)).unwrap();
+            let rng = test::rand::FixedSliceRandom { bytes: &k };
+            let actual_sig = key_pair.sign(&msg, &rng).unwrap();
+            let expected_sig =
+                asn1_sig_to_fixed(untrusted::Input::from(&expected_sig),
+                                  private_key.len());
+            assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+            assert!(signature::verify(fixed_verification_alg,
+                                      untrusted::Input::from(&public_key),
+                                      untrusted::Input::from(&msg),
+                                      untrusted::Input::from(&expected_sig))
+                        .is_ok());
+
+            // Using the precomputed tables doesn't change the signature.
+            #[cfg(feature = "use_heap")]
+            {
+                let mut key_pair = key_pair;
+                key_pair.precompute_signing_tables();
+                let rng = test::rand::FixedSliceRandom { bytes: &k };
+                let actual_sig = key_pair.sign(&msg, &rng).unwrap();
+                assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_ecdsa_hedged_sign_test() {
+        test::from_file("src/ec/suite_b/ecdsa_hedged_sign_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let curve_name = test_case.consume_string("Curve");
+            let digest_name = test_case.consume_string("Digest");
+
+            let msg = test_case.consume_bytes("Msg");
+            let private_key = test_case.consume_bytes("d");
+            let public_key = test_case.consume_bytes("Q");
+            let extra = test_case.consume_bytes("Extra");
+            let _ = test_case.consume_bytes("k");
+            let expected_sig = test_case.consume_bytes("Sig");
+
+            let (signing_alg, verification_alg) =
+                signing_alg_from_curve_and_digest(&curve_name, &digest_name);
+
+            let key_pair = signature::ECDSAKeyPair::from_bytes(
+                signing_alg, untrusted::Input::from(&private_key),
+                untrusted::Input::from(&public_key)).unwrap();
+
+            let actual_sig =
+                key_pair.sign_with_additional_data(&msg, &extra).unwrap();
+            assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+
+            assert!(signature::verify(verification_alg,
+                                      untrusted::Input::from(&public_key),
+                                      untrusted::Input::from(&msg),
+                                      untrusted::Input::from(&expected_sig))
+                        .is_ok());
+
+            // `sign_hedged` uses a scalar-length `k'` from `rng`.
+            if !extra.is_empty() {
+                let rng = test::rand::FixedSliceRandom { bytes: &extra };
+                let actual_sig = key_pair.sign_hedged(&msg, &rng).unwrap();
+                assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_ecdsa_sign_hedged_and_verify_test() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in SIGNING_ALGS {
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+
+            let sig_1 = key_pair.sign_hedged(MESSAGE, &rng).unwrap();
+            let sig_2 = key_pair.sign_hedged(MESSAGE, &rng).unwrap();
+            assert!(sig_1.as_slice() != sig_2.as_slice());
+
+            // Even with a broken `rng` the nonce depends on the message.
+            let zeros = test::rand::FixedByteRandom { byte: 0 };
+            let sig_3 = key_pair.sign_hedged(MESSAGE, &zeros).unwrap();
+            let sig_4 = key_pair.sign_hedged(b"hello", &zeros).unwrap();
+
+            for &(sig, msg) in &[(&sig_1, MESSAGE), (&sig_2, MESSAGE),
+                                 (&sig_3, MESSAGE), (&sig_4, b"hello")] {
+                let sig = untrusted::Input::from(sig.as_slice());
+                assert!(signature::verify(verification_alg, public_key,
+                                          untrusted::Input::from(msg),
+                                          sig).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn signature_ecdsa_sign_and_verify_test() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in SIGNING_ALGS {
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+
+            let sig_1 = key_pair.sign(MESSAGE, &rng).unwrap();
+            let sig_2 = key_pair.sign(MESSAGE, &rng).unwrap();
+            // The nonces are random, so the signatures differ.
+            assert!(sig_1.as_slice() != sig_2.as_slice());
+
+            for sig in &[sig_1, sig_2] {
+                let sig = untrusted::Input::from(sig.as_slice());
+                assert!(signature::verify(verification_alg, public_key,
+                                          untrusted::Input::from(MESSAGE),
+                                          sig).is_ok());
+                assert!(signature::verify(verification_alg, public_key,
+                                          untrusted::Input::from(b"hello"),
+                                          sig).is_err());
+            }
+        }
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn signature_ecdsa_generate_pkcs8_test() {
+        use pkcs8;
+
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in SIGNING_ALGS {
+            let document =
+                signature::ECDSAKeyPair::generate_pkcs8(signing_alg, &rng)
+                    .unwrap();
+
+            // Take the `ECPrivateKey` apart the same way OpenSSL would.
+            let ec_private_key = pkcs8::unwrap_key(
+                signing_alg.pkcs8_alg_id,
+                untrusted::Input::from(document.as_ref())).unwrap();
+            let (private_key, public_key) =
+                ec_private_key.read_all(error::Unspecified, |input| {
+                    der::nested(input, der::Tag::Sequence, error::Unspecified,
+                                |input| {
+                        assert_eq!(der::small_nonnegative_integer(input), Ok(1));
+                        let private_key = try!(der::expect_tag_and_get_value(
+                            input, der::Tag::OctetString));
+                        let public_key = try!(der::nested(
+                            input, der::Tag::ContextSpecificConstructed1,
+                            error::Unspecified, |input| {
+                                der::expect_tag_and_get_value(
+                                    input, der::Tag::BitString)
+                            }));
+                        Ok((private_key, public_key))
+                    })
+                }).unwrap();
+            let public_key = public_key.as_slice_less_safe();
+            assert_eq!(public_key[0], 0); // No unused bits.
+            let public_key = untrusted::Input::from(&public_key[1..]);
+
+            let key_pair = signature::ECDSAKeyPair::from_bytes(
+                signing_alg, private_key, public_key).unwrap();
+            let sig = key_pair.sign(b"hello, world", &rng).unwrap();
+            assert!(signature::verify(verification_alg, public_key,
+                                      untrusted::Input::from(b"hello, world"),
+                                      untrusted::Input::from(sig.as_slice()))
+                        .is_ok());
+        }
+    }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    fn signature_ecdsa_from_pkcs8_and_sec1_test() {
+        test::from_file("src/ec/suite_b/ecdsa_private_key_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let curve_name = test_case.consume_string("Curve");
+            let format = test_case.consume_string("Format");
+            let input = test_case.consume_bytes("Input");
+            let input = untrusted::Input::from(&input);
+            let error = test_case.consume_optional_string("Error");
+
+            let parse = |signing_alg| {
+                if format == "SEC1" {
+                    signature::ECDSAKeyPair::from_sec1_der(signing_alg, input)
+                } else if format == "PKCS8" {
+                    signature::ECDSAKeyPair::from_pkcs8(signing_alg, input)
+                } else {
+                    panic!("Unsupported format: {}", format);
+                }
+            };
+
+            let digest_name = if curve_name == "P-384" { "SHA384" }
+                              else { "SHA256" };
+            let (signing_alg, _) =
+                signing_alg_from_curve_and_digest(&curve_name, digest_name);
+            let result = parse(signing_alg);
+            match error {
+                None => {
+                    let expected_public_key = test_case.consume_bytes("Q");
+                    let key_pair = result.unwrap();
+                    assert_eq!(key_pair.public_key_bytes(),
+                               &expected_public_key[..]);
+                },
+                Some(_) => assert!(result.is_err()),
+            }
+
+            // The key is only valid for the algorithms for its curve.
+            for &(other_alg, _) in SIGNING_ALGS {
+                if other_alg.pkcs8_alg_id != signing_alg.pkcs8_alg_id {
+                    assert!(parse(other_alg).is_err());
+                }
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_ecdsa_sign_bad_rng_test() {
+        // A nonce of all 0xff bytes is never less than `n`, so signing gives
+        // up after a while of only getting that value from the PRNG.
+        let rng = rand::SystemRandom::new();
+        let random_ff = test::rand::FixedByteRandom { byte: 0xff };
+        for &(signing_alg, _) in SIGNING_ALGS {
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            assert!(key_pair.sign(b"hello, world", &random_ff).is_err());
+        }
+    }
+
+    #[test]
+    fn signature_ecdsa_recover_public_key_test() {
+        test::from_file("src/ec/suite_b/ecdsa_recover_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let curve_name = test_case.consume_string("Curve");
+            let digest_name = test_case.consume_string("Digest");
+            let msg = test_case.consume_bytes("Msg");
+            let msg = untrusted::Input::from(&msg);
+            let sig = test_case.consume_bytes("Sig");
+            let sig = untrusted::Input::from(&sig);
+            let recovery_id = test_case.consume_usize("RecoveryId") as u8;
+            let expected_result = test_case.consume_string("Result");
+
+            let (alg, ops, _) = alg_from_curve_and_digest(&curve_name,
+                                                          &digest_name);
+            let public_key_len =
+                1 + (2 * ops.public_key_ops.common.num_limbs * LIMB_BYTES);
+
+            let mut public_key = [0u8; ec::PUBLIC_KEY_MAX_LEN];
+            let public_key = &mut public_key[..public_key_len];
+            let result = signature::ecdsa_recover_public_key(
+                alg, msg, sig, recovery_id, public_key);
+            assert_eq!(result.is_ok(), expected_result == "P");
+            if result.is_ok() {
+                let expected_public_key = test_case.consume_bytes("Q");
+                assert_eq!(&public_key[..], &expected_public_key[..]);
+                assert!(signature::verify(alg,
+                                          untrusted::Input::from(public_key),
+                                          msg, sig).is_ok());
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_ecdsa_sign_recoverable_test() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in SIGNING_ALGS {
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = key_pair.public_key_bytes();
+            let (sig, recovery_id) =
+                key_pair.sign_recoverable(MESSAGE, &rng).unwrap();
+            assert!(recovery_id < 2);
+            let sig = untrusted::Input::from(sig.as_slice());
+
+            let mut recovered = [0u8; ec::PUBLIC_KEY_MAX_LEN];
+            let recovered = &mut recovered[..public_key.len()];
+            assert!(signature::ecdsa_recover_public_key(
+                        verification_alg, untrusted::Input::from(MESSAGE), sig,
+                        recovery_id, recovered).is_ok());
+            assert_eq!(&recovered[..], public_key);
+
+            // The other recovery ID for y gives a different public key.
+            assert!(signature::ecdsa_recover_public_key(
+                        verification_alg, untrusted::Input::from(MESSAGE), sig,
+                        recovery_id ^ 1, recovered).is_ok());
+            assert!(&recovered[..] != public_key);
+
+            // The output must be exactly the length of the public key.
+            let mut too_long = [0u8; ec::PUBLIC_KEY_MAX_LEN + 1];
+            let too_long = &mut too_long[..(public_key.len() + 1)];
+            assert!(signature::ecdsa_recover_public_key(
+                        verification_alg, untrusted::Input::from(MESSAGE), sig,
+                        recovery_id, too_long).is_err());
+            assert!(signature::ecdsa_recover_public_key(
+                        verification_alg, untrusted::Input::from(MESSAGE), sig,
+                        recovery_id, &mut too_long[..(public_key.len() - 1)])
+                        .is_err());
+        }
+    }
+
+    #[test]
+    fn signature_ecdsa_from_bytes_misuse_test() {
+        for &(signing_alg, _) in SIGNING_ALGS {
+            let ops = signing_alg.private_key_ops().common;
+            let num_bytes = ops.num_limbs * LIMB_BYTES;
+
+            // Any private key that starts with a byte less than 0xff is less
+            // than `n` for all the curves.
+            let private_key_1 = [0x11u8; 48];
+            let private_key_1 = &private_key_1[..num_bytes];
+            let private_key_2 = [0x22u8; 48];
+            let private_key_2 = &private_key_2[..num_bytes];
+
+            // Key generation uses the RNG output as the private key, so this
+            // gives us the public keys.
+            let rng = test::rand::FixedSliceRandom { bytes: private_key_1 };
+            let key_pair_1 =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key_1 = key_pair_1.public_key_bytes();
+            let rng = test::rand::FixedSliceRandom { bytes: private_key_2 };
+            let key_pair_2 =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key_2 = key_pair_2.public_key_bytes();
+
+            let from_bytes = |private_key: &[u8], public_key: &[u8]| {
+                signature::ECDSAKeyPair::from_bytes(
+                    signing_alg, untrusted::Input::from(private_key),
+                    untrusted::Input::from(public_key))
+            };
+
+            assert!(from_bytes(private_key_1, public_key_1).is_ok());
+            assert!(from_bytes(private_key_2, public_key_2).is_ok());
+
+            // Mismatched private and public key.
+            assert!(from_bytes(private_key_1, public_key_2).is_err());
+
+            // Truncated private key.
+            assert!(
+                from_bytes(&private_key_1[1..], public_key_1).is_err());
+
+            // Truncated public key.
+            assert!(from_bytes(private_key_1,
+                               &public_key_1[..(public_key_1.len() - 1)])
+                        .is_err());
+
+            // Swapped public and private key.
+            assert!(from_bytes(public_key_1, private_key_1).is_err());
+
+            // Zero and `n` are not valid private keys.
+            let zero = [0u8; 48];
+            assert!(from_bytes(&zero[..num_bytes], public_key_1).is_err());
+            let mut n = [0u8; 48];
+            big_endian_from_limbs(&mut n[..num_bytes],
+                                  &ops.n.limbs[..ops.num_limbs]);
+            assert!(from_bytes(&n[..num_bytes], public_key_1).is_err());
+        }
+    }
+
+    #[test]
+    fn signature_ecdsa_verify_fixed_misuse_test() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        // The fixed-length algorithms are at the end of `SIGNING_ALGS`.
+        for &(signing_alg, verification_alg) in &SIGNING_ALGS[3..] {
+            let ops = signing_alg.private_key_ops().common;
+            let scalar_len = ops.num_limbs * LIMB_BYTES;
+
+            let key_pair =
+                signature::ECDSAKeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+            let sig = key_pair.sign(MESSAGE, &rng).unwrap();
+            let sig = sig.as_slice();
+            assert_eq!(sig.len(), 2 * scalar_len);
+
+            // Everything that fails verification because of its encoding
+            // also fails conversion to the ASN.1 form.
+            let verify = |sig: &[u8]| {
+                let sig = untrusted::Input::from(sig);
+                let converted =
+                    signature::ecdsa_fixed_to_asn1(verification_alg, sig);
+                let result = signature::verify(verification_alg, public_key,
+                                               untrusted::Input::from(MESSAGE),
+                                               sig);
+                assert_eq!(converted.is_ok(), result.is_ok());
+                result
+            };
+            assert!(verify(sig).is_ok());
+
+            // Truncated and extended signatures.
+            assert!(verify(&sig[..(sig.len() - 1)]).is_err());
+            let mut extended = sig.to_vec();
+            extended.push(0);
+            assert!(verify(&extended).is_err());
+            let mut extended = vec![0];
+            extended.extend_from_slice(sig);
+            assert!(verify(&extended).is_err());
+
+            // Zero and `n` are out of range for both `r` and `s`.
+            let mut n = [0u8; 48];
+            big_endian_from_limbs(&mut n[..scalar_len],
+                                  &ops.n.limbs[..ops.num_limbs]);
+            for &(offset, value) in &[(0, &[0u8; 48][..]), (0, &n[..]),
+                                      (scalar_len, &[0u8; 48][..]),
+                                      (scalar_len, &n[..])] {
+                let mut bad = sig.to_vec();
+                bad[offset..(offset + scalar_len)]
+                    .copy_from_slice(&value[..scalar_len]);
+                assert!(verify(&bad).is_err());
+            }
+        }
+    }
+
+    // Converts an ASN.1 DER-encoded signature to the fixed-length form.
+    fn asn1_sig_to_fixed(sig: untrusted::Input, scalar_len: usize)
+                         -> std::vec::Vec<u8> {
+        let mut fixed = vec![0u8; 2 * scalar_len];
+        sig.read_all(error::Unspecified, |input| {
+            der::nested(input, der::Tag::Sequence, error::Unspecified,
+                        |input| {
+                for i in 0..2 {
+                    let value = try!(der::positive_integer(input));
+                    let value = value.as_slice_less_safe();
+                    let value = if value[0] == 0 { &value[1..] } else { value };
+                    let end = (i + 1) * scalar_len;
+                    fixed[(end - value.len())..end].copy_from_slice(value);
+                }
+                Ok(())
+            })
+        }).unwrap();
+        fixed
+    }
+
+    static SIGNING_ALGS: &'static [(&'static signature::ECDSASigningAlgorithm,
+                                    &'static signature::ECDSAParameters)] = &[
+        (&signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+         &signature::ECDSA_P256_SHA256_ASN1),
+        (&signature::ECDSA_P384_SHA384_ASN1_SIGNING,
+         &signature::ECDSA_P384_SHA384_ASN1),
+        (&signature::ECDSA_SECP256K1_SHA256_ASN1_SIGNING,
+         &signature::ECDSA_SECP256K1_SHA256_ASN1),
+        (&signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+         &signature::ECDSA_P256_SHA256_FIXED),
+        (&signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+         &signature::ECDSA_P384_SHA384_FIXED),
+        (&signature::ECDSA_SECP256K1_SHA256_FIXED_SIGNING,
+         &signature::ECDSA_SECP256K1_SHA256_FIXED),
+    ];
+
+    fn fixed_signing_alg_from_curve_and_digest(curve_name: &str,
+                                               digest_name: &str)
+            -> (&'static signature::ECDSASigningAlgorithm,
+                &'static signature::VerificationAlgorithm) {
+        if curve_name == "P-256" && digest_name == "SHA256" {
+            (&signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+             &signature::ECDSA_P256_SHA256_FIXED)
+        } else if curve_name == "P-384" && digest_name == "SHA384" {
+            (&signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+             &signature::ECDSA_P384_SHA384_FIXED)
+        } else if curve_name == "secp256k1" && digest_name == "SHA256" {
+            (&signature::ECDSA_SECP256K1_SHA256_FIXED_SIGNING,
+             &signature::ECDSA_SECP256K1_SHA256_FIXED)
+        } else {
+            panic!("Unsupported curve+digest: {}+{}", curve_name, digest_name);
+        }
+    }
+
+    // Returns the fixed-length verification algorithm and the scalar length,
+    // if there is such an algorithm for the curve and digest.
+    fn fixed_alg_from_curve_and_digest(curve_name: &str, digest_name: &str)
+            -> Option<(&'static signature::VerificationAlgorithm, usize)> {
+        if curve_name == "P-256" && digest_name == "SHA256" {
+            Some((&signature::ECDSA_P256_SHA256_FIXED, 32))
+        } else if curve_name == "P-384" && digest_name == "SHA384" {
+            Some((&signature::ECDSA_P384_SHA384_FIXED, 48))
+        } else if curve_name == "secp256k1" && digest_name == "SHA256" {
+            Some((&signature::ECDSA_SECP256K1_SHA256_FIXED, 32))
+        } else {
+            None
+        }
+    }
+
+    fn signing_alg_from_curve_and_digest(curve_name: &str, digest_name: &str)
+            -> (&'static signature::ECDSASigningAlgorithm,
+                &'static signature::VerificationAlgorithm) {
+        if curve_name == "P-256" && digest_name == "SHA256" {
+            (&signature::ECDSA_P256_SHA256_ASN1_SIGNING,
+             &signature::ECDSA_P256_SHA256_ASN1)
+        } else if curve_name == "P-384" && digest_name == "SHA384" {
+            (&signature::ECDSA_P384_SHA384_ASN1_SIGNING,
+             &signature::ECDSA_P384_SHA384_ASN1)
+        } else if curve_name == "secp256k1" && digest_name == "SHA256" {
+            (&signature::ECDSA_SECP256K1_SHA256_ASN1_SIGNING,
+             &signature::ECDSA_SECP256K1_SHA256_ASN1)
+        } else {
+            panic!("Unsupported curve+digest: {}+{}", curve_name, digest_name);
+        }
+    }
+
     fn alg_from_curve_and_digest(curve_name: &str, digest_name: &str)
-                                 -> (&'static signature::VerificationAlgorithm,
+                                 -> (&'static signature::ECDSAParameters,
                                      &'static PublicScalarOps,
                                      &'static digest::Algorithm) {
         if curve_name == "P-256" {
@@ -331,6 +2037,13 @@ mod tests {
             } else {
                 panic!("Unsupported digest algorithm: {}", digest_name);
             }
+        } else if curve_name == "secp256k1" {
+            if digest_name == "SHA256" {
+                (&signature::ECDSA_SECP256K1_SHA256_ASN1,
+                 &secp256k1::PUBLIC_SCALAR_OPS, &digest::SHA256)
+            } else {
+                panic!("Unsupported digest algorithm: {}", digest_name);
+            }
         } else {
             panic!("Unsupported curve: {}", curve_name);
         }
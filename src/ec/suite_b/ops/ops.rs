@@ -58,6 +58,13 @@ impl Scalar {
     pub fn from_limbs_unchecked(limbs: &[Limb; MAX_LIMBS]) -> Scalar {
         Scalar { limbs: *limbs }
     }
+
+    /// The scalar one.
+    pub fn one() -> Scalar {
+        let mut limbs = [0; MAX_LIMBS];
+        limbs[0] = 1;
+        Scalar { limbs: limbs }
+    }
 }
 
 /// A `Scalar`, except Montgomery-encoded, and not reduced. The range is
@@ -128,7 +135,8 @@ pub struct CommonOps {
 
     // These could be `Elem`s but we only use them in contexts that take
     // `ElemUnreduced`s.
-    pub a: ElemUnreduced, // Must be -3 mod q
+    pub a: ElemUnreduced, // -3 (mod q) for P-256, P-384, and SM2; 0 for
+                          // secp256k1.
     pub b: ElemUnreduced,
 
     // In all cases, `r`, `a`, and `b` may all alias each other.
@@ -263,6 +271,14 @@ pub struct PrivateKeyOps {
                                      p_scalar: *const Limb/*[num_limbs]*/,
                                      p_x: *const Limb/*[num_limbs]*/,
                                      p_y: *const Limb/*[num_limbs]*/),
+
+    point_double_impl: unsafe extern fn(r: *mut Limb/*[3][num_limbs]*/,
+                                        a: *const Limb/*[3][num_limbs]*/),
+
+    // Whether `point_mul_base_impl` already uses a large precomputed table,
+    // in which case a `PointTable` of multiples of G wouldn't be any faster.
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    point_mul_base_uses_table: bool,
 }
 
 impl PrivateKeyOps {
@@ -286,6 +302,139 @@ impl PrivateKeyOps {
     pub fn elem_inverse(&self, a: &ElemUnreduced) -> ElemUnreduced {
         (self.elem_inv)(a)
     }
+
+    /// Precomputes a `PointTable` of multiples of the generator G for use
+    /// with `point_mul_using_table`, or returns `None` if the curve's
+    /// `point_mul_base` is already at least as fast.
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    pub fn base_point_table(&self) -> Option<PointTable> {
+        if self.point_mul_base_uses_table {
+            return None;
+        }
+        Some(self.point_table_(self.point_mul_base(&Scalar::one())))
+    }
+
+    /// Precomputes a `PointTable` of multiples of the point `p_xy` for use
+    /// with `point_mul_using_table`.
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    pub fn point_table(&self, p_xy: &(Elem, Elem)) -> PointTable {
+        self.point_table_(self.point_mul(&Scalar::one(), p_xy))
+    }
+
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    fn point_table_(&self, p: Point) -> PointTable {
+        let spacing = self.point_table_spacing();
+
+        // `g` is 2**(t * spacing) * P for each tooth `t` in turn. Entry `u`
+        // of the table is the sum of the `g` for every bit `t` set in `u`.
+        let mut table = PointTable {
+            points: [Point::new_at_infinity(); POINT_TABLE_LEN],
+        };
+        let mut g = p;
+        for t in 0..POINT_TABLE_TEETH {
+            let bit = 1 << t;
+            table.points[bit] = g;
+            for u in 1..bit {
+                table.points[bit + u] =
+                    self.common.point_sum(&table.points[u], &g);
+            }
+            for _ in 0..spacing {
+                g = self.point_doubled(&g);
+            }
+        }
+        table
+    }
+
+    /// Returns `a` * P, where P is the point that `table` was computed for,
+    /// using the fixed-base comb method of Lim and Lee.
+    ///
+    /// This is constant-time with respect to `a`: every column of the comb
+    /// does one doubling and one addition, and the table entry is selected
+    /// using masking instead of indexing.
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    pub fn point_mul_using_table(&self, table: &PointTable, a: &Scalar)
+                                 -> Point {
+        self.comb(&[(table, a)])
+    }
+
+    /// Returns `a` * P + `b` * Q, where P and Q are the points that `a_table`
+    /// and `b_table` were computed for, respectively.
+    ///
+    /// This uses Shamir's trick: the two combs share their doublings, so
+    /// this is faster than two calls to `point_mul_using_table`. Like
+    /// `point_mul_using_table`, this is constant-time.
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    pub fn twin_mul_using_tables(&self, a_table: &PointTable, a: &Scalar,
+                                 b_table: &PointTable, b: &Scalar) -> Point {
+        self.comb(&[(a_table, a), (b_table, b)])
+    }
+
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    fn comb(&self, tables_and_scalars: &[(&PointTable, &Scalar)]) -> Point {
+        let spacing = self.point_table_spacing();
+        let num_bits = self.common.num_limbs * LIMB_BITS;
+
+        let scalar_bit = |a: &Scalar, i: usize| -> Limb {
+            if i >= num_bits {
+                return 0;
+            }
+            (a.limbs[i / LIMB_BITS] >> (i % LIMB_BITS)) & 1
+        };
+
+        let mut r = Point::new_at_infinity();
+        for i in (0..spacing).rev() {
+            r = self.point_doubled(&r);
+            for &(table, a) in tables_and_scalars {
+                let mut index = 0;
+                for t in 0..POINT_TABLE_TEETH {
+                    index |= scalar_bit(a, (t * spacing) + i) << t;
+                }
+                r = self.common.point_sum(&r, &table.select(index));
+            }
+        }
+        r
+    }
+
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    fn point_doubled(&self, a: &Point) -> Point {
+        let mut r = Point::new_at_infinity();
+        unsafe { (self.point_double_impl)(r.xyz.as_mut_ptr(), a.xyz.as_ptr()) }
+        r
+    }
+
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    fn point_table_spacing(&self) -> usize {
+        let num_bits = self.common.num_limbs * LIMB_BITS;
+        (num_bits + (POINT_TABLE_TEETH - 1)) / POINT_TABLE_TEETH
+    }
+}
+
+#[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+const POINT_TABLE_TEETH: usize = 5;
+#[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+const POINT_TABLE_LEN: usize = 1 << POINT_TABLE_TEETH;
+
+/// Precomputed multiples of a point, usually the generator, for
+/// `PrivateKeyOps::point_mul_using_table`. This is `POINT_TABLE_LEN` Jacobian
+/// points, i.e. 4.5KB on 64-bit platforms.
+#[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+pub struct PointTable {
+    points: [Point; POINT_TABLE_LEN],
+}
+
+impl PointTable {
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    fn select(&self, index: Limb) -> Point {
+        let mut r = Point::new_at_infinity();
+        for (i, entry) in self.points.iter().enumerate() {
+            let mask =
+                limbs_are_zero_constant_time(&[(i as Limb) ^ index]) as Limb;
+            for (r, &entry) in r.xyz.iter_mut().zip(entry.xyz.iter()) {
+                *r |= entry & mask;
+            }
+        }
+        r
+    }
 }
 
 
@@ -317,6 +466,75 @@ impl PublicKeyOps {
         }
         Ok(Elem { limbs: elem_limbs })
     }
+
+    // Returns the y coordinate with the parity `y_is_odd` of the point that
+    // has the x coordinate `x`, for decoding compressed points as described
+    // in SEC 1 Section 2.3.4. If there is no such point then the result is
+    // garbage, so the caller must verify that (x, y) is on the curve.
+    //
+    // q = 3 (mod 4) for all the supported curves, so a square root of
+    // x**3 + a*x + b is (x**3 + a*x + b)**((q + 1) / 4). `x` is public, so
+    // this doesn't need to be constant-time.
+    pub fn elem_y_from_x_vartime(&self, x: &Elem, y_is_odd: bool) -> Elem {
+        let cops = self.common;
+        let num_limbs = cops.num_limbs;
+        let q = &cops.q.p;
+
+        // (x**2 + a)*x + b.
+        let x = ElemUnreduced::from(x);
+        let mut rhs = cops.elem_squared(&x);
+        cops.elem_add(&mut rhs, &cops.a);
+        cops.elem_mul(&mut rhs, &x);
+        cops.elem_add(&mut rhs, &cops.b);
+
+        // (q + 1) / 4 == (q >> 2) + 1 since q = 3 (mod 4).
+        debug_assert_eq!(q[0] & 3, 3);
+        let mut e = [0; MAX_LIMBS];
+        for i in 0..num_limbs {
+            e[i] = q[i] >> 2;
+            if i + 1 < num_limbs {
+                e[i] |= q[i + 1] << (LIMB_BITS - 2);
+            }
+        }
+        for limb in e[..num_limbs].iter_mut() {
+            let (t, carry) = limb.overflowing_add(1);
+            *limb = t;
+            if !carry {
+                break;
+            }
+        }
+
+        // Left-to-right binary exponentiation. The top bit of `e` is
+        // accounted for by starting with `rhs`.
+        let num_bits = (num_limbs * LIMB_BITS) -
+                       (e[num_limbs - 1].leading_zeros() as usize);
+        let mut y = rhs.clone();
+        for bit in (0..(num_bits - 1)).rev() {
+            cops.elem_square(&mut y);
+            if (e[bit / LIMB_BITS] >> (bit % LIMB_BITS)) & 1 == 1 {
+                cops.elem_mul(&mut y, &rhs);
+            }
+        }
+
+        // Choose between y and q - y. When y is zero, q - y is q, which is
+        // wrong, but no point on a prime-order curve has y == 0.
+        let mut y = cops.elem_decoded(&y);
+        if ((y.limbs[0] & 1) == 1) != y_is_odd {
+            let mut borrow = 0;
+            for i in 0..num_limbs {
+                let (t, b1) = q[i].overflowing_sub(y.limbs[i]);
+                let (t, b2) = t.overflowing_sub(borrow);
+                y.limbs[i] = t;
+                borrow = (b1 | b2) as Limb;
+            }
+        }
+
+        // Montgomery encode (elem_to_mont).
+        let y = ElemUnreduced {
+            limbs: rab(cops.elem_mul_mont, &y.limbs, &cops.q.rr),
+        };
+        cops.elem_reduced(&y)
+    }
 }
 
 
@@ -346,6 +564,19 @@ impl PublicScalarOps {
         Ok(Scalar { limbs: limbs })
     }
 
+    // Like `scalar_parse`, but the scalar is encoded as a fixed-length,
+    // zero-padded, big-endian value instead of a DER `INTEGER`.
+    pub fn scalar_parse_fixed(&self, input: &mut untrusted::Reader)
+                              -> Result<Scalar, error::Unspecified> {
+        let num_limbs = self.public_key_ops.common.num_limbs;
+        let encoded_value =
+            try!(input.skip_and_get_input(num_limbs * LIMB_BYTES));
+        let limbs = try!(parse_big_endian_value_in_range(
+                            encoded_value, 1,
+                            &self.public_key_ops.common.n.limbs[..num_limbs]));
+        Ok(Scalar { limbs: limbs })
+    }
+
     // See the documentation for `reduced_limbs()` for the limitations of this
     // function.
     pub fn scalar_from_unreduced_limbs(&self, unreduced: &[Limb; MAX_LIMBS])
@@ -364,6 +595,25 @@ impl PublicScalarOps {
         (self.scalar_inv_to_mont_impl)(a)
     }
 
+    /// Returns -`a` (mod n). `a` must be fully reduced. This is not constant
+    /// time, so `a` must not be secret.
+    pub fn scalar_negated(&self, a: &Scalar) -> Scalar {
+        let cops = self.public_key_ops.common;
+        let num_limbs = cops.num_limbs;
+        let mut r = [0; MAX_LIMBS];
+        if a.limbs[..num_limbs].iter().all(|x| *x == 0) {
+            return Scalar { limbs: r };
+        }
+        let mut borrow = 0;
+        for i in 0..num_limbs {
+            let (t, b1) = cops.n.limbs[i].overflowing_sub(a.limbs[i]);
+            let (t, b2) = t.overflowing_sub(borrow);
+            r[i] = t;
+            borrow = (b1 | b2) as Limb;
+        }
+        Scalar { limbs: r }
+    }
+
     #[inline]
     pub fn scalar_mul_mixed(&self, a: &Scalar, b: &ScalarMont) -> Scalar {
         let unreduced = rab(self.scalar_mul_mont, &a.limbs, &b.limbs);
@@ -402,6 +652,96 @@ impl PublicScalarOps {
 }
 
 
+/// Operations on private scalars needed by ECDSA signing.
+pub struct PrivateScalarOps {
+    pub scalar_ops: &'static PublicScalarOps,
+
+    // R**2 (mod n), for converting scalars to Montgomery form.
+    n_rr: [Limb; MAX_LIMBS],
+}
+
+impl PrivateScalarOps {
+    #[inline]
+    pub fn scalar_to_mont(&self, a: &Scalar) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(self.scalar_ops.scalar_mul_mont, &a.limbs, &self.n_rr),
+        }
+    }
+
+    /// Returns (`a` + `b`) (mod n) in constant time. Both `a` and `b` must be
+    /// fully reduced.
+    pub fn scalar_sum(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        let cops = self.scalar_ops.public_key_ops.common;
+        let num_limbs = cops.num_limbs;
+        let n = &cops.n.limbs[..num_limbs];
+
+        let mut sum = [0; MAX_LIMBS];
+        let mut carry = 0;
+        for i in 0..num_limbs {
+            let (t, c1) = a.limbs[i].overflowing_add(b.limbs[i]);
+            let (t, c2) = t.overflowing_add(carry);
+            sum[i] = t;
+            carry = (c1 | c2) as Limb;
+        }
+
+        let mut difference = [0; MAX_LIMBS];
+        let mut borrow = 0;
+        for i in 0..num_limbs {
+            let (t, b1) = sum[i].overflowing_sub(n[i]);
+            let (t, b2) = t.overflowing_sub(borrow);
+            difference[i] = t;
+            borrow = (b1 | b2) as Limb;
+        }
+
+        // Use `difference` unless the full (`num_limbs` + 1)-limb sum is less
+        // than `n`, i.e. unless the subtraction borrowed and the addition
+        // didn't carry.
+        let keep_sum = (borrow & !carry).wrapping_neg();
+        let mut r = [0; MAX_LIMBS];
+        for i in 0..num_limbs {
+            r[i] = (sum[i] & keep_sum) | (difference[i] & !keep_sum);
+        }
+        Scalar { limbs: r }
+    }
+
+    /// Returns (`a` - `b`) (mod n) in constant time. Both `a` and `b` must be
+    /// fully reduced.
+    pub fn scalar_difference(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        let cops = self.scalar_ops.public_key_ops.common;
+        let num_limbs = cops.num_limbs;
+        let n = &cops.n.limbs[..num_limbs];
+
+        let mut difference = [0; MAX_LIMBS];
+        let mut borrow = 0;
+        for i in 0..num_limbs {
+            let (t, b1) = a.limbs[i].overflowing_sub(b.limbs[i]);
+            let (t, b2) = t.overflowing_sub(borrow);
+            difference[i] = t;
+            borrow = (b1 | b2) as Limb;
+        }
+
+        // If the subtraction borrowed then add `n` back, ignoring the final
+        // carry.
+        let add_n = borrow.wrapping_neg();
+        let mut r = [0; MAX_LIMBS];
+        let mut carry = 0;
+        for i in 0..num_limbs {
+            let (t, c1) = difference[i].overflowing_add(n[i] & add_n);
+            let (t, c2) = t.overflowing_add(carry);
+            r[i] = t;
+            carry = (c1 | c2) as Limb;
+        }
+        Scalar { limbs: r }
+    }
+
+    #[inline]
+    pub fn scalar_is_zero(&self, a: &Scalar) -> bool {
+        let num_limbs = self.scalar_ops.public_key_ops.common.num_limbs;
+        limbs_are_zero_constant_time(&a.limbs[..num_limbs]) == LimbMask::True
+    }
+}
+
+
 // Public Keys consist of two fixed-width, big-endian-encoded integers in the
 // range [0, q). ECDSA signatures consist of two variable-width,
 // big-endian-encoded integers in the range [1, n).
@@ -544,6 +884,7 @@ mod tests {
     use std;
     use super::*;
     use super::parse_big_endian_value_in_range;
+    use super::super::private_key::affine_from_jacobian;
     use untrusted;
 
     #[test]
@@ -552,6 +893,14 @@ mod tests {
     #[test]
     fn p384_elem_reduced_test() { test_elem_reduced(&p384::COMMON_OPS); }
 
+    #[test]
+    fn secp256k1_elem_reduced_test() {
+        test_elem_reduced(&secp256k1::COMMON_OPS);
+    }
+
+    #[test]
+    fn sm2_elem_reduced_test() { test_elem_reduced(&sm2::COMMON_OPS); }
+
     fn test_elem_reduced(ops: &CommonOps) {
         let zero = ElemUnreduced::zero();
 
@@ -698,6 +1047,35 @@ mod tests {
         })
     }
 
+    #[test]
+    fn p384_elem_mul_test() {
+        elem_mul_test(&p384::COMMON_OPS,
+                      "src/ec/suite_b/ops/p384_elem_mul_tests.txt");
+    }
+
+    fn elem_mul_test(ops: &CommonOps, file_path: &str) {
+        test::from_file(file_path, |section, test_case| {
+            assert_eq!(section, "");
+
+            let a = consume_elem_unreduced(ops, test_case, "a");
+            let b = consume_elem_unreduced(ops, test_case, "b");
+            let r = consume_elem_unreduced(ops, test_case, "r");
+
+            let actual_result = ops.elem_product(&a, &b);
+            assert_limbs_are_equal(ops, &actual_result.limbs, &r.limbs);
+
+            let actual_result = ops.elem_product(&b, &a);
+            assert_limbs_are_equal(ops, &actual_result.limbs, &r.limbs);
+
+            if a.limbs == b.limbs {
+                let actual_result = ops.elem_squared(&a);
+                assert_limbs_are_equal(ops, &actual_result.limbs, &r.limbs);
+            }
+
+            Ok(())
+        })
+    }
+
     // TODO: Add test vectors that test the range of values above `q`.
     #[test]
     fn p256_elem_neg_test() {
@@ -753,6 +1131,18 @@ mod tests {
         let _ = p384::PUBLIC_SCALAR_OPS.scalar_inv_to_mont(&ZERO_SCALAR);
     }
 
+    #[test]
+    #[should_panic(expected = "a.limbs[..num_limbs].iter().any(|x| *x != 0)")]
+    fn secp256k1_scalar_inv_to_mont_zero_panic_test() {
+        let _ = secp256k1::PUBLIC_SCALAR_OPS.scalar_inv_to_mont(&ZERO_SCALAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "a.limbs[..num_limbs].iter().any(|x| *x != 0)")]
+    fn sm2_scalar_inv_to_mont_zero_panic_test() {
+        let _ = sm2::PUBLIC_SCALAR_OPS.scalar_inv_to_mont(&ZERO_SCALAR);
+    }
+
     #[test]
     fn parse_big_endian_value_test() {
         // Empty input.
@@ -828,6 +1218,18 @@ mod tests {
                        "src/ec/suite_b/ops/p384_point_sum_tests.txt");
     }
 
+    #[test]
+    fn secp256k1_point_sum_test() {
+        point_sum_test(&secp256k1::PRIVATE_KEY_OPS,
+                       "src/ec/suite_b/ops/secp256k1_point_sum_tests.txt");
+    }
+
+    #[test]
+    fn sm2_point_sum_test() {
+        point_sum_test(&sm2::PRIVATE_KEY_OPS,
+                       "src/ec/suite_b/ops/sm2_point_sum_tests.txt");
+    }
+
     fn point_sum_test(ops: &PrivateKeyOps, file_path: &str) {
         test::from_file(file_path, |section, test_case| {
             assert_eq!(section, "");
@@ -855,6 +1257,18 @@ mod tests {
                         "src/ec/suite_b/ops/p384_point_mul_tests.txt");
     }
 
+    #[test]
+    fn secp256k1_point_mul_test() {
+        point_mul_tests(&secp256k1::PRIVATE_KEY_OPS,
+                        "src/ec/suite_b/ops/secp256k1_point_mul_tests.txt");
+    }
+
+    #[test]
+    fn sm2_point_mul_test() {
+        point_mul_tests(&sm2::PRIVATE_KEY_OPS,
+                        "src/ec/suite_b/ops/sm2_point_mul_tests.txt");
+    }
+
     fn point_mul_tests(ops: &PrivateKeyOps, file_path: &str) {
         test::from_file(file_path, |section, test_case| {
             assert_eq!(section, "");
@@ -885,6 +1299,19 @@ mod tests {
                              "src/ec/suite_b/ops/p384_point_mul_base_tests.txt");
     }
 
+    #[test]
+    fn secp256k1_point_mul_base_test() {
+        point_mul_base_tests(
+            &secp256k1::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/secp256k1_point_mul_base_tests.txt");
+    }
+
+    #[test]
+    fn sm2_point_mul_base_test() {
+        point_mul_base_tests(&sm2::PRIVATE_KEY_OPS,
+                             "src/ec/suite_b/ops/sm2_point_mul_base_tests.txt");
+    }
+
     fn point_mul_base_tests(ops: &PrivateKeyOps, file_path: &str) {
         test::from_file(file_path, |section, test_case| {
             assert_eq!(section, "");
@@ -897,6 +1324,122 @@ mod tests {
         })
     }
 
+    #[test]
+    fn p256_base_point_table_test() {
+        // P-256's `point_mul_base` already uses a precomputed table.
+        assert!(p256::PRIVATE_KEY_OPS.base_point_table().is_none());
+    }
+
+    #[test]
+    fn p256_point_mul_using_table_test() {
+        point_mul_using_table_tests(
+            &p256::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/p256_point_mul_tests.txt");
+    }
+
+    #[test]
+    fn p384_point_mul_using_table_test() {
+        point_mul_using_table_tests(
+            &p384::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/p384_point_mul_tests.txt");
+    }
+
+    #[test]
+    fn secp256k1_point_mul_using_table_test() {
+        point_mul_using_table_tests(
+            &secp256k1::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/secp256k1_point_mul_tests.txt");
+    }
+
+    #[test]
+    fn sm2_point_mul_using_table_test() {
+        point_mul_using_table_tests(
+            &sm2::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/sm2_point_mul_tests.txt");
+    }
+
+    fn point_mul_using_table_tests(ops: &PrivateKeyOps, file_path: &str) {
+        test::from_file(file_path, |section, test_case| {
+            assert_eq!(section, "");
+            let p_scalar = consume_scalar(ops.common, test_case, "p_scalar");
+            let p = match consume_point(ops, test_case, "p") {
+                TestPoint::Infinity => {
+                    panic!("can't be inf.");
+                },
+                TestPoint::Affine(x, y) => (x, y),
+            };
+            let expected_result = consume_point(ops, test_case, "r");
+            let table = ops.point_table(&p);
+            let actual_result = ops.point_mul_using_table(&table, &p_scalar);
+            assert_point_actual_equals_expected(ops, &actual_result,
+                                                &expected_result);
+
+            // p_scalar*P + p_scalar*G, with the combs interleaved, is the
+            // same as the sum of the separate products.
+            let g_table = ops.point_table_(ops.point_mul_base(&Scalar::one()));
+            let actual_result =
+                ops.twin_mul_using_tables(&table, &p_scalar, &g_table,
+                                          &p_scalar);
+            let expected_result =
+                ops.common.point_sum(&ops.point_mul(&p_scalar, &p),
+                                     &ops.point_mul_base(&p_scalar));
+            assert_points_are_equal(ops, &actual_result, &expected_result);
+            Ok(())
+        })
+    }
+
+    fn assert_points_are_equal(ops: &PrivateKeyOps, a: &Point, b: &Point) {
+        let a_is_inf =
+            ops.common.elem_verify_is_not_zero(&ops.common.point_z(a)).is_err();
+        let b_is_inf =
+            ops.common.elem_verify_is_not_zero(&ops.common.point_z(b)).is_err();
+        assert_eq!(a_is_inf, b_is_inf);
+        if a_is_inf {
+            return;
+        }
+        let (a_x, a_y) = affine_from_jacobian(ops, a).unwrap();
+        let (b_x, b_y) = affine_from_jacobian(ops, b).unwrap();
+        let cops = ops.common;
+        assert!(cops.elems_are_equal(&cops.elem_reduced(&a_x),
+                                     &cops.elem_reduced(&b_x)));
+        assert!(cops.elems_are_equal(&cops.elem_reduced(&a_y),
+                                     &cops.elem_reduced(&b_y)));
+    }
+
+    #[test]
+    fn p384_point_mul_base_using_table_test() {
+        point_mul_base_using_table_tests(
+            &p384::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/p384_point_mul_base_tests.txt");
+    }
+
+    #[test]
+    fn secp256k1_point_mul_base_using_table_test() {
+        point_mul_base_using_table_tests(
+            &secp256k1::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/secp256k1_point_mul_base_tests.txt");
+    }
+
+    #[test]
+    fn sm2_point_mul_base_using_table_test() {
+        point_mul_base_using_table_tests(
+            &sm2::PRIVATE_KEY_OPS,
+            "src/ec/suite_b/ops/sm2_point_mul_base_tests.txt");
+    }
+
+    fn point_mul_base_using_table_tests(ops: &PrivateKeyOps, file_path: &str) {
+        let table = ops.base_point_table().unwrap();
+        test::from_file(file_path, |section, test_case| {
+            assert_eq!(section, "");
+            let g_scalar = consume_scalar(ops.common, test_case, "g_scalar");
+            let expected_result = consume_point(ops, test_case, "r");
+            let actual_result = ops.point_mul_using_table(&table, &g_scalar);
+            assert_point_actual_equals_expected(ops, &actual_result,
+                                                &expected_result);
+            Ok(())
+        })
+    }
+
     fn assert_point_actual_equals_expected(ops: &PrivateKeyOps,
                                            actual_point: &Point,
                                            expected_point: &TestPoint) {
@@ -1099,3 +1642,5 @@ macro_rules! bench_curve {
 
 pub mod p256;
 pub mod p384;
+pub mod secp256k1;
+pub mod sm2;
@@ -63,6 +63,8 @@ pub static PRIVATE_KEY_OPS: PrivateKeyOps = PrivateKeyOps {
     elem_inv: p256_elem_inv,
     point_mul_base_impl: p256_point_mul_base_impl,
     point_mul_impl: GFp_nistz256_point_mul,
+    point_double_impl: GFp_nistz256_point_double,
+    point_mul_base_uses_table: true,
 };
 
 fn p256_elem_inv(a: &ElemUnreduced) -> ElemUnreduced {
@@ -142,6 +144,16 @@ pub static PUBLIC_SCALAR_OPS: PublicScalarOps = PublicScalarOps {
     scalar_mul_mont: GFp_p256_scalar_mul_mont,
 };
 
+pub static PRIVATE_SCALAR_OPS: PrivateScalarOps = PrivateScalarOps {
+    scalar_ops: &PUBLIC_SCALAR_OPS,
+    n_rr: N_RR,
+};
+
+// R**2 (mod n).
+const N_RR: [Limb; MAX_LIMBS] =
+    p256_limbs![0x66e12d94, 0xf3d95620, 0x2845b239, 0x2b6bec59,
+                0x4699799c, 0x49bd6fa6, 0x83244c95, 0xbe79eea2];
+
 fn p256_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
     // Calculate the modular inverse of scalar |a| using Fermat's Little
     // Theorem:
@@ -185,9 +197,6 @@ fn p256_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
     }
 
     fn to_mont(a: &Scalar) -> ScalarMont {
-        static N_RR: [Limb; MAX_LIMBS] =
-            p256_limbs![0x66e12d94, 0xf3d95620, 0x2845b239, 0x2b6bec59,
-                        0x4699799c, 0x49bd6fa6, 0x83244c95, 0xbe79eea2];
         ScalarMont { limbs: rab(GFp_p256_scalar_mul_mont, &a.limbs, &N_RR) }
     }
 
@@ -282,6 +291,8 @@ extern {
     fn GFp_nistz256_point_add(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
                               a: *const Limb/*[3][COMMON_OPS.num_limbs]*/,
                               b: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_nistz256_point_double(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                                 a: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
     fn GFp_nistz256_point_mul(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
                               p_scalar: *const Limb/*[COMMON_OPS.num_limbs]*/,
                               p_x: *const Limb/*[COMMON_OPS.num_limbs]*/,
@@ -67,6 +67,8 @@ pub static PRIVATE_KEY_OPS: PrivateKeyOps = PrivateKeyOps {
     elem_inv: p384_elem_inv,
     point_mul_base_impl: p384_point_mul_base_impl,
     point_mul_impl: GFp_nistz384_point_mul,
+    point_double_impl: GFp_nistz384_point_double,
+    point_mul_base_uses_table: false,
 };
 
 fn p384_elem_inv(a: &ElemUnreduced) -> ElemUnreduced {
@@ -138,6 +140,12 @@ fn p384_elem_inv(a: &ElemUnreduced) -> ElemUnreduced {
 
 fn p384_point_mul_base_impl(a: &Scalar) -> Point {
     // XXX: Not efficient. TODO: Precompute multiples of the generator.
+    //
+    // `GFp_nistz384_point_mul` is constant-time with respect to the scalar:
+    // it uses a fixed sequence of doublings and additions, with each window
+    // of the (Booth-recoded) scalar selecting its table entry using masking
+    // instead of indexing. This is required since `a` is an ECDH private key
+    // or an ECDSA private key or nonce.
     static P384_GENERATOR: (Elem, Elem) = (
         Elem {
             limbs: p384_limbs![0x4d3aadc2, 0x299e1513, 0x812ff723, 0x614ede2b,
@@ -171,6 +179,17 @@ pub static PUBLIC_SCALAR_OPS: PublicScalarOps = PublicScalarOps {
     scalar_mul_mont: GFp_p384_scalar_mul_mont,
 };
 
+pub static PRIVATE_SCALAR_OPS: PrivateScalarOps = PrivateScalarOps {
+    scalar_ops: &PUBLIC_SCALAR_OPS,
+    n_rr: N_RR,
+};
+
+// R**2 (mod n).
+const N_RR: [Limb; MAX_LIMBS] =
+    p384_limbs![0x0c84ee01, 0x2b39bf21, 0x3fb05b7a, 0x28266895,
+                0xd40d4917, 0x4aab1cc5, 0xbc3e483a, 0xfcb82947,
+                0xff3d81e5, 0xdf1aa419, 0x2d319b24, 0x19b409a9];
+
 fn p384_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
     // Calculate the modular inverse of scalar |a| using Fermat's Little
     // Theorem:
@@ -219,10 +238,6 @@ fn p384_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
     }
 
     fn to_mont(a: &Scalar) -> ScalarMont {
-        static N_RR: [Limb; MAX_LIMBS] =
-            p384_limbs![0x0c84ee01, 0x2b39bf21, 0x3fb05b7a, 0x28266895,
-                        0xd40d4917, 0x4aab1cc5, 0xbc3e483a, 0xfcb82947,
-                        0xff3d81e5, 0xdf1aa419, 0x2d319b24, 0x19b409a9];
         ScalarMont { limbs: rab(GFp_p384_scalar_mul_mont, &a.limbs, &N_RR) }
     }
 
@@ -322,15 +337,6 @@ fn p384_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
 }
 
 
-#[allow(non_snake_case)]
-unsafe extern fn GFp_p384_elem_sqr_mont(
-        r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
-        a: *const Limb/*[COMMON_OPS.num_limbs]*/) {
-    // XXX: Inefficient. TODO: Make a dedicated squaring routine.
-    GFp_p384_elem_mul_mont(r, a, a);
-}
-
-
 extern {
     fn GFp_p384_elem_add(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
                          a: *const Limb/*[COMMON_OPS.num_limbs]*/,
@@ -338,10 +344,14 @@ extern {
     fn GFp_p384_elem_mul_mont(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
                               a: *const Limb/*[COMMON_OPS.num_limbs]*/,
                               b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+    fn GFp_p384_elem_sqr_mont(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                              a: *const Limb/*[COMMON_OPS.num_limbs]*/);
 
     fn GFp_nistz384_point_add(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
                               a: *const Limb/*[3][COMMON_OPS.num_limbs]*/,
                               b: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_nistz384_point_double(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                                 a: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
     fn GFp_nistz384_point_mul(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
                               p_scalar: *const Limb/*[COMMON_OPS.num_limbs]*/,
                               p_x: *const Limb/*[COMMON_OPS.num_limbs]*/,
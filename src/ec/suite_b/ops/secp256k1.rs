@@ -0,0 +1,373 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The secp256k1 curve from [SEC 2: Recommended Elliptic Curve Domain
+//! Parameters], Section 2.4.1.
+//!
+//! secp256k1 isn't a Suite B curve, but it is a short Weierstrass curve of
+//! prime order over a ~256-bit prime field just like P-256, so it shares all
+//! the generic ECC code with P-256 and P-384. Unlike those curves, its `a`
+//! coefficient is zero, not -3.
+//!
+//! [SEC 2: Recommended Elliptic Curve Domain Parameters]:
+//!     http://www.secg.org/sec2-v2.pdf
+
+use super::*;
+use super::{elem_sqr_mul, elem_sqr_mul_acc, Mont, ab_assign, rab};
+
+
+macro_rules! secp256k1_limbs {
+    [$limb_7:expr, $limb_6:expr, $limb_5:expr, $limb_4:expr,
+     $limb_3:expr, $limb_2:expr, $limb_1:expr, $limb_0:expr] => {
+        limbs![0, 0, 0, 0,
+               $limb_7, $limb_6, $limb_5, $limb_4,
+               $limb_3, $limb_2, $limb_1, $limb_0]
+    };
+}
+
+
+pub static COMMON_OPS: CommonOps = CommonOps {
+    num_limbs: 256 / LIMB_BITS,
+
+    q: Mont {
+        p: secp256k1_limbs![0xffffffff, 0xffffffff, 0xffffffff, 0xffffffff,
+                            0xffffffff, 0xffffffff, 0xfffffffe, 0xfffffc2f],
+        rr: secp256k1_limbs![0x00000000, 0x00000000, 0x00000000, 0x00000000,
+                             0x00000000, 0x00000001, 0x000007a2, 0x000e90a1],
+    },
+
+    n: ElemDecoded {
+        limbs: secp256k1_limbs![0xffffffff, 0xffffffff, 0xffffffff,
+                                0xfffffffe, 0xbaaedce6, 0xaf48a03b,
+                                0xbfd25e8c, 0xd0364141],
+    },
+
+    a: ElemUnreduced {
+        limbs: secp256k1_limbs![0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    b: ElemUnreduced {
+        limbs: secp256k1_limbs![0x00000000, 0x00000000, 0x00000000,
+                                0x00000000, 0x00000000, 0x00000000,
+                                0x00000007, 0x00001ab7],
+    },
+
+    elem_add_impl: GFp_secp256k1_elem_add,
+    elem_mul_mont: GFp_secp256k1_elem_mul_mont,
+    elem_sqr_mont: GFp_secp256k1_elem_sqr_mont,
+
+    point_add_jacobian_impl: GFp_secp256k1_point_add,
+};
+
+
+pub static PRIVATE_KEY_OPS: PrivateKeyOps = PrivateKeyOps {
+    common: &COMMON_OPS,
+    elem_inv: secp256k1_elem_inv,
+    point_mul_base_impl: secp256k1_point_mul_base_impl,
+    point_mul_impl: GFp_secp256k1_point_mul,
+    point_double_impl: GFp_secp256k1_point_double,
+    point_mul_base_uses_table: false,
+};
+
+fn secp256k1_elem_inv(a: &ElemUnreduced) -> ElemUnreduced {
+    // Calculate the modular inverse of field element |a| using Fermat's Little
+    // Theorem:
+    //
+    //    a**-1 (mod q) == a**(q - 2) (mod q)
+    //
+    // The exponent (q - 2) is:
+    //
+    //    0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2d
+    //
+    // In binary, that is 223 ones, one zero, 22 ones, and then 0000101101.
+
+    #[inline]
+    fn sqr_mul(a: &ElemUnreduced, squarings: usize, b: &ElemUnreduced)
+               -> ElemUnreduced {
+        elem_sqr_mul(&COMMON_OPS, a, squarings, b)
+    }
+
+    #[inline]
+    fn sqr_mul_acc(a: &mut ElemUnreduced, squarings: usize, b: &ElemUnreduced) {
+        elem_sqr_mul_acc(&COMMON_OPS, a, squarings, b)
+    }
+
+    // `x_n` is `a` raised to a power that is `n` one bits in binary.
+    let x_1 = &a;
+    let x_2   = sqr_mul(x_1,   0 +  1, x_1);
+    let x_3   = sqr_mul(&x_2,  0 +  1, x_1);
+    let x_6   = sqr_mul(&x_3,  0 +  3, &x_3);
+    let x_9   = sqr_mul(&x_6,  0 +  3, &x_3);
+    let x_11  = sqr_mul(&x_9,  0 +  2, &x_2);
+    let x_22  = sqr_mul(&x_11, 0 + 11, &x_11);
+    let x_44  = sqr_mul(&x_22, 0 + 22, &x_22);
+    let x_88  = sqr_mul(&x_44, 0 + 44, &x_44);
+    let x_176 = sqr_mul(&x_88, 0 + 88, &x_88);
+    let x_220 = sqr_mul(&x_176, 0 + 44, &x_44);
+
+    // 223 ones.
+    let mut acc = sqr_mul(&x_220, 0 + 3, &x_3);
+
+    // 223 ones, 0, 22 ones.
+    sqr_mul_acc(&mut acc, 1 + 22, &x_22);
+
+    // ...00001
+    sqr_mul_acc(&mut acc, 4 + 1, x_1);
+
+    // ...00001011
+    sqr_mul_acc(&mut acc, 1 + 2, &x_2);
+
+    // ...0000101101
+    sqr_mul(&acc, 1 + 1, x_1)
+}
+
+
+fn secp256k1_point_mul_base_impl(a: &Scalar) -> Point {
+    // XXX: Not efficient. TODO: Precompute multiples of the generator.
+    //
+    // `GFp_secp256k1_point_mul` is constant-time with respect to the scalar,
+    // like `GFp_nistz384_point_mul`. This is required since `a` is an ECDSA
+    // private key or nonce.
+    static SECP256K1_GENERATOR: (Elem, Elem) = (
+        Elem {
+            limbs: secp256k1_limbs![0x9981e643, 0xe9089f48, 0x979f48c0,
+                                    0x33fd129c, 0x231e2953, 0x29bc66db,
+                                    0xd7362e5a, 0x487e2097]
+        },
+        Elem {
+            limbs: secp256k1_limbs![0xcf3f851f, 0xd4a582d6, 0x70b6b59a,
+                                    0xac19c136, 0x8dfc5d5d, 0x1f1dc64d,
+                                    0xb15ea6d2, 0xd3dbabe2]
+        }
+    );
+
+    PRIVATE_KEY_OPS.point_mul(a, &SECP256K1_GENERATOR)
+}
+
+
+pub static PUBLIC_KEY_OPS: PublicKeyOps = PublicKeyOps { common: &COMMON_OPS };
+
+
+pub static PUBLIC_SCALAR_OPS: PublicScalarOps = PublicScalarOps {
+    public_key_ops: &PUBLIC_KEY_OPS,
+    private_key_ops: &PRIVATE_KEY_OPS,
+
+    q_minus_n: ElemDecoded {
+        limbs: secp256k1_limbs![0, 0, 0, 0x00000001, 0x45512319, 0x50b75fc4,
+                                0x402da172, 0x2fc9baee],
+    },
+
+    scalar_inv_to_mont_impl: secp256k1_scalar_inv_to_mont,
+    scalar_mul_mont: GFp_secp256k1_scalar_mul_mont,
+};
+
+pub static PRIVATE_SCALAR_OPS: PrivateScalarOps = PrivateScalarOps {
+    scalar_ops: &PUBLIC_SCALAR_OPS,
+    n_rr: N_RR,
+};
+
+// R**2 (mod n).
+const N_RR: [Limb; MAX_LIMBS] =
+    secp256k1_limbs![0x9d671cd5, 0x81c69bc5, 0xe697f5e4, 0x5bcd07c6,
+                     0x741496c2, 0x0e7cf878, 0x896cf214, 0x67d7d140];
+
+fn secp256k1_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
+    // Calculate the modular inverse of scalar |a| using Fermat's Little
+    // Theorem:
+    //
+    //   a**-1 (mod n) == a**(n - 2) (mod n)
+    //
+    // The exponent (n - 2) is:
+    //
+    //     0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd036413f
+
+    // XXX(perf): This hasn't been optimized at all. TODO: optimize.
+
+    fn mul(a: &ScalarMont, b: &ScalarMont) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_secp256k1_scalar_mul_mont, &a.limbs, &b.limbs)
+        }
+    }
+
+    fn sqr(a: &ScalarMont) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_secp256k1_scalar_mul_mont, &a.limbs, &a.limbs)
+        }
+    }
+
+    fn sqr_mut(a: &mut ScalarMont) {
+        unsafe {
+            GFp_secp256k1_scalar_mul_mont(a.limbs.as_mut_ptr(),
+                                          a.limbs.as_ptr(), a.limbs.as_ptr())
+        }
+    }
+
+    // Returns (`a` squared `squarings` times) * `b`.
+    fn sqr_mul(a: &ScalarMont, squarings: usize, b: &ScalarMont) -> ScalarMont {
+        debug_assert!(squarings >= 1);
+        let mut tmp = sqr(a);
+        for _ in 1..squarings {
+            sqr_mut(&mut tmp);
+        }
+        mul(&tmp, b)
+    }
+
+    // Sets `acc` = (`acc` squared `squarings` times) * `b`.
+    fn sqr_mul_acc(acc: &mut ScalarMont, squarings: usize, b: &ScalarMont) {
+        debug_assert!(squarings >= 1);
+        for _ in 0..squarings {
+            sqr_mut(acc);
+        }
+        ab_assign(GFp_secp256k1_scalar_mul_mont, &mut acc.limbs, &b.limbs)
+    }
+
+    fn to_mont(a: &Scalar) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_secp256k1_scalar_mul_mont, &a.limbs, &N_RR)
+        }
+    }
+
+    // Indexes into `d`.
+    const B_1: usize = 0;
+    const B_10: usize = 1;
+    const B_11: usize = 2;
+    const B_101: usize = 3;
+    const B_111: usize = 4;
+    const B_1111: usize = 5;
+    const DIGIT_COUNT: usize = 6;
+
+    let mut d = [ScalarMont { limbs: [0; MAX_LIMBS] }; DIGIT_COUNT];
+
+    d[B_1]    = to_mont(a);
+    d[B_10]   = sqr    (&d[B_1]);
+    d[B_11]   = mul    (&d[B_10],         &d[B_1]);
+    d[B_101]  = sqr_mul(&d[B_10],  0 + 1, &d[B_1]);
+    d[B_111]  = mul    (&d[B_101],        &d[B_10]);
+    d[B_1111] = sqr_mul(&d[B_111], 0 + 1, &d[B_1]);
+
+    let ff       = sqr_mul(&d[B_1111], 0 +  4, &d[B_1111]);
+    let ffff     = sqr_mul(&ff,        0 +  8, &ff);
+    let ffffffff = sqr_mul(&ffff,      0 + 16, &ffff);
+
+    let ffffffffffffffff = sqr_mul(&ffffffff, 0 + 32, &ffffffff);
+
+    let ffffffffffffffffffffffff =
+        sqr_mul(&ffffffffffffffff, 0 + 32, &ffffffff);
+
+    // 112 ones.
+    let mut acc = sqr_mul(&ffffffffffffffffffffffff, 0 + 16, &ffff);
+
+    // 127 ones.
+    sqr_mul_acc(&mut acc, 0 + 8, &ff);
+    sqr_mul_acc(&mut acc, 0 + 4, &d[B_1111]);
+    sqr_mul_acc(&mut acc, 0 + 2, &d[B_11]);
+    sqr_mul_acc(&mut acc, 0 + 1, &d[B_1]);
+
+    // The rest of the exponent, in binary, is:
+    //
+    //    0
+    //    1011101010101110110111001110011010101111010010001010000000111011
+    //    1011111111010010010111101000110011010000001101100100000100111111
+
+    static REMAINING_WINDOWS: [(u8, u8); 32] = [
+        (1 + 3, B_101 as u8),
+        (    2, B_11 as u8),
+        (1 + 3, B_101 as u8),
+        (1 + 3, B_101 as u8),
+        (    2, B_11 as u8),
+        (1 + 2, B_11 as u8),
+        (1 + 3, B_111 as u8),
+        (2 + 3, B_111 as u8),
+        (2 + 2, B_11 as u8),
+        (1 + 3, B_101 as u8),
+        (1 + 4, B_1111 as u8),
+        (1 + 1, B_1 as u8),
+        (2 + 1, B_1 as u8),
+        (3 + 3, B_101 as u8),
+        (7 + 3, B_111 as u8),
+        (1 + 3, B_111 as u8),
+        (1 + 4, B_1111 as u8),
+        (    4, B_1111 as u8),
+        (1 + 1, B_1 as u8),
+        (2 + 1, B_1 as u8),
+        (2 + 3, B_101 as u8),
+        (    3, B_111 as u8),
+        (1 + 1, B_1 as u8),
+        (3 + 2, B_11 as u8),
+        (2 + 2, B_11 as u8),
+        (1 + 1, B_1 as u8),
+        (6 + 2, B_11 as u8),
+        (1 + 2, B_11 as u8),
+        (2 + 1, B_1 as u8),
+        (5 + 1, B_1 as u8),
+        (2 + 4, B_1111 as u8),
+        (    2, B_11 as u8),
+    ];
+
+    for &(squarings, digit) in &REMAINING_WINDOWS[..] {
+        sqr_mul_acc(&mut acc, squarings as usize, &d[digit as usize]);
+    }
+
+    acc
+}
+
+
+#[allow(non_snake_case)]
+unsafe extern fn GFp_secp256k1_elem_sqr_mont(
+        r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+        a: *const Limb/*[COMMON_OPS.num_limbs]*/) {
+    // XXX: Inefficient. TODO: Make a dedicated squaring routine.
+    GFp_secp256k1_elem_mul_mont(r, a, a);
+}
+
+
+extern {
+    fn GFp_secp256k1_elem_add(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                              a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                              b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+    fn GFp_secp256k1_elem_mul_mont(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                                   a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                                   b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+
+    fn GFp_secp256k1_point_add(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                               a: *const Limb/*[3][COMMON_OPS.num_limbs]*/,
+                               b: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_secp256k1_point_double(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                                  a: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_secp256k1_point_mul(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                               p_scalar: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                               p_x: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                               p_y: *const Limb/*[COMMON_OPS.num_limbs]*/);
+
+    fn GFp_secp256k1_scalar_mul_mont(
+            r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+            a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+            b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+}
+
+
+#[cfg(feature = "internal_benches")]
+mod internal_benches {
+    use super::*;
+    use super::super::internal_benches::*;
+
+    bench_curve!(&[
+        Scalar { limbs: LIMBS_1 },
+        Scalar { limbs: LIMBS_ALTERNATING_10, },
+        Scalar { // n - 1
+            limbs: secp256k1_limbs![0xffffffff, 0xffffffff, 0xffffffff,
+                                    0xfffffffe, 0xbaaedce6, 0xaf48a03b,
+                                    0xbfd25e8c, 0xd0364141 - 1],
+        },
+    ]);
+}
@@ -0,0 +1,359 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The SM2 curve, sm2p256v1, from [GB/T 32918.5-2017].
+//!
+//! The SM2 curve isn't a Suite B curve, but like P-256 it is a short
+//! Weierstrass curve of prime order over a ~256-bit prime field with `a`
+//! = -3, so it shares all the generic ECC code with P-256 and P-384.
+//!
+//! [GB/T 32918.5-2017]:
+//!     https://tools.ietf.org/html/draft-shen-sm2-ecdsa-02
+
+use super::*;
+use super::{elem_sqr_mul, elem_sqr_mul_acc, Mont, ab_assign, rab};
+
+
+macro_rules! sm2_limbs {
+    [$limb_7:expr, $limb_6:expr, $limb_5:expr, $limb_4:expr,
+     $limb_3:expr, $limb_2:expr, $limb_1:expr, $limb_0:expr] => {
+        limbs![0, 0, 0, 0,
+               $limb_7, $limb_6, $limb_5, $limb_4,
+               $limb_3, $limb_2, $limb_1, $limb_0]
+    };
+}
+
+
+pub static COMMON_OPS: CommonOps = CommonOps {
+    num_limbs: 256 / LIMB_BITS,
+
+    q: Mont {
+        p: sm2_limbs![0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff,
+                      0xffffffff, 0x00000000, 0xffffffff, 0xffffffff],
+        rr: sm2_limbs![0x00000004, 0x00000002, 0x00000001, 0x00000001,
+                       0x00000002, 0xffffffff, 0x00000002, 0x00000003],
+    },
+
+    n: ElemDecoded {
+        limbs: sm2_limbs![0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff,
+                          0x7203df6b, 0x21c6052b, 0x53bbf409, 0x39d54123],
+    },
+
+    a: ElemUnreduced {
+        limbs: sm2_limbs![0xfffffffb, 0xffffffff, 0xffffffff, 0xffffffff,
+                          0xfffffffc, 0x00000003, 0xffffffff, 0xfffffffc],
+    },
+    b: ElemUnreduced {
+        limbs: sm2_limbs![0x240fe188, 0xba20e2c8, 0x52798150, 0x5ea51c3c,
+                          0x71cf379a, 0xe9b537ab, 0x90d23063, 0x2bc0dd42],
+    },
+
+    elem_add_impl: GFp_sm2_elem_add,
+    elem_mul_mont: GFp_sm2_elem_mul_mont,
+    elem_sqr_mont: GFp_sm2_elem_sqr_mont,
+
+    point_add_jacobian_impl: GFp_sm2_point_add,
+};
+
+
+pub static PRIVATE_KEY_OPS: PrivateKeyOps = PrivateKeyOps {
+    common: &COMMON_OPS,
+    elem_inv: sm2_elem_inv,
+    point_mul_base_impl: sm2_point_mul_base_impl,
+    point_mul_impl: GFp_sm2_point_mul,
+    point_double_impl: GFp_sm2_point_double,
+    point_mul_base_uses_table: false,
+};
+
+fn sm2_elem_inv(a: &ElemUnreduced) -> ElemUnreduced {
+    // Calculate the modular inverse of field element |a| using Fermat's Little
+    // Theorem:
+    //
+    //    a**-1 (mod q) == a**(q - 2) (mod q)
+    //
+    // The exponent (q - 2) is:
+    //
+    //    0xfffffffeffffffffffffffffffffffffffffffff00000000fffffffffffffffd
+    //
+    // In binary, that is 31 ones, one zero, 128 ones, 32 zeros, 62 ones, one
+    // zero, and then a one.
+
+    #[inline]
+    fn sqr_mul(a: &ElemUnreduced, squarings: usize, b: &ElemUnreduced)
+               -> ElemUnreduced {
+        elem_sqr_mul(&COMMON_OPS, a, squarings, b)
+    }
+
+    #[inline]
+    fn sqr_mul_acc(a: &mut ElemUnreduced, squarings: usize, b: &ElemUnreduced) {
+        elem_sqr_mul_acc(&COMMON_OPS, a, squarings, b)
+    }
+
+    // `x_n` is `a` raised to a power that is `n` one bits in binary.
+    let x_1 = &a;
+    let x_2  = sqr_mul(x_1,   0 +  1, x_1);
+    let x_3  = sqr_mul(&x_2,  0 +  1, x_1);
+    let x_6  = sqr_mul(&x_3,  0 +  3, &x_3);
+    let x_12 = sqr_mul(&x_6,  0 +  6, &x_6);
+    let x_15 = sqr_mul(&x_12, 0 +  3, &x_3);
+    let x_30 = sqr_mul(&x_15, 0 + 15, &x_15);
+    let x_31 = sqr_mul(&x_30, 0 +  1, x_1);
+    let x_32 = sqr_mul(&x_31, 0 +  1, x_1);
+
+    // 31 ones.
+    let mut acc = x_31;
+
+    // 31 ones, 0, 128 ones.
+    sqr_mul_acc(&mut acc, 1 + 32, &x_32);
+    sqr_mul_acc(&mut acc, 0 + 32, &x_32);
+    sqr_mul_acc(&mut acc, 0 + 32, &x_32);
+    sqr_mul_acc(&mut acc, 0 + 32, &x_32);
+
+    // ...32 zeros, 62 ones.
+    sqr_mul_acc(&mut acc, 32 + 32, &x_32);
+    sqr_mul_acc(&mut acc, 0 + 30, &x_30);
+
+    // ...01
+    sqr_mul(&acc, 1 + 1, x_1)
+}
+
+
+fn sm2_point_mul_base_impl(a: &Scalar) -> Point {
+    // XXX: Not efficient. TODO: Precompute multiples of the generator.
+    //
+    // `GFp_sm2_point_mul` is constant-time with respect to the scalar, like
+    // `GFp_nistz384_point_mul`. This is required since `a` is a private key
+    // or nonce.
+    static SM2_GENERATOR: (Elem, Elem) = (
+        Elem {
+            limbs: sm2_limbs![0x91167a5e, 0xe1c13b05, 0xd6a1ed99, 0xac24c3c3,
+                              0x3e7981ed, 0xdca6c050, 0x61328990, 0xf418029e]
+        },
+        Elem {
+            limbs: sm2_limbs![0x63cd65d4, 0x81d735bd, 0x8d4cfb06, 0x6e2a48f8,
+                              0xc1f5e578, 0x8d3295fa, 0xc1354e59, 0x3c2d0ddd]
+        }
+    );
+
+    PRIVATE_KEY_OPS.point_mul(a, &SM2_GENERATOR)
+}
+
+
+pub static PUBLIC_KEY_OPS: PublicKeyOps = PublicKeyOps { common: &COMMON_OPS };
+
+
+pub static PUBLIC_SCALAR_OPS: PublicScalarOps = PublicScalarOps {
+    public_key_ops: &PUBLIC_KEY_OPS,
+    private_key_ops: &PRIVATE_KEY_OPS,
+
+    q_minus_n: ElemDecoded {
+        limbs: sm2_limbs![0, 0, 0, 0, 0x8dfc2093, 0xde39fad5, 0xac440bf6,
+                          0xc62abedc],
+    },
+
+    scalar_inv_to_mont_impl: sm2_scalar_inv_to_mont,
+    scalar_mul_mont: GFp_sm2_scalar_mul_mont,
+};
+
+pub static PRIVATE_SCALAR_OPS: PrivateScalarOps = PrivateScalarOps {
+    scalar_ops: &PUBLIC_SCALAR_OPS,
+    n_rr: N_RR,
+};
+
+// R**2 (mod n).
+const N_RR: [Limb; MAX_LIMBS] =
+    sm2_limbs![0x1eb5e412, 0xa22b3d3b, 0x620fc84c, 0x3affe0d4,
+               0x3464504a, 0xde6fa2fa, 0x901192af, 0x7c114f20];
+
+fn sm2_scalar_inv_to_mont(a: &Scalar) -> ScalarMont {
+    // Calculate the modular inverse of scalar |a| using Fermat's Little
+    // Theorem:
+    //
+    //   a**-1 (mod n) == a**(n - 2) (mod n)
+    //
+    // The exponent (n - 2) is:
+    //
+    //     0xfffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54121
+
+    // XXX(perf): This hasn't been optimized at all. TODO: optimize.
+
+    fn mul(a: &ScalarMont, b: &ScalarMont) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_sm2_scalar_mul_mont, &a.limbs, &b.limbs)
+        }
+    }
+
+    fn sqr(a: &ScalarMont) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_sm2_scalar_mul_mont, &a.limbs, &a.limbs)
+        }
+    }
+
+    fn sqr_mut(a: &mut ScalarMont) {
+        unsafe {
+            GFp_sm2_scalar_mul_mont(a.limbs.as_mut_ptr(), a.limbs.as_ptr(),
+                                    a.limbs.as_ptr())
+        }
+    }
+
+    // Returns (`a` squared `squarings` times) * `b`.
+    fn sqr_mul(a: &ScalarMont, squarings: usize, b: &ScalarMont) -> ScalarMont {
+        debug_assert!(squarings >= 1);
+        let mut tmp = sqr(a);
+        for _ in 1..squarings {
+            sqr_mut(&mut tmp);
+        }
+        mul(&tmp, b)
+    }
+
+    // Sets `acc` = (`acc` squared `squarings` times) * `b`.
+    fn sqr_mul_acc(acc: &mut ScalarMont, squarings: usize, b: &ScalarMont) {
+        debug_assert!(squarings >= 1);
+        for _ in 0..squarings {
+            sqr_mut(acc);
+        }
+        ab_assign(GFp_sm2_scalar_mul_mont, &mut acc.limbs, &b.limbs)
+    }
+
+    fn to_mont(a: &Scalar) -> ScalarMont {
+        ScalarMont {
+            limbs: rab(GFp_sm2_scalar_mul_mont, &a.limbs, &N_RR)
+        }
+    }
+
+    // Indexes into `d`.
+    const B_1: usize = 0;
+    const B_10: usize = 1;
+    const B_11: usize = 2;
+    const B_101: usize = 3;
+    const B_111: usize = 4;
+    const B_1111: usize = 5;
+    const DIGIT_COUNT: usize = 6;
+
+    let mut d = [ScalarMont { limbs: [0; MAX_LIMBS] }; DIGIT_COUNT];
+
+    d[B_1]    = to_mont(a);
+    d[B_10]   = sqr    (&d[B_1]);
+    d[B_11]   = mul    (&d[B_10],         &d[B_1]);
+    d[B_101]  = sqr_mul(&d[B_10],  0 + 1, &d[B_1]);
+    d[B_111]  = mul    (&d[B_101],        &d[B_10]);
+    d[B_1111] = sqr_mul(&d[B_111], 0 + 1, &d[B_1]);
+
+    let ff       = sqr_mul(&d[B_1111], 0 +  4, &d[B_1111]);
+    let ffff     = sqr_mul(&ff,        0 +  8, &ff);
+    let ffffffff = sqr_mul(&ffff,      0 + 16, &ffff);
+
+    // 31 ones.
+    let mut acc = sqr_mul(&ffff, 0 + 8, &ff);
+    sqr_mul_acc(&mut acc, 0 + 4, &d[B_1111]);
+    sqr_mul_acc(&mut acc, 0 + 2, &d[B_11]);
+    sqr_mul_acc(&mut acc, 0 + 1, &d[B_1]);
+
+    // 31 ones, 0, 96 ones.
+    sqr_mul_acc(&mut acc, 1 + 32, &ffffffff);
+    sqr_mul_acc(&mut acc, 0 + 32, &ffffffff);
+    sqr_mul_acc(&mut acc, 0 + 32, &ffffffff);
+
+    // The rest of the exponent, in binary, is:
+    //
+    //    0111001000000011110111110110101100100001110001100000010100101011
+    //    0101001110111011111101000000100100111001110101010100000100100001
+
+    static REMAINING_WINDOWS: [(u8, u8); 28] = [
+        (1 + 3, B_111 as u8),
+        (2 + 1, B_1 as u8),
+        (7 + 4, B_1111 as u8),
+        (1 + 4, B_1111 as u8),
+        (    3, B_101 as u8),
+        (    3, B_101 as u8),
+        (1 + 2, B_11 as u8),
+        (2 + 1, B_1 as u8),
+        (4 + 3, B_111 as u8),
+        (3 + 2, B_11 as u8),
+        (6 + 3, B_101 as u8),
+        (2 + 3, B_101 as u8),
+        (1 + 2, B_11 as u8),
+        (1 + 3, B_101 as u8),
+        (2 + 3, B_111 as u8),
+        (1 + 3, B_111 as u8),
+        (1 + 4, B_1111 as u8),
+        (    2, B_11 as u8),
+        (1 + 1, B_1 as u8),
+        (6 + 1, B_1 as u8),
+        (2 + 1, B_1 as u8),
+        (2 + 3, B_111 as u8),
+        (2 + 3, B_111 as u8),
+        (1 + 3, B_101 as u8),
+        (1 + 3, B_101 as u8),
+        (5 + 1, B_1 as u8),
+        (2 + 1, B_1 as u8),
+        (4 + 1, B_1 as u8),
+    ];
+
+    for &(squarings, digit) in &REMAINING_WINDOWS[..] {
+        sqr_mul_acc(&mut acc, squarings as usize, &d[digit as usize]);
+    }
+
+    acc
+}
+
+
+#[allow(non_snake_case)]
+unsafe extern fn GFp_sm2_elem_sqr_mont(
+        r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+        a: *const Limb/*[COMMON_OPS.num_limbs]*/) {
+    // XXX: Inefficient. TODO: Make a dedicated squaring routine.
+    GFp_sm2_elem_mul_mont(r, a, a);
+}
+
+
+extern {
+    fn GFp_sm2_elem_add(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                        a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                        b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+    fn GFp_sm2_elem_mul_mont(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                             a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                             b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+
+    fn GFp_sm2_point_add(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                         a: *const Limb/*[3][COMMON_OPS.num_limbs]*/,
+                         b: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_sm2_point_double(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                            a: *const Limb/*[3][COMMON_OPS.num_limbs]*/);
+    fn GFp_sm2_point_mul(r: *mut Limb/*[3][COMMON_OPS.num_limbs]*/,
+                         p_scalar: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                         p_x: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                         p_y: *const Limb/*[COMMON_OPS.num_limbs]*/);
+
+    fn GFp_sm2_scalar_mul_mont(r: *mut Limb/*[COMMON_OPS.num_limbs]*/,
+                               a: *const Limb/*[COMMON_OPS.num_limbs]*/,
+                               b: *const Limb/*[COMMON_OPS.num_limbs]*/);
+}
+
+
+#[cfg(feature = "internal_benches")]
+mod internal_benches {
+    use super::*;
+    use super::super::internal_benches::*;
+
+    bench_curve!(&[
+        Scalar { limbs: LIMBS_1 },
+        Scalar { limbs: LIMBS_ALTERNATING_10, },
+        Scalar { // n - 1
+            limbs: sm2_limbs![0xfffffffe, 0xffffffff, 0xffffffff, 0xffffffff,
+                              0x7203df6b, 0x21c6052b, 0x53bbf409,
+                              0x39d54123 - 1],
+        },
+    ]);
+}
@@ -18,6 +18,7 @@
 use {ec, error, rand};
 use super::ops::*;
 use super::verify_affine_point_is_on_the_curve;
+use untrusted;
 
 pub fn generate_private_key(ops: &PrivateKeyOps, rng: &rand::SecureRandom)
                             -> Result<ec::PrivateKey, error::Unspecified> {
@@ -145,10 +146,47 @@ pub fn public_from_private(ops: &PrivateKeyOps, public_out: &mut [u8],
                                     &my_public_key)
 }
 
+/// Parses a private key encoded as a fixed-length, big-endian-encoded
+/// integer in the range [1, n).
+pub fn private_key_from_bytes(ops: &PrivateKeyOps, bytes: untrusted::Input)
+                              -> Result<ec::PrivateKey, error::Unspecified> {
+    let num_limbs = ops.common.num_limbs;
+    let num_bytes = num_limbs * LIMB_BYTES;
+    if bytes.len() != num_bytes {
+        return Err(error::Unspecified);
+    }
+    let mut private_key = ec::PrivateKey { bytes: [0; ec::SCALAR_MAX_BYTES] };
+    private_key.bytes[..num_bytes].copy_from_slice(bytes.as_slice_less_safe());
+    let scalar = private_key_as_scalar_(ops, &private_key);
+    if !is_scalar_within_range(&scalar, &ops.common.n.limbs[..num_limbs]) {
+        return Err(error::Unspecified);
+    }
+    Ok(private_key)
+}
+
 pub fn big_endian_affine_from_jacobian(ops: &PrivateKeyOps,
                                        x_out: Option<&mut [u8]>,
                                        y_out: Option<&mut [u8]>, p: &Point)
                                        -> Result<(), error::Unspecified> {
+    let (x_aff, y_aff) = try!(affine_from_jacobian(ops, p));
+    let num_limbs = ops.common.num_limbs;
+    if let Some(x_out) = x_out {
+        let x_decoded = ops.common.elem_decoded(&x_aff);
+        big_endian_from_limbs(x_out, &x_decoded.limbs[..num_limbs]);
+    }
+    if let Some(y_out) = y_out {
+        let y_decoded = ops.common.elem_decoded(&y_aff);
+        big_endian_from_limbs(y_out, &y_decoded.limbs[..num_limbs]);
+    }
+
+    Ok(())
+}
+
+/// Converts `p` from Jacobian coordinates to (Montgomery-encoded) affine
+/// coordinates, verifying that the result is on the curve.
+pub fn affine_from_jacobian(ops: &PrivateKeyOps, p: &Point)
+                            -> Result<(ElemUnreduced, ElemUnreduced),
+                                      error::Unspecified> {
     let z = ops.common.point_z(p);
 
     // Since we restrict our private key to the range [1, n), the curve has
@@ -172,20 +210,10 @@ pub fn big_endian_affine_from_jacobian(ops: &PrivateKeyOps,
     // `verify_affine_point_is_on_the_curve_scaled` for the motivation.
     try!(verify_affine_point_is_on_the_curve(ops.common, (&x_aff, &y_aff)));
 
-    let num_limbs = ops.common.num_limbs;
-    if let Some(x_out) = x_out {
-        let x_decoded = ops.common.elem_decoded(&x_aff);
-        big_endian_from_limbs(x_out, &x_decoded.limbs[..num_limbs]);
-    }
-    if let Some(y_out) = y_out {
-        let y_decoded = ops.common.elem_decoded(&y_aff);
-        big_endian_from_limbs(y_out, &y_decoded.limbs[..num_limbs]);
-    }
-
-    Ok(())
+    Ok((x_aff, y_aff))
 }
 
-fn big_endian_from_limbs(out: &mut [u8], limbs: &[Limb]) {
+pub fn big_endian_from_limbs(out: &mut [u8], limbs: &[Limb]) {
     let num_limbs = limbs.len();
     debug_assert_eq!(out.len(), num_limbs * LIMB_BYTES);
     for i in 0..num_limbs {
@@ -197,12 +225,3 @@ fn big_endian_from_limbs(out: &mut [u8], limbs: &[Limb]) {
         }
     }
 }
-
-#[cfg(test)]
-pub mod test_util {
-    use super::super::ops::Limb;
-
-    pub fn big_endian_from_limbs(out: &mut [u8], limbs: &[Limb]) {
-        super::big_endian_from_limbs(out, limbs)
-    }
-}
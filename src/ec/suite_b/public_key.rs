@@ -20,37 +20,50 @@ use super::ops::*;
 use super::verify_affine_point_is_on_the_curve;
 use untrusted;
 
-/// Parses a public key encoded in uncompressed form. The key is validated
-/// using the ECC Partial Public-Key Validation Routine from
+/// Parses a public key encoded in uncompressed or compressed form, as
+/// described in [SEC 1] Section 2.3.4. The key is validated using the ECC
+/// Partial Public-Key Validation Routine from
 /// [NIST SP 800-56A, revision 2] Section 5.6.2.3.3, the NSA's
 /// "Suite B Implementer's Guide to NIST SP 800-56A," Appendix B.3, and the
 /// NSA's "Suite B Implementer's Guide to FIPS 186-3 (ECDSA)," Appendix A.3.
 ///
+/// [SEC 1]: http://www.secg.org/sec1-v2.pdf
 /// [NIST SP 800-56A, revision 2]:
 ///     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-56Ar2.pdf
-pub fn parse_uncompressed_point(ops: &PublicKeyOps, input: untrusted::Input)
-                                -> Result<(Elem, Elem), error::Unspecified> {
+pub fn parse_point(ops: &PublicKeyOps, input: untrusted::Input)
+                   -> Result<(Elem, Elem), error::Unspecified> {
     // NIST SP 800-56A Step 1: "Verify that Q is not the point at infinity.
     // This can be done by inspection if the point is entered in the standard
     // affine representation." (We do it by inspection since we only accept
     // the affine representation.)
     let (x, y) = try!(input.read_all(error::Unspecified, |input| {
-        // The encoding must be 4, which is the encoding for "uncompressed".
-        let encoding = try!(input.read_byte());
-        if encoding != 4 {
-            return Err(error::Unspecified);
-        }
-
         // NIST SP 800-56A Step 2: "Verify that xQ and yQ are integers in the
-        // interval [0, p-1] in the case that q is an odd prime p[.]"
-        let x = try!(ops.elem_parse(input));
-        let y = try!(ops.elem_parse(input));
-        Ok((x, y))
+        // interval [0, p-1] in the case that q is an odd prime p[.]" A
+        // decompressed yQ is always in that interval.
+        match try!(input.read_byte()) {
+            // Uncompressed.
+            4 => {
+                let x = try!(ops.elem_parse(input));
+                let y = try!(ops.elem_parse(input));
+                Ok((x, y))
+            },
+
+            // Compressed, with the parity of y in the low bit of the
+            // encoding.
+            encoding @ 2 | encoding @ 3 => {
+                let x = try!(ops.elem_parse(input));
+                let y = ops.elem_y_from_x_vartime(&x, encoding == 3);
+                Ok((x, y))
+            },
+
+            _ => Err(error::Unspecified),
+        }
     }));
 
     // NIST SP 800-56A Step 3: "If q is an odd prime p, verify that
     // yQ**2 = xQ**3 + axQ + b in GF(p), where the arithmetic is performed
-    // modulo p."
+    // modulo p." For a compressed point, this fails if xQ isn't the x
+    // coordinate of any point on the curve.
     let x_ = ElemUnreduced::from(&x);
     let y_ = ElemUnreduced::from(&y);
     try!(verify_affine_point_is_on_the_curve(ops.common, (&x_, &y_)));
@@ -75,7 +88,7 @@ mod tests {
     use untrusted;
 
     #[test]
-    fn parse_uncompressed_point_test() {
+    fn parse_point_test() {
         test::from_file("src/ec/suite_b/suite_b_public_key_tests.txt",
                         |section, test_case| {
             assert_eq!(section, "");
@@ -83,16 +96,30 @@ mod tests {
             let curve_name = test_case.consume_string("Curve");
 
             let public_key = test_case.consume_bytes("Q");
-            let public_key = untrusted::Input::from(&public_key);
             let valid = test_case.consume_string("Result") == "P";
 
             let curve_ops = public_key_ops_from_curve_name(&curve_name);
 
-            let result = parse_uncompressed_point(curve_ops, public_key);
+            let result =
+                parse_point(curve_ops, untrusted::Input::from(&public_key));
             assert_eq!(valid, result.is_ok());
 
-            // TODO: Verify that we when we re-serialize the parsed (x, y), the
-            // output is equal to the input.
+            // The compressed form of a valid uncompressed point decodes to
+            // the same point, and the other y parity gives its negation.
+            if let (Ok((x, y)), Some(&4)) = (result, public_key.first()) {
+                let elem_len = (public_key.len() - 1) / 2;
+                let y_is_odd = public_key[public_key.len() - 1] & 1 == 1;
+                let mut compressed = public_key[..(1 + elem_len)].to_vec();
+                for &(odd, same) in &[(y_is_odd, true), (!y_is_odd, false)] {
+                    compressed[0] = if odd { 3 } else { 2 };
+                    let (x_, y_) =
+                        parse_point(curve_ops,
+                                    untrusted::Input::from(&compressed))
+                            .unwrap();
+                    assert!(curve_ops.common.elems_are_equal(&x, &x_));
+                    assert_eq!(same, curve_ops.common.elems_are_equal(&y, &y_));
+                }
+            }
 
             Ok(())
         });
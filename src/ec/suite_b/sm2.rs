@@ -0,0 +1,533 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! SM2 signatures using SM3, as specified in [GB/T 32918.2-2016].
+//!
+//! [GB/T 32918.2-2016]:
+//!     https://tools.ietf.org/html/draft-shen-sm2-ecdsa-02
+
+use {digest, ec, error, init, private, rand, signature};
+use super::ecdsa::{digest_scalar_, format_rs_asn1, format_rs_fixed,
+                   split_rs_asn1, split_rs_fixed, twin_mul};
+use super::verify_jacobian_point_is_on_the_curve;
+use super::ops::*;
+use super::private_key::*;
+use super::public_key::*;
+use untrusted;
+
+/// Parameters for SM2 signing and verification.
+pub struct SM2Parameters {
+    ops: &'static PrivateScalarOps,
+    split_rs: fn(ops: &'static PublicScalarOps,
+                 input: &mut untrusted::Reader)
+                 -> Result<(Scalar, Scalar), error::Unspecified>,
+}
+
+impl signature::VerificationAlgorithm for SM2Parameters {
+    // Verify an SM2 signature as documented in GB/T 32918.2-2016 Section 7.1:
+    // Digital Signature Verification Algorithm.
+    fn verify(&self, public_key: untrusted::Input, msg: untrusted::Input,
+              signature: untrusted::Input) -> Result<(), error::Unspecified> {
+        let scalar_ops = self.ops.scalar_ops;
+        let cops = scalar_ops.public_key_ops.common;
+
+        let peer_pub_key =
+            try!(parse_point(scalar_ops.public_key_ops, public_key));
+
+        // Steps B1 and B2: "Verify whether r' ∈ [1, n-1] holds" and "verify
+        // whether s' ∈ [1, n-1] holds."
+        let (r, s) = try!(signature.read_all(error::Unspecified, |input| {
+            (self.split_rs)(scalar_ops, input)
+        }));
+
+        // Steps B3 and B4: Compute e' = SM3(Z_A || M') and convert it to an
+        // integer.
+        let (x, y) = public_key_coordinates(cops, &peer_pub_key);
+        let e = digest_scalar_with_za(scalar_ops, &x[..], &y[..],
+                                      msg.as_slice_less_safe());
+
+        // Step B5: "Calculate t = (r' + s') mod n, verification failed if
+        // t = 0."
+        let t = self.ops.scalar_sum(&r, &s);
+        if self.ops.scalar_is_zero(&t) {
+            return Err(error::Unspecified);
+        }
+
+        // Step B6: "Compute the elliptic curve point (x1', y1') = [s']G +
+        // [t]P_A."
+        let product = twin_mul(scalar_ops.private_key_ops, &s, &t,
+                               &peer_pub_key);
+
+        // See `ECDSAParameters::verify` for why this is done with Jacobian
+        // coordinates. This also rejects the point at infinity.
+        let z2 = try!(verify_jacobian_point_is_on_the_curve(cops, &product));
+
+        // Step B7: "Calculate R = (e' + x1') mod n, verification passes if
+        // R = r'."
+        //
+        // Instead, we check whether x1' = (r' - e') (mod n), which allows us
+        // to use Greg Maxwell's trick to avoid the inversion mod `q` that
+        // would be necessary to compute the affine X coordinate, as ECDSA
+        // verification does.
+        let x = cops.point_x(&product);
+        let x_decoded = cops.elem_decoded(&x);
+        let x_equals = |v: &ElemDecoded| {
+            let v_jacobian = cops.elem_mul_mixed(&z2, v);
+            scalar_ops.elem_decoded_equals(&v_jacobian, &x_decoded)
+        };
+        let v = self.ops.scalar_difference(&r, &e);
+        let v = scalar_ops.scalar_as_elem_decoded(&v);
+        if x_equals(&v) {
+            return Ok(());
+        }
+        if scalar_ops.elem_decoded_less_than(&v, &scalar_ops.q_minus_n) {
+            let v_plus_n = scalar_ops.elem_decoded_sum(&v, &cops.n);
+            if x_equals(&v_plus_n) {
+                return Ok(());
+            }
+        }
+
+        Err(error::Unspecified)
+    }
+}
+
+impl private::Private for SM2Parameters {}
+
+
+/// An SM2 signing algorithm.
+pub struct SM2SigningAlgorithm {
+    verification_alg: &'static SM2Parameters,
+    format_rs: fn(ops: &PublicScalarOps, r: &Scalar, s: &Scalar)
+                  -> signature::Signature,
+}
+
+impl SM2SigningAlgorithm {
+    #[inline]
+    fn private_key_ops(&self) -> &'static PrivateKeyOps {
+        self.verification_alg.ops.scalar_ops.private_key_ops
+    }
+
+    #[inline]
+    fn public_key_len(&self) -> usize {
+        1 + (2 * self.private_key_ops().common.num_limbs * LIMB_BYTES)
+    }
+}
+
+/// An SM2 key pair, for signing.
+pub struct SM2KeyPair {
+    alg: &'static SM2SigningAlgorithm,
+    private_key: ec::PrivateKey,
+    public_key: [u8; ec::PUBLIC_KEY_MAX_LEN],
+}
+
+impl<'a> SM2KeyPair {
+    /// Generates a new random key pair for the algorithm `alg`. There is no
+    /// way to extract the private key bytes to save them.
+    pub fn generate(alg: &'static SM2SigningAlgorithm,
+                    rng: &rand::SecureRandom)
+                    -> Result<SM2KeyPair, error::Unspecified> {
+        init::init_once();
+        let private_key =
+            try!(generate_private_key(alg.private_key_ops(), rng));
+        SM2KeyPair::new(alg, private_key)
+    }
+
+    /// Constructs an SM2 key pair for the algorithm `alg` from the bytes of
+    /// the private key and the public key.
+    ///
+    /// The private key must be encoded as a 32-byte big-endian integer in
+    /// the range [1, n - 1); SM2 doesn't allow the private key n - 1. The
+    /// public key must be encoded in uncompressed form.
+    ///
+    /// The private and public keys will be verified to be consistent, as
+    /// `ECDSAKeyPair::from_bytes()` does.
+    pub fn from_bytes(alg: &'static SM2SigningAlgorithm,
+                      private_key: untrusted::Input,
+                      public_key: untrusted::Input)
+                      -> Result<SM2KeyPair, error::Unspecified> {
+        init::init_once();
+        let private_key =
+            try!(private_key_from_bytes(alg.private_key_ops(), private_key));
+        let key_pair = try!(SM2KeyPair::new(alg, private_key));
+        if public_key.as_slice_less_safe() != key_pair.public_key_bytes() {
+            return Err(error::Unspecified);
+        }
+        Ok(key_pair)
+    }
+
+    fn new(alg: &'static SM2SigningAlgorithm, private_key: ec::PrivateKey)
+           -> Result<SM2KeyPair, error::Unspecified> {
+        // Signing needs the inverse of (1 + d), so d = n - 1 is not allowed.
+        let ops = alg.verification_alg.ops;
+        let d = private_key_as_scalar(alg.private_key_ops(), &private_key);
+        if ops.scalar_is_zero(&ops.scalar_sum(&d, &Scalar::one())) {
+            return Err(error::Unspecified);
+        }
+
+        let mut public_key = [0; ec::PUBLIC_KEY_MAX_LEN];
+        try!(public_from_private(alg.private_key_ops(),
+                                 &mut public_key[..alg.public_key_len()],
+                                 &private_key));
+        Ok(SM2KeyPair {
+            alg: alg,
+            private_key: private_key,
+            public_key: public_key,
+        })
+    }
+
+    /// Returns a reference to the public key, encoded in uncompressed form.
+    pub fn public_key_bytes(&'a self) -> &'a [u8] {
+        &self.public_key[..self.alg.public_key_len()]
+    }
+
+    /// Returns the signature of the message `msg`, encoded in the format of
+    /// the key pair's signing algorithm, using the default user ID.
+    ///
+    /// A new nonce is chosen for each signature, uniformly at random from
+    /// the range [1, n), by rejection sampling bytes from `rng`. Like ECDSA,
+    /// SM2 signing is catastrophically insecure if `rng` is not a secure
+    /// random number generator.
+    pub fn sign(&self, msg: &[u8], rng: &rand::SecureRandom)
+                -> Result<signature::Signature, error::Unspecified> {
+        // Generate an SM2 signature as documented in GB/T 32918.2-2016
+        // Section 6.1: Digital Signature Generation Algorithm.
+        let ops = self.alg.verification_alg.ops;
+        let scalar_ops = ops.scalar_ops;
+        let private_key_ops = scalar_ops.private_key_ops;
+
+        let d = private_key_as_scalar(private_key_ops, &self.private_key);
+
+        // (1 + d)**-1 doesn't depend on the nonce, so it is computed outside
+        // the loop. `new` ensured that 1 + d isn't zero.
+        let d_plus_one_inv =
+            scalar_ops.scalar_inv_to_mont(&ops.scalar_sum(&d, &Scalar::one()));
+        let d = ops.scalar_to_mont(&d);
+
+        // Steps A1 and A2: Compute e = SM3(Z_A || M) and convert it to an
+        // integer. This doesn't depend on the nonce either.
+        let elem_len = private_key_ops.common.num_limbs * LIMB_BYTES;
+        let (x, y) = self.public_key_bytes()[1..].split_at(elem_len);
+        let e = digest_scalar_with_za(scalar_ops, x, y, msg);
+
+        // XXX: The value 100 was chosen to match `generate_private_key()`.
+        for _ in 0..100 {
+            // Step A3: Generate the nonce `k` in [1, n) using the same
+            // procedure as is used for key pair generation.
+            let k = try!(generate_private_key(private_key_ops, rng));
+            let k = private_key_as_scalar(private_key_ops, &k);
+
+            // Step A4: Compute (x1, y1) = [k]G. `affine_from_jacobian` also
+            // verifies that the result is on the curve.
+            let p = private_key_ops.point_mul_base(&k);
+            let (x1, _) = try!(affine_from_jacobian(private_key_ops, &p));
+            let x1 = private_key_ops.common.elem_decoded(&x1);
+            let x1 = scalar_ops.scalar_from_unreduced_limbs(&x1.limbs);
+
+            // Step A5: Compute r = (e + x1) mod n. If r = 0 or r + k = n then
+            // choose another `k`.
+            let r = ops.scalar_sum(&e, &x1);
+            if ops.scalar_is_zero(&r) ||
+               ops.scalar_is_zero(&ops.scalar_sum(&r, &k)) {
+                continue;
+            }
+
+            // Step A6: Compute s = ((1 + d)**-1 * (k - r*d)) mod n. If s = 0
+            // then choose another `k`.
+            let rd = scalar_ops.scalar_mul_mixed(&r, &d);
+            let k_minus_rd = ops.scalar_difference(&k, &rd);
+            let s = scalar_ops.scalar_mul_mixed(&k_minus_rd, &d_plus_one_inv);
+            if ops.scalar_is_zero(&s) {
+                continue;
+            }
+
+            // Step A7: Return (r, s).
+            return Ok((self.alg.format_rs)(scalar_ops, &r, &s));
+        }
+
+        Err(error::Unspecified)
+    }
+}
+
+impl signature::KeyPair for SM2KeyPair {
+    /// The public key in uncompressed form; the same as
+    /// `public_key_bytes()`.
+    type PublicKey = [u8];
+
+    fn public_key(&self) -> &[u8] { self.public_key_bytes() }
+}
+
+impl signature::Signer for SM2KeyPair {
+    fn public_key_bytes(&self) -> &[u8] { self.public_key_bytes() }
+
+    fn max_signature_len(&self) -> usize { signature::MAX_LEN }
+
+    fn sign(&self, msg: &[u8], rng: &rand::SecureRandom, signature: &mut [u8])
+            -> Result<usize, error::Unspecified> {
+        try!(SM2KeyPair::sign(self, msg, rng)).write_to(signature)
+    }
+}
+
+// Returns the big-endian encodings of the affine coordinates of `p`.
+fn public_key_coordinates(cops: &CommonOps, p: &(Elem, Elem))
+                          -> ([u8; ec::ELEM_MAX_BYTES],
+                              [u8; ec::ELEM_MAX_BYTES]) {
+    let num_limbs = cops.num_limbs;
+    let elem_len = num_limbs * LIMB_BYTES;
+    let mut x_out = [0; ec::ELEM_MAX_BYTES];
+    let mut y_out = [0; ec::ELEM_MAX_BYTES];
+    let x = cops.elem_decoded(&ElemUnreduced::from(&p.0));
+    let y = cops.elem_decoded(&ElemUnreduced::from(&p.1));
+    big_endian_from_limbs(&mut x_out[..elem_len], &x.limbs[..num_limbs]);
+    big_endian_from_limbs(&mut y_out[..elem_len], &y.limbs[..num_limbs]);
+    (x_out, y_out)
+}
+
+// Computes e = SM3(Z_A || msg) as a scalar, where Z_A is the digest of the
+// default user ID, the curve parameters, and the public key (x, y); see
+// GB/T 32918.2-2016 Section 5.5. Only the first 32 bytes of `x` and `y` are
+// used.
+fn digest_scalar_with_za(ops: &PublicScalarOps, x: &[u8], y: &[u8],
+                         msg: &[u8]) -> Scalar {
+    let elem_len = ops.public_key_ops.common.num_limbs * LIMB_BYTES;
+    debug_assert_eq!(elem_len, 32);
+
+    let mut za = digest::Context::new(&digest::SM3);
+    za.update(&DEFAULT_ENTL_AND_ID);
+    za.update(&CURVE_PARAMETERS);
+    za.update(&x[..elem_len]);
+    za.update(&y[..elem_len]);
+    let za = za.finish();
+
+    let mut e = digest::Context::new(&digest::SM3);
+    e.update(za.as_ref());
+    e.update(msg);
+    digest_scalar_(ops, e.finish().as_ref())
+}
+
+// ENTL_A, the length of the user ID in bits as a 16-bit big-endian value,
+// followed by the default user ID "1234567812345678" from GM/T 0009-2012.
+const DEFAULT_ENTL_AND_ID: [u8; 2 + 16] = [
+    0x00, 0x80,
+    b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8',
+    b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8',
+];
+
+// a || b || x_G || y_G, each encoded as a 32-byte big-endian value.
+const CURVE_PARAMETERS: [u8; 4 * 32] = [
+    // a
+    0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfc,
+
+    // b
+    0x28, 0xe9, 0xfa, 0x9e, 0x9d, 0x9f, 0x5e, 0x34,
+    0x4d, 0x5a, 0x9e, 0x4b, 0xcf, 0x65, 0x09, 0xa7,
+    0xf3, 0x97, 0x89, 0xf5, 0x15, 0xab, 0x8f, 0x92,
+    0xdd, 0xbc, 0xbd, 0x41, 0x4d, 0x94, 0x0e, 0x93,
+
+    // x_G
+    0x32, 0xc4, 0xae, 0x2c, 0x1f, 0x19, 0x81, 0x19,
+    0x5f, 0x99, 0x04, 0x46, 0x6a, 0x39, 0xc9, 0x94,
+    0x8f, 0xe3, 0x0b, 0xbf, 0xf2, 0x66, 0x0b, 0xe1,
+    0x71, 0x5a, 0x45, 0x89, 0x33, 0x4c, 0x74, 0xc7,
+
+    // y_G
+    0xbc, 0x37, 0x36, 0xa2, 0xf4, 0xf6, 0x77, 0x9c,
+    0x59, 0xbd, 0xce, 0xe3, 0x6b, 0x69, 0x21, 0x53,
+    0xd0, 0xa9, 0x87, 0x7c, 0xc6, 0x2a, 0x47, 0x40,
+    0x02, 0xdf, 0x32, 0xe5, 0x21, 0x39, 0xf0, 0xa0,
+];
+
+
+/// Verification of ASN.1 DER-encoded SM2 signatures using SM3.
+///
+/// See "`SM2_SM3_*` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static SM2_SM3_ASN1: SM2Parameters = SM2Parameters {
+    ops: &sm2::PRIVATE_SCALAR_OPS,
+    split_rs: split_rs_asn1,
+};
+
+/// Verification of fixed-length SM2 signatures using SM3.
+///
+/// See "`SM2_SM3_*` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static SM2_SM3_FIXED: SM2Parameters = SM2Parameters {
+    ops: &sm2::PRIVATE_SCALAR_OPS,
+    split_rs: split_rs_fixed,
+};
+
+/// Signing of ASN.1 DER-encoded SM2 signatures using SM3.
+///
+/// See "`SM2_SM3_*` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static SM2_SM3_ASN1_SIGNING: SM2SigningAlgorithm = SM2SigningAlgorithm {
+    verification_alg: &SM2_SM3_ASN1,
+    format_rs: format_rs_asn1,
+};
+
+/// Signing of fixed-length SM2 signatures using SM3.
+///
+/// See "`SM2_SM3_*` Details" in `ring::signature`'s module-level
+/// documentation for more details.
+pub static SM2_SM3_FIXED_SIGNING: SM2SigningAlgorithm = SM2SigningAlgorithm {
+    verification_alg: &SM2_SM3_FIXED,
+    format_rs: format_rs_fixed,
+};
+
+
+#[cfg(test)]
+mod tests {
+    use {error, rand, test, signature};
+    use super::super::ecdsa::{format_rs_fixed, split_rs_asn1};
+    use super::super::ops::*;
+    use untrusted;
+
+    #[test]
+    fn signature_sm2_verify_test() {
+        test::from_file("src/ec/suite_b/sm2_verify_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let msg = test_case.consume_bytes("Msg");
+            let msg = untrusted::Input::from(&msg);
+            let public_key = test_case.consume_bytes("Q");
+            let public_key = untrusted::Input::from(&public_key);
+            let sig = test_case.consume_bytes("Sig");
+            let expected_result = test_case.consume_string("Result");
+
+            let actual_result =
+                signature::verify(&signature::SM2_SM3_ASN1, public_key, msg,
+                                  untrusted::Input::from(&sig));
+            assert_eq!(actual_result.is_ok(), expected_result == "P");
+
+            // Every valid signature is also valid in the fixed-length form.
+            if actual_result.is_ok() {
+                let fixed_sig = asn1_sig_to_fixed(&sig);
+                let fixed_sig = untrusted::Input::from(fixed_sig.as_slice());
+                assert!(signature::verify(&signature::SM2_SM3_FIXED,
+                                          public_key, msg, fixed_sig).is_ok());
+                assert!(signature::verify(&signature::SM2_SM3_ASN1,
+                                          public_key, msg, fixed_sig).is_err());
+            }
+
+            // SM2 signatures aren't ECDSA signatures, even though the
+            // encoding is the same.
+            assert!(signature::verify(&signature::ECDSA_P256_SHA256_ASN1,
+                                      public_key, msg,
+                                      untrusted::Input::from(&sig)).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_sm2_sign_test() {
+        test::from_file("src/ec/suite_b/sm2_sign_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let msg = test_case.consume_bytes("Msg");
+            let private_key = test_case.consume_bytes("d");
+            let public_key = test_case.consume_bytes("Q");
+            let k = test_case.consume_bytes("k");
+            let expected_sig = test_case.consume_bytes("Sig");
+
+            let key_pair = signature::SM2KeyPair::from_bytes(
+                &signature::SM2_SM3_ASN1_SIGNING,
+                untrusted::Input::from(&private_key),
+                untrusted::Input::from(&public_key)).unwrap();
+            assert_eq!(key_pair.public_key_bytes(), &public_key[..]);
+
+            let rng = test::rand::FixedSliceRandom { bytes: &k };
+            let actual_sig = key_pair.sign(&msg, &rng).unwrap();
+            assert_eq!(actual_sig.as_slice(), &expected_sig[..]);
+
+            assert!(signature::verify(&signature::SM2_SM3_ASN1,
+                                      untrusted::Input::from(&public_key),
+                                      untrusted::Input::from(&msg),
+                                      untrusted::Input::from(&expected_sig))
+                        .is_ok());
+
+            // The same nonce gives the same (r, s) in the fixed-length form.
+            let key_pair = signature::SM2KeyPair::from_bytes(
+                &signature::SM2_SM3_FIXED_SIGNING,
+                untrusted::Input::from(&private_key),
+                untrusted::Input::from(&public_key)).unwrap();
+            let rng = test::rand::FixedSliceRandom { bytes: &k };
+            let actual_sig = key_pair.sign(&msg, &rng).unwrap();
+            let expected_sig = asn1_sig_to_fixed(&expected_sig);
+            assert_eq!(actual_sig.as_slice(), expected_sig.as_slice());
+            assert!(signature::verify(
+                        &signature::SM2_SM3_FIXED,
+                        untrusted::Input::from(&public_key),
+                        untrusted::Input::from(&msg),
+                        untrusted::Input::from(expected_sig.as_slice()))
+                        .is_ok());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn signature_sm2_sign_and_verify_test() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        for &(signing_alg, verification_alg) in
+                &[(&signature::SM2_SM3_ASN1_SIGNING, &signature::SM2_SM3_ASN1),
+                  (&signature::SM2_SM3_FIXED_SIGNING,
+                   &signature::SM2_SM3_FIXED)] {
+            let key_pair =
+                signature::SM2KeyPair::generate(signing_alg, &rng).unwrap();
+            let public_key = untrusted::Input::from(key_pair.public_key_bytes());
+
+            let sig_1 = key_pair.sign(MESSAGE, &rng).unwrap();
+            let sig_2 = key_pair.sign(MESSAGE, &rng).unwrap();
+            // The nonces are random, so the signatures differ.
+            assert!(sig_1.as_slice() != sig_2.as_slice());
+
+            for sig in &[sig_1, sig_2] {
+                let sig = untrusted::Input::from(sig.as_slice());
+                assert!(signature::verify(verification_alg, public_key,
+                                          untrusted::Input::from(MESSAGE),
+                                          sig).is_ok());
+                assert!(signature::verify(verification_alg, public_key,
+                                          untrusted::Input::from(b"hello"),
+                                          sig).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn signature_sm2_private_key_n_minus_one_test() {
+        // n - 1 is a valid ECDSA private key but not a valid SM2 private key.
+        let n_minus_one = [
+            0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x72, 0x03, 0xdf, 0x6b, 0x21, 0xc6, 0x05, 0x2b,
+            0x53, 0xbb, 0xf4, 0x09, 0x39, 0xd5, 0x41, 0x22,
+        ];
+        let rng = test::rand::FixedSliceRandom { bytes: &n_minus_one };
+        assert!(signature::SM2KeyPair::generate(
+                    &signature::SM2_SM3_ASN1_SIGNING, &rng).is_err());
+    }
+
+    fn asn1_sig_to_fixed(sig: &[u8]) -> signature::Signature {
+        let ops = &sm2::PUBLIC_SCALAR_OPS;
+        let (r, s) = untrusted::Input::from(sig).read_all(
+            error::Unspecified, |input| split_rs_asn1(ops, input)).unwrap();
+        format_rs_fixed(ops, &r, &s)
+    }
+}
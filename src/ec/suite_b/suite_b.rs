@@ -12,7 +12,7 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
-//! Elliptic curve operations on P-256 & P-384.
+//! Elliptic curve operations on P-256, P-384, secp256k1, and SM2.
 
 use error;
 use self::ops::*;
@@ -158,6 +158,7 @@ fn verify_affine_point_is_on_the_curve_scaled(
 
 pub mod ecdsa;
 pub mod ecdh;
+pub mod sm2;
 
 #[macro_use]
 #[path = "ops/ops.rs"]
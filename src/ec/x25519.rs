@@ -14,7 +14,7 @@
 
 //! X25519 Key agreement.
 
-use {agreement, bssl, c, ec, error, rand};
+use {agreement, bssl, c, ec, error, init, rand};
 use untrusted;
 
 
@@ -81,8 +81,30 @@ fn x25519_ecdh(out: &mut [u8], my_private_key: &ec::PrivateKey,
     })
 }
 
+/// X25519 of the raw scalar `private_key` and point `peer_public_key`, for
+/// the NaCl `crypto_box` code, which works with static keys.
+pub fn x25519(out: &mut [u8; X25519_ELEM_SCALAR_PUBLIC_KEY_LEN],
+              private_key: &[u8; X25519_ELEM_SCALAR_PUBLIC_KEY_LEN],
+              peer_public_key: &[u8; X25519_ELEM_SCALAR_PUBLIC_KEY_LEN])
+              -> Result<(), error::Unspecified> {
+    init::init_once();
+    bssl::map_result(unsafe {
+        GFp_x25519_ecdh(out, private_key, peer_public_key)
+    })
+}
+
+/// The public key of the raw scalar `private_key`.
+pub fn public_from_private(
+        public_out: &mut [u8; X25519_ELEM_SCALAR_PUBLIC_KEY_LEN],
+        private_key: &[u8; X25519_ELEM_SCALAR_PUBLIC_KEY_LEN]) {
+    init::init_once();
+    unsafe {
+        GFp_x25519_public_from_private(public_out, private_key);
+    }
+}
+
 
-const X25519_ELEM_SCALAR_PUBLIC_KEY_LEN: usize = 32;
+pub const X25519_ELEM_SCALAR_PUBLIC_KEY_LEN: usize = 32;
 
 extern {
     fn GFp_x25519_ecdh(
@@ -0,0 +1,165 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! X448 Key agreement.
+
+use {agreement, bssl, c, ec, error, rand};
+use untrusted;
+
+
+/// X448 (ECDH using Curve448) as described in [RFC 7748].
+///
+/// Everything is as described in RFC 7748. Key agreement will fail if the
+/// result of the X448 operation is zero; see the notes on the
+/// "all-zero value" in [RFC 7748 section 6.2].
+///
+/// [RFC 7748]: https://tools.ietf.org/html/rfc7748
+/// [RFC 7748 section 6.2]: https://tools.ietf.org/html/rfc7748#section-6.2
+pub static X448: agreement::Algorithm = agreement::Algorithm {
+    i: ec::AgreementAlgorithmImpl {
+        public_key_len: X448_ELEM_SCALAR_PUBLIC_KEY_LEN,
+        elem_and_scalar_len: X448_ELEM_SCALAR_PUBLIC_KEY_LEN,
+        nid: 1035 /* NID_X448 */,
+        generate_private_key: x448_generate_private_key,
+        public_from_private: x448_public_from_private,
+        ecdh: x448_ecdh,
+    },
+};
+
+fn x448_generate_private_key(rng: &rand::SecureRandom)
+                             -> Result<ec::PrivateKey, error::Unspecified> {
+    let mut result = ec::PrivateKey { bytes: [0; ec::SCALAR_MAX_BYTES] };
+    try!(rng.fill(&mut result.bytes[..X448_ELEM_SCALAR_PUBLIC_KEY_LEN]));
+    Ok(result)
+}
+
+fn x448_public_from_private(public_out: &mut [u8],
+                            private_key: &ec::PrivateKey)
+                            -> Result<(), error::Unspecified> {
+    let public_out =
+        try!(slice_as_array_ref_mut!(public_out,
+                                     X448_ELEM_SCALAR_PUBLIC_KEY_LEN));
+    // XXX: This shouldn't require dynamic checks, but rustc can't slice an
+    // array reference to a shorter array reference. TODO(perf): Fix this.
+    let private_key =
+        try!(slice_as_array_ref!(
+                &private_key.bytes[..X448_ELEM_SCALAR_PUBLIC_KEY_LEN],
+                X448_ELEM_SCALAR_PUBLIC_KEY_LEN));
+    unsafe {
+        GFp_x448_public_from_private(public_out, private_key);
+    }
+    Ok(())
+}
+
+fn x448_ecdh(out: &mut [u8], my_private_key: &ec::PrivateKey,
+             peer_public_key: untrusted::Input)
+             -> Result<(), error::Unspecified> {
+    let out =
+        try!(slice_as_array_ref_mut!(out, X448_ELEM_SCALAR_PUBLIC_KEY_LEN));
+    // XXX: This shouldn't require dynamic checks, but rustc can't slice an
+    // array reference to a shorter array reference. TODO(perf): Fix this.
+    let my_private_key =
+        try!(slice_as_array_ref!(
+                &my_private_key.bytes[..X448_ELEM_SCALAR_PUBLIC_KEY_LEN],
+                X448_ELEM_SCALAR_PUBLIC_KEY_LEN));
+    let peer_public_key =
+        try!(slice_as_array_ref!(peer_public_key.as_slice_less_safe(),
+                                 X448_ELEM_SCALAR_PUBLIC_KEY_LEN));
+    bssl::map_result(unsafe {
+        GFp_x448_ecdh(out, my_private_key, peer_public_key)
+    })
+}
+
+
+const X448_ELEM_SCALAR_PUBLIC_KEY_LEN: usize = 56;
+
+extern {
+    fn GFp_x448_ecdh(
+        out_shared_key: &mut [u8; X448_ELEM_SCALAR_PUBLIC_KEY_LEN],
+        private_key: &[u8; X448_ELEM_SCALAR_PUBLIC_KEY_LEN],
+        peer_public_value: &[u8; X448_ELEM_SCALAR_PUBLIC_KEY_LEN]) -> c::int;
+    fn GFp_x448_public_from_private(
+        public_key_out: &mut [u8; X448_ELEM_SCALAR_PUBLIC_KEY_LEN],
+        private_key: &[u8; X448_ELEM_SCALAR_PUBLIC_KEY_LEN]);
+}
+
+#[cfg(test)]
+mod tests {
+    use {agreement, error, test};
+    use std;
+    use untrusted;
+
+    #[test]
+    fn test_agreement_ecdh_x448_rfc_iterated() {
+        let mut k =
+            h("0500000000000000000000000000000000000000000000000000000000000000\
+               000000000000000000000000000000000000000000000000");
+        let mut u = k.clone();
+
+        fn expect_iterated_x448(expected_result: &str,
+                                range: std::ops::Range<usize>,
+                                k: &mut std::vec::Vec<u8>,
+                                u: &mut std::vec::Vec<u8>) {
+            for _ in range {
+                let new_k = x448(k, u);
+                *u = k.clone();
+                *k = new_k;
+            }
+            assert_eq!(&h(expected_result), k);
+        }
+
+        expect_iterated_x448(
+            "3f482c8a9f19b01e6c46ee9711d9dc14fd4bf67af30765c2ae2b846a4d23a8cd\
+             0db897086239492caf350b51f833868b9bc2b3bca9cf4113",
+            0..1, &mut k, &mut u);
+        expect_iterated_x448(
+            "aa3b4749d55b9daf1e5b00288826c467274ce3ebbdd5c17b975e09d4af6c67cf\
+             10d087202db88286e2b79fceea3ec353ef54faa26e219f38",
+            1..1_000, &mut k, &mut u);
+
+        // The spec gives a test vector for 1,000,000 iterations but that
+        // takes far too long by default.
+        if cfg!(feature = "slow_tests") {
+          expect_iterated_x448(
+            "077f453681caca3693198420bbe515cae0002472519b3e67661a7e89cab94695\
+             c8f4bcd66e61b9b9c946da8d524de3d69bd9d9d66b997e37",
+            1_000..1_000_000, &mut k, &mut u);
+        }
+    }
+
+    fn x448(private_key: &[u8], public_key: &[u8]) -> std::vec::Vec<u8> {
+        x448_(private_key, public_key).unwrap()
+    }
+
+    fn x448_(private_key: &[u8], public_key: &[u8])
+             -> Result<std::vec::Vec<u8>, error::Unspecified> {
+        let private_key =
+            agreement::EphemeralPrivateKey::from_test_vector(&agreement::X448,
+                                                             private_key);
+        let public_key = untrusted::Input::from(public_key);
+        agreement::agree_ephemeral(private_key, &agreement::X448, public_key,
+                                   error::Unspecified, |agreed_value| {
+            Ok(std::vec::Vec::from(agreed_value))
+        })
+    }
+
+    fn h(s: &str) -> std::vec::Vec<u8> {
+        match test::from_hex(s) {
+            Ok(v) => v,
+            Err(msg) => {
+                panic!("{} in {}", msg, s);
+            },
+        }
+    }
+}
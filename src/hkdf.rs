@@ -16,95 +16,360 @@
 //!
 //! HKDF is specified in [RFC 5869].
 //!
-//! In most situations, it is best to use `extract_and_expand` to do both the
-//! HKDF-Extract and HKDF-Expand as one atomic operation. It is only necessary
-//! to use the separate `expand` and `extract` functions if a single derived
-//! `PRK` (defined in RFC 5869) is used more than once.
+//! A `Salt` is constructed once from the salt value and may be used for any
+//! number of HKDF-Extract operations. `Salt::extract` returns the `Prk`
+//! (pseudorandom key), and `Prk::expand` returns the `Okm` (output keying
+//! material) for a given `info` and key type. The length of the output is
+//! determined by the key type, so the output can only be used to fill a key
+//! of the right length, and the limit of 255 times the digest length that
+//! RFC 5869 places on the output is checked before any output is generated.
 //!
-//! Salts have type `hmac::SigningKey` instead of `&[u8]` because they are
-//! frequently used for multiple HKDF operations, and it is more efficient to
-//! construct the `SigningKey` once and reuse it. Given a digest algorithm
-//! `digest_alg` and a salt `salt: &[u8]`, the `SigningKey` should be
-//! constructed as `hmac::SigningKey::new(digest_alg, salt)`.
+//! Key schedules that need the PRK itself, e.g. as the input keying material
+//! of another HKDF-Extract, can get it with `Salt::extract_value`.
+//!
+//! Keys can be constructed directly from an `Okm`, without the caller ever
+//! seeing the key bytes: `aead::UnboundKey`, `hmac::SigningKey`, `Prk`, and
+//! `Salt` implement `From<Okm>` for the key types `&'static aead::Algorithm`
+//! and `PrkType`. A fixed-size array, e.g. `[u8; 32]`, is filled with
+//! `Okm::fill`. Other key types implement `KeyType` and `From<Okm>` the
+//! same way.
+//!
+//! `Prk::expand_label` implements the HKDF-Expand-Label function of TLS 1.3
+//! (and QUIC), which encodes the label, context, and output length into the
+//! `info` in the way that TLS 1.3 requires.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{digest, hkdf};
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! let salt = hkdf::Salt::new(&digest::SHA256, b"salt");
+//! let prk = salt.extract(b"input keying material");
+//!
+//! // Derive another PRK from the output of the first.
+//! let info: &[&[u8]] = &[b"application", b"context"];
+//! let next_prk: hkdf::Prk =
+//!     try!(prk.expand(info, hkdf::HKDF_SHA256_PRK)).into();
+//! # let _ = next_prk;
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
 //!
 //! [RFC 5869]: https://tools.ietf.org/html/rfc5869
 
 
-use hmac;
+use {core, digest, error, hmac};
 
-/// Fills `out` with the output of the HKDF Extract-and-Expand operation for
-/// the given inputs.
-///
-/// `extract_and_expand` is exactly equivalent to:
-///
-/// ```
-/// # use ring::{hkdf, hmac};
-/// # fn foo(salt: &hmac::SigningKey, secret: &[u8], info: &[u8],
-/// #        out: &mut [u8]) {
-/// let prk = hkdf::extract(salt, secret);
-/// hkdf::expand(&prk, info, out)
-/// # }
-/// ```
-///
-/// See the documentation for `extract` and `expand` for details.
-///
-/// # Panics
-///
-/// `extract_and_expand` panics if `expand` panics.
-pub fn extract_and_expand(salt: &hmac::SigningKey, secret: &[u8],
-                          info: &[u8], out: &mut [u8]) {
-    let prk = extract(salt, secret);
-    expand(&prk, info, out)
+/// A type of key, or other value, that is derived with HKDF-Expand or with
+/// `pbkdf2::derive_okm`.
+pub trait KeyType {
+    /// The length of the key, in bytes.
+    fn len(&self) -> usize;
 }
 
-/// The HKDF-Extract operation.
-///
-/// | Parameter                 | RFC 5869 Term
-/// |---------------------------|--------------
-/// | `salt.digest_algorithm()` | Hash
-/// | `secret`                  | IKM (Input Keying Material)
-/// | [return value]            | PRK
-pub fn extract(salt: &hmac::SigningKey, secret: &[u8]) -> hmac::SigningKey {
-    // The spec says that if no salt is provided then a key of
-    // `digest_alg.output_len` bytes of zeros is used. But, HMAC keys are
-    // already zero-padded to the block length, which is larger than the output
-    // length of the extract step (the length of the digest). Consequently, the
-    // `SigningKey` constructor will automatically do the right thing for a
-    // zero-length string.
-    let prk = hmac::sign(salt, secret);
-    hmac::SigningKey::new(salt.digest_algorithm(), prk.as_ref())
-}
-
-/// Fills `out` with the output of the HKDF-Expand operation for the given
-/// inputs.
-///
-/// `prk` should be the return value of an earlier call to `extract`.
-///
-/// | Parameter  | RFC 5869 Term
-/// |------------|--------------
-/// | prk        | PRK
-/// | info       | info
-/// | out        | OKM (Output Keying Material)
-/// | out.len()  | L (Length of output keying material in bytes)
+/// A key type for a PRK (or an HMAC key or salt) for the digest algorithm,
+/// whose length is the digest length.
+pub struct PrkType(&'static digest::Algorithm);
+
+impl PrkType {
+    /// The digest algorithm of the PRK.
+    #[inline(always)]
+    pub fn digest_algorithm(&self) -> &'static digest::Algorithm { self.0 }
+}
+
+impl KeyType for PrkType {
+    fn len(&self) -> usize { self.0.output_len }
+}
+
+/// A PRK for use with SHA-256.
+pub const HKDF_SHA256_PRK: PrkType = PrkType(&digest::SHA256);
+
+/// A PRK for use with SHA-384.
+pub const HKDF_SHA384_PRK: PrkType = PrkType(&digest::SHA384);
+
+/// A PRK for use with SHA-512.
+pub const HKDF_SHA512_PRK: PrkType = PrkType(&digest::SHA512);
+
+/// A salt for HKDF-Extract.
+pub struct Salt(hmac::SigningKey);
+
+impl Salt {
+    /// Constructs a new salt with the value `value` for use with the digest
+    /// algorithm `digest_alg`.
+    ///
+    /// RFC 5869 says that in the absence of a salt, a salt of `HashLen` zero
+    /// bytes is used. An empty `value` is equivalent to that.
+    pub fn new(digest_alg: &'static digest::Algorithm, value: &[u8]) -> Salt {
+        Salt(hmac::SigningKey::new(digest_alg, value))
+    }
+
+    /// The HKDF-Extract operation.
+    ///
+    /// | Parameter                    | RFC 5869 Term
+    /// |------------------------------|--------------
+    /// | `self.digest_algorithm()`    | Hash
+    /// | `self`                       | salt
+    /// | `secret`                     | IKM (Input Keying Material)
+    /// | [return value]               | PRK
+    pub fn extract(&self, secret: &[u8]) -> Prk {
+        Prk::from(&self.extract_value(secret))
+    }
+
+    /// The HKDF-Extract operation, returning the value of the PRK instead of
+    /// a `Prk`.
+    ///
+    /// This is for key schedules, like those of TLS 1.3 and Noise, that use
+    /// the PRK as an input to operations other than HKDF-Expand. Otherwise,
+    /// use `extract`.
+    pub fn extract_value(&self, secret: &[u8]) -> PrkValue {
+        // The spec says that if no salt is provided then a key of
+        // `digest_alg.output_len` bytes of zeros is used. But, HMAC keys are
+        // already zero-padded to the block length, which is larger than the
+        // output length of the extract step (the length of the digest).
+        // Consequently, the `SigningKey` constructor will automatically do the
+        // right thing for a zero-length string.
+        let prk = hmac::sign(&self.0, secret);
+        let mut value = [0u8; digest::MAX_OUTPUT_LEN];
+        value[..prk.as_ref().len()].copy_from_slice(prk.as_ref());
+        PrkValue {
+            value: value,
+            digest_alg: self.digest_algorithm(),
+        }
+    }
+
+    /// The digest algorithm used for HKDF-Extract.
+    #[inline(always)]
+    pub fn digest_algorithm(&self) -> &'static digest::Algorithm {
+        self.0.digest_algorithm()
+    }
+}
+
+impl<'a> From<Okm<'a, PrkType>> for Salt {
+    fn from(okm: Okm<'a, PrkType>) -> Salt {
+        Salt(okm.into())
+    }
+}
+
+/// A pseudorandom key, the output of HKDF-Extract and the input of
+/// HKDF-Expand.
+pub struct Prk(hmac::SigningKey);
+
+impl Prk {
+    /// Constructs a PRK directly from `value`, without HKDF-Extract, for use
+    /// with the digest algorithm `digest_alg`.
+    ///
+    /// This is only safe when `value` is already a uniformly random key of at
+    /// least `digest_alg.output_len` bytes, e.g. a key that was itself the
+    /// output of HKDF. Usually a `Prk` should be constructed with
+    /// `Salt::extract` instead.
+    pub fn new_less_safe(digest_alg: &'static digest::Algorithm, value: &[u8])
+                         -> Prk {
+        Prk(hmac::SigningKey::new(digest_alg, value))
+    }
+
+    /// The HKDF-Expand operation.
+    ///
+    /// | Parameter                    | RFC 5869 Term
+    /// |------------------------------|--------------
+    /// | `self`                       | PRK
+    /// | `info`                       | info (the concatenation of the slices)
+    /// | `key_type.len()`             | L (length of output keying material)
+    /// | [return value]               | OKM (Output Keying Material)
+    ///
+    /// Fails if `key_type.len()` is larger than 255 times the digest length.
+    /// This is the limit imposed by the HKDF specification, and is necessary
+    /// to prevent overflow of the 8-bit iteration counter in the expansion
+    /// step.
+    pub fn expand<'a, K: KeyType>(&'a self, info: &'a [&'a [u8]], key_type: K)
+                                  -> Result<Okm<'a, K>, error::Unspecified> {
+        let digest_alg = self.digest_algorithm();
+        assert!(digest_alg.block_len >= digest_alg.output_len);
+        if key_type.len() > 255 * digest_alg.output_len {
+            return Err(error::Unspecified);
+        }
+        Ok(Okm {
+            prk: self,
+            info: Info::Parts(info),
+            key_type: key_type,
+        })
+    }
+
+    /// The HKDF-Expand-Label operation of [RFC 8446 Section 7.1], which is
+    /// HKDF-Expand with an `info` of the encoded `HkdfLabel` structure.
+    ///
+    /// | Parameter                    | RFC 8446 Section 7.1 Term
+    /// |------------------------------|--------------
+    /// | `self`                       | Secret
+    /// | `label`                      | Label (without the "tls13 " prefix)
+    /// | `context`                    | Context
+    /// | `key_type.len()`             | Length
+    ///
+    /// `Derive-Secret(Secret, Label, Messages)` is `expand_label` with a
+    /// `context` of the transcript hash of `Messages` and a `key_type` of
+    /// the digest length, e.g. `HKDF_SHA256_PRK`.
+    ///
+    /// Fails if `label` is empty or longer than 249 bytes (so that it fits in
+    /// the `HkdfLabel` with its prefix), if `context` is longer than 255
+    /// bytes, or if `key_type.len()` is larger than 255 times the digest
+    /// length.
+    ///
+    /// [RFC 8446 Section 7.1]: https://tools.ietf.org/html/rfc8446#section-7.1
+    pub fn expand_label<'a, K: KeyType>(&'a self, label: &'a [u8],
+                                        context: &'a [u8], key_type: K)
+            -> Result<Okm<'a, K>, error::Unspecified> {
+        let label_len = LABEL_PREFIX.len() + label.len();
+        if label.is_empty() || label_len > 255 || context.len() > 255 {
+            return Err(error::Unspecified);
+        }
+        // This limit also ensures that the length fits in the `uint16`.
+        let len = key_type.len();
+        if len > 255 * self.digest_algorithm().output_len {
+            return Err(error::Unspecified);
+        }
+
+        Ok(Okm {
+            prk: self,
+            info: Info::Label {
+                length: [(len >> 8) as u8, len as u8],
+                label: label,
+                context: context,
+            },
+            key_type: key_type,
+        })
+    }
+
+    /// The digest algorithm used for HKDF-Expand.
+    #[inline(always)]
+    pub fn digest_algorithm(&self) -> &'static digest::Algorithm {
+        self.0.digest_algorithm()
+    }
+}
+
+impl<'a> From<Okm<'a, PrkType>> for Prk {
+    fn from(okm: Okm<'a, PrkType>) -> Prk {
+        Prk(okm.into())
+    }
+}
+
+/// The value of a PRK, as returned by `Salt::extract_value`.
 ///
-/// # Panics
+/// The value is as secret as the input keying material it was extracted
+/// from. Its `Debug` implementation doesn't show it.
+pub struct PrkValue {
+    value: [u8; digest::MAX_OUTPUT_LEN],
+    digest_alg: &'static digest::Algorithm,
+}
+
+impl PrkValue {
+    /// The value of the PRK. It is `self.digest_algorithm().output_len`
+    /// bytes long.
+    pub fn as_bytes_less_safe(&self) -> &[u8] {
+        &self.value[..self.digest_alg.output_len]
+    }
+
+    /// The digest algorithm of the HKDF-Extract operation.
+    #[inline(always)]
+    pub fn digest_algorithm(&self) -> &'static digest::Algorithm {
+        self.digest_alg
+    }
+}
+
+impl core::fmt::Debug for PrkValue {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "PrkValue {{ digest_algorithm: {:?} }}", self.digest_alg)
+    }
+}
+
+impl<'a> From<&'a PrkValue> for Prk {
+    fn from(value: &'a PrkValue) -> Prk {
+        Prk::new_less_safe(value.digest_alg, value.as_bytes_less_safe())
+    }
+}
+
+/// The output keying material of HKDF-Expand for a particular key type.
 ///
-/// `expand` panics if the requested output length is larger than 255 times the
-/// size of the digest algorithm, i.e. if
-/// `out.len() > 255 * salt.digest_algorithm().output_len`. This is the limit
-/// imposed by the HKDF specification, and is necessary to prevent overflow of
-/// the 8-bit iteration counter in the expansion step.
-pub fn expand(prk: &hmac::SigningKey, info: &[u8], out: &mut [u8]) {
-    let digest_alg = prk.digest_algorithm();
-    assert!(out.len() <= 255 * digest_alg.output_len);
-    assert!(digest_alg.block_len >= digest_alg.output_len);
+/// The output is only generated when it is used to fill a key, with `fill`
+/// or through a `From<Okm>` implementation of the key type.
+pub struct Okm<'a, K: KeyType> {
+    prk: &'a Prk,
+    info: Info<'a>,
+    key_type: K,
+}
+
+// The `info` of HKDF-Expand.
+enum Info<'a> {
+    // The concatenation of the parts, as passed to `Prk::expand`.
+    Parts(&'a [&'a [u8]]),
+
+    // The encoded `HkdfLabel` of `Prk::expand_label`. `length` is the
+    // big-endian `uint16` output length; the lengths of `label` (with its
+    // prefix) and `context` are encoded when the `info` is digested.
+    Label {
+        length: [u8; 2],
+        label: &'a [u8],
+        context: &'a [u8],
+    },
+}
+
+const LABEL_PREFIX: &'static [u8] = b"tls13 ";
+
+impl<'a> Info<'a> {
+    fn update(&self, ctx: &mut hmac::SigningContext) {
+        match *self {
+            Info::Parts(parts) => {
+                for part in parts {
+                    ctx.update(part);
+                }
+            },
+            Info::Label { ref length, label, context } => {
+                ctx.update(length);
+                ctx.update(&[(LABEL_PREFIX.len() + label.len()) as u8]);
+                ctx.update(LABEL_PREFIX);
+                ctx.update(label);
+                ctx.update(&[context.len() as u8]);
+                ctx.update(context);
+            },
+        }
+    }
+}
+
+impl<'a, K: KeyType> Okm<'a, K> {
+    /// The key type that the output is for.
+    #[inline(always)]
+    pub fn key_type(&self) -> &K { &self.key_type }
+
+    /// Fills `out` with the output keying material.
+    ///
+    /// Fails if `out.len()` isn't `self.key_type().len()`.
+    pub fn fill(self, out: &mut [u8]) -> Result<(), error::Unspecified> {
+        if out.len() != self.key_type.len() {
+            return Err(error::Unspecified);
+        }
+        fill_okm(&self.prk.0, &self.info, out);
+        Ok(())
+    }
+}
 
+impl<'a> From<Okm<'a, PrkType>> for hmac::SigningKey {
+    fn from(okm: Okm<'a, PrkType>) -> hmac::SigningKey {
+        let digest_alg = okm.key_type.digest_algorithm();
+        let mut buf = [0u8; digest::MAX_OUTPUT_LEN];
+        let buf = &mut buf[..digest_alg.output_len];
+        okm.fill(buf).unwrap();
+        hmac::SigningKey::new(digest_alg, buf)
+    }
+}
+
+fn fill_okm(prk: &hmac::SigningKey, info: &Info, out: &mut [u8]) {
+    let digest_alg = prk.digest_algorithm();
     let mut ctx = hmac::SigningContext::with_key(prk);
 
     let mut n = 1u8;
     let mut pos = 0;
     loop {
-        ctx.update(info);
+        info.update(&mut ctx);
         ctx.update(&[n]);
 
         let t = ctx.sign();
@@ -116,13 +381,11 @@ pub fn expand(prk: &hmac::SigningKey, info: &[u8], out: &mut [u8]) {
             digest_alg.output_len
         };
         let t_bytes = t.as_ref();
-        for i in 0..to_copy {
-            out[pos + i] = t_bytes[i];
-        }
-        if to_copy < digest_alg.output_len {
+        out[pos..][..to_copy].copy_from_slice(&t_bytes[..to_copy]);
+        pos += to_copy;
+        if pos == out.len() {
             break;
         }
-        pos += digest_alg.output_len;
 
         ctx = hmac::SigningContext::with_key(prk);
         ctx.update(t_bytes);
@@ -133,7 +396,13 @@ pub fn expand(prk: &hmac::SigningKey, info: &[u8], out: &mut [u8]) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use {error, hmac, test};
+    use {digest, error, test};
+
+    struct OutputLen(usize);
+
+    impl KeyType for OutputLen {
+        fn len(&self) -> usize { self.0 }
+    }
 
     #[test]
     pub fn hkdf_tests() {
@@ -146,19 +415,140 @@ mod tests {
             let salt = test_case.consume_bytes("salt");
             let info = test_case.consume_bytes("info");
 
-            // The PRK is an intermediate value that we can't test, but we
-            // have to consume it to make test::from_file happy.
-            let _ = test_case.consume_bytes("PRK");
-
+            let expected_prk = test_case.consume_bytes("PRK");
             let expected_out = test_case.consume_bytes("OKM");
 
-            let salt = hmac::SigningKey::new(digest_alg, &salt);
+            let salt = Salt::new(digest_alg, &salt);
+            let prk_value = salt.extract_value(&secret);
+            assert_eq!(prk_value.as_bytes_less_safe(), &expected_prk[..]);
+            assert_eq!(prk_value.digest_algorithm().output_len,
+                       digest_alg.output_len);
+            let prk = salt.extract(&secret);
+
+            let mut out = vec![0u8; expected_out.len()];
+            try!(try!(prk.expand(&[&info], OutputLen(out.len())))
+                     .fill(&mut out));
+            assert_eq!(out, expected_out);
+
+            // The info may be split into several pieces.
+            let (a, b) = info.split_at(info.len() / 2);
+            let mut out = vec![0u8; expected_out.len()];
+            try!(try!(prk.expand(&[a, b], OutputLen(out.len())))
+                     .fill(&mut out));
+            assert_eq!(out, expected_out);
+
+            let prk = Prk::from(&prk_value);
+            let mut out = vec![0u8; expected_out.len()];
+            try!(try!(prk.expand(&[&info], OutputLen(out.len())))
+                     .fill(&mut out));
+            assert_eq!(out, expected_out);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn hkdf_expand_label_tests() {
+        test::from_file("src/hkdf_expand_label_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+            let digest_alg =
+                try!(test_case.consume_digest_alg("Hash")
+                              .ok_or(error::Unspecified));
+            let secret = test_case.consume_bytes("Secret");
+            let label = test_case.consume_bytes("Label");
+            let context = test_case.consume_bytes("Context");
+            let expected_out = test_case.consume_bytes("Output");
 
+            let prk = Prk::new_less_safe(digest_alg, &secret);
             let mut out = vec![0u8; expected_out.len()];
-            extract_and_expand(&salt, &secret, &info, &mut out);
+            let okm = try!(prk.expand_label(&label, &context,
+                                            OutputLen(out.len())));
+            try!(okm.fill(&mut out));
             assert_eq!(out, expected_out);
 
             Ok(())
         });
     }
+
+    #[test]
+    fn hkdf_expand_label_len_tests() {
+        let prk = Prk::new_less_safe(&digest::SHA256, &[0; 32]);
+        let len = OutputLen;
+
+        assert!(prk.expand_label(b"", b"", len(32)).is_err());
+        assert!(prk.expand_label(&[b'a'; 249], b"", len(32)).is_ok());
+        assert!(prk.expand_label(&[b'a'; 250], b"", len(32)).is_err());
+        assert!(prk.expand_label(b"key", &[0; 255], len(32)).is_ok());
+        assert!(prk.expand_label(b"key", &[0; 256], len(32)).is_err());
+        assert!(prk.expand_label(b"key", b"", len(255 * 32)).is_ok());
+        assert!(prk.expand_label(b"key", b"", len(255 * 32 + 1)).is_err());
+
+        // The output can only fill a key of the length it was derived for.
+        let mut out = [0u8; 33];
+        let okm = prk.expand_label(b"key", b"", len(32)).unwrap();
+        assert!(okm.fill(&mut out).is_err());
+        let okm = prk.expand_label(b"key", b"", len(32)).unwrap();
+        assert!(okm.fill(&mut out[..32]).is_ok());
+
+        // `Derive-Secret` can construct the next secret directly.
+        let secret: Prk =
+            prk.expand_label(b"derived", b"", HKDF_SHA256_PRK).unwrap().into();
+        let mut secret_bytes = [0u8; 32];
+        prk.expand_label(b"derived", b"", len(32)).unwrap()
+           .fill(&mut secret_bytes).unwrap();
+        let expected = Prk::new_less_safe(&digest::SHA256, &secret_bytes);
+        let info: &[&[u8]] = &[b"info"];
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        secret.expand(info, len(32)).unwrap().fill(&mut a).unwrap();
+        expected.expand(info, len(32)).unwrap().fill(&mut b).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn hkdf_output_len_tests() {
+        let prk = Salt::new(&digest::SHA256, b"salt").extract(b"secret");
+
+        // The output is limited to 255 times the digest length.
+        let max_len = 255 * digest::SHA256.output_len;
+        let mut out = vec![0u8; max_len + 1];
+        assert!(prk.expand(&[b"info"], OutputLen(max_len + 1)).is_err());
+        let okm = prk.expand(&[b"info"], OutputLen(max_len)).unwrap();
+        assert!(okm.fill(&mut out[..max_len]).is_ok());
+
+        // The output must fill a key of exactly the key type's length.
+        for &len in &[0, 31, 33] {
+            let okm = prk.expand(&[b"info"], OutputLen(32)).unwrap();
+            assert!(okm.fill(&mut out[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn hkdf_prk_value_debug_test() {
+        let salt = Salt::new(&digest::SHA256, b"salt");
+        let value = salt.extract_value(b"secret");
+        assert_eq!(format!("{:?}", value),
+                   "PrkValue { digest_algorithm: SHA-256 }");
+    }
+
+    #[test]
+    fn hkdf_prk_type_tests() {
+        let prk = Salt::new(&digest::SHA384, b"salt").extract(b"secret");
+        let info: &[&[u8]] = &[b"info"];
+
+        let mut expected = [0u8; 48];
+        prk.expand(info, OutputLen(48)).unwrap().fill(&mut expected).unwrap();
+
+        // A PRK derived with `HKDF_SHA384_PRK` is the same as a PRK
+        // constructed from the output bytes.
+        let derived: Prk = prk.expand(info, HKDF_SHA384_PRK).unwrap().into();
+        assert_eq!(derived.digest_algorithm().output_len, 48);
+        let from_bytes = Prk::new_less_safe(&digest::SHA384, &expected);
+        let mut a = [0u8; 100];
+        let mut b = [0u8; 100];
+        derived.expand(info, OutputLen(100)).unwrap().fill(&mut a).unwrap();
+        from_bytes.expand(info, OutputLen(100)).unwrap().fill(&mut b).unwrap();
+        assert_eq!(&a[..], &b[..]);
+    }
 }
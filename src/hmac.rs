@@ -23,7 +23,9 @@
 //! Frequently all the data to be signed in a message is available in a single
 //! contiguous piece. In that case, the module-level `sign` function can be
 //! used. Otherwise, if the input is in multiple parts, `SigningContext` should
-//! be used.
+//! be used. Similarly, `verify` verifies a message in a single piece, and
+//! `VerificationContext` verifies one in multiple parts, e.g. a large
+//! payload that is streamed, without buffering the whole message.
 //!
 //! # Use Case: Multi-party Communication
 //!
@@ -132,13 +134,13 @@
 //! let signature = s_ctx.sign();
 //!
 //! // The receiver (somehow!) knows the key value, and uses it to verify the
-//! // integrity of the message.
+//! // integrity of the message as its parts arrive.
 //! let v_key = hmac::VerificationKey::new(&digest::SHA384, key_value.as_ref());
-//! let mut msg = Vec::<u8>::new();
+//! let mut v_ctx = hmac::VerificationContext::with_key(&v_key);
 //! for part in &parts {
-//!     msg.extend(part.as_bytes());
+//!     v_ctx.update(part.as_bytes());
 //! }
-//! try!(hmac::verify(&v_key, &msg.as_ref(), signature.as_ref()));
+//! try!(v_ctx.verify(signature.as_ref()));
 //! #
 //! # Ok(())
 //! # }
@@ -162,25 +164,34 @@ pub struct SigningKey {
 
 impl SigningKey {
     /// Generate an HMAC signing key for the given digest algorithm using
-    /// |ring::rand|. The key will be `digest_alg.chaining_len` bytes long. The
-    /// key size choice is based on the recommendation of
-    /// [NIST SP 800-107], Section 5.3.4: Security Effect of the HMAC
-    /// Key, and is consistent with the key lengths chosen for TLS as
-    /// described in [RFC 5246, Appendix C].
+    /// |ring::rand|. The key will be `recommended_key_len(digest_alg)` bytes
+    /// long.
     ///
-    /// [NIST SP 800-107]:
-    ///     http://csrc.nist.gov/publications/nistpubs/800-107-rev1/sp800-107-rev1.pdf
-    /// [RFC 5246, Appendix C]:
-    ///     https://tools.ietf.org/html/rfc5246#appendix-C
+    /// Use `generate_serializable` instead if the key needs to be stored so
+    /// that it can be used again later.
     pub fn generate(digest_alg: &'static digest::Algorithm,
                     rng: &rand::SecureRandom)
                     -> Result<SigningKey, error::Unspecified> {
-        // XXX: There should probably be a `digest::MAX_CHAINING_LEN`, but for
-        // now `digest::MAX_OUTPUT_LEN` is good enough.
-        let mut key_data = [0u8; digest::MAX_OUTPUT_LEN];
-        let key_data = &mut key_data[..digest_alg.output_len];
-        try!(rng.fill(key_data));
-        Ok(SigningKey::new(digest_alg, key_data))
+        let mut key_bytes = [0u8; digest::MAX_CHAINING_LEN];
+        let key_bytes = &mut key_bytes[..recommended_key_len(digest_alg)];
+        SigningKey::generate_serializable(digest_alg, rng, key_bytes)
+    }
+
+    /// Generate an HMAC signing key for the given digest algorithm using
+    /// |ring::rand|, writing the key's value into `key_bytes` so that it can
+    /// be stored and later reconstructed with `SigningKey::new`.
+    ///
+    /// `key_bytes` must be exactly `recommended_key_len(digest_alg)` bytes
+    /// long. `key_bytes` is as secret as the key itself.
+    pub fn generate_serializable(digest_alg: &'static digest::Algorithm,
+                                 rng: &rand::SecureRandom,
+                                 key_bytes: &mut [u8])
+                                 -> Result<SigningKey, error::Unspecified> {
+        if key_bytes.len() != recommended_key_len(digest_alg) {
+            return Err(error::Unspecified);
+        }
+        try!(rng.fill(key_bytes));
+        Ok(SigningKey::new(digest_alg, key_bytes))
     }
 
     /// Construct an HMAC signing key using the given digest algorithm and key
@@ -237,6 +248,22 @@ impl SigningKey {
     }
 }
 
+/// The recommended length of a key for HMAC with `digest_alg`, in bytes.
+///
+/// This is `digest_alg.chaining_len`, based on the recommendation of
+/// [NIST SP 800-107], Section 5.3.4: Security Effect of the HMAC Key, and is
+/// consistent with the key lengths chosen for TLS as described in
+/// [RFC 5246, Appendix C].
+///
+/// [NIST SP 800-107]:
+///     http://csrc.nist.gov/publications/nistpubs/800-107-rev1/sp800-107-rev1.pdf
+/// [RFC 5246, Appendix C]:
+///     https://tools.ietf.org/html/rfc5246#appendix-C
+#[inline(always)]
+pub fn recommended_key_len(digest_alg: &'static digest::Algorithm) -> usize {
+    digest_alg.chaining_len
+}
+
 /// A context for multi-step (Init-Update-Finish) HMAC signing.
 ///
 /// Use `sign` for single-step HMAC signing.
@@ -317,6 +344,45 @@ impl VerificationKey {
     }
 }
 
+/// A context for multi-step (Init-Update-Finish) HMAC verification.
+///
+/// Use `verify` or `verify_with_own_key` for single-step HMAC verification.
+pub struct VerificationContext {
+    wrapped: SigningContext,
+}
+
+impl VerificationContext {
+    /// Constructs a new HMAC verification context using the given key.
+    pub fn with_key(key: &VerificationKey) -> VerificationContext {
+        VerificationContext::with_own_key(&key.wrapped)
+    }
+
+    /// Constructs a new HMAC verification context using the signing key
+    /// `key`.
+    ///
+    /// This is logically equivalent to, but more efficient than, constructing
+    /// a `VerificationKey` with the same value as `key` and then using
+    /// `with_key`.
+    pub fn with_own_key(key: &SigningKey) -> VerificationContext {
+        VerificationContext { wrapped: SigningContext::with_key(key) }
+    }
+
+    /// Updates the HMAC with all the data in `data`. `update` may be called
+    /// zero or more times until `verify` is called.
+    pub fn update(&mut self, data: &[u8]) { self.wrapped.update(data); }
+
+    /// Finalizes the HMAC calculation and verifies whether the resultant
+    /// value equals `signature`. `verify` consumes the context so it cannot
+    /// be (mis-)used after `verify` has been called.
+    ///
+    /// The verification will be done in constant time to prevent timing
+    /// attacks.
+    pub fn verify(self, signature: &[u8]) -> Result<(), error::Unspecified> {
+        constant_time::verify_slices_are_equal(self.wrapped.sign().as_ref(),
+                                               signature)
+    }
+}
+
 /// Calculates the HMAC of `data` using the key `key`, and verifies whether the
 /// resultant value equals `signature`, in one step.
 ///
@@ -366,6 +432,37 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn hmac_generate_serializable_tests() {
+        let rng = rand::SystemRandom::new();
+
+        for d in &digest::test_util::ALL_ALGORITHMS {
+            let len = hmac::recommended_key_len(d);
+            assert_eq!(len, d.chaining_len);
+
+            let mut key_bytes = vec![0u8; len];
+            let key = hmac::SigningKey::generate_serializable(d, &rng,
+                                                              &mut key_bytes)
+                .unwrap();
+            assert!(key_bytes.iter().any(|&b| b != 0));
+
+            // The key can be reconstructed from the serialized bytes.
+            let imported = hmac::SigningKey::new(d, &key_bytes);
+            let signature = hmac::sign(&key, b"hello, world");
+            assert!(hmac::verify_with_own_key(&imported, b"hello, world",
+                                              signature.as_ref()).is_ok());
+
+            let mut too_short = vec![0u8; len - 1];
+            assert!(hmac::SigningKey::generate_serializable(d, &rng,
+                                                            &mut too_short)
+                        .is_err());
+            let mut too_long = vec![0u8; len + 1];
+            assert!(hmac::SigningKey::generate_serializable(d, &rng,
+                                                            &mut too_long)
+                        .is_err());
+        }
+    }
+
     #[test]
     pub fn hmac_tests() {
         test::from_file("src/hmac_tests.txt", |section, test_case| {
@@ -427,6 +524,29 @@ mod tests {
             assert_eq!(is_ok, signature.as_ref() == output);
         }
 
+        // Multi-part verification API, one single part.
+        {
+            let mut v_ctx = hmac::VerificationContext::with_key(&v_key);
+            v_ctx.update(input);
+            assert_eq!(is_ok, v_ctx.verify(output).is_ok());
+        }
+
+        // Multi-part verification API, byte by byte, with the signing key.
+        {
+            let mut v_ctx = hmac::VerificationContext::with_own_key(&s_key);
+            for b in input {
+                v_ctx.update(&[*b]);
+            }
+            assert_eq!(is_ok, v_ctx.verify(output).is_ok());
+        }
+
+        // A truncated signature is rejected.
+        {
+            let mut v_ctx = hmac::VerificationContext::with_key(&v_key);
+            v_ctx.update(input);
+            assert!(v_ctx.verify(&output[..(output.len() - 1)]).is_err());
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,256 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The key-based key derivation function (KBKDF) in counter mode of
+//! [NIST SP 800-108 Section 5.1], using HMAC or CMAC as the PRF.
+//!
+//! SP 800-108 leaves the layout of the fixed input data to the protocol.
+//! `derive` uses the layout recommended by SP 800-108, which is also the one
+//! used by Kerberos ([RFC 8009]). `derive_with_counter` lets the caller
+//! place the counter anywhere in the fixed input data and choose its length,
+//! as is needed for e.g. GlobalPlatform SCP03.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{cmac, kbkdf};
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! let key_value = [0u8; 16]; // A secret key in a real application.
+//! let key = kbkdf::Key::from(try!(cmac::Key::new(&cmac::AES_128,
+//!                                                &key_value)));
+//!
+//! let mut derived = [0u8; 32];
+//! try!(kbkdf::derive(&key, b"encryption", b"session 1", &mut derived));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [NIST SP 800-108 Section 5.1]:
+//!     http://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-108.pdf
+//! [RFC 8009]: https://tools.ietf.org/html/rfc8009
+
+use {cmac, error, hmac, polyfill};
+
+/// A key for the PRF of the KDF, K_I in SP 800-108.
+pub struct Key(Prf);
+
+enum Prf {
+    Hmac(hmac::SigningKey),
+    Cmac(cmac::Key),
+}
+
+impl Key {
+    /// The length of the PRF's output, _h_ in SP 800-108, in bytes.
+    pub fn prf_output_len(&self) -> usize {
+        match self.0 {
+            Prf::Hmac(ref key) => key.digest_algorithm().output_len,
+            Prf::Cmac(_) => cmac::TAG_LEN,
+        }
+    }
+
+    fn prf(&self, before: &[&[u8]], counter: &[u8], after: &[&[u8]],
+           out: &mut [u8]) {
+        match self.0 {
+            Prf::Hmac(ref key) => {
+                let mut ctx = hmac::SigningContext::with_key(key);
+                for part in before.iter().chain(&[counter]).chain(after) {
+                    ctx.update(part);
+                }
+                let block = ctx.sign();
+                out.copy_from_slice(&block.as_ref()[..out.len()]);
+            },
+            Prf::Cmac(ref key) => {
+                let mut ctx = cmac::Context::with_key(key);
+                for part in before.iter().chain(&[counter]).chain(after) {
+                    ctx.update(part);
+                }
+                let block = ctx.sign();
+                out.copy_from_slice(&block[..out.len()]);
+            },
+        }
+    }
+}
+
+/// HMAC as the PRF, with the key's digest algorithm.
+impl From<hmac::SigningKey> for Key {
+    fn from(key: hmac::SigningKey) -> Key { Key(Prf::Hmac(key)) }
+}
+
+/// CMAC as the PRF.
+impl From<cmac::Key> for Key {
+    fn from(key: cmac::Key) -> Key { Key(Prf::Cmac(key)) }
+}
+
+/// The length of the counter, _r_ in SP 800-108.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CounterLen {
+    /// An 8-bit counter.
+    Bits8,
+
+    /// A 16-bit counter.
+    Bits16,
+
+    /// A 24-bit counter.
+    Bits24,
+
+    /// A 32-bit counter.
+    Bits32,
+}
+
+impl CounterLen {
+    fn len(&self) -> usize {
+        match *self {
+            CounterLen::Bits8 => 1,
+            CounterLen::Bits16 => 2,
+            CounterLen::Bits24 => 3,
+            CounterLen::Bits32 => 4,
+        }
+    }
+}
+
+/// Fills `out` with the key derived from `key`, `label`, and `context`,
+/// using the fixed input data `label || 0x00 || context || [L]_32` after a
+/// 32-bit counter, where L is the length of `out` in bits.
+///
+/// | Parameter   | SP 800-108 Term
+/// |-------------|---------------------------------------
+/// | `key`       | K_I
+/// | `label`     | Label
+/// | `context`   | Context
+/// | `out.len()` | L / 8
+/// | `out`       | K_O
+///
+/// Fails if `out` is empty or if L doesn't fit in 32 bits.
+pub fn derive(key: &Key, label: &[u8], context: &[u8], out: &mut [u8])
+              -> Result<(), error::Unspecified> {
+    let bits = polyfill::u64_from_usize(out.len()) * 8;
+    if bits > u64::from(u32::max_value()) {
+        return Err(error::Unspecified);
+    }
+    let l = polyfill::slice::be_u8_from_u32(bits as u32);
+    derive_with_counter(key, CounterLen::Bits32, &[],
+                        &[label, &[0], context, &l], out)
+}
+
+/// Fills `out` with the key derived from `key`, where each block _K(i)_ is
+/// the PRF of the concatenation of `before`, the counter _i_ encoded as a
+/// big-endian integer of `counter_len`, and `after`.
+///
+/// The fixed input data is the concatenation of `before` and `after`. The
+/// caller is responsible for encoding any label, context, and length into
+/// it as the protocol requires.
+///
+/// Fails if `out` is empty or if more than 2**_r_ - 1 blocks are needed.
+pub fn derive_with_counter(key: &Key, counter_len: CounterLen,
+                           before: &[&[u8]], after: &[&[u8]], out: &mut [u8])
+                           -> Result<(), error::Unspecified> {
+    let h = key.prf_output_len();
+    let r = counter_len.len();
+    let num_blocks = polyfill::u64_from_usize((out.len() + h - 1) / h);
+    if num_blocks == 0 || num_blocks > (1 << (8 * r)) - 1 {
+        return Err(error::Unspecified);
+    }
+
+    let mut i = 0u32;
+    for chunk in out.chunks_mut(h) {
+        i += 1;
+        let counter = polyfill::slice::be_u8_from_u32(i);
+        key.prf(before, &counter[(4 - r)..], after, chunk);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {cmac, digest, hmac, test};
+
+    fn key_from_test_case(test_case: &mut test::TestCase) -> Key {
+        let prf = test_case.consume_string("PRF");
+        let key_value = test_case.consume_bytes("Key");
+        let digest_alg = match prf.as_str() {
+            "HMAC-SHA256" => &digest::SHA256,
+            "HMAC-SHA384" => &digest::SHA384,
+            "HMAC-SHA512" => &digest::SHA512,
+            "CMAC-AES128" => {
+                return Key::from(cmac::Key::new(&cmac::AES_128, &key_value)
+                                     .unwrap());
+            },
+            "CMAC-AES256" => {
+                return Key::from(cmac::Key::new(&cmac::AES_256, &key_value)
+                                     .unwrap());
+            },
+            _ => unreachable!(),
+        };
+        Key::from(hmac::SigningKey::new(digest_alg, &key_value))
+    }
+
+    #[test]
+    fn test_kbkdf() {
+        test::from_file("src/kbkdf_tests.txt", |section, test_case| {
+            let key = key_from_test_case(test_case);
+            match section {
+                "Counter" => {
+                    let counter_len = match test_case.consume_usize("R") {
+                        8 => CounterLen::Bits8,
+                        16 => CounterLen::Bits16,
+                        24 => CounterLen::Bits24,
+                        32 => CounterLen::Bits32,
+                        _ => unreachable!(),
+                    };
+                    let before = test_case.consume_bytes("Before");
+                    let after = test_case.consume_bytes("After");
+                    let expected = test_case.consume_bytes("Output");
+
+                    let mut out = vec![0u8; expected.len()];
+                    try!(derive_with_counter(&key, counter_len, &[&before],
+                                             &[&after], &mut out));
+                    assert_eq!(out, expected);
+                },
+                "Label" => {
+                    let label = test_case.consume_bytes("Label");
+                    let context = test_case.consume_bytes("Context");
+                    let expected = test_case.consume_bytes("Output");
+
+                    let mut out = vec![0u8; expected.len()];
+                    try!(derive(&key, &label, &context, &mut out));
+                    assert_eq!(out, expected);
+                },
+                _ => unreachable!(),
+            }
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_kbkdf_output_len() {
+        let key = Key::from(cmac::Key::new(&cmac::AES_128, &[0; 16])
+                                .unwrap());
+        assert_eq!(key.prf_output_len(), 16);
+
+        let mut out = vec![0u8; 255 * 16 + 1];
+        assert!(derive_with_counter(&key, CounterLen::Bits8, &[], &[],
+                                    &mut out[..0]).is_err());
+        assert!(derive_with_counter(&key, CounterLen::Bits8, &[], &[],
+                                    &mut out[..(255 * 16)]).is_ok());
+        assert!(derive_with_counter(&key, CounterLen::Bits8, &[], &[],
+                                    &mut out).is_err());
+        assert!(derive_with_counter(&key, CounterLen::Bits16, &[], &[],
+                                    &mut out).is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! KMAC, the Keccak-based MAC specified in [SP 800-185 Section 4].
+//!
+//! Unlike HMAC, KMAC takes a customization string, which separates the uses
+//! of a key in different contexts, and its output can be of any length. The
+//! output length is an input to the calculation, so a shorter tag is not a
+//! prefix of a longer tag for the same message.
+//!
+//! A `Key` is constructed once for a key value and customization string,
+//! which absorbs them into the Keccak state. It can then be used for any
+//! number of `sign` and `verify` operations.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{kmac, rand};
+//! use ring::rand::SecureRandom;
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! let rng = rand::SystemRandom::new();
+//! let mut key_value = [0u8; 32];
+//! try!(rng.fill(&mut key_value));
+//! let key = kmac::Key::new(&kmac::KMAC256, &key_value,
+//!                          b"My Tagged Application");
+//!
+//! let mut tag = [0u8; 32];
+//! try!(kmac::sign(&key, b"hello, world", &mut tag));
+//!
+//! try!(kmac::verify(&key, b"hello, world", &tag));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [SP 800-185 Section 4]:
+//!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+
+use {constant_time, error, polyfill};
+use digest::keccak;
+
+/// A KMAC algorithm.
+pub struct Algorithm {
+    rate: usize,
+}
+
+/// KMAC128. Its key should be at least 16 bytes long.
+pub static KMAC128: Algorithm = Algorithm { rate: (1600 - (2 * 128)) / 8 };
+
+/// KMAC256. Its key should be at least 32 bytes long.
+pub static KMAC256: Algorithm = Algorithm { rate: (1600 - (2 * 256)) / 8 };
+
+/// The minimum length of a tag, in bytes. SP 800-185 Section 8.4.2 forbids
+/// shorter tags.
+pub const MIN_TAG_LEN: usize = 32 / 8;
+
+/// A key for signing and verifying with KMAC.
+#[derive(Clone)]
+pub struct Key {
+    algorithm: &'static Algorithm,
+    sponge: keccak::Sponge,
+}
+
+impl Key {
+    /// Constructs a key from `key_value`, for use with the customization
+    /// string `customization`, which may be empty.
+    pub fn new(algorithm: &'static Algorithm, key_value: &[u8],
+               customization: &[u8]) -> Key {
+        let mut sponge =
+            keccak::cshake(algorithm.rate, b"KMAC", customization);
+        sponge.absorb_bytepad(&[key_value]);
+        Key {
+            algorithm: algorithm,
+            sponge: sponge,
+        }
+    }
+
+    /// The algorithm for the key.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+}
+
+/// A context for multi-step (Init-Update-Finish) KMAC signing.
+///
+/// Use `sign` for single-step KMAC signing.
+#[derive(Clone)]
+pub struct Context {
+    sponge: keccak::Sponge,
+}
+
+impl Context {
+    /// Constructs a new KMAC signing context using the given key.
+    pub fn with_key(key: &Key) -> Context {
+        Context { sponge: key.sponge.clone() }
+    }
+
+    /// Updates the KMAC with all the data in `data`. `update` may be called
+    /// zero or more times until `sign` is called.
+    pub fn update(&mut self, data: &[u8]) { self.sponge.absorb(data); }
+
+    /// Finalizes the KMAC calculation and writes a tag of `tag.len()` bytes
+    /// into `tag`.
+    ///
+    /// Fails if `tag` is shorter than `MIN_TAG_LEN`.
+    pub fn sign(self, tag: &mut [u8]) -> Result<(), error::Unspecified> {
+        let mut output = try!(self.output(tag.len()));
+        output.squeeze(tag);
+        Ok(())
+    }
+
+    // Finishes absorbing the input and returns the sponge to squeeze a tag
+    // of `tag_len` bytes from.
+    fn output(self, tag_len: usize)
+              -> Result<keccak::Sponge, error::Unspecified> {
+        if tag_len < MIN_TAG_LEN {
+            return Err(error::Unspecified);
+        }
+        let mut sponge = self.sponge;
+        let mut buf = [0u8; keccak::MAX_ENCODED_LEN];
+        let tag_bits = polyfill::u64_from_usize(tag_len) * 8;
+        sponge.absorb(keccak::right_encode(tag_bits, &mut buf));
+        Ok(sponge)
+    }
+}
+
+/// Calculates the KMAC of `data` using the key `key` in one step, writing a
+/// tag of `tag.len()` bytes into `tag`.
+///
+/// Fails if `tag` is shorter than `MIN_TAG_LEN`.
+pub fn sign(key: &Key, data: &[u8], tag: &mut [u8])
+            -> Result<(), error::Unspecified> {
+    let mut ctx = Context::with_key(key);
+    ctx.update(data);
+    ctx.sign(tag)
+}
+
+/// Calculates the KMAC of `data`, with an output length of `tag.len()`, and
+/// checks in constant time that it is equal to `tag`.
+///
+/// Fails if the tags differ or if `tag` is shorter than `MIN_TAG_LEN`.
+pub fn verify(key: &Key, data: &[u8], tag: &[u8])
+              -> Result<(), error::Unspecified> {
+    let mut ctx = Context::with_key(key);
+    ctx.update(data);
+    let mut output = try!(ctx.output(tag.len()));
+
+    // Compare the tag a piece at a time so that tags of any length can be
+    // verified, without stopping at the first difference.
+    let mut expected = [0u8; 64];
+    let mut equal = true;
+    for tag in tag.chunks(expected.len()) {
+        let expected = &mut expected[..tag.len()];
+        output.squeeze(expected);
+        equal &= constant_time::verify_slices_are_equal(expected, tag).is_ok();
+    }
+    if !equal {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_kmac() {
+        test::from_file("src/kmac_tests.txt", |section, test_case| {
+            let algorithm = match section {
+                "KMAC128" => &KMAC128,
+                "KMAC256" => &KMAC256,
+                _ => unreachable!(),
+            };
+            let key_value = test_case.consume_bytes("Key");
+            let input = test_case.consume_bytes("Input");
+            let customization = test_case.consume_bytes("Customization");
+            let expected = test_case.consume_bytes("Output");
+
+            let key = Key::new(algorithm, &key_value, &customization);
+            let mut actual = vec![0u8; expected.len()];
+            assert!(sign(&key, &input, &mut actual).is_ok());
+            assert_eq!(actual, expected);
+
+            let mut ctx = Context::with_key(&key);
+            let (a, b) = input.split_at(input.len() / 3);
+            ctx.update(a);
+            ctx.update(b);
+            let mut actual = vec![0u8; expected.len()];
+            assert!(ctx.sign(&mut actual).is_ok());
+            assert_eq!(actual, expected);
+
+            assert!(verify(&key, &input, &expected).is_ok());
+            let mut corrupted = expected.clone();
+            corrupted[0] ^= 1;
+            assert!(verify(&key, &input, &corrupted).is_err());
+            // A truncated tag isn't a valid tag.
+            assert!(verify(&key, &input,
+                           &expected[..(expected.len() - 1)]).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_kmac_short_tag() {
+        let key = Key::new(&KMAC128, &[0u8; 16], b"");
+        let mut tag = [0u8; MIN_TAG_LEN - 1];
+        assert!(sign(&key, b"", &mut tag).is_err());
+        assert!(verify(&key, b"", &tag).is_err());
+        assert!(verify(&key, b"", &[]).is_err());
+    }
+}
@@ -30,8 +30,20 @@
 //!         <code>dev_urandom_fallback</code> feature is disabled, such
 //!         fallbacks will not occur. See the documentation for
 //!         <code>rand::SystemRandom</code> for more details.
+//! <tr><td><code>nonce_reuse_detection</code>
+//!     <td>Enable the <code>ring::aead</code> wrappers that panic when a
+//!         nonce is reused, for testing and fuzzing. Implies
+//!         <code>use_heap</code>.
 //! <tr><td><code>rsa_signing</code>
 //!     <td>Enable RSA signing (<code>RSAKeyPair</code> and related things).
+//! <tr><td><code>rsa_pkcs1_sha1_signing</code>
+//!     <td>Enable RSA PKCS#1 1.5 signing with SHA-1
+//!         (<code>signature::RSA_PKCS1_SHA1</code>), for legacy protocols
+//!         that require it. Implies <code>rsa_signing</code>.
+//! <tr><td><code>rust_bigint</code>
+//!     <td>Do the modular arithmetic for RSA signing and verification in
+//!         Rust, instead of using BoringSSL's C <code>BIGNUM</code> code.
+//!         This is slower than the default implementation.
 //! </table>
 
 #![doc(html_root_url="https://briansmith.org/rustdoc/")]
@@ -120,13 +132,29 @@ mod polyfill;
 #[path = "aead/aead.rs"]
 pub mod aead;
 
+mod aes;
+
 pub mod agreement;
 
+#[cfg(feature = "use_heap")]
+pub mod argon2;
+
+pub mod bcrypt;
+
 #[cfg(feature = "use_heap")]
 mod bits;
 
+#[cfg(feature = "use_heap")]
+mod blake2b;
+
+pub mod blake3;
+mod blowfish;
+
 mod c;
 mod chacha;
+pub mod cipher;
+pub mod cmac;
+pub mod concat_kdf;
 pub mod constant_time;
 
 #[doc(hidden)]
@@ -142,10 +170,24 @@ pub mod error;
 pub mod hkdf;
 pub mod hmac;
 mod init;
+pub mod kbkdf;
+pub mod kmac;
 mod limb;
+pub mod nacl;
 pub mod pbkdf2;
-mod poly1305;
+
+#[cfg(feature = "use_heap")]
+mod phc;
+
+#[cfg(feature = "use_heap")]
+pub mod pkcs8;
+
+pub mod poly1305;
 pub mod rand;
+mod salsa20;
+
+#[cfg(feature = "use_heap")]
+pub mod scrypt;
 
 #[cfg(feature = "use_heap")]
 #[path = "rsa/rsa.rs"]
@@ -158,10 +200,14 @@ mod rsa;
 pub use rsa::GFp_rand_mod;
 
 pub mod signature;
+pub mod siphash;
+pub mod srtp;
 
 #[cfg(any(feature = "use_heap", test))]
 pub mod test;
 
+pub mod universal_hash;
+
 mod private {
     /// Traits that are designed to only be implemented internally in *ring*.
     //
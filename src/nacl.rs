@@ -0,0 +1,271 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! NaCl-compatible `crypto_secretbox` and `crypto_box`.
+//!
+//! `SecretBoxKey` is XSalsa20-Poly1305 (`crypto_secretbox_xsalsa20poly1305`)
+//! and `BoxKey` is X25519 followed by XSalsa20-Poly1305
+//! (`crypto_box_curve25519xsalsa20poly1305`), as described in
+//! [Cryptography in NaCl]. A box is the 16-byte MAC followed by the
+//! ciphertext, which is the format of libsodium's `crypto_secretbox_easy` and
+//! `crypto_box_easy`.
+//!
+//! These exist for interoperability with data that was already sealed with
+//! NaCl or libsodium. New protocols should use `aead::XCHACHA20_POLY1305`,
+//! which authenticates additional data and is faster.
+//!
+//! [Cryptography in NaCl]: https://cr.yp.to/highspeed/naclcrypto-20090310.pdf
+
+use {constant_time, error, poly1305, salsa20};
+use ec::x25519;
+
+/// The length of a `SecretBoxKey`.
+pub const KEY_LEN: usize = salsa20::KEY_LEN;
+
+/// The length of the nonces.
+pub const NONCE_LEN: usize = 192 / 8;
+
+/// The length of the MAC at the start of a box.
+pub const MAC_LEN: usize = poly1305::TAG_LEN;
+
+/// The length of a `crypto_box` public key.
+pub const PUBLIC_KEY_LEN: usize = x25519::X25519_ELEM_SCALAR_PUBLIC_KEY_LEN;
+
+/// The length of a `crypto_box` secret key.
+pub const SECRET_KEY_LEN: usize = x25519::X25519_ELEM_SCALAR_PUBLIC_KEY_LEN;
+
+/// A key for `crypto_secretbox`.
+///
+/// Nothing stops a nonce from being used twice with it; a random nonce for
+/// every box is long enough to be safe.
+pub struct SecretBoxKey {
+    key: salsa20::Key,
+}
+
+impl SecretBoxKey {
+    /// Constructs a new `SecretBoxKey`.
+    ///
+    /// `key_bytes` must be exactly `KEY_LEN` bytes long.
+    pub fn new(key_bytes: &[u8])
+               -> Result<SecretBoxKey, error::Unspecified> {
+        let key_bytes = try!(slice_as_array_ref!(key_bytes, KEY_LEN));
+        Ok(SecretBoxKey { key: salsa20::key_from_bytes(key_bytes) })
+    }
+
+    /// Seals `in_out[MAC_LEN..]` in place, like `crypto_secretbox_easy`.
+    ///
+    /// On input, the first `MAC_LEN` bytes of `in_out` are ignored and the
+    /// rest are the plaintext. On output, `in_out` is the box: the MAC
+    /// followed by the ciphertext. Fails if `in_out` is shorter than
+    /// `MAC_LEN` or `nonce` isn't `NONCE_LEN` bytes long.
+    pub fn seal_in_place(&self, nonce: &[u8], in_out: &mut [u8])
+                         -> Result<(), error::Unspecified> {
+        if in_out.len() < MAC_LEN {
+            return Err(error::Unspecified);
+        }
+        let (subkey, nonce) = try!(self.subkey_and_nonce(nonce));
+        let mac_key = salsa20::derive_poly1305_key(&subkey, &nonce);
+        let (mac, ciphertext) = in_out.split_at_mut(MAC_LEN);
+        salsa20::salsa20_xor_in_place(&subkey, &nonce,
+                                      poly1305::KEY_LEN as u64, ciphertext);
+        poly1305::sign(mac_key, ciphertext,
+                       try!(slice_as_array_ref_mut!(mac, MAC_LEN)));
+        Ok(())
+    }
+
+    /// Opens the box `in_out` in place, like `crypto_secretbox_open_easy`,
+    /// returning the plaintext, which is `in_out[MAC_LEN..]`.
+    ///
+    /// `in_out` is left unmodified if the box isn't authentic.
+    pub fn open_in_place<'a>(&self, nonce: &[u8], in_out: &'a mut [u8])
+                             -> Result<&'a mut [u8], error::Unspecified> {
+        if in_out.len() < MAC_LEN {
+            return Err(error::Unspecified);
+        }
+        let (subkey, nonce) = try!(self.subkey_and_nonce(nonce));
+        let mac_key = salsa20::derive_poly1305_key(&subkey, &nonce);
+        let (mac, ciphertext) = in_out.split_at_mut(MAC_LEN);
+        let mut calculated_mac = [0u8; MAC_LEN];
+        poly1305::sign(mac_key, ciphertext, &mut calculated_mac);
+        try!(constant_time::verify_slices_are_equal(&calculated_mac, mac));
+        salsa20::salsa20_xor_in_place(&subkey, &nonce,
+                                      poly1305::KEY_LEN as u64, ciphertext);
+        Ok(ciphertext)
+    }
+
+    /// Derives the Salsa20 subkey from the first 128 bits of the nonce using
+    /// HSalsa20. The Salsa20 nonce is the remaining 64 bits.
+    fn subkey_and_nonce(&self, nonce: &[u8])
+            -> Result<(salsa20::Key, [u8; salsa20::NONCE_LEN]),
+                      error::Unspecified> {
+        let nonce = try!(slice_as_array_ref!(nonce, NONCE_LEN));
+        let subkey = salsa20::hsalsa20(
+            &self.key,
+            try!(slice_as_array_ref!(&nonce[..salsa20::HSALSA20_NONCE_LEN],
+                                     salsa20::HSALSA20_NONCE_LEN)));
+        let mut salsa20_nonce = [0u8; salsa20::NONCE_LEN];
+        salsa20_nonce.copy_from_slice(&nonce[salsa20::HSALSA20_NONCE_LEN..]);
+        Ok((subkey, salsa20_nonce))
+    }
+}
+
+/// A key for `crypto_box` between the owner of a secret key and the owner of
+/// a peer's public key.
+///
+/// The same key is used in both directions, so the two parties must never
+/// use the same nonce, e.g. by using random nonces.
+pub struct BoxKey {
+    secretbox_key: SecretBoxKey,
+}
+
+impl BoxKey {
+    /// Computes the shared key, like `crypto_box_beforenm`.
+    ///
+    /// Fails if either key isn't 32 bytes long or if the result of X25519 is
+    /// zero, as libsodium does.
+    pub fn new(my_secret_key: &[u8], peer_public_key: &[u8])
+               -> Result<BoxKey, error::Unspecified> {
+        let my_secret_key =
+            try!(slice_as_array_ref!(my_secret_key, SECRET_KEY_LEN));
+        let peer_public_key =
+            try!(slice_as_array_ref!(peer_public_key, PUBLIC_KEY_LEN));
+        let mut shared = [0u8; x25519::X25519_ELEM_SCALAR_PUBLIC_KEY_LEN];
+        try!(x25519::x25519(&mut shared, my_secret_key, peer_public_key));
+        let key = salsa20::hsalsa20(&salsa20::key_from_bytes(&shared),
+                                    &[0u8; salsa20::HSALSA20_NONCE_LEN]);
+        Ok(BoxKey { secretbox_key: SecretBoxKey { key: key } })
+    }
+
+    /// Like `SecretBoxKey::seal_in_place`, i.e. `crypto_box_easy`.
+    pub fn seal_in_place(&self, nonce: &[u8], in_out: &mut [u8])
+                         -> Result<(), error::Unspecified> {
+        self.secretbox_key.seal_in_place(nonce, in_out)
+    }
+
+    /// Like `SecretBoxKey::open_in_place`, i.e. `crypto_box_open_easy`.
+    pub fn open_in_place<'a>(&self, nonce: &[u8], in_out: &'a mut [u8])
+                             -> Result<&'a mut [u8], error::Unspecified> {
+        self.secretbox_key.open_in_place(nonce, in_out)
+    }
+}
+
+/// Computes the public key for `secret_key`, like `crypto_scalarmult_base`.
+///
+/// `secret_key` must be `SECRET_KEY_LEN` bytes long and `public_key_out`
+/// must be `PUBLIC_KEY_LEN` bytes long.
+pub fn public_key_from_secret_key(secret_key: &[u8],
+                                  public_key_out: &mut [u8])
+                                  -> Result<(), error::Unspecified> {
+    let secret_key = try!(slice_as_array_ref!(secret_key, SECRET_KEY_LEN));
+    let public_key_out =
+        try!(slice_as_array_ref_mut!(public_key_out, PUBLIC_KEY_LEN));
+    x25519::public_from_private(public_key_out, secret_key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    fn test_secretbox() {
+        test::from_file("src/nacl_secretbox_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key = test_case.consume_bytes("Key");
+            let nonce = test_case.consume_bytes("Nonce");
+            let plaintext = test_case.consume_bytes("Plaintext");
+            let sealed = test_case.consume_bytes("Box");
+            let key = SecretBoxKey::new(&key).unwrap();
+            check_seal_and_open(&nonce, &plaintext, &sealed,
+                                |nonce, in_out| key.seal_in_place(nonce,
+                                                                  in_out),
+                                |nonce, in_out| key.open_in_place(nonce,
+                                                                  in_out)
+                                                   .map(|p| p.len()));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_box() {
+        test::from_file("src/nacl_box_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let secret_key = test_case.consume_bytes("SecretKey");
+            let public_key = test_case.consume_bytes("PublicKey");
+            let peer_secret_key = test_case.consume_bytes("PeerSecretKey");
+            let peer_public_key = test_case.consume_bytes("PeerPublicKey");
+            let nonce = test_case.consume_bytes("Nonce");
+            let plaintext = test_case.consume_bytes("Plaintext");
+            let sealed = test_case.consume_bytes("Box");
+
+            let mut computed_public_key = [0u8; PUBLIC_KEY_LEN];
+            public_key_from_secret_key(&secret_key, &mut computed_public_key)
+                .unwrap();
+            assert_eq!(&computed_public_key[..], &public_key[..]);
+
+            // Both parties compute the same key.
+            let key = BoxKey::new(&secret_key, &peer_public_key).unwrap();
+            let peer_key = BoxKey::new(&peer_secret_key, &public_key).unwrap();
+            check_seal_and_open(&nonce, &plaintext, &sealed,
+                                |nonce, in_out| key.seal_in_place(nonce,
+                                                                  in_out),
+                                |nonce, in_out| peer_key.open_in_place(nonce,
+                                                                       in_out)
+                                                        .map(|p| p.len()));
+            Ok(())
+        });
+    }
+
+    fn check_seal_and_open<S, O>(nonce: &[u8], plaintext: &[u8],
+                                 sealed: &[u8], seal: S, open: O)
+            where S: Fn(&[u8], &mut [u8]) -> Result<(), error::Unspecified>,
+                  O: Fn(&[u8], &mut [u8])
+                        -> Result<usize, error::Unspecified> {
+        let mut in_out = vec![0u8; MAC_LEN];
+        in_out.extend_from_slice(plaintext);
+        seal(nonce, &mut in_out).unwrap();
+        assert_eq!(&in_out[..], sealed);
+
+        assert_eq!(open(nonce, &mut in_out), Ok(plaintext.len()));
+        assert_eq!(&in_out[MAC_LEN..], plaintext);
+
+        // Any modification of the box is detected, and the box is left
+        // unmodified.
+        for i in 0..sealed.len() {
+            let mut in_out = sealed.to_vec();
+            in_out[i] ^= 1;
+            let modified = in_out.clone();
+            assert!(open(nonce, &mut in_out).is_err());
+            assert_eq!(in_out, modified);
+        }
+
+        let mut in_out = sealed.to_vec();
+        assert!(open(&nonce[1..], &mut in_out).is_err());
+        assert!(open(nonce, &mut in_out[..(MAC_LEN - 1)]).is_err());
+        assert!(seal(nonce, &mut [0u8; MAC_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn test_box_key_rejects_bad_keys() {
+        assert!(BoxKey::new(&[1; SECRET_KEY_LEN], &[9; PUBLIC_KEY_LEN - 1])
+                    .is_err());
+        assert!(BoxKey::new(&[1; SECRET_KEY_LEN - 1], &[9; PUBLIC_KEY_LEN])
+                    .is_err());
+        // A low-order point gives an all-zero shared secret.
+        assert!(BoxKey::new(&[1; SECRET_KEY_LEN], &[0; PUBLIC_KEY_LEN])
+                    .is_err());
+        assert!(SecretBoxKey::new(&[0; KEY_LEN + 1]).is_err());
+    }
+}
@@ -17,6 +17,20 @@
 //! Use `derive` to derive PBKDF2 outputs. Use `verify` to verify secret
 //! against previously-derived outputs.
 //!
+//! `hash_encoded` and `verify_encoded` do the same with hashes in the
+//! [PHC string format], which records the parameters and the salt along
+//! with the hash, so that they can be stored in a single field and read by
+//! other implementations.
+//!
+//! `derive_parallel` computes the blocks of long outputs on several threads
+//! at once.
+//!
+//! `derive_okm` returns the output as an `Okm` for a particular key type,
+//! like `hkdf::Prk::expand` does, so that a key, e.g. an
+//! `aead::UnboundKey` or an `hmac::SigningKey`, can be constructed directly
+//! from it without the derived bytes passing through a buffer of the
+//! caller's.
+//!
 //! PBKDF2 is specified in [RFC 2898 Section 5.2] with test vectors given in
 //! [RFC 6070]. See also [NIST Special Publication 800-132].
 //!
@@ -24,6 +38,8 @@
 //! [RFC 6070]: https://tools.ietf.org/html/rfc6070
 //! [NIST Special Publication 800-132]:
 //!    http://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-132.pdf
+//! [PHC string format]:
+//!     https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
 //!
 //! # Examples
 //!
@@ -111,8 +127,28 @@
 //!     // An attempt to log in with the right password succeeds.
 //!     assert!(db.verify_password("alice", "@74d7]404j|W}6u").is_ok());
 //! }
+//! ```
+//!
+//! ## Choosing the Iteration Count
+//!
+//! Rather than hard-coding an iteration count that becomes too small as
+//! hardware gets faster, an application can measure the host with
+//! `calibrate_iterations` when it is first configured and store the result
+//! alongside the configuration:
+//!
+//! ```
+//! use ring::pbkdf2;
+//! use std::time::Duration;
+//!
+//! let iterations = pbkdf2::calibrate_iterations(&pbkdf2::HMAC_SHA256,
+//!                                               Duration::from_millis(10));
+//! assert!(iterations >= 1);
+//! ```
 
-use {constant_time, digest, error, hmac, polyfill};
+use {constant_time, core, digest, error, hkdf, hmac, polyfill, std};
+
+#[cfg(feature = "use_heap")]
+use phc;
 
 /// Fills `out` with the key derived using PBKDF2 with the given inputs.
 ///
@@ -186,6 +222,140 @@ fn derive_block(secret: &hmac::SigningKey, iterations: usize, salt: &[u8],
     }
 }
 
+/// Fills `out` like `derive`, except that the blocks of the output are
+/// computed on up to `threads` threads at once.
+///
+/// Each block of the output is as long as the PRF's digest, and the blocks
+/// are independent, so this only helps when `out` is longer than that, e.g.
+/// a 64-byte key derived with `HMAC_SHA256` has two blocks that take the
+/// same time to compute on two threads as one block does on one thread. It
+/// doesn't make `derive` any faster for an attacker, who can already
+/// compute the blocks in parallel.
+///
+/// # Panics
+///
+/// `derive_parallel` panics under the same conditions as `derive`.
+#[cfg(feature = "use_heap")]
+pub fn derive_parallel(prf: &'static PRF, iterations: usize, salt: &[u8],
+                       secret: &[u8], out: &mut [u8], threads: usize) {
+    assert!(iterations >= 1);
+
+    let output_len = prf.digest_alg.output_len;
+    let num_blocks = (out.len() + output_len - 1) / output_len;
+    if threads < 2 || num_blocks < 2 {
+        derive(prf, iterations, salt, secret, out);
+        return;
+    }
+    assert!(polyfill::u64_from_usize(num_blocks) <=
+                u64::from(u32::max_value()),
+            "derived key too long");
+
+    let secret =
+        std::sync::Arc::new(hmac::SigningKey::new(prf.digest_alg, secret));
+    let salt = std::sync::Arc::new(salt.to_vec());
+
+    let blocks_per_thread = (num_blocks + threads - 1) / threads;
+    let group_len = blocks_per_thread * output_len;
+    let mut idx: u32 = 0;
+    let threads: std::vec::Vec<_> = out.chunks(group_len).map(|group| {
+        let first_idx = idx;
+        for _ in group.chunks(output_len) {
+            idx = idx.checked_add(1).expect("derived key too long");
+        }
+        let group_len = group.len();
+        let secret = secret.clone();
+        let salt = salt.clone();
+        std::thread::spawn(move || {
+            let mut group = vec![0u8; group_len];
+            let mut idx = first_idx;
+            for chunk in group.chunks_mut(output_len) {
+                idx = idx.checked_add(1).expect("derived key too long");
+                derive_block(&secret, iterations, &salt, idx, chunk);
+            }
+            group
+        })
+    }).collect();
+
+    for (group, thread) in out.chunks_mut(group_len).zip(threads) {
+        group.copy_from_slice(&thread.join().unwrap());
+    }
+}
+
+/// Returns the key derived using PBKDF2 with the given inputs as the output
+/// keying material for a key of type `key_type`.
+///
+/// The output is only computed when it is used to fill a key, with
+/// `Okm::fill` or through a `From<pbkdf2::Okm>` implementation of the key
+/// type; `aead::UnboundKey` and `hmac::SigningKey` have them for the key
+/// types `&'static aead::Algorithm` and `hkdf::PrkType`. The parameters are
+/// the same as `derive`'s, with `key_type.len()` as dkLen.
+///
+/// Fails if `key_type.len()` is larger than (2**32 - 1) * the PRF digest
+/// length, per the PBKDF2 specification.
+///
+/// # Panics
+///
+/// `derive_okm` panics if `iterations < 1`.
+pub fn derive_okm<'a, K: hkdf::KeyType>(prf: &'static PRF, iterations: usize,
+                                         salt: &'a [u8], secret: &'a [u8],
+                                         key_type: K)
+        -> Result<Okm<'a, K>, error::Unspecified> {
+    assert!(iterations >= 1);
+
+    let max_len = u64::from(u32::max_value()) *
+                  polyfill::u64_from_usize(prf.digest_alg.output_len);
+    if polyfill::u64_from_usize(key_type.len()) > max_len {
+        return Err(error::Unspecified);
+    }
+    Ok(Okm {
+        prf: prf,
+        iterations: iterations,
+        salt: salt,
+        secret: secret,
+        key_type: key_type,
+    })
+}
+
+/// The output keying material of PBKDF2 for a particular key type, as
+/// returned by `derive_okm`.
+///
+/// The output is only computed when it is used to fill a key, with `fill`
+/// or through a `From<pbkdf2::Okm>` implementation of the key type.
+pub struct Okm<'a, K: hkdf::KeyType> {
+    prf: &'static PRF,
+    iterations: usize,
+    salt: &'a [u8],
+    secret: &'a [u8],
+    key_type: K,
+}
+
+impl<'a, K: hkdf::KeyType> Okm<'a, K> {
+    /// The key type that the output is for.
+    #[inline(always)]
+    pub fn key_type(&self) -> &K { &self.key_type }
+
+    /// Fills `out` with the output keying material.
+    ///
+    /// Fails if `out.len()` isn't `self.key_type().len()`.
+    pub fn fill(self, out: &mut [u8]) -> Result<(), error::Unspecified> {
+        if out.len() != self.key_type.len() {
+            return Err(error::Unspecified);
+        }
+        derive(self.prf, self.iterations, self.salt, self.secret, out);
+        Ok(())
+    }
+}
+
+impl<'a> From<Okm<'a, hkdf::PrkType>> for hmac::SigningKey {
+    fn from(okm: Okm<'a, hkdf::PrkType>) -> hmac::SigningKey {
+        let digest_alg = okm.key_type.digest_algorithm();
+        let mut buf = [0u8; digest::MAX_OUTPUT_LEN];
+        let buf = &mut buf[..digest_alg.output_len];
+        okm.fill(buf).unwrap();
+        hmac::SigningKey::new(digest_alg, buf)
+    }
+}
+
 /// Verifies that a previously-derived (e.g., using `derive`) PBKDF2 value
 /// matches the PBKDF2 value derived from the other inputs.
 ///
@@ -252,20 +422,152 @@ pub fn verify(prf: &'static PRF, iterations: usize, salt: &[u8],
     Ok(())
 }
 
+/// Returns the PBKDF2 hash of `secret` in the PHC string format, e.g.
+/// `$pbkdf2-sha256$i=600000,l=32$<salt>$<hash>`, with a hash of `out_len`
+/// bytes.
+///
+/// The salt must be unique for each hash; it should be generated with a
+/// `SecureRandom`.
+///
+/// Fails if `iterations` is zero or doesn't fit in 32 bits, or if `out_len`
+/// is zero or doesn't fit in 32 bits.
+#[cfg(feature = "use_heap")]
+pub fn hash_encoded(prf: &'static PRF, iterations: usize, salt: &[u8],
+                    secret: &[u8], out_len: usize)
+                    -> Result<std::string::String, error::Unspecified> {
+    let max = u64::from(u32::max_value());
+    if iterations < 1 || polyfill::u64_from_usize(iterations) > max ||
+       out_len < 1 || polyfill::u64_from_usize(out_len) > max {
+        return Err(error::Unspecified);
+    }
+    let mut out = vec![0u8; out_len];
+    derive(prf, iterations, salt, secret, &mut out);
+    Ok(phc::encode(prf.phc_id, None,
+                   &[("i", iterations as u32), ("l", out_len as u32)], salt,
+                   &out))
+}
+
+/// Verifies that `secret` matches `encoded`, a PBKDF2 hash in the PHC string
+/// format, e.g. one returned by `hash_encoded`.
+///
+/// `$pbkdf2-sha256$` and `$pbkdf2-sha512$` hashes are accepted. The `l`
+/// (length) parameter is optional, but it must match the length of the hash
+/// if it is present. The comparison is done in constant time to prevent
+/// timing attacks.
+#[cfg(feature = "use_heap")]
+pub fn verify_encoded(secret: &[u8], encoded: &str)
+                      -> Result<(), error::Unspecified> {
+    let decoded = try!(phc::decode(encoded));
+    let prf = match decoded.id {
+        "pbkdf2-sha256" => &HMAC_SHA256,
+        "pbkdf2-sha512" => &HMAC_SHA512,
+        _ => {
+            return Err(error::Unspecified);
+        },
+    };
+    if decoded.version.is_some() {
+        return Err(error::Unspecified);
+    }
+    let mut params = decoded.params;
+    let iterations = try!(params.next("i"));
+    let len = try!(params.next_optional("l"));
+    try!(params.finish());
+    if iterations < 1 {
+        return Err(error::Unspecified);
+    }
+    if let Some(len) = len {
+        if polyfill::u64_from_usize(decoded.hash.len()) != u64::from(len) {
+            return Err(error::Unspecified);
+        }
+    }
+    verify(prf, iterations as usize, &decoded.salt, secret, &decoded.hash)
+}
+
+/// Measures how fast `derive` runs on this host and returns the iteration
+/// count for which deriving one block of output (`prf`'s digest length)
+/// takes approximately `target`.
+///
+/// The measurement runs `derive` with increasing iteration counts until a
+/// run takes a meaningful fraction of `target`, so `calibrate_iterations`
+/// itself takes less than `target`, and never much more than 150
+/// milliseconds. The result is an estimate; it varies with the load on the
+/// host, so applications should calibrate once, e.g. during setup, and store
+/// the result rather than calibrating on every use. Deriving more than one
+/// block of output takes proportionally longer.
+///
+/// The result is never less than one.
+pub fn calibrate_iterations(prf: &'static PRF, target: std::time::Duration)
+                            -> usize {
+    let target_nanos = nanos_from_duration(target);
+    let sample_nanos =
+        core::cmp::min(target_nanos / 4, CALIBRATION_MAX_SAMPLE_NANOS);
+    if sample_nanos == 0 {
+        return 1;
+    }
+
+    let mut out = [0u8; digest::MAX_OUTPUT_LEN];
+    let out = &mut out[..prf.digest_alg.output_len];
+    let mut iterations = CALIBRATION_INITIAL_ITERATIONS;
+    loop {
+        let start = std::time::Instant::now();
+        derive(prf, iterations, b"calibration salt", b"calibration secret",
+               out);
+        let elapsed_nanos = nanos_from_duration(start.elapsed());
+
+        if elapsed_nanos >= sample_nanos {
+            let iterations = polyfill::u64_from_usize(iterations);
+            let estimate = match iterations.checked_mul(target_nanos) {
+                Some(product) => product / elapsed_nanos,
+                // The product only overflows when `target` is far longer
+                // than the measurement, so the ratio is at least one and
+                // dropping its fractional part loses at most half of the
+                // estimate.
+                None => iterations.saturating_mul(target_nanos / elapsed_nanos),
+            };
+            let estimate = polyfill::usize_from_u64(estimate)
+                               .unwrap_or(usize::max_value());
+            return core::cmp::max(estimate, 1);
+        }
+
+        iterations = match iterations.checked_mul(2) {
+            Some(iterations) => iterations,
+            None => { return usize::max_value(); },
+        };
+    }
+}
+
+const CALIBRATION_INITIAL_ITERATIONS: usize = 1024;
+const CALIBRATION_MAX_SAMPLE_NANOS: u64 = 50 * 1_000_000;
+
+fn nanos_from_duration(d: std::time::Duration) -> u64 {
+    d.as_secs()
+        .saturating_mul(1_000_000_000)
+        .saturating_add(u64::from(d.subsec_nanos()))
+}
+
 /// A PRF algorithm for use with `derive` and `verify`.
 pub struct PRF {
     digest_alg: &'static digest::Algorithm,
+
+    #[cfg_attr(not(feature = "use_heap"), allow(dead_code))]
+    phc_id: &'static str,
 }
 
 /// HMAC-SHA256.
-pub static HMAC_SHA256: PRF = PRF { digest_alg: &digest::SHA256 };
+pub static HMAC_SHA256: PRF = PRF {
+    digest_alg: &digest::SHA256,
+    phc_id: "pbkdf2-sha256",
+};
 
 /// HMAC-SHA512.
-pub static HMAC_SHA512: PRF = PRF { digest_alg: &digest::SHA512 };
+pub static HMAC_SHA512: PRF = PRF {
+    digest_alg: &digest::SHA512,
+    phc_id: "pbkdf2-sha512",
+};
 
 #[cfg(test)]
 mod tests {
-    use {error, pbkdf2, test};
+    use {aead, error, hkdf, hmac, pbkdf2, polyfill, test};
 
     #[test]
     pub fn pkbdf2_tests() {
@@ -296,10 +598,131 @@ mod tests {
                            verify_expected_result.is_ok() || dk.is_empty());
             }
 
+            #[cfg(feature = "use_heap")]
+            {
+                let mut expected = vec![0u8; dk.len()];
+                pbkdf2::derive(prf, iterations, &salt, &secret, &mut expected);
+                let mut out = vec![0u8; dk.len()];
+                pbkdf2::derive_parallel(prf, iterations, &salt, &secret,
+                                        &mut out, 4);
+                assert_eq!(out, expected);
+            }
+
             assert_eq!(pbkdf2::verify(prf, iterations, &salt, &secret, &dk),
                        verify_expected_result);
 
             Ok(())
         });
     }
+
+    #[cfg(feature = "use_heap")]
+    #[test]
+    pub fn pbkdf2_derive_parallel_tests() {
+        for prf in &[&pbkdf2::HMAC_SHA256, &pbkdf2::HMAC_SHA512] {
+            for &len in &[1, 32, 33, 64, 65, 100, 320] {
+                let mut expected = vec![0u8; len];
+                pbkdf2::derive(prf, 3, b"salt", b"password", &mut expected);
+                for &threads in &[0, 1, 2, 3, 4, 16] {
+                    let mut out = vec![0xff; len];
+                    pbkdf2::derive_parallel(prf, 3, b"salt", b"password",
+                                            &mut out, threads);
+                    assert_eq!(out, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn pbkdf2_calibrate_iterations_tests() {
+        use std::time::{Duration, Instant};
+
+        assert_eq!(pbkdf2::calibrate_iterations(&pbkdf2::HMAC_SHA256,
+                                                Duration::from_millis(0)), 1);
+
+        let target = Duration::from_millis(20);
+        let start = Instant::now();
+        let iterations =
+            pbkdf2::calibrate_iterations(&pbkdf2::HMAC_SHA512, target);
+        assert!(iterations >= 1);
+        // Calibration shouldn't take much longer than the sample it needs.
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    struct OutputLen(usize);
+
+    impl hkdf::KeyType for OutputLen {
+        fn len(&self) -> usize { self.0 }
+    }
+
+    #[test]
+    pub fn pbkdf2_derive_okm_tests() {
+        for prf in &[&pbkdf2::HMAC_SHA256, &pbkdf2::HMAC_SHA512] {
+            let derive_okm = |key_type| {
+                pbkdf2::derive_okm(prf, 3, b"salt", b"password", key_type)
+            };
+
+            for &len in &[1, 32, 33, 64, 65, 100] {
+                let mut expected = vec![0u8; len];
+                pbkdf2::derive(prf, 3, b"salt", b"password", &mut expected);
+                let mut out = vec![0u8; len];
+                derive_okm(OutputLen(len)).unwrap().fill(&mut out).unwrap();
+                assert_eq!(out, expected);
+
+                let mut out = vec![0u8; len + 1];
+                assert!(derive_okm(OutputLen(len)).unwrap().fill(&mut out)
+                            .is_err());
+                assert!(derive_okm(OutputLen(len)).unwrap()
+                            .fill(&mut out[..len - 1]).is_err());
+            }
+
+            let output_len = prf.digest_alg.output_len;
+            let max_len = u64::from(u32::max_value()) *
+                          polyfill::u64_from_usize(output_len);
+            if let Some(max_len) = polyfill::usize_from_u64(max_len) {
+                assert!(derive_okm(OutputLen(max_len)).is_ok());
+                assert!(derive_okm(OutputLen(max_len + 1)).is_err());
+            }
+        }
+    }
+
+    #[test]
+    pub fn pbkdf2_hmac_key_from_okm_test() {
+        let prf = &pbkdf2::HMAC_SHA256;
+        let mut key_bytes = [0u8; 32];
+        pbkdf2::derive(prf, 3, b"salt", b"password", &mut key_bytes);
+        let expected = hmac::SigningKey::new(prf.digest_alg, &key_bytes);
+
+        let actual: hmac::SigningKey =
+            pbkdf2::derive_okm(prf, 3, b"salt", b"password",
+                               hkdf::HKDF_SHA256_PRK).unwrap().into();
+        assert_eq!(hmac::sign(&actual, b"message").as_ref(),
+                   hmac::sign(&expected, b"message").as_ref());
+    }
+
+    #[test]
+    pub fn pbkdf2_aead_key_from_okm_test() {
+        let prf = &pbkdf2::HMAC_SHA256;
+        let aead_alg = &aead::AES_256_GCM;
+        let mut key_bytes = vec![0u8; aead_alg.key_len()];
+        pbkdf2::derive(prf, 3, b"salt", b"password", &mut key_bytes);
+        let expected = aead::LessSafeKey::new(aead_alg, &key_bytes).unwrap();
+
+        let unbound: aead::UnboundKey =
+            pbkdf2::derive_okm(prf, 3, b"salt", b"password", aead_alg)
+                .unwrap().into();
+        let actual = aead::LessSafeKey::from(unbound);
+
+        // The keys are the same if they produce the same ciphertext.
+        let nonce = vec![0u8; aead_alg.nonce_len()];
+        let overhead = aead_alg.max_overhead_len();
+        let mut a = vec![0u8; 16 + overhead];
+        let mut b = vec![0u8; 16 + overhead];
+        let a_len =
+            aead::seal_in_place(&expected, &nonce, &mut a, overhead, b"")
+                .unwrap();
+        let b_len =
+            aead::seal_in_place(&actual, &nonce, &mut b, overhead, b"")
+                .unwrap();
+        assert_eq!(&a[..a_len], &b[..b_len]);
+    }
 }
@@ -0,0 +1,340 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The [PHC string format] for password hashes, e.g.
+//! `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`, which is used by
+//! `argon2`, `pbkdf2`, and `scrypt`.
+//!
+//! The salt and the hash are encoded in base 64 with the alphabet of
+//! RFC 4648 Section 4, without padding. Decoding is strict: a value that
+//! could be encoded in more than one way, e.g. a decimal number with a
+//! leading zero, is rejected.
+//!
+//! [PHC string format]:
+//!     https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+
+use {core, error, std};
+
+/// Returns `$<id>[$v=<version>]$<params>$<salt>$<hash>`, where `params` is a
+/// comma-separated list of `name=value` pairs in the given order.
+pub fn encode(id: &str, version: Option<u32>, params: &[(&str, u32)],
+              salt: &[u8], hash: &[u8]) -> std::string::String {
+    let mut encoded = std::string::String::from("$");
+    encoded.push_str(id);
+    if let Some(version) = version {
+        encoded.push_str(&format!("$v={}", version));
+    }
+    for (i, &(name, value)) in params.iter().enumerate() {
+        encoded.push(if i == 0 { '$' } else { ',' });
+        encoded.push_str(&format!("{}={}", name, value));
+    }
+    encoded.push('$');
+    encode_base64(salt, &mut encoded);
+    encoded.push('$');
+    encode_base64(hash, &mut encoded);
+    encoded
+}
+
+/// The fields of a password hash in the PHC string format.
+pub struct Decoded<'a> {
+    pub id: &'a str,
+    pub version: Option<u32>,
+    pub params: Params<'a>,
+    pub salt: std::vec::Vec<u8>,
+    pub hash: std::vec::Vec<u8>,
+}
+
+/// Decodes `encoded`, which must have a parameter list, a salt, and a hash.
+pub fn decode<'a>(encoded: &'a str)
+                  -> Result<Decoded<'a>, error::Unspecified> {
+    let mut fields = encoded.split('$');
+    if try!(next_field(&mut fields)) != "" {
+        return Err(error::Unspecified);
+    }
+    let id = try!(next_field(&mut fields));
+    if id.is_empty() {
+        return Err(error::Unspecified);
+    }
+    let mut field = try!(next_field(&mut fields));
+    let version = if field.starts_with("v=") {
+        let version = try!(decimal(&field[2..]));
+        field = try!(next_field(&mut fields));
+        Some(version)
+    } else {
+        None
+    };
+    let params = Params { remaining: Some(field) };
+    let salt = try!(decode_base64(try!(next_field(&mut fields))));
+    let hash = try!(decode_base64(try!(next_field(&mut fields))));
+    if fields.next().is_some() {
+        return Err(error::Unspecified);
+    }
+    Ok(Decoded {
+        id: id,
+        version: version,
+        params: params,
+        salt: salt,
+        hash: hash,
+    })
+}
+
+/// The parameter list of a password hash, which is consumed in order.
+pub struct Params<'a> {
+    remaining: Option<&'a str>,
+}
+
+impl<'a> Params<'a> {
+    /// Consumes the next parameter, which must be named `name`, and returns
+    /// its value.
+    pub fn next(&mut self, name: &str) -> Result<u32, error::Unspecified> {
+        let remaining = try!(self.remaining.ok_or(error::Unspecified));
+        let (param, rest) = match remaining.find(',') {
+            Some(i) => (&remaining[..i], Some(&remaining[(i + 1)..])),
+            None => (remaining, None),
+        };
+        if !is_named(param, name) {
+            return Err(error::Unspecified);
+        }
+        self.remaining = rest;
+        decimal(&param[(name.len() + 1)..])
+    }
+
+    /// Consumes the next parameter if it is named `name`, and returns its
+    /// value.
+    pub fn next_optional(&mut self, name: &str)
+                         -> Result<Option<u32>, error::Unspecified> {
+        match self.remaining {
+            Some(remaining) if is_named(remaining, name) => {
+                Ok(Some(try!(self.next(name))))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Fails if any parameters haven't been consumed.
+    pub fn finish(self) -> Result<(), error::Unspecified> {
+        match self.remaining {
+            Some(_) => Err(error::Unspecified),
+            None => Ok(()),
+        }
+    }
+}
+
+fn next_field<'a>(fields: &mut core::str::Split<'a, char>)
+                  -> Result<&'a str, error::Unspecified> {
+    fields.next().ok_or(error::Unspecified)
+}
+
+fn is_named(param: &str, name: &str) -> bool {
+    param.starts_with(name) && param[name.len()..].starts_with('=')
+}
+
+// Parses a decimal number without a sign or leading zeros.
+fn decimal(s: &str) -> Result<u32, error::Unspecified> {
+    if s.is_empty() || !s.bytes().all(|b| b >= b'0' && b <= b'9') ||
+       (s.len() > 1 && s.starts_with('0')) {
+        return Err(error::Unspecified);
+    }
+    s.parse().map_err(|_| error::Unspecified)
+}
+
+const ALPHABET: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(input: &[u8], out: &mut std::string::String) {
+    for chunk in input.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let word = (u32::from(block[0]) << 16) | (u32::from(block[1]) << 8) |
+                   u32::from(block[2]);
+        for i in 0..(chunk.len() + 1) {
+            let index = (word >> (18 - 6 * i)) & 0x3f;
+            out.push(ALPHABET[index as usize] as char);
+        }
+    }
+}
+
+fn decode_base64(input: &str) -> Result<std::vec::Vec<u8>, error::Unspecified> {
+    let input = input.as_bytes();
+    if input.len() % 4 == 1 {
+        return Err(error::Unspecified);
+    }
+    let mut out = std::vec::Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        let mut word = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = match ALPHABET.iter().position(|a| a == c) {
+                Some(value) => value as u32,
+                None => {
+                    return Err(error::Unspecified);
+                },
+            };
+            word |= value << (18 - 6 * i);
+        }
+        let len = chunk.len() - 1;
+        // The unused low-order bits of the last character must be zero.
+        if word & (0xffffff >> (8 * len)) != 0 {
+            return Err(error::Unspecified);
+        }
+        for i in 0..len {
+            out.push((word >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {argon2, core, pbkdf2, scrypt, std, test};
+
+    #[test]
+    fn test_phc() {
+        test::from_file("src/phc_tests.txt", |section, test_case| {
+            let secret = test_case.consume_bytes("Password");
+            let expected = test_case.consume_bytes("Hash");
+            let expected = core::str::from_utf8(&expected).unwrap();
+
+            let verify = match section {
+                "Argon2" => argon2::verify_encoded,
+                "PBKDF2" => pbkdf2::verify_encoded,
+                "scrypt" => scrypt::verify_encoded,
+                _ => unreachable!(),
+            };
+            assert!(verify(&secret, expected).is_ok());
+            let mut wrong = secret.clone();
+            wrong.push(b'!');
+            assert!(verify(&wrong, expected).is_err());
+
+            // Hashing with the same inputs produces the same string.
+            let decoded = decode(expected).unwrap();
+            let (salt, out_len) = (&decoded.salt, decoded.hash.len());
+            let mut params = decoded.params;
+            let actual = match decoded.id {
+                "argon2i" | "argon2id" => {
+                    let algorithm = if decoded.id == "argon2i" {
+                        &argon2::ARGON2I
+                    } else {
+                        &argon2::ARGON2ID
+                    };
+                    let params =
+                        argon2::Params::new(params.next("m").unwrap(),
+                                            params.next("t").unwrap(),
+                                            params.next("p").unwrap())
+                            .unwrap();
+                    argon2::hash_encoded(algorithm, &params, salt, &secret,
+                                         out_len)
+                },
+                "pbkdf2-sha256" | "pbkdf2-sha512" => {
+                    let prf = if decoded.id == "pbkdf2-sha256" {
+                        &pbkdf2::HMAC_SHA256
+                    } else {
+                        &pbkdf2::HMAC_SHA512
+                    };
+                    let iterations = params.next("i").unwrap() as usize;
+                    pbkdf2::hash_encoded(prf, iterations, salt, &secret,
+                                         out_len)
+                },
+                "scrypt" => {
+                    let n = 1u64 << params.next("ln").unwrap();
+                    let params = scrypt::Params::new(n,
+                                                     params.next("r").unwrap(),
+                                                     params.next("p").unwrap())
+                        .unwrap();
+                    scrypt::hash_encoded(&params, salt, &secret, out_len)
+                },
+                _ => unreachable!(),
+            };
+            assert_eq!(actual.unwrap(), expected);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_phc_malformed() {
+        const HASH: &'static str = "$pbkdf2-sha256$i=1,l=1$cw$hg";
+        assert!(pbkdf2::verify_encoded(b"", HASH).is_ok());
+        for &bad in &[
+            "",
+            "$",
+            "pbkdf2-sha256$i=1,l=1$cw$hg",
+            "$pbkdf2-sha256$i=1,l=1$cw",
+            "$pbkdf2-sha256$i=1,l=1$cw$hg$",
+            "$pbkdf2-sha256$i=1,l=1$cw$hh", // Non-zero unused bits.
+            "$pbkdf2-sha256$i=1,l=1$cw$hg==", // Padding.
+            "$pbkdf2-sha256$i=1,l=1$cw$h",
+            "$pbkdf2-sha256$i=1,l=1$c-$hg",
+            "$pbkdf2-sha256$i=01,l=1$cw$hg",
+            "$pbkdf2-sha256$i=+1,l=1$cw$hg",
+            "$pbkdf2-sha256$i=0,l=1$cw$hg",
+            "$pbkdf2-sha256$i=,l=1$cw$hg",
+            "$pbkdf2-sha256$i=1,l=2$cw$hg",
+            "$pbkdf2-sha256$i=1,$cw$hg",
+            "$pbkdf2-sha256$l=1,i=1$cw$hg",
+            "$pbkdf2-sha256$i=1,l=1,x=1$cw$hg",
+            "$pbkdf2-sha256$x=1$cw$hg",
+            "$pbkdf2-sha256$$cw$hg",
+            "$pbkdf2-sha256$i=4294967296$cw$hg",
+            "$pbkdf2-sha256$v=19$i=1,l=1$cw$hg",
+            "$pbkdf2-sha1$i=1,l=1$cw$hg",
+            "$pbkdf2-sha256$i=1,l=1$cw$",
+        ] {
+            assert!(pbkdf2::verify_encoded(b"", bad).is_err(), "{}", bad);
+        }
+
+        // The length parameter is optional.
+        assert!(pbkdf2::verify_encoded(b"", "$pbkdf2-sha256$i=1$cw$hg")
+                    .is_ok());
+
+        const ARGON2: &'static str =
+            "$argon2id$v=19$m=8,t=1,p=1$AAAAAAAAAAA$ZuKAoA";
+        assert!(argon2::verify_encoded(b"", ARGON2).is_ok());
+        for &bad in &[
+            "$argon2id$m=8,t=1,p=1$AAAAAAAAAAA$ZuKAoA",
+            "$argon2id$v=16$m=8,t=1,p=1$AAAAAAAAAAA$ZuKAoA",
+            "$argon2id$v=19$t=1,m=8,p=1$AAAAAAAAAAA$ZuKAoA",
+            "$argon2id$v=19$m=8,t=1$AAAAAAAAAAA$ZuKAoA",
+            "$argon2id$v=19$m=8,t=1,p=1,keyid=AA$AAAAAAAAAAA$ZuKAoA",
+            "$argon2d$v=19$m=8,t=1,p=1$AAAAAAAAAAA$ZuKAoA",
+            "$argon2id$v=19$m=8,t=1,p=1$AAAAAAAAAA$ZuKAoA",
+        ] {
+            assert!(argon2::verify_encoded(b"", bad).is_err(), "{}", bad);
+        }
+
+        const SCRYPT: &'static str =
+            "$scrypt$ln=1,r=1,p=1$c2FsdHNhbHQ$bQU8qXv9KVxtjYLyXDv+7w";
+        assert!(scrypt::verify_encoded(b"", SCRYPT).is_ok());
+        for &bad in &[
+            "$scrypt$ln=0,r=1,p=1$c2FsdHNhbHQ$bQU8qXv9KVxtjYLyXDv+7w",
+            "$scrypt$ln=64,r=1,p=1$c2FsdHNhbHQ$bQU8qXv9KVxtjYLyXDv+7w",
+            "$scrypt$ln=1,r=1$c2FsdHNhbHQ$bQU8qXv9KVxtjYLyXDv+7w",
+            "$scrypt$ln=1,r=1,p=1$c2FsdHNhbHQ$",
+        ] {
+            assert!(scrypt::verify_encoded(b"", bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_phc_base64() {
+        for len in 0..10 {
+            let input: std::vec::Vec<u8> = (0..len).map(|i| 0xf0 ^ i).collect();
+            let mut encoded = std::string::String::new();
+            encode_base64(&input, &mut encoded);
+            assert_eq!(encoded.len(), (len as usize * 8 + 5) / 6);
+            assert_eq!(decode_base64(&encoded).unwrap(), input);
+        }
+    }
+}
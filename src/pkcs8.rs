@@ -0,0 +1,480 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! PKCS#8 is specified in [RFC 5208].
+//!
+//! [RFC 5208]: https://tools.ietf.org/html/rfc5208.
+
+use {aead, aes, der, error, pbkdf2, polyfill};
+use std;
+use untrusted;
+
+/// A serialized private key, e.g. a DER-encoded PKCS#8 `PrivateKeyInfo`.
+///
+/// The contents are zeroized when the `Document` is dropped.
+pub struct Document {
+    bytes: std::vec::Vec<u8>,
+}
+
+impl Document {
+    // Constructs an empty document that can hold `capacity` bytes. XXX: This
+    // is public so that other *ring* submodules can use it, but it isn't
+    // intended for public use.
+    //
+    // Writing more than `capacity` bytes would cause the buffer to be
+    // reallocated and the old (unzeroized) buffer to be freed, so callers must
+    // calculate the exact length of the document up front.
+    #[doc(hidden)]
+    pub fn with_capacity(capacity: usize) -> Document {
+        Document { bytes: std::vec::Vec::with_capacity(capacity) }
+    }
+
+    // XXX: This is public so that other *ring* submodules can use it, but it
+    // isn't intended for public use.
+    #[doc(hidden)]
+    pub fn as_mut_vec(&mut self) -> &mut std::vec::Vec<u8> {
+        &mut self.bytes
+    }
+}
+
+impl AsRef<[u8]> for Document {
+    fn as_ref(&self) -> &[u8] { &self.bytes }
+}
+
+impl Drop for Document {
+    fn drop(&mut self) {
+        debug_assert!(self.bytes.len() <= self.bytes.capacity());
+        polyfill::slice::zeroize(&mut self.bytes);
+    }
+}
+
+/// Wraps the private key `key_der` in a PKCS#8 v1 `PrivateKeyInfo`.
+/// `alg_id` is the complete DER encoding of the `AlgorithmIdentifier` of the
+/// key's algorithm, including its tag and length.
+//
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub fn wrap_key(alg_id: &[u8], key_der: &[u8]) -> Document {
+    wrap_key_(Version::V1Only, alg_id, key_der, None)
+}
+
+/// Wraps the private key `key_der` and its public key `public_key` in a
+/// PKCS#8 v2 `OneAsymmetricKey` (see [RFC 5958 Section 2]). The form of
+/// `alg_id` is the same as for `wrap_key()`.
+///
+/// [RFC 5958 Section 2]: https://tools.ietf.org/html/rfc5958#section-2
+//
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub fn wrap_key_v2(alg_id: &[u8], key_der: &[u8], public_key: &[u8])
+                   -> Document {
+    wrap_key_(Version::V2Only, alg_id, key_der, Some(public_key))
+}
+
+fn wrap_key_(version: Version, alg_id: &[u8], key_der: &[u8],
+             public_key: Option<&[u8]>) -> Document {
+    let version_der: &[u8] = match version {
+        Version::V1Only => &[der::Tag::Integer as u8, 1, 0],
+        Version::V2Only => &[der::Tag::Integer as u8, 1, 1],
+    };
+
+    // The public key is a `BIT STRING` with no unused bits, implicitly tagged
+    // [1].
+    let public_key_len = public_key.map_or(0, |public_key| {
+        der::encoded_len(1 + public_key.len())
+    });
+
+    let inner_len = version_der.len() + alg_id.len() +
+                    der::encoded_len(key_der.len()) + public_key_len;
+    let mut result = Document::with_capacity(der::encoded_len(inner_len));
+    {
+        let output = result.as_mut_vec();
+        der::write_tag_and_len(output, der::Tag::Sequence, inner_len);
+        output.extend_from_slice(version_der);
+        output.extend_from_slice(alg_id);
+        der::write_tlv(output, der::Tag::OctetString, key_der);
+        if let Some(public_key) = public_key {
+            der::write_tag_and_len(output, der::Tag::ContextSpecific1,
+                                   1 + public_key.len());
+            output.push(0); // No unused bits.
+            output.extend_from_slice(public_key);
+        }
+    }
+    debug_assert_eq!(result.bytes.len(), result.bytes.capacity());
+    result
+}
+
+/// Parses a DER-encoded PKCS#8 v1 `PrivateKeyInfo` containing a key of the
+/// algorithm identified by `alg_id`, returning the encoded private key. The
+/// form of `alg_id` is the same as for `wrap_key()`.
+//
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub fn unwrap_key<'a>(alg_id: &[u8], input: untrusted::Input<'a>)
+                      -> Result<untrusted::Input<'a>, error::Unspecified> {
+    unwrap_key_(Version::V1Only, alg_id, input)
+        .map(|(private_key, _)| private_key)
+}
+
+/// Parses a DER-encoded PKCS#8 v2 `OneAsymmetricKey` containing a key of the
+/// algorithm identified by `alg_id`, returning the encoded private key and
+/// the public key. The form of `alg_id` is the same as for `wrap_key()`.
+//
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub fn unwrap_key_v2<'a>(alg_id: &[u8], input: untrusted::Input<'a>)
+                         -> Result<(untrusted::Input<'a>,
+                                    untrusted::Input<'a>),
+                                   error::Unspecified> {
+    let (private_key, public_key) =
+        try!(unwrap_key_(Version::V2Only, alg_id, input));
+    let public_key = try!(public_key.ok_or(error::Unspecified));
+    Ok((private_key, public_key))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Version {
+    V1Only,
+    V2Only,
+}
+
+fn unwrap_key_<'a>(version: Version, alg_id: &[u8],
+                   input: untrusted::Input<'a>)
+                   -> Result<(untrusted::Input<'a>,
+                              Option<untrusted::Input<'a>>),
+                             error::Unspecified> {
+    input.read_all(error::Unspecified, |input| {
+        der::nested(input, der::Tag::Sequence, error::Unspecified, |input| {
+            let actual_version = try!(der::small_nonnegative_integer(input));
+            let expected_version = match version {
+                Version::V1Only => 0,
+                Version::V2Only => 1,
+            };
+            if actual_version != expected_version {
+                return Err(error::Unspecified);
+            }
+
+            let alg_id_start = input.mark();
+            let _ = try!(der::expect_tag_and_get_value(input,
+                                                       der::Tag::Sequence));
+            let alg_id_end = input.mark();
+            let actual_alg_id =
+                try!(input.get_input_between_marks(alg_id_start, alg_id_end));
+            if actual_alg_id != untrusted::Input::from(alg_id) {
+                return Err(error::Unspecified);
+            }
+
+            // The optional attributes aren't supported.
+            let private_key =
+                try!(der::expect_tag_and_get_value(input,
+                                                   der::Tag::OctetString));
+
+            if version == Version::V1Only {
+                return Ok((private_key, None));
+            }
+
+            let public_key =
+                try!(der::expect_tag_and_get_value(input,
+                                                   der::Tag::ContextSpecific1));
+            let public_key = try!(public_key.read_all(error::Unspecified,
+                                                      |input| {
+                // Only whole-byte public keys are supported.
+                let unused_bits = try!(input.read_byte());
+                if unused_bits != 0 {
+                    return Err(error::Unspecified);
+                }
+                Ok(input.skip_to_end())
+            }));
+            Ok((private_key, Some(public_key)))
+        })
+    })
+}
+
+/// Decrypts a DER-encoded PKCS#8 `EncryptedPrivateKeyInfo` (see
+/// [RFC 5208 Section 6]) with `password`, returning the decrypted
+/// `PrivateKeyInfo`.
+///
+/// Only PBES2 ([RFC 8018 Section 6.2]) encryption is supported. The key must
+/// be derived using PBKDF2 with HMAC-SHA256 or HMAC-SHA512, and the encryption
+/// scheme must be AES-128 or AES-256 in either CBC mode ([RFC 8018 Appendix
+/// B.2.5]) or GCM mode with 128-bit tags ([RFC 5084 Section 3.2]). This is
+/// what `openssl pkcs8 -topk8 -v2 aes-256-cbc -v2prf hmacWithSHA256`
+/// produces.
+///
+/// [RFC 5208 Section 6]: https://tools.ietf.org/html/rfc5208#section-6
+/// [RFC 8018 Section 6.2]: https://tools.ietf.org/html/rfc8018#section-6.2
+/// [RFC 8018 Appendix B.2.5]:
+///     https://tools.ietf.org/html/rfc8018#appendix-B.2.5
+/// [RFC 5084 Section 3.2]: https://tools.ietf.org/html/rfc5084#section-3.2
+//
+// XXX: This is public so that other *ring* submodules can use it, but it isn't
+// intended for public use.
+#[doc(hidden)]
+pub fn decrypt(input: untrusted::Input, password: &[u8])
+               -> Result<Document, error::Unspecified> {
+    input.read_all(error::Unspecified, |input| {
+        der::nested(input, der::Tag::Sequence, error::Unspecified, |input| {
+            let params = try!(der::nested(input, der::Tag::Sequence,
+                                          error::Unspecified, |input| {
+                try!(expect_oid(input, PBES2_OID));
+                der::nested(input, der::Tag::Sequence, error::Unspecified,
+                            parse_pbes2_params)
+            }));
+            let encrypted =
+                try!(der::expect_tag_and_get_value(input,
+                                                   der::Tag::OctetString));
+            params.decrypt(password, encrypted.as_slice_less_safe())
+        })
+    })
+}
+
+struct PBES2Params<'a> {
+    prf: &'static pbkdf2::PRF,
+    salt: untrusted::Input<'a>,
+    iterations: usize,
+    scheme: EncryptionScheme<'a>,
+}
+
+enum EncryptionScheme<'a> {
+    AESCBC { key_len: usize, iv: untrusted::Input<'a> },
+    AESGCM { aead_alg: &'static aead::Algorithm, nonce: untrusted::Input<'a> },
+}
+
+impl<'a> EncryptionScheme<'a> {
+    fn key_len(&self) -> usize {
+        match *self {
+            EncryptionScheme::AESCBC { key_len, .. } => key_len,
+            EncryptionScheme::AESGCM { aead_alg, .. } => aead_alg.key_len(),
+        }
+    }
+}
+
+impl<'a> PBES2Params<'a> {
+    fn decrypt(&self, password: &[u8], encrypted: &[u8])
+               -> Result<Document, error::Unspecified> {
+        let mut key_buf = [0u8; MAX_KEY_LEN];
+        let key = &mut key_buf[..self.scheme.key_len()];
+        pbkdf2::derive(self.prf, self.iterations,
+                       self.salt.as_slice_less_safe(), password, key);
+
+        let mut result = Document::with_capacity(encrypted.len());
+        result.bytes.extend_from_slice(encrypted);
+        let decrypted_len = match self.scheme {
+            EncryptionScheme::AESCBC { iv, .. } =>
+                decrypt_aes_cbc(key, iv, &mut result.bytes),
+            EncryptionScheme::AESGCM { aead_alg, nonce } => {
+                let key = try!(aead::LessSafeKey::new(aead_alg, key));
+                aead::open_in_place(&key, nonce.as_slice_less_safe(), 0,
+                                    &mut result.bytes, &[])
+            },
+        };
+        polyfill::slice::zeroize(key);
+
+        // The truncated bytes are the padding or the tag, which aren't
+        // secret, so it doesn't matter that `Document` won't zeroize them.
+        result.bytes.truncate(try!(decrypted_len));
+        Ok(result)
+    }
+}
+
+// Returns the length of the plaintext, without the padding.
+fn decrypt_aes_cbc(key: &[u8], iv: untrusted::Input, in_out: &mut [u8])
+                   -> Result<usize, error::Unspecified> {
+    let key = try!(aes::DecryptionKey::new(key));
+    let iv = try!(slice_as_array_ref!(iv.as_slice_less_safe(),
+                                      aes::BLOCK_LEN));
+    if in_out.is_empty() {
+        return Err(error::Unspecified);
+    }
+    try!(aes::decrypt_cbc_in_place(&key, iv, in_out));
+
+    // Remove the PKCS#7 padding (RFC 8018 Section 6.1.1).
+    let padding_len = usize::from(in_out[in_out.len() - 1]);
+    if padding_len == 0 || padding_len > aes::BLOCK_LEN {
+        return Err(error::Unspecified);
+    }
+    let plaintext_len = in_out.len() - padding_len;
+    if in_out[plaintext_len..].iter().any(|&b| usize::from(b) != padding_len) {
+        return Err(error::Unspecified);
+    }
+    Ok(plaintext_len)
+}
+
+fn parse_pbes2_params<'a>(input: &mut untrusted::Reader<'a>)
+                          -> Result<PBES2Params<'a>, error::Unspecified> {
+    let (prf, salt, iterations, key_len) =
+        try!(der::nested(input, der::Tag::Sequence, error::Unspecified,
+                         |input| {
+            try!(expect_oid(input, PBKDF2_OID));
+            der::nested(input, der::Tag::Sequence, error::Unspecified,
+                        parse_pbkdf2_params)
+        }));
+
+    let scheme = try!(der::nested(input, der::Tag::Sequence, error::Unspecified,
+                                  parse_encryption_scheme));
+
+    // keyLength is optional, but if it is present it must be consistent with
+    // the encryption scheme.
+    match key_len {
+        Some(key_len) if key_len != scheme.key_len() => {
+            return Err(error::Unspecified);
+        },
+        _ => (),
+    }
+
+    Ok(PBES2Params {
+        prf: prf,
+        salt: salt,
+        iterations: iterations,
+        scheme: scheme,
+    })
+}
+
+fn parse_pbkdf2_params<'a>(input: &mut untrusted::Reader<'a>)
+                           -> Result<(&'static pbkdf2::PRF,
+                                      untrusted::Input<'a>, usize,
+                                      Option<usize>), error::Unspecified> {
+    // Only the `specified` choice of the salt is supported.
+    let salt = try!(der::expect_tag_and_get_value(input,
+                                                  der::Tag::OctetString));
+    let iterations = try!(small_positive_integer(input));
+    let key_len = if input.peek(der::Tag::Integer as u8) {
+        Some(try!(small_positive_integer(input)))
+    } else {
+        None
+    };
+
+    // The default PRF is HMAC-SHA1, which isn't supported.
+    let prf = try!(der::nested(input, der::Tag::Sequence, error::Unspecified,
+                               |input| {
+        let oid = try!(der::expect_tag_and_get_value(input, der::Tag::OID));
+        try!(optional_null_parameters(input));
+        if oid == untrusted::Input::from(HMAC_WITH_SHA256_OID) {
+            Ok(&pbkdf2::HMAC_SHA256)
+        } else if oid == untrusted::Input::from(HMAC_WITH_SHA512_OID) {
+            Ok(&pbkdf2::HMAC_SHA512)
+        } else {
+            Err(error::Unspecified)
+        }
+    }));
+
+    Ok((prf, salt, iterations, key_len))
+}
+
+fn parse_encryption_scheme<'a>(input: &mut untrusted::Reader<'a>)
+                               -> Result<EncryptionScheme<'a>,
+                                         error::Unspecified> {
+    let oid = try!(der::expect_tag_and_get_value(input, der::Tag::OID));
+    let cbc_key_len = if oid == untrusted::Input::from(AES_128_CBC_OID) {
+        Some(128 / 8)
+    } else if oid == untrusted::Input::from(AES_256_CBC_OID) {
+        Some(256 / 8)
+    } else {
+        None
+    };
+    if let Some(key_len) = cbc_key_len {
+        let iv = try!(der::expect_tag_and_get_value(input,
+                                                    der::Tag::OctetString));
+        return Ok(EncryptionScheme::AESCBC { key_len: key_len, iv: iv });
+    }
+
+    let aead_alg = if oid == untrusted::Input::from(AES_128_GCM_OID) {
+        &aead::AES_128_GCM
+    } else if oid == untrusted::Input::from(AES_256_GCM_OID) {
+        &aead::AES_256_GCM
+    } else {
+        return Err(error::Unspecified);
+    };
+    der::nested(input, der::Tag::Sequence, error::Unspecified, |input| {
+        let nonce = try!(der::expect_tag_and_get_value(input,
+                                                       der::Tag::OctetString));
+        // The default tag length is 12 bytes, but only 16-byte tags are
+        // supported, so the tag length must be present.
+        let tag_len = try!(der::small_nonnegative_integer(input));
+        if usize::from(tag_len) != aead_alg.max_overhead_len() {
+            return Err(error::Unspecified);
+        }
+        Ok(EncryptionScheme::AESGCM { aead_alg: aead_alg, nonce: nonce })
+    })
+}
+
+fn expect_oid(input: &mut untrusted::Reader, expected: &[u8])
+              -> Result<(), error::Unspecified> {
+    let oid = try!(der::expect_tag_and_get_value(input, der::Tag::OID));
+    if oid != untrusted::Input::from(expected) {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+// Parses the parameters of an `AlgorithmIdentifier` that has no parameters.
+// The parameters may be omitted or they may be NULL.
+fn optional_null_parameters(input: &mut untrusted::Reader)
+                            -> Result<(), error::Unspecified> {
+    if !input.at_end() {
+        let null = try!(der::expect_tag_and_get_value(input, der::Tag::Null));
+        if !null.is_empty() {
+            return Err(error::Unspecified);
+        }
+    }
+    Ok(())
+}
+
+// Parses a positive integer that fits in 32 bits, e.g. an iteration count.
+fn small_positive_integer(input: &mut untrusted::Reader)
+                          -> Result<usize, error::Unspecified> {
+    let value = try!(der::positive_integer(input));
+    if value.len() > 4 {
+        return Err(error::Unspecified);
+    }
+    Ok(value.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b)))
+}
+
+const MAX_KEY_LEN: usize = 256 / 8;
+
+// 1.2.840.113549.1.5.13
+const PBES2_OID: &'static [u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+
+// 1.2.840.113549.1.5.12
+const PBKDF2_OID: &'static [u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+
+// 1.2.840.113549.2.9
+const HMAC_WITH_SHA256_OID: &'static [u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+
+// 1.2.840.113549.2.11
+const HMAC_WITH_SHA512_OID: &'static [u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x0b];
+
+// 2.16.840.1.101.3.4.1.2
+const AES_128_CBC_OID: &'static [u8] =
+    &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x02];
+
+// 2.16.840.1.101.3.4.1.6
+const AES_128_GCM_OID: &'static [u8] =
+    &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x06];
+
+// 2.16.840.1.101.3.4.1.42
+const AES_256_CBC_OID: &'static [u8] =
+    &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+// 2.16.840.1.101.3.4.1.46
+const AES_256_GCM_OID: &'static [u8] =
+    &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2e];
@@ -13,12 +13,43 @@
 // OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
+//! Poly1305, the one-time authenticator specified in [RFC 7539 Section 2.5].
+//!
+//! **A Poly1305 key must never be used for more than one message.** Poly1305
+//! isn't a general-purpose MAC: anybody who sees the tags of two messages
+//! with the same key can forge tags for other messages with that key.
+//! Constructions that use Poly1305, like ChaCha20-Poly1305 and
+//! XSalsa20-Poly1305, derive a new key for each message from the output of a
+//! stream cipher; use `aead` or `nacl` instead of this module when one of
+//! them fits. To make accidental reuse harder, `Key` can't be cloned and is
+//! consumed by `sign`, `verify`, and `SigningContext::from_key`.
+//!
+//! Use `hmac` or `cmac` when a key needs to authenticate many messages.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::poly1305;
+//!
+//! // Normally `key_bytes` would be derived from a key and a nonce that is
+//! // never reused, e.g. as the first 32 bytes of a stream cipher's output.
+//! let key_bytes = [0x42; poly1305::KEY_LEN];
+//!
+//! let mut tag = [0u8; poly1305::TAG_LEN];
+//! poly1305::sign(poly1305::Key::new(&key_bytes), b"hello, world", &mut tag);
+//!
+//! assert!(poly1305::verify(poly1305::Key::new(&key_bytes), b"hello, world",
+//!                          &tag).is_ok());
+//! ```
+//!
+//! [RFC 7539 Section 2.5]: https://tools.ietf.org/html/rfc7539#section-2.5
+
 // TODO: enforce maximum input length.
 
 // Work around compiler bug?
 #![allow(non_shorthand_field_patterns)]
 
-use {c, chacha, constant_time, error, polyfill};
+use {c, constant_time, error, polyfill};
 use core;
 
 // The assembly functions we call expect the state to be 8-byte aligned. We do
@@ -39,6 +70,7 @@ fn with_aligned<F>(opaque: &mut Opaque, f: F)
 }
 
 impl SigningContext {
+    /// Constructs a new signing context, consuming the one-time key `key`.
     #[inline]
     pub fn from_key(key: Key) -> SigningContext {
         #[inline]
@@ -88,6 +120,8 @@ impl SigningContext {
         ctx
     }
 
+    /// Updates the tag with all the data in `input`. `update` may be called
+    /// zero or more times until `sign` is called.
     pub fn update(&mut self, mut input: &[u8]) {
         let &mut SigningContext {
             opaque: ref mut opaque,
@@ -125,6 +159,7 @@ impl SigningContext {
         });
     }
 
+    /// Finalizes the calculation and writes the tag into `tag_out`.
     pub fn sign(mut self, tag_out: &mut Tag) {
         let &mut SigningContext {
             opaque: ref mut opaque,
@@ -147,6 +182,8 @@ impl SigningContext {
     }
 }
 
+/// Calculates the tag of `msg` using the one-time key `key` and checks in
+/// constant time that it is equal to `tag`.
 pub fn verify(key: Key, msg: &[u8], tag: &Tag)
               -> Result<(), error::Unspecified> {
     let mut calculated_tag = [0u8; TAG_LEN];
@@ -154,6 +191,8 @@ pub fn verify(key: Key, msg: &[u8], tag: &Tag)
     constant_time::verify_slices_are_equal(&calculated_tag[..], tag)
 }
 
+/// Calculates the tag of `msg` using the one-time key `key` in one step,
+/// writing it into `tag`.
 pub fn sign(key: Key, msg: &[u8], tag: &mut Tag) {
     let mut ctx = SigningContext::from_key(key);
     ctx.update(msg);
@@ -161,7 +200,7 @@ pub fn sign(key: Key, msg: &[u8], tag: &mut Tag) {
 }
 
 #[cfg(test)]
-pub fn check_state_layout() {
+fn check_state_layout() {
     let required_state_size =
         if cfg!(target_arch = "x86") {
             // See comment above `_poly1305_init_sse2` in poly1305-x86.pl.
@@ -180,21 +219,18 @@ pub fn check_state_layout() {
     }
 }
 
-/// A Poly1305 key.
+/// A one-time Poly1305 key: the pair (r, s) of RFC 7539 Section 2.5.
+///
+/// A key must be used to authenticate only one message. See the module
+/// documentation.
 pub struct Key {
     bytes: KeyAndNonceBytes,
 }
 
 impl Key {
-    pub fn derive_using_chacha(chacha20_key: &chacha::Key,
-                               counter: &chacha::Counter) -> Key {
-        let mut bytes = [0u8; KEY_LEN];
-        chacha::chacha20_xor_in_place(chacha20_key, counter, &mut bytes);
-        Key { bytes: bytes }
-    }
-
-    #[cfg(test)]
-    pub fn from_test_vector(bytes: &[u8; KEY_LEN]) -> Key {
+    /// Constructs a key from the 32 bytes `bytes`, which are r followed by s.
+    /// r is clamped as described in RFC 7539 Section 2.5.
+    pub fn new(bytes: &[u8; KEY_LEN]) -> Key {
         Key { bytes: *bytes }
     }
 }
@@ -204,13 +240,13 @@ type KeyAndNonceBytes = [u8; 2 * BLOCK_LEN];
 type KeyBytes = [u8; BLOCK_LEN];
 type Nonce = [u32; BLOCK_LEN / 4];
 
-/// The length of a `key`.
+/// The length of a `Key`, in bytes.
 pub const KEY_LEN: usize = 32;
 
 /// A Poly1305 tag.
 pub type Tag = [u8; TAG_LEN];
 
-/// The length of a `Tag`.
+/// The length of a `Tag`, in bytes.
 pub const TAG_LEN: usize = BLOCK_LEN;
 
 const BLOCK_LEN: usize = 16;
@@ -259,6 +295,9 @@ impl Funcs {
     }
 }
 
+/// A context for multi-step (Init-Update-Finish) Poly1305 signing.
+///
+/// Use `sign` for single-step Poly1305 signing.
 pub struct SigningContext {
     opaque: Opaque,
     nonce: [u32; 4],
@@ -300,7 +339,7 @@ mod tests {
 
             // Test single-shot operation.
             {
-                let key = Key::from_test_vector(&key);
+                let key = Key::new(&key);
                 let mut ctx = SigningContext::from_key(key);
                 ctx.update(&input);
                 let mut actual_mac = [0; TAG_LEN];
@@ -308,19 +347,19 @@ mod tests {
                 assert_eq!(&expected_mac[..], &actual_mac[..]);
             }
             {
-                let key = Key::from_test_vector(&key);
+                let key = Key::new(&key);
                 let mut actual_mac = [0; TAG_LEN];
                 sign(key, &input, &mut actual_mac);
                 assert_eq!(&expected_mac[..], &actual_mac[..]);
             }
             {
-                let key = Key::from_test_vector(&key);
+                let key = Key::new(&key);
                 assert_eq!(Ok(()), verify(key, &input, &expected_mac));
             }
 
             // Test streaming byte-by-byte.
             {
-                let key = Key::from_test_vector(&key);
+                let key = Key::new(&key);
                 let mut ctx = SigningContext::from_key(key);
                 for chunk in input.chunks(1) {
                     ctx.update(chunk);
@@ -342,7 +381,7 @@ mod tests {
     fn test_poly1305_simd(excess: usize, key: &[u8; KEY_LEN], input: &[u8],
                           expected_mac: &[u8; TAG_LEN])
                           -> Result<(), error::Unspecified> {
-        let key = Key::from_test_vector(&key);
+        let key = Key::new(&key);
         let mut ctx = SigningContext::from_key(key);
 
         // Some implementations begin in non-SIMD mode and upgrade on demand.
@@ -26,6 +26,27 @@ pub fn ref_from_mut_ref<'a, T: ?Sized>(x: &'a mut T) -> &'a T { x }
 #[inline(always)]
 pub fn u64_from_usize(x: usize) -> u64 { x as u64 }
 
+#[inline(always)]
+pub fn u32_from_usize(x: usize) -> Option<u32> {
+    if u64_from_usize(x) > u64::from(core::u32::MAX) {
+        return None;
+    }
+    Some(x as u32)
+}
+
+#[inline(always)]
+pub fn usize_from_u64(x: u64) -> Option<usize> {
+    if x > u64_from_usize(core::usize::MAX) {
+        return None;
+    }
+    Some(x as usize)
+}
+
+/// The low and high halves of `x`, e.g. for a 64-bit counter that is input
+/// as two 32-bit words.
+#[inline(always)]
+pub fn u32s_from_u64(x: u64) -> [u32; 2] { [x as u32, (x >> 32) as u32] }
+
 /// `core::num::Wrapping` doesn't support `rotate_left`.
 /// There is no usable trait for `rotate_left`, so this polyfill just
 /// hard-codes u32. https://github.com/rust-lang/rust/issues/32463
@@ -63,6 +84,28 @@ pub mod slice {
          (value & 0xff) as u8]
     }
 
+    #[inline(always)]
+    pub fn le_u8_from_u32(value: u32) -> [u8; 4] {
+        [(value & 0xff) as u8,
+         ((value >> 8) & 0xff) as u8,
+         ((value >> 16) & 0xff) as u8,
+         ((value >> 24) & 0xff) as u8]
+    }
+
+    #[inline(always)]
+    pub fn u64_from_be_u8(buffer: &[u8; 8]) -> u64 {
+        buffer.iter().fold(0, |acc, b| (acc << 8) | u64::from(*b))
+    }
+
+    #[inline(always)]
+    pub fn be_u8_from_u64(value: u64) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (value >> (56 - (8 * i))) as u8;
+        }
+        bytes
+    }
+
     // https://github.com/rust-lang/rust/issues/27750
     // https://internals.rust-lang.org/t/stabilizing-basic-functions-on-arrays-and-slices/2868
     #[inline(always)]
@@ -72,6 +115,16 @@ pub mod slice {
         }
     }
 
+    // Like `fill(dest, 0)`, except the writes are volatile so that the
+    // compiler won't optimize them away even when `dest` isn't read again.
+    // This is used to clear secret values before the memory they are in is
+    // freed.
+    pub fn zeroize(dest: &mut [u8]) {
+        for d in dest {
+            unsafe { core::ptr::write_volatile(d, 0); }
+        }
+    }
+
     // https://internals.rust-lang.org/t/safe-trasnsmute-for-slices-e-g-u64-u32-particularly-simd-types/2871
     #[inline(always)]
     pub fn u64_as_u8(src: &[u64]) -> &[u8] {
@@ -29,10 +29,8 @@
 #[cfg(any(target_os = "linux", windows, test))]
 use c;
 
-#[cfg(test)]
 use core;
-
-use error;
+use {digest, error, hmac};
 
 
 /// A secure random number generator.
@@ -107,6 +105,167 @@ impl SecureRandom for SystemRandom {
     }
 }
 
+/// The HMAC_DRBG deterministic random bit generator specified in
+/// [NIST SP 800-90A Rev. 1 Section 10.1.2].
+///
+/// The output of an `HmacDrbg` is completely determined by the inputs it was
+/// instantiated and reseeded with, so it is only as unpredictable as its
+/// entropy inputs. It is useful where reproducible output is required, e.g.
+/// for deterministic signature nonces (RFC 6979 uses HMAC_DRBG) or for
+/// tests. Otherwise, use `SystemRandom`.
+///
+/// `fill()` is `generate()` without additional input, split into requests of
+/// at most `HMAC_DRBG_MAX_REQUEST_LEN` bytes.
+///
+/// The security strength of an `HmacDrbg` is 128 bits for SHA-1, 192 bits
+/// for SHA-512/224, and 256 bits for the other digest algorithms.
+///
+/// [NIST SP 800-90A Rev. 1 Section 10.1.2]:
+///     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf
+pub struct HmacDrbg {
+    digest_alg: &'static digest::Algorithm,
+    k: core::cell::Cell<[u8; digest::MAX_OUTPUT_LEN]>,
+    v: core::cell::Cell<[u8; digest::MAX_OUTPUT_LEN]>,
+    reseed_counter: core::cell::Cell<u64>,
+}
+
+/// The maximum number of bytes that `HmacDrbg::generate()` will generate in
+/// one request.
+pub const HMAC_DRBG_MAX_REQUEST_LEN: usize = (1 << 19) / 8;
+
+// SP 800-90A Rev. 1 Table 2.
+const HMAC_DRBG_RESEED_INTERVAL: u64 = 1 << 48;
+
+impl HmacDrbg {
+    /// Instantiates an HMAC_DRBG that uses `digest_alg`, as in SP 800-90A
+    /// Rev. 1 Section 10.1.2.3.
+    ///
+    /// Fails if `entropy_input` is shorter than the security strength. The
+    /// nonce should be at least half as long as the security strength.
+    /// `personalization_string` may be empty.
+    pub fn new(digest_alg: &'static digest::Algorithm, entropy_input: &[u8],
+               nonce: &[u8], personalization_string: &[u8])
+               -> Result<HmacDrbg, error::Unspecified> {
+        let drbg = HmacDrbg {
+            digest_alg: digest_alg,
+            k: core::cell::Cell::new([0x00; digest::MAX_OUTPUT_LEN]),
+            v: core::cell::Cell::new([0x01; digest::MAX_OUTPUT_LEN]),
+            reseed_counter: core::cell::Cell::new(1),
+        };
+        try!(drbg.check_entropy_input(entropy_input));
+        drbg.update(&[entropy_input, nonce, personalization_string]);
+        Ok(drbg)
+    }
+
+    /// Reseeds the DRBG with `entropy_input` and the optional
+    /// `additional_input`, as in SP 800-90A Rev. 1 Section 10.1.2.4.
+    ///
+    /// Fails if `entropy_input` is shorter than the security strength.
+    pub fn reseed(&self, entropy_input: &[u8], additional_input: &[u8])
+                  -> Result<(), error::Unspecified> {
+        try!(self.check_entropy_input(entropy_input));
+        self.update(&[entropy_input, additional_input]);
+        self.reseed_counter.set(1);
+        Ok(())
+    }
+
+    /// Fills `dest` with the next `dest.len()` bytes of output, using the
+    /// optional `additional_input`, as in SP 800-90A Rev. 1 Section
+    /// 10.1.2.5.
+    ///
+    /// Fails if `dest` is longer than `HMAC_DRBG_MAX_REQUEST_LEN` or if the
+    /// DRBG must be reseeded because it has served 2**48 requests since it
+    /// was last seeded.
+    pub fn generate(&self, dest: &mut [u8], additional_input: &[u8])
+                    -> Result<(), error::Unspecified> {
+        if dest.len() > HMAC_DRBG_MAX_REQUEST_LEN {
+            return Err(error::Unspecified);
+        }
+        let reseed_counter = self.reseed_counter.get();
+        if reseed_counter > HMAC_DRBG_RESEED_INTERVAL {
+            return Err(error::Unspecified);
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+
+        let output_len = self.digest_alg.output_len;
+        let key = hmac::SigningKey::new(self.digest_alg,
+                                        &self.k.get()[..output_len]);
+        let mut v = self.v.get();
+        for chunk in dest.chunks_mut(output_len) {
+            let new_v = hmac::sign(&key, &v[..output_len]);
+            v[..output_len].copy_from_slice(new_v.as_ref());
+            chunk.copy_from_slice(&v[..chunk.len()]);
+        }
+        self.v.set(v);
+
+        self.update(&[additional_input]);
+        self.reseed_counter.set(reseed_counter + 1);
+        Ok(())
+    }
+
+    /// The digest algorithm that the DRBG uses.
+    #[inline(always)]
+    pub fn digest_algorithm(&self) -> &'static digest::Algorithm {
+        self.digest_alg
+    }
+
+    fn check_entropy_input(&self, entropy_input: &[u8])
+                           -> Result<(), error::Unspecified> {
+        let security_strength_len = match self.digest_alg.output_len {
+            20 => 128 / 8,
+            28 => 192 / 8,
+            _ => 256 / 8,
+        };
+        if entropy_input.len() < security_strength_len {
+            return Err(error::Unspecified);
+        }
+        Ok(())
+    }
+
+    // HMAC_DRBG_Update, where the provided data is the concatenation of
+    // `provided_data`.
+    fn update(&self, provided_data: &[&[u8]]) {
+        self.update_step(0x00, provided_data);
+        if provided_data.iter().any(|part| !part.is_empty()) {
+            self.update_step(0x01, provided_data);
+        }
+    }
+
+    // K = HMAC_K(V || separator || provided_data), V = HMAC_K(V).
+    fn update_step(&self, separator: u8, provided_data: &[&[u8]]) {
+        let output_len = self.digest_alg.output_len;
+        let mut k = self.k.get();
+        let mut v = self.v.get();
+        {
+            let key = hmac::SigningKey::new(self.digest_alg, &k[..output_len]);
+            let mut ctx = hmac::SigningContext::with_key(&key);
+            ctx.update(&v[..output_len]);
+            ctx.update(&[separator]);
+            for part in provided_data {
+                ctx.update(part);
+            }
+            k[..output_len].copy_from_slice(ctx.sign().as_ref());
+        }
+        let key = hmac::SigningKey::new(self.digest_alg, &k[..output_len]);
+        let new_v = hmac::sign(&key, &v[..output_len]);
+        v[..output_len].copy_from_slice(new_v.as_ref());
+        self.k.set(k);
+        self.v.set(v);
+    }
+}
+
+impl SecureRandom for HmacDrbg {
+    fn fill(&self, dest: &mut [u8]) -> Result<(), error::Unspecified> {
+        for chunk in dest.chunks_mut(HMAC_DRBG_MAX_REQUEST_LEN) {
+            try!(self.generate(chunk, &[]));
+        }
+        Ok(())
+    }
+}
+
 #[cfg(not(any(target_os = "linux", windows)))]
 use self::urandom::fill as fill_impl;
 
@@ -247,8 +406,84 @@ extern {
 #[cfg(test)]
 mod tests {
     use rand;
+    use test;
     extern crate std;
 
+    #[test]
+    fn test_hmac_drbg() {
+        test::from_file("src/rand_hmac_drbg_tests.txt", |section, test_case| {
+            let digest_alg = test_case.consume_digest_alg("Hash").unwrap();
+            let entropy_input = test_case.consume_bytes("EntropyInput");
+            let nonce = test_case.consume_bytes("Nonce");
+            let personalization_string =
+                test_case.consume_bytes("PersonalizationString");
+            let drbg = rand::HmacDrbg::new(digest_alg, &entropy_input, &nonce,
+                                           &personalization_string).unwrap();
+            match section {
+                "NoReseed" => {},
+                "Reseed" => {
+                    let entropy_input =
+                        test_case.consume_bytes("EntropyInputReseed");
+                    let additional_input =
+                        test_case.consume_bytes("AdditionalInputReseed");
+                    assert!(drbg.reseed(&entropy_input, &additional_input)
+                                .is_ok());
+                },
+                _ => unreachable!(),
+            }
+            let additional_input_1 =
+                test_case.consume_bytes("AdditionalInput1");
+            let additional_input_2 =
+                test_case.consume_bytes("AdditionalInput2");
+            let expected = test_case.consume_bytes("ReturnedBits");
+
+            let mut actual = vec![0u8; expected.len()];
+            assert!(drbg.generate(&mut actual, &additional_input_1).is_ok());
+            assert!(drbg.generate(&mut actual, &additional_input_2).is_ok());
+            assert_eq!(actual, expected);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_hmac_drbg_limits() {
+        use digest;
+        use rand::SecureRandom;
+
+        // The entropy input must be at least as long as the security
+        // strength.
+        assert!(rand::HmacDrbg::new(&digest::SHA1, &[0; 15], b"", b"")
+                    .is_err());
+        assert!(rand::HmacDrbg::new(&digest::SHA256, &[0; 31], b"", b"")
+                    .is_err());
+        let drbg = rand::HmacDrbg::new(&digest::SHA256, &[0; 32], b"", b"")
+            .unwrap();
+        assert!(drbg.reseed(&[0; 31], b"").is_err());
+        assert!(drbg.reseed(&[0; 32], b"").is_ok());
+
+        // `generate` limits the request length, but `fill` doesn't.
+        let mut buf = vec![0u8; rand::HMAC_DRBG_MAX_REQUEST_LEN + 1];
+        assert!(drbg.generate(&mut buf, b"").is_err());
+        assert!(drbg.generate(&mut buf[1..], b"").is_ok());
+        assert!(drbg.fill(&mut buf).is_ok());
+
+        // `fill` is `generate` in chunks of the maximum request length.
+        let a = rand::HmacDrbg::new(&digest::SHA256, &[1; 32], b"", b"")
+            .unwrap();
+        let b = rand::HmacDrbg::new(&digest::SHA256, &[1; 32], b"", b"")
+            .unwrap();
+        assert!(a.fill(&mut buf).is_ok());
+        let mut expected = vec![0u8; buf.len()];
+        {
+            let (x, y) =
+                expected.split_at_mut(rand::HMAC_DRBG_MAX_REQUEST_LEN);
+            assert!(b.generate(x, b"").is_ok());
+            assert!(b.generate(y, b"").is_ok());
+        }
+        assert_eq!(buf, expected);
+    }
+
     #[test]
     fn test_system_random_lengths() {
         // Test that `fill` succeeds for various interesting lengths. `256` and
@@ -19,6 +19,9 @@
 
 use {bits, bssl, c, der, error, untrusted};
 use core;
+
+#[cfg(feature = "rsa_signing")]
+use std;
 use core::marker::PhantomData;
 
 /// This is defined for comparing values instead of using `PartialOrd` because
@@ -103,6 +106,14 @@ impl Positive {
         Ok(Positive(r))
     }
 
+    pub fn try_clone(&self) -> Result<Positive, error::Unspecified> {
+        let mut value = try!(Nonnegative::zero());
+        try!(bssl::map_result(unsafe {
+            GFp_BN_copy(value.as_mut_ref(), self.as_ref())
+        }));
+        Ok(Positive(value))
+    }
+
     pub fn into_elem<F: Field>(mut self, m: &Modulus<F>)
                                -> Result<Elem<F>, error::Unspecified> {
         try!(verify_less_than(&self, &m));
@@ -139,11 +150,7 @@ pub struct OddPositive(Positive);
 
 impl OddPositive {
     pub fn try_clone(&self) -> Result<OddPositive, error::Unspecified> {
-        let mut value = try!(Nonnegative::zero());
-        try!(bssl::map_result(unsafe {
-            GFp_BN_copy(value.as_mut_ref(), self.as_ref())
-        }));
-        Ok(OddPositive(Positive(value)))
+        Ok(OddPositive(try!(self.0.try_clone())))
     }
 
     pub fn into_elem<F: Field>(self, m: &Modulus<F>)
@@ -255,6 +262,36 @@ pub fn elem_mul_mixed<F: Field>(a: &Elem<F>, b: ElemDecoded<F>, m: &Modulus<F>)
 }
 
 
+/// Returns the length of the DER encoding of the positive integer `a`,
+/// including the tag and length.
+#[cfg(feature = "rsa_signing")]
+pub fn der_encoded_len(a: &Positive) -> usize {
+    der::encoded_len(der_value_len(a))
+}
+
+/// Writes the DER encoding of the positive integer `a` to `output`.
+#[cfg(feature = "rsa_signing")]
+pub fn write_der(a: &Positive, output: &mut std::vec::Vec<u8>)
+                 -> Result<(), error::Unspecified> {
+    let value_len = der_value_len(a);
+    der::write_tag_and_len(output, der::Tag::Integer, value_len);
+    let start = output.len();
+    output.resize(start + value_len, 0);
+    bssl::map_result(unsafe {
+        GFp_BN_bn2bin_padded(output[start..].as_mut_ptr(), value_len,
+                             a.as_ref())
+    })
+}
+
+// The encoding has a leading zero byte when the highest bit of the highest
+// byte is set, so that it isn't interpreted as a negative number.
+#[cfg(feature = "rsa_signing")]
+fn der_value_len(a: &Positive) -> usize {
+    let bits = a.bit_length().as_usize_bits();
+    (bits / 8) + 1
+}
+
+
 /// Nonnegative integers: `Positive` ∪ {0}.
 struct Nonnegative(*mut BIGNUM);
 
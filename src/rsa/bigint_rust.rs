@@ -0,0 +1,820 @@
+// Copyright 2015-2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! Multi-precision integers, implemented in Rust. Feature: `rust_bigint`.
+//!
+//! This has the same interface as the implementation in bigint.rs, which is
+//! built on BoringSSL's `BIGNUM` code, plus the modular arithmetic that the
+//! RSA private key operation and signature verification need, so that they
+//! can be done without any of the C code in crypto/bn or crypto/rsa.
+//!
+//! Values are stored as little-endian vectors of 32-bit words, so that the
+//! double-width products fit in a `u64` on every target. The length of a
+//! value is never secret, but everything else about a secret value is: all
+//! the arithmetic that is done on secret values, i.e. everything other than
+//! parsing, `bit_length()`, and `elem_exp_vartime()`, is done in constant time
+//! without any secret-dependent branches or memory accesses.
+
+// XXX TODO: Remove this once RSA verification has been done in Rust.
+#![cfg_attr(not(feature = "rsa_signing"), allow(dead_code))]
+
+use {bits, der, error, rand, untrusted};
+use core;
+use core::marker::PhantomData;
+use std;
+
+type Word = u32;
+type DoubleWord = u64;
+const WORD_BITS: usize = 32;
+const WORD_BYTES: usize = 4;
+
+/// This is defined for comparing values instead of using `PartialOrd` because
+/// there `PartialOrd` requires `PartialEq`, which we do not otherwise require.
+/// Also, this `Result<>`-based interface is more convenient for callers' uses.
+pub fn verify_less_than<A: core::convert::AsRef<Nonnegative>,
+                        B: core::convert::AsRef<Nonnegative>>(a: &A, b: &B)
+        -> Result<(), error::Unspecified> {
+    if !limbs_less_than(&a.as_ref().0, &b.as_ref().0) {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+
+impl AsRef<Nonnegative> for OddPositive {
+    fn as_ref<'a>(&'a self) -> &'a Nonnegative { self.0.as_ref() }
+}
+
+impl AsRef<Nonnegative> for Positive {
+    fn as_ref<'a>(&'a self) -> &'a Nonnegative { &self.0 }
+}
+
+
+/// Non-negative, non-zero integers.
+///
+/// This set is sometimes called `Natural` or `Counting`, but texts, libraries,
+/// and standards disagree on whether to include zero in them, so we avoid
+/// those names.
+pub struct Positive(Nonnegative);
+
+impl Positive {
+    // Parses a single ASN.1 DER-encoded `Integer`, which most be positive.
+    pub fn from_der(input: &mut untrusted::Reader)
+                    -> Result<Positive, error::Unspecified> {
+        Self::from_be_bytes(try!(der::positive_integer(input)))
+    }
+
+    // Turns a sequence of big-endian bytes into a Positive Integer.
+    pub fn from_be_bytes(input: untrusted::Input)
+                         -> Result<Positive, error::Unspecified> {
+        // Reject leading zeros. Also reject the value zero ([0]) because zero
+        // isn't positive.
+        if untrusted::Reader::new(input).peek(0) {
+            return Err(error::Unspecified);
+        }
+        Self::from_be_bytes_padded(input)
+    }
+
+    pub fn from_be_bytes_padded(input: untrusted::Input)
+                                -> Result<Positive, error::Unspecified> {
+        // Reject empty inputs.
+        if input.is_empty() {
+            return Err(error::Unspecified);
+        }
+        let r = Nonnegative(limbs_from_be_bytes(input.as_slice_less_safe()));
+        if r.is_zero() {
+            return Err(error::Unspecified);
+        }
+        Ok(Positive(r))
+    }
+
+    pub fn try_clone(&self) -> Result<Positive, error::Unspecified> {
+        Ok(Positive(Nonnegative((self.0).0.clone())))
+    }
+
+    pub fn into_elem<F: Field>(self, m: &Modulus<F>)
+                               -> Result<Elem<F>, error::Unspecified> {
+        let decoded = try!(self.into_elem_decoded(m));
+        Ok(decoded.into_elem(m))
+    }
+
+    pub fn into_elem_decoded<F: Field>(self, m: &Modulus<F>)
+            -> Result<ElemDecoded<F>, error::Unspecified> {
+        if !limbs_less_than(&(self.0).0, &m.n) {
+            return Err(error::Unspecified);
+        }
+        let mut value = (self.0).0;
+        value.resize(m.n.len(), 0);
+        Ok(ElemDecoded {
+            value: value,
+            field: PhantomData,
+        })
+    }
+
+    pub fn into_odd_positive(self) -> Result<OddPositive, error::Unspecified> {
+        self.0.into_odd_positive()
+    }
+
+    pub fn bit_length(&self) -> bits::BitLength {
+        bits::BitLength::from_usize_bits(limbs_bit_length(&(self.0).0))
+    }
+
+    pub fn limbs(&self) -> &[Word] { &(self.0).0 }
+}
+
+/// Odd positive integers.
+pub struct OddPositive(Positive);
+
+impl OddPositive {
+    pub fn try_clone(&self) -> Result<OddPositive, error::Unspecified> {
+        Ok(OddPositive(try!(self.0.try_clone())))
+    }
+
+    pub fn into_elem<F: Field>(self, m: &Modulus<F>)
+            -> Result<Elem<F>, error::Unspecified> {
+        self.0.into_elem(m)
+    }
+
+    pub fn into_elem_decoded<F: Field>(self, m: &Modulus<F>)
+            -> Result<ElemDecoded<F>, error::Unspecified> {
+        self.0.into_elem_decoded(m)
+    }
+
+    pub fn into_modulus<F: Field>(self)
+                                  -> Result<Modulus<F>, error::Unspecified> {
+        let mut n = ((self.0).0).0;
+        let bits = limbs_bit_length(&n);
+        // `bits < 2` means `n == 1`, for which Montgomery multiplication is
+        // meaningless.
+        if bits < 2 {
+            return Err(error::Unspecified);
+        }
+        n.truncate((bits + WORD_BITS - 1) / WORD_BITS);
+
+        // n0 = -1/n (mod 2**WORD_BITS), computed with Newton's method. Each
+        // iteration doubles the number of correct low-order bits, starting
+        // with the three bits that are correct because `n` is odd.
+        let mut inv: Word = n[0];
+        for _ in 0..4 {
+            inv = inv.wrapping_mul(2u32.wrapping_sub(n[0].wrapping_mul(inv)));
+        }
+        debug_assert_eq!(n[0].wrapping_mul(inv), 1);
+        let n0 = inv.wrapping_neg();
+
+        // RR = R**2 (mod n), calculated by doubling 2**(bits - 1), which is
+        // less than `n`, until it becomes R**2.
+        let mut rr = vec![0; n.len()];
+        rr[(bits - 1) / WORD_BITS] = 1 << ((bits - 1) % WORD_BITS);
+        for _ in (bits - 1)..(2 * WORD_BITS * n.len()) {
+            let carry = limbs_double(&mut rr);
+            limbs_reduce_once(&mut rr, carry, &n);
+        }
+
+        Ok(Modulus {
+            n: n,
+            n0: n0,
+            rr: rr,
+            field: PhantomData,
+        })
+    }
+}
+
+impl core::ops::Deref for OddPositive {
+    type Target = Positive;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+
+/// Every modulus (`n`, `p`, `q`, etc.) should be represented as a separate
+/// type implementing `Field`.
+pub unsafe trait Field {}
+
+/// A modulus that can be used for Montgomery math.
+///
+/// R is 2**(`WORD_BITS` * `n.len()`).
+pub struct Modulus<F: Field> {
+    n: std::vec::Vec<Word>,
+    n0: Word,
+    rr: std::vec::Vec<Word>,
+    field: PhantomData<F>,
+}
+
+impl<F: Field> Modulus<F> {
+    /// The length of the modulus in bytes, without leading zeros.
+    pub fn len_bytes(&self) -> usize {
+        (limbs_bit_length(&self.n) + 7) / 8
+    }
+}
+
+/// Montgomery-encoded elements of a field.
+pub struct Elem<F: Field> {
+    value: std::vec::Vec<Word>,
+    field: PhantomData<F>,
+}
+
+pub struct ElemDecoded<F: Field> {
+    value: std::vec::Vec<Word>,
+    field: PhantomData<F>
+}
+
+impl<F: Field> ElemDecoded<F> {
+    pub fn fill_be_bytes(&self, out: &mut [u8])
+                         -> Result<(), error::Unspecified> {
+        limbs_fill_be_bytes(&self.value, out)
+    }
+
+    pub fn try_clone(&self) -> Result<Self, error::Unspecified> {
+        Ok(ElemDecoded {
+            value: self.value.clone(),
+            field: PhantomData,
+        })
+    }
+
+    pub fn into_elem(self, m: &Modulus<F>) -> Elem<F> {
+        Elem {
+            value: mont_mul(&self.value, &m.rr, m),
+            field: PhantomData,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool { limbs_are_zero(&self.value) }
+
+    pub fn is_one(&self) -> bool {
+        let (low, high) = self.value.split_at(1);
+        (low[0] == 1) && limbs_are_zero(high)
+    }
+}
+
+// `a` * `b` (mod `m`).
+pub fn elem_mul_mixed<F: Field>(a: &Elem<F>, b: ElemDecoded<F>, m: &Modulus<F>)
+                                -> Result<ElemDecoded<F>, error::Unspecified> {
+    Ok(ElemDecoded {
+        value: mont_mul(&a.value, &b.value, m),
+        field: PhantomData,
+    })
+}
+
+// `a`**2 (mod `m`).
+pub fn elem_squared<F: Field>(a: Elem<F>, m: &Modulus<F>) -> Elem<F> {
+    Elem {
+        value: mont_mul(&a.value, &a.value, m),
+        field: PhantomData,
+    }
+}
+
+// `a` (mod `m`), where `a` is less than `m` * R. In particular, given
+// 0 < q < p, p * q == n, and p and q having the same number of words, any
+// element of n can be reduced modulo p or q, and any element of q or p can be
+// reduced modulo p or n.
+pub fn elem_reduced<Larger: Field, Smaller: Field>(a: &ElemDecoded<Larger>,
+                                                   m: &Modulus<Smaller>)
+        -> Result<ElemDecoded<Smaller>, error::Unspecified> {
+    if a.value.len() > 2 * m.n.len() {
+        return Err(error::Unspecified);
+    }
+    // (a / R) * R**2 / R == a (mod m).
+    let a_over_r = redc(&a.value, m);
+    Ok(ElemDecoded {
+        value: mont_mul(&a_over_r, &m.rr, m),
+        field: PhantomData,
+    })
+}
+
+// `a` + `b` (mod `m`).
+pub fn elem_add<F: Field>(a: ElemDecoded<F>, b: &ElemDecoded<F>,
+                          m: &Modulus<F>) -> ElemDecoded<F> {
+    let mut r = a.value;
+    let carry = limbs_add_assign(&mut r, &b.value);
+    limbs_reduce_once(&mut r, carry, &m.n);
+    ElemDecoded {
+        value: r,
+        field: PhantomData,
+    }
+}
+
+// `a` - `b` (mod `m`).
+pub fn elem_sub<F: Field>(a: ElemDecoded<F>, b: &ElemDecoded<F>,
+                          m: &Modulus<F>) -> ElemDecoded<F> {
+    let mut r = a.value;
+    let borrow = limbs_sub_assign(&mut r, &b.value);
+    // Add `m` back if the subtraction borrowed.
+    let mask = borrow.wrapping_neg();
+    let mut carry: DoubleWord = 0;
+    for (r, &n_i) in r.iter_mut().zip(m.n.iter()) {
+        let x = DoubleWord::from(*r) + DoubleWord::from(n_i & mask) + carry;
+        *r = x as Word;
+        carry = x >> WORD_BITS;
+    }
+    ElemDecoded {
+        value: r,
+        field: PhantomData,
+    }
+}
+
+// `base`**`exponent` (mod `m`), in constant time with respect to `base` and
+// `exponent`.
+pub fn elem_exp_consttime<F: Field>(base: ElemDecoded<F>,
+                                    exponent: &OddPositive, m: &Modulus<F>)
+        -> Result<ElemDecoded<F>, error::Unspecified> {
+    Ok(ElemDecoded {
+        value: exp_consttime(&base.value, exponent.limbs(), m),
+        field: PhantomData,
+    })
+}
+
+// `base`**(`m` - 2) (mod `m`), which is the inverse of `base` when `m` is
+// prime and `base` isn't zero.
+pub fn elem_inverse_mod_prime<F: Field>(base: ElemDecoded<F>, m: &Modulus<F>)
+                                        -> ElemDecoded<F> {
+    let mut exponent = m.n.clone();
+    let borrow = limbs_sub_assign(&mut exponent, &[2]);
+    debug_assert_eq!(borrow, 0);
+    ElemDecoded {
+        value: exp_consttime(&base.value, &exponent, m),
+        field: PhantomData,
+    }
+}
+
+// `base`**`exponent` (mod `m`). This is only constant time with respect to
+// `base`; `exponent` (the public exponent) must not be secret.
+pub fn elem_exp_vartime<F: Field>(base: ElemDecoded<F>,
+                                  exponent: &OddPositive, m: &Modulus<F>)
+        -> Result<ElemDecoded<F>, error::Unspecified> {
+    let exponent = exponent.limbs();
+    let bits = limbs_bit_length(exponent);
+    let base = mont_mul(&base.value, &m.rr, m);
+    let mut acc = base.clone();
+    for bit in (0..(bits - 1)).rev() {
+        acc = mont_mul(&acc, &acc, m);
+        if (exponent[bit / WORD_BITS] >> (bit % WORD_BITS)) & 1 == 1 {
+            acc = mont_mul(&acc, &base, m);
+        }
+    }
+    Ok(ElemDecoded {
+        value: redc(&acc, m),
+        field: PhantomData,
+    })
+}
+
+// Returns `Ok(())` if `a == b`, in constant time.
+pub fn elem_verify_equal<F: Field>(a: &ElemDecoded<F>, b: &ElemDecoded<F>)
+                                   -> Result<(), error::Unspecified> {
+    let diff = a.value.iter().zip(b.value.iter())
+                .fold(0, |acc, (&a, &b)| acc | (a ^ b));
+    if diff != 0 {
+        return Err(error::Unspecified);
+    }
+    Ok(())
+}
+
+// Chooses an element in the range [1, `m`) uniformly at random.
+pub fn elem_random<F: Field>(m: &Modulus<F>, rng: &rand::SecureRandom)
+                             -> Result<ElemDecoded<F>, error::Unspecified> {
+    let bits = limbs_bit_length(&m.n);
+    let mut bytes = [0u8; PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN];
+    let bytes = &mut bytes[..((bits + 7) / 8)];
+
+    // Each candidate is in range with probability greater than 1/2, so the
+    // chance of this failing for a good `rng` is negligible.
+    for _ in 0..100 {
+        try!(rng.fill(bytes));
+        // Mask off the bits above the top bit of `m`.
+        bytes[0] &= 0xff >> ((8 - (bits % 8)) % 8);
+        let mut candidate = limbs_from_be_bytes(bytes);
+        candidate.resize(m.n.len(), 0);
+        if limbs_less_than(&candidate, &m.n) && !limbs_are_zero(&candidate) {
+            return Ok(ElemDecoded {
+                value: candidate,
+                field: PhantomData,
+            });
+        }
+    }
+    Err(error::Unspecified)
+}
+
+const PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN: usize =
+    super::PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN;
+
+
+/// Returns the length of the DER encoding of the positive integer `a`,
+/// including the tag and length.
+#[cfg(feature = "rsa_signing")]
+pub fn der_encoded_len(a: &Positive) -> usize {
+    der::encoded_len(der_value_len(a))
+}
+
+/// Writes the DER encoding of the positive integer `a` to `output`.
+#[cfg(feature = "rsa_signing")]
+pub fn write_der(a: &Positive, output: &mut std::vec::Vec<u8>)
+                 -> Result<(), error::Unspecified> {
+    let value_len = der_value_len(a);
+    der::write_tag_and_len(output, der::Tag::Integer, value_len);
+    let start = output.len();
+    output.resize(start + value_len, 0);
+    limbs_fill_be_bytes(a.limbs(), &mut output[start..])
+}
+
+// The encoding has a leading zero byte when the highest bit of the highest
+// byte is set, so that it isn't interpreted as a negative number.
+#[cfg(feature = "rsa_signing")]
+fn der_value_len(a: &Positive) -> usize {
+    let bits = a.bit_length().as_usize_bits();
+    (bits / 8) + 1
+}
+
+
+/// Nonnegative integers: `Positive` ∪ {0}.
+pub struct Nonnegative(std::vec::Vec<Word>);
+
+impl Nonnegative {
+    fn is_zero(&self) -> bool { limbs_are_zero(&self.0) }
+
+    fn into_odd_positive(self) -> Result<OddPositive, error::Unspecified> {
+        if self.0[0] & 1 == 0 {
+            return Err(error::Unspecified);
+        }
+        Ok(OddPositive(Positive(self)))
+    }
+}
+
+
+// a * b / R (mod m). `a` and `b` must be less than `m`.
+//
+// This is the "Coarsely Integrated Operand Scanning" method of
+// [Analyzing and Comparing Montgomery Multiplication Algorithms].
+//
+// [Analyzing and Comparing Montgomery Multiplication Algorithms]:
+//     https://www.microsoft.com/en-us/research/wp-content/uploads/1996/01/j37acmon.pdf
+fn mont_mul<F: Field>(a: &[Word], b: &[Word], m: &Modulus<F>)
+                      -> std::vec::Vec<Word> {
+    let n = &m.n;
+    let k = n.len();
+    assert_eq!(a.len(), k);
+    assert_eq!(b.len(), k);
+
+    let mut t = vec![0; k + 2];
+    for &a_i in a {
+        // t += a_i * b.
+        let a_i = DoubleWord::from(a_i);
+        let mut carry: DoubleWord = 0;
+        for (t_j, &b_j) in t.iter_mut().zip(b.iter()) {
+            let x = DoubleWord::from(*t_j) + (a_i * DoubleWord::from(b_j)) +
+                    carry;
+            *t_j = x as Word;
+            carry = x >> WORD_BITS;
+        }
+        let x = DoubleWord::from(t[k]) + carry;
+        t[k] = x as Word;
+        t[k + 1] = (x >> WORD_BITS) as Word;
+
+        // t = (t + u * n) / 2**WORD_BITS, where `u` is chosen so that the
+        // division is exact.
+        let u = DoubleWord::from(t[0].wrapping_mul(m.n0));
+        let x = DoubleWord::from(t[0]) + (u * DoubleWord::from(n[0]));
+        let mut carry = x >> WORD_BITS;
+        for j in 1..k {
+            let x = DoubleWord::from(t[j]) + (u * DoubleWord::from(n[j])) +
+                    carry;
+            t[j - 1] = x as Word;
+            carry = x >> WORD_BITS;
+        }
+        let x = DoubleWord::from(t[k]) + carry;
+        t[k - 1] = x as Word;
+        t[k] = t[k + 1] + ((x >> WORD_BITS) as Word);
+    }
+
+    // t < 2 * n, so at most one subtraction is needed.
+    let carry = t[k];
+    t.truncate(k);
+    limbs_reduce_once(&mut t, carry, n);
+    t
+}
+
+// a / R (mod m), for any `a` less than `m` * R. `a` may have up to twice as
+// many words as `m`.
+fn redc<F: Field>(a: &[Word], m: &Modulus<F>) -> std::vec::Vec<Word> {
+    let n = &m.n;
+    let k = n.len();
+    assert!(a.len() <= 2 * k);
+
+    let mut t = vec![0; (2 * k) + 1];
+    t[..a.len()].copy_from_slice(a);
+    for i in 0..k {
+        // t += u * n * 2**(i * WORD_BITS), where `u` is chosen to make word
+        // `i` of `t` zero.
+        let u = DoubleWord::from(t[i].wrapping_mul(m.n0));
+        let mut carry: DoubleWord = 0;
+        for (t_j, &n_j) in t[i..(i + k)].iter_mut().zip(n.iter()) {
+            let x = DoubleWord::from(*t_j) + (u * DoubleWord::from(n_j)) +
+                    carry;
+            *t_j = x as Word;
+            carry = x >> WORD_BITS;
+        }
+        for t_j in t[(i + k)..].iter_mut() {
+            let x = DoubleWord::from(*t_j) + carry;
+            *t_j = x as Word;
+            carry = x >> WORD_BITS;
+        }
+    }
+
+    // The result is less than (m * R + R * m) / R = 2 * m.
+    let carry = t[2 * k];
+    let mut r = t[k..(2 * k)].to_vec();
+    limbs_reduce_once(&mut r, carry, n);
+    r
+}
+
+// `base`**`exponent` (mod `m`), using a fixed 4-bit window. Every entry of the
+// table is read for every window, so that the memory access pattern doesn't
+// depend on the exponent. `exponent` is treated as having at least as many
+// words as `m`.
+fn exp_consttime<F: Field>(base: &[Word], exponent: &[Word], m: &Modulus<F>)
+                           -> std::vec::Vec<Word> {
+    const WINDOW_BITS: usize = 4;
+    const TABLE_LEN: usize = 1 << WINDOW_BITS;
+
+    let k = m.n.len();
+    let mut table = std::vec::Vec::with_capacity(TABLE_LEN);
+    table.push(redc(&m.rr, m)); // R (mod m), i.e. 1 Montgomery-encoded.
+    table.push(mont_mul(base, &m.rr, m));
+    for i in 2..TABLE_LEN {
+        let entry = mont_mul(&table[i - 1], &table[1], m);
+        table.push(entry);
+    }
+
+    let num_words = core::cmp::max(exponent.len(), k);
+    let mut acc = table[0].clone();
+    let mut entry = vec![0; k];
+    let mut bit = num_words * WORD_BITS;
+    while bit > 0 {
+        bit -= WINDOW_BITS;
+        for _ in 0..WINDOW_BITS {
+            acc = mont_mul(&acc, &acc, m);
+        }
+        let word = exponent.get(bit / WORD_BITS).cloned().unwrap_or(0);
+        let window = (word >> (bit % WORD_BITS)) & (TABLE_LEN as Word - 1);
+        for e in entry.iter_mut() {
+            *e = 0;
+        }
+        for (i, table_entry) in table.iter().enumerate() {
+            let mask = word_is_zero_mask((i as Word) ^ window);
+            for (e, &t) in entry.iter_mut().zip(table_entry.iter()) {
+                *e |= t & mask;
+            }
+        }
+        acc = mont_mul(&acc, &entry, m);
+    }
+    redc(&acc, m)
+}
+
+// Returns all ones if `a == 0`, or zero otherwise.
+fn word_is_zero_mask(a: Word) -> Word {
+    ((a | a.wrapping_neg()) >> (WORD_BITS - 1)).wrapping_sub(1)
+}
+
+
+fn limbs_from_be_bytes(input: &[u8]) -> std::vec::Vec<Word> {
+    let mut r = vec![0; (input.len() + WORD_BYTES - 1) / WORD_BYTES];
+    for (i, &b) in input.iter().rev().enumerate() {
+        r[i / WORD_BYTES] |= Word::from(b) << (8 * (i % WORD_BYTES));
+    }
+    r
+}
+
+fn limbs_fill_be_bytes(a: &[Word], out: &mut [u8])
+                       -> Result<(), error::Unspecified> {
+    if limbs_bit_length(a) > 8 * out.len() {
+        return Err(error::Unspecified);
+    }
+    for (i, o) in out.iter_mut().rev().enumerate() {
+        let word = a.get(i / WORD_BYTES).cloned().unwrap_or(0);
+        *o = (word >> (8 * (i % WORD_BYTES))) as u8;
+    }
+    Ok(())
+}
+
+// This is not constant time, so it must only be used on values with public
+// lengths, like moduli.
+fn limbs_bit_length(a: &[Word]) -> usize {
+    match a.iter().rposition(|&w| w != 0) {
+        Some(i) => (i * WORD_BITS) + (WORD_BITS - a[i].leading_zeros() as usize),
+        None => 0,
+    }
+}
+
+fn limbs_are_zero(a: &[Word]) -> bool {
+    a.iter().fold(0, |acc, &w| acc | w) == 0
+}
+
+// Returns `a < b`. `a` and `b` may have different lengths.
+fn limbs_less_than(a: &[Word], b: &[Word]) -> bool {
+    let len = core::cmp::max(a.len(), b.len());
+    let mut borrow = 0;
+    for i in 0..len {
+        let a_i = a.get(i).cloned().unwrap_or(0);
+        let b_i = b.get(i).cloned().unwrap_or(0);
+        let (d, b1) = a_i.overflowing_sub(b_i);
+        let (_, b2) = d.overflowing_sub(borrow);
+        borrow = (b1 | b2) as Word;
+    }
+    borrow == 1
+}
+
+// r = 2 * r, returning the carry.
+fn limbs_double(r: &mut [Word]) -> Word {
+    let mut carry = 0;
+    for w in r.iter_mut() {
+        let new_carry = *w >> (WORD_BITS - 1);
+        *w = (*w << 1) | carry;
+        carry = new_carry;
+    }
+    carry
+}
+
+// r += a, returning the carry. `a` must not be longer than `r`.
+fn limbs_add_assign(r: &mut [Word], a: &[Word]) -> Word {
+    let mut carry: DoubleWord = 0;
+    for (i, r_i) in r.iter_mut().enumerate() {
+        let a_i = a.get(i).cloned().unwrap_or(0);
+        let x = DoubleWord::from(*r_i) + DoubleWord::from(a_i) + carry;
+        *r_i = x as Word;
+        carry = x >> WORD_BITS;
+    }
+    carry as Word
+}
+
+// r -= a, returning the borrow. `a` must not be longer than `r`.
+fn limbs_sub_assign(r: &mut [Word], a: &[Word]) -> Word {
+    assert!(a.len() <= r.len());
+    let mut borrow = 0;
+    for (i, r_i) in r.iter_mut().enumerate() {
+        let a_i = a.get(i).cloned().unwrap_or(0);
+        let (d, b1) = r_i.overflowing_sub(a_i);
+        let (d, b2) = d.overflowing_sub(borrow);
+        *r_i = d;
+        borrow = (b1 | b2) as Word;
+    }
+    borrow
+}
+
+// Given r + `carry` * 2**(WORD_BITS * r.len()) < 2 * m, sets r to
+// (r + `carry` * 2**(WORD_BITS * r.len())) mod m.
+fn limbs_reduce_once(r: &mut [Word], carry: Word, m: &[Word]) {
+    debug_assert_eq!(r.len(), m.len());
+    let mut reduced = r.to_vec();
+    let borrow = limbs_sub_assign(&mut reduced, m);
+    // Use `reduced` unless the subtraction borrowed more than `carry`.
+    let mask = (carry | (borrow ^ 1)).wrapping_neg();
+    for (r, reduced) in r.iter_mut().zip(reduced.iter()) {
+        *r = (*reduced & mask) | (*r & !mask);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {rand, test};
+    use untrusted;
+
+    enum M {}
+    unsafe impl Field for M {}
+
+    enum Larger {}
+    unsafe impl Field for Larger {}
+
+    fn consume_positive(test_case: &mut test::TestCase, name: &str)
+                        -> Positive {
+        let bytes = test_case.consume_bytes(name);
+        Positive::from_be_bytes_padded(untrusted::Input::from(&bytes))
+            .unwrap()
+    }
+
+    fn consume_modulus<F: Field>(test_case: &mut test::TestCase, name: &str)
+                                 -> (Modulus<F>, usize) {
+        let bytes = test_case.consume_bytes(name);
+        let m = Positive::from_be_bytes_padded(untrusted::Input::from(&bytes))
+            .unwrap();
+        let m = m.into_odd_positive().unwrap().into_modulus::<F>().unwrap();
+        (m, bytes.len())
+    }
+
+    fn assert_elem_eq<F: Field>(a: &ElemDecoded<F>, expected: &[u8]) {
+        let mut actual = vec![0u8; expected.len()];
+        a.fill_be_bytes(&mut actual).unwrap();
+        assert_eq!(&actual[..], expected);
+    }
+
+    #[test]
+    fn test_bigint_rust() {
+        test::from_file("src/rsa/bigint_rust_tests.txt",
+                        |section, test_case| {
+            let (m, m_len) = consume_modulus::<M>(test_case, "M");
+            match section {
+                "ModMul" => {
+                    let a = consume_positive(test_case, "A").into_elem(&m)
+                                .unwrap();
+                    let b = consume_positive(test_case, "B")
+                                .into_elem_decoded(&m).unwrap();
+                    let expected = test_case.consume_bytes("ModMul");
+                    assert_eq!(expected.len(), m_len);
+                    let actual = elem_mul_mixed(&a, b, &m).unwrap();
+                    assert_elem_eq(&actual, &expected);
+                },
+
+                "ModExp" => {
+                    let a = consume_positive(test_case, "A");
+                    let e = consume_positive(test_case, "E")
+                                .into_odd_positive().unwrap();
+                    let expected = test_case.consume_bytes("ModExp");
+                    assert_eq!(expected.len(), m_len);
+
+                    let base = a.try_clone().unwrap().into_elem_decoded(&m)
+                                .unwrap();
+                    let actual = elem_exp_vartime(base, &e, &m).unwrap();
+                    assert_elem_eq(&actual, &expected);
+
+                    let base = a.into_elem_decoded(&m).unwrap();
+                    let actual = elem_exp_consttime(base, &e, &m).unwrap();
+                    assert_elem_eq(&actual, &expected);
+                },
+
+                "ModInv" => {
+                    let a = consume_positive(test_case, "A")
+                                .into_elem_decoded(&m).unwrap();
+                    let expected = test_case.consume_bytes("ModInv");
+                    assert_eq!(expected.len(), m_len);
+                    let actual = elem_inverse_mod_prime(a, &m);
+                    assert_elem_eq(&actual, &expected);
+                },
+
+                "ModAddSub" => {
+                    let a = consume_positive(test_case, "A");
+                    let b = consume_positive(test_case, "B")
+                                .into_elem_decoded(&m).unwrap();
+                    let sum = test_case.consume_bytes("Sum");
+                    let difference = test_case.consume_bytes("Difference");
+                    let a_ = a.try_clone().unwrap().into_elem_decoded(&m)
+                                .unwrap();
+                    assert_elem_eq(&elem_add(a_, &b, &m), &sum);
+                    let a_ = a.into_elem_decoded(&m).unwrap();
+                    assert_elem_eq(&elem_sub(a_, &b, &m), &difference);
+                },
+
+                "Reduce" => {
+                    // `A` is reduced from a field with a modulus of `L`.
+                    let (l, _) = consume_modulus::<Larger>(test_case, "L");
+                    let a = consume_positive(test_case, "A")
+                                .into_elem_decoded(&l).unwrap();
+                    let expected = test_case.consume_bytes("Reduced");
+                    assert_eq!(expected.len(), m_len);
+                    let actual = elem_reduced(&a, &m).unwrap();
+                    assert_elem_eq(&actual, &expected);
+                },
+
+                _ => unreachable!(),
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_bigint_rust_modulus_one() {
+        let one = Positive::from_be_bytes(untrusted::Input::from(&[1]))
+            .unwrap();
+        let one = one.into_odd_positive().unwrap();
+        assert!(one.into_modulus::<M>().is_err());
+    }
+
+    #[test]
+    fn test_bigint_rust_elem_random() {
+        let m = Positive::from_be_bytes(untrusted::Input::from(&[0x01, 0x01]))
+            .unwrap();
+        let m = m.into_odd_positive().unwrap().into_modulus::<M>().unwrap();
+
+        let rng = rand::SystemRandom::new();
+        for _ in 0..100 {
+            let r = elem_random(&m, &rng).unwrap();
+            assert!(!r.is_zero());
+            let mut bytes = [0u8; 2];
+            r.fill_be_bytes(&mut bytes).unwrap();
+            assert!(bytes < [0x01, 0x01]);
+        }
+
+        // An RNG that only ever returns zero can never produce a value in
+        // range.
+        let rng = test::rand::FixedByteRandom { byte: 0x00 };
+        assert!(elem_random(&m, &rng).is_err());
+    }
+}
@@ -13,17 +13,25 @@
 // CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
 
 use error;
+use std;
 use super::bigint;
 
+#[cfg(feature = "rust_bigint")]
+use super::N;
+
+#[cfg(not(feature = "rust_bigint"))]
 pub struct Blinding(*mut BN_BLINDING);
 
+#[cfg(not(feature = "rust_bigint"))]
 impl Drop for Blinding {
     fn drop(&mut self) { unsafe { GFp_BN_BLINDING_free(self.as_mut_ref()) } }
 }
 
 // `Blinding` uniquely owns and references its contents.
+#[cfg(not(feature = "rust_bigint"))]
 unsafe impl Send for Blinding {}
 
+#[cfg(not(feature = "rust_bigint"))]
 impl Blinding {
     pub fn new() -> Result<Blinding, error::Unspecified> {
         let r = unsafe { GFp_BN_BLINDING_new() };
@@ -39,7 +47,149 @@ impl Blinding {
     pub fn as_mut_ref(&mut self) -> &mut BN_BLINDING { unsafe { &mut *self.0 } }
 }
 
+/// The blinding factors for the Rust implementation of the RSA private key
+/// operation. This works exactly like `BN_BLINDING` in
+/// `crypto/rsa/blinding.c`: the factors are squared each time they are used,
+/// and they are recreated every `COUNTER` uses.
+#[cfg(feature = "rust_bigint")]
+pub struct Blinding {
+    // `None` until the first use, and after a failure to recreate them.
+    factors: Option<Factors>,
+    counter: u32,
+}
+
+#[cfg(feature = "rust_bigint")]
+struct Factors {
+    // A random value raised to the public exponent, Montgomery-encoded.
+    a: bigint::Elem<N>,
+
+    // The inverse of the random value, Montgomery-encoded.
+    ai: bigint::Elem<N>,
+}
+
+#[cfg(feature = "rust_bigint")]
+const COUNTER: u32 = 32;
+
+#[cfg(feature = "rust_bigint")]
+impl Blinding {
+    pub fn new() -> Result<Blinding, error::Unspecified> {
+        Ok(Blinding {
+            factors: None,
+            // The factors need to be created before this can be used.
+            counter: COUNTER - 1,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn counter(&self) -> u32 { self.counter }
+
+    /// Updates the blinding factors and returns `x` blinded with them.
+    /// `new_factors` is called when the factors need to be recreated; it must
+    /// return (r**e, 1/r) (mod n), Montgomery-encoded, for a random `r`.
+    pub fn blind<F>(&mut self, x: bigint::ElemDecoded<N>,
+                    n: &bigint::Modulus<N>, new_factors: F)
+                    -> Result<bigint::ElemDecoded<N>, error::Unspecified>
+                    where F: FnOnce() -> Result<(bigint::Elem<N>,
+                                                 bigint::Elem<N>),
+                                                error::Unspecified> {
+        let factors = match self.factors.take() {
+            Some(factors) if self.counter + 1 < COUNTER => {
+                self.counter += 1;
+                Factors {
+                    a: bigint::elem_squared(factors.a, n),
+                    ai: bigint::elem_squared(factors.ai, n),
+                }
+            },
+            _ => {
+                // If this fails then the factors will be recreated the next
+                // time too.
+                self.counter = COUNTER - 1;
+                let (a, ai) = try!(new_factors());
+                self.counter = 0;
+                Factors { a: a, ai: ai }
+            },
+        };
+        let blinded = try!(bigint::elem_mul_mixed(&factors.a, x, n));
+        self.factors = Some(factors);
+        Ok(blinded)
+    }
+
+    /// Removes the blinding from `x`, which is the result of the private key
+    /// operation on a value returned from `blind()`.
+    pub fn unblind(&self, x: bigint::ElemDecoded<N>, n: &bigint::Modulus<N>)
+                   -> Result<bigint::ElemDecoded<N>, error::Unspecified> {
+        let factors = try!(self.factors.as_ref().ok_or(error::Unspecified));
+        bigint::elem_mul_mixed(&factors.ai, x, n)
+    }
+}
+
+/// The number of times a `Blinding` is used before its factors are recreated.
+#[cfg(all(test, not(feature = "rust_bigint")))]
+pub fn counter_limit() -> u32 { unsafe { GFp_BN_BLINDING_COUNTER } }
+
+/// The number of times a `Blinding` is used before its factors are recreated.
+#[cfg(all(test, feature = "rust_bigint"))]
+pub fn counter_limit() -> u32 { COUNTER }
+
+/// A lock-protected pool of `Blinding`s for a single key pair.
+///
+/// Creating the blinding factors for a `Blinding` requires a modular
+/// inversion, which is expensive. Returning `Blinding`s to the pool when they
+/// are no longer needed, instead of freeing them, lets short-lived users of
+/// the same key pair reuse blinding factors that have already been computed;
+/// each use of a `Blinding` squares its factors and they are periodically
+/// recreated, exactly as if the same user had used it the whole time.
+///
+/// The capacity is protected by the same lock as the `Blinding`s so that it
+/// can be changed while the key pair is shared.
+pub struct Cache {
+    pool: std::sync::Mutex<Pool>,
+}
+
+struct Pool {
+    capacity: usize,
+    blindings: std::vec::Vec<Blinding>,
+}
+
+impl Cache {
+    /// Constructs an empty cache that holds at most `capacity` `Blinding`s. A
+    /// cache with a capacity of zero never holds anything.
+    pub fn new(capacity: usize) -> Cache {
+        Cache {
+            pool: std::sync::Mutex::new(Pool {
+                capacity: capacity,
+                blindings: std::vec::Vec::new(),
+            }),
+        }
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        if let Ok(mut pool) = self.pool.lock() {
+            pool.capacity = capacity;
+            pool.blindings.truncate(capacity);
+        }
+    }
+
+    /// Removes a `Blinding` from the cache, if the cache isn't empty.
+    pub fn take(&self) -> Option<Blinding> {
+        match self.pool.lock() {
+            Ok(mut pool) => pool.blindings.pop(),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns `blinding` to the cache, or frees it if the cache is full.
+    pub fn put(&self, blinding: Blinding) {
+        if let Ok(mut pool) = self.pool.lock() {
+            if pool.blindings.len() < pool.capacity {
+                pool.blindings.push(blinding);
+            }
+        }
+    }
+}
+
 /// Needs to be kept in sync with `bn_blinding_st` in `crypto/rsa/blinding.c`.
+#[cfg(not(feature = "rust_bigint"))]
 #[allow(non_camel_case_types)]
 #[repr(C)]
 pub struct BN_BLINDING {
@@ -48,14 +198,15 @@ pub struct BN_BLINDING {
     counter: u32,
 }
 
+#[cfg(not(feature = "rust_bigint"))]
 extern {
     fn GFp_BN_BLINDING_new() -> *mut BN_BLINDING;
     fn GFp_BN_BLINDING_free(b: &mut BN_BLINDING);
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "rust_bigint")))]
 extern {
-    pub static GFp_BN_BLINDING_COUNTER: u32;
+    static GFp_BN_BLINDING_COUNTER: u32;
 }
 
 #[cfg(test)]
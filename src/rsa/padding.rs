@@ -130,7 +130,11 @@ macro_rules! rsa_pkcs1_padding {
 
 rsa_pkcs1_padding!(RSA_PKCS1_SHA1, &digest::SHA1,
                    &SHA1_PKCS1_DIGESTINFO_PREFIX,
-                   "PKCS#1 1.5 padding using SHA-1 for RSA signatures.");
+                   "PKCS#1 1.5 padding using SHA-1 for RSA signatures.\n\n\
+                   **Legacy**: SHA-1 is broken, so this must not be used \
+                   except for interoperability with legacy protocols that \
+                   require it. Signing with it also requires the \
+                   `rsa_pkcs1_sha1_signing` feature.");
 rsa_pkcs1_padding!(RSA_PKCS1_SHA256, &digest::SHA256,
                    &SHA256_PKCS1_DIGESTINFO_PREFIX,
                    "PKCS#1 1.5 padding using SHA-256 for RSA signatures.");
@@ -178,6 +182,7 @@ pkcs1_digestinfo_prefix!(
 /// [RFC 3447 Section 8.1]: https://tools.ietf.org/html/rfc3447#section-8.1
 pub struct PSS {
     digest_alg: &'static digest::Algorithm,
+    mgf1_digest_alg: &'static digest::Algorithm,
 }
 
 impl ::private::Private for PSS { }
@@ -232,7 +237,7 @@ impl RSAEncoding for PSS {
         // Step 9. First output the mask into the out buffer.
         let (mut masked_db, mut digest_terminator) =
             em.split_at_mut(metrics.db_len);
-        try!(mgf1(self.digest_alg, h_hash.as_ref(), &mut masked_db));
+        try!(mgf1(self.mgf1_digest_alg, h_hash.as_ref(), &mut masked_db));
 
         {
             // Steps 7.
@@ -304,7 +309,7 @@ impl RSAVerification for PSS {
         let mut db = [0u8; PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN];
         let db = &mut db[..metrics.db_len];
 
-        try!(mgf1(self.digest_alg, h_hash.as_slice_less_safe(), db));
+        try!(mgf1(self.mgf1_digest_alg, h_hash.as_slice_less_safe(), db));
 
         try!(masked_db.read_all(error::Unspecified, |masked_bytes| {
             // Step 6. Check the top bits of first byte are zero.
@@ -434,12 +439,18 @@ fn pss_digest(digest_alg: &'static digest::Algorithm, m_hash: &digest::Digest,
 
 macro_rules! rsa_pss_padding {
     ( $PADDING_ALGORITHM:ident, $digest_alg:expr, $doc_str:expr ) => {
+        rsa_pss_padding!($PADDING_ALGORITHM, $digest_alg, $digest_alg,
+                         $doc_str);
+    };
+    ( $PADDING_ALGORITHM:ident, $digest_alg:expr, $mgf1_digest_alg:expr,
+      $doc_str:expr ) => {
         #[doc=$doc_str]
         /// Feature: `rsa_signing`.
         pub static $PADDING_ALGORITHM: PSS = PSS {
             digest_alg: $digest_alg,
+            mgf1_digest_alg: $mgf1_digest_alg,
         };
-    }
+    };
 }
 
 rsa_pss_padding!(RSA_PSS_SHA256, &digest::SHA256,
@@ -455,6 +466,22 @@ rsa_pss_padding!(RSA_PSS_SHA512, &digest::SHA512,
                  \"`RSA_PSS_*` Details\" in `ring::signature`'s module-level
                  documentation for more details.");
 
+rsa_pss_padding!(RSA_PSS_SHA256_MGF1_SHA1, &digest::SHA256, &digest::SHA1,
+                 "RSA PSS padding using SHA-256 for the message digest and
+                 SHA-1 for MGF1 for RSA signatures.\n\nSee
+                 \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+                 module-level documentation for more details.");
+rsa_pss_padding!(RSA_PSS_SHA384_MGF1_SHA1, &digest::SHA384, &digest::SHA1,
+                 "RSA PSS padding using SHA-384 for the message digest and
+                 SHA-1 for MGF1 for RSA signatures.\n\nSee
+                 \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+                 module-level documentation for more details.");
+rsa_pss_padding!(RSA_PSS_SHA512_MGF1_SHA1, &digest::SHA512, &digest::SHA1,
+                 "RSA PSS padding using SHA-512 for the message digest and
+                 SHA-1 for MGF1 for RSA signatures.\n\nSee
+                 \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+                 module-level documentation for more details.");
+
 #[cfg(test)]
 mod test {
     use {digest, error, test};
@@ -19,10 +19,13 @@ use untrusted;
 
 mod padding;
 
-// `RSA_PKCS1_SHA1` is intentionally not exposed.
 #[cfg(feature = "rsa_signing")]
 pub use self::padding::RSAEncoding;
 
+// `RSA_PKCS1_SHA1` is only exposed for legacy protocols that require it.
+#[cfg(feature = "rsa_pkcs1_sha1_signing")]
+pub use self::padding::RSA_PKCS1_SHA1;
+
 pub use self::padding::{
     RSA_PKCS1_SHA256,
     RSA_PKCS1_SHA384,
@@ -30,7 +33,11 @@ pub use self::padding::{
 
     RSA_PSS_SHA256,
     RSA_PSS_SHA384,
-    RSA_PSS_SHA512
+    RSA_PSS_SHA512,
+
+    RSA_PSS_SHA256_MGF1_SHA1,
+    RSA_PSS_SHA384_MGF1_SHA1,
+    RSA_PSS_SHA512_MGF1_SHA1
 };
 
 
@@ -46,10 +53,30 @@ const PRIVATE_KEY_PUBLIC_MODULUS_MAX_LIMBS: usize =
     (4096 + limb::LIMB_BITS - 1) / limb::LIMB_BITS;
 
 
+// Mitigate DoS attacks by limiting the exponent size. 33 bits was chosen as
+// the limit based on the recommendations in [1] and [2]. Windows CryptoAPI
+// (at least older versions) doesn't support values larger than 32 bits [3], so
+// it is unlikely that exponents larger than 32 bits are being used for
+// anything Windows commonly does.
+//
+// [1] https://www.imperialviolet.org/2012/03/16/rsae.html
+// [2] https://www.imperialviolet.org/2012/03/17/rsados.html
+// [3] https://msdn.microsoft.com/en-us/library/aa387685(VS.85).aspx
+const PUBLIC_EXPONENT_MAX_BITS: bits::BitLength = bits::BitLength(33);
+
+// Some legacy devices (e.g. some smartcards) use public exponents larger than
+// `PUBLIC_EXPONENT_MAX_BITS`. This is the limit used by the `*_E64`
+// verification algorithms that exist for interoperability with them. As the
+// public key operation is done in variable time with respect to the exponent,
+// this still bounds the amount of work an attacker can cause.
+const PUBLIC_EXPONENT_LEGACY_MAX_BITS: bits::BitLength = bits::BitLength(64);
+
+
 /// Parameters for RSA verification.
 pub struct RSAParameters {
     padding_alg: &'static padding::RSAVerification,
     min_bits: bits::BitLength,
+    e_max_bits: bits::BitLength,
 }
 
 fn parse_public_key(input: untrusted::Input)
@@ -66,29 +93,19 @@ fn parse_public_key(input: untrusted::Input)
 
 fn check_public_modulus_and_exponent(
         n: bigint::Positive, e: bigint::Positive, n_min_bits: bits::BitLength,
-        n_max_bits: bits::BitLength)
+        n_max_bits: bits::BitLength, e_max_bits: bits::BitLength)
         -> Result<(bigint::OddPositive, bigint::OddPositive),
                   error::Unspecified> {
     let n = try!(n.into_odd_positive());
     let e = try!(e.into_odd_positive());
 
-    // Mitigate DoS attacks by limiting the exponent size. 33 bits was chosen
-    // as the limit based on the recommendations in [1] and [2]. Windows
-    // CryptoAPI (at least older versions) doesn't support values larger than
-    // 32 bits [3], so it is unlikely that exponents larger than 32 bits are
-    // being used for anything Windows commonly does.
-    //
-    // [1] https://www.imperialviolet.org/2012/03/16/rsae.html
-    // [2] https://www.imperialviolet.org/2012/03/17/rsados.html
-    // [3] https://msdn.microsoft.com/en-us/library/aa387685(VS.85).aspx
-    const MAX_EXPONENT_BITS: bits::BitLength = bits::BitLength(33);
-
     // The public modulus must be large enough. `pkcs1_encode` depends on this
     // not being small. Without it, `pkcs1_encode` would generate padding that
     // is invalid (too few 0xFF bytes) for very small keys.
     const N_MIN_BITS: bits::BitLength = bits::BitLength(2048);
     assert!(n_min_bits >= N_MIN_BITS);
-    debug_assert!(MAX_EXPONENT_BITS < N_MIN_BITS);
+    assert!(e_max_bits <= PUBLIC_EXPONENT_LEGACY_MAX_BITS);
+    debug_assert!(PUBLIC_EXPONENT_LEGACY_MAX_BITS < N_MIN_BITS);
 
     let n_bits = n.bit_length();
     let n_bits_rounded_up =
@@ -105,7 +122,7 @@ fn check_public_modulus_and_exponent(
     if e_bits < bits::BitLength::from_usize_bits(2) {
         return Err(error::Unspecified);
     }
-    if e_bits > MAX_EXPONENT_BITS {
+    if e_bits > e_max_bits {
         return Err(error::Unspecified);
     }
 
@@ -120,6 +137,11 @@ pub mod verification;
 #[cfg(feature = "rsa_signing")]
 pub mod signing;
 
+#[cfg(not(feature = "rust_bigint"))]
+mod bigint;
+
+#[cfg(feature = "rust_bigint")]
+#[path = "bigint_rust.rs"]
 mod bigint;
 
 #[cfg(feature = "rsa_signing")]
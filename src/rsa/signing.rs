@@ -14,36 +14,60 @@
 
 /// RSA PKCS#1 1.5 signatures.
 
-use {bits, bssl, c, der, digest, error};
+use {bits, der, digest, error, pkcs8, signature};
 use rand;
 use std;
 use super::{blinding, bigint, N};
 use untrusted;
 
+#[cfg(not(feature = "rust_bigint"))]
+use {bssl, c};
+
 /// An RSA key pair, used for signing. Feature: `rsa_signing`.
 ///
 /// After constructing an `RSAKeyPair`, construct one or more
 /// `RSASigningState`s that reference the `RSAKeyPair` and use
 /// `RSASigningState::sign()` to generate signatures. See `ring::signature`'s
 /// module-level documentation for an example.
+///
+/// `RSAKeyPair` is cheap to clone: the clones share the same immutable key
+/// material, Montgomery contexts, and blinding cache, so it is never necessary
+/// to wrap an `RSAKeyPair` in an `Arc`.
+#[derive(Clone)]
 pub struct RSAKeyPair {
-    n: bigint::Modulus<N>,
+    inner: std::sync::Arc<Inner>,
+}
+
+// The state shared by all the clones of an `RSAKeyPair`.
+struct Inner {
+    n: bigint::OddPositive,
     e: bigint::OddPositive,
-    p: bigint::Modulus<P>,
-    q: bigint::Modulus<Q>,
+    d: bigint::OddPositive,
+    p: bigint::OddPositive,
+    q: bigint::OddPositive,
     dmp1: bigint::OddPositive,
     dmq1: bigint::OddPositive,
-    iqmp: bigint::Elem<P>,
-
-    qq: bigint::Modulus<QQ>,
-    q_mod_n: bigint::Elem<N>,
+    iqmp: bigint::Positive,
 
     n_bits: bits::BitLength,
+
+    // The DER-encoded `RSAPublicKey`; see `KeyPair::public_key()`.
+    public_key: std::vec::Vec<u8>,
+
+    // Constructed by `RSAKeyPair::from_der()`, which needs them to check the
+    // consistency of the key.
+    contexts: Contexts,
+
+    // Constructed the first time the key pair is used for signing; see
+    // `Inner::q_contexts()`.
+    q_contexts: std::sync::Mutex<Option<std::sync::Arc<QContexts>>>,
+
+    blinding_cache: blinding::Cache,
 }
 
-// `RSAKeyPair` is immutable. TODO: Make all the elements of `RSAKeyPair`
-// implement `Sync` so that it doesn't have to do this itself.
-unsafe impl Sync for RSAKeyPair {}
+// `Inner` is immutable. TODO: Make all the elements of `Inner` implement
+// `Sync` so that it doesn't have to do this itself.
+unsafe impl Sync for Inner {}
 
 impl RSAKeyPair {
     /// Parse a private key in DER-encoded ASN.1 `RSAPrivateKey` form (see
@@ -53,6 +77,12 @@ impl RSAKeyPair {
     /// (n) must be at least 2048 bits. Currently, the public modulus must be
     /// no larger than 4096 bits.
     ///
+    /// The consistency of the key, e.g. that `n == p * q`, is checked here,
+    /// using Montgomery contexts for `n` and `p` that are kept for signing.
+    /// To keep loading keys cheap, the remaining contexts that are needed for
+    /// signing, for `q` and values derived from it, are constructed the first
+    /// time the key pair is used to sign, not here.
+    ///
     /// Here's one way to generate a key in the required format using OpenSSL:
     ///
     /// ```sh
@@ -72,9 +102,10 @@ impl RSAKeyPair {
     /// ```
     ///
     /// If these commands don't work, it is likely that the private key is in a
-    /// different format like PKCS#8, which isn't supported yet. An upcoming
-    /// version of *ring* will likely replace the support for the
-    /// `RSAPrivateKey` format with support for the PKCS#8 format.
+    /// different format like PKCS#8. Only encrypted PKCS#8 documents are
+    /// supported so far; see `from_encrypted_pkcs8()`. An upcoming version of
+    /// *ring* will likely replace the support for the `RSAPrivateKey` format
+    /// with support for the PKCS#8 format.
     ///
     /// [RFC 3447 Appendix A.1.2]:
     ///     https://tools.ietf.org/html/rfc3447#appendix-A.1.2
@@ -106,7 +137,8 @@ impl RSAKeyPair {
                 // later.
                 let (n, e) = try!(super::check_public_modulus_and_exponent(
                     n, e, bits::BitLength::from_usize_bits(2048),
-                    super::PRIVATE_KEY_PUBLIC_MODULUS_MAX_BITS));
+                    super::PRIVATE_KEY_PUBLIC_MODULUS_MAX_BITS,
+                    super::PUBLIC_EXPONENT_MAX_BITS));
 
                 let d = try!(d.into_odd_positive());
                 try!(bigint::verify_less_than(&e, &d));
@@ -127,27 +159,7 @@ impl RSAKeyPair {
                 let q = try!(q.into_odd_positive());
                 try!(bigint::verify_less_than(&q, &p));
 
-                let n = try!(n.into_modulus::<N>());
-
-                // Verify that p * q == n. We restrict ourselves to modular
-                // multiplication. We rely on the fact that we've verified
-                // 0 < q < p < n. We check that q and p are close to sqrt(n)
-                // and then assume that these preconditions are enough to
-                // let us assume that checking p * q == 0 (mod n) is equivalent
-                // to checking p * q == n.
-                 let q_mod_n = {
-                    let q = try!(q.try_clone());
-                    try!(q.into_elem(&n))
-                };
-                let p_mod_n = {
-                    let p = try!(p.try_clone());
-                    try!(p.into_elem_decoded(&n))
-                };
-                let pq_mod_n =
-                    try!(bigint::elem_mul_mixed(&q_mod_n, p_mod_n, &n));
-                if !pq_mod_n.is_zero() {
-                    return Err(error::Unspecified);
-                }
+                let contexts = try!(Contexts::new(&n, &p, &q, &iqmp));
 
                 // XXX: We don't check that `dmp1 == d % (p - 1)` or that
                 // `dmq1 == d % (q - 1)` because we don't (in the long term)
@@ -171,55 +183,331 @@ impl RSAKeyPair {
                 let dmq1 = try!(dmq1.into_odd_positive());
                 try!(bigint::verify_less_than(&dmq1, &q));
 
-                let p = try!(p.into_modulus::<P>());
-
-                let iqmp = try!(iqmp.into_elem(&p));
-                let q_mod_p = {
-                    let q = try!(q.try_clone());
-                    try!(q.into_elem_decoded(&p))
-                };
-                let iqmp_times_q_mod_p =
-                    try!(bigint::elem_mul_mixed(&iqmp, q_mod_p, &p));
-                if !iqmp_times_q_mod_p.is_one() {
-                    return Err(error::Unspecified);
-                }
+                try!(bigint::verify_less_than(&iqmp, &p));
 
-                let q_mod_n_decoded = {
-                    let q = try!(q.try_clone());
-                    try!(q.into_elem_decoded(&n))
-                };
-                let qq =
-                    try!(bigint::elem_mul_mixed(&q_mod_n, q_mod_n_decoded,
-                                                &n));
-                let qq = try!(qq.into_odd_positive());
-                let qq = try!(qq.into_modulus::<QQ>());
-
-                let q = try!(q.into_modulus::<Q>());
+                let public_key = try!(public_key_der(&n, &e));
 
-                Ok(RSAKeyPair {
+                let inner = Inner {
                     n: n,
                     e: e,
+                    d: d,
                     p: p,
                     q: q,
                     dmp1: dmp1,
                     dmq1: dmq1,
                     iqmp: iqmp,
-                    q_mod_n: q_mod_n,
-                    qq: qq,
                     n_bits: n_bits,
-                })
+                    public_key: public_key,
+                    contexts: contexts,
+                    q_contexts: std::sync::Mutex::new(None),
+                    blinding_cache: blinding::Cache::new(0),
+                };
+                Ok(RSAKeyPair { inner: std::sync::Arc::new(inner) })
             })
         })
     }
 
+    /// Parse a password-protected private key in DER-encoded PKCS#8
+    /// `EncryptedPrivateKeyInfo` form (see [RFC 5208 Section 6]).
+    ///
+    /// The key must have been encrypted using PBES2 with PBKDF2 (using
+    /// HMAC-SHA256 or HMAC-SHA512) and AES-128 or AES-256 in CBC mode, or in
+    /// GCM mode with 128-bit tags. The decrypted document must be a PKCS#8 v1
+    /// `PrivateKeyInfo` that contains an `rsaEncryption` key in the form
+    /// accepted by `from_der()`, and the same restrictions on the key apply.
+    ///
+    /// Here's one way to encrypt a key in the required format using OpenSSL:
+    ///
+    /// ```sh
+    /// openssl pkcs8 -topk8 -v2 aes-256-cbc -v2prf hmacWithSHA256 \
+    ///               -inform DER -in private_key.der \
+    ///               -outform DER -out private_key.p8
+    /// ```
+    ///
+    /// An incorrect password is indistinguishable from a malformed document;
+    /// both result in an error.
+    ///
+    /// [RFC 5208 Section 6]: https://tools.ietf.org/html/rfc5208#section-6
+    pub fn from_encrypted_pkcs8(input: untrusted::Input, password: &[u8])
+                                -> Result<RSAKeyPair, error::Unspecified> {
+        let private_key_info = try!(pkcs8::decrypt(input, password));
+        let key_der = try!(pkcs8::unwrap_key(
+            &RSA_ENCRYPTION_ALG_ID,
+            untrusted::Input::from(private_key_info.as_ref())));
+        RSAKeyPair::from_der(key_der)
+    }
+
+    /// Serializes the key pair in DER-encoded ASN.1 `RSAPrivateKey` form (see
+    /// [RFC 3447 Appendix A.1.2]), the form accepted by `from_der()`.
+    ///
+    /// Since the result contains the private key, it is zeroized when it is
+    /// dropped.
+    ///
+    /// [RFC 3447 Appendix A.1.2]:
+    ///     https://tools.ietf.org/html/rfc3447#appendix-A.1.2
+    pub fn to_der(&self) -> Result<pkcs8::Document, error::Unspecified> {
+        const VERSION: &'static [u8] = &[der::Tag::Integer as u8, 1, 0];
+
+        let key = &self.inner;
+        let components: [&bigint::Positive; 8] = [
+            &key.n,
+            &key.e,
+            &key.d,
+            &key.p,
+            &key.q,
+            &key.dmp1,
+            &key.dmq1,
+            &key.iqmp,
+        ];
+
+        let inner_len = components.iter().fold(VERSION.len(), |acc, c| {
+            acc + bigint::der_encoded_len(c)
+        });
+        let mut result =
+            pkcs8::Document::with_capacity(der::encoded_len(inner_len));
+        {
+            let output = result.as_mut_vec();
+            der::write_tag_and_len(output, der::Tag::Sequence, inner_len);
+            output.extend_from_slice(VERSION);
+            for component in components.iter() {
+                try!(bigint::write_der(component, output));
+            }
+            debug_assert_eq!(output.len(), output.capacity());
+        }
+        Ok(result)
+    }
+
+    /// Serializes the key pair as an unencrypted PKCS#8 v1 `PrivateKeyInfo`
+    /// (see [RFC 5208 Section 5]) containing the key in the form produced by
+    /// `to_der()`.
+    ///
+    /// Since the result contains the private key, it is zeroized when it is
+    /// dropped.
+    ///
+    /// [RFC 5208 Section 5]: https://tools.ietf.org/html/rfc5208#section-5
+    pub fn to_pkcs8(&self) -> Result<pkcs8::Document, error::Unspecified> {
+        let key_der = try!(self.to_der());
+        Ok(pkcs8::wrap_key(&RSA_ENCRYPTION_ALG_ID, key_der.as_ref()))
+    }
+
+    /// Returns the digest, using `digest_alg`, of the key pair's public key
+    /// in DER-encoded X.509 `SubjectPublicKeyInfo` form (see
+    /// [RFC 5280 Section 4.1]), with the `rsaEncryption` algorithm identifier
+    /// and an `RSAPublicKey` (see [RFC 3447 Appendix A.1.1]).
+    ///
+    /// This is the same value that tools like OpenSSL compute for public key
+    /// pinning, e.g. for HTTP Public Key Pinning ([RFC 7469]) when
+    /// `digest_alg` is `&digest::SHA256`:
+    ///
+    /// ```sh
+    /// openssl rsa -in private_key.der -inform DER -pubout -outform DER | \
+    ///     openssl dgst -sha256
+    /// ```
+    ///
+    /// [RFC 5280 Section 4.1]: https://tools.ietf.org/html/rfc5280#section-4.1
+    /// [RFC 3447 Appendix A.1.1]:
+    ///     https://tools.ietf.org/html/rfc3447#appendix-A.1.1
+    /// [RFC 7469]: https://tools.ietf.org/html/rfc7469
+    pub fn fingerprint(&self, digest_alg: &'static digest::Algorithm)
+                       -> Result<digest::Digest, error::Unspecified> {
+        let spki = try!(self.public_key_spki_der());
+        Ok(digest::digest(digest_alg, &spki))
+    }
+
+    fn public_key_spki_der(&self)
+                           -> Result<std::vec::Vec<u8>, error::Unspecified> {
+        let public_key: &[u8] = &self.inner.public_key;
+        // The `BIT STRING`'s value starts with the number of unused bits.
+        let bit_string_len = 1 + public_key.len();
+        let spki_len =
+            RSA_ENCRYPTION_ALG_ID.len() + der::encoded_len(bit_string_len);
+
+        let mut output =
+            std::vec::Vec::with_capacity(der::encoded_len(spki_len));
+        der::write_tag_and_len(&mut output, der::Tag::Sequence, spki_len);
+        output.extend_from_slice(&RSA_ENCRYPTION_ALG_ID);
+        der::write_tag_and_len(&mut output, der::Tag::BitString,
+                               bit_string_len);
+        output.push(0);
+        output.extend_from_slice(public_key);
+        debug_assert_eq!(output.len(), output.capacity());
+        Ok(output)
+    }
+
     /// Returns the length in bytes of the key pair's public modulus.
     ///
     /// A signature has the same length as the public modulus.
     pub fn public_modulus_len(&self) -> usize {
-        self.n_bits.as_usize_bytes_rounded_up()
+        self.inner.n_bits.as_usize_bytes_rounded_up()
+    }
+
+    /// Enables a cache of up to `capacity` blinding states that is shared by
+    /// all the `RSASigningState`s that reference this key pair or any of its
+    /// clones.
+    ///
+    /// By default, every `RSASigningState` starts with no blinding factors,
+    /// so each one does the expensive computation explained in
+    /// `RSASigningState`'s documentation the first time it is used. When the
+    /// cache is enabled, a dropped `RSASigningState` returns its blinding
+    /// state to the cache, and a new `RSASigningState` takes one from the
+    /// cache when it isn't empty. This greatly reduces the cost of using many
+    /// short-lived `RSASigningState`s with the same key pair. The cache is
+    /// protected by a lock, so it is only accessed when an `RSASigningState`
+    /// is constructed or dropped, never during `sign()`.
+    ///
+    /// A `capacity` of zero disables the cache, which is the default. Since
+    /// the cache is shared, changing its capacity affects all the clones of
+    /// the key pair.
+    pub fn enable_blinding_cache(&self, capacity: usize) {
+        self.inner.blinding_cache.set_capacity(capacity);
     }
 }
 
+impl signature::KeyPair for RSAKeyPair {
+    /// The public key in DER-encoded ASN.1 `RSAPublicKey` form (see
+    /// [RFC 3447 Appendix A.1.1]), the form accepted by the `RSA_PKCS1_*` and
+    /// `RSA_PSS_*` verification algorithms.
+    ///
+    /// [RFC 3447 Appendix A.1.1]:
+    ///     https://tools.ietf.org/html/rfc3447#appendix-A.1.1
+    type PublicKey = [u8];
+
+    fn public_key(&self) -> &[u8] { &self.inner.public_key }
+}
+
+// Encodes `n` and `e` as a DER-encoded ASN.1 `RSAPublicKey`.
+fn public_key_der(n: &bigint::Positive, e: &bigint::Positive)
+                  -> Result<std::vec::Vec<u8>, error::Unspecified> {
+    let public_key_len =
+        bigint::der_encoded_len(n) + bigint::der_encoded_len(e);
+    let mut output =
+        std::vec::Vec::with_capacity(der::encoded_len(public_key_len));
+    der::write_tag_and_len(&mut output, der::Tag::Sequence, public_key_len);
+    try!(bigint::write_der(n, &mut output));
+    try!(bigint::write_der(e, &mut output));
+    debug_assert_eq!(output.len(), output.capacity());
+    Ok(output)
+}
+
+// The Montgomery contexts for `n` and `p`, and the values encoded for use
+// with them, that the private key operation needs.
+struct Contexts {
+    n: bigint::Modulus<N>,
+    p: bigint::Modulus<P>,
+    q_mod_n: bigint::Elem<N>,
+    iqmp: bigint::Elem<P>,
+}
+
+impl Contexts {
+    // Verifies that p * q == n and that iqmp * q == 1 (mod p), the checks of
+    // `RSAKeyPair::from_der()` that require Montgomery math, keeping the
+    // contexts that the checks construct.
+    fn new(n: &bigint::OddPositive, p: &bigint::OddPositive,
+           q: &bigint::OddPositive, iqmp: &bigint::Positive)
+           -> Result<Contexts, error::Unspecified> {
+        let n = try!(try!(n.try_clone()).into_modulus::<N>());
+
+        // Verify that p * q == n. We restrict ourselves to modular
+        // multiplication. We rely on the fact that we've verified
+        // 0 < q < p < n. We check that q and p are close to sqrt(n)
+        // and then assume that these preconditions are enough to
+        // let us assume that checking p * q == 0 (mod n) is equivalent
+        // to checking p * q == n.
+        let q_mod_n = try!(try!(q.try_clone()).into_elem(&n));
+        let p_mod_n = try!(try!(p.try_clone()).into_elem_decoded(&n));
+        let pq_mod_n = try!(bigint::elem_mul_mixed(&q_mod_n, p_mod_n, &n));
+        if !pq_mod_n.is_zero() {
+            return Err(error::Unspecified);
+        }
+
+        let p = try!(try!(p.try_clone()).into_modulus::<P>());
+        let iqmp = try!(try!(iqmp.try_clone()).into_elem(&p));
+        let q_mod_p = try!(try!(q.try_clone()).into_elem_decoded(&p));
+        let iqmp_times_q_mod_p =
+            try!(bigint::elem_mul_mixed(&iqmp, q_mod_p, &p));
+        if !iqmp_times_q_mod_p.is_one() {
+            return Err(error::Unspecified);
+        }
+
+        Ok(Contexts {
+            n: n,
+            p: p,
+            q_mod_n: q_mod_n,
+            iqmp: iqmp,
+        })
+    }
+}
+
+impl Inner {
+    // Returns the `QContexts` for the key pair, constructing them if this is
+    // the first time they are needed. They are only needed for signing, so
+    // their construction is deferred until the key pair is used for signing;
+    // that way, users that load many keys but only sign with a few, or that
+    // only inspect the keys, don't pay for it.
+    fn q_contexts(&self)
+                  -> Result<std::sync::Arc<QContexts>, error::Unspecified> {
+        let mut q_contexts =
+            try!(self.q_contexts.lock().map_err(|_| error::Unspecified));
+        if let Some(ref q_contexts) = *q_contexts {
+            return Ok(q_contexts.clone());
+        }
+        let new_q_contexts = std::sync::Arc::new(try!(QContexts::new(self)));
+        *q_contexts = Some(new_q_contexts.clone());
+        Ok(new_q_contexts)
+    }
+}
+
+// The Montgomery contexts for `q`, and for `q**2` in the C implementation,
+// that the private key operation needs.
+struct QContexts {
+    q: bigint::Modulus<Q>,
+    #[cfg(not(feature = "rust_bigint"))]
+    qq: bigint::Modulus<QQ>,
+}
+
+// `QContexts` is immutable. TODO: Make all the elements of `QContexts`
+// implement `Sync` so that it doesn't have to do this itself.
+unsafe impl Sync for QContexts {}
+
+impl QContexts {
+    // The key was validated by `RSAKeyPair::from_der()`.
+    fn new(key: &Inner) -> Result<QContexts, error::Unspecified> {
+        Ok(QContexts {
+            q: try!(try!(key.q.try_clone()).into_modulus::<Q>()),
+            #[cfg(not(feature = "rust_bigint"))]
+            qq: try!(QContexts::qq(key)),
+        })
+    }
+
+    // The C implementation of the private key operation reduces values modulo
+    // q**2 before reducing them modulo q.
+    #[cfg(not(feature = "rust_bigint"))]
+    fn qq(key: &Inner) -> Result<bigint::Modulus<QQ>, error::Unspecified> {
+        let n = &key.contexts.n;
+        let q_mod_n = &key.contexts.q_mod_n;
+        let q_mod_n_decoded = {
+            let q = try!(key.q.try_clone());
+            try!(q.into_elem_decoded(n))
+        };
+        let qq = try!(bigint::elem_mul_mixed(q_mod_n, q_mod_n_decoded, n));
+        let qq = try!(qq.into_odd_positive());
+        qq.into_modulus::<QQ>()
+    }
+}
+
+
+// The DER encoding of the `AlgorithmIdentifier` for `rsaEncryption`
+// (1.2.840.113549.1.1.1) with NULL parameters, as described in
+// [RFC 3447 Appendix C].
+//
+// [RFC 3447 Appendix C]: https://tools.ietf.org/html/rfc3447#appendix-C
+const RSA_ENCRYPTION_ALG_ID: [u8; 15] = [
+    der::Tag::Sequence as u8, 13,
+        der::Tag::OID as u8, 9,
+            0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01,
+        der::Tag::Null as u8, 0,
+];
+
 
 enum P {}
 unsafe impl bigint::Field for P {}
@@ -227,11 +515,14 @@ unsafe impl bigint::Field for P {}
 enum Q {}
 unsafe impl bigint::Field for Q {}
 
+#[cfg(not(feature = "rust_bigint"))]
 enum QQ {}
+#[cfg(not(feature = "rust_bigint"))]
 unsafe impl bigint::Field for QQ {}
 
 
 /// Needs to be kept in sync with `struct rsa_st` (in `include/openssl/rsa.h`).
+#[cfg(not(feature = "rust_bigint"))]
 #[repr(C)]
 struct RSA<'a> {
     e: &'a bigint::BIGNUM,
@@ -246,6 +537,37 @@ struct RSA<'a> {
 }
 
 
+/// The RSA private key operation, for keys that aren't held in memory, e.g.
+/// keys held in an HSM, a TPM, or a cloud key management service. Feature:
+/// `rsa_signing`.
+///
+/// An `RSASigningState` constructed with
+/// `RSASigningState::from_private_key_operation()` digests and pads the
+/// message exactly as it does for an `RSAKeyPair`, and then uses the
+/// `RSAPrivateKeyOperation` instead of an in-memory private key.
+pub trait RSAPrivateKeyOperation: Send + Sync {
+    /// Returns the length of the public modulus in bits.
+    fn public_modulus_bits(&self) -> usize;
+
+    /// Replaces `in_out`, the big-endian encoding of the encoded message, with
+    /// the big-endian encoding of the signature: the encoded message raised to
+    /// the private exponent, modulo the public modulus. This is the `RSASP1`
+    /// primitive of [RFC 3447 Section 5.2.1]; no digesting or padding is done.
+    ///
+    /// `in_out.len()` is the length of the public modulus in bytes, and the
+    /// signature must be written left-padded with zeros to that length.
+    ///
+    /// Since the encoded message isn't secret, implementations don't need to
+    /// blind it, but they should protect the private key from side channel
+    /// attacks in whatever way is appropriate to where it is held.
+    ///
+    /// [RFC 3447 Section 5.2.1]:
+    ///     https://tools.ietf.org/html/rfc3447#section-5.2.1
+    fn private_transform(&self, in_out: &mut [u8])
+                         -> Result<(), error::Unspecified>;
+}
+
+
 /// State used for RSA Signing. Feature: `rsa_signing`.
 ///
 /// # Performance Considerations
@@ -272,32 +594,107 @@ struct RSA<'a> {
 /// computational efficiency by increasing the frequency of the expensive
 /// modular inversions; managing a pool of `RSASigningState`s in a
 /// most-recently-used fashion would improve the computational efficiency.
+/// `RSAKeyPair::enable_blinding_cache()` implements such a pool.
+///
+/// None of the above applies to an `RSASigningState` constructed with
+/// `from_private_key_operation()`, which doesn't do any blinding itself.
 pub struct RSASigningState {
-    key_pair: std::sync::Arc<RSAKeyPair>,
-    blinding: blinding::Blinding,
+    key: Key,
+}
+
+enum Key {
+    Local {
+        key_pair: RSAKeyPair,
+
+        // This is only `None` while the `RSASigningState` is being dropped.
+        blinding: Option<blinding::Blinding>,
+    },
+
+    Remote {
+        operation: std::sync::Arc<RSAPrivateKeyOperation>,
+        n_bits: bits::BitLength,
+    },
 }
 
 impl RSASigningState {
     /// Construct an `RSASigningState` for the given `RSAKeyPair`.
-    pub fn new(key_pair: std::sync::Arc<RSAKeyPair>)
-               -> Result<Self, error::Unspecified> {
-        let blinding = try!(blinding::Blinding::new());
+    ///
+    /// If the key pair's blinding cache is enabled and it isn't empty, the
+    /// new `RSASigningState` will reuse a blinding state from the cache.
+    ///
+    /// To construct multiple `RSASigningState`s that share the same key pair,
+    /// pass each of them a clone of the `RSAKeyPair`.
+    pub fn new(key_pair: RSAKeyPair) -> Result<Self, error::Unspecified> {
+        let blinding = match key_pair.inner.blinding_cache.take() {
+            Some(blinding) => blinding,
+            None => try!(blinding::Blinding::new()),
+        };
         Ok(RSASigningState {
-            key_pair: key_pair,
-            blinding: blinding,
+            key: Key::Local {
+                key_pair: key_pair,
+                blinding: Some(blinding),
+            },
+        })
+    }
+
+    /// Construct an `RSASigningState` that uses `operation` for the private
+    /// key operation, e.g. for a key that is held in an HSM.
+    ///
+    /// The digesting and padding are done exactly as they are for an
+    /// `RSAKeyPair`. The public modulus must be at least 2048 bits and no
+    /// larger than 8192 bits.
+    pub fn from_private_key_operation(
+            operation: std::sync::Arc<RSAPrivateKeyOperation>)
+            -> Result<Self, error::Unspecified> {
+        let n_bits = bits::BitLength::from_usize_bits(
+            operation.public_modulus_bits());
+        if n_bits.as_usize_bits() < 2048 ||
+           n_bits.as_usize_bytes_rounded_up() >
+               super::PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN {
+            return Err(error::Unspecified);
+        }
+        Ok(RSASigningState {
+            key: Key::Remote {
+                operation: operation,
+                n_bits: n_bits,
+            },
         })
     }
 
     /// The `RSAKeyPair`. This can be used, for example, to access the key
     /// pair's public key through the `RSASigningState`.
-    pub fn key_pair(&self) -> &RSAKeyPair { self.key_pair.as_ref() }
+    ///
+    /// This is `None` if the `RSASigningState` was constructed with
+    /// `from_private_key_operation()`.
+    pub fn key_pair(&self) -> Option<&RSAKeyPair> {
+        match self.key {
+            Key::Local { ref key_pair, .. } => Some(key_pair),
+            Key::Remote { .. } => None,
+        }
+    }
+
+    /// Returns the length in bytes of the public modulus of the key used for
+    /// signing.
+    ///
+    /// A signature has the same length as the public modulus.
+    pub fn public_modulus_len(&self) -> usize {
+        self.n_bits().as_usize_bytes_rounded_up()
+    }
+
+    fn n_bits(&self) -> bits::BitLength {
+        match self.key {
+            Key::Local { ref key_pair, .. } => key_pair.inner.n_bits,
+            Key::Remote { n_bits, .. } => n_bits,
+        }
+    }
 
     /// Sign `msg`. `msg` is digested using the digest algorithm from
     /// `padding_alg` and the digest is then padded using the padding algorithm
     /// from `padding_alg`. The signature it written into `signature`;
     /// `signature`'s length must be exactly the length returned by
     /// `public_modulus_len()`. `rng` is used for blinding the message during
-    /// signing, to mitigate some side-channel (e.g. timing) attacks.
+    /// signing, to mitigate some side-channel (e.g. timing) attacks, and for
+    /// generating the salt for PSS padding.
     ///
     /// Many other crypto libraries have signing functions that takes a
     /// precomputed digest as input, instead of the message to digest. This
@@ -310,52 +707,294 @@ impl RSASigningState {
     /// platforms, it is done less perfectly. To help mitigate the current
     /// imperfections, and for defense-in-depth, base blinding is always done.
     /// Exponent blinding is not done, but it may be done in the future.
-    #[allow(non_shorthand_field_patterns)] // Work around compiler bug.
+    ///
+    /// To sign a message that isn't available all at once, use an
+    /// `RSASigningContext` instead.
     pub fn sign(&mut self, padding_alg: &'static ::signature::RSAEncoding,
                 rng: &rand::SecureRandom, msg: &[u8], signature: &mut [u8])
                 -> Result<(), error::Unspecified> {
-        let mod_bits = self.key_pair.n_bits;
+        let m_hash = digest::digest(padding_alg.digest_alg(), msg);
+        self.sign_digest(padding_alg, rng, &m_hash, signature)
+    }
+
+    // `m_hash` must have been calculated using `padding_alg.digest_alg()`.
+    fn sign_digest(&mut self, padding_alg: &'static ::signature::RSAEncoding,
+                   rng: &rand::SecureRandom, m_hash: &digest::Digest,
+                   signature: &mut [u8]) -> Result<(), error::Unspecified> {
+        let mod_bits = self.n_bits();
         if signature.len() != mod_bits.as_usize_bytes_rounded_up() {
             return Err(error::Unspecified);
         }
 
-        let &mut RSASigningState {
-            key_pair: ref key,
-            blinding: ref mut blinding,
-        } = self;
-
-        let rsa =  RSA {
-            e: key.e.as_ref(),
-            dmp1: key.dmp1.as_ref(),
-            dmq1: key.dmq1.as_ref(),
-            mont_n: key.n.as_ref(),
-            mont_p: key.p.as_ref(),
-            mont_q: key.q.as_ref(),
-            mont_qq: key.qq.as_ref(),
-            qmn_mont: key.q_mod_n.as_ref_montgomery_encoded(),
-            iqmp_mont: key.iqmp.as_ref_montgomery_encoded(),
-        };
+        try!(padding_alg.encode(m_hash, signature, mod_bits, rng));
 
-        let m_hash = digest::digest(padding_alg.digest_alg(), msg);
-        try!(padding_alg.encode(&m_hash, signature, mod_bits, rng));
-        // TODO: Avoid having `encode()` pad its output, and then remove
-        // `Positive::from_be_bytes_padded()`.
-        let base = try!(bigint::Positive::from_be_bytes_padded(
-            untrusted::Input::from(signature)));
-        let mut base = try!(base.into_elem_decoded(&key.n));
+        match self.key {
+            Key::Local { ref key_pair, ref mut blinding } => {
+                let blinding =
+                    try!(blinding.as_mut().ok_or(error::Unspecified));
+                private_transform(&key_pair.inner, blinding, rng, signature)
+            },
+            Key::Remote { ref operation, .. } =>
+                operation.private_transform(signature),
+        }
+    }
+}
+
+// Replaces `in_out`, which contains the encoded message, with the result of
+// the RSA private key operation on it.
+#[cfg(not(feature = "rust_bigint"))]
+fn private_transform(key: &Inner, blinding: &mut blinding::Blinding,
+                     rng: &rand::SecureRandom, in_out: &mut [u8])
+                     -> Result<(), error::Unspecified> {
+    let contexts = &key.contexts;
+    let q_contexts = try!(key.q_contexts());
+
+    let rsa =  RSA {
+        e: key.e.as_ref(),
+        dmp1: key.dmp1.as_ref(),
+        dmq1: key.dmq1.as_ref(),
+        mont_n: contexts.n.as_ref(),
+        mont_p: contexts.p.as_ref(),
+        mont_q: q_contexts.q.as_ref(),
+        mont_qq: q_contexts.qq.as_ref(),
+        qmn_mont: contexts.q_mod_n.as_ref_montgomery_encoded(),
+        iqmp_mont: contexts.iqmp.as_ref_montgomery_encoded(),
+    };
+
+    // TODO: Avoid having `encode()` pad its output, and then remove
+    // `Positive::from_be_bytes_padded()`.
+    let base = try!(bigint::Positive::from_be_bytes_padded(
+        untrusted::Input::from(in_out)));
+    let mut base = try!(base.into_elem_decoded(&contexts.n));
+
+    let mut rand = rand::RAND::new(rng);
+
+    try!(bssl::map_result(unsafe {
+        GFp_rsa_private_transform(&rsa, base.as_mut_ref(),
+                                  blinding.as_mut_ref(), &mut rand)
+    }));
+
+    base.fill_be_bytes(in_out)
+}
+
+// Replaces `in_out`, which contains the encoded message, with the result of
+// the RSA private key operation on it. This does the same thing, in the same
+// way, as `GFp_rsa_private_transform` in `crypto/rsa/rsa_impl.c`.
+#[cfg(feature = "rust_bigint")]
+fn private_transform(key: &Inner, blinding: &mut blinding::Blinding,
+                     rng: &rand::SecureRandom, in_out: &mut [u8])
+                     -> Result<(), error::Unspecified> {
+    let q_contexts = try!(key.q_contexts());
+    let n = &key.contexts.n;
+
+    let base = try!(bigint::Positive::from_be_bytes_padded(
+        untrusted::Input::from(in_out)));
+    let base = try!(base.into_elem_decoded(n));
+    let base = try!(blinding.blind(base, n, || {
+        new_blinding_factors(key, &q_contexts, rng)
+    }));
+
+    let result = try!(private_transform_crt(key, &q_contexts,
+                                            try!(base.try_clone())));
+
+    // Verify the result to protect against fault attacks; see the comments in
+    // `GFp_rsa_private_transform`.
+    let verify = try!(bigint::elem_exp_vartime(try!(result.try_clone()),
+                                               &key.e, n));
+    try!(bigint::elem_verify_equal(&verify, &base));
+
+    let result = try!(blinding.unblind(result, n));
+    result.fill_be_bytes(in_out)
+}
+
+// `base`**d (mod n), calculated using the CRT parameters.
+#[cfg(feature = "rust_bigint")]
+fn private_transform_crt(key: &Inner, q_contexts: &QContexts,
+                         base: bigint::ElemDecoded<N>)
+        -> Result<bigint::ElemDecoded<N>, error::Unspecified> {
+    let p = &key.contexts.p;
+    let q = &q_contexts.q;
+
+    // mp := base**dmp1 (mod p), mq := base**dmq1 (mod q).
+    let mp = try!(bigint::elem_reduced(&base, p));
+    let mp = try!(bigint::elem_exp_consttime(mp, &key.dmp1, p));
+    let mq = try!(bigint::elem_reduced(&base, q));
+    let mq = try!(bigint::elem_exp_consttime(mq, &key.dmq1, q));
+    crt_combine(&key.contexts, mp, mq)
+}
+
+// Combines `mp` and `mq` with Garner's algorithm: the result is
+// mq + q * (iqmp * (mp - mq) (mod p)), which is less than n.
+#[cfg(feature = "rust_bigint")]
+fn crt_combine(contexts: &Contexts, mp: bigint::ElemDecoded<P>,
+               mq: bigint::ElemDecoded<Q>)
+               -> Result<bigint::ElemDecoded<N>, error::Unspecified> {
+    // `mq < q < p`, so reducing it modulo p doesn't change its value.
+    let mq_mod_p = try!(bigint::elem_reduced(&mq, &contexts.p));
+    let h = bigint::elem_sub(mp, &mq_mod_p, &contexts.p);
+    let h = try!(bigint::elem_mul_mixed(&contexts.iqmp, h, &contexts.p));
+    let h = try!(bigint::elem_reduced(&h, &contexts.n));
+    let h_q = try!(bigint::elem_mul_mixed(&contexts.q_mod_n, h, &contexts.n));
+    let mq = try!(bigint::elem_reduced(&mq, &contexts.n));
+    Ok(bigint::elem_add(h_q, &mq, &contexts.n))
+}
+
+// Returns (r**e, 1/r) (mod n), Montgomery-encoded, for a random r. The inverse
+// is calculated using the CRT, with Fermat's little theorem modulo p and q.
+#[cfg(feature = "rust_bigint")]
+fn new_blinding_factors(key: &Inner, q_contexts: &QContexts,
+                        rng: &rand::SecureRandom)
+        -> Result<(bigint::Elem<N>, bigint::Elem<N>), error::Unspecified> {
+    let n = &key.contexts.n;
+    let p = &key.contexts.p;
+    let q = &q_contexts.q;
+    let r = try!(bigint::elem_random(n, rng));
+
+    let r_inv_mod_p = try!(bigint::elem_reduced(&r, p));
+    let r_inv_mod_p = bigint::elem_inverse_mod_prime(r_inv_mod_p, p);
+    let r_inv_mod_q = try!(bigint::elem_reduced(&r, q));
+    let r_inv_mod_q = bigint::elem_inverse_mod_prime(r_inv_mod_q, q);
+    let r_inv = try!(crt_combine(&key.contexts, r_inv_mod_p, r_inv_mod_q));
+
+    // For reasonably-sized RSA keys, it should almost never be the case that
+    // `r` isn't invertible, but if it isn't then the inverse calculated above
+    // is wrong.
+    let r_inv = r_inv.into_elem(n);
+    let check = try!(bigint::elem_mul_mixed(&r_inv, try!(r.try_clone()), n));
+    if !check.is_one() {
+        return Err(error::Unspecified);
+    }
+
+    let a = try!(bigint::elem_exp_vartime(r, &key.e, n));
+    Ok((a.into_elem(n), r_inv))
+}
+
+/// A context for signing a message that is input in multiple parts. Feature:
+/// `rsa_signing`.
+///
+/// The message is digested as it is input, so it never needs to be in memory
+/// all at once. The resulting signature is exactly the same as the one that
+/// `RSASigningState::sign()` would produce for the concatenation of the
+/// parts.
+///
+/// ```
+/// # #[cfg(all(feature = "rsa_signing", feature = "use_heap"))]
+/// # fn sign_parts(signing_state: &mut ring::signature::RSASigningState,
+/// #               parts: &[&[u8]])
+/// #               -> Result<std::vec::Vec<u8>, ring::error::Unspecified> {
+/// use ring::{rand, signature};
+///
+/// let rng = rand::SystemRandom::new();
+/// let mut signature =
+///     vec![0; signing_state.public_modulus_len()];
+/// let mut ctx = signature::RSASigningContext::new(
+///     signing_state, &signature::RSA_PKCS1_SHA256, &rng);
+/// for part in parts {
+///     ctx.update(part);
+/// }
+/// try!(ctx.finish(&mut signature));
+/// # Ok(signature)
+/// # }
+/// # fn main() {}
+/// ```
+pub struct RSASigningContext<'a> {
+    signing_state: &'a mut RSASigningState,
+    padding_alg: &'static ::signature::RSAEncoding,
+    rng: &'a rand::SecureRandom,
+    digest: digest::Context,
+}
+
+impl<'a> RSASigningContext<'a> {
+    /// Constructs a new context that will sign a message with the key pair of
+    /// `signing_state`, using `padding_alg` and `rng` as
+    /// `RSASigningState::sign()` does.
+    pub fn new(signing_state: &'a mut RSASigningState,
+               padding_alg: &'static ::signature::RSAEncoding,
+               rng: &'a rand::SecureRandom) -> RSASigningContext<'a> {
+        RSASigningContext {
+            signing_state: signing_state,
+            padding_alg: padding_alg,
+            rng: rng,
+            digest: digest::Context::new(padding_alg.digest_alg()),
+        }
+    }
+
+    /// Updates the message to sign with all the data in `data`. `update` may
+    /// be called zero or more times before `finish` is called.
+    pub fn update(&mut self, data: &[u8]) { self.digest.update(data) }
+
+    /// Signs the message and writes the signature into `signature`.
+    /// `signature`'s length must be exactly the length returned by
+    /// `RSASigningState::public_modulus_len()`.
+    pub fn finish(self, signature: &mut [u8])
+                  -> Result<(), error::Unspecified> {
+        let m_hash = self.digest.finish();
+        self.signing_state.sign_digest(self.padding_alg, self.rng, &m_hash,
+                                       signature)
+    }
+}
+
+/// An `RSAKeyPair` bundled with a padding algorithm, for use as a
+/// `signature::Signer`. Feature: `rsa_signing`.
+///
+/// Each signature is generated with a new `RSASigningState`, so enable the
+/// key pair's blinding cache with `RSAKeyPair::enable_blinding_cache()` to
+/// avoid recomputing the blinding values for every signature.
+pub struct RSASigner {
+    key_pair: RSAKeyPair,
+    padding_alg: &'static ::signature::RSAEncoding,
+}
 
-        let mut rand = rand::RAND::new(rng);
+impl RSASigner {
+    /// Constructs a signer that signs with `key_pair` using `padding_alg`, as
+    /// `RSASigningState::sign()` does.
+    pub fn new(key_pair: RSAKeyPair,
+               padding_alg: &'static ::signature::RSAEncoding) -> Self {
+        RSASigner {
+            key_pair: key_pair,
+            padding_alg: padding_alg,
+        }
+    }
 
-        try!(bssl::map_result(unsafe {
-            GFp_rsa_private_transform(&rsa, base.as_mut_ref(),
-                                      blinding.as_mut_ref(), &mut rand)
-        }));
+    /// The key pair.
+    pub fn key_pair(&self) -> &RSAKeyPair { &self.key_pair }
+}
+
+impl signature::Signer for RSASigner {
+    fn public_key_bytes(&self) -> &[u8] { &self.key_pair.inner.public_key }
+
+    /// The length of the public modulus; RSA signatures are always exactly
+    /// this long.
+    fn max_signature_len(&self) -> usize {
+        self.key_pair.public_modulus_len()
+    }
+
+    fn sign(&self, msg: &[u8], rng: &rand::SecureRandom, signature: &mut [u8])
+            -> Result<usize, error::Unspecified> {
+        let len = self.key_pair.public_modulus_len();
+        if signature.len() < len {
+            return Err(error::Unspecified);
+        }
+        let mut signing_state =
+            try!(RSASigningState::new(self.key_pair.clone()));
+        try!(signing_state.sign(self.padding_alg, rng, msg,
+                                &mut signature[..len]));
+        Ok(len)
+    }
+}
 
-        base.fill_be_bytes(signature)
+impl Drop for RSASigningState {
+    fn drop(&mut self) {
+        if let Key::Local { ref key_pair, ref mut blinding } = self.key {
+            if let Some(blinding) = blinding.take() {
+                key_pair.inner.blinding_cache.put(blinding);
+            }
+        }
     }
 }
 
 
+#[cfg(not(feature = "rust_bigint"))]
 #[allow(improper_ctypes)]
 extern {
     fn GFp_rsa_private_transform(rsa: &RSA, base: &mut bigint::BIGNUM,
@@ -368,7 +1007,8 @@ extern {
 mod tests {
     // We intentionally avoid `use super::*` so that we are sure to use only
     // the public API; this ensures that enough of the API is public.
-    use {error, rand, signature, test};
+    use {digest, error, rand, signature, test};
+    use core;
     use std;
     use super::super::blinding;
     use untrusted;
@@ -399,14 +1039,13 @@ mod tests {
                 return Ok(());
             }
             let key_pair = key_pair.unwrap();
-            let key_pair = std::sync::Arc::new(key_pair);
 
             // XXX: This test is too slow on Android ARM Travis CI builds.
             // TODO: re-enable these tests on Android ARM.
             let mut signing_state =
                 signature::RSASigningState::new(key_pair).unwrap();
             let mut actual: std::vec::Vec<u8> =
-                vec![0; signing_state.key_pair().public_modulus_len()];
+                vec![0; signing_state.public_modulus_len()];
             signing_state.sign(alg, &rng, &msg, actual.as_mut_slice()).unwrap();
             assert_eq!(actual.as_slice() == &expected[..], result == "Pass");
             Ok(())
@@ -415,6 +1054,147 @@ mod tests {
 
 
 
+    #[cfg(feature = "rsa_pkcs1_sha1_signing")]
+    #[test]
+    fn test_signature_rsa_pkcs1_sign_sha1() {
+        let rng = rand::SystemRandom::new();
+        test::from_file("src/rsa/rsa_pkcs1_sign_sha1_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+            let private_key = test_case.consume_bytes("Key");
+            let msg = test_case.consume_bytes("Msg");
+            let expected = test_case.consume_bytes("Sig");
+
+            let key_pair = signature::RSAKeyPair::from_der(
+                untrusted::Input::from(&private_key)).unwrap();
+            let mut signing_state =
+                signature::RSASigningState::new(key_pair).unwrap();
+            let mut actual: std::vec::Vec<u8> =
+                vec![0; signing_state.public_modulus_len()];
+            try!(signing_state.sign(&signature::RSA_PKCS1_SHA1, &rng, &msg,
+                                    actual.as_mut_slice()));
+            assert_eq!(actual, expected);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_signature_rsa_to_der() {
+        test::from_file("src/rsa/rsa_pkcs1_sign_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let _ = test_case.consume_string("Digest");
+            let _ = test_case.consume_bytes("Msg");
+            let _ = test_case.consume_bytes("Sig");
+            let result = test_case.consume_string("Result");
+            let private_key = test_case.consume_bytes("Key");
+
+            let key_pair = signature::RSAKeyPair::from_der(
+                untrusted::Input::from(&private_key));
+            if key_pair.is_err() && result == "Fail-Invalid-Key" {
+                return Ok(());
+            }
+            let key_pair = key_pair.unwrap();
+
+            // `from_der` only accepts valid DER, so the serialization must be
+            // identical to the input.
+            let serialized = try!(key_pair.to_der());
+            assert_eq!(serialized.as_ref(), &private_key[..]);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_signature_rsa_to_pkcs8() {
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        // Generated with
+        // `openssl pkcs8 -topk8 -nocrypt -inform DER -outform DER`
+        const PRIVATE_KEY_PKCS8: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.pk8");
+
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+        let pkcs8 = key_pair.to_pkcs8().unwrap();
+        assert_eq!(pkcs8.as_ref(), PRIVATE_KEY_PKCS8);
+    }
+
+    #[test]
+    fn test_signature_rsa_from_encrypted_pkcs8() {
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        const PASSWORD: &'static [u8] = b"ring";
+
+        // The CBC documents were generated with
+        // `openssl pkcs8 -topk8 -v2 aes-256-cbc -v2prf hmacWithSHA256
+        //  -iter 2048` and the equivalent with `aes-128-cbc` and
+        // `hmacWithSHA512`. OpenSSL can't produce the GCM document, so it was
+        // generated with a Python script.
+        const ENCRYPTED: [&'static [u8]; 3] = [
+            include_bytes!("signature_rsa_example_private_key_aes_128_cbc.pk8"),
+            include_bytes!("signature_rsa_example_private_key_aes_256_cbc.pk8"),
+            include_bytes!("signature_rsa_example_private_key_aes_256_gcm.pk8"),
+        ];
+
+        for encrypted in ENCRYPTED.iter() {
+            let key_pair = signature::RSAKeyPair::from_encrypted_pkcs8(
+                untrusted::Input::from(encrypted), PASSWORD).unwrap();
+            assert_eq!(key_pair.to_der().unwrap().as_ref(), PRIVATE_KEY_DER);
+
+            assert!(signature::RSAKeyPair::from_encrypted_pkcs8(
+                untrusted::Input::from(encrypted), b"wrong").is_err());
+
+            // Corrupt the last byte of the `encryptedData`.
+            let mut corrupted = encrypted.to_vec();
+            let last = corrupted.len() - 1;
+            corrupted[last] ^= 1;
+            assert!(signature::RSAKeyPair::from_encrypted_pkcs8(
+                untrusted::Input::from(&corrupted), PASSWORD).is_err());
+        }
+
+        // An unencrypted document isn't accepted.
+        const PRIVATE_KEY_PKCS8: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.pk8");
+        assert!(signature::RSAKeyPair::from_encrypted_pkcs8(
+            untrusted::Input::from(PRIVATE_KEY_PKCS8), PASSWORD).is_err());
+    }
+
+    #[test]
+    fn test_signature_rsa_pkcs1_sign_multi_part() {
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        const MESSAGE: &'static [u8] = b"hello, world";
+
+        let rng = rand::SystemRandom::new();
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+        let mut signing_state =
+            signature::RSASigningState::new(key_pair.clone()).unwrap();
+
+        let mut expected = vec![0; key_pair.public_modulus_len()];
+        signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                           &mut expected).unwrap();
+
+        for i in 0..(MESSAGE.len() + 1) {
+            let (a, b) = MESSAGE.split_at(i);
+            let mut actual = vec![0; key_pair.public_modulus_len()];
+            let mut ctx = signature::RSASigningContext::new(
+                &mut signing_state, &signature::RSA_PKCS1_SHA256, &rng);
+            ctx.update(a);
+            ctx.update(b);
+            ctx.finish(&mut actual).unwrap();
+            assert_eq!(actual, expected);
+        }
+
+        // The output buffer must be the same length as the public modulus.
+        let mut too_short = vec![0; key_pair.public_modulus_len() - 1];
+        let mut ctx = signature::RSASigningContext::new(
+            &mut signing_state, &signature::RSA_PKCS1_SHA256, &rng);
+        ctx.update(MESSAGE);
+        assert!(ctx.finish(&mut too_short).is_err());
+    }
+
     // `RSAKeyPair::sign` requires that the output buffer is the same length as
     // the public key modulus. Test what happens when it isn't the same length.
     #[test]
@@ -428,13 +1208,12 @@ mod tests {
             include_bytes!("signature_rsa_example_private_key.der");
         let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
         let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
-        let key_pair = std::sync::Arc::new(key_pair);
         let mut signing_state =
             signature::RSASigningState::new(key_pair).unwrap();
 
         // The output buffer is one byte too short.
         let mut signature =
-            vec![0; signing_state.key_pair().public_modulus_len() - 1];
+            vec![0; signing_state.public_modulus_len() - 1];
 
         assert!(signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
                                    &mut signature).is_err());
@@ -464,23 +1243,116 @@ mod tests {
             include_bytes!("signature_rsa_example_private_key.der");
         let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
         let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
-        let key_pair = std::sync::Arc::new(key_pair);
         let mut signature = vec![0; key_pair.public_modulus_len()];
 
         let mut signing_state =
             signature::RSASigningState::new(key_pair).unwrap();
 
-        let blinding_counter = unsafe { blinding::GFp_BN_BLINDING_COUNTER };
+        let blinding_counter = blinding::counter_limit();
 
         for _ in 0..(blinding_counter + 1) {
-            let prev_counter = signing_state.blinding.counter();
+            let prev_counter = blinding_counter_of(&signing_state);
             let _ = signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng,
                                        MESSAGE, &mut signature);
-            let counter = signing_state.blinding.counter();
+            let counter = blinding_counter_of(&signing_state);
             assert_eq!(counter, (prev_counter + 1) % blinding_counter);
         }
     }
 
+    // When the blinding cache is enabled, a new `RSASigningState` should
+    // continue using the blinding of a dropped one instead of starting over.
+    #[test]
+    fn test_signature_rsa_signer() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+
+        // PKCS#1 1.5 signatures are deterministic, so the signer's signature
+        // must be the same as the signing state's.
+        let mut expected = vec![0; key_pair.public_modulus_len()];
+        signature::RSASigningState::new(key_pair.clone()).unwrap()
+            .sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE, &mut expected)
+            .unwrap();
+
+        let signer: std::boxed::Box<signature::Signer> = std::boxed::Box::new(
+            signature::RSASigner::new(key_pair.clone(),
+                                      &signature::RSA_PKCS1_SHA256));
+        assert_eq!(signer.max_signature_len(), expected.len());
+        assert_eq!(signer.public_key_bytes(),
+                   signature::KeyPair::public_key(&key_pair));
+
+        // The output buffer may be longer than the signature.
+        let mut actual = vec![0; expected.len() + 1];
+        assert_eq!(signer.sign(MESSAGE, &rng, &mut actual), Ok(expected.len()));
+        assert_eq!(&actual[..expected.len()], &expected[..]);
+
+        // It may not be shorter.
+        let mut actual = vec![0; expected.len() - 1];
+        assert!(signer.sign(MESSAGE, &rng, &mut actual).is_err());
+    }
+
+    #[test]
+    fn test_signature_rsa_pkcs1_sign_blinding_cache() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+        key_pair.enable_blinding_cache(1);
+        let mut signature = vec![0; key_pair.public_modulus_len()];
+
+        let blinding_counter = blinding::counter_limit();
+
+        // A new blinding is created the first time an `RSASigningState` is
+        // used, which resets the counter to zero.
+        let fresh_counter = {
+            let mut signing_state =
+                signature::RSASigningState::new(key_pair.clone()).unwrap();
+            let fresh_counter = blinding_counter_of(&signing_state);
+            assert_eq!(fresh_counter, blinding_counter - 1);
+            signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                               &mut signature).unwrap();
+            assert_eq!(blinding_counter_of(&signing_state), 0);
+            fresh_counter
+        };
+
+        // Two states exist at once; only one of them can get the cached
+        // blinding.
+        let mut signing_state =
+            signature::RSASigningState::new(key_pair.clone()).unwrap();
+        assert_eq!(blinding_counter_of(&signing_state), 0);
+        let other_signing_state =
+            signature::RSASigningState::new(key_pair.clone()).unwrap();
+        assert_eq!(blinding_counter_of(&other_signing_state), fresh_counter);
+
+        signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                           &mut signature).unwrap();
+        assert_eq!(blinding_counter_of(&signing_state), 1);
+
+        // The cache only holds one blinding, so dropping both states keeps
+        // the first one returned.
+        core::mem::drop(signing_state);
+        core::mem::drop(other_signing_state);
+        let signing_state =
+            signature::RSASigningState::new(key_pair).unwrap();
+        assert_eq!(blinding_counter_of(&signing_state), 1);
+    }
+
+    fn blinding_counter_of(signing_state: &signature::RSASigningState)
+                           -> u32 {
+        match signing_state.key {
+            super::Key::Local { ref blinding, .. } =>
+                blinding.as_ref().unwrap().counter(),
+            super::Key::Remote { .. } => unreachable!(),
+        }
+    }
+
     // In `crypto/rsa/blinding.c`, when `bn_blinding_create_param` fails to
     // randomly generate an invertible blinding factor too many times in a
     // loop, it returns an error. Check that we observe this.
@@ -497,11 +1369,10 @@ mod tests {
             include_bytes!("signature_rsa_example_private_key.der");
         let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
         let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
-        let key_pair = std::sync::Arc::new(key_pair);
         let mut signing_state =
             signature::RSASigningState::new(key_pair).unwrap();
         let mut signature =
-            vec![0; signing_state.key_pair().public_modulus_len()];
+            vec![0; signing_state.public_modulus_len()];
         let result = signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng,
                                         MESSAGE, &mut signature);
 
@@ -549,7 +1420,6 @@ mod tests {
                 return Ok(());
             }
             let key_pair = key_pair.unwrap();
-            let key_pair = std::sync::Arc::new(key_pair);
             let msg = test_case.consume_bytes("Msg");
             let salt = test_case.consume_bytes("Salt");
             let expected = test_case.consume_bytes("Sig");
@@ -559,7 +1429,7 @@ mod tests {
             let mut signing_state =
                 signature::RSASigningState::new(key_pair).unwrap();
             let mut actual: std::vec::Vec<u8> =
-                vec![0; signing_state.key_pair().public_modulus_len()];
+                vec![0; signing_state.public_modulus_len()];
             try!(signing_state.sign(alg, &new_rng, &msg, actual.as_mut_slice()));
             assert_eq!(actual.as_slice() == &expected[..], result == "Pass");
             Ok(())
@@ -567,13 +1437,55 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_signature_rsa_pss_mgf1_sha1_sign() {
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        const PUBLIC_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_public_key.der");
+        const MESSAGE: &'static [u8] = b"hello, world";
+
+        let rng = rand::SystemRandom::new();
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+        let mut signing_state =
+            signature::RSASigningState::new(key_pair).unwrap();
+
+        let algs: [(&'static signature::RSAEncoding,
+                    &'static signature::RSAParameters,
+                    &'static signature::RSAParameters); 3] = [
+            (&signature::RSA_PSS_SHA256_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA256_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA256),
+            (&signature::RSA_PSS_SHA384_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA384_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA384),
+            (&signature::RSA_PSS_SHA512_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA512_MGF1_SHA1,
+             &signature::RSA_PSS_2048_8192_SHA512),
+        ];
+        for &(padding_alg, verification_alg, other_alg) in algs.iter() {
+            let mut sig =
+                vec![0; signing_state.public_modulus_len()];
+            signing_state.sign(padding_alg, &rng, MESSAGE, &mut sig).unwrap();
+
+            let public_key = untrusted::Input::from(PUBLIC_KEY_DER);
+            let msg = untrusted::Input::from(MESSAGE);
+            let sig = untrusted::Input::from(&sig);
+            assert!(signature::verify(verification_alg, public_key, msg, sig)
+                        .is_ok());
+            // The MGF1 digest algorithm isn't the message digest algorithm.
+            assert!(signature::verify(other_alg, public_key, msg, sig)
+                        .is_err());
+        }
+    }
+
     #[test]
     fn test_sync_and_send() {
         const PRIVATE_KEY_DER: &'static [u8] =
             include_bytes!("signature_rsa_example_private_key.der");
         let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
         let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
-        let key_pair = std::sync::Arc::new(key_pair);
 
         let _: &Send = &key_pair;
         let _: &Sync = &key_pair;
@@ -583,4 +1495,136 @@ mod tests {
         // TODO: Test that signing_state is NOT Sync; i.e.
         // `let _: &Sync = &signing_state;` must fail
     }
+
+    #[test]
+    fn test_signature_rsa_key_pair_fingerprint() {
+        // The expected values were calculated using:
+        //
+        // ```sh
+        // openssl rsa -in signature_rsa_example_private_key.der -inform DER \
+        //             -pubout -outform DER | openssl dgst -sha256
+        // ```
+        //
+        // (and similarly with `-sha1`).
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+
+        let sha256 = key_pair.fingerprint(&digest::SHA256).unwrap();
+        assert_eq!(sha256.as_ref(), &test::from_hex(
+            "2bb6edc4e5e381efb611492954f6f09a51dba38ab64844fc3491089fc3c3c801")
+            .unwrap()[..]);
+
+        let sha1 = key_pair.fingerprint(&digest::SHA1).unwrap();
+        assert_eq!(sha1.as_ref(), &test::from_hex(
+            "dbab12a3395eadca42cbc91651889dafe8375584").unwrap()[..]);
+    }
+
+    // An `RSAPrivateKeyOperation` that checks that it is given a PKCS#1 1.5
+    // encoded SHA-256 digest of `MESSAGE` and then returns a signature that
+    // was calculated in advance, like a remote signer would.
+    struct PrecomputedPrivateKeyOperation {
+        n_bits: usize,
+        signature: std::vec::Vec<u8>,
+    }
+
+    impl signature::RSAPrivateKeyOperation for PrecomputedPrivateKeyOperation {
+        fn public_modulus_bits(&self) -> usize { self.n_bits }
+
+        fn private_transform(&self, in_out: &mut [u8])
+                             -> Result<(), error::Unspecified> {
+            assert_eq!(in_out.len(), self.signature.len());
+            assert_eq!(&in_out[..2], &[0x00, 0x01]);
+            let m_hash = digest::digest(&digest::SHA256, MESSAGE);
+            assert!(in_out.ends_with(m_hash.as_ref()));
+            in_out.copy_from_slice(&self.signature);
+            Ok(())
+        }
+    }
+
+    const MESSAGE: &'static [u8] = b"hello, world";
+
+    #[test]
+    fn test_signature_rsa_private_key_operation() {
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        const PUBLIC_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_public_key.der");
+        let rng = rand::SystemRandom::new();
+
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+        let mut signing_state =
+            signature::RSASigningState::new(key_pair).unwrap();
+        let mut expected = vec![0; signing_state.public_modulus_len()];
+        signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                           &mut expected).unwrap();
+
+        let operation = PrecomputedPrivateKeyOperation {
+            n_bits: 2048,
+            signature: expected.clone(),
+        };
+        let mut signing_state =
+            signature::RSASigningState::from_private_key_operation(
+                std::sync::Arc::new(operation)).unwrap();
+        assert!(signing_state.key_pair().is_none());
+        assert_eq!(signing_state.public_modulus_len(), expected.len());
+
+        let mut actual = vec![0; signing_state.public_modulus_len()];
+        signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                           &mut actual).unwrap();
+        assert_eq!(actual, expected);
+        assert!(signature::verify(&signature::RSA_PKCS1_2048_8192_SHA256,
+                                  untrusted::Input::from(PUBLIC_KEY_DER),
+                                  untrusted::Input::from(MESSAGE),
+                                  untrusted::Input::from(&actual)).is_ok());
+
+        // The signature buffer must be exactly the right length.
+        let mut too_short = vec![0; signing_state.public_modulus_len() - 1];
+        assert!(signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng,
+                                   MESSAGE, &mut too_short).is_err());
+    }
+
+    #[test]
+    fn test_signature_rsa_private_key_operation_modulus_bits() {
+        for &(n_bits, ok) in [(2047, false), (2048, true), (8192, true),
+                              (8193, false)].iter() {
+            let operation = PrecomputedPrivateKeyOperation {
+                n_bits: n_bits,
+                signature: std::vec::Vec::new(),
+            };
+            let result = signature::RSASigningState::from_private_key_operation(
+                std::sync::Arc::new(operation));
+            assert_eq!(result.is_ok(), ok);
+        }
+    }
+
+    // Clones of a key pair, including ones moved to other threads, should
+    // produce the same signatures as the original.
+    #[test]
+    fn test_signature_rsa_key_pair_clone() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("signature_rsa_example_private_key.der");
+        let key_bytes_der = untrusted::Input::from(PRIVATE_KEY_DER);
+        let key_pair = signature::RSAKeyPair::from_der(key_bytes_der).unwrap();
+
+        fn sign(key_pair: signature::RSAKeyPair) -> std::vec::Vec<u8> {
+            let rng = rand::SystemRandom::new();
+            let mut signature = vec![0; key_pair.public_modulus_len()];
+            let mut signing_state =
+                signature::RSASigningState::new(key_pair).unwrap();
+            signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
+                               &mut signature).unwrap();
+            signature
+        }
+
+        let cloned = key_pair.clone();
+        let from_other_thread =
+            std::thread::spawn(move || sign(cloned)).join().unwrap();
+        let expected = sign(key_pair);
+        assert_eq!(from_other_thread, expected);
+    }
 }
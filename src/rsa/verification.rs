@@ -14,11 +14,15 @@
 
 /// RSA PKCS#1 1.5 signatures.
 
-use {bits, bssl, c, digest, error, private, signature};
-use super::{bigint, N, PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN, RSAParameters,
-            parse_public_key};
+use {bits, digest, error, private, signature};
+use super::{bigint, N, PUBLIC_EXPONENT_LEGACY_MAX_BITS,
+            PUBLIC_EXPONENT_MAX_BITS, PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN,
+            RSAParameters, parse_public_key};
 use untrusted;
 
+#[cfg(not(feature = "rust_bigint"))]
+use {bssl, c};
+
 
 impl signature::VerificationAlgorithm for RSAParameters {
     fn verify(&self, public_key: untrusted::Input, msg: untrusted::Input,
@@ -34,6 +38,11 @@ impl private::Private for RSAParameters {}
 macro_rules! rsa_params {
     ( $VERIFY_ALGORITHM:ident, $min_bits:expr, $PADDING_ALGORITHM:expr,
       $doc_str:expr ) => {
+        rsa_params!($VERIFY_ALGORITHM, $min_bits, $PADDING_ALGORITHM,
+                    PUBLIC_EXPONENT_MAX_BITS, $doc_str);
+    };
+    ( $VERIFY_ALGORITHM:ident, $min_bits:expr, $PADDING_ALGORITHM:expr,
+      $e_max_bits:expr, $doc_str:expr ) => {
         #[doc=$doc_str]
         ///
         /// Only available in `use_heap` mode.
@@ -41,8 +50,9 @@ macro_rules! rsa_params {
             RSAParameters {
                 padding_alg: $PADDING_ALGORITHM,
                 min_bits: bits::BitLength($min_bits),
+                e_max_bits: $e_max_bits,
             };
-    }
+    };
 }
 
 rsa_params!(RSA_PKCS1_2048_8192_SHA1, 2048, &super::padding::RSA_PKCS1_SHA1,
@@ -66,6 +76,31 @@ rsa_params!(RSA_PKCS1_3072_8192_SHA384, 3072, &super::RSA_PKCS1_SHA384,
              PKCS#1.5 padding, and SHA-384.\n\nSee \"`RSA_PKCS1_*` Details\" in
              `ring::signature`'s module-level documentation for more details.");
 
+rsa_params!(RSA_PKCS1_2048_8192_SHA1_E64, 2048, &super::padding::RSA_PKCS1_SHA1,
+            PUBLIC_EXPONENT_LEGACY_MAX_BITS,
+            "Verification of signatures using RSA keys of 2048-8192 bits with
+             public exponents of up to 64 bits, PKCS#1.5 padding, and
+             SHA-1.\n\nSee \"`RSA_PKCS1_*_E64` Details\" in
+             `ring::signature`'s module-level documentation for more details.");
+rsa_params!(RSA_PKCS1_2048_8192_SHA256_E64, 2048, &super::RSA_PKCS1_SHA256,
+            PUBLIC_EXPONENT_LEGACY_MAX_BITS,
+            "Verification of signatures using RSA keys of 2048-8192 bits with
+             public exponents of up to 64 bits, PKCS#1.5 padding, and
+             SHA-256.\n\nSee \"`RSA_PKCS1_*_E64` Details\" in
+             `ring::signature`'s module-level documentation for more details.");
+rsa_params!(RSA_PKCS1_2048_8192_SHA384_E64, 2048, &super::RSA_PKCS1_SHA384,
+            PUBLIC_EXPONENT_LEGACY_MAX_BITS,
+            "Verification of signatures using RSA keys of 2048-8192 bits with
+             public exponents of up to 64 bits, PKCS#1.5 padding, and
+             SHA-384.\n\nSee \"`RSA_PKCS1_*_E64` Details\" in
+             `ring::signature`'s module-level documentation for more details.");
+rsa_params!(RSA_PKCS1_2048_8192_SHA512_E64, 2048, &super::RSA_PKCS1_SHA512,
+            PUBLIC_EXPONENT_LEGACY_MAX_BITS,
+            "Verification of signatures using RSA keys of 2048-8192 bits with
+             public exponents of up to 64 bits, PKCS#1.5 padding, and
+             SHA-512.\n\nSee \"`RSA_PKCS1_*_E64` Details\" in
+             `ring::signature`'s module-level documentation for more details.");
+
 rsa_params!(RSA_PSS_2048_8192_SHA256, 2048, &super::RSA_PSS_SHA256,
             "Verification of signatures using RSA keys of 2048-8192 bits,
              PSS padding, and SHA-256.\n\nSee \"`RSA_PSS_*` Details\" in
@@ -79,6 +114,25 @@ rsa_params!(RSA_PSS_2048_8192_SHA512, 2048, &super::RSA_PSS_SHA512,
              PSS padding, and SHA-512.\n\nSee \"`RSA_PSS_*` Details\" in
              `ring::signature`'s module-level documentation for more details.");
 
+rsa_params!(RSA_PSS_2048_8192_SHA256_MGF1_SHA1, 2048,
+            &super::RSA_PSS_SHA256_MGF1_SHA1,
+            "Verification of signatures using RSA keys of 2048-8192 bits,
+             PSS padding, SHA-256, and MGF1 with SHA-1.\n\nSee
+             \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+             module-level documentation for more details.");
+rsa_params!(RSA_PSS_2048_8192_SHA384_MGF1_SHA1, 2048,
+            &super::RSA_PSS_SHA384_MGF1_SHA1,
+            "Verification of signatures using RSA keys of 2048-8192 bits,
+             PSS padding, SHA-384, and MGF1 with SHA-1.\n\nSee
+             \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+             module-level documentation for more details.");
+rsa_params!(RSA_PSS_2048_8192_SHA512_MGF1_SHA1, 2048,
+            &super::RSA_PSS_SHA512_MGF1_SHA1,
+            "Verification of signatures using RSA keys of 2048-8192 bits,
+             PSS padding, SHA-512, and MGF1 with SHA-1.\n\nSee
+             \"`RSA_PSS_*_MGF1_SHA1` Details\" in `ring::signature`'s
+             module-level documentation for more details.");
+
 
 /// Lower-level API for the verification of RSA signatures.
 ///
@@ -121,15 +175,13 @@ pub fn verify_rsa(params: &RSAParameters,
         PUBLIC_KEY_PUBLIC_MODULUS_MAX_LEN));
     let (n, e) =
         try!(super::check_public_modulus_and_exponent(n, e, params.min_bits,
-                                                      max_bits));
+                                                      max_bits,
+                                                      params.e_max_bits));
     let n_bits = n.bit_length();
     let n = try!(n.into_modulus::<N>());
 
     let decoded = &mut decoded[..signature.len()];
-    try!(bssl::map_result(unsafe {
-        GFp_rsa_public_decrypt(decoded.as_mut_ptr(), decoded.len(), n.as_ref(),
-                               e.as_ref(), signature.as_ptr(), signature.len())
-    }));
+    try!(public_decrypt(&n, &e, signature, decoded));
 
     let m_hash = digest::digest(params.padding_alg.digest_alg(),
                                 msg.as_slice_less_safe());
@@ -138,6 +190,35 @@ pub fn verify_rsa(params: &RSAParameters,
         error::Unspecified, |m| params.padding_alg.verify(&m_hash, m, n_bits))
 }
 
+// Sets `out` to `signature`**e (mod n). `signature` and `out` must have the
+// same length as `n`.
+#[cfg(not(feature = "rust_bigint"))]
+fn public_decrypt(n: &bigint::Modulus<N>, e: &bigint::OddPositive,
+                  signature: &[u8], out: &mut [u8])
+                  -> Result<(), error::Unspecified> {
+    bssl::map_result(unsafe {
+        GFp_rsa_public_decrypt(out.as_mut_ptr(), out.len(), n.as_ref(),
+                               e.as_ref(), signature.as_ptr(), signature.len())
+    })
+}
+
+// Sets `out` to `signature`**e (mod n). `signature` and `out` must have the
+// same length as `n`.
+#[cfg(feature = "rust_bigint")]
+fn public_decrypt(n: &bigint::Modulus<N>, e: &bigint::OddPositive,
+                  signature: &[u8], out: &mut [u8])
+                  -> Result<(), error::Unspecified> {
+    if signature.len() != n.len_bytes() || out.len() != signature.len() {
+        return Err(error::Unspecified);
+    }
+    let s = try!(bigint::Positive::from_be_bytes_padded(
+        untrusted::Input::from(signature)));
+    let s = try!(s.into_elem_decoded(n));
+    let m = try!(bigint::elem_exp_vartime(s, e, n));
+    m.fill_be_bytes(out)
+}
+
+#[cfg(not(feature = "rust_bigint"))]
 extern {
     fn GFp_rsa_public_decrypt(out: *mut u8, out_len: c::size_t,
                               mont_n: &bigint::BN_MONT_CTX, e: &bigint::BIGNUM,
@@ -197,6 +278,74 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_signature_rsa_pkcs1_verify_e64() {
+        test::from_file("src/rsa/rsa_pkcs1_verify_e64_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let digest_name = test_case.consume_string("Digest");
+            let (alg, strict_alg) = match digest_name.as_ref() {
+                "SHA256" => (&signature::RSA_PKCS1_2048_8192_SHA256_E64,
+                             &signature::RSA_PKCS1_2048_8192_SHA256),
+                _ =>  { panic!("Unsupported digest: {}", digest_name) }
+            };
+
+            let public_key = test_case.consume_bytes("Key");
+            let public_key = untrusted::Input::from(&public_key);
+
+            let msg = test_case.consume_bytes("Msg");
+            let msg = untrusted::Input::from(&msg);
+
+            let sig = test_case.consume_bytes("Sig");
+            let sig = untrusted::Input::from(&sig);
+
+            let expected_result = test_case.consume_string("Result");
+
+            let actual_result = signature::verify(alg, public_key, msg, sig);
+            assert_eq!(actual_result.is_ok(), expected_result == "P");
+
+            // None of these keys have an exponent small enough to be accepted
+            // by the default algorithms.
+            assert!(signature::verify(strict_alg, public_key, msg, sig)
+                        .is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_signature_rsa_pss_mgf1_sha1_verify() {
+        test::from_file("src/rsa/rsa_pss_mgf1_sha1_verify_tests.txt",
+                        |section, test_case| {
+            assert_eq!(section, "");
+
+            let digest_name = test_case.consume_string("Digest");
+            let alg = match digest_name.as_ref() {
+                "SHA256" => &signature::RSA_PSS_2048_8192_SHA256_MGF1_SHA1,
+                "SHA384" => &signature::RSA_PSS_2048_8192_SHA384_MGF1_SHA1,
+                "SHA512" => &signature::RSA_PSS_2048_8192_SHA512_MGF1_SHA1,
+                _ =>  { panic!("Unsupported digest: {}", digest_name) }
+            };
+
+            let public_key = test_case.consume_bytes("Key");
+            let public_key = untrusted::Input::from(&public_key);
+
+            let msg = test_case.consume_bytes("Msg");
+            let msg = untrusted::Input::from(&msg);
+
+            let sig = test_case.consume_bytes("Sig");
+            let sig = untrusted::Input::from(&sig);
+
+            let expected_result = test_case.consume_string("Result");
+
+            let actual_result = signature::verify(alg, public_key, msg, sig);
+            assert_eq!(actual_result.is_ok(), expected_result == "P");
+
+            Ok(())
+        });
+    }
+
     #[test]
     fn test_signature_rsa_pss_verify() {
         test::from_file("src/rsa/rsa_pss_verify_tests.txt",
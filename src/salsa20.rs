@@ -0,0 +1,214 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The Salsa20 stream cipher and HSalsa20, which XSalsa20 uses to extend the
+//! nonce, as described in [Salsa20 specification] and
+//! [Extending the Salsa20 nonce].
+//!
+//! This is a portable implementation that exists only for compatibility with
+//! NaCl's `crypto_secretbox` and `crypto_box`; new protocols should use
+//! ChaCha20, which has optimized implementations.
+//!
+//! [Salsa20 specification]: https://cr.yp.to/snuffle/spec.pdf
+//! [Extending the Salsa20 nonce]: https://cr.yp.to/snuffle/xsalsa-20110204.pdf
+
+use {core, poly1305};
+use polyfill::slice::{le_u8_from_u32, u32_from_le_u8};
+
+pub type Key = [u32; KEY_LEN / 4];
+
+pub fn key_from_bytes(key_bytes: &[u8; KEY_LEN]) -> Key {
+    let mut key = [0u32; KEY_LEN / 4];
+    for (key_u32, key_u8_4) in key.iter_mut().zip(key_bytes.chunks(4)) {
+        *key_u32 = u32_from_le_u8(slice_as_array_ref!(key_u8_4, 4).unwrap());
+    }
+    key
+}
+
+/// HSalsa20: the Salsa20 core without the final addition of the input,
+/// keeping only the diagonal and the words that held the nonce as the output
+/// key.
+pub fn hsalsa20(key: &Key, nonce: &[u8; HSALSA20_NONCE_LEN]) -> Key {
+    let mut x = initial_state(key, nonce);
+    rounds(&mut x, 10);
+    [x[0], x[5], x[10], x[15], x[6], x[7], x[8], x[9]]
+}
+
+/// XORs the Salsa20 key stream, starting at byte `position` of the stream,
+/// into `in_out`.
+pub fn salsa20_xor_in_place(key: &Key, nonce: &[u8; NONCE_LEN],
+                            position: u64, in_out: &mut [u8]) {
+    let mut counter = position / (BLOCK_LEN as u64);
+    let mut offset = (position % (BLOCK_LEN as u64)) as usize;
+    let mut input = [0u8; 16];
+    input[..NONCE_LEN].copy_from_slice(nonce);
+
+    let mut done = 0;
+    while done < in_out.len() {
+        // The counter is a little-endian 64-bit integer.
+        input[8..12].copy_from_slice(&le_u8_from_u32(counter as u32));
+        input[12..].copy_from_slice(&le_u8_from_u32((counter >> 32) as u32));
+        let key_stream = block(key, &input);
+        let n = core::cmp::min(BLOCK_LEN - offset, in_out.len() - done);
+        for (b, k) in in_out[done..(done + n)].iter_mut()
+                                              .zip(&key_stream[offset..]) {
+            *b ^= *k;
+        }
+        done += n;
+        offset = 0;
+        counter = counter.wrapping_add(1);
+    }
+}
+
+/// Derives a Poly1305 key from the first 32 bytes of the key stream, as
+/// NaCl's `crypto_secretbox` does.
+pub fn derive_poly1305_key(key: &Key, nonce: &[u8; NONCE_LEN])
+                           -> poly1305::Key {
+    let mut bytes = [0u8; poly1305::KEY_LEN];
+    salsa20_xor_in_place(key, nonce, 0, &mut bytes);
+    poly1305::Key::new(&bytes)
+}
+
+/// The Salsa20 core, which maps the key and the 16 bytes of nonce and
+/// counter to a block of the key stream.
+fn block(key: &Key, input: &[u8; 16]) -> [u8; BLOCK_LEN] {
+    let initial = initial_state(key, input);
+    let mut x = initial;
+    rounds(&mut x, 10);
+    let mut out = [0u8; BLOCK_LEN];
+    for ((out, x), initial) in
+            out.chunks_mut(4).zip(x.iter()).zip(initial.iter()) {
+        out.copy_from_slice(&le_u8_from_u32(x.wrapping_add(*initial)));
+    }
+    out
+}
+
+/// The Salsa20/8 core, which scrypt uses as its hash function: replaces `b`
+/// with the sum of `b` and the result of applying eight rounds to `b`.
+#[cfg(feature = "use_heap")]
+pub fn salsa20_8_core(b: &mut [u32; 16]) {
+    let mut x = *b;
+    rounds(&mut x, 4);
+    for (b, x) in b.iter_mut().zip(x.iter()) {
+        *b = b.wrapping_add(*x);
+    }
+}
+
+fn initial_state(key: &Key, input: &[u8; 16]) -> [u32; 16] {
+    let input_word = |i: usize| {
+        u32_from_le_u8(slice_as_array_ref!(&input[(4 * i)..(4 * i + 4)], 4)
+                           .unwrap())
+    };
+    [0x61707865, key[0], key[1], key[2],
+     key[3], 0x3320646e, input_word(0), input_word(1),
+     input_word(2), input_word(3), 0x79622d32, key[4],
+     key[5], key[6], key[7], 0x6b206574]
+}
+
+fn rounds(x: &mut [u32; 16], double_rounds: usize) {
+    for _ in 0..double_rounds {
+        // Column round.
+        quarter_round(x, 0, 4, 8, 12);
+        quarter_round(x, 5, 9, 13, 1);
+        quarter_round(x, 10, 14, 2, 6);
+        quarter_round(x, 15, 3, 7, 11);
+        // Row round.
+        quarter_round(x, 0, 1, 2, 3);
+        quarter_round(x, 5, 6, 7, 4);
+        quarter_round(x, 10, 11, 8, 9);
+        quarter_round(x, 15, 12, 13, 14);
+    }
+}
+
+#[inline(always)]
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+pub const KEY_LEN: usize = 256 / 8;
+
+const BLOCK_LEN: usize = 64;
+
+pub const NONCE_LEN: usize = 8; /* 64 bits */
+
+pub const HSALSA20_NONCE_LEN: usize = 16; /* 128 bits */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The test vector from NaCl's tests/core1.c.
+    #[test]
+    pub fn hsalsa20_test() {
+        let shared = [
+            0x4a, 0x5d, 0x9d, 0x5b, 0xa4, 0xce, 0x2d, 0xe1,
+            0x72, 0x8e, 0x3b, 0xf4, 0x80, 0x35, 0x0f, 0x25,
+            0xe0, 0x7e, 0x21, 0xc9, 0x47, 0xd1, 0x9e, 0x33,
+            0x76, 0xf0, 0x9b, 0x3c, 0x1e, 0x16, 0x17, 0x42,
+        ];
+        let expected = [
+            0x1b, 0x27, 0x55, 0x64, 0x73, 0xe9, 0x85, 0xd4,
+            0x62, 0xcd, 0x51, 0x19, 0x7a, 0x9a, 0x46, 0xc7,
+            0x60, 0x09, 0x54, 0x9e, 0xac, 0x64, 0x74, 0xf2,
+            0x06, 0xc4, 0xee, 0x08, 0x44, 0xf6, 0x83, 0x89,
+        ];
+        let key = key_from_bytes(&shared);
+        assert_eq!(hsalsa20(&key, &[0; HSALSA20_NONCE_LEN]),
+                   key_from_bytes(&expected));
+    }
+
+    // The test vector from RFC 7914 Section 8.
+    #[cfg(feature = "use_heap")]
+    #[test]
+    pub fn salsa20_8_core_test() {
+        use polyfill::slice::{le_u8_from_u32, u32_from_le_u8};
+        use test;
+
+        let input = test::from_hex(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+             ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e")
+            .unwrap();
+        let expected = test::from_hex(
+            "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+             b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81")
+            .unwrap();
+
+        let mut b = [0u32; 16];
+        for (b, bytes) in b.iter_mut().zip(input.chunks(4)) {
+            *b = u32_from_le_u8(slice_as_array_ref!(bytes, 4).unwrap());
+        }
+        salsa20_8_core(&mut b);
+        let actual: std::vec::Vec<u8> =
+            b.iter().flat_map(|b| le_u8_from_u32(*b).to_vec()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    // Starting in the middle of the key stream gives the same key stream.
+    #[test]
+    pub fn salsa20_position_test() {
+        let key = key_from_bytes(&[7; KEY_LEN]);
+        let nonce = [9; NONCE_LEN];
+        let mut expected = [0u8; 200];
+        salsa20_xor_in_place(&key, &nonce, 0, &mut expected);
+        for position in 0..expected.len() {
+            let mut actual = [0u8; 200];
+            let actual = &mut actual[position..];
+            salsa20_xor_in_place(&key, &nonce, position as u64, actual);
+            assert_eq!(&actual[..], &expected[position..]);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! scrypt derivation and verification.
+//!
+//! Use `derive` to derive scrypt outputs. Use `verify` to verify secrets
+//! against previously-derived outputs.
+//!
+//! `hash_encoded` and `verify_encoded` do the same with hashes in the
+//! [PHC string format], which records the parameters and the salt along
+//! with the hash, so that they can be stored in a single field and read by
+//! other implementations.
+//!
+//! scrypt is a memory-hard function that is used by many existing password
+//! stores and file formats. New systems should use `argon2` instead.
+//!
+//! scrypt is specified in [RFC 7914].
+//!
+//! [RFC 7914]: https://tools.ietf.org/html/rfc7914
+//! [PHC string format]:
+//!     https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::scrypt;
+//!
+//! // N = 2**14, r = 8, p = 1, which uses 16 MiB of memory.
+//! let params = scrypt::Params::new(1 << 14, 8, 1).unwrap();
+//! let salt = b"SodiumChloride";
+//!
+//! let mut derived = [0u8; 64];
+//! scrypt::derive(&params, salt, b"pleaseletmein", &mut derived).unwrap();
+//!
+//! assert!(scrypt::verify(&params, salt, b"pleaseletmein", &derived).is_ok());
+//! assert!(scrypt::verify(&params, salt, b"wrong password", &derived)
+//!             .is_err());
+//! ```
+
+use {constant_time, error, pbkdf2, phc, polyfill, salsa20, std};
+use polyfill::slice::{le_u8_from_u32, u32_from_le_u8};
+
+/// The cost parameters of an scrypt calculation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    n: u64,
+    r: u32,
+    p: u32,
+}
+
+impl Params {
+    /// Constructs the parameters with the CPU/memory cost `n`, the block size
+    /// `r`, and the parallelization parameter `p`.
+    ///
+    /// The calculation uses 128 * `r` * `n` bytes of memory.
+    ///
+    /// | Parameter | RFC 7914 Section 2 Term
+    /// |-----------|---------------------------------------
+    /// | `n`       | N (CPU/Memory cost parameter)
+    /// | `r`       | r (blockSize parameter)
+    /// | `p`       | p (parallelization parameter)
+    ///
+    /// Fails if `n` isn't a power of two greater than one, if `r` or `p` is
+    /// zero, if `n` isn't less than 2**(128 * `r` / 8), if `p` * `r` isn't
+    /// less than 2**30, or if the memory needed doesn't fit in the address
+    /// space.
+    pub fn new(n: u64, r: u32, p: u32) -> Result<Params, error::Unspecified> {
+        if n < 2 || !n.is_power_of_two() || r < 1 || p < 1 {
+            return Err(error::Unspecified);
+        }
+        if r < 4 && n >= 1 << (16 * r) {
+            return Err(error::Unspecified);
+        }
+        if u64::from(p) * u64::from(r) >= 1 << 30 {
+            return Err(error::Unspecified);
+        }
+        let block_len = 128 * u64::from(r);
+        let max = polyfill::u64_from_usize(usize::max_value());
+        if try!(checked_mul(block_len, n)) > max ||
+           try!(checked_mul(block_len, u64::from(p))) > max {
+            return Err(error::Unspecified);
+        }
+        Ok(Params { n: n, r: r, p: p })
+    }
+
+    /// The CPU/memory cost parameter N.
+    #[inline(always)]
+    pub fn n(&self) -> u64 { self.n }
+
+    /// The block size parameter r.
+    #[inline(always)]
+    pub fn r(&self) -> u32 { self.r }
+
+    /// The parallelization parameter p.
+    #[inline(always)]
+    pub fn p(&self) -> u32 { self.p }
+}
+
+/// Fills `out` with the key derived using scrypt with the given inputs.
+///
+/// Do not use `derive` as part of verifying a secret; use `verify` instead,
+/// to minimize the effectiveness of timing attacks.
+///
+/// | Parameter   | RFC 7914 Section 6 Term
+/// |-------------|---------------------------------------
+/// | params      | N, r, p
+/// | salt        | S (salt)
+/// | secret      | P (passphrase)
+/// | out         | DK (derived key)
+/// | out.len()   | dkLen (intended output length)
+///
+/// Fails if `out.len()` is larger than (2**32 - 1) * 32.
+pub fn derive(params: &Params, salt: &[u8], secret: &[u8], out: &mut [u8])
+              -> Result<(), error::Unspecified> {
+    if polyfill::u64_from_usize(out.len()) > ((1 << 32) - 1) * 32 {
+        return Err(error::Unspecified);
+    }
+
+    let r = params.r as usize;
+    let block_words = 32 * r;
+
+    let mut b = vec![0u8; 128 * r * (params.p as usize)];
+    pbkdf2::derive(PBKDF2_PRF, 1, salt, secret, &mut b);
+
+    let mut x = vec![0u32; block_words];
+    let mut scratch = vec![0u32; block_words];
+    let mut v = vec![0u32; block_words * (params.n as usize)];
+    for chunk in b.chunks_mut(128 * r) {
+        for (x, bytes) in x.iter_mut().zip(chunk.chunks(4)) {
+            *x = u32_from_le_u8(slice_as_array_ref!(bytes, 4).unwrap());
+        }
+        ro_mix(&mut x, &mut v, &mut scratch, params.n);
+        for (bytes, x) in chunk.chunks_mut(4).zip(x.iter()) {
+            bytes.copy_from_slice(&le_u8_from_u32(*x));
+        }
+    }
+
+    pbkdf2::derive(PBKDF2_PRF, 1, &b, secret, out);
+    Ok(())
+}
+
+/// Verifies that a previously-derived (e.g., using `derive`) scrypt value
+/// matches the scrypt value derived from the other inputs.
+///
+/// The comparison is done in constant time to prevent timing attacks. The
+/// comparison will fail if `previously_derived` is empty (has a length of
+/// zero).
+///
+/// | Parameter                  | RFC 7914 Section 6 Term
+/// |----------------------------|---------------------------------------
+/// | `params`                   | N, r, p
+/// | `salt`                     | S (salt)
+/// | `secret`                   | P (passphrase)
+/// | `previously_derived`       | DK (derived key)
+/// | `previously_derived.len()` | dkLen (intended output length)
+pub fn verify(params: &Params, salt: &[u8], secret: &[u8],
+              previously_derived: &[u8]) -> Result<(), error::Unspecified> {
+    if previously_derived.is_empty() {
+        return Err(error::Unspecified);
+    }
+    let mut derived = vec![0u8; previously_derived.len()];
+    try!(derive(params, salt, secret, &mut derived));
+    constant_time::verify_slices_are_equal(&derived, previously_derived)
+}
+
+/// Returns the scrypt hash of `secret` in the PHC string format, e.g.
+/// `$scrypt$ln=17,r=8,p=1$<salt>$<hash>`, where `ln` is log2(N), with a hash
+/// of `out_len` bytes.
+///
+/// The salt must be unique for each hash; it should be generated with a
+/// `SecureRandom`.
+///
+/// Fails if `out_len` is zero or is larger than (2**32 - 1) * 32.
+pub fn hash_encoded(params: &Params, salt: &[u8], secret: &[u8],
+                    out_len: usize)
+                    -> Result<std::string::String, error::Unspecified> {
+    if out_len == 0 {
+        return Err(error::Unspecified);
+    }
+    let mut out = vec![0u8; out_len];
+    try!(derive(params, salt, secret, &mut out));
+    Ok(phc::encode("scrypt", None,
+                   &[("ln", params.n.trailing_zeros()), ("r", params.r),
+                     ("p", params.p)],
+                   salt, &out))
+}
+
+/// Verifies that `secret` matches `encoded`, an scrypt hash in the PHC string
+/// format, e.g. one returned by `hash_encoded`.
+///
+/// The comparison is done in constant time to prevent timing attacks.
+pub fn verify_encoded(secret: &[u8], encoded: &str)
+                      -> Result<(), error::Unspecified> {
+    let decoded = try!(phc::decode(encoded));
+    if decoded.id != "scrypt" || decoded.version.is_some() {
+        return Err(error::Unspecified);
+    }
+    let mut params = decoded.params;
+    let log_n = try!(params.next("ln"));
+    let r = try!(params.next("r"));
+    let p = try!(params.next("p"));
+    try!(params.finish());
+    if log_n >= 64 {
+        return Err(error::Unspecified);
+    }
+    let params = try!(Params::new(1 << log_n, r, p));
+    verify(&params, &decoded.salt, secret, &decoded.hash)
+}
+
+static PBKDF2_PRF: &'static pbkdf2::PRF = &pbkdf2::HMAC_SHA256;
+
+// scryptROMix of RFC 7914 Section 5, with `v` and `scratch` as preallocated
+// working space.
+fn ro_mix(x: &mut [u32], v: &mut [u32], scratch: &mut [u32], n: u64) {
+    let block_words = x.len();
+    for v in v.chunks_mut(block_words) {
+        v.copy_from_slice(x);
+        block_mix(x, scratch);
+    }
+    for _ in 0..n {
+        let j = (integerify(x) & (n - 1)) as usize;
+        for (x, v) in x.iter_mut().zip(&v[(j * block_words)..]) {
+            *x ^= *v;
+        }
+        block_mix(x, scratch);
+    }
+}
+
+// scryptBlockMix of RFC 7914 Section 4, using Salsa20/8.
+fn block_mix(b: &mut [u32], y: &mut [u32]) {
+    let r = b.len() / 32;
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[(b.len() - 16)..]);
+    for (i, b) in b.chunks(16).enumerate() {
+        for (x, b) in x.iter_mut().zip(b) {
+            *x ^= *b;
+        }
+        salsa20::salsa20_8_core(&mut x);
+
+        // The even-numbered blocks go first, then the odd-numbered blocks.
+        let position = (i / 2) + (i % 2) * r;
+        y[(16 * position)..][..16].copy_from_slice(&x);
+    }
+    b.copy_from_slice(y);
+}
+
+// The first 64 bits of the last 64-byte block of `b`, as a little-endian
+// integer.
+fn integerify(b: &[u32]) -> u64 {
+    let last = b.len() - 16;
+    u64::from(b[last]) | (u64::from(b[last + 1]) << 32)
+}
+
+fn checked_mul(a: u64, b: u64) -> Result<u64, error::Unspecified> {
+    a.checked_mul(b).ok_or(error::Unspecified)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {error, scrypt, test};
+
+    #[test]
+    pub fn scrypt_tests() {
+        test::from_file("src/scrypt_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let secret = test_case.consume_bytes("P");
+            let salt = test_case.consume_bytes("S");
+            let n = test_case.consume_usize("N") as u64;
+            let r = test_case.consume_usize("r") as u32;
+            let p = test_case.consume_usize("p") as u32;
+            let dk = test_case.consume_bytes("DK");
+
+            let params = scrypt::Params::new(n, r, p).unwrap();
+            let mut out = vec![0u8; dk.len()];
+            assert!(scrypt::derive(&params, &salt, &secret, &mut out).is_ok());
+            assert_eq!(out, dk);
+
+            assert!(scrypt::verify(&params, &salt, &secret, &dk).is_ok());
+            let mut wrong = dk.clone();
+            wrong[0] ^= 1;
+            assert_eq!(scrypt::verify(&params, &salt, &secret, &wrong),
+                       Err(error::Unspecified));
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    pub fn scrypt_params_tests() {
+        assert!(scrypt::Params::new(2, 1, 1).is_ok());
+        assert!(scrypt::Params::new(0, 1, 1).is_err());
+        assert!(scrypt::Params::new(1, 1, 1).is_err());
+        assert!(scrypt::Params::new(3, 1, 1).is_err());
+        assert!(scrypt::Params::new(16, 0, 1).is_err());
+        assert!(scrypt::Params::new(16, 1, 0).is_err());
+
+        // N must be less than 2**(128 * r / 8).
+        assert!(scrypt::Params::new(1 << 15, 1, 1).is_ok());
+        assert!(scrypt::Params::new(1 << 16, 1, 1).is_err());
+        assert!(scrypt::Params::new(1 << 16, 2, 1).is_ok());
+
+        // p * r must be less than 2**30.
+        assert!(scrypt::Params::new(16, 1, 1 << 30).is_err());
+        assert!(scrypt::Params::new(16, 1 << 15, 1 << 15).is_err());
+
+        assert!(scrypt::Params::new(1 << 63, 8, 1).is_err());
+    }
+
+    #[test]
+    pub fn scrypt_verify_empty_test() {
+        let params = scrypt::Params::new(16, 1, 1).unwrap();
+        assert!(scrypt::verify(&params, b"salt", b"password", &[]).is_err());
+    }
+}
@@ -16,7 +16,9 @@
 //!
 //! Use the `verify` function to verify signatures, passing a reference to the
 //! algorithm that identifies the algorithm. See the documentation for `verify`
-//! for examples.
+//! for examples. Alternatively, an `UnparsedPublicKey` bundles a public key
+//! with its algorithm. The `Signer` and `Verifier` traits allow keys of
+//! different kinds to be used through trait objects.
 //!
 //! For signature verification, this API treats each combination of parameters
 //! as a separate algorithm. For example, instead of having a single "RSA"
@@ -26,11 +28,13 @@
 //! reduce the risks of algorithm agility and to provide consistency with ECDSA
 //! and EdDSA.
 //!
-//! Currently this module does not support digesting the message to be signed
-//! separately from the public key operation, as it is currently being
+//! Currently this module mostly does not support digesting the message to be
+//! signed separately from the public key operation, as it is currently being
 //! optimized for Ed25519 and for the implementation of protocols that do not
-//! requiring signing large messages. An interface for efficiently supporting
-//! larger messages may be added later.
+//! requiring signing large messages. The exception is Ed25519ph, which signs
+//! the SHA-512 digest of the message; see
+//! `Ed25519KeyPair::sign_ph_digest()` and
+//! `primitive::verify_ed25519ph_digest()`.
 //!
 //!
 //! # Algorithm Details
@@ -43,7 +47,8 @@
 //!
 //! The public key is encoding in uncompressed form using the
 //! Octet-String-to-Elliptic-Curve-Point algorithm in
-//! [SEC 1: Elliptic Curve Cryptography, Version 2.0].
+//! [SEC 1: Elliptic Curve Cryptography, Version 2.0]. Public keys in
+//! compressed form are also accepted.
 //!
 //! During verification, the public key is validated using the ECC Partial
 //! Public-Key Validation Routine from Section 5.6.2.3.3 of
@@ -51,6 +56,54 @@
 //! NSA's [Suite B implementer's guide to FIPS 186-3]. Note that, as explained
 //! in the NSA guide, ECC Partial Public-Key Validation is equivalent to ECC
 //! Full Public-Key Validation for prime-order curves like this one.
+//! `ecdsa_validate_public_key()` does the same validation without verifying
+//! a signature, and `ECDSAVerificationKey` does it once for verifying many
+//! signatures with the same public key. `ecdsa_public_key_from_raw()` and
+//! `ecdsa_public_key_to_raw()` convert public keys to and from the raw
+//! *x*||*y* form, without the leading byte, used by WebAuthn and others.
+//!
+//! The `ECDSA_*_ASN1_SIGNING` algorithms are used with `ECDSAKeyPair` to
+//! produce signatures in the same format. During signing, the nonce is chosen
+//! uniformly at random, by rejection sampling, using the secure random number
+//! generator passed to `sign()`. `sign_hedged()` instead derives the nonce
+//! from the private key, the message, and fresh random bytes, following
+//! [RFC 6979] with additional data. `sign_recoverable()` also returns the
+//! recovery ID that `ecdsa_recover_public_key()` needs to recover the public
+//! key from the signature and message, as is done for secp256k1 in Ethereum.
+//!
+//! ## `ECDSA_*_FIXED` Details: Fixed-length (PKCS#11-style) ECDSA Signatures
+//!
+//! The signature is *r*||*s*, where || denotes concatenation, and where *r*
+//! and *s* are big-endian-encoded values that are left-padded with zeros to
+//! the length of the curve's order. A P-256 or secp256k1 signature is 64 bytes
+//! long (two 32-byte components) and a P-384 signature is 96 bytes long (two
+//! 48-byte components). This is the form of ECDSA signature used in PKCS#11,
+//! IEEE P1363, JOSE ([RFC 7518 Section 3.4]), WebAuthn, and COSE.
+//!
+//! The public key is encoded and validated the same way as for the
+//! `ECDSA_*_ASN1` algorithms, and the `ECDSA_*_FIXED_SIGNING` algorithms are
+//! used with `ECDSAKeyPair` in the same way as the `ECDSA_*_ASN1_SIGNING`
+//! algorithms. `ecdsa_asn1_to_fixed()` and `ecdsa_fixed_to_asn1()` convert
+//! signatures between the two forms.
+//!
+//!
+//! ## `SM2_SM3_*` Details: SM2 Signatures
+//!
+//! These are SM2 signatures, as specified in [GB/T 32918.2-2016], using the
+//! SM2 curve and the SM3 digest algorithm. They exist for interoperability
+//! with systems that must use the Chinese national standards.
+//!
+//! The message is digested together with Z_A, the SM3 digest of the signer's
+//! user ID, the curve parameters, and the public key. The default user ID
+//! "1234567812345678" from GM/T 0009-2012 is always used. Note that some
+//! tools, including OpenSSL 3.0's `pkeyutl`, use an empty user ID unless
+//! another one is given.
+//!
+//! `SM2_SM3_ASN1` signatures are encoded like `ECDSA_*_ASN1` signatures and
+//! `SM2_SM3_FIXED` signatures are encoded like `ECDSA_*_FIXED` signatures.
+//! Public keys are encoded and validated the same way as for the ECDSA
+//! algorithms. The `SM2_SM3_*_SIGNING` algorithms are used with `SM2KeyPair`,
+//! which chooses the nonce the same way `ECDSAKeyPair` does.
 //!
 //!
 //! ## `RSA_PKCS1_*` Details: RSA PKCS#1 1.5 Signatures
@@ -65,6 +118,16 @@
 //! bits, inclusive.
 //!
 //!
+//! ## `RSA_PKCS1_*_E64` Details: RSA PKCS#1 1.5 Signatures with Large Exponents
+//!
+//! These are the same as the `RSA_PKCS1_*` algorithms, except the public
+//! exponent must be an odd integer of 2-64 bits, inclusive. They exist only
+//! for interoperability with legacy devices, such as some smartcards, that use
+//! unusually large public exponents. Because the cost of verification grows
+//! with the size of the exponent, the `RSA_PKCS1_*` algorithms should be used
+//! instead whenever possible.
+//!
+//!
 //! ## `RSA_PSS_*` Details: RSA PSS Signatures
 //!
 //! The signature is an RSASSA-PSS signature as described in
@@ -87,6 +150,14 @@
 //! Additionally, the entire salt is randomly generated separately for each
 //! signature using the secure random number generator passed to `sign()`.
 //!
+//! ## `RSA_PSS_*_MGF1_SHA1` Details: RSA PSS Signatures with MGF1-SHA1
+//!
+//! These are the same as the `RSA_PSS_*` algorithms, except that SHA-1 is
+//! used as the MGF1 digest algorithm, independently of the message digest
+//! algorithm. The salt is still the same length as the message digest. Some
+//! deployed software uses these combinations, e.g. SHA-256 with MGF1-SHA1,
+//! but new protocols should use the `RSA_PSS_*` algorithms instead.
+//!
 //!
 //! [SEC 1: Elliptic Curve Cryptography, Version 2.0]:
 //!     http://www.secg.org/sec1-v2.pdf
@@ -94,6 +165,8 @@
 //!     http://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-56Ar2.pdf
 //! [Suite B implementer's guide to FIPS 186-3]:
 //!     https://github.com/briansmith/ring/blob/master/doc/ecdsa.pdf
+//! [GB/T 32918.2-2016]:
+//!     https://tools.ietf.org/html/draft-shen-sm2-ecdsa-02
 //! [RFC 3279 Section 2.2.3]:
 //!     https://tools.ietf.org/html/rfc3279#section-2.2.3
 //! [RFC 3447 Section 8.2]:
@@ -102,6 +175,10 @@
 //!     https://tools.ietf.org/html/rfc3447#section-8.1
 //! [RFC 3447 Appendix-A.1.1]:
 //!     https://tools.ietf.org/html/rfc3447#appendix-A.1.1
+//! [RFC 6979]:
+//!     https://tools.ietf.org/html/rfc6979
+//! [RFC 7518 Section 3.4]:
+//!     https://tools.ietf.org/html/rfc7518#section-3.4
 //!
 //!
 //! # Examples
@@ -156,10 +233,42 @@
 //! # fn main() { sign_and_verify_ed25519().unwrap() }
 //! ```
 //!
+//! ## Signing and verifying with ECDSA
+//!
+//! ```
+//! extern crate ring;
+//!
+//! use ring::{rand, signature};
+//!
+//! # fn sign_and_verify_ecdsa() -> Result<(), ring::error::Unspecified> {
+//! // Generate a key pair.
+//! let rng = rand::SystemRandom::new();
+//! let key_pair = try!(signature::ECDSAKeyPair::generate(
+//!     &signature::ECDSA_P256_SHA256_ASN1_SIGNING, &rng));
+//!
+//! // Sign the message "hello, world". Each signature uses a new random
+//! // nonce.
+//! const MESSAGE: &'static [u8] = b"hello, world";
+//! let sig = try!(key_pair.sign(MESSAGE, &rng));
+//!
+//! // Verify the signature using the matching verification algorithm. An
+//! // `UnparsedPublicKey` can be kept around to verify many signatures.
+//! let peer_public_key =
+//!     signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1,
+//!                                       key_pair.public_key_bytes());
+//! try!(peer_public_key.verify(MESSAGE, sig.as_slice()));
+//! # Ok(())
+//! # }
+//!
+//! # fn main() { sign_and_verify_ecdsa().unwrap() }
+//! ```
+//!
 //! ## Signing and verifying with RSA (PKCS#1 1.5 padding)
 //!
 //! RSA signing (but not verification) requires the `rsa_signing` feature to
-//! be enabled.
+//! be enabled. Signing with SHA-1 (`RSA_PKCS1_SHA1`), which should only be
+//! done for legacy protocols that require it, additionally requires the
+//! `rsa_pkcs1_sha1_signing` feature.
 //!
 //! ```
 //! extern crate ring;
@@ -179,14 +288,13 @@
 //!    try!(signature::RSAKeyPair::from_der(key_bytes_der));
 //!
 //! // Create a signing state.
-//! let key_pair = std::sync::Arc::new(key_pair);
 //! let mut signing_state = try!(signature::RSASigningState::new(key_pair));
 //!
 //! // Sign the message "hello, world", using PKCS#1 v1.5 padding and the
 //! // SHA256 digest algorithm.
 //! const MESSAGE: &'static [u8] = b"hello, world";
 //! let rng = rand::SystemRandom::new();
-//! let mut signature = vec![0; signing_state.key_pair().public_modulus_len()];
+//! let mut signature = vec![0; signing_state.public_modulus_len()];
 //! try!(signing_state.sign(&signature::RSA_PKCS1_SHA256, &rng, MESSAGE,
 //!                         &mut signature));
 //!
@@ -210,7 +318,7 @@
 //! ```
 
 
-use {error, init, private};
+use {error, init, private, rand};
 use untrusted;
 
 pub use ec::suite_b::ecdsa::{
@@ -221,28 +329,78 @@ pub use ec::suite_b::ecdsa::{
 
     ECDSA_P384_SHA256_ASN1,
     ECDSA_P384_SHA384_ASN1,
+
+    ECDSA_SECP256K1_SHA256_ASN1,
+
+    ECDSA_P256_SHA256_FIXED,
+    ECDSA_P384_SHA384_FIXED,
+    ECDSA_SECP256K1_SHA256_FIXED,
+
+    ECDSAVerificationKey,
+
+    ECDSAKeyPair,
+    ECDSASigningAlgorithm,
+
+    ECDSA_P256_SHA256_ASN1_SIGNING,
+    ECDSA_P384_SHA384_ASN1_SIGNING,
+    ECDSA_SECP256K1_SHA256_ASN1_SIGNING,
+
+    ECDSA_P256_SHA256_FIXED_SIGNING,
+    ECDSA_P384_SHA384_FIXED_SIGNING,
+    ECDSA_SECP256K1_SHA256_FIXED_SIGNING,
+
+    ECDSAPublicKeyBytes,
+
+    ecdsa_asn1_to_fixed,
+    ecdsa_fixed_to_asn1,
+    ecdsa_public_key_from_raw,
+    ecdsa_public_key_to_raw,
+    ecdsa_recover_public_key,
+    ecdsa_validate_public_key,
+};
+
+pub use ec::suite_b::sm2::{
+    SM2Parameters,
+
+    SM2_SM3_ASN1,
+    SM2_SM3_FIXED,
+
+    SM2KeyPair,
+    SM2SigningAlgorithm,
+
+    SM2_SM3_ASN1_SIGNING,
+    SM2_SM3_FIXED_SIGNING,
 };
 
 pub use ec::eddsa::{
     EdDSAParameters,
 
     ED25519,
+    ED25519PH,
+    ED448,
+
+    verify_batch,
 
     Ed25519KeyPair,
-    Ed25519KeyPairBytes
+    Ed25519KeyPairBytes,
+    Ed448KeyPair,
 };
 
 #[cfg(all(feature = "rsa_signing", feature = "use_heap"))]
-pub use rsa::signing::{RSAKeyPair, RSASigningState};
+pub use rsa::signing::{
+    RSAKeyPair,
+    RSAPrivateKeyOperation,
+    RSASigner,
+    RSASigningContext,
+    RSASigningState,
+};
 
 #[cfg(all(feature = "rsa_signing", feature = "use_heap"))]
 pub use rsa::{
     RSAEncoding,
 
-    // `RSA_PKCS1_SHA1` is intentionally not exposed. At a minimum, we'd need
-    // to create test vectors for signing with it, which we don't currently
-    // have. But, it's a bad idea to use SHA-1 anyway, so perhaps we just won't
-    // ever expose it.
+    // `RSA_PKCS1_SHA1` is only exposed when the `rsa_pkcs1_sha1_signing`
+    // feature is enabled; see below.
     RSA_PKCS1_SHA256,
     RSA_PKCS1_SHA384,
     RSA_PKCS1_SHA512,
@@ -250,8 +408,17 @@ pub use rsa::{
     RSA_PSS_SHA256,
     RSA_PSS_SHA384,
     RSA_PSS_SHA512,
+
+    RSA_PSS_SHA256_MGF1_SHA1,
+    RSA_PSS_SHA384_MGF1_SHA1,
+    RSA_PSS_SHA512_MGF1_SHA1,
 };
 
+// It's a bad idea to use SHA-1, so this is only exposed for legacy protocols
+// that require it.
+#[cfg(all(feature = "rsa_pkcs1_sha1_signing", feature = "use_heap"))]
+pub use rsa::RSA_PKCS1_SHA1;
+
 #[cfg(feature = "use_heap")]
 pub use rsa::RSAParameters;
 
@@ -264,22 +431,34 @@ pub use rsa::verification::{
 
     RSA_PKCS1_3072_8192_SHA384,
 
+    RSA_PKCS1_2048_8192_SHA1_E64,
+    RSA_PKCS1_2048_8192_SHA256_E64,
+    RSA_PKCS1_2048_8192_SHA384_E64,
+    RSA_PKCS1_2048_8192_SHA512_E64,
+
     RSA_PSS_2048_8192_SHA256,
     RSA_PSS_2048_8192_SHA384,
     RSA_PSS_2048_8192_SHA512,
+
+    RSA_PSS_2048_8192_SHA256_MGF1_SHA1,
+    RSA_PSS_2048_8192_SHA384_MGF1_SHA1,
+    RSA_PSS_2048_8192_SHA512_MGF1_SHA1,
 };
 
 /// Lower-level verification primitives. Usage of `ring::signature::verify()`
 /// is preferred when the public key and signature are encoded in standard
 /// formats, as it also handles the parsing.
-#[cfg(feature = "use_heap")]
 pub mod primitive {
+    pub use ec::eddsa::{verify_ed25519ctx, verify_ed25519ph_digest};
+
+    #[cfg(feature = "use_heap")]
     pub use rsa::verification::verify_rsa;
 }
 
 /// A public key signature returned from a signing operation.
 pub struct Signature {
-    value: [u8; 64],
+    value: [u8; MAX_LEN],
+    len: usize,
 }
 
 impl<'a> Signature {
@@ -288,11 +467,88 @@ impl<'a> Signature {
     // public use.
     #[doc(hidden)]
     pub fn new(signature_bytes: [u8; 64]) -> Signature {
-        Signature { value: signature_bytes }
+        Signature::new_using(|value| {
+            value[..64].copy_from_slice(&signature_bytes);
+            64
+        })
+    }
+
+    // Initialize a variable-length signature by having `fill` write the
+    // encoded signature at the start of `value` and return its length. XXX:
+    // This is public so that other *ring* submodules can use it, but it isn't
+    // intended for public use.
+    #[doc(hidden)]
+    pub fn new_using<F>(fill: F) -> Signature
+                        where F: FnOnce(&mut [u8; MAX_LEN]) -> usize {
+        let mut r = Signature {
+            value: [0; MAX_LEN],
+            len: 0,
+        };
+        r.len = fill(&mut r.value);
+        r
     }
 
     /// Returns a reference to the signature's encoded value.
-    pub fn as_slice(&'a self) -> &'a [u8] { &self.value[..] }
+    pub fn as_slice(&'a self) -> &'a [u8] { &self.value[..self.len] }
+
+    // Copies the encoded value to the start of `out` and returns its length,
+    // as `Signer::sign()` does. XXX: This is public so that other *ring*
+    // submodules can use it, but it isn't intended for public use.
+    #[doc(hidden)]
+    pub fn write_to(&self, out: &mut [u8])
+                    -> Result<usize, error::Unspecified> {
+        if out.len() < self.len {
+            return Err(error::Unspecified);
+        }
+        out[..self.len].copy_from_slice(self.as_slice());
+        Ok(self.len)
+    }
+}
+
+// The longest signature is an Ed448 signature, which is 114 bytes. The next
+// longest is an ASN.1 DER-encoded ECDSA signature using P-384: a `SEQUENCE` of
+// two `INTEGER`s, each of which may need a leading zero byte, which is at most
+// 104 bytes.
+#[doc(hidden)]
+pub const MAX_LEN: usize = 2 * 57;
+
+/// A key pair for signing: a private key and its public key.
+///
+/// This allows code that doesn't care about the kind of key, such as code
+/// that issues certificates or that keeps a registry of keys, to handle RSA,
+/// ECDSA, SM2, and Ed25519 key pairs uniformly.
+pub trait KeyPair {
+    /// The type of the public key. This is `[u8]` for *ring*'s key pairs.
+    type PublicKey: AsRef<[u8]> + ?Sized;
+
+    /// The public key, encoded in the form accepted by the verification
+    /// algorithm(s) for the key pair's signature algorithm.
+    fn public_key(&self) -> &Self::PublicKey;
+}
+
+/// A key that can sign messages, independent of its algorithm.
+///
+/// Unlike `KeyPair`, this trait can be used as a trait object, so
+/// applications can keep keys of different kinds, e.g. in a
+/// `Vec<Box<Signer>>`, without wrapping them in an enum. It is implemented
+/// by `ECDSAKeyPair`, `SM2KeyPair`, `Ed25519KeyPair`, `Ed448KeyPair`, and
+/// `RSASigner`. Use `Verifier` to verify the signatures.
+pub trait Signer {
+    /// The public key, encoded as it is for `KeyPair::public_key()`.
+    fn public_key_bytes(&self) -> &[u8];
+
+    /// An upper bound on the length of the signatures produced by `sign()`.
+    fn max_signature_len(&self) -> usize;
+
+    /// Signs the message `msg`, writing the signature to the start of
+    /// `signature` and returning its length. This fails if `signature` is
+    /// shorter than the signature.
+    ///
+    /// The signature is the same as the one the key type's own `sign()`
+    /// would produce. `rng` is used as it is there; it is ignored by the
+    /// EdDSA key pairs, whose signatures are deterministic.
+    fn sign(&self, msg: &[u8], rng: &rand::SecureRandom, signature: &mut [u8])
+            -> Result<usize, error::Unspecified>;
 }
 
 /// A signature verification algorithm.
@@ -336,9 +592,190 @@ pub fn verify(alg: &VerificationAlgorithm, public_key: untrusted::Input,
     alg.verify(public_key, msg, signature)
 }
 
+/// An unparsed, possibly malformed, public key for signature verification,
+/// bundled with the algorithm it is to be used with.
+///
+/// Unlike `verify()`, this doesn't require the key to be wrapped in an
+/// `untrusted::Input` for every verification, so it is convenient to store in
+/// long-lived structures. `B` is typically `&[u8]` or `Vec<u8>`. The key isn't
+/// parsed or validated until `verify()` is called.
+#[derive(Clone, Copy)]
+pub struct UnparsedPublicKey<B: AsRef<[u8]>> {
+    algorithm: &'static VerificationAlgorithm,
+    bytes: B,
+}
+
+impl<B: AsRef<[u8]>> UnparsedPublicKey<B> {
+    /// Constructs a new `UnparsedPublicKey` for the public key `bytes`, to be
+    /// used with the verification algorithm `algorithm`.
+    #[inline]
+    pub fn new(algorithm: &'static VerificationAlgorithm, bytes: B) -> Self {
+        UnparsedPublicKey {
+            algorithm: algorithm,
+            bytes: bytes,
+        }
+    }
+
+    /// The verification algorithm the key is to be used with.
+    #[inline]
+    pub fn algorithm(&self) -> &'static VerificationAlgorithm {
+        self.algorithm
+    }
+
+    /// Verify the signature `signature` of message `message` with this public
+    /// key, as `verify()` does.
+    pub fn verify(&self, message: &[u8], signature: &[u8])
+                  -> Result<(), error::Unspecified> {
+        verify(self.algorithm, untrusted::Input::from(self.bytes.as_ref()),
+               untrusted::Input::from(message),
+               untrusted::Input::from(signature))
+    }
+}
+
+impl<B: AsRef<[u8]>> AsRef<[u8]> for UnparsedPublicKey<B> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] { self.bytes.as_ref() }
+}
+
+/// A public key that can verify signatures, independent of its algorithm.
+///
+/// This is the counterpart of `Signer`: it can be used as a trait object,
+/// e.g. in a `Vec<Box<Verifier>>`. It is implemented by `UnparsedPublicKey`
+/// for every verification algorithm.
+pub trait Verifier {
+    /// Verify the signature `signature` of message `msg` with this public
+    /// key.
+    fn verify(&self, msg: &[u8], signature: &[u8])
+              -> Result<(), error::Unspecified>;
+}
+
+impl<B: AsRef<[u8]>> Verifier for UnparsedPublicKey<B> {
+    fn verify(&self, msg: &[u8], signature: &[u8])
+              -> Result<(), error::Unspecified> {
+        UnparsedPublicKey::verify(self, msg, signature)
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
     // ECDSA tests are in crypto/ec/ecdsa.rs.
     // EdDSA tests are in crypto/ec/eddsa.rs.
+
+    use {rand, signature};
+    use std;
+    use untrusted;
+
+    // Signs with `key_pair` and verifies the signature using only the public
+    // key exposed through `signature::KeyPair`.
+    fn sign_and_verify<K, F>(key_pair: &K,
+                             alg: &signature::VerificationAlgorithm, sign: F)
+                             where K: signature::KeyPair,
+                                   F: Fn(&K, &[u8]) -> signature::Signature {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let sig = sign(key_pair, MESSAGE);
+        let public_key = key_pair.public_key().as_ref();
+        assert!(signature::verify(alg, untrusted::Input::from(public_key),
+                                  untrusted::Input::from(MESSAGE),
+                                  untrusted::Input::from(sig.as_slice()))
+                    .is_ok());
+    }
+
+    #[test]
+    fn test_key_pair_public_key() {
+        let rng = rand::SystemRandom::new();
+
+        let key_pair = signature::Ed25519KeyPair::generate(&rng).unwrap();
+        sign_and_verify(&key_pair, &signature::ED25519,
+                        |key_pair, msg| key_pair.sign(msg));
+
+        let key_pair = signature::ECDSAKeyPair::generate(
+            &signature::ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        sign_and_verify(&key_pair, &signature::ECDSA_P256_SHA256_ASN1,
+                        |key_pair, msg| key_pair.sign(msg, &rng).unwrap());
+    }
+
+    #[test]
+    fn test_signer_and_verifier_trait_objects() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+
+        let signers: std::vec::Vec<(std::boxed::Box<signature::Signer>,
+                                    &signature::VerificationAlgorithm)> =
+            vec![
+                (std::boxed::Box::new(
+                    signature::Ed25519KeyPair::generate(&rng).unwrap()),
+                 &signature::ED25519),
+                (std::boxed::Box::new(
+                    signature::Ed448KeyPair::generate(&rng).unwrap()),
+                 &signature::ED448),
+                (std::boxed::Box::new(signature::ECDSAKeyPair::generate(
+                    &signature::ECDSA_P384_SHA384_ASN1_SIGNING, &rng)
+                    .unwrap()),
+                 &signature::ECDSA_P384_SHA384_ASN1),
+                (std::boxed::Box::new(signature::SM2KeyPair::generate(
+                    &signature::SM2_SM3_FIXED_SIGNING, &rng).unwrap()),
+                 &signature::SM2_SM3_FIXED),
+            ];
+
+        for &(ref signer, alg) in &signers {
+            let verifier: std::boxed::Box<signature::Verifier> =
+                std::boxed::Box::new(signature::UnparsedPublicKey::new(
+                    alg, std::vec::Vec::from(signer.public_key_bytes())));
+
+            let mut sig = vec![0; signer.max_signature_len()];
+            let sig_len = signer.sign(MESSAGE, &rng, &mut sig).unwrap();
+            let sig = &sig[..sig_len];
+            assert!(verifier.verify(MESSAGE, sig).is_ok());
+            assert!(verifier.verify(b"hello", sig).is_err());
+
+            // The output buffer must be long enough for the signature.
+            let mut short = [0u8; 1];
+            assert!(signer.sign(MESSAGE, &rng, &mut short).is_err());
+        }
+    }
+
+    #[test]
+    fn test_unparsed_public_key() {
+        const MESSAGE: &'static [u8] = b"hello, world";
+        let rng = rand::SystemRandom::new();
+        let key_pair = signature::Ed25519KeyPair::generate(&rng).unwrap();
+        let sig = key_pair.sign(MESSAGE);
+        let sig = sig.as_slice();
+
+        // Borrowed and owned keys.
+        let public_key = key_pair.public_key_bytes();
+        let borrowed =
+            signature::UnparsedPublicKey::new(&signature::ED25519, public_key);
+        assert!(borrowed.verify(MESSAGE, sig).is_ok());
+        assert!(borrowed.verify(b"hello", sig).is_err());
+        assert!(borrowed.verify(MESSAGE, &sig[1..]).is_err());
+        let owned = signature::UnparsedPublicKey::new(&signature::ED25519,
+                                                      public_key.to_vec());
+        assert!(owned.clone().verify(MESSAGE, sig).is_ok());
+        assert_eq!(owned.as_ref(), public_key);
+
+        // The wrong algorithm or a malformed key.
+        let wrong_alg =
+            signature::UnparsedPublicKey::new(
+                &signature::ECDSA_P256_SHA256_FIXED, public_key);
+        assert!(wrong_alg.verify(MESSAGE, sig).is_err());
+        let malformed = signature::UnparsedPublicKey::new(
+            &signature::ED25519, &public_key[1..]);
+        assert!(malformed.verify(MESSAGE, sig).is_err());
+    }
+
+    #[cfg(all(feature = "rsa_signing", feature = "use_heap"))]
+    #[test]
+    fn test_rsa_key_pair_public_key() {
+        use signature::KeyPair;
+
+        const PRIVATE_KEY_DER: &'static [u8] =
+            include_bytes!("rsa/signature_rsa_example_private_key.der");
+        const PUBLIC_KEY_DER: &'static [u8] =
+            include_bytes!("rsa/signature_rsa_example_public_key.der");
+        let key_pair = signature::RSAKeyPair::from_der(
+            untrusted::Input::from(PRIVATE_KEY_DER)).unwrap();
+        assert_eq!(key_pair.public_key(), PUBLIC_KEY_DER);
+    }
 }
@@ -0,0 +1,330 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! SipHash-2-4, a keyed pseudorandom function optimized for short inputs,
+//! as specified in [SipHash: a fast short-input PRF].
+//!
+//! SipHash is intended for protecting hash tables against hash-flooding
+//! attacks and for authenticating short messages, such as tokens, where a
+//! general-purpose MAC like `hmac` is unnecessarily slow. `Hasher`
+//! implements `core::hash::Hasher` for use with hash tables. Use `hash` or
+//! `hash_128` for everything else.
+//!
+//! The 128-bit output variant is the one described in the reference
+//! implementation. The 64-bit output is too short for a MAC tag when an
+//! attacker can make many forgery attempts; prefer `hash_128` for message
+//! authentication.
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::{rand, siphash};
+//! use ring::rand::SecureRandom;
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! let rng = rand::SystemRandom::new();
+//! let mut key_value = [0u8; siphash::KEY_LEN];
+//! try!(rng.fill(&mut key_value));
+//! let key = siphash::Key::new(&key_value);
+//!
+//! let tag = siphash::hash_128(&key, b"session=1234");
+//! try!(siphash::verify(&key, b"session=1234", &tag));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [SipHash: a fast short-input PRF]: https://131002.net/siphash/siphash.pdf
+
+use {core, constant_time, error, polyfill};
+
+/// The length of a SipHash key, in bytes.
+pub const KEY_LEN: usize = 128 / 8;
+
+/// The length of the output of `hash`, when serialized, in bytes.
+pub const OUTPUT_LEN: usize = 64 / 8;
+
+/// The length of the output of `hash_128`, in bytes.
+pub const OUTPUT_128_LEN: usize = 128 / 8;
+
+const BLOCK_LEN: usize = 8;
+
+/// A SipHash key.
+#[derive(Clone)]
+pub struct Key {
+    k0: u64,
+    k1: u64,
+}
+
+impl Key {
+    /// Constructs a key from the 16 bytes of `key_bytes`.
+    pub fn new(key_bytes: &[u8; KEY_LEN]) -> Key {
+        Key {
+            k0: u64_from_le_u8(&key_bytes[..8]),
+            k1: u64_from_le_u8(&key_bytes[8..]),
+        }
+    }
+}
+
+/// A keyed `core::hash::Hasher` that computes 64-bit SipHash-2-4.
+///
+/// Unlike most `Hasher` implementations, the result is a function of the
+/// concatenation of the bytes written, so `write(b"ab")` is equivalent to
+/// `write(b"a")` followed by `write(b"b")`.
+#[derive(Clone)]
+pub struct Hasher {
+    state: State,
+}
+
+impl Hasher {
+    /// Constructs a new hasher using the given key.
+    pub fn new(key: &Key) -> Hasher {
+        Hasher { state: State::new(key, false) }
+    }
+}
+
+impl core::hash::Hasher for Hasher {
+    fn write(&mut self, bytes: &[u8]) { self.state.update(bytes); }
+
+    fn finish(&self) -> u64 { self.state.clone().finish_64() }
+}
+
+/// Calculates the 64-bit SipHash-2-4 of `data` using the key `key`.
+///
+/// The reference implementation outputs the little-endian encoding of the
+/// result.
+pub fn hash(key: &Key, data: &[u8]) -> u64 {
+    let mut state = State::new(key, false);
+    state.update(data);
+    state.finish_64()
+}
+
+/// Calculates the 128-bit SipHash-2-4 of `data` using the key `key`.
+pub fn hash_128(key: &Key, data: &[u8]) -> [u8; OUTPUT_128_LEN] {
+    let mut state = State::new(key, true);
+    state.update(data);
+    state.finish_128()
+}
+
+/// Calculates the SipHash-2-4 of `data` and checks in constant time that
+/// `tag` is equal to it.
+///
+/// A `tag` of `OUTPUT_128_LEN` bytes is compared against the output of
+/// `hash_128`; a `tag` of `OUTPUT_LEN` bytes is compared against the
+/// little-endian encoding of the output of `hash`. Fails if the tags differ
+/// or if `tag` has any other length.
+pub fn verify(key: &Key, data: &[u8], tag: &[u8])
+              -> Result<(), error::Unspecified> {
+    match tag.len() {
+        OUTPUT_128_LEN => {
+            constant_time::verify_slices_are_equal(&hash_128(key, data), tag)
+        },
+        OUTPUT_LEN => {
+            let expected = le_u8_from_u64(hash(key, data));
+            constant_time::verify_slices_are_equal(&expected, tag)
+        },
+        _ => Err(error::Unspecified),
+    }
+}
+
+#[derive(Clone)]
+struct State {
+    v: [u64; 4],
+
+    // The bytes not yet compressed, always fewer than `BLOCK_LEN`.
+    pending: [u8; BLOCK_LEN],
+    num_pending: usize,
+
+    // The total input length, modulo 2**64. Only the low byte is used.
+    len: u64,
+
+    is_128: bool,
+}
+
+impl State {
+    fn new(key: &Key, is_128: bool) -> State {
+        let mut v = [
+            key.k0 ^ 0x736f6d6570736575,
+            key.k1 ^ 0x646f72616e646f6d,
+            key.k0 ^ 0x6c7967656e657261,
+            key.k1 ^ 0x7465646279746573,
+        ];
+        if is_128 {
+            v[1] ^= 0xee;
+        }
+        State {
+            v: v,
+            pending: [0u8; BLOCK_LEN],
+            num_pending: 0,
+            len: 0,
+            is_128: is_128,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.len = self.len.wrapping_add(polyfill::u64_from_usize(data.len()));
+
+        if self.num_pending > 0 {
+            let to_copy = core::cmp::min(BLOCK_LEN - self.num_pending,
+                                         data.len());
+            self.pending[self.num_pending..][..to_copy]
+                .copy_from_slice(&data[..to_copy]);
+            self.num_pending += to_copy;
+            data = &data[to_copy..];
+            if self.num_pending < BLOCK_LEN {
+                return;
+            }
+            let m = u64_from_le_u8(&self.pending);
+            self.compress(m);
+            self.num_pending = 0;
+        }
+
+        let num_to_compress = data.len() - (data.len() % BLOCK_LEN);
+        let (to_compress, remainder) = data.split_at(num_to_compress);
+        for block in to_compress.chunks(BLOCK_LEN) {
+            self.compress(u64_from_le_u8(block));
+        }
+        self.pending[..remainder.len()].copy_from_slice(remainder);
+        self.num_pending = remainder.len();
+    }
+
+    fn compress(&mut self, m: u64) {
+        self.v[3] ^= m;
+        sip_round(&mut self.v);
+        sip_round(&mut self.v);
+        self.v[0] ^= m;
+    }
+
+    fn finalize(&mut self) -> u64 {
+        // The last block is the remaining bytes, zero-padded, with the low
+        // byte of the input length in its most significant byte.
+        let mut last = [0u8; BLOCK_LEN];
+        last[..self.num_pending]
+            .copy_from_slice(&self.pending[..self.num_pending]);
+        last[BLOCK_LEN - 1] = self.len as u8;
+        let m = u64_from_le_u8(&last);
+        self.compress(m);
+
+        self.v[2] ^= if self.is_128 { 0xee } else { 0xff };
+        for _ in 0..4 {
+            sip_round(&mut self.v);
+        }
+        self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3]
+    }
+
+    fn finish_64(mut self) -> u64 {
+        debug_assert!(!self.is_128);
+        self.finalize()
+    }
+
+    fn finish_128(mut self) -> [u8; OUTPUT_128_LEN] {
+        debug_assert!(self.is_128);
+        let first = self.finalize();
+        self.v[1] ^= 0xdd;
+        for _ in 0..4 {
+            sip_round(&mut self.v);
+        }
+        let second = self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3];
+
+        let mut out = [0u8; OUTPUT_128_LEN];
+        out[..8].copy_from_slice(&le_u8_from_u64(first));
+        out[8..].copy_from_slice(&le_u8_from_u64(second));
+        out
+    }
+}
+
+#[inline(always)]
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+fn u64_from_le_u8(bytes: &[u8]) -> u64 {
+    debug_assert_eq!(bytes.len(), 8);
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+fn le_u8_from_u64(x: u64) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    for (i, out) in out.iter_mut().enumerate() {
+        *out = (x >> (8 * i)) as u8;
+    }
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::hash::Hasher as CoreHasher;
+    use test;
+
+    #[test]
+    fn test_siphash() {
+        test::from_file("src/siphash_tests.txt", |section, test_case| {
+            let key_value = test_case.consume_bytes("Key");
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let key = Key::new(slice_as_array_ref!(&key_value, KEY_LEN)
+                                   .unwrap());
+            match section {
+                "SipHash-2-4" => {
+                    let actual = hash(&key, &input);
+                    assert_eq!(&le_u8_from_u64(actual)[..], &expected[..]);
+
+                    let mut hasher = Hasher::new(&key);
+                    for chunk in input.chunks(3) {
+                        hasher.write(chunk);
+                    }
+                    assert_eq!(hasher.finish(), actual);
+                },
+                "SipHash-2-4-128" => {
+                    assert_eq!(&hash_128(&key, &input)[..], &expected[..]);
+                },
+                _ => unreachable!(),
+            }
+
+            assert!(verify(&key, &input, &expected).is_ok());
+            let mut wrong = expected.clone();
+            wrong[0] ^= 1;
+            assert!(verify(&key, &input, &wrong).is_err());
+            assert!(verify(&key, &input, &expected[1..]).is_err());
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_siphash_hasher_finish_is_idempotent() {
+        let key = Key::new(&[0x42; KEY_LEN]);
+        let mut hasher = Hasher::new(&key);
+        hasher.write(b"hello");
+        assert_eq!(hasher.finish(), hasher.finish());
+        hasher.write(b", world");
+        assert_eq!(hasher.finish(), hash(&key, b"hello, world"));
+    }
+}
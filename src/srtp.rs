@@ -0,0 +1,217 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The SRTP key derivation function of [RFC 3711 Section 4.3], which derives
+//! the SRTP and SRTCP session keys and salts from a master key and master
+//! salt using AES in counter mode.
+//!
+//! AES-128 master keys are specified in RFC 3711; AES-192 and AES-256 master
+//! keys are specified in [RFC 6188].
+//!
+//! # Examples
+//!
+//! ```
+//! use ring::srtp;
+//!
+//! # fn main_with_result() -> Result<(), ring::error::Unspecified> {
+//! // In a real application, these come from e.g. DTLS-SRTP keying material.
+//! let master_key = [0u8; 16];
+//! let master_salt = [0u8; srtp::MASTER_SALT_LEN];
+//! let master = try!(srtp::MasterKey::new(&master_key, &master_salt));
+//!
+//! let mut session_key = [0u8; 16];
+//! try!(srtp::derive(&master, srtp::Label::RtpEncryption, 0, 0,
+//!                   &mut session_key));
+//! let mut session_salt = [0u8; 14];
+//! try!(srtp::derive(&master, srtp::Label::RtpSalt, 0, 0, &mut session_salt));
+//! # Ok(())
+//! # }
+//! # fn main() { main_with_result().unwrap() }
+//! ```
+//!
+//! [RFC 3711 Section 4.3]: https://tools.ietf.org/html/rfc3711#section-4.3
+//! [RFC 6188]: https://tools.ietf.org/html/rfc6188
+
+use {cipher, error, polyfill};
+
+/// The length of the master salt, in bytes.
+pub const MASTER_SALT_LEN: usize = 112 / 8;
+
+/// The largest allowed key derivation rate.
+pub const MAX_KEY_DERIVATION_RATE: u32 = 1 << 24;
+
+/// The kind of key or salt to derive, which determines the label of RFC 3711
+/// Sections 4.3.1 and 4.3.2.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Label {
+    /// The SRTP encryption key, k_e.
+    RtpEncryption = 0x00,
+
+    /// The SRTP authentication key, k_a.
+    RtpAuthentication = 0x01,
+
+    /// The SRTP salting key, k_s.
+    RtpSalt = 0x02,
+
+    /// The SRTCP encryption key.
+    RtcpEncryption = 0x03,
+
+    /// The SRTCP authentication key.
+    RtcpAuthentication = 0x04,
+
+    /// The SRTCP salting key.
+    RtcpSalt = 0x05,
+}
+
+/// An SRTP master key and master salt.
+pub struct MasterKey {
+    key: cipher::AesCtrKey,
+    salt: [u8; MASTER_SALT_LEN],
+}
+
+impl MasterKey {
+    /// Constructs a master key from the key `key_bytes`, which must be 16
+    /// bytes (AES-128), 24 bytes (AES-192), or 32 bytes (AES-256) long, and
+    /// the master salt `salt`.
+    pub fn new(key_bytes: &[u8], salt: &[u8; MASTER_SALT_LEN])
+               -> Result<MasterKey, error::Unspecified> {
+        Ok(MasterKey {
+            key: try!(cipher::AesCtrKey::new(key_bytes)),
+            salt: *salt,
+        })
+    }
+}
+
+/// Fills `out` with the session key or salt for `label`.
+///
+/// | Parameter             | RFC 3711 Section 4.3 Term
+/// |-----------------------|---------------------------------------
+/// | `master_key`          | k_m, k_s (master key, master salt)
+/// | `label`               | label
+/// | `key_derivation_rate` | key_derivation_rate
+/// | `index`               | index (the packet index or SRTCP index)
+/// | `out`                 | k_e, k_a, or k_s
+/// | `out.len()`           | n_e, n_a, or n_s, in bytes
+///
+/// A `key_derivation_rate` of zero, the default, means that the session keys
+/// are derived once, and then `index` is ignored.
+///
+/// Fails if `key_derivation_rate` isn't zero or a power of two no larger
+/// than `MAX_KEY_DERIVATION_RATE`, if `index` doesn't fit in 48 bits, or if
+/// `out` is longer than 2**16 AES blocks.
+pub fn derive(master_key: &MasterKey, label: Label, key_derivation_rate: u32,
+              index: u64, out: &mut [u8]) -> Result<(), error::Unspecified> {
+    if key_derivation_rate != 0 &&
+       (!key_derivation_rate.is_power_of_two() ||
+        key_derivation_rate > MAX_KEY_DERIVATION_RATE) {
+        return Err(error::Unspecified);
+    }
+    if index >= 1 << 48 {
+        return Err(error::Unspecified);
+    }
+    // The counter is the low 16 bits of the IV.
+    if polyfill::u64_from_usize(out.len()) >
+       (1 << 16) * polyfill::u64_from_usize(cipher::AES_BLOCK_LEN) {
+        return Err(error::Unspecified);
+    }
+
+    let r = if key_derivation_rate == 0 {
+        0
+    } else {
+        index / u64::from(key_derivation_rate)
+    };
+
+    // key_id = label || r, XORed into the low 56 bits of the master salt,
+    // and then shifted left by 16 bits.
+    let mut iv = [0u8; cipher::AES_BLOCK_LEN];
+    iv[..MASTER_SALT_LEN].copy_from_slice(&master_key.salt);
+    iv[7] ^= label as u8;
+    for (i, iv) in iv[8..MASTER_SALT_LEN].iter_mut().enumerate() {
+        *iv ^= (r >> (8 * (5 - i))) as u8;
+    }
+
+    polyfill::slice::fill(out, 0);
+    master_key.key.xor_in_place(&iv, out);
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test;
+
+    #[test]
+    fn test_srtp_kdf() {
+        test::from_file("src/srtp_tests.txt", |section, test_case| {
+            assert_eq!(section, "");
+            let key_bytes = test_case.consume_bytes("MasterKey");
+            let salt = test_case.consume_bytes("MasterSalt");
+            let kdr = test_case.consume_usize("KDR") as u32;
+            let index = test_case.consume_usize("Index") as u64;
+            let label = match test_case.consume_usize("Label") {
+                0 => Label::RtpEncryption,
+                1 => Label::RtpAuthentication,
+                2 => Label::RtpSalt,
+                3 => Label::RtcpEncryption,
+                4 => Label::RtcpAuthentication,
+                5 => Label::RtcpSalt,
+                _ => unreachable!(),
+            };
+            let expected = test_case.consume_bytes("Output");
+
+            let master_key =
+                try!(MasterKey::new(&key_bytes,
+                                    slice_as_array_ref!(&salt,
+                                                        MASTER_SALT_LEN)
+                                        .unwrap()));
+            let mut out = vec![0u8; expected.len()];
+            try!(derive(&master_key, label, kdr, index, &mut out));
+            assert_eq!(out, expected);
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_srtp_kdf_params() {
+        let master_key = MasterKey::new(&[0; 16], &[0; MASTER_SALT_LEN])
+            .unwrap();
+        let mut out = [0u8; 16];
+        let label = Label::RtpEncryption;
+
+        assert!(derive(&master_key, label, 0, 0, &mut out).is_ok());
+        assert!(derive(&master_key, label, 1, 0, &mut out).is_ok());
+        assert!(derive(&master_key, label, 3, 0, &mut out).is_err());
+        assert!(derive(&master_key, label, MAX_KEY_DERIVATION_RATE, 0,
+                       &mut out).is_ok());
+        assert!(derive(&master_key, label, MAX_KEY_DERIVATION_RATE * 2, 0,
+                       &mut out).is_err());
+        assert!(derive(&master_key, label, 0, (1 << 48) - 1, &mut out)
+                    .is_ok());
+        assert!(derive(&master_key, label, 0, 1 << 48, &mut out).is_err());
+
+        // With a key derivation rate, the keys change every
+        // `key_derivation_rate` packets.
+        let mut a = [0u8; 16];
+        let mut b = [0u8; 16];
+        derive(&master_key, label, 4, 4, &mut a).unwrap();
+        derive(&master_key, label, 4, 7, &mut b).unwrap();
+        assert_eq!(a, b);
+        derive(&master_key, label, 4, 8, &mut b).unwrap();
+        assert!(a != b);
+
+        assert!(MasterKey::new(&[0; 20], &[0; MASTER_SALT_LEN]).is_err());
+    }
+}
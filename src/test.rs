@@ -148,6 +148,8 @@ impl TestCase {
             "SHA256" => Some(&digest::SHA256),
             "SHA384" => Some(&digest::SHA384),
             "SHA512" => Some(&digest::SHA512),
+            "SHA512_224" => Some(&digest::SHA512_224),
+            "SM3" => Some(&digest::SM3),
             _ => panic!("Unsupported digest algorithm: {}", name),
         }
     }
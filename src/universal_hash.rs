@@ -0,0 +1,252 @@
+// Copyright 2017 Brian Smith.
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHORS DISCLAIM ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY
+// SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN ACTION
+// OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF OR IN
+// CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+//! The GHASH and POLYVAL universal hash functions.
+//!
+//! These are the polynomial hash functions that AES-GCM and AES-GCM-SIV use
+//! internally, for protocols and PRF constructions that specify their own use
+//! of them. Both use the same carry-less multiplication implementations as
+//! AES-GCM.
+//!
+//! # Security
+//!
+//! **GHASH and POLYVAL are not MACs.** For a fixed key, the output is a
+//! linear function of the input, so anybody who learns the outputs for a few
+//! inputs under the same key can usually solve for the key and then compute
+//! the output for any other input. The only guarantee they give is that, for a
+//! secret key chosen uniformly at random, two different inputs of at most `n`
+//! blocks have outputs that differ by any given value with probability at most
+//! `n / 2**128`. AES-GCM and AES-GCM-SIV turn that into a MAC by encrypting
+//! the output; using one of these functions safely requires a construction
+//! like that.
+//!
+//! The last partial block of each input is padded with zeros, so inputs that
+//! differ only in trailing zeros have the same output unless the construction
+//! also hashes their lengths, as GCM does.
+//!
+//! [RFC 8452 Section 3] describes POLYVAL, and its Appendix A describes how
+//! POLYVAL relates to GHASH, which is described in
+//! [NIST SP 800-38D Section 6.4].
+//!
+//! [RFC 8452 Section 3]: https://tools.ietf.org/html/rfc8452#section-3
+//! [NIST SP 800-38D Section 6.4]:
+//!     http://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf
+
+use {c, init};
+
+/// A universal hash function.
+pub struct Algorithm {
+    id: AlgorithmID,
+}
+
+#[derive(PartialEq)]
+enum AlgorithmID {
+    GHASH,
+    POLYVAL,
+}
+
+/// GHASH, as used by AES-GCM.
+pub static GHASH: Algorithm = Algorithm { id: AlgorithmID::GHASH };
+
+/// POLYVAL, as used by AES-GCM-SIV.
+pub static POLYVAL: Algorithm = Algorithm { id: AlgorithmID::POLYVAL };
+
+/// The length of a key, in bytes.
+pub const KEY_LEN: usize = 16;
+
+/// The length of the blocks that the input is processed in, in bytes.
+pub const BLOCK_LEN: usize = 16;
+
+/// The length of the output, in bytes.
+pub const OUTPUT_LEN: usize = 16;
+
+type Block = [u8; BLOCK_LEN];
+
+/// A key for GHASH or POLYVAL, with the tables of multiples of it that the
+/// multiplication uses.
+pub struct Key {
+    algorithm: &'static Algorithm,
+    htable: [u8; HTABLE_LEN],
+}
+
+impl Key {
+    /// Constructs a new key for `algorithm`.
+    pub fn new(algorithm: &'static Algorithm, key_bytes: &[u8; KEY_LEN])
+               -> Key {
+        init::init_once();
+
+        // POLYVAL(H, X_1, ..., X_n) =
+        //     ByteReverse(GHASH(mulX_GHASH(ByteReverse(H)),
+        //                       ByteReverse(X_1), ..., ByteReverse(X_n))).
+        let h = match algorithm.id {
+            AlgorithmID::GHASH => *key_bytes,
+            AlgorithmID::POLYVAL => mul_x_ghash(&byte_reversed(key_bytes)),
+        };
+        let mut key = Key {
+            algorithm: algorithm,
+            htable: [0u8; HTABLE_LEN],
+        };
+        unsafe {
+            GFp_gcm128_init_serialized_from_h(key.htable.as_mut_ptr(), &h);
+        }
+        key
+    }
+
+    /// The algorithm for the key.
+    #[inline(always)]
+    pub fn algorithm(&self) -> &'static Algorithm { self.algorithm }
+}
+
+/// A context for hashing an input with a `Key`.
+pub struct Context<'a> {
+    key: &'a Key,
+    xi: Block,
+}
+
+impl<'a> Context<'a> {
+    /// Constructs a new context for hashing with `key`.
+    pub fn new(key: &'a Key) -> Context<'a> {
+        Context {
+            key: key,
+            xi: [0u8; BLOCK_LEN],
+        }
+    }
+
+    /// Processes `input`, padding its last partial block, if any, with zeros.
+    ///
+    /// Because of the padding, calling `update_padded` with `a` and then with
+    /// `b` is equivalent to calling it once with their concatenation only if
+    /// the length of `a` is a multiple of `BLOCK_LEN`.
+    pub fn update_padded(&mut self, input: &[u8]) {
+        match self.key.algorithm.id {
+            AlgorithmID::GHASH => {
+                let whole_len = input.len() - (input.len() % BLOCK_LEN);
+                let (whole, partial) = input.split_at(whole_len);
+                self.ghash(whole);
+                if !partial.is_empty() {
+                    let mut block = [0u8; BLOCK_LEN];
+                    block[..partial.len()].copy_from_slice(partial);
+                    self.ghash(&block);
+                }
+            },
+            AlgorithmID::POLYVAL => {
+                let mut buf = [0u8; POLYVAL_BUF_LEN];
+                for chunk in input.chunks(POLYVAL_BUF_LEN) {
+                    let mut buf_len = 0;
+                    for block in chunk.chunks(BLOCK_LEN) {
+                        let reversed = &mut buf[buf_len..(buf_len + BLOCK_LEN)];
+                        for (r, b) in reversed.iter_mut().rev().zip(block) {
+                            *r = *b;
+                        }
+                        for r in &mut reversed[..(BLOCK_LEN - block.len())] {
+                            *r = 0;
+                        }
+                        buf_len += BLOCK_LEN;
+                    }
+                    self.ghash(&buf[..buf_len]);
+                }
+            },
+        }
+    }
+
+    /// Returns the output for the input processed so far.
+    pub fn finish(self) -> [u8; OUTPUT_LEN] {
+        match self.key.algorithm.id {
+            AlgorithmID::GHASH => self.xi,
+            AlgorithmID::POLYVAL => byte_reversed(&self.xi),
+        }
+    }
+
+    fn ghash(&mut self, blocks: &[u8]) {
+        debug_assert_eq!(blocks.len() % BLOCK_LEN, 0);
+        unsafe {
+            GFp_gcm128_ghash(self.key.htable.as_ptr(), &mut self.xi,
+                             blocks.as_ptr(), blocks.len());
+        }
+    }
+}
+
+// Keep in sync with `GCM128_SERIALIZED_LEN` in crypto/modes/internal.h.
+const HTABLE_LEN: usize = 16 * 16;
+
+// The number of bytes of POLYVAL input that are byte-reversed at a time
+// before being passed to GHASH.
+const POLYVAL_BUF_LEN: usize = 16 * BLOCK_LEN;
+
+fn byte_reversed(block: &Block) -> Block {
+    let mut reversed = *block;
+    reversed.reverse();
+    reversed
+}
+
+// Multiplies `h` by x in GHASH's field, in which the most significant bit of
+// the first byte is the coefficient of x**0. `h` is secret, so the reduction
+// is done without branching on it.
+fn mul_x_ghash(h: &Block) -> Block {
+    let mut out = [0u8; BLOCK_LEN];
+    let mut carry = 0;
+    for (out, h) in out.iter_mut().zip(h.iter()) {
+        *out = (h >> 1) | (carry << 7);
+        carry = h & 1;
+    }
+    out[0] ^= 0xe1 & 0u8.wrapping_sub(carry);
+    out
+}
+
+extern {
+    fn GFp_gcm128_init_serialized_from_h(htable: *mut u8, h: &Block);
+    fn GFp_gcm128_ghash(htable: *const u8, xi: &mut Block, input: *const u8,
+                        len: c::size_t);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use test;
+    use super::*;
+
+    #[test]
+    pub fn test_universal_hash() {
+        test::from_file("src/universal_hash_tests.txt",
+                        |section, test_case| {
+            let algorithm = match section {
+                "GHASH" => &GHASH,
+                "POLYVAL" => &POLYVAL,
+                _ => unreachable!(),
+            };
+            let key = test_case.consume_bytes("Key");
+            let key = try!(slice_as_array_ref!(&key, KEY_LEN));
+            let input = test_case.consume_bytes("Input");
+            let expected = test_case.consume_bytes("Output");
+
+            let key = Key::new(algorithm, key);
+            let mut ctx = Context::new(&key);
+            ctx.update_padded(&input);
+            assert_eq!(&ctx.finish()[..], &expected[..]);
+
+            // Splitting the input at every block boundary gives the same
+            // result.
+            let mut split = 0;
+            while split <= input.len() {
+                let mut ctx = Context::new(&key);
+                ctx.update_padded(&input[..split]);
+                ctx.update_padded(&input[split..]);
+                assert_eq!(&ctx.finish()[..], &expected[..]);
+                split += BLOCK_LEN;
+            }
+
+            Ok(())
+        });
+    }
+}